@@ -0,0 +1,45 @@
+//! Creating and destroying many directed channels, each in its own allocation
+//! versus in a channel arena that is reset after each round.
+//!
+//! Run with `cargo bench --bench arena`.
+
+use std::time::Instant;
+
+use two_phase_channel::{arena::ChannelArena, directed::DirectedChannel};
+
+const CHANNELS: usize = 10_000;
+const ROUNDS: u32 = 100;
+
+fn main() {
+    let mut channels = Vec::with_capacity(CHANNELS);
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        channels.extend((0..CHANNELS).map(|i| DirectedChannel::create(i, i)));
+        for (channel_pointer, read_only, writable) in channels.drain(..) {
+            channel_pointer.destroy_single(read_only, writable);
+        }
+    }
+    println!(
+        "{} rounds of creating and destroying {} channels, boxed: {:?}",
+        ROUNDS,
+        CHANNELS,
+        start.elapsed()
+    );
+
+    let mut arena = ChannelArena::new();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        channels.extend((0..CHANNELS).map(|i| arena.create_directed(i, i)));
+        for (channel_pointer, read_only, writable) in channels.drain(..) {
+            channel_pointer.destroy_single(read_only, writable);
+        }
+        arena.reset();
+    }
+    println!(
+        "{} rounds of creating and destroying {} channels, arena: {:?}",
+        ROUNDS,
+        CHANNELS,
+        start.elapsed()
+    );
+}
@@ -0,0 +1,57 @@
+//! Swapping large `Data` in an undirected channel, which copies the `Data`,
+//! versus in a boxed undirected channel, which exchanges the allocations.
+//! Also measures the cost of the additional pointer indirection of the boxed channel on reads.
+//!
+//! Run with `cargo bench --bench boxed_swap`.
+
+use std::{ptr, time::Instant};
+
+use two_phase_channel::{boxed::BoxedUndirectedChannel, undirected::UndirectedChannel, MasterKey};
+
+type Payload = [u64; 1 << 16];
+const SWAPS: u32 = 10_000;
+const READS: u32 = 100_000_000;
+
+fn main() {
+    let mut master_key = MasterKey::create();
+
+    let (mut channel_pointer, data_pointer1, data_pointer2) =
+        UndirectedChannel::<Payload>::create([0; 1 << 16], [1; 1 << 16]);
+    let start = Instant::now();
+    for _ in 0..SWAPS {
+        channel_pointer.swap(&master_key.get_channel_key());
+    }
+    println!(
+        "{} swaps of {} bytes, copying: {:?}",
+        SWAPS,
+        std::mem::size_of::<Payload>(),
+        start.elapsed()
+    );
+    let start = Instant::now();
+    let data_key = master_key.get_data_key();
+    for _ in 0..READS {
+        unsafe { ptr::read_volatile(&data_pointer1.get(&data_key)[0]) };
+    }
+    println!("{} reads, copying: {:?}", READS, start.elapsed());
+    UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+
+    let (mut channel_pointer, data_pointer1, data_pointer2) =
+        BoxedUndirectedChannel::<Payload>::create([0; 1 << 16], [1; 1 << 16]);
+    let start = Instant::now();
+    for _ in 0..SWAPS {
+        channel_pointer.swap(&master_key.get_channel_key());
+    }
+    println!(
+        "{} swaps of {} bytes, boxed: {:?}",
+        SWAPS,
+        std::mem::size_of::<Payload>(),
+        start.elapsed()
+    );
+    let start = Instant::now();
+    let data_key = master_key.get_data_key();
+    for _ in 0..READS {
+        unsafe { ptr::read_volatile(&data_pointer1.get(&data_key)[0]) };
+    }
+    println!("{} reads, boxed: {:?}", READS, start.elapsed());
+    BoxedUndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+}
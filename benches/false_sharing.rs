@@ -0,0 +1,45 @@
+//! Two threads incrementing the counters on the two sides of an undirected channel.
+//! Without the `cache-padded` feature, both counters share a cache line.
+//!
+//! Compare `cargo bench --bench false_sharing` with `cargo bench --bench false_sharing --features cache-padded`.
+
+use std::{ptr, thread, time::Instant};
+
+use two_phase_channel::{
+    undirected::{UndirectedChannel, UndirectedDataPointer},
+    MasterKey,
+};
+
+const INCREMENTS: u64 = 100_000_000;
+
+fn increment(mut data_pointer: UndirectedDataPointer<u64>) -> UndirectedDataPointer<u64> {
+    // Each thread uses its own data key, which is fine since both threads access different `Data` fields.
+    let mut master_key = unsafe { MasterKey::create_unlimited() };
+    let data_key = master_key.get_data_key();
+    let counter = data_pointer.get_mut(&data_key);
+    for _ in 0..INCREMENTS {
+        unsafe { ptr::write_volatile(counter, ptr::read_volatile(counter) + 1) };
+    }
+    data_pointer
+}
+
+fn main() {
+    let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(0u64, 0u64);
+
+    let start = Instant::now();
+    let thread1 = thread::spawn(move || increment(data_pointer1));
+    let thread2 = thread::spawn(move || increment(data_pointer2));
+    let data_pointer1 = thread1.join().unwrap();
+    let data_pointer2 = thread2.join().unwrap();
+    let duration = start.elapsed();
+
+    let (counter1, counter2) =
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    assert_eq!((counter1, counter2), (INCREMENTS, INCREMENTS));
+    println!(
+        "{} increments on each of two threads (cache-padded: {}): {:?}",
+        INCREMENTS,
+        cfg!(feature = "cache-padded"),
+        duration
+    );
+}
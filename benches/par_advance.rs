@@ -0,0 +1,62 @@
+//! Advancing a channel group of many directed channels serially versus in parallel on the rayon thread pool.
+//!
+//! Run with `cargo bench --bench par_advance --features rayon`.
+
+use std::time::Instant;
+
+use two_phase_channel::{
+    directed::{DirectedChannel, DirectedChannelPointer},
+    group::ChannelGroup,
+    MasterKey,
+};
+
+const CHANNELS: usize = 200;
+const PAYLOAD: usize = 1 << 16;
+const PHASES: u32 = 100;
+
+fn main() {
+    let mut master_key = MasterKey::create();
+    let mut group = ChannelGroup::new();
+    let channel_key = master_key.get_channel_key();
+    let mut channels: Vec<_> = (0..CHANNELS)
+        .map(|_| {
+            let (channel_pointer, read_only, writable) =
+                DirectedChannel::create(vec![0u8; PAYLOAD], vec![0u8; PAYLOAD]);
+            let id = group.push_directed(&channel_key, "payload", channel_pointer);
+            (id, read_only, writable)
+        })
+        .collect();
+
+    for parallel in [false, true] {
+        let start = Instant::now();
+        for phase in 0..PHASES {
+            // Write to every channel, such that every flush clones its payload.
+            let data_key = master_key.get_data_key();
+            for (_, _, writable) in &mut channels {
+                writable.get_mut(&data_key)[0] = phase as u8;
+            }
+
+            let channel_key = data_key.into_channel_key();
+            if parallel {
+                group.par_advance_all(&channel_key);
+            } else {
+                group.advance_all(&channel_key);
+            }
+        }
+        println!(
+            "{} phases of {} channels of {} bytes, {}: {:?}",
+            PHASES,
+            CHANNELS,
+            PAYLOAD,
+            if parallel { "parallel" } else { "serial" },
+            start.elapsed()
+        );
+    }
+
+    let channel_key = master_key.get_channel_key();
+    for (id, read_only, writable) in channels {
+        let channel_pointer: DirectedChannelPointer<Vec<u8>> =
+            group.remove(&channel_key, id).unwrap();
+        channel_pointer.destroy_single(read_only, writable);
+    }
+}
@@ -0,0 +1,6 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // Declare the `loom` cfg of the loom tests, see the `loom` dev-dependency in `Cargo.toml`.
+    // The single-colon form is ignored by versions of Cargo that do not check cfgs.
+    println!("cargo:rustc-check-cfg=cfg(loom)");
+}
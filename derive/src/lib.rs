@@ -0,0 +1,229 @@
+//! Derive macros for the `two_phase_channel` crate.
+//! Use them via the `derive` feature of `two_phase_channel`, which re-exports them.
+
+use proc_macro::TokenStream;
+use proc_macro2::Ident;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Type};
+
+/// The kind of channel generated for a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Directed,
+    Undirected,
+}
+
+/// A field of the derived struct with the kind of its channel.
+struct Field {
+    ident: Ident,
+    ty: Type,
+    kind: Kind,
+}
+
+/// Generate a channel per field of a struct, see the documentation of `two_phase_channel::SwapFields`.
+#[proc_macro_derive(SwapFields, attributes(channel))]
+pub fn derive_swap_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    swap_fields(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn swap_fields(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "SwapFields can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "SwapFields can only be derived for structs",
+            ))
+        }
+    };
+    let fields = fields
+        .iter()
+        .map(|field| {
+            Ok(Field {
+                ident: field.ident.clone().unwrap(),
+                ty: field.ty.clone(),
+                kind: kind(&field.attrs)?,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let crate_path = quote!(::two_phase_channel);
+    let visibility = &input.vis;
+    let name = &input.ident;
+    let channels = format_ident!("{}Channels", name);
+    let pointers = format_ident!("{}Pointers", name);
+
+    let struct_generics = &input.generics;
+    let mut generics = input.generics.clone();
+    let (_, type_generics, where_clause) = input.generics.split_for_impl();
+    let where_predicates = &mut generics.make_where_clause().predicates;
+    for field in &fields {
+        let ty = &field.ty;
+        where_predicates.push(parse_quote!(#ty: Clone));
+    }
+    let (impl_generics, _, clone_where_clause) = generics.split_for_impl();
+
+    let mut channel_fields = Vec::new();
+    let mut pointer_fields = Vec::new();
+    let mut create = Vec::new();
+    let mut flush = Vec::new();
+    let mut destroy = Vec::new();
+    for Field { ident, ty, kind } in &fields {
+        let documentation = format!("The channel of the `{}` field.", ident);
+        match kind {
+            Kind::Directed => {
+                let reader = format_ident!("{}_reader", ident);
+                let writer = format_ident!("{}_writer", ident);
+                let reader_documentation =
+                    format!("The read-only data pointer of the `{}` field.", ident);
+                let writer_documentation =
+                    format!("The writable data pointer of the `{}` field.", ident);
+                channel_fields.push(quote! {
+                    #[doc = #documentation]
+                    #ident: #crate_path::directed::DirectedChannelPointer<#ty>
+                });
+                pointer_fields.push(quote! {
+                    #[doc = #reader_documentation]
+                    pub #reader: #crate_path::directed::ReadOnlyDataPointer<#ty>,
+                    #[doc = #writer_documentation]
+                    pub #writer: #crate_path::directed::WritableDataPointer<#ty>
+                });
+                create.push(quote! {
+                    let (#ident, #reader, #writer) =
+                        #crate_path::directed::DirectedChannel::create_equal(initial.#ident);
+                });
+                flush.push(quote!(self.#ident.flush(channel_key);));
+                destroy.push(quote! {
+                    let #ident = self.#ident.destroy_single(pointers.#reader, pointers.#writer);
+                });
+            }
+            Kind::Undirected => {
+                let pointer1 = format_ident!("{}_pointer1", ident);
+                let pointer2 = format_ident!("{}_pointer2", ident);
+                let pointer1_documentation =
+                    format!("The first data pointer of the `{}` field.", ident);
+                let pointer2_documentation =
+                    format!("The second data pointer of the `{}` field.", ident);
+                channel_fields.push(quote! {
+                    #[doc = #documentation]
+                    #ident: #crate_path::undirected::UndirectedChannelPointer<#ty>
+                });
+                pointer_fields.push(quote! {
+                    #[doc = #pointer1_documentation]
+                    pub #pointer1: #crate_path::undirected::UndirectedDataPointer<#ty>,
+                    #[doc = #pointer2_documentation]
+                    pub #pointer2: #crate_path::undirected::UndirectedDataPointer<#ty>
+                });
+                create.push(quote! {
+                    let (#ident, #pointer1, #pointer2) =
+                        #crate_path::undirected::UndirectedChannel::create_equal(initial.#ident);
+                });
+                flush.push(quote!(self.#ident.swap(channel_key);));
+                destroy.push(quote! {
+                    let #ident = self.#ident.destroy(pointers.#pointer1, pointers.#pointer2);
+                });
+            }
+        }
+    }
+
+    let idents: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let pointer_idents: Vec<_> = fields
+        .iter()
+        .flat_map(|field| match field.kind {
+            Kind::Directed => [
+                format_ident!("{}_reader", field.ident),
+                format_ident!("{}_writer", field.ident),
+            ],
+            Kind::Undirected => [
+                format_ident!("{}_pointer1", field.ident),
+                format_ident!("{}_pointer2", field.ident),
+            ],
+        })
+        .collect();
+    let channels_documentation = format!(
+        "The channels of the fields of [{}], advanced together via [{}::flush_all].",
+        name, channels
+    );
+    let pointers_documentation = format!(
+        "The data pointers of the channels of the fields of [{}].",
+        name
+    );
+
+    Ok(quote! {
+        #[doc = #channels_documentation]
+        ///
+        /// This type should always be destroyed via its `destroy` method to ensure soundness (at runtime).
+        #[must_use]
+        #visibility struct #channels #struct_generics #where_clause {
+            #(#channel_fields,)*
+        }
+
+        #[doc = #pointers_documentation]
+        #[must_use]
+        #visibility struct #pointers #struct_generics #where_clause {
+            #(#pointer_fields,)*
+        }
+
+        impl #impl_generics #channels #type_generics #clone_where_clause {
+            /// Create a channel per field, whose `Data` fields are initialised equally from the field of the given value.
+            /// Returns the channels and their data pointers.
+            #visibility fn create(initial: #name #type_generics) -> (Self, #pointers #type_generics) {
+                #(#create)*
+                (
+                    Self { #(#idents,)* },
+                    #pointers { #(#pointer_idents,)* },
+                )
+            }
+
+            /// Flush the directed and swap the undirected channels of all fields.
+            #visibility fn flush_all(&mut self, channel_key: &#crate_path::ChannelKey) {
+                #(#flush)*
+            }
+
+            /// Destroy the channels of all fields, and reassemble the values of their `Data` fields.
+            /// Returns the value assembled from the read-only `Data` of the directed and the first `Data` of the undirected channels,
+            /// and the value assembled from the writable `Data` of the directed and the second `Data` of the undirected channels, in this order.
+            ///
+            /// **Panics** if the data pointers do not point to these channels.
+            #visibility fn destroy(self, pointers: #pointers #type_generics) -> (#name #type_generics, #name #type_generics) {
+                #(#destroy)*
+                (
+                    #name { #(#idents: #idents.0,)* },
+                    #name { #(#idents: #idents.1,)* },
+                )
+            }
+        }
+    })
+}
+
+/// Parse the kind of the channel of a field from its `#[channel(...)]` attribute, defaulting to directed.
+fn kind(attributes: &[syn::Attribute]) -> syn::Result<Kind> {
+    let mut kind = Kind::Directed;
+    for attribute in attributes {
+        if attribute.path().is_ident("channel") {
+            let ident: Ident = attribute.parse_args()?;
+            kind = if ident == "directed" {
+                Kind::Directed
+            } else if ident == "undirected" {
+                Kind::Undirected
+            } else {
+                return Err(Error::new(
+                    ident.span(),
+                    "expected `directed` or `undirected`",
+                ));
+            };
+        }
+    }
+    Ok(kind)
+}
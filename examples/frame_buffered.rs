@@ -0,0 +1,86 @@
+//! A game loop with an update thread, a render thread and the main thread, communicating via a frame buffer.
+//!
+//! The update thread simulates the scene and writes the next frame, the render thread draws the last presented frame,
+//! and the main thread presents the written frame once per frame.
+//! Every fourth frame, the simulation is paused, hence nothing is written, presenting is skipped and the render thread sees no fresh frame.
+//!
+//! Run with `cargo run --example frame_buffered`.
+
+use std::{
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use two_phase_channel::{frame::FrameBuffered, runner::PhaseRunner, MasterKey};
+
+const FRAMES: usize = 60;
+
+#[derive(Debug, Clone, Default)]
+struct Scene {
+    tick: u64,
+    positions: Vec<(f32, f32)>,
+    velocities: Vec<(f32, f32)>,
+}
+
+fn main() {
+    let scene = Scene {
+        positions: vec![(0.0, 0.0); 4],
+        velocities: (0..4).map(|i| (i as f32, 1.0)).collect(),
+        ..Scene::default()
+    };
+    let (mut producer, consumer, mut presenter) = FrameBuffered::create(scene);
+    let rendered = Arc::new(AtomicUsize::new(0));
+
+    let mut runner = PhaseRunner::new(MasterKey::create());
+
+    let mut frame = 0;
+    runner.add_worker("update", move |data_key| {
+        frame += 1;
+        if frame % 4 == 0 {
+            return;
+        }
+        producer.write(data_key, |scene| {
+            scene.tick += 1;
+            for (position, velocity) in scene.positions.iter_mut().zip(&scene.velocities) {
+                position.0 += velocity.0;
+                position.1 += velocity.1;
+            }
+        });
+    });
+
+    let rendered_by_worker = rendered.clone();
+    runner.add_worker("render", move |data_key| {
+        let scene = consumer.read(data_key);
+        if consumer.is_fresh() {
+            rendered_by_worker.fetch_add(1, Ordering::Relaxed);
+            if scene.tick % 15 == 0 {
+                println!("render: tick {} at {:?}", scene.tick, scene.positions);
+            }
+        }
+    });
+
+    let mut presented = 0;
+    runner.set_coordinator(move |channel_key| {
+        presenter.present(channel_key);
+        presented += 1;
+        if presented < FRAMES {
+            ControlFlow::Continue(())
+        } else {
+            let stats = presenter.stats(channel_key);
+            println!(
+                "main: {} frames presented, {} skipped",
+                stats.flushes, stats.skipped
+            );
+            ControlFlow::Break(())
+        }
+    });
+
+    runner.run().expect("a worker panicked");
+    println!(
+        "render: {} fresh frames rendered",
+        rendered.load(Ordering::Relaxed)
+    );
+}
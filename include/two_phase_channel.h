@@ -0,0 +1,54 @@
+/*
+ * C interface of the two_phase_channel crate, available with the `ffi` feature.
+ * See the documentation of the `ffi` module for details.
+ */
+#ifndef TWO_PHASE_CHANNEL_H
+#define TWO_PHASE_CHANNEL_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum sc_status {
+    SC_OK = 0,
+    SC_NULL_POINTER = 1,
+    SC_MASTER_KEY_EXISTS = 2,
+    SC_PANIC = 3,
+} sc_status;
+
+typedef struct sc_master_key sc_master_key;
+typedef struct sc_undirected_channel sc_undirected_channel;
+typedef struct sc_undirected_data sc_undirected_data;
+typedef struct sc_directed_channel sc_directed_channel;
+typedef struct sc_read_only_data sc_read_only_data;
+typedef struct sc_writable_data sc_writable_data;
+
+sc_status sc_master_key_create(sc_master_key **out_key);
+sc_status sc_master_key_destroy(sc_master_key *key);
+
+/* Buffers are zero-initialised and aligned to 16 bytes. */
+sc_status sc_undirected_create(size_t size, sc_undirected_channel **out_channel,
+                               sc_undirected_data **out_data1, sc_undirected_data **out_data2);
+sc_status sc_undirected_swap(sc_master_key *key, sc_undirected_channel *channel);
+/* The buffer address is valid until the next swap. */
+sc_status sc_undirected_get(sc_master_key *key, sc_undirected_data *data, void **out_buffer);
+sc_status sc_undirected_destroy(sc_undirected_channel *channel, sc_undirected_data *data1,
+                                sc_undirected_data *data2);
+
+sc_status sc_directed_create(size_t size, sc_directed_channel **out_channel,
+                             sc_read_only_data **out_read_only, sc_writable_data **out_writable);
+sc_status sc_directed_flush(sc_master_key *key, sc_directed_channel *channel);
+/* The buffer address is valid until the next flush. */
+sc_status sc_directed_read(sc_master_key *key, sc_read_only_data *read_only,
+                           const void **out_buffer);
+sc_status sc_directed_write(sc_master_key *key, sc_writable_data *writable, void **out_buffer);
+sc_status sc_directed_destroy(sc_directed_channel *channel, sc_read_only_data *read_only,
+                              sc_writable_data *writable);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,290 @@
+//! Python bindings for the [DoubleBuffer](two_phase_channel::double_buffer::DoubleBuffer) of the `two_phase_channel` crate,
+//! e.g. for tooling scripts that peek at and inject into the double-buffered state of a running Rust process.
+//! The Python module is built with the `python` feature, see `pyproject.toml`.
+//!
+//! # Participating in phases
+//!
+//! Python code cannot hold a [DataKey](two_phase_channel::DataKey), since it does not take part in the phases of the Rust threads,
+//! and accessing a buffer whenever the Python code runs would race with the coordinator presenting it.
+//! Instead, each access of a Python caller is sent as a request to a queue, which the coordinator serves in its channel phase via [PythonBridge::pump].
+//! The coordinator holds the [ChannelKey] anyway, and accesses the buffers through the [Presenter] with it,
+//! such that no data key exists while a request is served, exactly as for a present.
+//! The Python caller blocks until its request was served, while releasing the GIL, such that other Python threads keep running.
+//!
+//! On the Python side, the GIL serialises the callers, such that the requests of one interpreter are queued in the order they are made.
+//! Reads see the front buffer as of the channel phase they are served in,
+//! and a write replaces the back buffer, which is moved to the front by the next present.
+//!
+//! # Payloads
+//!
+//! The requests carry bytes, which are converted to the `Data` of the double buffer by its [Payload] implementation.
+//! This crate implements it for raw bytes (`Vec<u8>`) and for `serde` types encoded as JSON (see [Json]).
+//!
+//! ```
+//! use std::thread;
+//! use two_phase_channel::{double_buffer::DoubleBuffer, MasterKey};
+//! use two_phase_channel_python::PythonBridge;
+//!
+//! let mut master_key = MasterKey::create();
+//! let (presenter, front, back) = DoubleBuffer::create(b"front".to_vec(), b"back".to_vec());
+//! let (mut bridge, handle) = PythonBridge::new(presenter);
+//!
+//! // Stands in for a Python thread, which would use the handle through the `DoubleBuffer` Python class.
+//! let caller = thread::spawn(move || {
+//!     handle.write_back(b"injected".to_vec()).unwrap();
+//!     handle.present().unwrap();
+//!     handle.read_front().unwrap()
+//! });
+//! while bridge.pump(&master_key.get_channel_key()) {}
+//! assert_eq!(caller.join().unwrap(), b"injected");
+//! # bridge.into_presenter().destroy(front, back);
+//! ```
+
+use core::fmt;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+
+use serde::{de::DeserializeOwned, Serialize};
+use two_phase_channel::{double_buffer::Presenter, ChannelKey};
+
+#[cfg(feature = "python")]
+mod python;
+
+/// The `Data` of a double buffer that is accessed from Python as bytes.
+pub trait Payload: Sized {
+    /// Convert the `Data` into the bytes returned to Python.
+    fn encode(&self) -> Result<Vec<u8>, String>;
+
+    /// Convert the bytes written by Python into `Data`.
+    fn decode(bytes: Vec<u8>) -> Result<Self, String>;
+}
+
+/// A `Data` that is accessed from Python as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+/// The error returned by a [PythonHandle].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeError {
+    /// The [PythonBridge] was dropped, e.g. because the Rust process shuts down.
+    Closed,
+    /// The `Data` could not be converted from or to bytes, see [Payload].
+    Payload(String),
+}
+
+/// A request of a Python caller, together with the sender of its reply.
+enum Request {
+    ReadFront(SyncSender<Result<Vec<u8>, BridgeError>>),
+    WriteBack(Vec<u8>, SyncSender<Result<(), BridgeError>>),
+    Present(SyncSender<Result<(), BridgeError>>),
+}
+
+/// Serves the requests of the Python callers of a double buffer in the channel phase, see the [crate documentation](crate).
+pub struct PythonBridge<Data> {
+    presenter: Presenter<Data>,
+    requests: Receiver<Request>,
+    /// `false` once all handles were dropped and their requests were served.
+    connected: bool,
+}
+
+/// The handle of the Python callers to a double buffer, wrapped by the `DoubleBuffer` Python class.
+/// Each method blocks until the coordinator served it in a channel phase.
+#[derive(Debug, Clone)]
+pub struct PythonHandle {
+    requests: Sender<Request>,
+}
+
+impl Payload for Vec<u8> {
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        Ok(self.clone())
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Self, String> {
+        Ok(bytes)
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Payload for Json<T> {
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(&self.0).map_err(|error| error.to_string())
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Self, String> {
+        serde_json::from_slice(&bytes)
+            .map(Json)
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl<Data: Payload> PythonBridge<Data> {
+    /// Create a bridge that serves the requests of the Python callers via the given presenter, and the handle for the Python callers.
+    pub fn new(presenter: Presenter<Data>) -> (Self, PythonHandle) {
+        let (sender, requests) = mpsc::channel();
+        (
+            Self {
+                presenter,
+                requests,
+                connected: true,
+            },
+            PythonHandle { requests: sender },
+        )
+    }
+
+    /// Serve the queued requests in the order they were made, without waiting for further requests.
+    /// This is called by the coordinator in each channel phase, e.g. right before or after presenting itself.
+    ///
+    /// Returns `false` if all handles were dropped and no requests are left, i.e. the bridge can be destroyed.
+    pub fn pump(&mut self, channel_key: &ChannelKey) -> bool {
+        loop {
+            match self.requests.try_recv() {
+                Ok(request) => self.serve(channel_key, request),
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn serve(&mut self, channel_key: &ChannelKey, request: Request) {
+        // The caller may have given up waiting, in which case its reply is dropped.
+        match request {
+            Request::ReadFront(reply) => {
+                let front = self
+                    .presenter
+                    .inspect(channel_key, |front, _| front.encode());
+                let _ = reply.send(front.map_err(BridgeError::Payload));
+            }
+            Request::WriteBack(bytes, reply) => {
+                let result = Data::decode(bytes).map(|back| {
+                    self.presenter.replace_back(channel_key, back);
+                });
+                let _ = reply.send(result.map_err(BridgeError::Payload));
+            }
+            Request::Present(reply) => {
+                self.presenter.present(channel_key);
+                let _ = reply.send(Ok(()));
+            }
+        }
+    }
+}
+
+impl<Data> PythonBridge<Data> {
+    /// Returns `true` if a handle may still make requests, i.e. if [PythonBridge::pump] did not return `false` yet.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Return the presenter, e.g. for destroying the double buffer.
+    /// Pending and future requests fail with [BridgeError::Closed].
+    pub fn into_presenter(self) -> Presenter<Data> {
+        self.presenter
+    }
+}
+
+impl PythonHandle {
+    /// Encode the front buffer as of the next channel phase.
+    pub fn read_front(&self) -> Result<Vec<u8>, BridgeError> {
+        self.request(Request::ReadFront)
+    }
+
+    /// Replace the back buffer with the given bytes in the next channel phase.
+    /// The next present moves it to the front.
+    pub fn write_back(&self, bytes: Vec<u8>) -> Result<(), BridgeError> {
+        self.request(|reply| Request::WriteBack(bytes, reply))
+    }
+
+    /// Present the back buffer in the next channel phase, i.e. swap the front and back buffers.
+    pub fn present(&self) -> Result<(), BridgeError> {
+        self.request(Request::Present)
+    }
+
+    /// Send the request and block until it was served.
+    fn request<T>(
+        &self,
+        request: impl FnOnce(SyncSender<Result<T, BridgeError>>) -> Request,
+    ) -> Result<T, BridgeError> {
+        let (reply, result) = mpsc::sync_channel(1);
+        self.requests
+            .send(request(reply))
+            .map_err(|_| BridgeError::Closed)?;
+        result.recv().unwrap_or(Err(BridgeError::Closed))
+    }
+}
+
+impl<Data> fmt::Debug for PythonBridge<Data> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PythonBridge")
+            .field("connected", &self.connected)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Request::ReadFront(_) => f.write_str("ReadFront"),
+            Request::WriteBack(bytes, _) => write!(f, "WriteBack({} bytes)", bytes.len()),
+            Request::Present(_) => f.write_str("Present"),
+        }
+    }
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeError::Closed => {
+                write!(f, "the double buffer is no longer served by a coordinator")
+            }
+            BridgeError::Payload(error) => write!(f, "invalid payload: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use two_phase_channel::{double_buffer::DoubleBuffer, MasterKey};
+
+    use crate::{BridgeError, Json, PythonBridge};
+
+    #[test]
+    fn requests_are_served_in_channel_phases() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (presenter, front, back) = DoubleBuffer::create(Json(vec![0]), Json(Vec::<u32>::new()));
+        let (mut bridge, handle) = PythonBridge::new(presenter);
+
+        let caller = thread::spawn(move || {
+            assert_eq!(handle.read_front().unwrap(), b"[0]");
+            handle.write_back(b"[1, 2]".to_vec()).unwrap();
+            assert!(matches!(
+                handle.write_back(b"[".to_vec()),
+                Err(BridgeError::Payload(_))
+            ));
+            handle.present().unwrap();
+            handle.read_front().unwrap()
+        });
+        while bridge.pump(&master_key.get_channel_key()) {
+            // The Rust threads keep reading the buffers in the data phases.
+            let data_key = master_key.get_data_key();
+            assert!([vec![0], vec![1, 2]].contains(&front.get(&data_key).0));
+        }
+        assert_eq!(caller.join().unwrap(), b"[1,2]");
+        assert!(!bridge.is_connected());
+        assert_eq!(
+            bridge.into_presenter().destroy(front, back),
+            (Json(vec![1, 2]), Json(vec![0]))
+        );
+    }
+
+    #[test]
+    fn dropped_bridge_closes_handles() {
+        let (presenter, front, back) = DoubleBuffer::create(Vec::new(), Vec::new());
+        let (bridge, handle) = PythonBridge::new(presenter);
+        bridge.into_presenter().destroy(front, back);
+        assert_eq!(handle.present(), Err(BridgeError::Closed));
+    }
+}
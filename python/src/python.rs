@@ -0,0 +1,151 @@
+//! The `two_phase_channel` Python module.
+
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex, OnceLock, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+use two_phase_channel::{
+    double_buffer::{BackHandle, DoubleBuffer, FrontHandle},
+    ChannelKey, MasterKey,
+};
+
+use crate::{BridgeError, Json, Payload, PythonBridge, PythonHandle};
+
+/// A double buffer accessed from Python, see the crate documentation.
+///
+/// In a Rust process, a `DoubleBuffer` is created from the [PythonHandle] of a [PythonBridge] pumped by the coordinator of that process.
+/// A Python process creates it via `DoubleBuffer.create` or `DoubleBuffer.create_json` instead, which serve it from a coordinator thread of the module.
+#[pyclass(name = "DoubleBuffer", module = "two_phase_channel", frozen)]
+pub struct PyDoubleBuffer {
+    handle: PythonHandle,
+}
+
+impl From<PythonHandle> for PyDoubleBuffer {
+    fn from(handle: PythonHandle) -> Self {
+        Self { handle }
+    }
+}
+
+#[pymethods]
+impl PyDoubleBuffer {
+    /// Create a double buffer of bytes with the given front and back buffers.
+    #[staticmethod]
+    fn create(front: Vec<u8>, back: Vec<u8>) -> Self {
+        host(front, back)
+    }
+
+    /// Create a double buffer of JSON values with the given front and back buffers, each encoded as JSON.
+    /// Writing bytes that are not valid JSON to the back buffer raises a `ValueError`.
+    #[staticmethod]
+    fn create_json(front: Vec<u8>, back: Vec<u8>) -> PyResult<Self> {
+        let decode =
+            |bytes| Json::<serde_json::Value>::decode(bytes).map_err(PyValueError::new_err);
+        Ok(host(decode(front)?, decode(back)?))
+    }
+
+    /// Return the front buffer.
+    fn read_front<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let front = py
+            .allow_threads(|| self.handle.read_front())
+            .map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &front))
+    }
+
+    /// Replace the back buffer, which becomes the front buffer with the next present.
+    fn write_back(&self, py: Python<'_>, back: Vec<u8>) -> PyResult<()> {
+        py.allow_threads(|| self.handle.write_back(back))
+            .map_err(to_py_err)
+    }
+
+    /// Swap the front and the back buffer.
+    fn present(&self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.handle.present())
+            .map_err(to_py_err)
+    }
+}
+
+fn to_py_err(error: BridgeError) -> PyErr {
+    match error {
+        BridgeError::Closed => PyRuntimeError::new_err(error.to_string()),
+        BridgeError::Payload(_) => PyValueError::new_err(error.to_string()),
+    }
+}
+
+/// A double buffer served by the coordinator thread of the module.
+trait Hosted: Send {
+    /// Serve the requests of the double buffer, see [PythonBridge::pump].
+    fn pump(&mut self, channel_key: &ChannelKey) -> bool;
+
+    /// Destroy the double buffer once its handles were dropped.
+    fn destroy(self: Box<Self>);
+}
+
+impl<Data: Payload + Send> Hosted for (PythonBridge<Data>, FrontHandle<Data>, BackHandle<Data>) {
+    fn pump(&mut self, channel_key: &ChannelKey) -> bool {
+        self.0.pump(channel_key)
+    }
+
+    fn destroy(self: Box<Self>) {
+        let (bridge, front, back) = *self;
+        bridge.into_presenter().destroy(front, back);
+    }
+}
+
+/// The sender of new double buffers to the coordinator thread of the module, which is started by the first double buffer.
+static HOST: OnceLock<Mutex<Sender<Box<dyn Hosted>>>> = OnceLock::new();
+
+/// Serve a new double buffer from the coordinator thread of the module.
+fn host<Data: Payload + Send + 'static>(front: Data, back: Data) -> PyDoubleBuffer {
+    let (presenter, front, back) = DoubleBuffer::create(front, back);
+    let (bridge, handle) = PythonBridge::new(presenter);
+    let host = HOST.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("two_phase_channel coordinator".to_owned())
+            .spawn(move || run_host(receiver))
+            .expect("failed to spawn coordinator thread");
+        Mutex::new(sender)
+    });
+    host.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .send(Box::new((bridge, front, back)))
+        .expect("coordinator thread exited");
+    handle.into()
+}
+
+/// The loop of the coordinator thread of the module.
+/// Its data phases are empty, since only Python accesses the double buffers it serves.
+fn run_host(receiver: Receiver<Box<dyn Hosted>>) {
+    let mut master_key = MasterKey::create();
+    let mut hosted: Vec<Box<dyn Hosted>> = Vec::new();
+    loop {
+        let _ = master_key.get_data_key();
+        let channel_key = master_key.get_channel_key();
+        hosted.extend(receiver.try_iter());
+        for mut double_buffer in std::mem::take(&mut hosted) {
+            if double_buffer.pump(&channel_key) {
+                hosted.push(double_buffer);
+            } else {
+                double_buffer.destroy();
+            }
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Python bindings for the double buffers of the `two_phase_channel` crate.
+#[pymodule]
+#[pyo3(name = "two_phase_channel")]
+fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDoubleBuffer>()
+}
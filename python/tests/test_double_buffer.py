@@ -0,0 +1,53 @@
+"""Peeking at and injecting into double buffers from Python.
+
+Build and install the module via `maturin develop`, then run `pytest tests`.
+Each call blocks until the coordinator thread of the module served it in its channel phase.
+"""
+
+import json
+import threading
+
+import pytest
+
+from two_phase_channel import DoubleBuffer
+
+
+def test_bytes():
+    buffer = DoubleBuffer.create(b"front", b"back")
+    assert buffer.read_front() == b"front"
+
+    buffer.write_back(b"injected")
+    # The write is only visible after the next present.
+    assert buffer.read_front() == b"front"
+    buffer.present()
+    assert buffer.read_front() == b"injected"
+    buffer.present()
+    assert buffer.read_front() == b"front"
+
+
+def test_json():
+    buffer = DoubleBuffer.create_json(b'{"frame": 0}', b"{}")
+    assert json.loads(buffer.read_front()) == {"frame": 0}
+
+    buffer.write_back(json.dumps({"frame": 1, "tags": ["a"]}).encode())
+    buffer.present()
+    assert json.loads(buffer.read_front()) == {"frame": 1, "tags": ["a"]}
+
+    with pytest.raises(ValueError):
+        buffer.write_back(b"{")
+    with pytest.raises(ValueError):
+        DoubleBuffer.create_json(b"[", b"[]")
+
+
+def test_threads():
+    buffer = DoubleBuffer.create(b"0", b"0")
+    # The callers release the GIL while waiting for the coordinator, hence they do not block each other.
+    readers = [threading.Thread(target=buffer.read_front) for _ in range(8)]
+    for reader in readers:
+        reader.start()
+    for value in range(1, 10):
+        buffer.write_back(str(value).encode())
+        buffer.present()
+    for reader in readers:
+        reader.join()
+    assert buffer.read_front() == b"9"
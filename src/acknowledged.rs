@@ -0,0 +1,208 @@
+//! A directed two-phase channel with an acknowledgement back channel.
+//! The consumer acknowledges the generation it consumed, and the producer sees the acknowledgement after the next flush.
+//! The back channel is a single `u64`, so it costs much less than a full [bidirected channel](crate::bidirected).
+
+use crate::{
+    directed::{DirectedChannel, ReadOnlyDataPointer, WritableDataPointer},
+    ChannelKey, DataKey, GenerationPointer,
+};
+
+/// A directed channel with an acknowledgement back channel, used for communication between a producer and a consumer thread.
+///
+/// See [AcknowledgedChannel::create] for more info.
+#[derive(Debug)]
+pub struct AcknowledgedChannel<Data> {
+    channel: DirectedChannel<Data>,
+    /// The generation acknowledged by the consumer during the last data phase.
+    pending_ack: u64,
+    /// The generation acknowledged by the consumer as of the last flush.
+    acked: u64,
+}
+
+/// A pointer to an acknowledged channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [AcknowledgedChannel::destroy] or [AcknowledgedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct AcknowledgedChannelPointer<Data> {
+    channel: Box<AcknowledgedChannel<Data>>,
+}
+
+/// A pointer to the writable data field of an acknowledged channel, which also reads the acknowledgements.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [AcknowledgedChannel::destroy] or [AcknowledgedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct ProducerDataPointer<Data> {
+    output: WritableDataPointer<Data>,
+    generation: GenerationPointer,
+    acked: *const u64,
+}
+
+/// A pointer to the read-only data field of an acknowledged channel, which also writes the acknowledgements.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [AcknowledgedChannel::destroy] or [AcknowledgedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct ConsumerDataPointer<Data> {
+    input: ReadOnlyDataPointer<Data>,
+    generation: GenerationPointer,
+    pending_ack: *mut u64,
+}
+
+impl<Data> AcknowledgedChannel<Data> {
+    /// Create an acknowledged channel and hand out three pointers to it.
+    /// One [AcknowledgedChannelPointer] used to flush the `Data` to the consumer and the acknowledgement to the producer,
+    /// one [ProducerDataPointer] used to write the `Data`, and
+    /// one [ConsumerDataPointer] used to read and acknowledge the `Data`.
+    ///
+    /// Initially, generation zero is acknowledged.
+    pub fn create(
+        read_only: Data,
+        writable: Data,
+    ) -> (
+        AcknowledgedChannelPointer<Data>,
+        ProducerDataPointer<Data>,
+        ConsumerDataPointer<Data>,
+    ) {
+        let mut channel = Box::new(AcknowledgedChannel {
+            channel: DirectedChannel::new(read_only, writable),
+            pending_ack: 0,
+            acked: 0,
+        });
+        let generation = GenerationPointer::new(&channel.channel.generation);
+        let producer = ProducerDataPointer {
+            output: channel.channel.writable_data_pointer(),
+            generation,
+            acked: &channel.acked as *const u64,
+        };
+        let consumer = ConsumerDataPointer {
+            input: channel.channel.read_only_data_pointer(),
+            generation,
+            pending_ack: &mut channel.pending_ack as *mut u64,
+        };
+        (AcknowledgedChannelPointer { channel }, producer, consumer)
+    }
+
+    /// Destroys the acknowledged channel linked with the three pointers (see [AcknowledgedChannel::create]).
+    /// Returns the read-only and the writable `Data`, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: AcknowledgedChannelPointer<Data>,
+        producer: ProducerDataPointer<Data>,
+        consumer: ConsumerDataPointer<Data>,
+    ) -> (Data, Data) {
+        let AcknowledgedChannelPointer { mut channel } = channel_pointer;
+        assert_eq!(producer.acked, &channel.acked as *const u64);
+        assert_eq!(consumer.pending_ack, &mut channel.pending_ack as *mut u64);
+        assert_eq!(
+            producer.output.data,
+            &mut channel.channel.writable as *mut Data
+        );
+        assert_eq!(
+            consumer.input.data,
+            &channel.channel.read_only as *const Data
+        );
+
+        let AcknowledgedChannel { channel, .. } = *channel;
+        (channel.read_only, channel.writable)
+    }
+}
+
+impl<Data: Clone> AcknowledgedChannelPointer<Data> {
+    /// Clone the writable `Data` into the read-only `Data`, if it was accessed mutably since the last flush,
+    /// and deliver the latest acknowledgement of the consumer to the producer.
+    pub fn flush(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        let channel = &mut *self.channel;
+        channel.channel.flush_if_dirty();
+        channel.acked = channel.pending_ack;
+    }
+}
+
+impl<Data> AcknowledgedChannelPointer<Data> {
+    /// Shorthand for [AcknowledgedChannel::destroy].
+    pub fn destroy(
+        self,
+        producer: ProducerDataPointer<Data>,
+        consumer: ConsumerDataPointer<Data>,
+    ) -> (Data, Data) {
+        AcknowledgedChannel::destroy(self, producer, consumer)
+    }
+}
+
+impl<Data> ProducerDataPointer<Data> {
+    /// Get a mutable reference to the writable `Data`.
+    pub fn get_mut(&mut self, data_key: &DataKey) -> &mut Data {
+        self.output.get_mut(data_key)
+    }
+
+    /// The generation of the `Data` published to the consumer, i.e. the number of flushes that changed it.
+    pub fn published_generation(&self, #[allow(unused)] data_key: &DataKey) -> u64 {
+        self.generation.get()
+    }
+
+    /// The generation last acknowledged by the consumer (see [ConsumerDataPointer::ack]), as of the last flush.
+    /// If this equals [ProducerDataPointer::published_generation], the consumer has consumed the latest published `Data`.
+    pub fn last_acked(&self, #[allow(unused)] data_key: &DataKey) -> u64 {
+        unsafe { *self.acked }
+    }
+}
+
+impl<Data> ConsumerDataPointer<Data> {
+    /// Get a reference to the read-only `Data`.
+    pub fn get(&self, data_key: &DataKey) -> &Data {
+        self.input.get(data_key)
+    }
+
+    /// The generation of the read-only `Data`, i.e. the number of flushes that changed it.
+    pub fn generation(&self, #[allow(unused)] data_key: &DataKey) -> u64 {
+        self.generation.get()
+    }
+
+    /// Acknowledge the current generation of the read-only `Data`.
+    /// The producer sees the acknowledgement after the next flush.
+    pub fn ack(&mut self, #[allow(unused)] data_key: &DataKey) {
+        unsafe { *self.pending_ack = self.generation.get() };
+    }
+}
+
+unsafe impl<Data> Send for AcknowledgedChannelPointer<Data> {}
+unsafe impl<Data> Send for ProducerDataPointer<Data> {}
+unsafe impl<Data> Send for ConsumerDataPointer<Data> {}
+
+unsafe impl<Data> Sync for AcknowledgedChannelPointer<Data> {}
+unsafe impl<Data> Sync for ProducerDataPointer<Data> {}
+unsafe impl<Data> Sync for ConsumerDataPointer<Data> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{acknowledged::AcknowledgedChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut producer, mut consumer) = AcknowledgedChannel::create(0, 0);
+
+        *producer.get_mut(&master_key.get_data_key()) = 1;
+        channel_pointer.flush(&master_key.get_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(producer.published_generation(&data_key), 1);
+        assert_eq!(producer.last_acked(&data_key), 0);
+        assert_eq!(*consumer.get(&data_key), 1);
+        consumer.ack(&data_key);
+        // The acknowledgement is only delivered by the next flush.
+        assert_eq!(producer.last_acked(&data_key), 0);
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(producer.last_acked(&data_key), 1);
+        assert_eq!(consumer.generation(&data_key), 1);
+
+        assert_eq!(channel_pointer.destroy(producer, consumer), (1, 1));
+    }
+}
@@ -0,0 +1,180 @@
+//! A minimal allocator interface for placing channels in custom memory,
+//! see for example [`UndirectedChannel::create_in`](crate::undirected::UndirectedChannel::create_in).
+//! This mirrors the unstable `Allocator` trait of the standard library, such that it is available on stable Rust.
+
+use core::ptr::{self, NonNull};
+use std::alloc::{self, Layout};
+
+/// An allocator that channels can be allocated with.
+///
+/// Unlike with the unstable `Allocator` trait of the standard library, the allocator is not part of the type of the channel pointers.
+/// Instead, it is moved into the allocation of the channel, and dropped after the channel was deallocated with it.
+///
+/// # Safety
+///
+/// [Allocator::allocate] must return either null or a pointer to memory that is valid for reads and writes of the given layout
+/// until it is passed to [Allocator::deallocate].
+pub unsafe trait Allocator {
+    /// Allocate memory for the given layout, which never has a size of zero.
+    /// Returns null if the allocation failed.
+    fn allocate(&self, layout: Layout) -> *mut u8;
+
+    /// Deallocate memory returned by [Allocator::allocate].
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have been returned by [Allocator::allocate] of this allocator for the given layout,
+    /// and must not have been deallocated yet.
+    unsafe fn deallocate(&self, pointer: *mut u8, layout: Layout);
+}
+
+/// The global allocator, i.e. the allocator used by [Box].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Global;
+
+unsafe impl Allocator for Global {
+    fn allocate(&self, layout: Layout) -> *mut u8 {
+        unsafe { alloc::alloc(layout) }
+    }
+
+    unsafe fn deallocate(&self, pointer: *mut u8, layout: Layout) {
+        alloc::dealloc(pointer, layout)
+    }
+}
+
+unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+    fn allocate(&self, layout: Layout) -> *mut u8 {
+        (**self).allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, pointer: *mut u8, layout: Layout) {
+        (**self).deallocate(pointer, layout)
+    }
+}
+
+/// A channel together with the allocator its allocation came from.
+/// The channel is the first field, so pointers to the allocation and to the channel are the same.
+#[repr(C)]
+struct WithAllocator<T, A> {
+    channel: T,
+    allocator: A,
+}
+
+/// Move the given channel and allocator into an allocation from the allocator.
+/// Returns the pointer to the channel, and the function that deallocates the allocation given the pointer to the channel.
+/// The function does not drop the channel, but drops the allocator after deallocating.
+pub(crate) fn allocate<T, A: Allocator>(
+    channel: T,
+    allocator: A,
+) -> (NonNull<T>, unsafe fn(*mut u8)) {
+    let layout = Layout::new::<WithAllocator<T, A>>();
+    let pointer = allocator.allocate(layout) as *mut WithAllocator<T, A>;
+    if pointer.is_null() {
+        alloc::handle_alloc_error(layout);
+    }
+    unsafe {
+        pointer.write(WithAllocator { channel, allocator });
+        (
+            NonNull::new_unchecked(pointer as *mut T),
+            deallocate::<T, A>,
+        )
+    }
+}
+
+/// Deallocate an allocation created by [allocate].
+///
+/// # Safety
+///
+/// The pointer must have been returned by [allocate] for the same types, the channel must have been moved out or dropped,
+/// and the pointer must not be used afterwards.
+unsafe fn deallocate<T, A: Allocator>(pointer: *mut u8) {
+    let pointer = pointer as *mut WithAllocator<T, A>;
+    let allocator = ptr::read(ptr::addr_of!((*pointer).allocator));
+    allocator.deallocate(pointer as *mut u8, Layout::new::<WithAllocator<T, A>>());
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::{alloc::Layout, sync::Arc};
+
+    use crate::{
+        allocator::{Allocator, Global},
+        bidirected::BidirectedChannel,
+        directed::DirectedChannel,
+        undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    /// Counts the bytes that are currently allocated through it.
+    #[derive(Clone, Default)]
+    struct CountingAllocator {
+        allocated: Arc<AtomicUsize>,
+    }
+
+    unsafe impl Allocator for CountingAllocator {
+        fn allocate(&self, layout: Layout) -> *mut u8 {
+            self.allocated.fetch_add(layout.size(), Ordering::Relaxed);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, pointer: *mut u8, layout: Layout) {
+            self.allocated.fetch_sub(layout.size(), Ordering::Relaxed);
+            Global.deallocate(pointer, layout)
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let allocator = CountingAllocator::default();
+        let allocated = || allocator.allocated.load(Ordering::Relaxed);
+
+        let (mut undirected, undirected1, undirected2) =
+            UndirectedChannel::create_in([0u8; 100], [1; 100], allocator.clone());
+        assert!(allocated() >= 200);
+        let (mut directed, read_only, writable) =
+            DirectedChannel::create_in([2u8; 100], [3; 100], allocator.clone());
+        assert!(allocated() >= 400);
+        let (mut bidirected, bidirected1, bidirected2) = BidirectedChannel::create_in(
+            [4u8; 100],
+            [5; 100],
+            [6u8; 100],
+            [7; 100],
+            allocator.clone(),
+        );
+        assert!(allocated() >= 800);
+
+        undirected.swap(&master_key.get_channel_key());
+        directed.flush(&master_key.get_channel_key());
+        bidirected.flush(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(undirected1.get(&data_key), &[1; 100]);
+        assert_eq!(read_only.get(&data_key), &[3; 100]);
+        assert_eq!(bidirected1.get_input(&data_key), &[5; 100]);
+
+        assert_eq!(
+            undirected.destroy(undirected1, undirected2),
+            ([1; 100], [0; 100])
+        );
+        let (read_only, writable) = DirectedChannel::destroy_boxed(directed, [read_only], writable);
+        assert_eq!((*read_only, *writable), ([3; 100], [3; 100]));
+        assert_eq!(
+            bidirected.destroy(bidirected1, bidirected2),
+            ([5; 100], [5; 100], [7; 100], [7; 100])
+        );
+        assert_eq!(allocated(), 0);
+    }
+
+    #[test]
+    fn drop_deallocates() {
+        let allocator = CountingAllocator::default();
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            UndirectedChannel::create_in(vec![0], vec![1], allocator.clone());
+        assert_ne!(allocator.allocated.load(Ordering::Relaxed), 0);
+
+        drop((channel_pointer, data_pointer1, data_pointer2));
+        assert_eq!(allocator.allocated.load(Ordering::Relaxed), 0);
+        assert_eq!(Arc::strong_count(&allocator.allocated), 1);
+    }
+}
@@ -0,0 +1,297 @@
+//! A dynamically typed channel built on top of the [directed channel](crate::directed), e.g. for values of a scripting layer whose types are only known at runtime.
+//!
+//! The writer puts values of any type, and the reader takes them and downcasts them to the expected type.
+//! A failed downcast returns the value in a [WrongType] error together with its actual type, such that it is not lost.
+//! A flush moves the value to the reader instead of cloning it.
+
+use core::{
+    any::{self, Any, TypeId},
+    cell::Cell,
+    fmt,
+};
+use std::borrow::Cow;
+
+use crate::{
+    directed::{
+        DirectedChannel, DirectedChannelPointer, ReadOnlyDataPointer, TakeFlush,
+        WritableDataPointer,
+    },
+    ChannelKey, DataKey, SwapChannel,
+};
+
+/// A dynamically typed channel used for sending one value of any type from one thread to another per channel phase.
+///
+/// See [AnyChannel::create] for more info.
+#[derive(Debug)]
+pub struct AnyChannel {
+    _private: (),
+}
+
+/// A value together with the name of its type.
+#[derive(Debug)]
+struct AnyValue {
+    value: Box<dyn Any + Send>,
+    type_name: &'static str,
+}
+
+/// The `Data` of both sides of an any channel.
+/// Only the reader accesses the read-only side in the data phase, hence it can take the value through a shared reference.
+///
+/// The [Cell] is not `Sync`, hence this is only sound as long as no other pointer to the read-only side exists, see [AnyReader].
+#[derive(Default)]
+struct Slot(Cell<Option<AnyValue>>);
+
+impl fmt::Debug for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Slot")
+    }
+}
+
+/// A pointer to an any channel, used to move the put value to the reader.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [AnyChannel::destroy] or [AnyChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct AnyChannelPointer {
+    channel_pointer: DirectedChannelPointer<Slot>,
+}
+
+/// A pointer used to put values into an any channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [AnyChannel::destroy] or [AnyChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct AnyWriter {
+    data_pointer: WritableDataPointer<Slot>,
+}
+
+/// A pointer used to take values out of an any channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [AnyChannel::destroy] or [AnyChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct AnyReader {
+    // Invariant: this is the only pointer to the read-only side, and it is never handed out.
+    // Read-only data pointers are `Copy` and `Sync`, hence taking the value through the `Cell` of the `Slot`
+    // behind a shared reference is only sound because neither a copy of this pointer nor an observer of the wrapped channel pointer
+    // can be made from outside of this module.
+    data_pointer: ReadOnlyDataPointer<Slot>,
+}
+
+/// The value taken from an [AnyReader] is not of the requested type.
+/// The value is kept, such that it can be downcast to another type via [WrongType::into_value].
+pub struct WrongType {
+    value: Box<dyn Any + Send>,
+    type_name: &'static str,
+    requested: &'static str,
+}
+
+impl AnyChannel {
+    /// Create an empty any channel and hand out three pointers to it.
+    /// One [AnyChannelPointer] used to move the put value to the reader,
+    /// one [AnyWriter] used to put values, and
+    /// one [AnyReader] used to take the moved values.
+    pub fn create() -> (AnyChannelPointer, AnyWriter, AnyReader) {
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create(Slot::default(), Slot::default());
+        (
+            AnyChannelPointer { channel_pointer },
+            AnyWriter {
+                data_pointer: writable_data_pointer,
+            },
+            AnyReader {
+                data_pointer: read_only_data_pointer,
+            },
+        )
+    }
+
+    /// Destroys the any channel linked with the three pointers (see [AnyChannel::create]).
+    /// Returns the value that was moved to the reader but not taken, and the value that was put but not moved, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    #[allow(clippy::type_complexity)]
+    pub fn destroy(
+        channel_pointer: AnyChannelPointer,
+        writer: AnyWriter,
+        reader: AnyReader,
+    ) -> (Option<Box<dyn Any + Send>>, Option<Box<dyn Any + Send>>) {
+        let (read_only, writable) = DirectedChannel::destroy_single(
+            channel_pointer.channel_pointer,
+            reader.data_pointer,
+            writer.data_pointer,
+        );
+        (
+            read_only.0.into_inner().map(|value| value.value),
+            writable.0.into_inner().map(|value| value.value),
+        )
+    }
+}
+
+impl AnyChannelPointer {
+    /// Move the value put since the last flush to the reader, replacing a value the reader did not take yet.
+    /// If no value was put since the last flush, the flush is skipped and the reader keeps its value.
+    pub fn flush(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.channel_pointer.flush_with_if_dirty(&TakeFlush);
+    }
+
+    /// Attach a label to the channel, see [DirectedChannelPointer::with_label].
+    pub fn with_label(self, label: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            channel_pointer: self.channel_pointer.with_label(label),
+        }
+    }
+
+    /// Shorthand for [AnyChannel::destroy].
+    #[allow(clippy::type_complexity)]
+    pub fn destroy(
+        self,
+        writer: AnyWriter,
+        reader: AnyReader,
+    ) -> (Option<Box<dyn Any + Send>>, Option<Box<dyn Any + Send>>) {
+        AnyChannel::destroy(self, writer, reader)
+    }
+}
+
+impl SwapChannel for AnyChannelPointer {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        AnyChannelPointer::flush(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.channel_pointer.label()
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel_pointer.stats(channel_key).generation)
+    }
+
+    fn dirty(&self, channel_key: &ChannelKey) -> Option<bool> {
+        Some(self.channel_pointer.is_dirty(channel_key))
+    }
+}
+
+impl AnyWriter {
+    /// Put the given value, such that it is moved to the reader by the next flush.
+    /// A value that was put since the last flush is replaced.
+    pub fn put<T: Any + Send>(&mut self, data_key: &DataKey, value: T) {
+        self.data_pointer.get_mut(data_key).0.set(Some(AnyValue {
+            value: Box::new(value),
+            type_name: any::type_name::<T>(),
+        }));
+    }
+}
+
+impl AnyReader {
+    /// Take the value moved to the reader by the last flush, if it was not taken yet.
+    pub fn take(&mut self, data_key: &DataKey) -> Option<Box<dyn Any + Send>> {
+        self.data_pointer
+            .get(data_key)
+            .0
+            .take()
+            .map(|value| value.value)
+    }
+
+    /// Take the value moved to the reader by the last flush, if it was not taken yet, and downcast it to `T`.
+    /// If the value is not a `T`, it is taken nevertheless and returned in the error.
+    pub fn take_downcast<T: Any>(&mut self, data_key: &DataKey) -> Result<Option<T>, WrongType> {
+        match self.data_pointer.get(data_key).0.take() {
+            None => Ok(None),
+            Some(AnyValue { value, type_name }) => match value.downcast() {
+                Ok(value) => Ok(Some(*value)),
+                Err(value) => Err(WrongType {
+                    value,
+                    type_name,
+                    requested: any::type_name::<T>(),
+                }),
+            },
+        }
+    }
+}
+
+impl WrongType {
+    /// The [TypeId] of the value, i.e. of its actual type, not of the requested one.
+    pub fn actual_type_id(&self) -> TypeId {
+        (*self.value).type_id()
+    }
+
+    /// The name of the type of the value, as returned by [core::any::type_name].
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The name of the requested type.
+    pub fn requested(&self) -> &'static str {
+        self.requested
+    }
+
+    /// The value, e.g. to downcast it to another type.
+    pub fn into_value(self) -> Box<dyn Any + Send> {
+        self.value
+    }
+}
+
+impl fmt::Debug for WrongType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WrongType")
+            .field("type_name", &self.type_name)
+            .field("requested", &self.requested)
+            .finish()
+    }
+}
+
+impl fmt::Display for WrongType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a value of type {}, but got {}",
+            self.requested, self.type_name
+        )
+    }
+}
+
+impl std::error::Error for WrongType {}
+
+#[cfg(test)]
+mod tests {
+    use core::any::TypeId;
+
+    use crate::{any::AnyChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut writer, mut reader) = AnyChannel::create();
+
+        let data_key = master_key.get_data_key();
+        writer.put(&data_key, String::from("hello"));
+        assert!(reader.take(&data_key).is_none());
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        let error = reader.take_downcast::<u32>(&data_key).unwrap_err();
+        assert_eq!(error.actual_type_id(), TypeId::of::<String>());
+        assert_eq!(
+            error.to_string(),
+            "expected a value of type u32, but got alloc::string::String"
+        );
+        // The value is not lost by the failed downcast.
+        assert_eq!(*error.into_value().downcast::<String>().unwrap(), "hello");
+        assert_eq!(reader.take_downcast::<u32>(&data_key).unwrap(), None);
+
+        writer.put(&data_key, 1u32);
+        let channel_key = data_key.into_channel_key();
+        channel_pointer.flush(&channel_key);
+        // A flush without a new value keeps the value of the reader.
+        channel_pointer.flush(&channel_key);
+
+        let data_key = channel_key.into_data_key();
+        writer.put(&data_key, 2u32);
+        assert_eq!(reader.take_downcast::<u32>(&data_key).unwrap(), Some(1));
+        let (published, pending) = channel_pointer.destroy(writer, reader);
+        assert!(published.is_none());
+        assert_eq!(*pending.unwrap().downcast::<u32>().unwrap(), 2);
+    }
+}
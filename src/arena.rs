@@ -0,0 +1,432 @@
+//! An arena that holds many channels in a few large allocations.
+//! Channels created in an arena are accessed and destroyed through the usual pointer types,
+//! only their storage is reclaimed when the whole arena is reset or dropped.
+
+use core::{
+    fmt,
+    mem::{self, MaybeUninit},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::{
+    alloc::{self, Layout},
+    thread,
+};
+
+use crate::{
+    allocator::{self, Allocator},
+    bidirected::{BidirectedChannel, BidirectedChannelPointer, BidirectedDataPointer},
+    directed::{DirectedChannel, DirectedChannelPointer, ReadOnlyDataPointer, WritableDataPointer},
+    heap, rt,
+    undirected::{UndirectedChannel, UndirectedChannelPointer, UndirectedDataPointer},
+};
+
+/// The default size of the allocations of a [ChannelArena], in bytes.
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The minimum alignment of the allocations of a [ChannelArena].
+const CHUNK_ALIGN: usize = 64;
+
+/// An arena used for creating many channels with few allocations.
+/// The channels are allocated in large chunks, and destroying a channel returns its `Data` fields,
+/// but its storage is only reclaimed when the arena is [reset](ChannelArena::reset) or dropped.
+///
+/// The arena counts the channels that were created in it but not yet destroyed,
+/// and resetting or dropping it **panics** if there are any.
+/// When dropping panics, the chunks are leaked, such that the pointers to the remaining channels stay valid.
+pub struct ChannelArena {
+    /// The chunks of the arena and their layouts, in the order they were allocated.
+    chunks: Vec<(NonNull<u8>, Layout)>,
+    /// The index of the chunk that channels are currently allocated in.
+    current: usize,
+    /// The offset of the first free byte in the current chunk.
+    offset: usize,
+    chunk_size: usize,
+    /// The number of channels in the arena that were not yet destroyed.
+    /// This is boxed, since the channels refer to it.
+    live: Box<AtomicUsize>,
+}
+
+impl ChannelArena {
+    /// Create an empty arena that allocates its chunks with a default size.
+    pub fn new() -> Self {
+        Self::with_chunk_size(DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Create an empty arena that allocates its chunks with the given size in bytes.
+    /// Channels larger than the chunk size get a chunk of their own.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        Self {
+            chunks: Vec::new(),
+            current: 0,
+            offset: 0,
+            chunk_size,
+            live: Box::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Create an undirected channel in this arena, like [UndirectedChannel::create].
+    pub fn create_undirected<Data>(
+        &mut self,
+        data1: Data,
+        data2: Data,
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        UndirectedChannel::hand_out(self.allocate(UndirectedChannel::new(data1, data2)))
+    }
+
+    /// Create a directed channel in this arena, like [DirectedChannel::create].
+    pub fn create_directed<Data>(
+        &mut self,
+        read_only: Data,
+        writable: Data,
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        DirectedChannel::hand_out(self.allocate(DirectedChannel::new(read_only, writable)))
+    }
+
+    /// Create a bidirected channel in this arena, like [BidirectedChannel::create].
+    pub fn create_bidirected<Data1, Data2>(
+        &mut self,
+        read_only1: Data1,
+        writable1: Data1,
+        read_only2: Data2,
+        writable2: Data2,
+    ) -> (
+        BidirectedChannelPointer<Data1, Data2>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
+    ) {
+        BidirectedChannel::hand_out(self.allocate(BidirectedChannel::new(
+            DirectedChannel::new(read_only1, writable1),
+            DirectedChannel::new(read_only2, writable2),
+        )))
+    }
+
+    /// The number of channels that were created in this arena and were not yet destroyed.
+    pub fn live(&self) -> usize {
+        self.live.load(Ordering::Acquire)
+    }
+
+    /// The total size of the chunks of this arena in bytes.
+    pub fn capacity(&self) -> usize {
+        self.chunks.iter().map(|(_, layout)| layout.size()).sum()
+    }
+
+    /// Reclaim the storage of all channels created in this arena, keeping the chunks for new channels.
+    ///
+    /// **Panics** if not all channels created in this arena were destroyed.
+    pub fn reset(&mut self) {
+        let live = self.live();
+        assert_eq!(
+            live, 0,
+            "{} channels in the arena were not destroyed before resetting it",
+            live
+        );
+        self.current = 0;
+        self.offset = 0;
+    }
+
+    /// Move the given channel into this arena.
+    fn allocate<T>(&mut self, channel: T) -> ChannelBox<T> {
+        let layout = Layout::new::<T>();
+        let pointer = loop {
+            if let Some(&(chunk, chunk_layout)) = self.chunks.get(self.current) {
+                let start = chunk.as_ptr() as usize + self.offset;
+                let start = (start + layout.align() - 1) & !(layout.align() - 1);
+                let offset = start - chunk.as_ptr() as usize;
+                if offset + layout.size() <= chunk_layout.size() {
+                    self.offset = offset + layout.size();
+                    break chunk.as_ptr().wrapping_add(offset) as *mut T;
+                }
+                self.current += 1;
+                self.offset = 0;
+            } else {
+                let chunk_layout = Layout::from_size_align(
+                    self.chunk_size.max(layout.size()),
+                    CHUNK_ALIGN.max(layout.align()),
+                )
+                .expect("chunk size overflows");
+                let chunk = unsafe { alloc::alloc(chunk_layout) };
+                let chunk =
+                    NonNull::new(chunk).unwrap_or_else(|| alloc::handle_alloc_error(chunk_layout));
+                self.chunks.push((chunk, chunk_layout));
+            }
+        };
+
+        unsafe { pointer.write(channel) };
+        self.live.fetch_add(1, Ordering::Relaxed);
+        ChannelBox {
+            pointer: unsafe { NonNull::new_unchecked(pointer) },
+            storage: Storage::Arena(&*self.live),
+        }
+    }
+}
+
+impl Default for ChannelArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ChannelArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelArena")
+            .field("chunks", &self.chunks.len())
+            .field("capacity", &self.capacity())
+            .field("live", &self.live())
+            .finish()
+    }
+}
+
+impl Drop for ChannelArena {
+    fn drop(&mut self) {
+        let live = self.live();
+        if live != 0 {
+            // The remaining channels still point into the chunks and to the live counter.
+            mem::forget(mem::take(&mut self.chunks));
+            mem::forget(mem::replace(&mut self.live, Box::new(AtomicUsize::new(0))));
+            if !thread::panicking() {
+                panic!(
+                    "{} channels in the arena were not destroyed before dropping it",
+                    live
+                );
+            }
+            return;
+        }
+
+        for (chunk, layout) in self.chunks.drain(..) {
+            unsafe { alloc::dealloc(chunk.as_ptr(), layout) };
+        }
+    }
+}
+
+unsafe impl Send for ChannelArena {}
+
+/// The owning pointer from a channel pointer to its channel,
+/// which lives either in its own heap allocation, in a [ChannelArena], or in memory from an [Allocator].
+pub(crate) struct ChannelBox<T> {
+    pointer: NonNull<T>,
+    storage: Storage,
+}
+
+/// Where the channel of a [ChannelBox] lives.
+#[derive(Clone, Copy)]
+enum Storage {
+    /// In its own allocation from the global allocator.
+    Global,
+    /// In a [ChannelArena], with the given live channel counter.
+    Arena(*const AtomicUsize),
+    /// In an allocation from an [Allocator], which is freed by the given function.
+    Allocator(unsafe fn(*mut u8)),
+}
+
+impl<T> ChannelBox<T> {
+    /// Move the given channel into its own heap allocation.
+    pub(crate) fn new(channel: T) -> Self {
+        Self::from_box(Box::new(channel))
+    }
+
+    /// Take ownership of the given heap-allocated channel.
+    pub(crate) fn from_box(channel: Box<T>) -> Self {
+        rt::assert_not_realtime("creating a channel");
+        Self {
+            pointer: unsafe { NonNull::new_unchecked(Box::into_raw(channel)) },
+            storage: Storage::Global,
+        }
+    }
+
+    /// Move the given channel into an allocation from the given allocator.
+    pub(crate) fn new_in<A: Allocator + Send + 'static>(channel: T, allocator: A) -> Self {
+        rt::assert_not_realtime("creating a channel");
+        let (pointer, deallocate) = allocator::allocate(channel, allocator);
+        Self {
+            pointer,
+            storage: Storage::Allocator(deallocate),
+        }
+    }
+
+    /// Move the channel out of its storage.
+    pub(crate) fn into_inner(self) -> T {
+        let channel = unsafe { self.pointer.as_ptr().read() };
+        self.release();
+        channel
+    }
+
+    /// Like [ChannelBox::into_inner], but wipe the storage before freeing it, such that no copy of the channel lingers in it.
+    #[cfg(feature = "zeroize")]
+    pub(crate) fn into_inner_wiped(self) -> T {
+        let channel = unsafe { self.pointer.as_ptr().read() };
+        unsafe { heap::wipe(self.pointer.as_ptr()) };
+        self.release();
+        channel
+    }
+
+    /// Like [ChannelBox::into_box], but wipe the storage before freeing it if the channel is moved out of it.
+    #[cfg(feature = "zeroize")]
+    pub(crate) fn into_box_wiped(self) -> Box<T> {
+        if let Storage::Global = self.storage {
+            return self.into_box();
+        }
+        let channel = heap::allocate::<T>(false);
+        unsafe {
+            ptr::copy_nonoverlapping(self.pointer.as_ptr(), channel, 1);
+            heap::wipe(self.pointer.as_ptr());
+            self.release();
+            Box::from_raw(channel)
+        }
+    }
+
+    /// Move the channel into its own heap allocation, if it is not in one already.
+    /// This avoids materialising large channels on the stack.
+    pub(crate) fn into_box(self) -> Box<T> {
+        if let Storage::Global = self.storage {
+            let channel = unsafe { Box::from_raw(self.pointer.as_ptr()) };
+            mem::forget(self);
+            channel
+        } else {
+            let channel = heap::allocate::<T>(false);
+            unsafe {
+                ptr::copy_nonoverlapping(self.pointer.as_ptr(), channel, 1);
+                self.release();
+                Box::from_raw(channel)
+            }
+        }
+    }
+
+    /// Free the storage of the channel without dropping the channel.
+    fn release(self) {
+        rt::assert_not_realtime("destroying a channel");
+        unsafe { self.free() };
+        mem::forget(self);
+    }
+
+    /// Free the storage of the channel, or return it to its arena.
+    ///
+    /// # Safety
+    ///
+    /// The channel must have been moved out or dropped, and the storage must not be used afterwards.
+    unsafe fn free(&self) {
+        match self.storage {
+            Storage::Global => drop(Box::from_raw(self.pointer.as_ptr() as *mut MaybeUninit<T>)),
+            Storage::Arena(live) => {
+                (*live).fetch_sub(1, Ordering::Release);
+            }
+            Storage::Allocator(deallocate) => deallocate(self.pointer.as_ptr() as *mut u8),
+        }
+    }
+}
+
+impl<T> Deref for ChannelBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.pointer.as_ref() }
+    }
+}
+
+impl<T> DerefMut for ChannelBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.pointer.as_mut() }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ChannelBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> Drop for ChannelBox<T> {
+    fn drop(&mut self) {
+        rt::assert_not_realtime("destroying a channel");
+        unsafe {
+            ptr::drop_in_place(self.pointer.as_ptr());
+            self.free();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{arena::ChannelArena, directed::DirectedChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut arena = ChannelArena::with_chunk_size(1024);
+        let mut capacity = None;
+
+        for round in 0..3 {
+            let mut channels: Vec<_> = (0..100)
+                .map(|i| arena.create_directed(vec![i], vec![i + round]))
+                .collect();
+            let (mut undirected, undirected1, undirected2) =
+                arena.create_undirected(String::from("a"), String::from("b"));
+            let (mut bidirected, bidirected1, bidirected2) =
+                arena.create_bidirected(0u8, 1, 2u64, 3);
+            assert_eq!(arena.live(), 102);
+
+            for (channel_pointer, _, _) in &mut channels {
+                channel_pointer.flush(&master_key.get_channel_key());
+            }
+            undirected.swap(&master_key.get_channel_key());
+            bidirected.flush(&master_key.get_channel_key());
+
+            let data_key = master_key.get_data_key();
+            for (i, (_, read_only, _)) in channels.iter().enumerate() {
+                assert_eq!(read_only.get(&data_key), &[i + round]);
+            }
+            assert_eq!(undirected1.get(&data_key), "b");
+            assert_eq!(*bidirected1.get_input(&data_key), 1);
+
+            for (i, (channel_pointer, read_only, writable)) in channels.into_iter().enumerate() {
+                if i % 2 == 0 {
+                    assert_eq!(
+                        channel_pointer.destroy_single(read_only, writable),
+                        (vec![i + round], vec![i + round])
+                    );
+                } else {
+                    let (read_only, writable) =
+                        DirectedChannel::destroy_boxed(channel_pointer, [read_only], writable);
+                    assert_eq!((*read_only, *writable), (vec![i + round], vec![i + round]));
+                }
+            }
+            assert_eq!(
+                undirected.destroy(undirected1, undirected2),
+                (String::from("b"), String::from("a"))
+            );
+            assert_eq!(bidirected.destroy(bidirected1, bidirected2), (1, 1, 3, 3));
+
+            assert_eq!(arena.live(), 0);
+            arena.reset();
+            // The chunks are reused after a reset.
+            assert_eq!(*capacity.get_or_insert(arena.capacity()), arena.capacity());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn reset_rejects_live_channels() {
+        let mut arena = ChannelArena::new();
+        let (channel_pointer, read_only, writable) = arena.create_directed(0, 0);
+        arena.reset();
+        channel_pointer.destroy_single(read_only, writable);
+    }
+
+    #[test]
+    #[should_panic]
+    fn drop_rejects_live_channels() {
+        let mut arena = ChannelArena::new();
+        let (channel_pointer, read_only, writable) = arena.create_directed(0, 0);
+        drop(arena);
+        channel_pointer.destroy_single(read_only, writable);
+    }
+}
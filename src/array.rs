@@ -0,0 +1,314 @@
+//! A fixed number of channels of the same kind behind a single channel pointer.
+//! All channels live contiguously in a single allocation, and each channel is padded to its own cache line if the `cache-padded` feature is enabled.
+
+use core::ptr;
+
+use crate::{
+    arena::ChannelBox,
+    directed::{DirectedChannel, ReadOnlyDataPointer, WritableDataPointer},
+    heap,
+    undirected::{UndirectedChannel, UndirectedDataPointer},
+    CachePadded, ChannelKey,
+};
+
+/// `N` directed channels that are flushed through a single channel pointer.
+///
+/// See [DirectedChannelArray::create] for more info.
+#[derive(Debug)]
+pub struct DirectedChannelArray<Data, const N: usize> {
+    channels: [CachePadded<DirectedChannel<Data>>; N],
+}
+
+/// A pointer to an array of directed channels.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [DirectedChannelArray::destroy] or [DirectedChannelArrayPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct DirectedChannelArrayPointer<Data, const N: usize> {
+    array: ChannelBox<DirectedChannelArray<Data, N>>,
+}
+
+/// `N` undirected channels that are swapped through a single channel pointer.
+///
+/// See [UndirectedChannelArray::create] for more info.
+#[derive(Debug)]
+pub struct UndirectedChannelArray<Data, const N: usize> {
+    channels: [CachePadded<UndirectedChannel<Data>>; N],
+}
+
+/// A pointer to an array of undirected channels.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [UndirectedChannelArray::destroy] or [UndirectedChannelArrayPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct UndirectedChannelArrayPointer<Data, const N: usize> {
+    array: ChannelBox<UndirectedChannelArray<Data, N>>,
+}
+
+/// Initialise the array at the given pointer in place, where element `i` is given by `init(i)`.
+/// If `init` panics, the elements initialised so far are leaked.
+///
+/// # Safety
+///
+/// The pointer must be valid for writes of the array.
+unsafe fn init_array<T, const N: usize>(array: *mut [T; N], init: impl Fn(usize) -> T) {
+    for index in 0..N {
+        (array as *mut T).add(index).write(init(index));
+    }
+}
+
+/// Map the array elements to an array of the same length in order.
+fn map_array<T, U, const N: usize>(array: [T; N], mut f: impl FnMut(usize, T) -> U) -> [U; N] {
+    let mut index = 0;
+    array.map(|element| {
+        index += 1;
+        f(index - 1, element)
+    })
+}
+
+impl<Data, const N: usize> DirectedChannelArray<Data, N> {
+    /// Create `N` directed channels in a single allocation, where both `Data` fields of channel `i` are initialised with `init(i)`.
+    /// The channels are initialised in place, so they are never materialised on the stack together.
+    /// Returns one [DirectedChannelArrayPointer] used to flush the channels, and the [ReadOnlyDataPointer]s and [WritableDataPointer]s of the channels, in order.
+    pub fn create(
+        init: impl Fn(usize) -> Data,
+    ) -> (
+        DirectedChannelArrayPointer<Data, N>,
+        [ReadOnlyDataPointer<Data>; N],
+        [WritableDataPointer<Data>; N],
+    ) {
+        let array = heap::allocate::<Self>(false);
+        let mut array = unsafe {
+            init_array(ptr::addr_of_mut!((*array).channels), |index| {
+                CachePadded(DirectedChannel::new(init(index), init(index)))
+            });
+            ChannelBox::from_box(Box::from_raw(array))
+        };
+        let read_only_data_pointers = map_array([(); N], |index, ()| {
+            array.channels[index].read_only_data_pointer()
+        });
+        let writable_data_pointers = map_array([(); N], |index, ()| {
+            array.channels[index].writable_data_pointer()
+        });
+        (
+            DirectedChannelArrayPointer { array },
+            read_only_data_pointers,
+            writable_data_pointers,
+        )
+    }
+
+    /// Destroys the directed channels linked with the given pointers (see [DirectedChannelArray::create]).
+    /// Returns the read-only and the writable `Data` field of each channel, in order.
+    ///
+    /// **Panics** if not all data pointers point to their respective channel in the array.
+    pub fn destroy(
+        channel_pointer: DirectedChannelArrayPointer<Data, N>,
+        read_only_data_pointers: [ReadOnlyDataPointer<Data>; N],
+        writable_data_pointers: [WritableDataPointer<Data>; N],
+    ) -> [(Data, Data); N] {
+        let array = channel_pointer.array;
+        for (index, channel) in array.channels.iter().enumerate() {
+            assert!(
+                ptr::eq(read_only_data_pointers[index].data, &channel.read_only)
+                    && ptr::eq(writable_data_pointers[index].data, &channel.writable),
+                "the data pointers at index {} do not point to the channel at that index",
+                index
+            );
+        }
+
+        map_array(array.into_inner().channels, |_, channel| {
+            (channel.0.read_only, channel.0.writable)
+        })
+    }
+}
+
+impl<Data: Clone, const N: usize> DirectedChannelArrayPointer<Data, N> {
+    /// Flush all channels in the array in order, see [`DirectedChannelPointer::flush`](crate::directed::DirectedChannelPointer::flush).
+    pub fn flush_all(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        for channel in &mut self.array.channels {
+            channel.flush_if_dirty();
+        }
+    }
+
+    /// Flush the channel at the given index, see [`DirectedChannelPointer::flush`](crate::directed::DirectedChannelPointer::flush).
+    ///
+    /// **Panics** if the index is out of range.
+    pub fn flush_index(&mut self, #[allow(unused)] channel_key: &ChannelKey, index: usize) {
+        self.array.channels[index].flush_if_dirty();
+    }
+}
+
+impl<Data, const N: usize> DirectedChannelArrayPointer<Data, N> {
+    /// Shorthand for [DirectedChannelArray::destroy].
+    pub fn destroy(
+        self,
+        read_only_data_pointers: [ReadOnlyDataPointer<Data>; N],
+        writable_data_pointers: [WritableDataPointer<Data>; N],
+    ) -> [(Data, Data); N] {
+        DirectedChannelArray::destroy(self, read_only_data_pointers, writable_data_pointers)
+    }
+}
+
+impl<Data, const N: usize> UndirectedChannelArray<Data, N> {
+    /// Create `N` undirected channels in a single allocation, where both `Data` fields of channel `i` are initialised with `init(i)`.
+    /// Returns one [UndirectedChannelArrayPointer] used to swap the channels, the first [UndirectedDataPointer]s of the channels in order,
+    /// and the second [UndirectedDataPointer]s of the channels in order.
+    pub fn create(
+        init: impl Fn(usize) -> Data,
+    ) -> (
+        UndirectedChannelArrayPointer<Data, N>,
+        [UndirectedDataPointer<Data>; N],
+        [UndirectedDataPointer<Data>; N],
+    ) {
+        let array = heap::allocate::<Self>(false);
+        let mut array = unsafe {
+            init_array(ptr::addr_of_mut!((*array).channels), |index| {
+                CachePadded(UndirectedChannel::new(init(index), init(index)))
+            });
+            ChannelBox::from_box(Box::from_raw(array))
+        };
+        let data_pointers1 =
+            map_array([(); N], |index, ()| array.channels[index].data_pointers().0);
+        let data_pointers2 =
+            map_array([(); N], |index, ()| array.channels[index].data_pointers().1);
+        (
+            UndirectedChannelArrayPointer { array },
+            data_pointers1,
+            data_pointers2,
+        )
+    }
+
+    /// Destroys the undirected channels linked with the given pointers (see [UndirectedChannelArray::create]).
+    /// Returns the `Data` fields of each channel in the order of the given data pointers,
+    /// which may be exchanged between the two arrays at the same index.
+    ///
+    /// **Panics** if not all data pointers point to their respective channel in the array.
+    pub fn destroy(
+        channel_pointer: UndirectedChannelArrayPointer<Data, N>,
+        data_pointers1: [UndirectedDataPointer<Data>; N],
+        data_pointers2: [UndirectedDataPointer<Data>; N],
+    ) -> [(Data, Data); N] {
+        let array = channel_pointer.array;
+        let mut swapped = [false; N];
+        for (index, channel) in array.channels.iter().enumerate() {
+            let (data_pointer1, data_pointer2) = (&data_pointers1[index], &data_pointers2[index]);
+            let data1 = &channel.data1.0 as *const Data;
+            let data2 = &channel.data2.0 as *const Data;
+            if ptr::eq(data_pointer1.data, data2) && ptr::eq(data_pointer2.data, data1) {
+                swapped[index] = true;
+            } else {
+                assert!(
+                    ptr::eq(data_pointer1.data, data1) && ptr::eq(data_pointer2.data, data2),
+                    "the data pointers at index {} do not point to the channel at that index",
+                    index
+                );
+            }
+        }
+
+        map_array(array.into_inner().channels, |index, channel| {
+            let channel = channel.0;
+            if swapped[index] {
+                (channel.data2.0, channel.data1.0)
+            } else {
+                (channel.data1.0, channel.data2.0)
+            }
+        })
+    }
+}
+
+impl<Data, const N: usize> UndirectedChannelArrayPointer<Data, N> {
+    /// Swap the `Data` fields of all channels in the array, see [`UndirectedChannelPointer::swap`](crate::undirected::UndirectedChannelPointer::swap).
+    pub fn swap_all(&mut self, channel_key: &ChannelKey) {
+        for channel in &mut self.array.channels {
+            channel.swap(channel_key);
+        }
+    }
+
+    /// Swap the `Data` fields of the channel at the given index, see [`UndirectedChannelPointer::swap`](crate::undirected::UndirectedChannelPointer::swap).
+    ///
+    /// **Panics** if the index is out of range.
+    pub fn swap_index(&mut self, channel_key: &ChannelKey, index: usize) {
+        self.array.channels[index].swap(channel_key);
+    }
+
+    /// Shorthand for [UndirectedChannelArray::destroy].
+    pub fn destroy(
+        self,
+        data_pointers1: [UndirectedDataPointer<Data>; N],
+        data_pointers2: [UndirectedDataPointer<Data>; N],
+    ) -> [(Data, Data); N] {
+        UndirectedChannelArray::destroy(self, data_pointers1, data_pointers2)
+    }
+}
+
+unsafe impl<Data, const N: usize> Send for DirectedChannelArrayPointer<Data, N> {}
+unsafe impl<Data, const N: usize> Send for UndirectedChannelArrayPointer<Data, N> {}
+
+unsafe impl<Data, const N: usize> Sync for DirectedChannelArrayPointer<Data, N> {}
+unsafe impl<Data, const N: usize> Sync for UndirectedChannelArrayPointer<Data, N> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        array::{DirectedChannelArray, UndirectedChannelArray},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only_data_pointers, mut writable_data_pointers) =
+            DirectedChannelArray::<_, 4>::create(|index| vec![index]);
+
+        let data_key = master_key.get_data_key();
+        for (index, writable) in writable_data_pointers.iter_mut().enumerate() {
+            writable.get_mut(&data_key).push(index * 10);
+        }
+        channel_pointer.flush_index(&master_key.get_channel_key(), 1);
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only_data_pointers[0].get(&data_key), &[0]);
+        assert_eq!(read_only_data_pointers[1].get(&data_key), &[1, 10]);
+
+        channel_pointer.flush_all(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only_data_pointers[3].get(&data_key), &[3, 30]);
+
+        let contents = channel_pointer.destroy(read_only_data_pointers, writable_data_pointers);
+        assert_eq!(contents[2], (vec![2, 20], vec![2, 20]));
+    }
+
+    #[test]
+    fn undirected() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointers1, data_pointers2) =
+            UndirectedChannelArray::<_, 3>::create(|index| index);
+
+        *data_pointers1[0].get_mut(&master_key.get_data_key()) = 10;
+        channel_pointer.swap_index(&master_key.get_channel_key(), 0);
+        channel_pointer.swap_all(&master_key.get_channel_key());
+        channel_pointer.swap_index(&master_key.get_channel_key(), 2);
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointers1[0].get(&data_key), 10);
+        assert_eq!(*data_pointers2[0].get(&data_key), 0);
+
+        let [data1, data3, data5] = data_pointers1;
+        let [data2, data4, data6] = data_pointers2;
+        assert_eq!(
+            channel_pointer.destroy([data2, data3, data6], [data1, data4, data5]),
+            [(0, 10), (1, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn destroy_rejects_misplaced_pointers() {
+        let (channel_pointer, data_pointers1, data_pointers2) =
+            UndirectedChannelArray::<_, 2>::create(|index| index);
+
+        let [data1, data3] = data_pointers1;
+        let [data2, data4] = data_pointers2;
+        let _ = channel_pointer.destroy([data1, data3], [data4, data2]);
+    }
+}
@@ -0,0 +1,348 @@
+//! A phase-free channel for small `Copy` values, such as a frame counter or a position.
+//!
+//! The [atomic channel](AtomicChannel) needs no keys at all: the writer stores and the readers load the value at any time.
+//! It is the escape hatch for data too small to justify a phase-based channel.
+//! A value that fits into a machine word is stored in a single atomic,
+//! and larger values are protected by a seqlock, i.e. an atomic sequence counter, where a reader retries a load that overlapped with a store.
+//!
+//! | Use | if |
+//! |-----|----|
+//! | [AtomicChannel] | the value is a few words of `Copy` data, and readers only need the latest value |
+//! | [ReadMostly](crate::read_mostly::ReadMostly) | the value is large or not `Copy`, and readers do not participate in the phase protocol |
+//! | [DirectedChannel](crate::directed::DirectedChannel) | the value is large, and all threads participate in the phase protocol |
+//! | [QueueChannel](crate::queue::QueueChannel) | every value must be delivered, not only the latest |
+//!
+//! Values larger than a few words make readers retry more often while the writer stores, and should use a phase-based channel instead.
+//!
+//! The value is copied word by word through atomics.
+//! Since stores and loads never overlap on the same word without the seqlock noticing, a load never returns a torn value.
+//! Copying the bytes of a value requires all of them to be initialised, hence `T` must be [NoUninit],
+//! which excludes types with padding bytes:
+//!
+//! ```compile_fail
+//! use two_phase_channel::atomic::AtomicChannel;
+//!
+//! // One padding byte after the `u8`.
+//! let (writer, reader) = AtomicChannel::create((1u8, 2u16));
+//! ```
+//!
+//! Reorder or pad such types explicitly instead, and derive [NoUninit] for them.
+//!
+//! The seqlock is tested with [loom](https://docs.rs/loom), see `RUSTFLAGS="--cfg loom" cargo test --release --features bytemuck --lib atomic`.
+
+use bytemuck::NoUninit;
+
+use core::{
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr,
+};
+
+#[cfg(loom)]
+use loom::{
+    hint,
+    sync::{
+        atomic::{fence, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+#[cfg(not(loom))]
+use std::{
+    hint,
+    sync::{
+        atomic::{fence, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+const WORD: usize = mem::size_of::<usize>();
+
+/// A phase-free channel used for sending small `Copy` values without keys.
+///
+/// See [AtomicChannel::create] for more info.
+#[derive(Debug)]
+pub struct AtomicChannel<T> {
+    phantom: PhantomData<T>,
+}
+
+/// The writer of an atomic channel.
+#[derive(Debug)]
+pub struct AtomicWriter<T> {
+    cell: Arc<AtomicCell<T>>,
+}
+
+/// A reader of an atomic channel.
+/// Clone it to hand out further readers.
+#[derive(Debug)]
+pub struct AtomicReader<T> {
+    cell: Arc<AtomicCell<T>>,
+}
+
+/// The value of an atomic channel, stored as atomic words.
+#[derive(Debug)]
+struct AtomicCell<T> {
+    /// The sequence counter of the seqlock, which is odd while a store is in progress.
+    /// Unused if the value fits into a single word.
+    sequence: AtomicUsize,
+    words: Box<[AtomicUsize]>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: NoUninit> AtomicChannel<T> {
+    /// Create an atomic channel holding the given value, and hand out its writer and a first reader.
+    pub fn create(value: T) -> (AtomicWriter<T>, AtomicReader<T>) {
+        let word_count = (mem::size_of::<T>() + WORD - 1) / WORD;
+        let cell = Arc::new(AtomicCell {
+            sequence: AtomicUsize::new(0),
+            words: (0..word_count).map(|_| AtomicUsize::new(0)).collect(),
+            phantom: PhantomData,
+        });
+        let writer = AtomicWriter { cell };
+        writer.store(value);
+        let reader = AtomicReader {
+            cell: writer.cell.clone(),
+        };
+        (writer, reader)
+    }
+
+    /// Returns `true` if values of type `T` are stored in a single atomic, and hence without the seqlock.
+    pub fn is_lock_free() -> bool {
+        mem::size_of::<T>() <= WORD
+    }
+}
+
+impl<T: NoUninit> AtomicWriter<T> {
+    /// Store the given value, such that following loads return it.
+    /// Waits only for concurrent stores, never for readers.
+    pub fn store(&self, value: T) {
+        let cell: &AtomicCell<T> = &self.cell;
+        if AtomicChannel::<T>::is_lock_free() {
+            if let Some(word) = cell.words.first() {
+                word.store(word_of(&value, 0), Ordering::Release);
+            }
+            return;
+        }
+
+        let sequence = loop {
+            let sequence = cell.sequence.load(Ordering::Relaxed);
+            if sequence % 2 == 0
+                && cell
+                    .sequence
+                    .compare_exchange_weak(
+                        sequence,
+                        sequence.wrapping_add(1),
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            {
+                break sequence;
+            }
+            hint::spin_loop();
+        };
+        // Readers that see any of the following words also see the odd sequence counter.
+        fence(Ordering::Release);
+        for (index, word) in cell.words.iter().enumerate() {
+            word.store(word_of(&value, index), Ordering::Relaxed);
+        }
+        cell.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Create a new reader of this channel.
+    pub fn reader(&self) -> AtomicReader<T> {
+        AtomicReader {
+            cell: self.cell.clone(),
+        }
+    }
+}
+
+impl<T: NoUninit> AtomicReader<T> {
+    /// Load the value of the last store.
+    /// If a store is in progress, this returns the value of either the store before or the store in progress, but never a mix of both.
+    pub fn load(&self) -> T {
+        let cell: &AtomicCell<T> = &self.cell;
+        let mut value = MaybeUninit::<T>::uninit();
+        if AtomicChannel::<T>::is_lock_free() {
+            if let Some(word) = cell.words.first() {
+                set_word(&mut value, 0, word.load(Ordering::Acquire));
+            }
+            return unsafe { value.assume_init() };
+        }
+
+        loop {
+            let before = cell.sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+            for (index, word) in cell.words.iter().enumerate() {
+                set_word(&mut value, index, word.load(Ordering::Relaxed));
+            }
+            // The sequence counter is loaded after all words.
+            fence(Ordering::Acquire);
+            if cell.sequence.load(Ordering::Relaxed) == before {
+                return unsafe { value.assume_init() };
+            }
+        }
+    }
+}
+
+impl<T> Clone for AtomicReader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            cell: self.cell.clone(),
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for AtomicCell<T> {}
+unsafe impl<T: Send> Sync for AtomicCell<T> {}
+
+/// The word at the given index of the bytes of the given value, padded with zeroes.
+fn word_of<T: NoUninit>(value: &T, index: usize) -> usize {
+    let offset = index * WORD;
+    let length = WORD.min(mem::size_of::<T>() - offset);
+    let mut word = 0usize;
+    unsafe {
+        ptr::copy_nonoverlapping(
+            (value as *const T as *const u8).add(offset),
+            &mut word as *mut usize as *mut u8,
+            length,
+        );
+    }
+    word
+}
+
+/// Write the given word to the given index of the bytes of the given value, ignoring the padding of the last word.
+fn set_word<T>(value: &mut MaybeUninit<T>, index: usize, word: usize) {
+    let offset = index * WORD;
+    let length = WORD.min(mem::size_of::<T>() - offset);
+    unsafe {
+        ptr::copy_nonoverlapping(
+            &word as *const usize as *const u8,
+            (value.as_mut_ptr() as *mut u8).add(offset),
+            length,
+        );
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use crate::atomic::AtomicChannel;
+
+    #[test]
+    fn test() {
+        let (writer, reader) = AtomicChannel::create(1u64);
+        assert!(AtomicChannel::<u64>::is_lock_free());
+        assert_eq!(reader.load(), 1);
+        writer.store(2);
+        assert_eq!(writer.reader().load(), 2);
+
+        let (writer, reader) = AtomicChannel::create([1.0f32, 2.0, 3.0]);
+        assert!(!AtomicChannel::<[f32; 3]>::is_lock_free());
+        writer.store([4.0, 5.0, 6.0]);
+        assert_eq!(reader.clone().load(), [4.0, 5.0, 6.0]);
+
+        let (writer, reader) = AtomicChannel::create(());
+        writer.store(());
+        reader.load();
+    }
+
+    #[test]
+    fn explicitly_padded() {
+        #[derive(Debug, Clone, Copy, PartialEq, bytemuck::NoUninit)]
+        #[repr(C)]
+        struct Position {
+            x: u16,
+            layer: u8,
+            padding: u8,
+            y: u32,
+        }
+
+        let position = |x| Position {
+            x,
+            layer: 1,
+            padding: 0,
+            y: 3,
+        };
+        let (writer, reader) = AtomicChannel::create(position(0));
+        assert!(AtomicChannel::<Position>::is_lock_free());
+        writer.store(position(2));
+        assert_eq!(reader.load(), position(2));
+    }
+
+    #[test]
+    fn loads_are_never_torn() {
+        let (writer, reader) = AtomicChannel::create([0u64; 4]);
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let reader = reader.clone();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    while last < 10000 {
+                        let value = reader.load();
+                        assert!(value.iter().all(|&element| element == value[0]));
+                        assert!(value[0] >= last);
+                        last = value[0];
+                    }
+                })
+            })
+            .collect();
+        for value in 1..=10000 {
+            writer.store([value; 4]);
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::thread;
+
+    use crate::atomic::AtomicChannel;
+
+    #[test]
+    fn seqlock_load_is_never_torn() {
+        loom::model(|| {
+            let (writer, reader) = AtomicChannel::create([0usize; 2]);
+            let thread = thread::spawn(move || {
+                let value = reader.load();
+                assert_eq!(value[0], value[1]);
+                value[0]
+            });
+            writer.store([1; 2]);
+            writer.store([2; 2]);
+            assert!(thread.join().unwrap() <= 2);
+        });
+    }
+
+    #[test]
+    fn seqlock_concurrent_stores() {
+        loom::model(|| {
+            let (writer, reader) = AtomicChannel::create([0usize; 2]);
+            let writer = loom::sync::Arc::new(writer);
+            let other_writer = writer.clone();
+            let thread = thread::spawn(move || other_writer.store([1; 2]));
+            writer.store([2; 2]);
+            thread.join().unwrap();
+            let value = reader.load();
+            assert_eq!(value[0], value[1]);
+            assert!(value[0] == 1 || value[0] == 2);
+        });
+    }
+
+    #[test]
+    fn single_word() {
+        loom::model(|| {
+            let (writer, reader) = AtomicChannel::create(0u32);
+            let thread = thread::spawn(move || reader.load());
+            writer.store(1);
+            assert!(thread.join().unwrap() <= 1);
+        });
+    }
+}
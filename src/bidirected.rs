@@ -3,9 +3,35 @@
 //! Each endpoint has an input and an output pointer,
 //! where the input of one endpoint is connected to the output of the other endpoint via a directed channel.
 
+use core::{
+    iter,
+    mem::{self, MaybeUninit},
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use std::{
+    any::{self, Any},
+    borrow::Cow,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Barrier},
+    thread,
+    time::Instant,
+};
+
+pub use crate::common::BidirectedStats;
 use crate::{
-    directed::{DirectedChannel, ReadOnlyDataPointer, WritableDataPointer},
-    ChannelKey, DataKey,
+    allocator::Allocator,
+    arena::ChannelBox,
+    checked::KeyPhase,
+    common,
+    directed::{
+        DirectedChannel, DirectedChannelPointer, DirectedSnapshot, FlushStrategy,
+        ReadOnlyDataPointer, WritableDataPointer,
+    },
+    erased::{self, DestroyError, ErasedDataPointer, ErasedDestroy},
+    instrument,
+    report::{self, ChannelReport},
+    ChannelKey, DataKey, GenerationPointer, Label, MasterKey, Projection, SwapChannel,
 };
 
 /// A bidirected channel used for communication between threads.
@@ -16,6 +42,46 @@ use crate::{
 pub struct BidirectedChannel<Data1, Data2> {
     channel1: DirectedChannel<Data1>,
     channel2: DirectedChannel<Data2>,
+    /// Whether the first and the second data pointer, in this order, were disconnected (see [BidirectedDataPointer::disconnect]).
+    /// These are atomic, since one endpoint may disconnect while the other endpoint checks for it in the same data phase.
+    disconnected: [AtomicBool; 2],
+}
+
+/// A bidirected channel that carries the same type in both directions.
+/// Both of its endpoints have the same type [SymmetricEndpoint], so they can be stored together.
+///
+/// See [BidirectedChannel::create_symmetric] for more info.
+pub type SymmetricBidirectedChannel<Data> = BidirectedChannel<Data, Data>;
+
+/// An endpoint of a [SymmetricBidirectedChannel].
+pub type SymmetricEndpoint<Data> = BidirectedDataPointer<Data, Data>;
+
+/// A copy of all four `Data` fields of a bidirected channel, taken during the channel phase.
+/// The forward direction transmits `Data1` from the second to the first data pointer,
+/// and the backward direction transmits `Data2` from the first to the second data pointer.
+/// See [BidirectedChannelPointer::snapshot].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BidirectedSnapshot<Data1, Data2> {
+    /// The read-only `Data` of the forward direction, i.e. what was published by the last flush.
+    pub forward_published: Data1,
+    /// The writable `Data` of the forward direction, i.e. what will be published by the next flush.
+    pub forward_pending: Data1,
+    /// The read-only `Data` of the backward direction, i.e. what was published by the last flush.
+    pub backward_published: Data2,
+    /// The writable `Data` of the backward direction, i.e. what will be published by the next flush.
+    pub backward_pending: Data2,
+}
+
+/// Serialises like a [BidirectedSnapshot], but without cloning the `Data` fields.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(rename = "BidirectedSnapshot")]
+struct BidirectedSnapshotRef<'a, Data1, Data2> {
+    forward_published: &'a Data1,
+    forward_pending: &'a Data1,
+    backward_published: &'a Data2,
+    backward_pending: &'a Data2,
 }
 
 /// A pointer to a bidirected channel.
@@ -25,7 +91,24 @@ pub struct BidirectedChannel<Data1, Data2> {
 #[derive(Debug)]
 #[must_use]
 pub struct BidirectedChannelPointer<Data1, Data2> {
-    channel: Box<BidirectedChannel<Data1, Data2>>,
+    channel: ChannelBox<BidirectedChannel<Data1, Data2>>,
+    skip_disconnected: bool,
+    label: Label,
+    created_at: Option<Instant>,
+}
+
+/// A pointer to a bidirected channel that flushes each direction with its own [FlushStrategy].
+/// It can only be accessed using a [ChannelKey].
+///
+/// See [BidirectedChannel::create_with_strategies] for more info.
+///
+/// This type should always be destroyed via the [StrategyBidirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct StrategyBidirectedChannelPointer<Data1, Data2, Forward, Backward> {
+    channel_pointer: BidirectedChannelPointer<Data1, Data2>,
+    forward: Forward,
+    backward: Backward,
 }
 
 /// A pair of pointers to the data fields of a bidirected channel.
@@ -37,6 +120,66 @@ pub struct BidirectedChannelPointer<Data1, Data2> {
 pub struct BidirectedDataPointer<Input, Output> {
     input: ReadOnlyDataPointer<Input>,
     output: WritableDataPointer<Output>,
+    disconnected: *const AtomicBool,
+    peer_disconnected: *const AtomicBool,
+    input_generation: GenerationPointer,
+}
+
+/// A data pointer of a bidirected channel that was disconnected via [BidirectedDataPointer::disconnect].
+/// It cannot access the channel anymore, and only serves as proof of the disconnection when destroying the channel
+/// via [BidirectedChannelPointer::destroy_disconnected1] or [BidirectedChannelPointer::destroy_disconnected2].
+#[derive(Debug)]
+#[must_use]
+pub struct DisconnectedEndpoint<Input, Output> {
+    data_pointer: BidirectedDataPointer<Input, Output>,
+}
+
+/// A builder for a bidirected channel that names the direction of each `Data`, instead of relying on the argument order of [BidirectedChannel::create].
+/// The forward direction transmits `Data1` from the second to the first data pointer,
+/// and the backward direction transmits `Data2` from the first to the second data pointer.
+///
+/// Start with [BidirectedChannelBuilder::forward], continue with [BidirectedChannelBuilder::backward], and finish with [BidirectedChannelBuilder::build].
+#[derive(Debug)]
+#[must_use]
+pub struct BidirectedChannelBuilder<Data1, Data2> {
+    forward: (Data1, Data1),
+    backward: (Data2, Data2),
+}
+
+impl<Data1> BidirectedChannelBuilder<Data1, ()> {
+    /// Initialise both `Data` fields of the forward direction by calling the given function twice.
+    pub fn forward(mut init: impl FnMut() -> Data1) -> Self {
+        Self {
+            forward: (init(), init()),
+            backward: ((), ()),
+        }
+    }
+
+    /// Initialise both `Data` fields of the backward direction by calling the given function twice.
+    pub fn backward<Data2>(
+        self,
+        mut init: impl FnMut() -> Data2,
+    ) -> BidirectedChannelBuilder<Data1, Data2> {
+        BidirectedChannelBuilder {
+            forward: self.forward,
+            backward: (init(), init()),
+        }
+    }
+}
+
+impl<Data1, Data2> BidirectedChannelBuilder<Data1, Data2> {
+    /// Create the bidirected channel (see [BidirectedChannel::create]).
+    pub fn build(
+        self,
+    ) -> (
+        BidirectedChannelPointer<Data1, Data2>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
+    ) {
+        let (read_only1, writable1) = self.forward;
+        let (read_only2, writable2) = self.backward;
+        BidirectedChannel::create(read_only1, writable1, read_only2, writable2)
+    }
 }
 
 impl<Data1, Data2> BidirectedChannel<Data1, Data2> {
@@ -45,6 +188,8 @@ impl<Data1, Data2> BidirectedChannel<Data1, Data2> {
     /// two [`BidirectedDataPointer`]s used to read from the input end and write from the output end of the corresponding directed channels.
     ///
     /// See [`BidirectedChannelPointer::flush`] for how to exchange information between the [`BidirectedDataPointer`]s.
+    ///
+    /// This allocates the channel, and is hence not [real-time safe](crate::rt).
     pub fn create(
         read_only1: Data1,
         writable1: Data1,
@@ -54,78 +199,385 @@ impl<Data1, Data2> BidirectedChannel<Data1, Data2> {
         BidirectedChannelPointer<Data1, Data2>,
         BidirectedDataPointer<Data1, Data2>,
         BidirectedDataPointer<Data2, Data1>,
+    ) {
+        Self::hand_out(ChannelBox::new(Self::new(
+            DirectedChannel::new(read_only1, writable1),
+            DirectedChannel::new(read_only2, writable2),
+        )))
+    }
+
+    /// Create a bidirected channel like [BidirectedChannel::create], but allocate it with the given allocator.
+    /// The allocator is moved into the allocation, and dropped when the channel is destroyed.
+    pub fn create_in<A: Allocator + Send + 'static>(
+        read_only1: Data1,
+        writable1: Data1,
+        read_only2: Data2,
+        writable2: Data2,
+        allocator: A,
+    ) -> (
+        BidirectedChannelPointer<Data1, Data2>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
+    ) {
+        Self::hand_out(ChannelBox::new_in(
+            Self::new(
+                DirectedChannel::new(read_only1, writable1),
+                DirectedChannel::new(read_only2, writable2),
+            ),
+            allocator,
+        ))
+    }
+
+    /// Compose a bidirected channel from the given directed channels, as returned by [DirectedChannel::create].
+    /// The forward directed channel becomes the directed channel of `Data1`, which is written by the second data pointer and read by the first data pointer,
+    /// and the backward directed channel becomes the directed channel of `Data2`, which is written by the first data pointer and read by the second data pointer.
+    ///
+    /// The directed channels are moved into a single new allocation, so the given pointers are consumed and new pointers are handed out.
+    /// The `Data` fields, generations, dirty flags and statistics of the directed channels are kept,
+    /// but a hook set via [DirectedChannelPointer::set_on_flush] is dropped.
+    /// Since composing requires a channel key, no data pointer can access the directed channels while they are moved.
+    ///
+    /// **Panics** if not all pointers of a directed channel point to the same channel.
+    #[allow(clippy::type_complexity)]
+    pub fn compose(
+        #[allow(unused)] channel_key: &ChannelKey,
+        forward: (
+            DirectedChannelPointer<Data1>,
+            ReadOnlyDataPointer<Data1>,
+            WritableDataPointer<Data1>,
+        ),
+        backward: (
+            DirectedChannelPointer<Data2>,
+            ReadOnlyDataPointer<Data2>,
+            WritableDataPointer<Data2>,
+        ),
+    ) -> (
+        BidirectedChannelPointer<Data1, Data2>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
+    ) {
+        let (channel_pointer1, read_only1, writable1) = forward;
+        let (channel_pointer2, read_only2, writable2) = backward;
+        let channel1 = DirectedChannel::into_channel(channel_pointer1, [read_only1], writable1);
+        let channel2 = DirectedChannel::into_channel(channel_pointer2, [read_only2], writable2);
+        Self::hand_out(ChannelBox::new(Self::new(
+            channel1.into_inner(),
+            channel2.into_inner(),
+        )))
+    }
+
+    /// Decompose the bidirected channel linked with the given pointers into its two directed channels (see [BidirectedChannel::compose]).
+    /// Returns the pointers of the directed channel of `Data1` and of `Data2`, in this order, in the order returned by [DirectedChannel::create].
+    ///
+    /// Each directed channel is moved into its own new allocation, keeping its `Data` fields, generation, dirty flag and statistics.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    #[allow(clippy::type_complexity)]
+    pub fn decompose(
+        #[allow(unused)] channel_key: &ChannelKey,
+        channel_pointer: BidirectedChannelPointer<Data1, Data2>,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+    ) -> (
+        (
+            DirectedChannelPointer<Data1>,
+            ReadOnlyDataPointer<Data1>,
+            WritableDataPointer<Data1>,
+        ),
+        (
+            DirectedChannelPointer<Data2>,
+            ReadOnlyDataPointer<Data2>,
+            WritableDataPointer<Data2>,
+        ),
+    ) {
+        let channel = Self::into_channel(
+            channel_pointer,
+            data_pointer1,
+            data_pointer2,
+            iter::empty(),
+            iter::empty(),
+        );
+        let BidirectedChannel {
+            channel1, channel2, ..
+        } = channel.into_inner();
+        (
+            DirectedChannel::hand_out(ChannelBox::new(channel1)),
+            DirectedChannel::hand_out(ChannelBox::new(channel2)),
+        )
+    }
+
+    pub(crate) fn new(channel1: DirectedChannel<Data1>, channel2: DirectedChannel<Data2>) -> Self {
+        Self {
+            channel1,
+            channel2,
+            disconnected: [AtomicBool::new(false), AtomicBool::new(false)],
+        }
+    }
+
+    /// Wrap the given channel into a bidirected channel pointer and create the data pointers to it.
+    pub(crate) fn hand_out(
+        channel: ChannelBox<Self>,
+    ) -> (
+        BidirectedChannelPointer<Data1, Data2>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
     ) {
         let mut channel_pointer = BidirectedChannelPointer {
-            channel: Box::new(BidirectedChannel {
-                channel1: DirectedChannel {
-                    read_only: read_only1,
-                    writable: writable1,
-                },
-                channel2: DirectedChannel {
-                    read_only: read_only2,
-                    writable: writable2,
-                },
-            }),
-        };
-        let input_data_pointer1 = ReadOnlyDataPointer {
-            data: (&channel_pointer.channel.channel1.read_only) as *const Data1,
-        };
-        let output_data_pointer1 = WritableDataPointer {
-            data: (&mut channel_pointer.channel.channel2.writable) as *mut Data2,
-        };
-        let input_data_pointer2 = ReadOnlyDataPointer {
-            data: (&channel_pointer.channel.channel2.read_only) as *const Data2,
-        };
-        let output_data_pointer2 = WritableDataPointer {
-            data: (&mut channel_pointer.channel.channel1.writable) as *mut Data1,
+            channel,
+            skip_disconnected: false,
+            label: Label::default(),
+            created_at: report::now(),
         };
+        let [disconnected1, disconnected2] = &channel_pointer.channel.disconnected;
+        let (disconnected1, disconnected2) = (
+            disconnected1 as *const AtomicBool,
+            disconnected2 as *const AtomicBool,
+        );
+        let input_generation1 =
+            GenerationPointer::new(&channel_pointer.channel.channel1.generation);
+        let input_generation2 =
+            GenerationPointer::new(&channel_pointer.channel.channel2.generation);
+        let input_data_pointer1 = channel_pointer.channel.channel1.read_only_data_pointer();
+        let output_data_pointer1 = channel_pointer.channel.channel2.writable_data_pointer();
+        let input_data_pointer2 = channel_pointer.channel.channel2.read_only_data_pointer();
+        let output_data_pointer2 = channel_pointer.channel.channel1.writable_data_pointer();
         (
             channel_pointer,
             BidirectedDataPointer {
                 input: input_data_pointer1,
                 output: output_data_pointer1,
+                disconnected: disconnected1,
+                peer_disconnected: disconnected2,
+                input_generation: input_generation1,
             },
             BidirectedDataPointer {
                 input: input_data_pointer2,
                 output: output_data_pointer2,
+                disconnected: disconnected2,
+                peer_disconnected: disconnected1,
+                input_generation: input_generation2,
+            },
+        )
+    }
+
+    /// In this constructor, both `Data` fields of each directed channel are initialised by calling the respective function twice.
+    ///
+    /// See [`BidirectedChannel::create`] for more details, and [BidirectedChannelBuilder] for naming the directions explicitly.
+    pub fn create_with(
+        mut f1: impl FnMut() -> Data1,
+        mut f2: impl FnMut() -> Data2,
+    ) -> (
+        BidirectedChannelPointer<Data1, Data2>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
+    ) {
+        Self::create(f1(), f1(), f2(), f2())
+    }
+
+    /// Create a bidirected channel like [BidirectedChannel::create],
+    /// whose forward and backward directions are flushed with the given strategies, e.g. [`CloneFlush`](crate::directed::CloneFlush),
+    /// [`SwapFlush`](crate::directed::SwapFlush) or [`TakeFlush`](crate::directed::TakeFlush).
+    /// Only the strategy of a direction determines the bounds required on its `Data`,
+    /// e.g. the forward direction can be cloned while the backward direction is taken without requiring `Clone`.
+    #[allow(clippy::type_complexity)]
+    pub fn create_with_strategies<Forward, Backward>(
+        forward: Forward,
+        backward: Backward,
+        read_only1: Data1,
+        writable1: Data1,
+        read_only2: Data2,
+        writable2: Data2,
+    ) -> (
+        StrategyBidirectedChannelPointer<Data1, Data2, Forward, Backward>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
+    ) {
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            Self::create(read_only1, writable1, read_only2, writable2);
+        (
+            StrategyBidirectedChannelPointer {
+                channel_pointer,
+                forward,
+                backward,
             },
+            data_pointer1,
+            data_pointer2,
         )
     }
 
     /// Destroys the bidirected channel linked with the given pointers (see [`BidirectedChannel::create`]).
+    /// The data pointers can also be given as the pairs of input and output pointers returned by [BidirectedDataPointer::split].
+    /// This frees the channel, and is hence not [real-time safe](crate::rt).
     ///
     /// **Panics** if not all pointers point to the same channel.
     pub fn destroy(
         channel_pointer: BidirectedChannelPointer<Data1, Data2>,
-        data_pointer1: BidirectedDataPointer<Data1, Data2>,
-        data_pointer2: BidirectedDataPointer<Data2, Data1>,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        Self::destroy_with_observers(
+            channel_pointer,
+            data_pointer1,
+            data_pointer2,
+            iter::empty(),
+            iter::empty(),
+        )
+    }
+
+    /// Destroys the bidirected channel linked with the given pointers like [`BidirectedChannel::destroy`],
+    /// and additionally returns the [ChannelReport] of the channel, e.g. to log its usage at shutdown.
+    /// The flushes, skipped flushes and generations of both directions are summed up in the report.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy_with_report(
+        channel_pointer: BidirectedChannelPointer<Data1, Data2>,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+    ) -> (Data1, Data1, Data2, Data2, ChannelReport) {
+        let (forward, backward) = (
+            channel_pointer.channel.channel1.stats(),
+            channel_pointer.channel.channel2.stats(),
+        );
+        let report = ChannelReport::new(
+            &channel_pointer.label,
+            forward.flushes + backward.flushes,
+            forward.skipped + backward.skipped,
+            channel_pointer.created_at,
+            forward.generation + backward.generation,
+        );
+        let (read_only1, writable1, read_only2, writable2) =
+            Self::destroy(channel_pointer, data_pointer1, data_pointer2);
+        (read_only1, writable1, read_only2, writable2, report)
+    }
+
+    /// Destroys the bidirected channel linked with the given pointers (see [`BidirectedChannel::create`]).
+    /// Compared to [`BidirectedChannel::destroy`], this function additionally accepts copies of the input pointers of the first and the second data pointer,
+    /// as obtained via [BidirectedDataPointer::split], like [`DirectedChannel::destroy`] does.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy_with_observers(
+        channel_pointer: BidirectedChannelPointer<Data1, Data2>,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+        observers1: impl IntoIterator<Item = ReadOnlyDataPointer<Data1>>,
+        observers2: impl IntoIterator<Item = ReadOnlyDataPointer<Data2>>,
     ) -> (Data1, Data1, Data2, Data2) {
-        let BidirectedChannelPointer { mut channel } = channel_pointer;
+        let BidirectedChannel {
+            channel1, channel2, ..
+        } = Self::into_channel(
+            channel_pointer,
+            data_pointer1,
+            data_pointer2,
+            observers1,
+            observers2,
+        )
+        .into_inner();
+        (
+            channel1.read_only,
+            channel1.writable,
+            channel2.read_only,
+            channel2.writable,
+        )
+    }
+
+    /// Unwrap the channel from the given channel pointer.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    fn into_channel(
+        channel_pointer: BidirectedChannelPointer<Data1, Data2>,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+        observers1: impl IntoIterator<Item = ReadOnlyDataPointer<Data1>>,
+        observers2: impl IntoIterator<Item = ReadOnlyDataPointer<Data2>>,
+    ) -> ChannelBox<Self> {
+        let BidirectedChannelPointer {
+            mut channel, label, ..
+        } = channel_pointer;
         let BidirectedDataPointer {
-            input: ReadOnlyDataPointer { data: read_only1 },
-            output: WritableDataPointer { data: writable1 },
-        } = data_pointer1;
+            input: ReadOnlyDataPointer {
+                data: read_only1, ..
+            },
+            output: WritableDataPointer {
+                data: writable1, ..
+            },
+            ..
+        } = data_pointer1.into();
         let BidirectedDataPointer {
-            input: ReadOnlyDataPointer { data: read_only2 },
-            output: WritableDataPointer { data: writable2 },
-        } = data_pointer2;
+            input: ReadOnlyDataPointer {
+                data: read_only2, ..
+            },
+            output: WritableDataPointer {
+                data: writable2, ..
+            },
+            ..
+        } = data_pointer2.into();
 
         let channel1_read_only = &channel.channel1.read_only as *const Data1;
         let channel2_writable = &mut channel.channel1.writable as *mut Data1;
         let channel2_read_only = &channel.channel2.read_only as *const Data2;
         let channel1_writable = &mut channel.channel2.writable as *mut Data2;
 
-        assert_eq!(channel1_read_only, read_only1);
-        assert_eq!(channel1_writable, writable1);
-        assert_eq!(channel2_read_only, read_only2);
-        assert_eq!(channel2_writable, writable2);
+        assert!(
+            channel1_read_only == read_only1
+                && channel1_writable == writable1
+                && channel2_read_only == read_only2
+                && channel2_writable == writable2,
+            "the data pointers do not point to the {}",
+            label
+        );
+        for ReadOnlyDataPointer { data, .. } in observers1 {
+            assert_eq!(
+                channel1_read_only, data,
+                "an observer does not point to the {}",
+                label
+            );
+        }
+        for ReadOnlyDataPointer { data, .. } in observers2 {
+            assert_eq!(
+                channel2_read_only, data,
+                "an observer does not point to the {}",
+                label
+            );
+        }
 
-        (
-            channel.channel1.read_only,
-            channel.channel1.writable,
-            channel.channel2.read_only,
-            channel.channel2.writable,
-        )
+        channel
+    }
+
+    /// The offsets of the read-only and writable `Data` fields of the first and the second directed channel,
+    /// in this order, relative to the start of the bidirected channel.
+    fn offsets() -> [usize; 4] {
+        let channel = MaybeUninit::<Self>::uninit();
+        let base = channel.as_ptr();
+        unsafe {
+            [
+                ptr::addr_of!((*base).channel1.read_only) as usize - base as usize,
+                ptr::addr_of!((*base).channel1.writable) as usize - base as usize,
+                ptr::addr_of!((*base).channel2.read_only) as usize - base as usize,
+                ptr::addr_of!((*base).channel2.writable) as usize - base as usize,
+            ]
+        }
+    }
+
+    /// The disconnection flags of the first and the second data pointer of the bidirected channel starting at the given address.
+    fn disconnected_flags(base: usize) -> [*const AtomicBool; 2] {
+        let channel = MaybeUninit::<Self>::uninit();
+        let offset = unsafe { ptr::addr_of!((*channel.as_ptr()).disconnected) } as usize
+            - channel.as_ptr() as usize;
+        let disconnected = (base + offset) as *const AtomicBool;
+        [disconnected, disconnected.wrapping_add(1)]
+    }
+}
+
+impl<Data1: Default, Data2: Default> BidirectedChannel<Data1, Data2> {
+    /// In this constructor, all `Data` fields are initialised to their default values.
+    ///
+    /// See [`BidirectedChannel::create`] for more details.
+    pub fn create_default() -> (
+        BidirectedChannelPointer<Data1, Data2>,
+        BidirectedDataPointer<Data1, Data2>,
+        BidirectedDataPointer<Data2, Data1>,
+    ) {
+        Self::create_with(Default::default, Default::default)
     }
 }
 
@@ -145,100 +597,1984 @@ impl<Data1: Clone, Data2: Clone> BidirectedChannel<Data1, Data2> {
     }
 }
 
+impl<Data: Clone> BidirectedChannel<Data, Data> {
+    /// Create a bidirected channel that carries the same type in both directions, and hand out three pointers to it.
+    /// Both `Data` fields of the direction from the first endpoint `a` to the second endpoint `b` are initialised from `a_to_b`,
+    /// and both `Data` fields of the direction from `b` to `a` are initialised from `b_to_a`.
+    ///
+    /// See [`BidirectedChannel::create`] for more details.
+    pub fn create_symmetric(
+        a_to_b: Data,
+        b_to_a: Data,
+    ) -> (
+        BidirectedChannelPointer<Data, Data>,
+        SymmetricEndpoint<Data>,
+        SymmetricEndpoint<Data>,
+    ) {
+        // The first endpoint reads the first directed channel and writes the second.
+        Self::create(b_to_a.clone(), b_to_a, a_to_b.clone(), a_to_b)
+    }
+
+    /// Create a bidirected channel that carries the same type in both directions,
+    /// where all four `Data` fields are initialised equally from the given `Data`.
+    ///
+    /// See [`BidirectedChannel::create_symmetric`] for more details.
+    pub fn create_symmetric_equal(
+        data: Data,
+    ) -> (
+        BidirectedChannelPointer<Data, Data>,
+        SymmetricEndpoint<Data>,
+        SymmetricEndpoint<Data>,
+    ) {
+        Self::create_symmetric(data.clone(), data)
+    }
+}
+
 impl<Data1: Clone, Data2: Clone> BidirectedChannelPointer<Data1, Data2> {
     /// Clone the writable `Data`s into the read-only `Data`s.
-    pub fn flush(&mut self, key: &ChannelKey) {
-        DirectedChannel::flush(&mut self.channel.channel1, key);
-        self.channel.channel2.flush(key);
+    /// Each direction is only cloned if its writable `Data` was accessed mutably since the last flush (see [BidirectedChannelPointer::is_dirty]).
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        self.flush_forward(channel_key);
+        self.flush_backward(channel_key);
     }
-}
 
-impl<Data1, Data2> BidirectedChannelPointer<Data1, Data2> {
-    /// Shorthand for [BidirectedChannel::destroy].
-    pub fn destroy(
-        self,
-        data_pointer1: BidirectedDataPointer<Data1, Data2>,
-        data_pointer2: BidirectedDataPointer<Data2, Data1>,
-    ) -> (Data1, Data1, Data2, Data2) {
-        BidirectedChannel::destroy(self, data_pointer1, data_pointer2)
+    /// Like [BidirectedChannelPointer::flush], but only for the forward direction,
+    /// i.e. the directed channel of `Data1`, which is written by the second data pointer and read by the first data pointer.
+    pub fn flush_forward(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        if self.live_directions()[0] {
+            let channel = &mut self.channel.channel1;
+            instrument::flush(
+                &self.label,
+                Some("forward"),
+                mem::size_of::<Data1>(),
+                || (channel.flush_if_dirty(), channel.generation),
+            );
+        }
     }
-}
 
-impl<Input, Output> BidirectedDataPointer<Input, Output> {
-    /// Get a reference to the input data field pointed to by this pointer.
-    pub fn get_input(&self, data_key: &DataKey) -> &Input {
-        self.input.get(data_key)
+    /// Like [BidirectedChannelPointer::flush], but only for the backward direction,
+    /// i.e. the directed channel of `Data2`, which is written by the first data pointer and read by the second data pointer.
+    pub fn flush_backward(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        if self.live_directions()[1] {
+            let channel = &mut self.channel.channel2;
+            instrument::flush(
+                &self.label,
+                Some("backward"),
+                mem::size_of::<Data2>(),
+                || (channel.flush_if_dirty(), channel.generation),
+            );
+        }
     }
 
-    /// Get a mutable reference to the output data field pointed to by this pointer.
-    pub fn get_output(&mut self, data_key: &DataKey) -> &mut Output {
-        self.output.get_mut(data_key)
+    /// Clone all four `Data` fields of the channel, e.g. for debugging.
+    pub fn snapshot(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+    ) -> BidirectedSnapshot<Data1, Data2> {
+        let DirectedSnapshot {
+            published: forward_published,
+            pending: forward_pending,
+        } = self.channel.channel1.snapshot();
+        let DirectedSnapshot {
+            published: backward_published,
+            pending: backward_pending,
+        } = self.channel.channel2.snapshot();
+        BidirectedSnapshot {
+            forward_published,
+            forward_pending,
+            backward_published,
+            backward_pending,
+        }
     }
 }
 
-unsafe impl<Data1, Data2> Send for BidirectedChannelPointer<Data1, Data2> {}
-unsafe impl<Input, Output> Send for BidirectedDataPointer<Input, Output> {}
+impl<Data1: Clone, Data2: Clone> BidirectedChannelPointer<Data1, Data2> {
+    /// Reset all four `Data` fields of the channel, such that both `Data` fields of the forward direction equal `forward`,
+    /// and both `Data` fields of the backward direction equal `backward`.
+    /// The channel is then indistinguishable from a newly created one, i.e. both directions are dirty again.
+    /// The data pointers stay valid.
+    pub fn reset_with(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        forward: Data1,
+        backward: Data2,
+    ) {
+        self.channel.channel1.reset(forward.clone(), forward);
+        self.channel.channel2.reset(backward.clone(), backward);
+    }
+}
 
-unsafe impl<Data1, Data2> Sync for BidirectedChannelPointer<Data1, Data2> {}
-unsafe impl<Input, Output> Sync for BidirectedDataPointer<Input, Output> {}
+#[cfg(feature = "serde")]
+impl<Data1: serde::Serialize, Data2: serde::Serialize> BidirectedChannelPointer<Data1, Data2> {
+    /// Serialise all four `Data` fields of the channel in the format of a [BidirectedSnapshot], without cloning them.
+    pub fn serialize_contents<S: serde::Serializer>(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let forward = &self.channel.channel1;
+        let backward = &self.channel.channel2;
+        serde::Serialize::serialize(
+            &BidirectedSnapshotRef {
+                forward_published: &forward.read_only,
+                forward_pending: &forward.writable,
+                backward_published: &backward.read_only,
+                backward_pending: &backward.writable,
+            },
+            serializer,
+        )
+    }
+}
 
-/// Object-safe trait for [`BidirectedChannelPointer`]s.
-pub trait IBidirectedChannel: Send + Sync {
-    /// Perform the [`BidirectedChannelPointer::flush`] operation.
-    fn flush(&mut self, channel_key: &ChannelKey);
+#[cfg(feature = "serde")]
+impl<Data1, Data2> BidirectedChannelPointer<Data1, Data2> {
+    /// Deserialise all four `Data` fields of the channel from the format of a [BidirectedSnapshot], replacing the current ones.
+    /// The generations of both directions advance and both directions are dirty afterwards.
+    /// If deserialisation fails, the channel is unchanged.
+    pub fn deserialize_contents<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        Data1: serde::Deserialize<'de>,
+        Data2: serde::Deserialize<'de>,
+    {
+        let BidirectedSnapshot {
+            forward_published,
+            forward_pending,
+            backward_published,
+            backward_pending,
+        } = serde::Deserialize::deserialize(deserializer)?;
+        self.channel.channel1.restore(DirectedSnapshot {
+            published: forward_published,
+            pending: forward_pending,
+        });
+        self.channel.channel2.restore(DirectedSnapshot {
+            published: backward_published,
+            pending: backward_pending,
+        });
+        Ok(())
+    }
 }
 
-impl<Data1: Clone, Data2: Clone> IBidirectedChannel for BidirectedChannelPointer<Data1, Data2> {
-    fn flush(&mut self, channel_key: &ChannelKey) {
-        BidirectedChannelPointer::flush(self, channel_key);
+impl<Data1: Default, Data2: Default> BidirectedChannelPointer<Data1, Data2> {
+    /// Reset all four `Data` fields of the channel to their default values.
+    /// The channel is then indistinguishable from a newly created one, i.e. both directions are dirty again.
+    /// The data pointers stay valid.
+    pub fn reset(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.channel
+            .channel1
+            .reset(Default::default(), Default::default());
+        self.channel
+            .channel2
+            .reset(Default::default(), Default::default());
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        bidirected::{BidirectedChannel, IBidirectedChannel},
-        MasterKey,
-    };
+impl<Data1, Data2> BidirectedChannelPointer<Data1, Data2> {
+    /// Convert this channel pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this channel pointer, and must be converted back with [BidirectedChannelPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
 
-    #[test]
-    fn test() {
-        let mut master_key = unsafe { MasterKey::create_unlimited() };
-        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
-            BidirectedChannel::create(0, 0, 10, 10);
+    /// Reconstitute a channel pointer from a raw pointer returned by [BidirectedChannelPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [BidirectedChannelPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
 
-        for i in 0..3 {
-            let data_key = master_key.get_data_key();
-            assert_eq!(*data_pointer1.get_input(&data_key), i);
-            assert_eq!(*data_pointer2.get_input(&data_key), 10 - i);
-            *data_pointer2.get_output(&data_key) = i + 1;
-            *data_pointer1.get_output(&data_key) = 10 - (i + 1);
+    /// Attach a label to the channel, which is used in the panic messages of destroying the channel and returned by [BidirectedChannelPointer::label].
+    pub fn with_label(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.label.set(label);
+        self
+    }
 
-            let channel_key = data_key.into_channel_key();
-            channel_pointer.flush(&channel_key);
+    /// The label of the channel, if it was set via [BidirectedChannelPointer::with_label].
+    pub fn label(&self) -> Option<&str> {
+        self.label.get()
+    }
+
+    /// Consult the given fault plan at each flush of either direction, see the [fault](crate::fault) module.
+    /// The faults are recorded with the current label of the channel.
+    #[cfg(feature = "fault-injection")]
+    pub fn set_fault_plan(&mut self, plan: Arc<crate::fault::FaultPlan>) {
+        let hook = crate::fault::FaultHook::new(plan, self.label.get());
+        self.channel.channel1.fault = Some(hook.clone());
+        self.channel.channel2.fault = Some(hook);
+    }
+
+    /// Swap the writable `Data`s with the read-only `Data`s instead of cloning them, which requires no `Clone` bounds.
+    /// Each direction is only swapped if its writable `Data` was accessed mutably since the last flush (see [BidirectedChannelPointer::is_dirty]).
+    ///
+    /// Unlike with [BidirectedChannelPointer::flush], each writer afterwards sees what was previously published in its direction,
+    /// and not what it wrote itself.
+    pub fn flush_swap(&mut self, channel_key: &ChannelKey) {
+        self.flush_forward_swap(channel_key);
+        self.flush_backward_swap(channel_key);
+    }
+
+    /// Like [BidirectedChannelPointer::flush_swap], but only for the forward direction,
+    /// i.e. the directed channel of `Data1`, which is written by the second data pointer and read by the first data pointer.
+    pub fn flush_forward_swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        if self.live_directions()[0] {
+            let channel = &mut self.channel.channel1;
+            instrument::flush(
+                &self.label,
+                Some("forward"),
+                mem::size_of::<Data1>(),
+                || (channel.flush_swap_if_dirty(), channel.generation),
+            );
         }
+    }
 
-        let (read_only_data1, writable_data1, read_only_data2, writable_data2) =
-            BidirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    /// Like [BidirectedChannelPointer::flush_swap], but only for the backward direction,
+    /// i.e. the directed channel of `Data2`, which is written by the first data pointer and read by the second data pointer.
+    pub fn flush_backward_swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        if self.live_directions()[1] {
+            let channel = &mut self.channel.channel2;
+            instrument::flush(
+                &self.label,
+                Some("backward"),
+                mem::size_of::<Data2>(),
+                || (channel.flush_swap_if_dirty(), channel.generation),
+            );
+        }
+    }
 
-        assert_eq!(read_only_data1, 3);
-        assert_eq!(writable_data1, 3);
-        assert_eq!(read_only_data2, 7);
-        assert_eq!(writable_data2, 7);
+    /// Flush both directions with custom merge functions instead of cloning, which requires no `Clone` bounds.
+    /// Each function is called with the writable `Data` and the read-only `Data` of its direction, in this order,
+    /// and only if the writable `Data` was accessed mutably since the last flush (see [BidirectedChannelPointer::is_dirty]).
+    pub fn flush_with(
+        &mut self,
+        channel_key: &ChannelKey,
+        forward: impl FnOnce(&Data1, &mut Data1),
+        backward: impl FnOnce(&Data2, &mut Data2),
+    ) {
+        self.flush_forward_with(channel_key, forward);
+        self.flush_backward_with(channel_key, backward);
     }
 
-    #[test]
-    fn ensure_channel_is_object_safe() {
-        let mut master_key = unsafe { MasterKey::create_unlimited() };
-        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
-            BidirectedChannel::create(1, 2, 3, 4);
-        let dyn_channel_pointer: &mut dyn IBidirectedChannel = &mut channel_pointer;
+    /// Like [BidirectedChannelPointer::flush_with], but only for the forward direction,
+    /// i.e. the directed channel of `Data1`, which is written by the second data pointer and read by the first data pointer.
+    pub fn flush_forward_with(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        forward: impl FnOnce(&Data1, &mut Data1),
+    ) {
+        if self.live_directions()[0] {
+            let channel = &mut self.channel.channel1;
+            instrument::flush(
+                &self.label,
+                Some("forward"),
+                mem::size_of::<Data1>(),
+                || {
+                    let flushed = channel
+                        .flush_by_if_dirty(|read_only, writable| forward(writable, read_only));
+                    (flushed, channel.generation)
+                },
+            );
+        }
+    }
 
-        dyn_channel_pointer.flush(&master_key.get_channel_key());
-        assert_eq!(*data_pointer1.get_input(&master_key.get_data_key()), 2);
-        assert_eq!(*data_pointer1.get_output(&master_key.get_data_key()), 4);
-        assert_eq!(*data_pointer2.get_input(&master_key.get_data_key()), 4);
+    /// Like [BidirectedChannelPointer::flush_with], but only for the backward direction,
+    /// i.e. the directed channel of `Data2`, which is written by the first data pointer and read by the second data pointer.
+    pub fn flush_backward_with(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        backward: impl FnOnce(&Data2, &mut Data2),
+    ) {
+        if self.live_directions()[1] {
+            let channel = &mut self.channel.channel2;
+            instrument::flush(
+                &self.label,
+                Some("backward"),
+                mem::size_of::<Data2>(),
+                || {
+                    let flushed = channel
+                        .flush_by_if_dirty(|read_only, writable| backward(writable, read_only));
+                    (flushed, channel.generation)
+                },
+            );
+        }
+    }
+
+    /// If set to `true`, flushing skips the direction written by a disconnected data pointer (see [BidirectedDataPointer::disconnect]).
+    /// By default, all directions are flushed.
+    pub fn set_skip_disconnected(&mut self, skip_disconnected: bool) {
+        self.skip_disconnected = skip_disconnected;
+    }
+
+    /// Returns for the first and the second data pointer, in this order, `true` if it was disconnected (see [BidirectedDataPointer::disconnect]).
+    pub fn disconnected(&self, #[allow(unused)] channel_key: &ChannelKey) -> (bool, bool) {
+        let [disconnected1, disconnected2] = &self.channel.disconnected;
+        (
+            disconnected1.load(Ordering::Relaxed),
+            disconnected2.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Returns `true` if the given data pointer is the first data pointer of this channel, i.e. the one reading `Data1`.
+    pub(crate) fn is_first_data_pointer(
+        &self,
+        data_pointer: &BidirectedDataPointer<Data1, Data2>,
+    ) -> bool {
+        ptr::eq(data_pointer.input.data, &self.channel.channel1.read_only)
+    }
+
+    /// The read-only `Data` fields of the forward and the backward direction, in this order.
+    pub(crate) fn published_mut(&mut self) -> (&mut Data1, &mut Data2) {
+        let channel = &mut *self.channel;
+        (
+            &mut channel.channel1.read_only,
+            &mut channel.channel2.read_only,
+        )
+    }
+
+    /// Whether the forward and the backward direction, in this order, should be flushed.
+    /// The forward direction is written by the second data pointer, and the backward direction by the first.
+    fn live_directions(&self) -> [bool; 2] {
+        let [disconnected1, disconnected2] = &self.channel.disconnected;
+        [
+            !(self.skip_disconnected && disconnected2.load(Ordering::Relaxed)),
+            !(self.skip_disconnected && disconnected1.load(Ordering::Relaxed)),
+        ]
+    }
+
+    /// Call the given function with the `Data` fields of the channel without cloning them,
+    /// in the order of the fields of [BidirectedSnapshot].
+    pub fn inspect<R>(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        f: impl FnOnce(&Data1, &Data1, &Data2, &Data2) -> R,
+    ) -> R {
+        let channel: &BidirectedChannel<Data1, Data2> = &self.channel;
+        f(
+            &channel.channel1.read_only,
+            &channel.channel1.writable,
+            &channel.channel2.read_only,
+            &channel.channel2.writable,
+        )
+    }
+
+    /// Returns for the directed channels of `Data1` and `Data2`, in this order,
+    /// `true` if its writable `Data` was accessed mutably since the last flush, i.e. if the next flush will clone it.
+    pub fn is_dirty(&self, #[allow(unused)] channel_key: &ChannelKey) -> (bool, bool) {
+        (self.channel.channel1.dirty, self.channel.channel2.dirty)
+    }
+
+    /// Returns the generations of the directed channels of `Data1` and `Data2`, in this order,
+    /// i.e. the number of flushes that changed their read-only `Data`.
+    pub fn generations(&self, #[allow(unused)] channel_key: &ChannelKey) -> (u64, u64) {
+        (
+            self.channel.channel1.generation,
+            self.channel.channel2.generation,
+        )
+    }
+
+    /// Statistics about the flushes performed on both directions of this channel.
+    /// Flushes of a direction skipped due to a disconnected data pointer (see [BidirectedChannelPointer::set_skip_disconnected]) are not counted.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> BidirectedStats {
+        BidirectedStats {
+            forward: self.channel.channel1.stats(),
+            backward: self.channel.channel2.stats(),
+        }
+    }
+
+    /// Shorthand for [BidirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        BidirectedChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+
+    /// Shorthand for [BidirectedChannel::destroy_with_report].
+    pub fn destroy_with_report(
+        self,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+    ) -> (Data1, Data1, Data2, Data2, ChannelReport) {
+        BidirectedChannel::destroy_with_report(self, data_pointer1, data_pointer2)
+    }
+
+    /// Shorthand for [BidirectedChannel::destroy_with_observers].
+    pub fn destroy_with_observers(
+        self,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+        observers1: impl IntoIterator<Item = ReadOnlyDataPointer<Data1>>,
+        observers2: impl IntoIterator<Item = ReadOnlyDataPointer<Data2>>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        BidirectedChannel::destroy_with_observers(
+            self,
+            data_pointer1,
+            data_pointer2,
+            observers1,
+            observers2,
+        )
+    }
+
+    /// Like [BidirectedChannelPointer::destroy], but the first data pointer was disconnected (see [BidirectedDataPointer::disconnect]).
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy_disconnected1(
+        self,
+        disconnected1: DisconnectedEndpoint<Data1, Data2>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        BidirectedChannel::destroy(self, disconnected1.data_pointer, data_pointer2)
+    }
+
+    /// Like [BidirectedChannelPointer::destroy], but the second data pointer was disconnected (see [BidirectedDataPointer::disconnect]).
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy_disconnected2(
+        self,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        disconnected2: DisconnectedEndpoint<Data2, Data1>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        BidirectedChannel::destroy(self, data_pointer1, disconnected2.data_pointer)
+    }
+}
+
+impl<Data1, Data2, Forward: FlushStrategy<Data1>, Backward: FlushStrategy<Data2>>
+    StrategyBidirectedChannelPointer<Data1, Data2, Forward, Backward>
+{
+    /// Flush each direction with its strategy (see [BidirectedChannel::create_with_strategies]).
+    /// Each direction is only flushed if its writable `Data` was accessed mutably since the last flush (see [BidirectedChannelPointer::is_dirty]).
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        self.flush_forward(channel_key);
+        self.flush_backward(channel_key);
+    }
+
+    /// Like [StrategyBidirectedChannelPointer::flush], but only for the forward direction.
+    pub fn flush_forward(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        if self.channel_pointer.live_directions()[0] {
+            let channel = &mut self.channel_pointer.channel.channel1;
+            let strategy = &self.forward;
+            instrument::flush(
+                &self.channel_pointer.label,
+                Some("forward"),
+                mem::size_of::<Data1>(),
+                || (channel.flush_with_if_dirty(strategy), channel.generation),
+            );
+        }
+    }
+
+    /// Like [StrategyBidirectedChannelPointer::flush], but only for the backward direction.
+    pub fn flush_backward(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        if self.channel_pointer.live_directions()[1] {
+            let channel = &mut self.channel_pointer.channel.channel2;
+            let strategy = &self.backward;
+            instrument::flush(
+                &self.channel_pointer.label,
+                Some("backward"),
+                mem::size_of::<Data2>(),
+                || (channel.flush_with_if_dirty(strategy), channel.generation),
+            );
+        }
+    }
+}
+
+impl<Data1, Data2, Forward, Backward>
+    StrategyBidirectedChannelPointer<Data1, Data2, Forward, Backward>
+{
+    /// The underlying channel pointer, e.g. for querying [BidirectedChannelPointer::stats].
+    pub fn channel_pointer(&self) -> &BidirectedChannelPointer<Data1, Data2> {
+        &self.channel_pointer
+    }
+
+    /// Destroys the bidirected channel linked with the given pointers (see [`BidirectedChannel::destroy`]).
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy(
+        self,
+        data_pointer1: impl Into<BidirectedDataPointer<Data1, Data2>>,
+        data_pointer2: impl Into<BidirectedDataPointer<Data2, Data1>>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        self.channel_pointer.destroy(data_pointer1, data_pointer2)
+    }
+}
+
+impl<Input, Output> BidirectedDataPointer<Input, Output> {
+    /// Convert this data pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this data pointer, and must be converted back with [BidirectedDataPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
+
+    /// Reconstitute a data pointer from a raw pointer returned by [BidirectedDataPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [BidirectedDataPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
+
+    /// Split this pointer into its input and output pointers, such that they can be owned separately.
+    /// The input pointer can be copied, e.g. for observers.
+    /// Use [BidirectedDataPointer::join] to join them again, or pass them to [BidirectedChannel::destroy] directly.
+    pub fn split(self) -> (ReadOnlyDataPointer<Input>, WritableDataPointer<Output>) {
+        (self.input, self.output)
+    }
+
+    /// Get a copy of the input pointer of this pointer, e.g. for an observer thread that reads the same input as this endpoint.
+    /// The observer can only read the input, and must be given to [BidirectedChannel::destroy_with_observers] when destroying the channel.
+    pub fn observer(&self) -> ReadOnlyDataPointer<Input> {
+        self.input
+    }
+
+    /// Disconnect this endpoint from the channel, e.g. when its thread is shutting down.
+    /// The other endpoint can observe this via [BidirectedDataPointer::peer_disconnected],
+    /// and the channel pointer can be configured to stop flushing the direction written by this endpoint (see [BidirectedChannelPointer::set_skip_disconnected]).
+    ///
+    /// Returns a [DisconnectedEndpoint], which is required to destroy the channel in place of this pointer.
+    pub fn disconnect(
+        self,
+        #[allow(unused)] data_key: &DataKey,
+    ) -> DisconnectedEndpoint<Input, Output> {
+        unsafe { &*self.disconnected }.store(true, Ordering::Relaxed);
+        DisconnectedEndpoint { data_pointer: self }
+    }
+
+    /// Returns `true` if the other endpoint of the channel was disconnected (see [BidirectedDataPointer::disconnect]).
+    /// In this case, the input of this endpoint is not updated anymore.
+    pub fn peer_disconnected(&self, #[allow(unused)] data_key: &DataKey) -> bool {
+        unsafe { &*self.peer_disconnected }.load(Ordering::Relaxed)
+    }
+
+    /// Join the given input and output pointers, as returned by [BidirectedDataPointer::split].
+    ///
+    /// **Panics** if the pointers do not belong to the same endpoint of the same bidirected channel.
+    pub fn join(input: ReadOnlyDataPointer<Input>, output: WritableDataPointer<Output>) -> Self {
+        let distance = (output.data as usize).wrapping_sub(input.data as usize);
+        let [read_only1, _, _, writable2] = BidirectedChannel::<Input, Output>::offsets();
+        let [_, writable1, read_only2, _] = BidirectedChannel::<Output, Input>::offsets();
+        let [disconnected, peer_disconnected] = if distance == writable2.wrapping_sub(read_only1) {
+            BidirectedChannel::<Input, Output>::disconnected_flags(
+                (input.data as usize).wrapping_sub(read_only1),
+            )
+        } else if distance == writable1.wrapping_sub(read_only2) {
+            let [peer_disconnected, disconnected] =
+                BidirectedChannel::<Output, Input>::disconnected_flags(
+                    (input.data as usize).wrapping_sub(read_only2),
+                );
+            [disconnected, peer_disconnected]
+        } else {
+            panic!(
+                "the input and output pointers do not belong to the same bidirected channel endpoint"
+            );
+        };
+
+        Self {
+            input_generation: DirectedChannel::generation_of_read_only(input.data),
+            input,
+            output,
+            disconnected,
+            peer_disconnected,
+        }
+    }
+
+    /// The generation of the input of this pointer, i.e. the number of flushes that changed the input.
+    pub fn input_generation(&self, #[allow(unused)] data_key: &DataKey) -> u64 {
+        self.input_generation.get()
+    }
+
+    /// Returns `true` if the input of this pointer was changed by a flush since the generation `last`,
+    /// and updates `last` to the current generation (see [BidirectedDataPointer::input_generation]).
+    /// This allows to skip recomputations if the input did not change.
+    pub fn input_changed_since(&self, #[allow(unused)] data_key: &DataKey, last: &mut u64) -> bool {
+        self.input_generation.changed_since(last)
+    }
+
+    /// Returns `true` if the output of this pointer was delivered by a flush since it was last accessed mutably,
+    /// i.e. if overwriting it does not lose an undelivered output.
+    /// A newly created channel has not delivered its outputs yet.
+    pub fn output_delivered(&self, #[allow(unused)] data_key: &DataKey) -> bool {
+        unsafe { !*self.output.dirty }
+    }
+
+    common::endpoint_accessors!();
+
+    /// Project the input pointer of this pointer to a field of the input data field.
+    /// The projected pointer keeps pointing to the same data field of the channel, so after a flush, it sees the field of the newly published `Input`.
+    ///
+    /// Returns the projected pointer and a [Projection] that restores this pointer from the projected pointer via [`Projection::restore`].
+    /// Input and output can be projected one after the other, in which case the projections are restored in reverse order.
+    ///
+    /// ```
+    /// use std::ptr;
+    /// use two_phase_channel::{bidirected::BidirectedChannel, MasterKey};
+    ///
+    /// #[derive(Clone)]
+    /// struct CameraInput { exposure: u32 }
+    /// #[derive(Clone)]
+    /// struct AudioOutput { volume: u32 }
+    /// #[derive(Clone)]
+    /// struct SensorData { camera: CameraInput, temperature: i32 }
+    /// #[derive(Clone)]
+    /// struct Actuation { audio: AudioOutput, motor: u32 }
+    ///
+    /// // A worker that only sees the parts of the structs that it needs.
+    /// fn worker(camera: &CameraInput, audio: &mut AudioOutput) {
+    ///     audio.volume = camera.exposure / 2;
+    /// }
+    ///
+    /// let mut master_key = unsafe { MasterKey::create_unlimited() };
+    /// let sensor_data = || SensorData { camera: CameraInput { exposure: 8 }, temperature: 20 };
+    /// let actuation = || Actuation { audio: AudioOutput { volume: 0 }, motor: 0 };
+    /// let (mut channel_pointer, data_pointer1, data_pointer2) =
+    ///     BidirectedChannel::create(sensor_data(), sensor_data(), actuation(), actuation());
+    ///
+    /// let (data_pointer1, input_projection) =
+    ///     unsafe { data_pointer1.project_input(|input| ptr::addr_of!((*input).camera)) };
+    /// let (mut data_pointer1, output_projection) =
+    ///     unsafe { data_pointer1.project_output(|output| ptr::addr_of_mut!((*output).audio)) };
+    ///
+    /// data_pointer1.process(&master_key.get_data_key(), worker);
+    /// channel_pointer.flush(&master_key.get_channel_key());
+    ///
+    /// let data_pointer1 = input_projection.restore(output_projection.restore(data_pointer1));
+    /// let (_, _, actuation, _) = channel_pointer.destroy(data_pointer1, data_pointer2);
+    /// assert_eq!(actuation.audio.volume, 4);
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// `projection` must return a pointer to a field stored inline in the given `Input`,
+    /// for example via [`core::ptr::addr_of`].
+    /// It must not dereference any pointers stored in `Input` (such as a `Box` or a `Vec`),
+    /// since these are replaced when flushing and the projected pointer would then dangle.
+    pub unsafe fn project_input<Field>(
+        self,
+        projection: impl FnOnce(*const Input) -> *const Field,
+    ) -> (
+        BidirectedDataPointer<Field, Output>,
+        Projection<BidirectedDataPointer<Input, Output>>,
+    ) {
+        (
+            BidirectedDataPointer {
+                input: ReadOnlyDataPointer {
+                    data: projection(self.input.data),
+                    progress: self.input.progress,
+                },
+                output: WritableDataPointer {
+                    data: self.output.data,
+                    dirty: self.output.dirty,
+                    progress: self.output.progress,
+                },
+                disconnected: self.disconnected,
+                peer_disconnected: self.peer_disconnected,
+                input_generation: self.input_generation,
+            },
+            Projection { original: self },
+        )
+    }
+
+    /// Project the output pointer of this pointer to a field of the output data field.
+    /// Writing through the projected pointer marks the direction as dirty, just like writing through the original pointer.
+    ///
+    /// Returns the projected pointer and a [Projection] that restores this pointer from the projected pointer via [`Projection::restore`].
+    /// See [BidirectedDataPointer::project_input] for an example.
+    ///
+    /// # Safety
+    ///
+    /// `projection` must return a pointer to a field stored inline in the given `Output`,
+    /// for example via [`core::ptr::addr_of_mut`].
+    /// It must not dereference any pointers stored in `Output` (such as a `Box` or a `Vec`),
+    /// since these may be swapped by a flush and the projected pointer would then alias the other `Output`.
+    pub unsafe fn project_output<Field>(
+        self,
+        projection: impl FnOnce(*mut Output) -> *mut Field,
+    ) -> (
+        BidirectedDataPointer<Input, Field>,
+        Projection<BidirectedDataPointer<Input, Output>>,
+    ) {
+        (
+            BidirectedDataPointer {
+                input: ReadOnlyDataPointer {
+                    data: self.input.data,
+                    progress: self.input.progress,
+                },
+                output: WritableDataPointer {
+                    data: projection(self.output.data),
+                    dirty: self.output.dirty,
+                    progress: self.output.progress,
+                },
+                disconnected: self.disconnected,
+                peer_disconnected: self.peer_disconnected,
+                input_generation: self.input_generation,
+            },
+            Projection { original: self },
+        )
+    }
+}
+
+impl<Input: 'static, Output: 'static> BidirectedDataPointer<Input, Output> {
+    /// Erase the type of this data pointer, such that the channel can be destroyed via [ErasedDestroy].
+    pub fn erase(self) -> ErasedDataPointer {
+        ErasedDataPointer::new(self)
+    }
+}
+
+impl<Input, Output> Projection<BidirectedDataPointer<Input, Output>> {
+    /// Restore the original pointer from the given pointer projected from it
+    /// (see [BidirectedDataPointer::project_input] and [BidirectedDataPointer::project_output]).
+    ///
+    /// **Panics** if the projected pointer does not point into the data fields of the original pointer.
+    pub fn restore<InputField, OutputField>(
+        self,
+        projected: BidirectedDataPointer<InputField, OutputField>,
+    ) -> BidirectedDataPointer<Input, Output> {
+        fn contains<Data, Field>(original: *const Data, projected: *const Field) -> bool {
+            let original = original as usize;
+            let projected = projected as usize;
+            original <= projected
+                && projected + mem::size_of::<Field>() <= original + mem::size_of::<Data>()
+        }
+        assert!(
+            contains(self.original.input.data, projected.input.data)
+                && contains(self.original.output.data, projected.output.data)
+                && ptr::eq(self.original.output.dirty, projected.output.dirty)
+        );
+
+        self.original
+    }
+}
+
+impl<Input, Output> From<(ReadOnlyDataPointer<Input>, WritableDataPointer<Output>)>
+    for BidirectedDataPointer<Input, Output>
+{
+    /// See [BidirectedDataPointer::join].
+    fn from((input, output): (ReadOnlyDataPointer<Input>, WritableDataPointer<Output>)) -> Self {
+        Self::join(input, output)
+    }
+}
+
+/// Run two endpoints of a bidirected channel on their own threads, and the given coordinator on the current thread.
+/// The channel is created from `channel_init`, which holds the arguments of [BidirectedChannel::create], in this order.
+///
+/// Each of the given number of phases consists of a data phase, in which both endpoints are called once in parallel,
+/// followed by a channel phase, in which the coordinator is called once.
+/// The master key is borrowed for the whole run, such that no other key can exist in the meantime,
+/// and the threads are synchronised with a barrier at each phase change.
+/// At the end, the channel is destroyed and its `Data` fields are returned like by [BidirectedChannel::destroy].
+///
+/// The endpoints must be `'static`, since scoped threads are not available with the minimum supported Rust version of this crate.
+/// With the `scoped-run` feature, `run_scoped` runs endpoints that borrow from the caller.
+///
+/// **Panics** if an endpoint or the coordinator panics.
+/// In this case, the remaining phases are skipped, the channel is destroyed, and the first panic is resumed on the current thread.
+#[allow(clippy::type_complexity)]
+pub fn run<Data1: Send + 'static, Data2: Send + 'static>(
+    master_key: &mut MasterKey,
+    channel_init: (Data1, Data1, Data2, Data2),
+    phases: usize,
+    endpoint1: impl FnMut(&DataKey, &mut BidirectedDataPointer<Data1, Data2>) + Send + 'static,
+    endpoint2: impl FnMut(&DataKey, &mut BidirectedDataPointer<Data2, Data1>) + Send + 'static,
+    coordinator: impl FnMut(&ChannelKey, &mut BidirectedChannelPointer<Data1, Data2>),
+) -> (Data1, Data1, Data2, Data2) {
+    let (read_only1, writable1, read_only2, writable2) = channel_init;
+    let (channel_pointer, data_pointer1, data_pointer2) =
+        BidirectedChannel::create(read_only1, writable1, read_only2, writable2);
+    let state = Arc::new(RunState::default());
+    let endpoint1 = {
+        let state = state.clone();
+        thread::spawn(move || state.run_endpoint(data_pointer1, endpoint1, phases))
+    };
+    let endpoint2 = {
+        let state = state.clone();
+        thread::spawn(move || state.run_endpoint(data_pointer2, endpoint2, phases))
+    };
+    state.run_coordinator(master_key, channel_pointer, phases, coordinator, || {
+        (
+            endpoint1.join().expect("endpoint thread panicked"),
+            endpoint2.join().expect("endpoint thread panicked"),
+        )
+    })
+}
+
+/// Like [run], but the endpoints run on scoped threads, such that they may borrow from the caller.
+///
+/// **Panics** if an endpoint or the coordinator panics, like [run].
+#[cfg(feature = "scoped-run")]
+#[clippy::msrv = "1.63"]
+#[allow(clippy::type_complexity)]
+pub fn run_scoped<Data1: Send, Data2: Send>(
+    master_key: &mut MasterKey,
+    channel_init: (Data1, Data1, Data2, Data2),
+    phases: usize,
+    endpoint1: impl FnMut(&DataKey, &mut BidirectedDataPointer<Data1, Data2>) + Send,
+    endpoint2: impl FnMut(&DataKey, &mut BidirectedDataPointer<Data2, Data1>) + Send,
+    coordinator: impl FnMut(&ChannelKey, &mut BidirectedChannelPointer<Data1, Data2>),
+) -> (Data1, Data1, Data2, Data2) {
+    let (read_only1, writable1, read_only2, writable2) = channel_init;
+    let (channel_pointer, data_pointer1, data_pointer2) =
+        BidirectedChannel::create(read_only1, writable1, read_only2, writable2);
+    let state = RunState::default();
+    thread::scope(|scope| {
+        let endpoint1 = scope.spawn(|| state.run_endpoint(data_pointer1, endpoint1, phases));
+        let endpoint2 = scope.spawn(|| state.run_endpoint(data_pointer2, endpoint2, phases));
+        state.run_coordinator(master_key, channel_pointer, phases, coordinator, || {
+            (
+                endpoint1.join().expect("endpoint thread panicked"),
+                endpoint2.join().expect("endpoint thread panicked"),
+            )
+        })
+    })
+}
+
+/// The synchronisation of the threads of [run] and [run_scoped].
+#[derive(Debug)]
+struct RunState {
+    /// Each phase starts and ends with a wait on the barrier, such that the endpoints only run while the coordinator holds a data key.
+    barrier: Barrier,
+    /// Set if an endpoint or the coordinator panicked, respectively.
+    /// Endpoints only panic in the data phase and the coordinator only in the channel phase,
+    /// hence all participants see the same value when reading the flag right after the end of that phase, and stop at the same point.
+    endpoint_panicked: AtomicBool,
+    coordinator_panicked: AtomicBool,
+}
+
+/// The data pointer returned by an endpoint thread, and the panic of the endpoint, if any.
+type EndpointResult<Input, Output> = (
+    BidirectedDataPointer<Input, Output>,
+    Option<Box<dyn Any + Send>>,
+);
+
+impl Default for RunState {
+    fn default() -> Self {
+        Self {
+            barrier: Barrier::new(3),
+            endpoint_panicked: AtomicBool::new(false),
+            coordinator_panicked: AtomicBool::new(false),
+        }
+    }
+}
+
+impl RunState {
+    /// The loop of the thread of an endpoint.
+    fn run_endpoint<Input, Output>(
+        &self,
+        mut data_pointer: BidirectedDataPointer<Input, Output>,
+        mut endpoint: impl FnMut(&DataKey, &mut BidirectedDataPointer<Input, Output>),
+        phases: usize,
+    ) -> EndpointResult<Input, Output> {
+        let mut endpoint_panic = None;
+        for _ in 0..phases {
+            self.barrier.wait();
+            if self.coordinator_panicked.load(Ordering::Relaxed) {
+                break;
+            }
+
+            // The coordinator holds a data key until the next barrier wait, hence no channel key exists in the meantime.
+            let data_key = DataKey::new(KeyPhase::default());
+            if let Err(payload) =
+                panic::catch_unwind(AssertUnwindSafe(|| endpoint(&data_key, &mut data_pointer)))
+            {
+                endpoint_panic = Some(payload);
+                self.endpoint_panicked.store(true, Ordering::Relaxed);
+            }
+
+            self.barrier.wait();
+            if self.endpoint_panicked.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        (data_pointer, endpoint_panic)
+    }
+
+    /// The loop of the coordinator, which afterwards joins the endpoint threads via `join`,
+    /// destroys the channel, and resumes the first panic, if any.
+    fn run_coordinator<Data1, Data2>(
+        &self,
+        master_key: &mut MasterKey,
+        mut channel_pointer: BidirectedChannelPointer<Data1, Data2>,
+        phases: usize,
+        mut coordinator: impl FnMut(&ChannelKey, &mut BidirectedChannelPointer<Data1, Data2>),
+        join: impl FnOnce() -> (EndpointResult<Data1, Data2>, EndpointResult<Data2, Data1>),
+    ) -> (Data1, Data1, Data2, Data2) {
+        let mut coordinator_panic = None;
+        for phase in 0..phases {
+            let data_key = master_key.get_data_key();
+            self.barrier.wait();
+            self.barrier.wait();
+            if self.endpoint_panicked.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let channel_key = data_key.into_channel_key();
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| {
+                coordinator(&channel_key, &mut channel_pointer)
+            })) {
+                coordinator_panic = Some(payload);
+                self.coordinator_panicked.store(true, Ordering::Relaxed);
+                // Release the endpoints waiting for the next phase, which then see the abort.
+                if phase + 1 < phases {
+                    let _data_key = master_key.get_data_key();
+                    self.barrier.wait();
+                }
+                break;
+            }
+        }
+
+        let ((data_pointer1, endpoint1_panic), (data_pointer2, endpoint2_panic)) = join();
+        let result = channel_pointer.destroy(data_pointer1, data_pointer2);
+        if let Some(payload) = coordinator_panic.or(endpoint1_panic).or(endpoint2_panic) {
+            panic::resume_unwind(payload);
+        }
+        result
+    }
+}
+
+unsafe impl<Data1, Data2> Send for BidirectedChannelPointer<Data1, Data2> {}
+unsafe impl<Input, Output> Send for BidirectedDataPointer<Input, Output> {}
+unsafe impl<Input, Output> Send for DisconnectedEndpoint<Input, Output> {}
+
+unsafe impl<Data1, Data2> Sync for BidirectedChannelPointer<Data1, Data2> {}
+unsafe impl<Input, Output> Sync for BidirectedDataPointer<Input, Output> {}
+unsafe impl<Input, Output> Sync for DisconnectedEndpoint<Input, Output> {}
+
+/// Object-safe trait for [`BidirectedChannelPointer`]s.
+pub trait IBidirectedChannel: SwapChannel {
+    /// Perform the [`BidirectedChannelPointer::flush`] operation.
+    fn flush(&mut self, channel_key: &ChannelKey);
+
+    /// Perform the [`BidirectedChannelPointer::flush_forward`] operation.
+    /// Defaults to flushing both directions.
+    fn flush_forward(&mut self, channel_key: &ChannelKey) {
+        self.flush(channel_key);
+    }
+
+    /// Perform the [`BidirectedChannelPointer::flush_backward`] operation.
+    /// Defaults to flushing both directions.
+    fn flush_backward(&mut self, channel_key: &ChannelKey) {
+        self.flush(channel_key);
+    }
+
+    /// Perform the [`BidirectedChannelPointer::is_dirty`] operation.
+    fn is_dirty(&self, channel_key: &ChannelKey) -> (bool, bool);
+
+    /// Perform the [`BidirectedChannelPointer::generations`] operation.
+    fn generations(&self, channel_key: &ChannelKey) -> (u64, u64);
+
+    /// Perform the [`BidirectedChannelPointer::stats`] operation.
+    fn stats(&self, channel_key: &ChannelKey) -> BidirectedStats;
+}
+
+impl<Data1: Clone, Data2: Clone> SwapChannel for BidirectedChannelPointer<Data1, Data2> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        BidirectedChannelPointer::flush(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        BidirectedChannelPointer::label(self)
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        let (generation1, generation2) = self.generations(channel_key);
+        Some(generation1.wrapping_add(generation2))
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<(Data1, Data2)>())
+    }
+
+    fn dirty(&self, channel_key: &ChannelKey) -> Option<bool> {
+        let (dirty1, dirty2) = BidirectedChannelPointer::is_dirty(self, channel_key);
+        Some(dirty1 || dirty2)
+    }
+}
+
+impl<Data1: Clone + Send + 'static, Data2: Clone + Send + 'static> ErasedDestroy
+    for BidirectedChannelPointer<Data1, Data2>
+{
+    /// Expects the first and the second [BidirectedDataPointer] in this order,
+    /// and returns all four `Data` fields like [BidirectedChannel::destroy].
+    fn destroy_erased(
+        mut self: Box<Self>,
+        pointers: Vec<ErasedDataPointer>,
+    ) -> Result<Vec<Box<dyn Any + Send>>, DestroyError> {
+        if pointers.len() != 2 {
+            let error = DestroyError::WrongPointerCount {
+                expected: 2,
+                found: pointers.len(),
+            };
+            return Err(erased::leak(self, error));
+        }
+        let mut pointers = pointers.into_iter();
+        let data_pointer1 = pointers
+            .next()
+            .unwrap()
+            .downcast_at::<BidirectedDataPointer<Data1, Data2>>(0);
+        let data_pointer2 = pointers
+            .next()
+            .unwrap()
+            .downcast_at::<BidirectedDataPointer<Data2, Data1>>(1);
+        let (data_pointer1, data_pointer2) = match (data_pointer1, data_pointer2) {
+            (Ok(data_pointer1), Ok(data_pointer2)) => (data_pointer1, data_pointer2),
+            (Err(error), _) | (_, Err(error)) => return Err(erased::leak(self, error)),
+        };
+
+        let channel: &mut BidirectedChannel<Data1, Data2> = &mut self.channel;
+        let channel1_read_only = &channel.channel1.read_only as *const Data1;
+        let channel2_writable = &mut channel.channel1.writable as *mut Data1;
+        let channel2_read_only = &channel.channel2.read_only as *const Data2;
+        let channel1_writable = &mut channel.channel2.writable as *mut Data2;
+        if data_pointer1.input.data != channel1_read_only
+            || data_pointer1.output.data != channel1_writable
+        {
+            return Err(erased::leak(
+                self,
+                DestroyError::ForeignPointer { index: 0 },
+            ));
+        }
+        if data_pointer2.input.data != channel2_read_only
+            || data_pointer2.output.data != channel2_writable
+        {
+            return Err(erased::leak(
+                self,
+                DestroyError::ForeignPointer { index: 1 },
+            ));
+        }
+
+        let (read_only1, writable1, read_only2, writable2) =
+            self.destroy(data_pointer1, data_pointer2);
+        Ok(vec![
+            Box::new(read_only1),
+            Box::new(writable1),
+            Box::new(read_only2),
+            Box::new(writable2),
+        ])
+    }
+}
+
+impl<Data1: Clone, Data2: Clone> IBidirectedChannel for BidirectedChannelPointer<Data1, Data2> {
+    fn flush(&mut self, channel_key: &ChannelKey) {
+        BidirectedChannelPointer::flush(self, channel_key);
+    }
+
+    fn flush_forward(&mut self, channel_key: &ChannelKey) {
+        BidirectedChannelPointer::flush_forward(self, channel_key);
+    }
+
+    fn flush_backward(&mut self, channel_key: &ChannelKey) {
+        BidirectedChannelPointer::flush_backward(self, channel_key);
+    }
+
+    fn is_dirty(&self, channel_key: &ChannelKey) -> (bool, bool) {
+        BidirectedChannelPointer::is_dirty(self, channel_key)
+    }
+
+    fn generations(&self, channel_key: &ChannelKey) -> (u64, u64) {
+        BidirectedChannelPointer::generations(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> BidirectedStats {
+        BidirectedChannelPointer::stats(self, channel_key)
+    }
+}
+
+impl<Data1, Data2, Forward, Backward> SwapChannel
+    for StrategyBidirectedChannelPointer<Data1, Data2, Forward, Backward>
+where
+    Forward: FlushStrategy<Data1> + Send + Sync,
+    Backward: FlushStrategy<Data2> + Send + Sync,
+{
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        StrategyBidirectedChannelPointer::flush(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.channel_pointer.label()
+    }
+}
+
+impl<Data1, Data2, Forward, Backward> IBidirectedChannel
+    for StrategyBidirectedChannelPointer<Data1, Data2, Forward, Backward>
+where
+    Forward: FlushStrategy<Data1> + Send + Sync,
+    Backward: FlushStrategy<Data2> + Send + Sync,
+{
+    fn flush(&mut self, channel_key: &ChannelKey) {
+        StrategyBidirectedChannelPointer::flush(self, channel_key);
+    }
+
+    fn flush_forward(&mut self, channel_key: &ChannelKey) {
+        StrategyBidirectedChannelPointer::flush_forward(self, channel_key);
+    }
+
+    fn flush_backward(&mut self, channel_key: &ChannelKey) {
+        StrategyBidirectedChannelPointer::flush_backward(self, channel_key);
+    }
+
+    fn is_dirty(&self, channel_key: &ChannelKey) -> (bool, bool) {
+        self.channel_pointer.is_dirty(channel_key)
+    }
+
+    fn generations(&self, channel_key: &ChannelKey) -> (u64, u64) {
+        self.channel_pointer.generations(channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> BidirectedStats {
+        self.channel_pointer.stats(channel_key)
+    }
+}
+
+impl<T: IBidirectedChannel + ?Sized> IBidirectedChannel for Box<T> {
+    fn flush(&mut self, channel_key: &ChannelKey) {
+        T::flush(self, channel_key);
+    }
+
+    fn flush_forward(&mut self, channel_key: &ChannelKey) {
+        T::flush_forward(self, channel_key);
+    }
+
+    fn flush_backward(&mut self, channel_key: &ChannelKey) {
+        T::flush_backward(self, channel_key);
+    }
+
+    fn is_dirty(&self, channel_key: &ChannelKey) -> (bool, bool) {
+        T::is_dirty(self, channel_key)
+    }
+
+    fn generations(&self, channel_key: &ChannelKey) -> (u64, u64) {
+        T::generations(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> BidirectedStats {
+        T::stats(self, channel_key)
+    }
+}
+
+impl<T: IBidirectedChannel + ?Sized> IBidirectedChannel for &mut T {
+    fn flush(&mut self, channel_key: &ChannelKey) {
+        T::flush(self, channel_key);
+    }
+
+    fn flush_forward(&mut self, channel_key: &ChannelKey) {
+        T::flush_forward(self, channel_key);
+    }
+
+    fn flush_backward(&mut self, channel_key: &ChannelKey) {
+        T::flush_backward(self, channel_key);
+    }
+
+    fn is_dirty(&self, channel_key: &ChannelKey) -> (bool, bool) {
+        T::is_dirty(self, channel_key)
+    }
+
+    fn generations(&self, channel_key: &ChannelKey) -> (u64, u64) {
+        T::generations(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> BidirectedStats {
+        T::stats(self, channel_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use crate::{
+        bidirected::{
+            BidirectedChannel, BidirectedChannelBuilder, BidirectedChannelPointer,
+            BidirectedDataPointer, BidirectedSnapshot, BidirectedStats, IBidirectedChannel,
+            SymmetricBidirectedChannel, SymmetricEndpoint,
+        },
+        directed::{CloneFlush, DirectedChannel, FlushStats, TakeFlush},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(0, 0, 10, 10);
+
+        for i in 0..3 {
+            let data_key = master_key.get_data_key();
+            assert_eq!(*data_pointer1.get_input(&data_key), i);
+            assert_eq!(*data_pointer2.get_input(&data_key), 10 - i);
+            *data_pointer2.get_output(&data_key) = i + 1;
+            *data_pointer1.get_output(&data_key) = 10 - (i + 1);
+
+            let channel_key = data_key.into_channel_key();
+            channel_pointer.flush(&channel_key);
+        }
+
+        let (read_only_data1, writable_data1, read_only_data2, writable_data2) =
+            BidirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+
+        assert_eq!(read_only_data1, 3);
+        assert_eq!(writable_data1, 3);
+        assert_eq!(read_only_data2, 7);
+        assert_eq!(writable_data2, 7);
+    }
+
+    #[test]
+    fn ensure_channel_is_object_safe() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(1, 2, 3, 4);
+        let dyn_channel_pointer: &mut dyn IBidirectedChannel = &mut channel_pointer;
+
+        dyn_channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*data_pointer1.get_input(&master_key.get_data_key()), 2);
+        assert_eq!(*data_pointer1.get_output(&master_key.get_data_key()), 4);
+        assert_eq!(*data_pointer2.get_input(&master_key.get_data_key()), 4);
         assert_eq!(*data_pointer2.get_output(&master_key.get_data_key()), 2);
         BidirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
     }
+
+    #[test]
+    fn closure_accessors() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0, 0, vec![1], vec![1]);
+
+        let data_key = master_key.get_data_key();
+        let len = data_pointer1.write_output(&data_key, |output| {
+            output.push(2);
+            output.len()
+        });
+        assert_eq!(len, 2);
+
+        channel_pointer.flush(&data_key.into_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(
+            data_pointer2.read_input(&data_key, |input| input.clone()),
+            [1, 2]
+        );
+
+        BidirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn flush_only_dirty_directions() {
+        #[derive(Debug, Default)]
+        struct CloneCounter(Arc<AtomicUsize>);
+
+        impl Clone for CloneCounter {
+            fn clone(&self) -> Self {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Self(self.0.clone())
+            }
+        }
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let commands = Arc::new(AtomicUsize::new(0));
+        let telemetry = Arc::new(AtomicUsize::new(0));
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) = BidirectedChannel::create(
+            CloneCounter(commands.clone()),
+            CloneCounter(commands.clone()),
+            CloneCounter(telemetry.clone()),
+            CloneCounter(telemetry.clone()),
+        );
+        assert_eq!(
+            channel_pointer.is_dirty(&master_key.get_channel_key()),
+            (true, true)
+        );
+        channel_pointer.flush(&master_key.get_channel_key());
+
+        for phase in 0..4 {
+            let data_key = master_key.get_data_key();
+            data_pointer2.write_output(&data_key, |_| ());
+            if phase == 2 {
+                data_pointer1.write_output(&data_key, |_| ());
+            }
+            channel_pointer.flush(&data_key.into_channel_key());
+        }
+        assert_eq!(commands.load(Ordering::Relaxed), 5);
+        assert_eq!(telemetry.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            channel_pointer.is_dirty(&master_key.get_channel_key()),
+            (false, false)
+        );
+
+        BidirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn split_and_join() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0, 1, 2, 3);
+        let (input1, mut output1) = data_pointer1.split();
+        let observer = input1;
+
+        *output1.get_mut(&master_key.get_data_key()) = 4;
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*observer.get(&master_key.get_data_key()), 1);
+
+        let data_pointer1 = BidirectedDataPointer::join(input1, output1);
+        assert_eq!(
+            channel_pointer.destroy_with_observers(
+                data_pointer1,
+                data_pointer2.split(),
+                [observer],
+                []
+            ),
+            (1, 1, 4, 4)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn join_rejects_mixed_endpoints() {
+        let (_channel_pointer, data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0, 1, 2, 3);
+        let (input1, _) = data_pointer1.split();
+        let (_, output2) = data_pointer2.split();
+        let _ = BidirectedDataPointer::join(input1, output2);
+    }
+
+    #[test]
+    fn process() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(1, 1, 0, 0);
+
+        for _ in 0..3 {
+            let data_key = master_key.get_data_key();
+            data_pointer1.process(&data_key, |input, output| *output = input * 2);
+            let (input, output) = data_pointer2.get_input_output(&data_key);
+            *output = input + 1;
+            channel_pointer.flush(&data_key.into_channel_key());
+        }
+
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (3, 3, 6, 6)
+        );
+    }
+
+    #[test]
+    fn flush_swap() {
+        trait Message: Send {
+            fn value(&self) -> u32;
+        }
+
+        struct Request(u32);
+        struct Response(u32);
+
+        impl Message for Request {
+            fn value(&self) -> u32 {
+                self.0
+            }
+        }
+
+        impl Message for Response {
+            fn value(&self) -> u32 {
+                self.0 + 100
+            }
+        }
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut client, mut server) =
+            BidirectedChannel::<Box<dyn Message>, Box<dyn Message>>::create(
+                Box::new(Response(0)),
+                Box::new(Response(0)),
+                Box::new(Request(0)),
+                Box::new(Request(0)),
+            );
+
+        *client.get_output(&master_key.get_data_key()) = Box::new(Request(1));
+        channel_pointer.flush_backward_swap(&master_key.get_channel_key());
+        assert_eq!(server.get_input(&master_key.get_data_key()).value(), 1);
+        assert_eq!(client.get_output(&master_key.get_data_key()).value(), 0);
+
+        *server.get_output(&master_key.get_data_key()) = Box::new(Response(2));
+        channel_pointer.flush_swap(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(client.get_input(&data_key).value(), 102);
+        assert_eq!(server.get_input(&data_key).value(), 0);
+
+        let (response, _, request, _) = channel_pointer.destroy(client, server);
+        assert_eq!((response.value(), request.value()), (102, 0));
+    }
+
+    #[test]
+    fn builder() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            BidirectedChannelBuilder::forward(|| 1)
+                .backward(String::new)
+                .build();
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointer1.get_input(&data_key), 1);
+        assert_eq!(data_pointer2.get_input(&data_key), "");
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            BidirectedChannel::<u8, Vec<u8>>::create_default();
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (0, 0, Vec::new(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0, 1, 2, 3);
+        channel_pointer.flush(&master_key.get_channel_key());
+        *data_pointer1.get_output(&master_key.get_data_key()) = 4;
+
+        let channel_key = master_key.get_channel_key();
+        let snapshot = channel_pointer.snapshot(&channel_key);
+        assert_eq!(
+            snapshot,
+            BidirectedSnapshot {
+                forward_published: 1,
+                forward_pending: 1,
+                backward_published: 3,
+                backward_pending: 4,
+            }
+        );
+        channel_pointer.inspect(&channel_key, |forward_published, _, _, backward_pending| {
+            assert_eq!((*forward_published, *backward_pending), (1, 4))
+        });
+
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn symmetric() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, a, b) =
+            SymmetricBidirectedChannel::create_symmetric("a to b", "b to a");
+        let mut endpoints: Vec<SymmetricEndpoint<&str>> = vec![a, b];
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*endpoints[0].get_input(&data_key), "b to a");
+        assert_eq!(*endpoints[1].get_input(&data_key), "a to b");
+        for endpoint in &mut endpoints {
+            endpoint.process(&data_key, |input, output| *output = input);
+        }
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let b = endpoints.pop().unwrap();
+        let a = endpoints.pop().unwrap();
+        assert_eq!(
+            channel_pointer.destroy(a, b),
+            ("a to b", "a to b", "b to a", "b to a")
+        );
+    }
+
+    #[test]
+    fn reset() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(1, 2, 3, 4);
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(
+            channel_pointer.is_dirty(&master_key.get_channel_key()),
+            (false, false)
+        );
+
+        channel_pointer.reset(&master_key.get_channel_key());
+        assert_eq!(
+            channel_pointer.is_dirty(&master_key.get_channel_key()),
+            (true, true)
+        );
+        assert_eq!(
+            channel_pointer.snapshot(&master_key.get_channel_key()),
+            BidirectedSnapshot {
+                forward_published: 0,
+                forward_pending: 0,
+                backward_published: 0,
+                backward_pending: 0,
+            }
+        );
+
+        // After resetting, the data pointers still work and each direction is flushed separately.
+        channel_pointer.reset_with(&master_key.get_channel_key(), 5, 6);
+        let data_key = master_key.get_data_key();
+        *data_pointer1.get_output(&data_key) = 7;
+        *data_pointer2.get_output(&data_key) = 8;
+        let channel_key = data_key.into_channel_key();
+        channel_pointer.flush_forward_swap(&channel_key);
+        assert_eq!(channel_pointer.is_dirty(&channel_key), (false, true));
+        channel_pointer.reset_with(&channel_key, 9, 10);
+        assert_eq!(channel_pointer.is_dirty(&channel_key), (true, true));
+
+        let data_key = channel_key.into_data_key();
+        assert_eq!(*data_pointer1.get_input(&data_key), 9);
+        assert_eq!(*data_pointer2.get_input(&data_key), 10);
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (9, 9, 10, 10)
+        );
+    }
+
+    #[test]
+    fn disconnect() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(0, 0, 0, 0);
+        channel_pointer.set_skip_disconnected(true);
+
+        let data_key = master_key.get_data_key();
+        *data_pointer1.get_output(&data_key) = 1;
+        *data_pointer2.get_output(&data_key) = 2;
+        let disconnected1 = data_pointer1.disconnect(&data_key);
+        let (input2, output2) = data_pointer2.split();
+        let data_pointer2 = BidirectedDataPointer::join(input2, output2);
+        assert!(data_pointer2.peer_disconnected(&data_key));
+
+        // The backward direction, written by the disconnected first data pointer, is not flushed anymore.
+        let channel_key = data_key.into_channel_key();
+        assert_eq!(channel_pointer.disconnected(&channel_key), (true, false));
+        channel_pointer.flush(&channel_key);
+        assert_eq!(*data_pointer2.get_input(&master_key.get_data_key()), 0);
+
+        assert_eq!(
+            channel_pointer.destroy_disconnected1(disconnected1, data_pointer2),
+            (2, 2, 0, 1)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn destroy_rejects_foreign_observers() {
+        let (channel_pointer, data_pointer1, data_pointer2) = BidirectedChannel::create(0, 1, 2, 3);
+        let (_other_channel_pointer, other_data_pointer1, _other_data_pointer2) =
+            BidirectedChannel::create(0, 1, 2, 3);
+        let (other_input1, _) = other_data_pointer1.split();
+        channel_pointer.destroy_with_observers(data_pointer1, data_pointer2, [other_input1], []);
+    }
+
+    #[test]
+    fn stats() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0, 0, 0, 0);
+        let dyn_channel_pointer: &mut dyn IBidirectedChannel = &mut channel_pointer;
+
+        dyn_channel_pointer.flush(&master_key.get_channel_key());
+        *data_pointer1.get_output(&master_key.get_data_key()) = 1;
+        dyn_channel_pointer.flush(&master_key.get_channel_key());
+
+        assert_eq!(
+            dyn_channel_pointer.stats(&master_key.get_channel_key()),
+            BidirectedStats {
+                forward: FlushStats {
+                    flushes: 1,
+                    skipped: 1,
+                    generation: 1,
+                },
+                backward: FlushStats {
+                    flushes: 2,
+                    skipped: 0,
+                    generation: 2,
+                },
+            }
+        );
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (0, 0, 1, 1)
+        );
+    }
+
+    #[test]
+    fn observer() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(0, 0, 0, 0);
+        let observer = data_pointer1.observer();
+
+        for phase in 1..=3 {
+            let data_key = master_key.get_data_key();
+            let observer_thread = thread::spawn(move || {
+                // The observer thread synchronises with the main thread via join, which ends the data phase.
+                let mut master_key = unsafe { MasterKey::create_unlimited() };
+                *observer.get(&master_key.get_data_key())
+            });
+            data_pointer1.process(&data_key, |input, output| *output = input + 1);
+            *data_pointer2.get_output(&data_key) = phase * 10;
+            assert_eq!(observer_thread.join().unwrap(), (phase - 1) * 10);
+
+            channel_pointer.flush(&data_key.into_channel_key());
+        }
+
+        assert_eq!(
+            channel_pointer.destroy_with_observers(data_pointer1, data_pointer2, [observer], []),
+            (30, 30, 21, 21)
+        );
+    }
+
+    #[test]
+    fn flush_strategies() {
+        // Not `Clone`, so it can only be flushed by swapping or taking.
+        #[derive(Debug, Default, PartialEq)]
+        struct Telemetry(Vec<u32>);
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut worker, mut coordinator) =
+            BidirectedChannel::create_with_strategies(
+                CloneFlush,
+                TakeFlush,
+                0,
+                0,
+                Telemetry::default(),
+                Telemetry::default(),
+            );
+        let dyn_channel_pointer: &mut dyn IBidirectedChannel = &mut channel_pointer;
+
+        for command in 1..=2 {
+            let data_key = master_key.get_data_key();
+            *coordinator.get_output(&data_key) = command;
+            worker.get_output(&data_key).0.push(command);
+            dyn_channel_pointer.flush(&data_key.into_channel_key());
+        }
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*worker.get_input(&data_key), 2);
+        assert_eq!(coordinator.get_input(&data_key).0, [2]);
+        assert_eq!(
+            channel_pointer.destroy(worker, coordinator),
+            (2, 2, Telemetry(vec![2]), Telemetry::default())
+        );
+    }
+
+    #[test]
+    fn dyn_per_direction() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer1, mut data_pointer11, data_pointer12) =
+            BidirectedChannel::create(0, 0, 0, 0);
+        let (mut channel_pointer2, data_pointer21, data_pointer22) =
+            BidirectedChannel::create_with_strategies(CloneFlush, TakeFlush, 0, 0, 0u8, 0u8);
+        let mut channels: Vec<&mut dyn IBidirectedChannel> =
+            vec![&mut channel_pointer1, &mut channel_pointer2];
+        for channel in &mut channels {
+            channel.flush(&master_key.get_channel_key());
+        }
+
+        // Only flush the directions that were written to.
+        *data_pointer11.get_output(&master_key.get_data_key()) = 1;
+        let channel_key = master_key.get_channel_key();
+        for channel in &mut channels {
+            let (forward, backward) = channel.is_dirty(&channel_key);
+            if forward {
+                channel.flush_forward(&channel_key);
+            }
+            if backward {
+                channel.flush_backward(&channel_key);
+            }
+        }
+        assert_eq!(channels[0].generations(&channel_key), (1, 2));
+        assert_eq!(channels[1].generations(&channel_key), (1, 1));
+        assert_eq!(*data_pointer12.get_input(&master_key.get_data_key()), 1);
+
+        assert_eq!(
+            channel_pointer1.destroy(data_pointer11, data_pointer12),
+            (0, 0, 1, 1)
+        );
+        assert_eq!(
+            channel_pointer2.destroy(data_pointer21, data_pointer22),
+            (0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn exchange() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            BidirectedChannel::create(2, 2, 0, 0);
+        channel_pointer.flush(&master_key.get_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert!(data_pointer1.exchange(&data_key, |input| (input * 3, input % 2 == 0)));
+        assert_eq!(
+            data_pointer1.exchange_replace(&data_key, |input| (input * 4, ())),
+            (6, ())
+        );
+        let channel_key = data_key.into_channel_key();
+        channel_pointer.flush(&channel_key);
+
+        // A failed exchange neither changes the output nor marks it as dirty.
+        let data_key = master_key.get_data_key();
+        assert_eq!(
+            data_pointer1.try_exchange(&data_key, |_| Err::<(i32, ()), _>("failed")),
+            Err("failed")
+        );
+        assert_eq!(
+            channel_pointer.is_dirty(&data_key.into_channel_key()),
+            (false, false)
+        );
+        assert_eq!(
+            data_pointer1.try_exchange(&master_key.get_data_key(), |input| Ok::<_, ()>((
+                input + 1,
+                ()
+            ))),
+            Ok(())
+        );
+
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (2, 2, 8, 3)
+        );
+    }
+
+    #[test]
+    fn compose_and_decompose() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (forward_pointer, forward_reader, mut forward_writer) = DirectedChannel::create(0, 0);
+        let backward = DirectedChannel::create(1, 1);
+        *forward_writer.get_mut(&master_key.get_data_key()) = 2;
+
+        let (mut channel_pointer, data_pointer1, mut data_pointer2) = BidirectedChannel::compose(
+            &master_key.get_channel_key(),
+            (forward_pointer, forward_reader, forward_writer),
+            backward,
+        );
+        // The dirty flag of the forward direction was kept.
+        assert_eq!(
+            channel_pointer.is_dirty(&master_key.get_channel_key()),
+            (true, true)
+        );
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*data_pointer1.get_input(&master_key.get_data_key()), 2);
+        *data_pointer2.get_output(&master_key.get_data_key()) = 3;
+
+        let ((forward_pointer, forward_reader, forward_writer), backward) =
+            BidirectedChannel::decompose(
+                &master_key.get_channel_key(),
+                channel_pointer,
+                data_pointer1,
+                data_pointer2,
+            );
+        assert_eq!(
+            forward_pointer.stats(&master_key.get_channel_key()).flushes,
+            1
+        );
+        assert_eq!(
+            forward_pointer.destroy_single(forward_reader, forward_writer),
+            (2, 3)
+        );
+        let (backward_pointer, backward_reader, backward_writer) = backward;
+        assert_eq!(
+            backward_pointer.destroy_single(backward_reader, backward_writer),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn freshness() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0, 0, 0, 0);
+        let mut last = 0;
+
+        let data_key = master_key.get_data_key();
+        assert!(!data_pointer1.output_delivered(&data_key));
+        assert!(!data_pointer1.input_changed_since(&data_key, &mut last));
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert!(data_pointer1.output_delivered(&data_key));
+        assert!(data_pointer1.input_changed_since(&data_key, &mut last));
+        assert_eq!(last, 1);
+        *data_pointer1.get_output(&data_key) = 1;
+        assert!(!data_pointer1.output_delivered(&data_key));
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        // Only the output of the first data pointer was written, so its input did not change.
+        let (input1, output1) = data_pointer1.split();
+        let data_pointer1 = BidirectedDataPointer::join(input1, output1);
+        let data_key = master_key.get_data_key();
+        assert!(data_pointer1.output_delivered(&data_key));
+        assert!(!data_pointer1.input_changed_since(&data_key, &mut last));
+        assert_eq!(data_pointer2.input_generation(&data_key), 2);
+
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (0, 0, 1, 1)
+        );
+    }
+
+    #[test]
+    fn flush_with() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(vec![0], vec![0], vec![0], vec![0]);
+        channel_pointer.flush_with(&master_key.get_channel_key(), |_, _| {}, |_, _| {});
+
+        let data_key = master_key.get_data_key();
+        data_pointer1.get_output(&data_key).push(1);
+        data_pointer2.get_output(&data_key).push(2);
+        channel_pointer.flush_forward_with(&data_key.into_channel_key(), |writable, read_only| {
+            read_only.extend(writable.iter().map(|x| x * 10))
+        });
+        channel_pointer
+            .flush_backward_with(&master_key.get_channel_key(), |writable, read_only| {
+                *read_only = vec![writable.iter().sum()]
+            });
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.get_input(&data_key), &[0, 0, 20]);
+        assert_eq!(data_pointer2.get_input(&data_key), &[1]);
+        // Clean directions are not merged again.
+        channel_pointer.flush_with(
+            &data_key.into_channel_key(),
+            |_, _| unreachable!(),
+            |_, _| unreachable!(),
+        );
+
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn run() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut flushes = 0;
+        let result = crate::bidirected::run(
+            &mut master_key,
+            (0, 0, 0, 0),
+            3,
+            |data_key, data_pointer| *data_pointer.get_output(data_key) += 1,
+            |data_key, data_pointer| {
+                let input = *data_pointer.get_input(data_key);
+                *data_pointer.get_output(data_key) = input * 10;
+            },
+            |channel_key, channel_pointer| {
+                channel_pointer.flush(channel_key);
+                flushes += 1;
+            },
+        );
+
+        assert_eq!(flushes, 3);
+        // The second endpoint always sees the output of the first endpoint from the previous phase.
+        assert_eq!(result, (20, 20, 3, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "endpoint failed")]
+    fn run_propagates_endpoint_panics() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut phase = 0;
+        crate::bidirected::run(
+            &mut master_key,
+            (0, 0, 0, 0),
+            3,
+            move |_, _| {
+                phase += 1;
+                assert!(phase < 2, "endpoint failed");
+            },
+            |_, _| {},
+            |channel_key, channel_pointer| channel_pointer.flush(channel_key),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "scoped-run")]
+    fn run_scoped() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let inputs = [1, 2, 3];
+        let mut received = Vec::new();
+        let mut phase = 0;
+        let result = crate::bidirected::run_scoped(
+            &mut master_key,
+            (0, 0, 0, 0),
+            inputs.len(),
+            // The endpoints borrow from the caller.
+            |data_key, data_pointer| {
+                *data_pointer.get_output(data_key) = inputs[phase];
+                phase += 1;
+            },
+            |data_key, data_pointer| received.push(*data_pointer.get_input(data_key)),
+            |channel_key, channel_pointer| channel_pointer.flush(channel_key),
+        );
+
+        assert_eq!(result, (0, 0, 3, 3));
+        assert_eq!(received, [0, 1, 2]);
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0u8, 0, 0u16, 0);
+
+        let raw = (
+            channel_pointer.into_raw(),
+            data_pointer1.into_raw(),
+            data_pointer2.into_raw(),
+        );
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) = unsafe {
+            (
+                BidirectedChannelPointer::<u8, u16>::from_raw(raw.0),
+                BidirectedDataPointer::<u8, u16>::from_raw(raw.1),
+                BidirectedDataPointer::<u16, u8>::from_raw(raw.2),
+            )
+        };
+
+        *data_pointer1.get_output(&master_key.get_data_key()) = 1;
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*data_pointer2.get_input(&master_key.get_data_key()), 1);
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (0, 0, 1, 1)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        use bincode::Options;
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) =
+            BidirectedChannel::create(0u8, 1, String::from("a"), String::from("b"));
+
+        let mut json = Vec::new();
+        channel_pointer
+            .serialize_contents(
+                &master_key.get_channel_key(),
+                &mut serde_json::Serializer::new(&mut json),
+            )
+            .unwrap();
+        let snapshot: BidirectedSnapshot<u8, String> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            snapshot,
+            channel_pointer.snapshot(&master_key.get_channel_key())
+        );
+        let bincode = bincode::DefaultOptions::new().serialize(&snapshot).unwrap();
+
+        channel_pointer.reset(&master_key.get_channel_key());
+        channel_pointer
+            .deserialize_contents(
+                &master_key.get_channel_key(),
+                &mut bincode::Deserializer::from_slice(&bincode, bincode::DefaultOptions::new()),
+            )
+            .unwrap();
+        assert_eq!(
+            channel_pointer.snapshot(&master_key.get_channel_key()),
+            snapshot
+        );
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (0, 1, String::from("a"), String::from("b"))
+        );
+    }
 }
@@ -0,0 +1,211 @@
+//! An undirected two-phase channel where each `Data` field lives in its own heap allocation.
+//! Swapping exchanges the allocations instead of the content of the `Data` fields,
+//! which makes it independent of the size of `Data`.
+
+use std::{any, mem};
+
+use crate::{
+    undirected::{SwapStats, UndirectedSwapChannel},
+    ChannelKey, DataKey, SwapChannel,
+};
+
+/// An undirected channel used for communication between threads, where each `Data` field lives in its own heap allocation.
+/// It behaves like an [`UndirectedChannel`](crate::undirected::UndirectedChannel),
+/// but a swap exchanges the allocations the data pointers refer to, instead of copying the `Data` fields.
+/// This makes swapping O(1) regardless of the size of `Data`,
+/// at the cost of one additional pointer indirection on every access through a data pointer.
+///
+/// See [BoxedUndirectedChannel::create] for more info.
+#[derive(Debug)]
+pub struct BoxedUndirectedChannel<Data> {
+    slots: [Box<Data>; 2],
+    generation: u64,
+    stats: SwapStats,
+}
+
+/// A pointer to a boxed undirected channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [BoxedUndirectedChannel::destroy] or [BoxedUndirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BoxedUndirectedChannelPointer<Data> {
+    channel: Box<BoxedUndirectedChannel<Data>>,
+}
+
+/// A pointer to one of the data fields in a boxed undirected channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [BoxedUndirectedChannel::destroy] or [BoxedUndirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BoxedUndirectedDataPointer<Data> {
+    slot: *mut Box<Data>,
+}
+
+impl<Data> BoxedUndirectedChannel<Data> {
+    /// Create a boxed undirected channel and hand out three pointers to it.
+    /// One [BoxedUndirectedChannelPointer] used to swap the two `Data` fields,
+    /// and two [BoxedUndirectedDataPointer]s, one to each data field.
+    pub fn create(
+        data1: Data,
+        data2: Data,
+    ) -> (
+        BoxedUndirectedChannelPointer<Data>,
+        BoxedUndirectedDataPointer<Data>,
+        BoxedUndirectedDataPointer<Data>,
+    ) {
+        let mut channel_pointer = BoxedUndirectedChannelPointer {
+            channel: Box::new(BoxedUndirectedChannel {
+                slots: [Box::new(data1), Box::new(data2)],
+                generation: 0,
+                stats: SwapStats::default(),
+            }),
+        };
+        let [slot1, slot2] = &mut channel_pointer.channel.slots;
+        let data_pointer1 = BoxedUndirectedDataPointer {
+            slot: slot1 as *mut Box<Data>,
+        };
+        let data_pointer2 = BoxedUndirectedDataPointer {
+            slot: slot2 as *mut Box<Data>,
+        };
+        (channel_pointer, data_pointer1, data_pointer2)
+    }
+
+    /// Destroys the boxed undirected channel linked with the three pointers (see [BoxedUndirectedChannel::create]).
+    /// Returns the `Data` fields pointed to by the first and the second data pointer handed out by [BoxedUndirectedChannel::create], in this order,
+    /// independent of how many swaps were performed.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: BoxedUndirectedChannelPointer<Data>,
+        data_pointer1: BoxedUndirectedDataPointer<Data>,
+        data_pointer2: BoxedUndirectedDataPointer<Data>,
+    ) -> (Data, Data) {
+        let BoxedUndirectedChannelPointer { mut channel } = channel_pointer;
+        let [slot1, slot2] = &mut channel.slots;
+        let channel_slot1 = slot1 as *mut Box<Data>;
+        let channel_slot2 = slot2 as *mut Box<Data>;
+        let BoxedUndirectedDataPointer {
+            slot: data_pointer1,
+        } = data_pointer1;
+        let BoxedUndirectedDataPointer {
+            slot: data_pointer2,
+        } = data_pointer2;
+
+        assert!(
+            (channel_slot1 == data_pointer1 && channel_slot2 == data_pointer2)
+                || (channel_slot1 == data_pointer2 && channel_slot2 == data_pointer1)
+        );
+
+        let [data1, data2] = channel.slots;
+        (*data1, *data2)
+    }
+}
+
+impl<Data> BoxedUndirectedChannelPointer<Data> {
+    /// Swap the two `Data` fields in the channel by exchanging their allocations.
+    pub fn swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        let channel: &mut BoxedUndirectedChannel<Data> = &mut self.channel;
+        let [slot1, slot2] = &mut channel.slots;
+        mem::swap(slot1, slot2);
+        channel.generation += 1;
+        channel.stats.swaps += 1;
+        channel.stats.last_swap_generation = channel.generation;
+    }
+
+    /// The number of swaps performed on this channel.
+    pub fn swap_count(&self, #[allow(unused)] channel_key: &ChannelKey) -> u64 {
+        self.channel.stats.swaps
+    }
+
+    /// Statistics about the swaps performed on this channel.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> SwapStats {
+        self.channel.stats
+    }
+
+    /// Shorthand for [BoxedUndirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: BoxedUndirectedDataPointer<Data>,
+        data_pointer2: BoxedUndirectedDataPointer<Data>,
+    ) -> (Data, Data) {
+        BoxedUndirectedChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+}
+
+impl<Data> BoxedUndirectedDataPointer<Data> {
+    /// Get a reference to the `Data` field pointed to by this pointer.
+    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> &Data {
+        unsafe { &*self.slot }
+    }
+
+    /// Get a mutable reference to the `Data` field pointed to by this pointer.
+    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> &mut Data {
+        unsafe { &mut *self.slot }
+    }
+}
+
+unsafe impl<Data> Send for BoxedUndirectedChannelPointer<Data> {}
+unsafe impl<Data> Send for BoxedUndirectedDataPointer<Data> {}
+
+unsafe impl<Data> Sync for BoxedUndirectedChannelPointer<Data> {}
+unsafe impl<Data> Sync for BoxedUndirectedDataPointer<Data> {}
+
+impl<Data> SwapChannel for BoxedUndirectedChannelPointer<Data> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        BoxedUndirectedChannelPointer::swap(self, channel_key);
+    }
+
+    fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel.generation)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<Data>())
+    }
+}
+
+impl<Data> UndirectedSwapChannel for BoxedUndirectedChannelPointer<Data> {
+    fn swap(&mut self, channel_key: &ChannelKey) {
+        BoxedUndirectedChannelPointer::swap(self, channel_key);
+    }
+
+    fn swap_count(&self, channel_key: &ChannelKey) -> u64 {
+        BoxedUndirectedChannelPointer::swap_count(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> SwapStats {
+        BoxedUndirectedChannelPointer::stats(self, channel_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{boxed::BoxedUndirectedChannel, undirected::UndirectedSwapChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            BoxedUndirectedChannel::create(vec![0; 3], vec![1; 3]);
+
+        for swaps in 1..4 {
+            let data_key = master_key.get_data_key();
+            data_pointer1.get_mut(&data_key)[0] = swaps;
+            let dyn_channel_pointer: &mut dyn UndirectedSwapChannel = &mut channel_pointer;
+            dyn_channel_pointer.swap(&data_key.into_channel_key());
+        }
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointer1.get(&data_key), [2, 1, 1]);
+        assert_eq!(*data_pointer2.get(&data_key), [3, 0, 0]);
+        assert_eq!(channel_pointer.swap_count(&data_key.into_channel_key()), 3);
+
+        // The data is returned in the order of the data pointers, even after an odd number of swaps.
+        assert_eq!(
+            BoxedUndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2),
+            (vec![2, 1, 1], vec![3, 0, 0])
+        );
+    }
+}
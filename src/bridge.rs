@@ -0,0 +1,117 @@
+//! A bridge from an ordinary [mpsc](std::sync::mpsc) channel into a [queue channel](crate::queue),
+//! for inputs from threads that do not participate in the phase protocol, such as I/O threads.
+
+use core::iter;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use crate::{queue::QueueChannelPointer, ChannelKey};
+
+/// Moves messages from an mpsc [Receiver] into the inbox of a [queue channel](crate::queue::QueueChannel) during the channel phase.
+///
+/// Each [MpscBridge::pump] moves at most the budget of messages, if set.
+/// Further messages are left in the mpsc channel for the next pump, such that the senders are slowed down by a bounded mpsc channel
+/// ([`sync_channel`](std::sync::mpsc::sync_channel)) instead of the readers receiving an unbounded number of messages per phase.
+#[derive(Debug)]
+pub struct MpscBridge<T> {
+    receiver: Receiver<T>,
+    channel_pointer: QueueChannelPointer<T>,
+    budget: Option<usize>,
+    disconnected: bool,
+}
+
+impl<T> MpscBridge<T> {
+    /// Create a bridge from the given receiver into the queue channel of the given pointer, without a budget.
+    /// Items pushed by the [QueueSender](crate::queue::QueueSender) of the queue channel are still delivered as well.
+    pub fn new(receiver: Receiver<T>, channel_pointer: QueueChannelPointer<T>) -> Self {
+        Self {
+            receiver,
+            channel_pointer,
+            budget: None,
+            disconnected: false,
+        }
+    }
+
+    /// Limit the number of messages moved per [MpscBridge::pump].
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Flush the queue channel, and then move up to the budget of messages from the mpsc channel into its inbox.
+    /// The messages are delivered after the flushed items.
+    /// Returns the number of moved messages.
+    pub fn pump(&mut self, channel_key: &ChannelKey) -> usize {
+        self.pump_at_most(channel_key, self.budget.unwrap_or(usize::MAX))
+    }
+
+    /// Like [MpscBridge::pump], but ignores the budget and moves all messages that are currently in the mpsc channel.
+    pub fn pump_all(&mut self, channel_key: &ChannelKey) -> usize {
+        self.pump_at_most(channel_key, usize::MAX)
+    }
+
+    fn pump_at_most(&mut self, channel_key: &ChannelKey, limit: usize) -> usize {
+        self.channel_pointer.flush(channel_key);
+        let receiver = &self.receiver;
+        let disconnected = &mut self.disconnected;
+        let messages = iter::from_fn(|| match receiver.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                *disconnected = true;
+                None
+            }
+        })
+        .take(limit);
+        self.channel_pointer.deliver(channel_key, messages)
+    }
+
+    /// Returns `true` if a pump found the mpsc channel empty and all of its senders dropped, i.e. no more messages will arrive.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Return the receiver and the queue channel pointer, e.g. to destroy the queue channel.
+    pub fn into_parts(self) -> (Receiver<T>, QueueChannelPointer<T>) {
+        (self.receiver, self.channel_pointer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::{bridge::MpscBridge, queue::QueueChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, mut queue_sender, mut queue_receiver) = QueueChannel::create();
+        let (sender, receiver) = mpsc::channel();
+        let mut bridge = MpscBridge::new(receiver, channel_pointer).with_budget(2);
+        for message in 0..5 {
+            sender.send(message).unwrap();
+        }
+        queue_sender.push(&master_key.get_data_key(), 10);
+
+        assert_eq!(bridge.pump(&master_key.get_channel_key()), 2);
+        assert!(queue_receiver
+            .drain(&master_key.get_data_key())
+            .eq([10, 0, 1]));
+        // The remaining messages stay in the mpsc channel until the next pump.
+        assert_eq!(bridge.pump(&master_key.get_channel_key()), 2);
+        sender.send(5).unwrap();
+        drop(sender);
+        assert!(!bridge.is_disconnected());
+        assert_eq!(bridge.pump_all(&master_key.get_channel_key()), 2);
+        assert!(bridge.is_disconnected());
+        assert!(queue_receiver
+            .drain(&master_key.get_data_key())
+            .eq([2, 3, 4, 5]));
+
+        let (_, channel_pointer) = bridge.into_parts();
+        assert_eq!(
+            channel_pointer.destroy(queue_sender, queue_receiver),
+            (Vec::new(), Vec::new())
+        );
+    }
+}
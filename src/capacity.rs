@@ -0,0 +1,86 @@
+//! Capacity management for growable `Data`, such as vectors or hash maps.
+//! Channel pointers allow to manage the capacity of both of their `Data` fields during the channel phase,
+//! for example via [`UndirectedChannelPointer::shrink_both`](crate::undirected::UndirectedChannelPointer::shrink_both).
+//!
+//! [ManageCapacity] is implemented for the growable collections of the standard library with the `capacity-impls` feature.
+
+#[cfg(feature = "capacity-impls")]
+use std::{
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, Hash},
+};
+
+/// A type whose allocated capacity can be managed.
+pub trait ManageCapacity {
+    /// Shrink the capacity as much as possible.
+    fn shrink_to_fit(&mut self);
+
+    /// Reserve capacity for at least `additional` more elements.
+    fn reserve(&mut self, additional: usize);
+}
+
+#[cfg(feature = "capacity-impls")]
+impl<T> ManageCapacity for Vec<T> {
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+}
+
+#[cfg(feature = "capacity-impls")]
+impl<T> ManageCapacity for VecDeque<T> {
+    fn shrink_to_fit(&mut self) {
+        VecDeque::shrink_to_fit(self);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        VecDeque::reserve(self, additional);
+    }
+}
+
+#[cfg(feature = "capacity-impls")]
+impl<T: Ord> ManageCapacity for BinaryHeap<T> {
+    fn shrink_to_fit(&mut self) {
+        BinaryHeap::shrink_to_fit(self);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        BinaryHeap::reserve(self, additional);
+    }
+}
+
+#[cfg(feature = "capacity-impls")]
+impl ManageCapacity for String {
+    fn shrink_to_fit(&mut self) {
+        String::shrink_to_fit(self);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        String::reserve(self, additional);
+    }
+}
+
+#[cfg(feature = "capacity-impls")]
+impl<K: Eq + Hash, V, S: BuildHasher> ManageCapacity for HashMap<K, V, S> {
+    fn shrink_to_fit(&mut self) {
+        HashMap::shrink_to_fit(self);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        HashMap::reserve(self, additional);
+    }
+}
+
+#[cfg(feature = "capacity-impls")]
+impl<T: Eq + Hash, S: BuildHasher> ManageCapacity for HashSet<T, S> {
+    fn shrink_to_fit(&mut self) {
+        HashSet::shrink_to_fit(self);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        HashSet::reserve(self, additional);
+    }
+}
@@ -0,0 +1,295 @@
+//! A self-coordinating channel between exactly two threads that ping-pong two buffers, without any keys.
+//!
+//! Each of the two [endpoints](EndpointHandle) of a [two-phase cell](TwoPhaseCell) works on its current buffer via [EndpointHandle::with],
+//! and then calls [EndpointHandle::commit].
+//! Once both endpoints committed, the buffers are swapped, i.e. each endpoint continues with the buffer the other endpoint worked on.
+//! The endpoint that commits first parks until the other one commits as well, hence a commit acts like a barrier of two threads.
+//!
+//! # Relation to the key-based channels
+//!
+//! The key-based channels, e.g. the [undirected channel](crate::undirected), guarantee at compile time that no `Data` is accessed while a channel is swapped,
+//! since a channel can only be swapped with a [ChannelKey](crate::ChannelKey), which cannot exist at the same time as a [DataKey](crate::DataKey).
+//! Accessing the `Data` therefore costs nothing at runtime, and swapping many channels costs a single phase change of the coordinator.
+//!
+//! A two-phase cell gives the same guarantee, i.e. that both endpoints never access the same buffer at the same time,
+//! but establishes it at runtime instead: the swap is a counter of commits, which each endpoint increments and waits on.
+//! It hence costs two atomic operations per commit and possibly parking the thread, but requires neither a coordinator nor a master key.
+//! The synchronisation is checked with [loom](https://docs.rs/loom), see `RUSTFLAGS="--cfg loom" cargo test --release --lib cell`.
+//!
+//! | Use | if |
+//! |-----|----|
+//! | [TwoPhaseCell] | exactly two threads exchange a buffer, and neither of them wants to coordinate the other |
+//! | [UndirectedChannel](crate::undirected::UndirectedChannel) | more threads or more channels take part in the phases, or a thread needs the buffer of both sides |
+
+use core::fmt;
+#[cfg(not(loom))]
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread::{self, Thread},
+};
+
+#[cfg(loom)]
+use loom::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, Thread},
+};
+#[cfg(loom)]
+use std::sync::PoisonError;
+
+/// A self-coordinating channel between exactly two threads.
+///
+/// See [TwoPhaseCell::create] for more info.
+#[derive(Debug)]
+pub struct TwoPhaseCell<Data> {
+    phantom: core::marker::PhantomData<Data>,
+}
+
+/// One of the two endpoints of a [TwoPhaseCell].
+///
+/// Dropping an endpoint makes the other endpoint **panic** when it commits, instead of waiting forever.
+#[must_use]
+pub struct EndpointHandle<Data> {
+    shared: Arc<Shared<Data>>,
+    /// Zero for the first endpoint, and one for the second.
+    index: usize,
+    /// The number of swaps this endpoint took part in.
+    phase: usize,
+}
+
+struct Shared<Data> {
+    buffers: [UnsafeCell<Data>; 2],
+    /// The number of commits of both endpoints, such that the buffers were swapped half as many times.
+    commits: AtomicUsize,
+    /// The thread of each endpoint while it waits for the other endpoint to commit, such that the other endpoint can unpark it.
+    /// Locking a slot also synchronises the waiting endpoint with the other endpoint, instead of relying on parking for that.
+    threads: [Mutex<Option<Thread>>; 2],
+    /// `true` if an endpoint was dropped.
+    closed: AtomicBool,
+}
+
+/// [core::cell::UnsafeCell] with the interface of `loom::cell::UnsafeCell`, such that loom can check the accesses to the buffers.
+#[cfg(not(loom))]
+struct UnsafeCell<T>(core::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    fn new(data: T) -> Self {
+        Self(core::cell::UnsafeCell::new(data))
+    }
+
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+// The buffers are only accessed by the endpoint they currently belong to, see [EndpointHandle::with].
+unsafe impl<Data: Send> Sync for Shared<Data> {}
+unsafe impl<Data: Send> Send for Shared<Data> {}
+
+impl<Data> TwoPhaseCell<Data> {
+    /// Create a two-phase cell and hand out its two endpoints.
+    /// The first endpoint starts with the `first` buffer, and the second endpoint with the `second` buffer.
+    pub fn create(first: Data, second: Data) -> (EndpointHandle<Data>, EndpointHandle<Data>) {
+        let shared = Arc::new(Shared {
+            buffers: [UnsafeCell::new(first), UnsafeCell::new(second)],
+            commits: AtomicUsize::new(0),
+            threads: [Mutex::new(None), Mutex::new(None)],
+            closed: AtomicBool::new(false),
+        });
+        (
+            EndpointHandle {
+                shared: Arc::clone(&shared),
+                index: 0,
+                phase: 0,
+            },
+            EndpointHandle {
+                shared,
+                index: 1,
+                phase: 0,
+            },
+        )
+    }
+
+    /// Destroys the two-phase cell of the given endpoints (see [TwoPhaseCell::create]).
+    /// Returns the current buffer of the first endpoint and the current buffer of the second endpoint, in this order.
+    ///
+    /// **Panics** if the endpoints do not belong to the same cell.
+    pub fn destroy(first: EndpointHandle<Data>, second: EndpointHandle<Data>) -> (Data, Data) {
+        assert!(
+            Arc::ptr_eq(&first.shared, &second.shared),
+            "the endpoints do not belong to the same two-phase cell"
+        );
+        let (first, second) = if first.index == 0 {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        // Both endpoints are owned here, hence none of them waits in a commit, and both are in the same phase.
+        let swapped = first.phase % 2 == 1;
+        let shared = first.shared.clone();
+        drop((first, second));
+        let shared = match Arc::try_unwrap(shared) {
+            Ok(shared) => shared,
+            Err(_) => unreachable!("all endpoints were dropped"),
+        };
+        let [buffer0, buffer1] = shared.buffers;
+        let (buffer0, buffer1) = (buffer0.into_inner(), buffer1.into_inner());
+        if swapped {
+            (buffer1, buffer0)
+        } else {
+            (buffer0, buffer1)
+        }
+    }
+}
+
+impl<Data> EndpointHandle<Data> {
+    /// Call the given function with the current buffer of this endpoint.
+    pub fn with<R>(&mut self, f: impl FnOnce(&mut Data) -> R) -> R {
+        // In each phase, the endpoints own different buffers,
+        // and an endpoint only enters the next phase after the other endpoint committed the current one.
+        self.shared.buffers[(self.index + self.phase) % 2].with_mut(|data| f(unsafe { &mut *data }))
+    }
+
+    /// Commit the current buffer of this endpoint, and swap the buffers once the other endpoint committed as well.
+    /// If the other endpoint did not commit yet, this parks the current thread until it does.
+    ///
+    /// **Panics** if the other endpoint was dropped before committing.
+    pub fn commit(&mut self) {
+        let shared = &*self.shared;
+        let swapped = 2 * (self.phase + 1);
+
+        // Release the accesses to the current buffer, and acquire the ones of the other endpoint to the buffer of the next phase.
+        if shared.commits.fetch_add(1, Ordering::AcqRel) + 1 == swapped {
+            unpark(&shared.threads[1 - self.index]);
+        } else {
+            loop {
+                {
+                    // The other endpoint commits before it takes the thread from the slot, hence the commit is visible after locking the slot.
+                    let mut thread = shared.threads[self.index]
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner);
+                    if shared.commits.load(Ordering::Acquire) >= swapped {
+                        // Remove the thread if it was not unparked, such that it is never unparked outside of a commit.
+                        *thread = None;
+                        break;
+                    }
+                    assert!(
+                        !shared.closed.load(Ordering::Acquire),
+                        "the other endpoint of the two-phase cell was dropped"
+                    );
+                    if thread.is_none() {
+                        *thread = Some(thread::current());
+                    }
+                }
+                thread::park();
+            }
+        }
+        self.phase += 1;
+    }
+
+    /// The number of times the buffers were swapped.
+    pub fn phase(&self) -> usize {
+        self.phase
+    }
+}
+
+/// Unpark the thread stored in the given slot, if any.
+fn unpark(thread: &Mutex<Option<Thread>>) {
+    if let Some(thread) = thread.lock().unwrap_or_else(PoisonError::into_inner).take() {
+        thread.unpark();
+    }
+}
+
+impl<Data> Drop for EndpointHandle<Data> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        unpark(&self.shared.threads[1 - self.index]);
+    }
+}
+
+impl<Data> fmt::Debug for EndpointHandle<Data> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EndpointHandle")
+            .field("index", &self.index)
+            .field("phase", &self.phase)
+            .finish()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use crate::cell::TwoPhaseCell;
+
+    #[test]
+    fn test() {
+        let (mut first, mut second) = TwoPhaseCell::create(Vec::new(), Vec::new());
+        let thread = thread::spawn(move || {
+            for i in 0..100 {
+                second.with(|buffer| buffer.push(100 + i));
+                second.commit();
+            }
+            second
+        });
+        for i in 0..100 {
+            first.with(|buffer| buffer.push(i));
+            first.commit();
+        }
+        let second = thread.join().unwrap();
+        assert_eq!((first.phase(), second.phase()), (100, 100));
+
+        // The endpoints alternate between the buffers, such that each buffer holds the values of both endpoints.
+        let (buffer0, buffer1) = TwoPhaseCell::destroy(second, first);
+        assert_eq!(buffer0.len(), 100);
+        assert_eq!(buffer1.len(), 100);
+        assert_eq!(buffer0[..3], [0, 101, 2]);
+        assert_eq!(buffer1[..3], [100, 1, 102]);
+    }
+
+    #[test]
+    #[should_panic(expected = "the other endpoint of the two-phase cell was dropped")]
+    fn dropped_endpoint_panics() {
+        let (mut first, second) = TwoPhaseCell::create((), ());
+        thread::spawn(move || drop(second));
+        first.commit();
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::thread;
+
+    use crate::cell::TwoPhaseCell;
+
+    #[test]
+    fn endpoints_never_share_a_buffer() {
+        loom::model(|| {
+            let (mut first, mut second) = TwoPhaseCell::create(0, 10);
+            let thread = thread::spawn(move || {
+                for _ in 0..2 {
+                    second.with(|buffer| *buffer += 1);
+                    second.commit();
+                }
+                second
+            });
+            for _ in 0..2 {
+                first.with(|buffer| *buffer += 1);
+                first.commit();
+            }
+            let second = thread.join().unwrap();
+            assert_eq!(TwoPhaseCell::destroy(first, second), (2, 12));
+        });
+    }
+}
@@ -0,0 +1,304 @@
+//! Runtime bookkeeping of the `checked-backend` feature, which turns misuse of directed, undirected and bidirected channels into immediate panics.
+//!
+//! Each channel is registered in a global registry, and each of its data pointers refers to it by id.
+//! Each key knows the master key it was derived from and the phase of that master key it belongs to.
+//! Accessing a data pointer of a destroyed channel **panics** before the freed memory is touched,
+//! and advancing a channel **panics** if it was accessed via a data key of another master key that may still exist,
+//! e.g. if master keys created via [MasterKey::create_unlimited](crate::MasterKey::create_unlimited) are used on different threads at the same time.
+//! A channel accessed via a key of a [ThreadLocalMasterKey](crate::local::ThreadLocalMasterKey) is branded with that master key,
+//! and accessing it via a key of any other master key **panics**.
+//!
+//! Without the feature, all types in this module are zero-sized and all checks do nothing.
+
+#[cfg(feature = "checked-backend")]
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+/// The master key a key was derived from, and the phase of that master key the key belongs to.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct KeyPhase {
+    /// The id of the master key, or zero if unknown, as for the keys created by a [PhaseRunner](crate::runner::PhaseRunner).
+    #[cfg(feature = "checked-backend")]
+    master: u64,
+    #[cfg(feature = "checked-backend")]
+    phase: u64,
+}
+
+/// The registration of a master key, which is removed when it is dropped.
+#[derive(Debug)]
+pub(crate) struct CheckedMaster {
+    #[cfg(feature = "checked-backend")]
+    id: u64,
+}
+
+/// The registration of a channel, which is removed when the channel is dropped or destroyed.
+#[derive(Debug)]
+pub(crate) struct CheckedChannel {
+    #[cfg(feature = "checked-backend")]
+    id: u64,
+}
+
+/// The reference of a data pointer to the registration of its channel.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CheckedPointer {
+    #[cfg(feature = "checked-backend")]
+    id: u64,
+}
+
+#[cfg(feature = "checked-backend")]
+#[derive(Debug, Default)]
+struct Registry {
+    /// The current phase of each master key.
+    masters: HashMap<u64, u64>,
+    /// The key phase of the last data access of each channel, if any.
+    channels: HashMap<u64, Option<KeyPhase>>,
+    /// The master keys whose channels are branded, i.e. thread-local master keys.
+    branding: HashSet<u64>,
+    /// The master key each branded channel is branded with.
+    brands: HashMap<u64, u64>,
+}
+
+/// Const `Mutex::new` is why the `checked-backend` feature needs Rust 1.63.
+#[cfg(feature = "checked-backend")]
+#[clippy::msrv = "1.63"]
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+#[cfg(feature = "checked-backend")]
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Call the given function with the registry.
+/// The registry stays usable after a panic in a check, since the checks panic only after releasing it.
+#[cfg(feature = "checked-backend")]
+fn with_registry<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    f(registry.get_or_insert_with(Registry::default))
+}
+
+#[cfg(feature = "checked-backend")]
+fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+impl KeyPhase {
+    /// Start the next phase of the master key of this phase, ending this phase.
+    pub(crate) fn next(self) -> Self {
+        #[cfg(feature = "checked-backend")]
+        if self.master != 0 {
+            return with_registry(|registry| {
+                let phase = registry.masters.entry(self.master).or_insert(0);
+                *phase += 1;
+                Self {
+                    master: self.master,
+                    phase: *phase,
+                }
+            });
+        }
+        self
+    }
+
+    /// Returns `true` if a key of this phase may still exist.
+    #[cfg(feature = "checked-backend")]
+    fn is_current(&self, registry: &Registry) -> bool {
+        self.master != 0 && registry.masters.get(&self.master) == Some(&self.phase)
+    }
+}
+
+impl CheckedMaster {
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(feature = "checked-backend")]
+            id: next_id(),
+        }
+    }
+
+    /// Like [CheckedMaster::new], but brands each channel accessed via a key of this master key.
+    #[cfg(feature = "std")]
+    pub(crate) fn branded() -> Self {
+        let master = Self::new();
+        #[cfg(feature = "checked-backend")]
+        with_registry(|registry| registry.branding.insert(master.id));
+        master
+    }
+
+    /// Start the next phase of this master key.
+    pub(crate) fn next_phase(&self) -> KeyPhase {
+        KeyPhase {
+            #[cfg(feature = "checked-backend")]
+            master: self.id,
+            #[cfg(feature = "checked-backend")]
+            phase: 0,
+        }
+        .next()
+    }
+}
+
+#[cfg(feature = "checked-backend")]
+impl Drop for CheckedMaster {
+    fn drop(&mut self) {
+        with_registry(|registry| {
+            registry.masters.remove(&self.id);
+            registry.branding.remove(&self.id);
+        });
+    }
+}
+
+impl CheckedChannel {
+    pub(crate) fn new() -> Self {
+        #[cfg(feature = "checked-backend")]
+        {
+            let id = next_id();
+            with_registry(|registry| registry.channels.insert(id, None));
+            Self { id }
+        }
+        #[cfg(not(feature = "checked-backend"))]
+        Self {}
+    }
+
+    /// The reference to this registration for a data pointer.
+    pub(crate) fn pointer(&self) -> CheckedPointer {
+        CheckedPointer {
+            #[cfg(feature = "checked-backend")]
+            id: self.id,
+        }
+    }
+
+    /// Check that the channel can be advanced.
+    ///
+    /// **Panics** if the channel was accessed via a data key of another master key that may still exist.
+    #[track_caller]
+    pub(crate) fn advance(&self) {
+        #[cfg(feature = "checked-backend")]
+        {
+            let accessed_concurrently = with_registry(|registry| {
+                registry
+                    .channels
+                    .get(&self.id)
+                    .copied()
+                    .flatten()
+                    .map_or(false, |accessed| accessed.is_current(registry))
+            });
+            assert!(
+                !accessed_concurrently,
+                "a channel was advanced while a data key that accessed it may still exist, \
+                 i.e. keys of different master keys were used at the same time"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "checked-backend")]
+impl Drop for CheckedChannel {
+    fn drop(&mut self) {
+        with_registry(|registry| {
+            registry.channels.remove(&self.id);
+            registry.brands.remove(&self.id);
+        });
+    }
+}
+
+impl CheckedPointer {
+    /// Check that the `Data` of the channel can be accessed via a data key of the given phase, and record the access.
+    ///
+    /// **Panics** if the channel was destroyed, or if it is branded with another master key.
+    #[track_caller]
+    pub(crate) fn access(&self, #[allow(unused)] phase: KeyPhase) {
+        #[cfg(feature = "checked-backend")]
+        {
+            let (exists, branded_by_other) = with_registry(|registry| {
+                let exists = match registry.channels.get_mut(&self.id) {
+                    Some(accessed) => {
+                        *accessed = Some(phase);
+                        true
+                    }
+                    None => return (false, false),
+                };
+                if phase.master == 0 {
+                    return (exists, false);
+                }
+                let brand = match registry.brands.get(&self.id) {
+                    Some(&brand) => Some(brand),
+                    None if registry.branding.contains(&phase.master) => {
+                        registry.brands.insert(self.id, phase.master);
+                        None
+                    }
+                    None => None,
+                };
+                (exists, brand.map_or(false, |brand| brand != phase.master))
+            });
+            assert!(
+                exists,
+                "a data pointer was used after its channel was destroyed"
+            );
+            assert!(
+                !branded_by_other,
+                "a channel branded by a thread-local master key was accessed via a key of another master key"
+            );
+        }
+    }
+}
+
+#[cfg(all(test, feature = "checked-backend"))]
+mod tests {
+    use crate::{
+        directed::DirectedChannel, local::ThreadLocalMasterKey, undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    #[test]
+    fn correct_usage_passes() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create(1, 2);
+        for _ in 0..3 {
+            let data_key = master_key.get_data_key();
+            *data_pointer1.get_mut(&data_key) += 1;
+            channel_pointer.swap(&data_key.into_channel_key());
+        }
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (3, 3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a data pointer was used after its channel was destroyed")]
+    fn use_after_destroy_panics() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+        let copy = read_only;
+        assert_eq!(channel_pointer.destroy_single(read_only, writable), (0, 0));
+        copy.get(&master_key.get_data_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "keys of different master keys were used at the same time")]
+    fn concurrent_master_keys_panic() {
+        let mut master_key1 = unsafe { MasterKey::create_unlimited() };
+        let mut master_key2 = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 0);
+        let data_key = master_key1.get_data_key();
+        data_pointer1.get(&data_key);
+        channel_pointer.swap(&master_key2.get_channel_key());
+        let _ = (data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    #[should_panic(expected = "a channel branded by a thread-local master key was accessed")]
+    fn sharing_across_thread_local_master_keys_panics() {
+        let (_channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+        let mut master_key = unsafe { ThreadLocalMasterKey::create() };
+        read_only.get(&master_key.get_data_key());
+        std::thread::spawn(move || {
+            let mut master_key = unsafe { ThreadLocalMasterKey::create() };
+            writable.get(&master_key.get_data_key());
+            read_only.get(&master_key.get_data_key());
+        })
+        .join()
+        .unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+    }
+}
@@ -0,0 +1,264 @@
+//! Checkpoints of the contents of all channels of a [ChannelGroup](crate::group::ChannelGroup) at a single phase boundary, e.g. for crash recovery.
+//! See [ChannelGroup::checkpoint](crate::group::ChannelGroup::checkpoint) and [ChannelGroup::restore](crate::group::ChannelGroup::restore).
+//!
+//! A checkpoint starts with the magic bytes `TPCCKPT1` and the number of channels,
+//! followed by one record per channel consisting of its label, the hash of its type name and its contents encoded with bincode.
+//! All integers are little-endian `u64`, and the label and the contents are prefixed with their length.
+
+use core::fmt;
+use std::{
+    any::{self, Any},
+    io::{self, Read, Write},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    bidirected::BidirectedChannelPointer, directed::DirectedChannelPointer,
+    undirected::UndirectedChannelPointer, ChannelKey,
+};
+
+const MAGIC: &[u8; 8] = b"TPCCKPT1";
+
+/// A channel pointer whose contents can be written to and restored from a checkpoint.
+/// It is implemented for the channel pointers of undirected, directed and bidirected channels whose `Data` supports serde.
+pub trait Checkpoint {
+    /// Encode all `Data` fields of the channel.
+    fn save_contents(&self, channel_key: &ChannelKey) -> io::Result<Vec<u8>>;
+
+    /// Replace all `Data` fields of the channel with the given encoded ones.
+    /// If decoding fails, the channel must be unchanged.
+    fn load_contents(&mut self, channel_key: &ChannelKey, contents: &[u8]) -> io::Result<()>;
+}
+
+/// The reason why a checkpoint does not match a [ChannelGroup](crate::group::ChannelGroup).
+/// It is returned by [ChannelGroup::restore](crate::group::ChannelGroup::restore) as the inner error of an [io::Error] of kind [io::ErrorKind::InvalidData].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// The data does not start with the magic bytes of a checkpoint.
+    NotACheckpoint,
+    /// The checkpoint holds a different number of channels than the group has checkpointed channels.
+    WrongChannelCount {
+        /// The number of checkpointed channels in the group.
+        expected: usize,
+        /// The number of channels in the checkpoint.
+        found: usize,
+    },
+    /// The label of a channel in the checkpoint differs from the label of the channel at the same position in the group.
+    WrongLabel {
+        /// The position of the channel among the checkpointed channels.
+        index: usize,
+        /// The label of the channel in the group.
+        expected: String,
+        /// The label of the channel in the checkpoint.
+        found: String,
+    },
+    /// The type of a channel in the checkpoint differs from the type of the channel at the same position in the group.
+    WrongType {
+        /// The label of the channel.
+        label: String,
+    },
+}
+
+/// The type-erased checkpoint functions of a channel in a group.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CheckpointFns {
+    pub(crate) type_hash: u64,
+    pub(crate) save: fn(&(dyn Any + Send + Sync), &ChannelKey) -> io::Result<Vec<u8>>,
+    pub(crate) load: fn(&mut (dyn Any + Send + Sync), &ChannelKey, &[u8]) -> io::Result<()>,
+}
+
+/// A channel as written to a checkpoint.
+#[derive(Debug)]
+pub(crate) struct Record {
+    pub(crate) label: String,
+    pub(crate) type_hash: u64,
+    pub(crate) contents: Vec<u8>,
+}
+
+impl CheckpointFns {
+    pub(crate) fn of<T: Checkpoint + 'static>() -> Self {
+        Self {
+            type_hash: type_hash::<T>(),
+            save: |channel, channel_key| {
+                channel
+                    .downcast_ref::<T>()
+                    .unwrap()
+                    .save_contents(channel_key)
+            },
+            load: |channel, channel_key, contents| {
+                channel
+                    .downcast_mut::<T>()
+                    .unwrap()
+                    .load_contents(channel_key, contents)
+            },
+        }
+    }
+}
+
+/// The FNV-1a hash of the type name of `T`.
+/// Type names are not guaranteed to be stable across compiler versions, hence this is only a sanity check.
+fn type_hash<T: ?Sized>() -> u64 {
+    any::type_name::<T>()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+pub(crate) fn write_records(mut writer: impl Write, records: &[Record]) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    write_u64(&mut writer, records.len() as u64)?;
+    for record in records {
+        write_u64(&mut writer, record.label.len() as u64)?;
+        writer.write_all(record.label.as_bytes())?;
+        write_u64(&mut writer, record.type_hash)?;
+        write_u64(&mut writer, record.contents.len() as u64)?;
+        writer.write_all(&record.contents)?;
+    }
+    writer.flush()
+}
+
+pub(crate) fn read_records(mut reader: impl Read) -> io::Result<Vec<Record>> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(CheckpointError::NotACheckpoint.into());
+    }
+    let count = read_u64(&mut reader)?;
+    (0..count)
+        .map(|_| {
+            let label = String::from_utf8(read_bytes(&mut reader)?)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            let type_hash = read_u64(&mut reader)?;
+            let contents = read_bytes(&mut reader)?;
+            Ok(Record {
+                label,
+                type_hash,
+                contents,
+            })
+        })
+        .collect()
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_u64(reader)?;
+    let mut bytes = Vec::new();
+    // Read incrementally, such that a corrupt length does not cause a huge allocation.
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+fn invalid_data(error: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl<Data: Serialize + DeserializeOwned> Checkpoint for UndirectedChannelPointer<Data> {
+    fn save_contents(&self, channel_key: &ChannelKey) -> io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.serialize_contents(
+            channel_key,
+            &mut bincode::Serializer::new(&mut contents, bincode::DefaultOptions::new()),
+        )
+        .map_err(invalid_data)?;
+        Ok(contents)
+    }
+
+    fn load_contents(&mut self, channel_key: &ChannelKey, contents: &[u8]) -> io::Result<()> {
+        self.deserialize_contents(
+            channel_key,
+            &mut bincode::Deserializer::from_slice(contents, bincode::DefaultOptions::new()),
+        )
+        .map_err(invalid_data)
+    }
+}
+
+impl<Data: Serialize + DeserializeOwned> Checkpoint for DirectedChannelPointer<Data> {
+    fn save_contents(&self, channel_key: &ChannelKey) -> io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.serialize_contents(
+            channel_key,
+            &mut bincode::Serializer::new(&mut contents, bincode::DefaultOptions::new()),
+        )
+        .map_err(invalid_data)?;
+        Ok(contents)
+    }
+
+    fn load_contents(&mut self, channel_key: &ChannelKey, contents: &[u8]) -> io::Result<()> {
+        self.deserialize_contents(
+            channel_key,
+            &mut bincode::Deserializer::from_slice(contents, bincode::DefaultOptions::new()),
+        )
+        .map_err(invalid_data)
+    }
+}
+
+impl<Data1: Serialize + DeserializeOwned, Data2: Serialize + DeserializeOwned> Checkpoint
+    for BidirectedChannelPointer<Data1, Data2>
+{
+    fn save_contents(&self, channel_key: &ChannelKey) -> io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.serialize_contents(
+            channel_key,
+            &mut bincode::Serializer::new(&mut contents, bincode::DefaultOptions::new()),
+        )
+        .map_err(invalid_data)?;
+        Ok(contents)
+    }
+
+    fn load_contents(&mut self, channel_key: &ChannelKey, contents: &[u8]) -> io::Result<()> {
+        self.deserialize_contents(
+            channel_key,
+            &mut bincode::Deserializer::from_slice(contents, bincode::DefaultOptions::new()),
+        )
+        .map_err(invalid_data)
+    }
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::NotACheckpoint => write!(f, "the data is not a checkpoint"),
+            CheckpointError::WrongChannelCount { expected, found } => write!(
+                f,
+                "expected {} checkpointed channels, but the checkpoint holds {}",
+                expected, found
+            ),
+            CheckpointError::WrongLabel {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected channel {:?} at position {}, but the checkpoint holds {:?}",
+                expected, index, found
+            ),
+            CheckpointError::WrongType { label } => write!(
+                f,
+                "the channel {:?} in the checkpoint has a different type",
+                label
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<CheckpointError> for io::Error {
+    fn from(error: CheckpointError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
@@ -0,0 +1,100 @@
+//! Diagnostic checksums that detect writes to channels outside of the data phase.
+//! Enabled by the `checksum` feature.
+
+use core::fmt;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::ChannelKey;
+
+/// Hands out the ids of data phases, which are unique across master keys.
+/// Zero is the id of no data phase.
+static NEXT_DATA_PHASE: AtomicU64 = AtomicU64::new(1);
+
+/// The id of the current data phase of a master key, which the keys derived from it refer to.
+/// A channel that is used via keys of different master keys hence never mistakes a data phase of one for a data phase of another.
+#[derive(Debug, Default)]
+pub(crate) struct DataPhases {
+    current: AtomicU64,
+}
+
+impl DataPhases {
+    /// Called whenever a data key is created from the master key, returns `self` for the data key.
+    pub(crate) fn begin(&self) -> &Self {
+        self.current.store(
+            NEXT_DATA_PHASE.fetch_add(1, Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+        self
+    }
+
+    /// The id of the current data phase, which changes with every data key created from the master key.
+    pub(crate) fn current(&self) -> u64 {
+        self.current.load(Ordering::Relaxed)
+    }
+}
+
+fn hash<Data: Hash>(data: &Data) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The hashes of both `Data` fields of a channel, recorded at the end of a channel operation.
+pub(crate) struct Checksum<Data> {
+    hash: fn(&Data) -> u64,
+    hashes: (u64, u64),
+    /// The data phase of the master key of the last channel operation, or `None` if its channel key was not derived from a master key.
+    data_phase: Option<u64>,
+}
+
+impl<Data: Hash> Checksum<Data> {
+    /// Create a checksum that is first verified at the second channel operation,
+    /// since the creation of the channel is not bound to a master key.
+    pub(crate) fn new() -> Self {
+        Self {
+            hash: hash::<Data>,
+            hashes: (0, 0),
+            data_phase: None,
+        }
+    }
+}
+
+impl<Data> Checksum<Data> {
+    /// Record the hashes of both `Data` fields at the end of a channel operation.
+    pub(crate) fn record(&mut self, data1: &Data, data2: &Data, channel_key: &ChannelKey) {
+        self.hashes = ((self.hash)(data1), (self.hash)(data2));
+        self.data_phase = channel_key.data_phase();
+    }
+
+    /// Verify the hashes of both `Data` fields at the start of a channel operation.
+    ///
+    /// **Panics** if a `Data` field changed even though the master key of the given channel key did not create a data key since the last record.
+    pub(crate) fn verify(
+        &self,
+        data1: &Data,
+        data2: &Data,
+        channel: *const (),
+        channel_key: &ChannelKey,
+    ) {
+        if self.data_phase.is_some() && self.data_phase == channel_key.data_phase() {
+            assert!(
+                self.hashes == ((self.hash)(data1), (self.hash)(data2)),
+                "the channel at {:p} was written to while no data key existed",
+                channel
+            );
+        }
+    }
+}
+
+impl<Data> fmt::Debug for Checksum<Data> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checksum")
+            .field("hashes", &self.hashes)
+            .field("data_phase", &self.data_phase)
+            .finish()
+    }
+}
@@ -0,0 +1,227 @@
+//! Items shared by the channels allocated by this crate and the [scoped](crate::scoped) channels,
+//! which are also available without the `std` feature.
+//!
+//! The public items are re-exported by the [scoped](crate::scoped) module, and with the `std` feature also by the
+//! [directed](crate::directed), [undirected](crate::undirected) and [bidirected](crate::bidirected) modules.
+//! Data pointers get their `get`, `read`, `get_mut` and `write` accessors from [read_accessors] and [write_accessors],
+//! and the data pointers of bidirected channels get theirs from [endpoint_accessors].
+
+use core::mem;
+
+/// Implement `get` and `read` in an `impl` block of a data pointer to a single `Data` field,
+/// which has a `data: *const Data` or `data: *mut Data` field and a method `fn access(&self, data_key: &DataKey)`
+/// that checks an access via the given data key.
+macro_rules! read_accessors {
+    () => {
+        /// Get a reference to the `Data` field pointed to by this pointer.
+        /// Prefer [Self::read], which does not allow the reference to outlive the data phase by accident.
+        pub fn get(&self, data_key: &$crate::DataKey) -> &Data {
+            self.access(data_key);
+            unsafe { &*self.data }
+        }
+
+        /// Call the given function with a reference to the `Data` field pointed to by this pointer, and return its result.
+        /// This is the recommended accessor, since the reference cannot outlive the call.
+        pub fn read<R>(&self, data_key: &$crate::DataKey, f: impl FnOnce(&Data) -> R) -> R {
+            f(self.get(data_key))
+        }
+    };
+}
+
+/// Implement `get_mut` and `write` like [read_accessors], for a data pointer with a `data: *mut Data` field
+/// and a method `fn access_mut(&mut self, data_key: &DataKey)`.
+/// The given doc comments are added to `get_mut`.
+macro_rules! write_accessors {
+    ($(#[doc = $doc:expr])*) => {
+        /// Get a mutable reference to the `Data` field pointed to by this pointer.
+        $(#[doc = $doc])*
+        /// Prefer [Self::write], which does not allow the reference to outlive the data phase by accident.
+        pub fn get_mut(&mut self, data_key: &$crate::DataKey) -> &mut Data {
+            self.access_mut(data_key);
+            unsafe { &mut *self.data }
+        }
+
+        /// Call the given function with a mutable reference to the `Data` field pointed to by this pointer, and return its result.
+        /// This is the recommended accessor, since the reference cannot outlive the call.
+        pub fn write<R>(&mut self, data_key: &$crate::DataKey, f: impl FnOnce(&mut Data) -> R) -> R {
+            f(self.get_mut(data_key))
+        }
+    };
+}
+
+/// Implement the accessors of a data pointer to a bidirected channel in its `impl<Input, Output>` block,
+/// which has an `input` field with [read_accessors] and an `output` field with [write_accessors].
+macro_rules! endpoint_accessors {
+    () => {
+        /// Get a reference to the input data field pointed to by this pointer.
+        /// Prefer [Self::read_input], which does not allow the reference to outlive the data phase by accident.
+        pub fn get_input(&self, data_key: &$crate::DataKey) -> &Input {
+            self.input.get(data_key)
+        }
+
+        /// Get a mutable reference to the output data field pointed to by this pointer.
+        /// Prefer [Self::write_output], which does not allow the reference to outlive the data phase by accident.
+        pub fn get_output(&mut self, data_key: &$crate::DataKey) -> &mut Output {
+            self.output.get_mut(data_key)
+        }
+
+        /// Call the given function with a reference to the input data field pointed to by this pointer, and return its result.
+        /// This is the recommended accessor, since the reference cannot outlive the call.
+        pub fn read_input<R>(&self, data_key: &$crate::DataKey, f: impl FnOnce(&Input) -> R) -> R {
+            self.input.read(data_key, f)
+        }
+
+        /// Call the given function with a mutable reference to the output data field pointed to by this pointer, and return its result.
+        /// This is the recommended accessor, since the reference cannot outlive the call.
+        pub fn write_output<R>(
+            &mut self,
+            data_key: &$crate::DataKey,
+            f: impl FnOnce(&mut Output) -> R,
+        ) -> R {
+            self.output.write(data_key, f)
+        }
+
+        /// Get a reference to the input data field and a mutable reference to the output data field pointed to by this pointer at the same time.
+        /// Prefer [Self::process], which does not allow the references to outlive the data phase by accident.
+        pub fn get_input_output(&mut self, data_key: &$crate::DataKey) -> (&Input, &mut Output) {
+            (self.input.get(data_key), self.output.get_mut(data_key))
+        }
+
+        /// Call the given function with a reference to the input data field and a mutable reference to the output data field pointed to by this pointer,
+        /// and return its result.
+        pub fn process<R>(
+            &mut self,
+            data_key: &$crate::DataKey,
+            f: impl FnOnce(&Input, &mut Output) -> R,
+        ) -> R {
+            let (input, output) = self.get_input_output(data_key);
+            f(input, output)
+        }
+
+        /// Call the given function with a reference to the input data field pointed to by this pointer,
+        /// store the first returned value in the output data field, and return the second returned value.
+        /// The previous output is dropped, see [Self::exchange_replace] for retrieving it instead.
+        pub fn exchange<R>(
+            &mut self,
+            data_key: &$crate::DataKey,
+            f: impl FnOnce(&Input) -> (Output, R),
+        ) -> R {
+            self.exchange_replace(data_key, f).1
+        }
+
+        /// Like [Self::exchange], but also returns the previous output.
+        pub fn exchange_replace<R>(
+            &mut self,
+            data_key: &$crate::DataKey,
+            f: impl FnOnce(&Input) -> (Output, R),
+        ) -> (Output, R) {
+            let (output, result) = f(self.input.get(data_key));
+            (
+                core::mem::replace(self.output.get_mut(data_key), output),
+                result,
+            )
+        }
+
+        /// Like [Self::exchange], but for a fallible function.
+        /// If the function returns an error, the output is left untouched and not marked as dirty.
+        pub fn try_exchange<R, E>(
+            &mut self,
+            data_key: &$crate::DataKey,
+            f: impl FnOnce(&Input) -> Result<(Output, R), E>,
+        ) -> Result<R, E> {
+            let (output, result) = f(self.input.get(data_key))?;
+            *self.output.get_mut(data_key) = output;
+            Ok(result)
+        }
+    };
+}
+
+pub(crate) use {endpoint_accessors, read_accessors, write_accessors};
+
+/// Statistics about the swaps performed on an undirected channel.
+/// These are maintained during the channel phase, and hence cost nothing during the data phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SwapStats {
+    /// The number of swaps performed on the channel.
+    pub swaps: u64,
+    /// The number of conditional swaps that were skipped because their condition did not hold.
+    pub skipped: u64,
+    /// The generation of the channel after the last swap.
+    /// The generation is incremented by every operation that changes the content of the `Data` fields.
+    pub last_swap_generation: u64,
+}
+
+/// Statistics about the flushes performed on a directed channel.
+/// These are maintained during the channel phase, and hence cost nothing during the data phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FlushStats {
+    /// The number of flushes performed on the channel.
+    pub flushes: u64,
+    /// The number of flushes that were skipped because the writable `Data` was not accessed mutably since the last flush.
+    pub skipped: u64,
+    /// The generation of the channel, i.e. the number of flushes that changed the read-only `Data`.
+    pub generation: u64,
+}
+
+/// Statistics about the flushes performed on both directions of a bidirected channel.
+/// The forward direction transmits `Data1` from the second to the first data pointer,
+/// and the backward direction transmits `Data2` from the first to the second data pointer.
+/// See [BidirectedChannelPointer::stats](crate::bidirected::BidirectedChannelPointer::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BidirectedStats {
+    /// The statistics of the forward direction.
+    pub forward: FlushStats,
+    /// The statistics of the backward direction.
+    pub backward: FlushStats,
+}
+
+/// How a flush moves the content of the writable `Data` of a directed channel into the read-only `Data`.
+/// See [`BidirectedChannel::create_with_strategies`](crate::bidirected::BidirectedChannel::create_with_strategies).
+pub trait FlushStrategy<Data> {
+    /// Move the content of the writable `Data` into the read-only `Data`.
+    fn flush(&self, read_only: &mut Data, writable: &mut Data);
+}
+
+/// Clone the writable `Data` into the read-only `Data`, like [DirectedChannelPointer::flush](crate::directed::DirectedChannelPointer::flush).
+/// The writer afterwards still sees what it wrote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CloneFlush;
+
+/// Clone the writable `Data` into the read-only `Data` via [Clone::clone_from], like [DirectedChannelPointer::flush_clone_from](crate::directed::DirectedChannelPointer::flush_clone_from).
+/// The writer afterwards still sees what it wrote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CloneFromFlush;
+
+/// Swap the writable `Data` with the read-only `Data`, like [DirectedChannelPointer::flush_swap](crate::directed::DirectedChannelPointer::flush_swap).
+/// The writer afterwards sees what was previously published.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SwapFlush;
+
+/// Move the writable `Data` into the read-only `Data`, leaving the default value behind.
+/// This suits `Data` that is consumed exactly once, since nothing is cloned and the writer starts over from the default value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TakeFlush;
+
+impl<Data: Clone> FlushStrategy<Data> for CloneFlush {
+    fn flush(&self, read_only: &mut Data, writable: &mut Data) {
+        *read_only = writable.clone();
+    }
+}
+
+impl<Data: Clone> FlushStrategy<Data> for CloneFromFlush {
+    fn flush(&self, read_only: &mut Data, writable: &mut Data) {
+        read_only.clone_from(writable);
+    }
+}
+
+impl<Data> FlushStrategy<Data> for SwapFlush {
+    fn flush(&self, read_only: &mut Data, writable: &mut Data) {
+        mem::swap(read_only, writable);
+    }
+}
+
+impl<Data: Default> FlushStrategy<Data> for TakeFlush {
+    fn flush(&self, read_only: &mut Data, writable: &mut Data) {
+        *read_only = mem::take(writable);
+    }
+}
@@ -2,7 +2,38 @@
 //! The channel provides two data pointers, one of which is read-only.
 //! Data is only transmitted from the writable end to the readable end.
 
-use crate::{ChannelKey, DataKey};
+use core::{
+    mem::{self, MaybeUninit},
+    ptr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+#[cfg(feature = "fault-injection")]
+use std::sync::Arc;
+use std::{
+    any::{self, Any},
+    borrow::Cow,
+    time::Instant,
+};
+
+pub use crate::common::{
+    CloneFlush, CloneFromFlush, FlushStats, FlushStrategy, SwapFlush, TakeFlush,
+};
+#[cfg(feature = "fault-injection")]
+use crate::fault::{self, FaultHook, FaultPlan, FaultSite};
+use crate::{
+    allocator::Allocator,
+    arena::ChannelBox,
+    capacity::ManageCapacity,
+    checked::{CheckedChannel, CheckedPointer},
+    common,
+    erased::{self, DestroyError, ErasedDataPointer, ErasedDestroy},
+    heap::{self, Zeroable},
+    instrument,
+    observer::{ObserverPointer, Observers},
+    report::{self, ChannelReport},
+    ChannelKey, DataKey, GenerationPointer, Hook, Label, SwapChannel,
+};
 
 /// A directed channel used for communication between threads.
 /// It holds two instances of `Data`, which can be accessed or flushed.
@@ -18,6 +49,51 @@ use crate::{ChannelKey, DataKey};
 pub struct DirectedChannel<Data> {
     pub(crate) read_only: Data,
     pub(crate) writable: Data,
+    pub(crate) generation: u64,
+    /// `true` if the writable `Data` was accessed mutably since the last flush.
+    pub(crate) dirty: bool,
+    /// The generation last marked as consumed by a reader, see [ReadOnlyDataPointer::mark_consumed].
+    /// Written by the reader and read by the writer during the data phase, hence atomic.
+    consumed: AtomicU64,
+    flushes: u64,
+    skipped: u64,
+    checked: CheckedChannel,
+    #[cfg(feature = "fault-injection")]
+    pub(crate) fault: Option<FaultHook>,
+    /// Zeroizes a `Data` before it is displaced or overwritten, if the channel was created via [DirectedChannel::create_zeroizing].
+    #[cfg(feature = "zeroize")]
+    zeroize: Option<fn(&mut Data)>,
+}
+
+/// Copies the bytes of the writable `Data` into the read-only `Data`, see [DirectedChannelPointer::flush_copy].
+#[cfg(feature = "bytemuck")]
+struct CopyFlush;
+
+#[cfg(feature = "bytemuck")]
+impl<Data: bytemuck::Pod> FlushStrategy<Data> for CopyFlush {
+    fn flush(&self, read_only: &mut Data, writable: &mut Data) {
+        bytemuck::bytes_of_mut(read_only).copy_from_slice(bytemuck::bytes_of(writable));
+    }
+}
+
+/// A copy of both `Data` fields of a directed channel, taken during the channel phase.
+/// See [DirectedChannelPointer::snapshot].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DirectedSnapshot<Data> {
+    /// The read-only `Data`, i.e. what was published by the last flush.
+    pub published: Data,
+    /// The writable `Data`, i.e. what will be published by the next flush.
+    pub pending: Data,
+}
+
+/// Serialises like a [DirectedSnapshot], but without cloning the `Data` fields.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(rename = "DirectedSnapshot")]
+struct DirectedSnapshotRef<'a, Data> {
+    published: &'a Data,
+    pending: &'a Data,
 }
 
 /// A pointer to a directed channel.
@@ -27,7 +103,12 @@ pub struct DirectedChannel<Data> {
 #[derive(Debug)]
 #[must_use]
 pub struct DirectedChannelPointer<Data> {
-    channel: Box<DirectedChannel<Data>>,
+    /// Dropped first, such that reading observers are waited for before the channel is freed.
+    observers: Observers,
+    channel: ChannelBox<DirectedChannel<Data>>,
+    on_flush: Hook<u64>,
+    label: Label,
+    created_at: Option<Instant>,
 }
 
 /// A pointer to the read-only data field in a directed channel.
@@ -38,6 +119,7 @@ pub struct DirectedChannelPointer<Data> {
 #[must_use]
 pub struct ReadOnlyDataPointer<Data> {
     pub(crate) data: *const Data,
+    pub(crate) progress: ProgressPointer,
 }
 
 /// A pointer to the writable data field in a directed channel.
@@ -48,6 +130,37 @@ pub struct ReadOnlyDataPointer<Data> {
 #[must_use]
 pub struct WritableDataPointer<Data> {
     pub(crate) data: *mut Data,
+    pub(crate) dirty: *mut bool,
+    pub(crate) progress: ProgressPointer,
+}
+
+/// Pointers to the generation and the consumed generation of a directed channel, shared by its data pointers.
+/// These are stored separately from the `Data` pointer, which may point to a field of `Data` after a projection.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProgressPointer {
+    generation: GenerationPointer,
+    consumed: *const AtomicU64,
+    checked: CheckedPointer,
+}
+
+impl ProgressPointer {
+    /// Check an access to the `Data` of the channel via the given data key, if the `checked-backend` feature is enabled.
+    #[track_caller]
+    pub(crate) fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+
+    /// Mark the current generation as consumed.
+    fn mark_consumed(&self) {
+        unsafe { &*self.consumed }.store(self.generation.get(), Ordering::Relaxed);
+    }
+
+    /// The number of generations since the last generation marked as consumed.
+    fn lag(&self) -> u64 {
+        self.generation
+            .get()
+            .saturating_sub(unsafe { &*self.consumed }.load(Ordering::Relaxed))
+    }
 }
 
 impl<Data> DirectedChannel<Data> {
@@ -59,6 +172,8 @@ impl<Data> DirectedChannel<Data> {
     /// Note that the `ReadOnlyPointer` and the `WriteOnlyPointer` point to different copies of `Data`,
     /// and hence can safely be accessed concurrently.
     /// See [`DirectedChannelPointer::flush`] for how to exchange information between the pointers.
+    ///
+    /// This allocates the channel, and is hence not [real-time safe](crate::rt).
     pub fn create(
         read_only: Data,
         writable: Data,
@@ -67,18 +182,240 @@ impl<Data> DirectedChannel<Data> {
         ReadOnlyDataPointer<Data>,
         WritableDataPointer<Data>,
     ) {
-        let mut channel_pointer = DirectedChannelPointer {
-            channel: Box::new(DirectedChannel {
-                read_only,
-                writable,
-            }),
-        };
-        let read_only_data_pointer = ReadOnlyDataPointer {
-            data: (&channel_pointer.channel.read_only) as *const Data,
+        Self::hand_out(ChannelBox::new(Self::new(read_only, writable)))
+    }
+
+    /// Create a directed channel like [DirectedChannel::create], but allocate it with the given allocator.
+    /// The allocator is moved into the allocation, and dropped when the channel is destroyed.
+    pub fn create_in<A: Allocator + Send + 'static>(
+        read_only: Data,
+        writable: Data,
+        allocator: A,
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        Self::hand_out(ChannelBox::new_in(
+            Self::new(read_only, writable),
+            allocator,
+        ))
+    }
+
+    pub(crate) fn new(read_only: Data, writable: Data) -> Self {
+        Self {
+            read_only,
+            writable,
+            generation: 0,
+            dirty: true,
+            consumed: AtomicU64::new(0),
+            flushes: 0,
+            skipped: 0,
+            checked: CheckedChannel::new(),
+            #[cfg(feature = "fault-injection")]
+            fault: None,
+            #[cfg(feature = "zeroize")]
+            zeroize: None,
+        }
+    }
+
+    /// Zeroize both `Data` fields before they are replaced, if this is a zeroizing channel.
+    fn zeroize_both(&mut self) {
+        #[cfg(feature = "zeroize")]
+        if let Some(zeroize) = self.zeroize {
+            zeroize(&mut self.read_only);
+            zeroize(&mut self.writable);
+        }
+    }
+
+    /// Replace both `Data` fields in place, and reset the generation and dirty flag as if the channel was newly created.
+    /// Data pointers stay valid, since the channel is not moved.
+    pub(crate) fn reset(&mut self, read_only: Data, writable: Data) {
+        self.zeroize_both();
+        self.read_only = read_only;
+        self.writable = writable;
+        self.generation = 0;
+        self.dirty = true;
+        self.consumed = AtomicU64::new(0);
+        self.flushes = 0;
+        self.skipped = 0;
+    }
+
+    /// Replace both `Data` fields with the ones of the snapshot.
+    /// In contrast to [DirectedChannel::reset], the statistics are kept and the generation advances.
+    #[cfg(feature = "serde")]
+    pub(crate) fn restore(&mut self, snapshot: DirectedSnapshot<Data>) {
+        self.zeroize_both();
+        self.read_only = snapshot.published;
+        self.writable = snapshot.pending;
+        self.generation += 1;
+        self.dirty = true;
+    }
+
+    /// Swap the writable `Data` with the read-only `Data`, if the channel is dirty.
+    /// Returns `true` if the channel was dirty and hence flushed.
+    pub(crate) fn flush_swap_if_dirty(&mut self) -> bool {
+        self.flush_with_if_dirty(&SwapFlush)
+    }
+
+    /// Flush the channel with the given strategy, if the channel is dirty.
+    /// Returns `true` if the channel was dirty and hence flushed.
+    pub(crate) fn flush_with_if_dirty(&mut self, strategy: &impl FlushStrategy<Data>) -> bool {
+        self.flush_by_if_dirty(|read_only, writable| strategy.flush(read_only, writable))
+    }
+
+    /// Flush the channel by calling the given function with the read-only and the writable `Data`, if the channel is dirty.
+    /// Returns `true` if the channel was dirty and hence flushed.
+    ///
+    /// In a zeroizing channel, the read-only `Data` is zeroized before, since every flush displaces or overwrites it.
+    /// A swapping flush hence hands the zeroized `Data` to the writer.
+    pub(crate) fn flush_by_if_dirty(&mut self, flush: impl FnOnce(&mut Data, &mut Data)) -> bool {
+        self.checked.advance();
+        #[cfg(feature = "fault-injection")]
+        if !fault::inject(&self.fault, FaultSite::Flush) {
+            return false;
+        }
+        if self.dirty {
+            #[cfg(feature = "zeroize")]
+            if let Some(zeroize) = self.zeroize {
+                zeroize(&mut self.read_only);
+            }
+            flush(&mut self.read_only, &mut self.writable);
+            self.generation += 1;
+            self.flushes += 1;
+            self.dirty = false;
+            true
+        } else {
+            self.skipped += 1;
+            false
+        }
+    }
+
+    pub(crate) fn stats(&self) -> FlushStats {
+        FlushStats {
+            flushes: self.flushes,
+            skipped: self.skipped,
+            generation: self.generation,
+        }
+    }
+
+    /// The generation counter of the directed channel whose read-only `Data` field is at the given address.
+    pub(crate) fn generation_of_read_only(read_only: *const Data) -> GenerationPointer {
+        let channel = MaybeUninit::<Self>::uninit();
+        let base = channel.as_ptr();
+        let (read_only_offset, generation_offset) = unsafe {
+            (
+                ptr::addr_of!((*base).read_only) as usize - base as usize,
+                ptr::addr_of!((*base).generation) as usize - base as usize,
+            )
         };
-        let writable_data_pointer = WritableDataPointer {
-            data: (&mut channel_pointer.channel.writable) as *mut Data,
+        GenerationPointer::from_raw(
+            (read_only as usize)
+                .wrapping_sub(read_only_offset)
+                .wrapping_add(generation_offset) as *const u64,
+        )
+    }
+
+    pub(crate) fn read_only_data_pointer(&self) -> ReadOnlyDataPointer<Data> {
+        ReadOnlyDataPointer {
+            data: (&self.read_only) as *const Data,
+            progress: self.progress_pointer(),
+        }
+    }
+
+    pub(crate) fn writable_data_pointer(&mut self) -> WritableDataPointer<Data> {
+        WritableDataPointer {
+            data: (&mut self.writable) as *mut Data,
+            dirty: (&mut self.dirty) as *mut bool,
+            progress: self.progress_pointer(),
+        }
+    }
+
+    fn progress_pointer(&self) -> ProgressPointer {
+        ProgressPointer {
+            generation: GenerationPointer::new(&self.generation),
+            consumed: &self.consumed,
+            checked: self.checked.pointer(),
+        }
+    }
+
+    /// The number of flushes since the generation last marked as consumed.
+    pub(crate) fn reader_lag(&self) -> u64 {
+        self.generation
+            .saturating_sub(self.consumed.load(Ordering::Relaxed))
+    }
+
+    /// Create a directed channel like [DirectedChannel::create],
+    /// where both `Data` fields are initialised in place on the heap by the given function.
+    /// This avoids materialising large `Data` on the stack.
+    ///
+    /// # Safety
+    ///
+    /// The given function must fully initialise the given `Data`.
+    pub unsafe fn create_boxed_with(
+        f: impl Fn(&mut MaybeUninit<Data>),
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        Self::create_in_place(false, |data| f(&mut *(data as *mut MaybeUninit<Data>)))
+    }
+
+    /// Allocate the channel and initialise the `Data` fields in place with the given function.
+    ///
+    /// # Safety
+    ///
+    /// The given function must fully initialise the given `Data`, where the memory of `Data` is zeroed if `zeroed` is `true`.
+    unsafe fn create_in_place(
+        zeroed: bool,
+        init: impl Fn(*mut Data),
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        let channel = Self::allocate_uninit(zeroed);
+        init(ptr::addr_of_mut!((*channel).read_only));
+        init(ptr::addr_of_mut!((*channel).writable));
+        Self::hand_out(ChannelBox::from_box(Box::from_raw(channel)))
+    }
+
+    /// Allocate a directed channel on the heap where all fields except for the `Data` fields are initialised.
+    pub(crate) fn allocate_uninit(zeroed: bool) -> *mut Self {
+        let channel = heap::allocate::<Self>(zeroed);
+        unsafe {
+            ptr::addr_of_mut!((*channel).generation).write(0);
+            ptr::addr_of_mut!((*channel).dirty).write(true);
+            ptr::addr_of_mut!((*channel).consumed).write(AtomicU64::new(0));
+            ptr::addr_of_mut!((*channel).flushes).write(0);
+            ptr::addr_of_mut!((*channel).skipped).write(0);
+            ptr::addr_of_mut!((*channel).checked).write(CheckedChannel::new());
+            #[cfg(feature = "fault-injection")]
+            ptr::addr_of_mut!((*channel).fault).write(None);
+            #[cfg(feature = "zeroize")]
+            ptr::addr_of_mut!((*channel).zeroize).write(None);
+        }
+        channel
+    }
+
+    /// Wrap the given channel into a channel pointer and create the data pointers to it.
+    pub(crate) fn hand_out(
+        channel: ChannelBox<Self>,
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        let mut channel_pointer = DirectedChannelPointer {
+            channel,
+            on_flush: Hook::default(),
+            label: Label::default(),
+            created_at: report::now(),
+            observers: Observers::default(),
         };
+        let read_only_data_pointer = channel_pointer.channel.read_only_data_pointer();
+        let writable_data_pointer = channel_pointer.channel.writable_data_pointer();
         (
             channel_pointer,
             read_only_data_pointer,
@@ -88,6 +425,7 @@ impl<Data> DirectedChannel<Data> {
 
     /// Destroys the directed channel linked with the given pointers (see [DirectedChannel::create]).
     /// Compared to [`DirectedChannel::destroy_single`], this function accepts multiple [`ReadOnlyDataPointer`]s for destruction.
+    /// This frees the channel, and is hence not [real-time safe](crate::rt).
     ///
     /// **Panics** if not all pointers point to the same channel.
     pub fn destroy(
@@ -95,22 +433,128 @@ impl<Data> DirectedChannel<Data> {
         read_only_data_pointers: impl IntoIterator<Item = ReadOnlyDataPointer<Data>>,
         writable_data_pointer: WritableDataPointer<Data>,
     ) -> (Data, Data) {
-        let DirectedChannelPointer { mut channel } = channel_pointer;
+        let channel = Self::into_channel(
+            channel_pointer,
+            read_only_data_pointers,
+            writable_data_pointer,
+        );
+        #[cfg(feature = "zeroize")]
+        if channel.zeroize.is_some() {
+            let channel = channel.into_inner_wiped();
+            return (channel.read_only, channel.writable);
+        }
+        let channel = channel.into_inner();
+        (channel.read_only, channel.writable)
+    }
+
+    /// Destroys the directed channel linked with the given pointers like [DirectedChannel::destroy],
+    /// and additionally returns the [ChannelReport] of the channel, e.g. to log its usage at shutdown.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy_with_report(
+        channel_pointer: DirectedChannelPointer<Data>,
+        read_only_data_pointers: impl IntoIterator<Item = ReadOnlyDataPointer<Data>>,
+        writable_data_pointer: WritableDataPointer<Data>,
+    ) -> (Data, Data, ChannelReport) {
+        let stats = channel_pointer.channel.stats();
+        let report = ChannelReport::new(
+            &channel_pointer.label,
+            stats.flushes,
+            stats.skipped,
+            channel_pointer.created_at,
+            stats.generation,
+        );
+        let (read_only, writable) = Self::destroy(
+            channel_pointer,
+            read_only_data_pointers,
+            writable_data_pointer,
+        );
+        (read_only, writable, report)
+    }
+
+    /// Destroys the directed channel linked with the given pointers (see [DirectedChannel::create]),
+    /// moving the `Data` fields into separate boxes on the heap.
+    /// This avoids materialising large `Data` on the stack.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy_boxed(
+        channel_pointer: DirectedChannelPointer<Data>,
+        read_only_data_pointers: impl IntoIterator<Item = ReadOnlyDataPointer<Data>>,
+        writable_data_pointer: WritableDataPointer<Data>,
+    ) -> (Box<Data>, Box<Data>) {
+        let channel = Self::into_channel(
+            channel_pointer,
+            read_only_data_pointers,
+            writable_data_pointer,
+        );
+        #[cfg(feature = "zeroize")]
+        let channel = if channel.zeroize.is_some() {
+            channel.into_box_wiped()
+        } else {
+            channel.into_box()
+        };
+        #[cfg(not(feature = "zeroize"))]
+        let channel = channel.into_box();
+        let channel = Box::into_raw(channel);
+        unsafe {
+            let read_only = heap::allocate::<Data>(false);
+            let writable = heap::allocate::<Data>(false);
+            ptr::copy_nonoverlapping(ptr::addr_of!((*channel).read_only), read_only, 1);
+            ptr::copy_nonoverlapping(ptr::addr_of!((*channel).writable), writable, 1);
+            ptr::drop_in_place(ptr::addr_of_mut!((*channel).checked));
+            #[cfg(feature = "fault-injection")]
+            ptr::drop_in_place(ptr::addr_of_mut!((*channel).fault));
+            #[cfg(feature = "zeroize")]
+            if (*channel).zeroize.is_some() {
+                heap::wipe(channel);
+            }
+            // Free the channel without dropping the moved `Data` fields.
+            drop(Box::from_raw(channel as *mut MaybeUninit<Self>));
+            (Box::from_raw(read_only), Box::from_raw(writable))
+        }
+    }
+
+    /// Unwrap the channel from the given channel pointer.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub(crate) fn into_channel(
+        channel_pointer: DirectedChannelPointer<Data>,
+        read_only_data_pointers: impl IntoIterator<Item = ReadOnlyDataPointer<Data>>,
+        writable_data_pointer: WritableDataPointer<Data>,
+    ) -> ChannelBox<Self> {
+        let DirectedChannelPointer {
+            mut channel,
+            label,
+            observers,
+            ..
+        } = channel_pointer;
+        // Wait for reading observers before the channel is freed.
+        drop(observers);
         let channel_writable_data_pointer = (&mut channel.writable) as *mut Data;
         let WritableDataPointer {
             data: writable_data_pointer,
+            ..
         } = writable_data_pointer;
-        assert_eq!(channel_writable_data_pointer, writable_data_pointer);
+        assert_eq!(
+            channel_writable_data_pointer, writable_data_pointer,
+            "the writable data pointer does not point to the {}",
+            label
+        );
         let channel_read_only_data_pointer = (&channel.read_only) as *const Data;
 
         for read_only_data_pointer in read_only_data_pointers {
             let ReadOnlyDataPointer {
                 data: read_only_data_pointer,
+                ..
             } = read_only_data_pointer;
-            assert_eq!(channel_read_only_data_pointer, read_only_data_pointer);
+            assert_eq!(
+                channel_read_only_data_pointer, read_only_data_pointer,
+                "a read-only data pointer does not point to the {}",
+                label
+            );
         }
 
-        (channel.read_only, channel.writable)
+        channel
     }
 
     /// Destroys the directed channel linked with the given pointers (see [DirectedChannel::create]).
@@ -130,6 +574,44 @@ impl<Data> DirectedChannel<Data> {
     }
 }
 
+impl<Data: Zeroable> DirectedChannel<Data> {
+    /// Create a directed channel like [DirectedChannel::create], where both `Data` fields are zeroed in place on the heap.
+    /// This avoids materialising large `Data` on the stack.
+    pub fn create_boxed_zeroed() -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        unsafe { Self::create_in_place(true, |_| {}) }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<Data: zeroize::Zeroize> DirectedChannel<Data> {
+    /// Create a directed channel like [DirectedChannel::create] for secrets, e.g. key material or session tokens.
+    /// The channel zeroizes each `Data` that goes away, but never the one that is currently published or written:
+    ///
+    ///  * each flush zeroizes the read-only `Data` before replacing it, such that [DirectedChannelPointer::flush_swap] hands the writer a zeroized `Data`
+    ///    instead of the previously published one,
+    ///  * resetting or restoring the channel zeroizes both `Data` fields before replacing them, and
+    ///  * destroying the channel overwrites the memory of the channel with zeros after moving out the `Data` fields.
+    ///
+    /// Channels created otherwise pay nothing for this.
+    /// Note that `Data` dropped by other means, e.g. by dropping the channel pointer instead of destroying the channel, is not zeroized.
+    pub fn create_zeroizing(
+        read_only: Data,
+        writable: Data,
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        let mut channel = Self::new(read_only, writable);
+        channel.zeroize = Some(<Data as zeroize::Zeroize>::zeroize);
+        Self::hand_out(ChannelBox::new(channel))
+    }
+}
+
 impl<Data: Clone> DirectedChannel<Data> {
     /// In this constructor, both `Data` fields are initialised equally from the given `Data`.
     ///
@@ -144,20 +626,278 @@ impl<Data: Clone> DirectedChannel<Data> {
         Self::create(data.clone(), data)
     }
 
+    /// Clone the writable `Data` into the read-only `Data`, if the writable `Data` was accessed mutably since the last flush.
     pub fn flush(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
-        self.read_only = self.writable.clone();
+        self.flush_if_dirty();
+    }
+
+    pub(crate) fn snapshot(&self) -> DirectedSnapshot<Data> {
+        DirectedSnapshot {
+            published: self.read_only.clone(),
+            pending: self.writable.clone(),
+        }
+    }
+
+    /// Returns `true` if the channel was dirty and hence flushed.
+    pub(crate) fn flush_if_dirty(&mut self) -> bool {
+        self.flush_with_if_dirty(&CloneFlush)
     }
 }
 
 impl<Data: Clone> DirectedChannelPointer<Data> {
     /// Clone the writable `Data` into the read-only `Data`.
+    /// If the writable `Data` was not accessed mutably since the last flush, the read-only `Data` is still equal to it,
+    /// and the flush is skipped (see [DirectedChannelPointer::is_dirty]).
+    ///
+    /// This clones `Data`, and is hence not [real-time safe](crate::rt) if cloning allocates.
     pub fn flush(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
-        let channel: &mut DirectedChannel<Data> = &mut self.channel;
-        channel.read_only = channel.writable.clone();
+        self.flush_with_if_dirty(&CloneFlush);
+    }
+
+    /// Like [DirectedChannelPointer::flush], but clone via [Clone::clone_from], which may reuse the allocations of the read-only `Data`.
+    /// For example, flushing a `Vec` does not allocate if the read-only `Vec` has enough capacity,
+    /// which makes this flush [real-time safe](crate::rt) once the capacity was reserved, e.g. via [DirectedChannelPointer::manage_capacity].
+    pub fn flush_clone_from(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&CloneFromFlush);
+    }
+
+    /// Clone the read-only `Data` of the source channel into the writable `Data` of this channel via [Clone::clone_from],
+    /// e.g. for a coordinator that forwards the output of one stage to the input of the next stage in each channel phase.
+    /// This replaces cloning into a temporary and writing it in the next data phase.
+    /// The forwarded `Data` is visible to the readers of this channel after its next flush.
+    ///
+    /// The source and the destination are always distinct channels,
+    /// since each channel has a single channel pointer, which cannot be borrowed mutably and immutably at the same time:
+    ///
+    /// ```compile_fail
+    /// use two_phase_channel::{directed::DirectedChannel, MasterKey};
+    ///
+    /// let mut master_key = MasterKey::create();
+    /// let (mut channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+    /// channel_pointer.copy_through(&channel_pointer, &master_key.get_channel_key());
+    /// ```
+    pub fn copy_through(
+        &mut self,
+        source: &DirectedChannelPointer<Data>,
+        #[allow(unused)] channel_key: &ChannelKey,
+    ) {
+        self.channel.writable.clone_from(&source.channel.read_only);
+        self.channel.dirty = true;
+    }
+
+    /// Clone both `Data` fields of the channel, e.g. for debugging.
+    pub fn snapshot(&self, #[allow(unused)] channel_key: &ChannelKey) -> DirectedSnapshot<Data> {
+        self.channel.snapshot()
+    }
+
+    /// Create a new directed channel whose `Data` fields are clones of the `Data` fields of this channel,
+    /// and hand out three pointers to it (see [DirectedChannel::create]).
+    /// The new channel is independent of this channel and must be destroyed separately.
+    pub fn clone_channel(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        DirectedChannel::create(
+            self.channel.read_only.clone(),
+            self.channel.writable.clone(),
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Data: serde::Serialize> DirectedChannelPointer<Data> {
+    /// Serialise both `Data` fields of the channel in the format of a [DirectedSnapshot], without cloning them.
+    pub fn serialize_contents<S: serde::Serializer>(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(
+            &DirectedSnapshotRef {
+                published: &self.channel.read_only,
+                pending: &self.channel.writable,
+            },
+            serializer,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Data> DirectedChannelPointer<Data> {
+    /// Deserialise both `Data` fields of the channel from the format of a [DirectedSnapshot], replacing the current ones.
+    /// The generation of the channel advances and the channel is dirty afterwards.
+    /// If deserialisation fails, the channel is unchanged.
+    pub fn deserialize_contents<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        Data: serde::Deserialize<'de>,
+    {
+        let snapshot = serde::Deserialize::deserialize(deserializer)?;
+        self.channel.restore(snapshot);
+        Ok(())
     }
 }
 
 impl<Data> DirectedChannelPointer<Data> {
+    /// Convert this channel pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this channel pointer, and must be converted back with [DirectedChannelPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
+
+    /// Reconstitute a channel pointer from a raw pointer returned by [DirectedChannelPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [DirectedChannelPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
+
+    /// Attach a label to the channel, which is used in the panic messages of destroying the channel and returned by [DirectedChannelPointer::label].
+    pub fn with_label(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.label.set(label);
+        self
+    }
+
+    /// The label of the channel, if it was set via [DirectedChannelPointer::with_label].
+    pub fn label(&self) -> Option<&str> {
+        self.label.get()
+    }
+
+    /// Consult the given fault plan at each flush, see the [fault] module.
+    /// The faults are recorded with the current label of the channel.
+    #[cfg(feature = "fault-injection")]
+    pub fn set_fault_plan(&mut self, plan: Arc<FaultPlan>) {
+        self.channel.fault = Some(FaultHook::new(plan, self.label.get()));
+    }
+
+    /// Call the given function on both `Data` fields of the channel.
+    /// This allows to manage the capacity of growable `Data` during the channel phase,
+    /// where no data pointer can access the `Data` fields.
+    pub fn manage_capacity(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        mut f: impl FnMut(&mut Data),
+    ) {
+        f(&mut self.channel.read_only);
+        f(&mut self.channel.writable);
+        self.channel.dirty = true;
+    }
+
+    /// Get a mutable reference to the read-only `Data`, e.g. to reset bookkeeping of a wrapper when its flush was skipped.
+    pub(crate) fn read_only_mut(&mut self, #[allow(unused)] channel_key: &ChannelKey) -> &mut Data {
+        &mut self.channel.read_only
+    }
+
+    /// Create an observer of the read-only `Data`, which reads it like a [ReadOnlyDataPointer],
+    /// but does not need to be returned when destroying the channel.
+    /// Once the channel is destroyed, the observer reads `None`, see the [observer module](crate::observer).
+    pub fn observer(&mut self) -> ObserverPointer<Data> {
+        self.observers.observe(&self.channel.read_only)
+    }
+
+    /// Replace the writable `Data` with the given `Data`, and return the previous writable `Data`.
+    /// The new `Data` is visible to the readers after the next flush.
+    pub fn replace_writable(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        data: Data,
+    ) -> Data {
+        self.channel.dirty = true;
+        mem::replace(&mut self.channel.writable, data)
+    }
+
+    /// Like [DirectedChannelPointer::copy_through], but swap the read-only `Data` of the source channel with the writable `Data` of this channel,
+    /// which requires no `Clone` bound and is [real-time safe](crate::rt).
+    ///
+    /// The readers of the source channel afterwards see the previous writable `Data` of this channel until the next flush of the source channel,
+    /// hence this suits chains where the coordinator is the only consumer of the source channel.
+    /// The generation of the source channel is not advanced.
+    pub fn move_through(
+        &mut self,
+        source: &mut DirectedChannelPointer<Data>,
+        #[allow(unused)] channel_key: &ChannelKey,
+    ) {
+        mem::swap(&mut self.channel.writable, &mut source.channel.read_only);
+        self.channel.dirty = true;
+    }
+
+    /// Swap the writable `Data` with the read-only `Data` instead of cloning it, which requires no `Clone` bound and is [real-time safe](crate::rt).
+    /// The swap is skipped if the writable `Data` was not accessed mutably since the last flush (see [DirectedChannelPointer::is_dirty]).
+    ///
+    /// Unlike with [DirectedChannelPointer::flush], the writer afterwards sees what was previously published, and not what it wrote itself.
+    pub fn flush_swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&SwapFlush);
+    }
+
+    /// Copy the bytes of the writable `Data` into the read-only `Data` with a single `memcpy`.
+    /// Like [DirectedChannelPointer::flush], the copy is skipped if the writable `Data` was not accessed mutably since the last flush.
+    #[cfg(feature = "bytemuck")]
+    pub fn flush_copy(&mut self, #[allow(unused)] channel_key: &ChannelKey)
+    where
+        Data: bytemuck::Pod,
+    {
+        self.flush_with_if_dirty(&CopyFlush);
+    }
+
+    /// Flush the channel with the given strategy, if it is dirty, instrumenting the flush and calling the flush hook.
+    /// Returns `true` if the channel was dirty and hence flushed.
+    pub(crate) fn flush_with_if_dirty(&mut self, strategy: &impl FlushStrategy<Data>) -> bool {
+        let channel: &mut DirectedChannel<Data> = &mut self.channel;
+        let flushed = instrument::flush(&self.label, None, mem::size_of::<Data>(), || {
+            (channel.flush_with_if_dirty(strategy), channel.generation)
+        });
+        if flushed {
+            self.on_flush.call(self.channel.generation);
+        }
+        flushed
+    }
+
+    /// Call the given function with the read-only and the writable `Data`, in this order, without cloning them.
+    pub fn inspect<R>(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        f: impl FnOnce(&Data, &Data) -> R,
+    ) -> R {
+        f(&self.channel.read_only, &self.channel.writable)
+    }
+
+    /// Returns `true` if the writable `Data` was accessed mutably since the last flush,
+    /// i.e. if the next flush will clone it.
+    /// A newly created channel is dirty.
+    pub fn is_dirty(&self, #[allow(unused)] channel_key: &ChannelKey) -> bool {
+        self.channel.dirty
+    }
+
+    /// Statistics about the flushes performed on this channel.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> FlushStats {
+        self.channel.stats()
+    }
+
+    /// The number of flushes that changed the read-only `Data` since the reader last called [ReadOnlyDataPointer::mark_consumed],
+    /// e.g. to skip a flush while the reader is lagging.
+    /// See [WritableDataPointer::reader_lag] for the same signal in the data phase.
+    pub fn reader_lag(&self, #[allow(unused)] channel_key: &ChannelKey) -> u64 {
+        self.channel.reader_lag()
+    }
+
+    /// Set a hook that is called at the end of each flush with the number of flushes performed on the channel so far.
+    /// Skipped flushes of clean channels do not call the hook.
+    /// The hook runs inside the flush, i.e. while the channel key is held.
+    pub fn set_on_flush(&mut self, hook: Box<dyn FnMut(u64) + Send>) {
+        self.on_flush.set(hook);
+    }
+
     /// Shorthand for [DirectedChannel::destroy].
     pub fn destroy(
         self,
@@ -167,6 +907,53 @@ impl<Data> DirectedChannelPointer<Data> {
         DirectedChannel::destroy(self, read_only_data_pointers, writable_data_pointer)
     }
 
+    /// Shorthand for [DirectedChannel::destroy_with_report].
+    pub fn destroy_with_report(
+        self,
+        read_only_data_pointers: impl IntoIterator<Item = ReadOnlyDataPointer<Data>>,
+        writable_data_pointer: WritableDataPointer<Data>,
+    ) -> (Data, Data, ChannelReport) {
+        DirectedChannel::destroy_with_report(self, read_only_data_pointers, writable_data_pointer)
+    }
+
+    /// Convert the `Data` type of the channel, e.g. after hot-reloading a plugin that changed the shape of its payload.
+    /// This consumes the given pointers, validating them like [DirectedChannel::destroy],
+    /// converts both `Data` fields with the given function, and hands out pointers to the converted channel.
+    /// The new [ReadOnlyDataPointer] can be copied to replace all given read-only data pointers.
+    ///
+    /// Since this requires a channel key, no data pointer observes a half-converted channel.
+    /// The label, the flush hook and the creation time of the channel are kept, while its generation and statistics start over,
+    /// and the converted channel is dirty, such that the next flush publishes the converted writable `Data`.
+    /// This reallocates the channel, and is hence not [real-time safe](crate::rt).
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn migrate<New>(
+        mut self,
+        read_only_data_pointers: impl IntoIterator<Item = ReadOnlyDataPointer<Data>>,
+        writable_data_pointer: WritableDataPointer<Data>,
+        #[allow(unused)] channel_key: &ChannelKey,
+        mut f: impl FnMut(Data) -> New,
+    ) -> (
+        DirectedChannelPointer<New>,
+        ReadOnlyDataPointer<New>,
+        WritableDataPointer<New>,
+    ) {
+        let on_flush = mem::take(&mut self.on_flush);
+        let label = self.label.clone();
+        let created_at = self.created_at;
+        let (read_only, writable) = self.destroy(read_only_data_pointers, writable_data_pointer);
+        let (mut channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create(f(read_only), f(writable));
+        channel_pointer.on_flush = on_flush;
+        channel_pointer.label = label;
+        channel_pointer.created_at = created_at;
+        (
+            channel_pointer,
+            read_only_data_pointer,
+            writable_data_pointer,
+        )
+    }
+
     /// Shorthand for [DirectedChannel::destroy_single].
     pub fn destroy_single(
         self,
@@ -177,22 +964,168 @@ impl<Data> DirectedChannelPointer<Data> {
     }
 }
 
+impl<Data: ManageCapacity> DirectedChannelPointer<Data> {
+    /// Shrink the capacity of both `Data` fields as much as possible.
+    pub fn shrink_both(&mut self, channel_key: &ChannelKey) {
+        self.manage_capacity(channel_key, ManageCapacity::shrink_to_fit);
+    }
+
+    /// Reserve capacity for at least `additional` more elements in both `Data` fields.
+    pub fn reserve_both(&mut self, channel_key: &ChannelKey, additional: usize) {
+        self.manage_capacity(channel_key, |data| data.reserve(additional));
+    }
+}
+
+impl<Data> ReadOnlyDataPointer<Data> {
+    /// Convert this data pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this data pointer, and must be converted back with [ReadOnlyDataPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
+
+    /// Reconstitute a data pointer from a raw pointer returned by [ReadOnlyDataPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [ReadOnlyDataPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
+
+    common::read_accessors!();
+
+    fn access(&self, data_key: &DataKey) {
+        self.progress.access(data_key);
+    }
+
+    /// Mark the current read-only `Data` as consumed, resetting the [reader lag](WritableDataPointer::reader_lag) to zero.
+    /// If multiple read-only data pointers mark the `Data` as consumed, the lag is measured from the last of them.
+    pub fn mark_consumed(&self, data_key: &DataKey) {
+        self.progress.access(data_key);
+        self.progress.mark_consumed();
+    }
+}
+
+impl<Data> WritableDataPointer<Data> {
+    /// Convert this data pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this data pointer, and must be converted back with [WritableDataPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
+
+    /// Reconstitute a data pointer from a raw pointer returned by [WritableDataPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [WritableDataPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
+
+    common::read_accessors!();
+    common::write_accessors!(
+        /// This marks the channel as dirty, such that the next flush clones the `Data`.
+    );
+
+    fn access(&self, data_key: &DataKey) {
+        self.progress.access(data_key);
+    }
+
+    fn access_mut(&mut self, data_key: &DataKey) {
+        self.access(data_key);
+        unsafe { *self.dirty = true };
+    }
+
+    /// The number of flushes that changed the read-only `Data` since the reader last called [ReadOnlyDataPointer::mark_consumed],
+    /// e.g. to write less while the reader is lagging.
+    /// Before the reader marks any `Data` as consumed, this is the generation of the channel.
+    pub fn reader_lag(&self, data_key: &DataKey) -> u64 {
+        self.progress.access(data_key);
+        self.progress.lag()
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<Data: bytemuck::Pod> ReadOnlyDataPointer<Data> {
+    /// Get the bytes of the `Data` field pointed to by this pointer, e.g. to upload them to the GPU without copying them first.
+    ///
+    /// Types with padding bytes are not [Pod](bytemuck::Pod), and hence cannot be viewed as bytes:
+    ///
+    /// ```compile_fail
+    /// #[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+    /// #[repr(C)]
+    /// struct Padded {
+    ///     a: u8,
+    ///     b: u32,
+    /// }
+    /// ```
+    pub fn as_bytes(&self, data_key: &DataKey) -> &[u8] {
+        bytemuck::bytes_of(self.get(data_key))
+    }
+}
+
+#[cfg(feature = "bytemuck")]
 impl<Data> ReadOnlyDataPointer<Data> {
-    /// Get a reference to the `Data` field pointed to by this pointer.
-    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> &Data {
-        unsafe { &*self.data }
+    /// Get the bytes of the elements of the slice in the `Data` field pointed to by this pointer,
+    /// e.g. of a `[T; N]`, a `Box<[T]>` or a `Vec<T>`.
+    pub fn as_slice_bytes<T: bytemuck::Pod>(&self, data_key: &DataKey) -> &[u8]
+    where
+        Data: AsRef<[T]>,
+    {
+        bytemuck::cast_slice(self.get(data_key).as_ref())
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<Data: bytemuck::Pod> WritableDataPointer<Data> {
+    /// Get the bytes of the `Data` field pointed to by this pointer.
+    pub fn as_bytes(&self, data_key: &DataKey) -> &[u8] {
+        bytemuck::bytes_of(self.get(data_key))
+    }
+
+    /// Get the mutable bytes of the `Data` field pointed to by this pointer, e.g. to fill them from a buffer.
+    /// This marks the channel as dirty, like [WritableDataPointer::get_mut].
+    pub fn as_bytes_mut(&mut self, data_key: &DataKey) -> &mut [u8] {
+        bytemuck::bytes_of_mut(self.get_mut(data_key))
     }
 }
 
+#[cfg(feature = "bytemuck")]
 impl<Data> WritableDataPointer<Data> {
-    /// Get a reference to the `Data` field pointed to by this pointer.
-    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> &Data {
-        unsafe { &*self.data }
+    /// Get the bytes of the elements of the slice in the `Data` field pointed to by this pointer,
+    /// e.g. of a `[T; N]`, a `Box<[T]>` or a `Vec<T>`.
+    pub fn as_slice_bytes<T: bytemuck::Pod>(&self, data_key: &DataKey) -> &[u8]
+    where
+        Data: AsRef<[T]>,
+    {
+        bytemuck::cast_slice(self.get(data_key).as_ref())
     }
 
-    /// Get a mutable reference to the `Data` field pointed to by this pointer.
-    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> &mut Data {
-        unsafe { &mut *self.data }
+    /// Get the mutable bytes of the elements of the slice in the `Data` field pointed to by this pointer.
+    /// This marks the channel as dirty, like [WritableDataPointer::get_mut].
+    pub fn as_slice_bytes_mut<T: bytemuck::Pod>(&mut self, data_key: &DataKey) -> &mut [u8]
+    where
+        Data: AsMut<[T]>,
+    {
+        bytemuck::cast_slice_mut(self.get_mut(data_key).as_mut())
+    }
+}
+
+impl<Data: 'static> ReadOnlyDataPointer<Data> {
+    /// Erase the type of this data pointer, such that the channel can be destroyed via [ErasedDestroy].
+    pub fn erase(self) -> ErasedDataPointer {
+        ErasedDataPointer::new(self)
+    }
+}
+
+impl<Data: 'static> WritableDataPointer<Data> {
+    /// Erase the type of this data pointer, such that the channel can be destroyed via [ErasedDestroy].
+    pub fn erase(self) -> ErasedDataPointer {
+        ErasedDataPointer::new(self)
     }
 }
 
@@ -213,22 +1146,119 @@ unsafe impl<Data> Sync for ReadOnlyDataPointer<Data> {}
 unsafe impl<Data> Sync for WritableDataPointer<Data> {}
 
 /// Object-safe trait for [`DirectedChannelPointer`]s.
-pub trait IDirectedChannel: Send + Sync {
+pub trait IDirectedChannel: SwapChannel {
     /// Perform the [`DirectedChannelPointer::flush`] operation.
     fn flush(&mut self, channel_key: &ChannelKey);
 }
 
+impl<Data: Clone> SwapChannel for DirectedChannelPointer<Data> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        DirectedChannelPointer::flush(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        DirectedChannelPointer::label(self)
+    }
+
+    fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel.generation)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<Data>())
+    }
+
+    fn dirty(&self, channel_key: &ChannelKey) -> Option<bool> {
+        Some(DirectedChannelPointer::is_dirty(self, channel_key))
+    }
+}
+
+impl<Data: Clone + Send + 'static> ErasedDestroy for DirectedChannelPointer<Data> {
+    /// Expects exactly one [WritableDataPointer] and any number of [ReadOnlyDataPointer]s in any order,
+    /// and returns the read-only and the writable `Data` like [DirectedChannel::destroy].
+    fn destroy_erased(
+        mut self: Box<Self>,
+        pointers: Vec<ErasedDataPointer>,
+    ) -> Result<Vec<Box<dyn Any + Send>>, DestroyError> {
+        let channel_read_only_data_pointer = (&self.channel.read_only) as *const Data;
+        let channel_writable_data_pointer = (&mut self.channel.writable) as *mut Data;
+        let mut read_only_data_pointers = Vec::new();
+        let mut writable_data_pointers = Vec::new();
+        for (index, pointer) in pointers.into_iter().enumerate() {
+            let is_foreign = match pointer.downcast::<ReadOnlyDataPointer<Data>>() {
+                Ok(read_only_data_pointer) => {
+                    read_only_data_pointers.push(read_only_data_pointer);
+                    read_only_data_pointer.data != channel_read_only_data_pointer
+                }
+                Err(pointer) => match pointer.downcast::<WritableDataPointer<Data>>() {
+                    Ok(writable_data_pointer) => {
+                        let is_foreign =
+                            writable_data_pointer.data != channel_writable_data_pointer;
+                        writable_data_pointers.push(writable_data_pointer);
+                        is_foreign
+                    }
+                    Err(pointer) => {
+                        // Report the writable data pointer as expected as long as it is missing.
+                        let expected = if writable_data_pointers.is_empty() {
+                            any::type_name::<WritableDataPointer<Data>>()
+                        } else {
+                            any::type_name::<ReadOnlyDataPointer<Data>>()
+                        };
+                        let error = DestroyError::WrongPointerType {
+                            index,
+                            expected,
+                            found: pointer.type_name(),
+                        };
+                        return Err(erased::leak(self, error));
+                    }
+                },
+            };
+            if is_foreign {
+                return Err(erased::leak(self, DestroyError::ForeignPointer { index }));
+            }
+        }
+        if writable_data_pointers.len() != 1 {
+            let error = DestroyError::WrongPointerCount {
+                expected: 1,
+                found: writable_data_pointers.len(),
+            };
+            return Err(erased::leak(self, error));
+        }
+
+        let writable_data_pointer = writable_data_pointers.pop().unwrap();
+        let (read_only, writable) = self.destroy(read_only_data_pointers, writable_data_pointer);
+        Ok(vec![Box::new(read_only), Box::new(writable)])
+    }
+}
+
 impl<Data: Clone> IDirectedChannel for DirectedChannelPointer<Data> {
     fn flush(&mut self, channel_key: &ChannelKey) {
         DirectedChannelPointer::flush(self, channel_key);
     }
 }
 
+impl<T: IDirectedChannel + ?Sized> IDirectedChannel for Box<T> {
+    fn flush(&mut self, channel_key: &ChannelKey) {
+        T::flush(self, channel_key);
+    }
+}
+
+impl<T: IDirectedChannel + ?Sized> IDirectedChannel for &mut T {
+    fn flush(&mut self, channel_key: &ChannelKey) {
+        T::flush(self, channel_key);
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::{mpsc, Arc};
+
     use crate::{
-        directed::{DirectedChannel, IDirectedChannel},
-        MasterKey,
+        directed::{
+            DirectedChannel, DirectedChannelPointer, IDirectedChannel, ReadOnlyDataPointer,
+            WritableDataPointer,
+        },
+        ChannelKey, MasterKey,
     };
 
     #[test]
@@ -267,4 +1297,443 @@ mod tests {
         assert_eq!(*writable_data_pointer.get(&master_key.get_data_key()), 2);
         DirectedChannel::destroy_single(channel, read_only_data_pointer, writable_data_pointer);
     }
+
+    #[test]
+    fn boxed_trait_objects() {
+        fn flush_all(channels: &mut [impl IDirectedChannel], channel_key: &ChannelKey) {
+            for channel in channels {
+                channel.flush(channel_key);
+            }
+        }
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer1, read_only_data_pointer1, writable_data_pointer1) =
+            DirectedChannel::create(1, 2);
+        let (mut channel_pointer2, read_only_data_pointer2, writable_data_pointer2) =
+            DirectedChannel::create("a", "b");
+
+        let mut channels: Vec<Box<dyn IDirectedChannel + '_>> = vec![
+            Box::new(&mut channel_pointer1),
+            Box::new(&mut channel_pointer2),
+        ];
+        flush_all(&mut channels, &master_key.get_channel_key());
+        drop(channels);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*read_only_data_pointer1.get(&data_key), 2);
+        assert_eq!(*read_only_data_pointer2.get(&data_key), "b");
+        DirectedChannel::destroy_single(
+            channel_pointer1,
+            read_only_data_pointer1,
+            writable_data_pointer1,
+        );
+        DirectedChannel::destroy_single(
+            channel_pointer2,
+            read_only_data_pointer2,
+            writable_data_pointer2,
+        );
+    }
+
+    #[test]
+    fn on_flush() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            DirectedChannel::create(0, 1);
+        let (sender, receiver) = mpsc::channel();
+        channel_pointer.set_on_flush(Box::new(move |flushes| sender.send(flushes).unwrap()));
+
+        channel_pointer.flush(&master_key.get_channel_key());
+        channel_pointer.flush(&master_key.get_channel_key());
+        *writable_data_pointer.get_mut(&master_key.get_data_key()) = 2;
+        channel_pointer.flush(&master_key.get_channel_key());
+
+        channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer);
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn flush_swap_and_clone_from() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            DirectedChannel::create(vec![0], vec![1]);
+
+        channel_pointer.flush_swap(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(*read_only_data_pointer.get(&data_key), [1]);
+        // The writer sees what was previously published.
+        assert_eq!(*writable_data_pointer.get(&data_key), [0]);
+        writable_data_pointer.get_mut(&data_key).push(2);
+
+        channel_pointer.flush_clone_from(&master_key.get_channel_key());
+        channel_pointer.flush_swap(&master_key.get_channel_key());
+        assert_eq!(
+            channel_pointer.stats(&master_key.get_channel_key()).skipped,
+            1
+        );
+        assert_eq!(
+            channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer),
+            (vec![0, 2], vec![0, 2])
+        );
+    }
+
+    #[test]
+    fn copy_and_move_through() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut pointer_a, read_only_a, mut writable_a) = DirectedChannel::create(0, 0);
+        let (mut pointer_b, read_only_b, writable_b) = DirectedChannel::create(0, 0);
+        let (mut pointer_c, read_only_c, writable_c) = DirectedChannel::create(0, 0);
+
+        let mut outputs = Vec::new();
+        for phase in 1..=4 {
+            let data_key = master_key.get_data_key();
+            *writable_a.get_mut(&data_key) = phase;
+            outputs.push(*read_only_c.get(&data_key));
+
+            // Forward from the back of the chain, such that each value moves one stage per phase.
+            let channel_key = data_key.into_channel_key();
+            pointer_c.copy_through(&pointer_b, &channel_key);
+            pointer_c.flush(&channel_key);
+            pointer_b.copy_through(&pointer_a, &channel_key);
+            pointer_b.flush(&channel_key);
+            pointer_a.flush(&channel_key);
+        }
+        assert_eq!(outputs, [0, 0, 0, 1]);
+        assert_eq!(pointer_c.stats(&master_key.get_channel_key()).flushes, 4);
+
+        // Moving needs no `Clone`, and hands the previous destination `Data` back to the source.
+        let (mut pointer_d, read_only_d, writable_d) =
+            DirectedChannel::create(String::from("published"), String::new());
+        let (mut pointer_e, read_only_e, writable_e) =
+            DirectedChannel::create(String::new(), String::from("stale"));
+        pointer_e.move_through(&mut pointer_d, &master_key.get_channel_key());
+        pointer_e.flush_swap(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only_e.get(&data_key), "published");
+        assert_eq!(read_only_d.get(&data_key), "stale");
+
+        pointer_a.destroy_single(read_only_a, writable_a);
+        pointer_b.destroy_single(read_only_b, writable_b);
+        assert_eq!(pointer_c.destroy_single(read_only_c, writable_c), (2, 2));
+        pointer_d.destroy_single(read_only_d, writable_d);
+        pointer_e.destroy_single(read_only_e, writable_e);
+    }
+
+    #[test]
+    fn migrate() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let marker = Arc::new(());
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create((Arc::clone(&marker), 1u8), (Arc::clone(&marker), 2u8));
+        let mut channel_pointer = channel_pointer.with_label("plugin");
+        let (sender, receiver) = mpsc::channel();
+        channel_pointer.set_on_flush(Box::new(move |flushes| sender.send(flushes).unwrap()));
+        channel_pointer.flush(&master_key.get_channel_key());
+
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            channel_pointer.migrate(
+                [read_only_data_pointer, read_only_data_pointer],
+                writable_data_pointer,
+                &master_key.get_channel_key(),
+                |(_, old)| format!("converted {}", old),
+            );
+        // The old `Data` was dropped by the conversion.
+        assert_eq!(Arc::strong_count(&marker), 1);
+        assert_eq!(channel_pointer.label(), Some("plugin"));
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only_data_pointer.get(&data_key), "converted 2");
+        writable_data_pointer.get_mut(&data_key).push('!');
+        channel_pointer.flush(&data_key.into_channel_key());
+        assert_eq!(
+            channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer),
+            ("converted 2!".to_owned(), "converted 2!".to_owned())
+        );
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), [1, 1]);
+    }
+
+    #[test]
+    fn reader_lag() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            DirectedChannel::create(0, 0);
+
+        for _ in 0..3 {
+            *writable_data_pointer.get_mut(&master_key.get_data_key()) += 1;
+            channel_pointer.flush(&master_key.get_channel_key());
+        }
+        // Clean flushes do not publish anything, hence do not increase the lag.
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(channel_pointer.reader_lag(&master_key.get_channel_key()), 3);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(writable_data_pointer.reader_lag(&data_key), 3);
+        read_only_data_pointer.mark_consumed(&data_key);
+        assert_eq!(writable_data_pointer.reader_lag(&data_key), 0);
+
+        *writable_data_pointer.get_mut(&data_key) += 1;
+        channel_pointer.flush(&data_key.into_channel_key());
+        assert_eq!(
+            writable_data_pointer.reader_lag(&master_key.get_data_key()),
+            1
+        );
+        assert_eq!(
+            channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer),
+            (4, 4)
+        );
+    }
+
+    #[test]
+    fn large_payloads_stay_on_the_heap() {
+        const LEN: usize = 8 << 20;
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            DirectedChannel::<[u8; LEN]>::create_boxed_zeroed();
+        writable_data_pointer.get_mut(&master_key.get_data_key())[LEN - 1] = 1;
+        assert_eq!(
+            read_only_data_pointer.get(&master_key.get_data_key())[LEN - 1],
+            0
+        );
+
+        let (read_only, writable) = DirectedChannel::destroy_boxed(
+            channel_pointer,
+            [read_only_data_pointer],
+            writable_data_pointer,
+        );
+        assert_eq!((read_only[LEN - 1], writable[LEN - 1]), (0, 1));
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+
+        let raw = (
+            channel_pointer.into_raw(),
+            read_only.into_raw(),
+            writable.into_raw(),
+        );
+        let (mut channel_pointer, read_only, mut writable) = unsafe {
+            (
+                DirectedChannelPointer::<i32>::from_raw(raw.0),
+                ReadOnlyDataPointer::<i32>::from_raw(raw.1),
+                WritableDataPointer::<i32>::from_raw(raw.2),
+            )
+        };
+
+        *writable.get_mut(&master_key.get_data_key()) = 1;
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*read_only.get(&master_key.get_data_key()), 1);
+        assert_eq!(channel_pointer.destroy_single(read_only, writable), (1, 1));
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn byte_views() {
+        use core::mem;
+
+        #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+        #[repr(C)]
+        struct Vertex {
+            position: [f32; 3],
+            color: [u8; 4],
+        }
+
+        let vertex = Vertex {
+            position: [1.0, 2.0, 3.0],
+            color: [4, 5, 6, 7],
+        };
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            DirectedChannel::<Vertex>::create_equal(bytemuck::Zeroable::zeroed());
+
+        let data_key = master_key.get_data_key();
+        writable_data_pointer
+            .as_bytes_mut(&data_key)
+            .copy_from_slice(bytemuck::bytes_of(&vertex));
+        let bytes = read_only_data_pointer.as_bytes(&data_key);
+        assert_eq!(bytes.len(), mem::size_of::<Vertex>());
+        assert_eq!(bytes.as_ptr() as usize % mem::align_of::<Vertex>(), 0);
+        assert_eq!(bytes, [0; 16]);
+
+        channel_pointer.flush_copy(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(
+            read_only_data_pointer.as_bytes(&data_key),
+            bytemuck::bytes_of(&vertex)
+        );
+        assert_eq!(*read_only_data_pointer.get(&data_key), vertex);
+        channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer);
+
+        // Zero-sized `Data` has no bytes.
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create((), ());
+        assert!(read_only_data_pointer
+            .as_bytes(&master_key.get_data_key())
+            .is_empty());
+        channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer);
+
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            DirectedChannel::create_equal([vertex; 2]);
+        writable_data_pointer.as_slice_bytes_mut(&master_key.get_data_key())[16..20].fill(0);
+        channel_pointer.flush_copy(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only_data_pointer.as_slice_bytes(&data_key).len(), 32);
+        assert_eq!(read_only_data_pointer.get(&data_key)[1].position[0], 0.0);
+        channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        use bincode::Options;
+
+        use crate::directed::DirectedSnapshot;
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer) =
+            DirectedChannel::create(vec![1], vec![2, 3]);
+
+        let mut json = Vec::new();
+        channel_pointer
+            .serialize_contents(
+                &master_key.get_channel_key(),
+                &mut serde_json::Serializer::new(&mut json),
+            )
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<DirectedSnapshot<Vec<i32>>>(&json).unwrap(),
+            DirectedSnapshot {
+                published: vec![1],
+                pending: vec![2, 3],
+            }
+        );
+        let bincode = bincode::DefaultOptions::new()
+            .serialize(&channel_pointer.snapshot(&master_key.get_channel_key()))
+            .unwrap();
+
+        writable_data_pointer
+            .get_mut(&master_key.get_data_key())
+            .clear();
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert!(read_only_data_pointer
+            .get(&master_key.get_data_key())
+            .is_empty());
+
+        channel_pointer
+            .deserialize_contents(
+                &master_key.get_channel_key(),
+                &mut serde_json::Deserializer::from_slice(&json),
+            )
+            .unwrap();
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only_data_pointer.get(&data_key), &[1]);
+        assert_eq!(writable_data_pointer.get(&data_key), &[2, 3]);
+
+        channel_pointer.flush(&master_key.get_channel_key());
+        channel_pointer
+            .deserialize_contents(
+                &master_key.get_channel_key(),
+                &mut bincode::Deserializer::from_slice(&bincode, bincode::DefaultOptions::new()),
+            )
+            .unwrap();
+        assert_eq!(
+            channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer),
+            (vec![1], vec![2, 3])
+        );
+    }
+
+    /// A secret that records the values it zeroized.
+    #[cfg(feature = "zeroize")]
+    #[derive(Debug, Clone, Default)]
+    struct Secret {
+        value: u32,
+        zeroized: std::sync::Arc<std::sync::Mutex<Vec<u32>>>,
+    }
+
+    #[cfg(feature = "zeroize")]
+    impl zeroize::Zeroize for Secret {
+        fn zeroize(&mut self) {
+            self.zeroized.lock().unwrap().push(self.value);
+            self.value = 0;
+        }
+    }
+
+    /// Create a zeroizing channel publishing 1 and writing 0, which records the zeroized values in the returned vector.
+    #[cfg(feature = "zeroize")]
+    #[allow(clippy::type_complexity)]
+    fn create_secret_channel() -> (
+        DirectedChannelPointer<Secret>,
+        ReadOnlyDataPointer<Secret>,
+        WritableDataPointer<Secret>,
+        std::sync::Arc<std::sync::Mutex<Vec<u32>>>,
+    ) {
+        let zeroized = std::sync::Arc::default();
+        let secret = |value| Secret {
+            value,
+            zeroized: std::sync::Arc::clone(&zeroized),
+        };
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create_zeroizing(secret(1), secret(0));
+        (
+            channel_pointer,
+            read_only_data_pointer,
+            writable_data_pointer,
+            zeroized,
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn zeroizing_flushes() {
+        use crate::directed::{CloneFlush, CloneFromFlush, FlushStrategy, SwapFlush, TakeFlush};
+
+        /// Publish 2 over the published 1 with the given strategy, returning the published, the written and the zeroized values.
+        fn flush(strategy: &impl FlushStrategy<Secret>) -> (u32, u32, Vec<u32>) {
+            let mut master_key = unsafe { MasterKey::create_unlimited() };
+            let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer, zeroized) =
+                create_secret_channel();
+            writable_data_pointer
+                .get_mut(&master_key.get_data_key())
+                .value = 2;
+            // End the data phase, since the strategy is applied without a channel key.
+            let _ = master_key.get_channel_key();
+            channel_pointer.flush_with_if_dirty(strategy);
+            let zeroized = zeroized.lock().unwrap().clone();
+            let (published, written) =
+                channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer);
+            (published.value, written.value, zeroized)
+        }
+
+        // Only the displaced published value is zeroized, never the newly published or the written one.
+        assert_eq!(flush(&CloneFlush), (2, 2, vec![1]));
+        assert_eq!(flush(&CloneFromFlush), (2, 2, vec![1]));
+        assert_eq!(flush(&TakeFlush), (2, 0, vec![1]));
+        // The writer gets the zeroized previously published value.
+        assert_eq!(flush(&SwapFlush), (2, 0, vec![1]));
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn zeroizing_reset_and_destroy() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only_data_pointer, mut writable_data_pointer, zeroized) =
+            create_secret_channel();
+        let data_key = master_key.get_data_key();
+        let replacement = writable_data_pointer.get(&data_key).clone();
+        writable_data_pointer.get_mut(&data_key).value = 2;
+        drop(data_key);
+        channel_pointer
+            .channel
+            .reset(replacement.clone(), replacement);
+        assert_eq!(*zeroized.lock().unwrap(), [1, 2]);
+
+        // Destroying returns the live values intact, and wipes only the memory of the channel.
+        writable_data_pointer
+            .get_mut(&master_key.get_data_key())
+            .value = 3;
+        let (published, written) =
+            channel_pointer.destroy_single(read_only_data_pointer, writable_data_pointer);
+        assert_eq!((published.value, written.value), (0, 3));
+        assert_eq!(*zeroized.lock().unwrap(), [1, 2]);
+    }
 }
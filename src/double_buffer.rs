@@ -0,0 +1,182 @@
+//! A classic double buffer built on top of the [undirected channel](crate::undirected).
+//! The front buffer can only be read, the back buffer can be read and written,
+//! and presenting swaps the two buffers.
+
+use core::marker::PhantomData;
+
+use crate::{
+    undirected::{
+        ImmutableUndirectedDataPointer, Side, UndirectedChannel, UndirectedChannelPointer,
+        UndirectedDataPointer,
+    },
+    ChannelKey, DataKey,
+};
+
+/// A double buffer used for communication between threads.
+/// This is an [`UndirectedChannel`] where one of the data pointers can only read.
+///
+/// See [DoubleBuffer::create] for more info.
+#[derive(Debug)]
+pub struct DoubleBuffer<Data> {
+    phantom: PhantomData<Data>,
+}
+
+/// The pointer used to present a double buffer, i.e. to swap its front and back buffers.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [DoubleBuffer::destroy] or [Presenter::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct Presenter<Data> {
+    channel_pointer: UndirectedChannelPointer<Data>,
+}
+
+/// A read-only pointer to the front buffer of a double buffer.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [DoubleBuffer::destroy] or [Presenter::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct FrontHandle<Data> {
+    data_pointer: ImmutableUndirectedDataPointer<Data>,
+}
+
+/// A pointer to the back buffer of a double buffer.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [DoubleBuffer::destroy] or [Presenter::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BackHandle<Data> {
+    data_pointer: UndirectedDataPointer<Data>,
+}
+
+impl<Data> DoubleBuffer<Data> {
+    /// Create a double buffer and hand out three pointers to it.
+    /// One [Presenter] used to swap the front and back buffers,
+    /// one [FrontHandle] used to read the front buffer, and
+    /// one [BackHandle] used to read and write the back buffer.
+    pub fn create(
+        front: Data,
+        back: Data,
+    ) -> (Presenter<Data>, FrontHandle<Data>, BackHandle<Data>) {
+        let (channel_pointer, front_data_pointer, back_data_pointer) =
+            UndirectedChannel::create(front, back);
+        (
+            Presenter { channel_pointer },
+            FrontHandle {
+                data_pointer: front_data_pointer.into_immutable(),
+            },
+            BackHandle {
+                data_pointer: back_data_pointer,
+            },
+        )
+    }
+
+    /// Destroys the double buffer linked with the three pointers (see [DoubleBuffer::create]).
+    /// Returns the front and the back buffer, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same double buffer.
+    pub fn destroy(
+        presenter: Presenter<Data>,
+        front: FrontHandle<Data>,
+        back: BackHandle<Data>,
+    ) -> (Data, Data) {
+        UndirectedChannel::destroy_immutable(
+            presenter.channel_pointer,
+            back.data_pointer,
+            [front.data_pointer],
+        )
+    }
+}
+
+impl<Data> Presenter<Data> {
+    /// Swap the front and back buffers.
+    pub fn present(&mut self, channel_key: &ChannelKey) {
+        self.channel_pointer.swap(channel_key);
+    }
+
+    /// Call the given function with the front and the back buffer, in this order, without cloning them.
+    /// This allows to read the buffers during the channel phase, e.g. on behalf of a thread that does not participate in the phases.
+    pub fn inspect<R>(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        f: impl FnOnce(&Data, &Data) -> R,
+    ) -> R {
+        // The front handle points to the first `Data` field, and swapping moves the buffers between the fields.
+        let channel = &self.channel_pointer.channel;
+        f(&channel.data1.0, &channel.data2.0)
+    }
+
+    /// Replace the back buffer with the given buffer, and return the previous back buffer.
+    /// The [BackHandle] sees the new buffer in the next data phase, and the next present moves it to the front.
+    pub fn replace_back(&mut self, channel_key: &ChannelKey, back: Data) -> Data {
+        self.channel_pointer
+            .replace_side(channel_key, Side::Second, back)
+    }
+
+    /// Shorthand for [DoubleBuffer::destroy].
+    pub fn destroy(self, front: FrontHandle<Data>, back: BackHandle<Data>) -> (Data, Data) {
+        DoubleBuffer::destroy(self, front, back)
+    }
+}
+
+impl<Data> FrontHandle<Data> {
+    /// Get a reference to the front buffer.
+    pub fn get(&self, data_key: &DataKey) -> &Data {
+        self.data_pointer.get(data_key)
+    }
+}
+
+impl<Data> BackHandle<Data> {
+    /// Get a reference to the back buffer.
+    pub fn get(&self, data_key: &DataKey) -> &Data {
+        self.data_pointer.get(data_key)
+    }
+
+    /// Get a mutable reference to the back buffer.
+    pub fn get_mut(&mut self, data_key: &DataKey) -> &mut Data {
+        self.data_pointer.get_mut(data_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{double_buffer::DoubleBuffer, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut presenter, front, mut back) = DoubleBuffer::create(0, 0);
+
+        for i in 1..4 {
+            let data_key = master_key.get_data_key();
+            assert_eq!(*front.get(&data_key), i - 1);
+            *back.get_mut(&data_key) = i;
+
+            presenter.present(&data_key.into_channel_key());
+        }
+
+        assert_eq!(presenter.destroy(front, back), (3, 2));
+    }
+
+    #[test]
+    fn channel_phase_access() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut presenter, front, mut back) = DoubleBuffer::create(0, 0);
+
+        *back.get_mut(&master_key.get_data_key()) = 1;
+        let channel_key = master_key.get_channel_key();
+        presenter.present(&channel_key);
+        assert_eq!(
+            presenter.inspect(&channel_key, |front, back| (*front, *back)),
+            (1, 0)
+        );
+        assert_eq!(presenter.replace_back(&channel_key, 2), 0);
+
+        let data_key = channel_key.into_data_key();
+        assert_eq!((*front.get(&data_key), *back.get(&data_key)), (1, 2));
+        presenter.present(&data_key.into_channel_key());
+        assert_eq!(presenter.destroy(front, back), (2, 1));
+    }
+}
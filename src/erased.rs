@@ -0,0 +1,270 @@
+//! Type-erased destruction of channels, e.g. of channels stored as trait objects in a [ChannelGroup](crate::group::ChannelGroup).
+//! Each data pointer can be converted into an [ErasedDataPointer] via its `erase` method,
+//! and the channel pointer is then destroyed via [ErasedDestroy::destroy_erased] with runtime checks instead of compile-time types.
+
+use core::{fmt, mem};
+use std::any::{self, Any};
+
+use crate::SwapChannel;
+
+/// A data pointer of any kind of channel, with its type erased.
+/// It is created via the `erase` method of the data pointer, e.g. [UndirectedDataPointer::erase](crate::undirected::UndirectedDataPointer::erase).
+#[derive(Debug)]
+#[must_use]
+pub struct ErasedDataPointer {
+    pointer: Box<dyn Any + Send>,
+    type_name: &'static str,
+}
+
+/// The reason why [ErasedDestroy::destroy_erased] rejected the given data pointers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestroyError {
+    /// The wrong number of data pointers was given,
+    /// i.e. not two for an undirected or bidirected channel, or not exactly one writable data pointer for a directed channel.
+    WrongPointerCount {
+        /// The number of data pointers the channel requires.
+        expected: usize,
+        /// The number of data pointers that were given.
+        found: usize,
+    },
+    /// The data pointer at the given index has a type that does not belong to the channel.
+    WrongPointerType {
+        /// The index of the data pointer.
+        index: usize,
+        /// The type of data pointer the channel requires.
+        expected: &'static str,
+        /// The type of the data pointer that was given.
+        found: &'static str,
+    },
+    /// The data pointer at the given index has the right type, but points to a different channel.
+    ForeignPointer {
+        /// The index of the data pointer.
+        index: usize,
+    },
+    /// The channel of a [ChannelGroup](crate::group::ChannelGroup) was not added as a `Box<dyn ErasedDestroy>`.
+    NotErased,
+    /// The channel of a [ChannelGroup](crate::group::ChannelGroup) was already removed.
+    UnknownChannel,
+}
+
+/// Object-safe destruction of a channel pointer from type-erased data pointers.
+pub trait ErasedDestroy: SwapChannel {
+    /// Destroy the channel linked with this pointer and the given data pointers,
+    /// returning its `Data` fields in the same order as the typed `destroy` function of the channel.
+    ///
+    /// If the data pointers are rejected, the channel is leaked instead of being dropped,
+    /// since data pointers that were not given may still be in use.
+    fn destroy_erased(
+        self: Box<Self>,
+        pointers: Vec<ErasedDataPointer>,
+    ) -> Result<Vec<Box<dyn Any + Send>>, DestroyError>;
+}
+
+impl ErasedDataPointer {
+    pub(crate) fn new<Pointer: Any + Send>(pointer: Pointer) -> Self {
+        Self {
+            pointer: Box::new(pointer),
+            type_name: any::type_name::<Pointer>(),
+        }
+    }
+
+    /// The type name of the erased data pointer, as given by [`std::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns `true` if the erased data pointer is of type `Pointer`.
+    pub fn is<Pointer: Any>(&self) -> bool {
+        self.pointer.is::<Pointer>()
+    }
+
+    /// Recover the typed data pointer.
+    /// Returns `self` if the erased data pointer is not of type `Pointer`.
+    pub fn downcast<Pointer: Any>(self) -> Result<Pointer, Self> {
+        let Self { pointer, type_name } = self;
+        match pointer.downcast() {
+            Ok(pointer) => Ok(*pointer),
+            Err(pointer) => Err(Self { pointer, type_name }),
+        }
+    }
+
+    /// Like [ErasedDataPointer::downcast], but returns a [DestroyError::WrongPointerType] with the given index.
+    pub(crate) fn downcast_at<Pointer: Any>(self, index: usize) -> Result<Pointer, DestroyError> {
+        self.downcast()
+            .map_err(|pointer| DestroyError::WrongPointerType {
+                index,
+                expected: any::type_name::<Pointer>(),
+                found: pointer.type_name,
+            })
+    }
+}
+
+/// Downcast exactly `N` erased data pointers of the same type.
+pub(crate) fn downcast_all<Pointer: Any, const N: usize>(
+    pointers: Vec<ErasedDataPointer>,
+) -> Result<[Pointer; N], DestroyError> {
+    if pointers.len() != N {
+        return Err(DestroyError::WrongPointerCount {
+            expected: N,
+            found: pointers.len(),
+        });
+    }
+    let pointers: Vec<Pointer> = pointers
+        .into_iter()
+        .enumerate()
+        .map(|(index, pointer)| pointer.downcast_at(index))
+        .collect::<Result<_, _>>()?;
+    match pointers.try_into() {
+        Ok(pointers) => Ok(pointers),
+        Err(_) => unreachable!("the number of pointers was checked"),
+    }
+}
+
+/// Leak the given channel pointer and return the error, see [ErasedDestroy::destroy_erased].
+pub(crate) fn leak<Channel>(channel_pointer: Box<Channel>, error: DestroyError) -> DestroyError {
+    mem::forget(channel_pointer);
+    error
+}
+
+impl fmt::Display for DestroyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DestroyError::WrongPointerCount { expected, found } => write!(
+                f,
+                "expected {} data pointers, but found {}",
+                expected, found
+            ),
+            DestroyError::WrongPointerType {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected data pointer {} to be a {}, but found a {}",
+                index, expected, found
+            ),
+            DestroyError::ForeignPointer { index } => {
+                write!(f, "data pointer {} points to a different channel", index)
+            }
+            DestroyError::NotErased => write!(f, "the channel was not added as an erased channel"),
+            DestroyError::UnknownChannel => write!(f, "the channel was already removed"),
+        }
+    }
+}
+
+impl std::error::Error for DestroyError {}
+
+impl<T: ErasedDestroy + ?Sized> ErasedDestroy for Box<T> {
+    fn destroy_erased(
+        self: Box<Self>,
+        pointers: Vec<ErasedDataPointer>,
+    ) -> Result<Vec<Box<dyn Any + Send>>, DestroyError> {
+        T::destroy_erased(*self, pointers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::{
+        bidirected::BidirectedChannel,
+        directed::DirectedChannel,
+        erased::{DestroyError, ErasedDestroy},
+        undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (undirected, data_pointer1, mut data_pointer2) = UndirectedChannel::create(1u32, 2);
+        let (directed, read_only, mut writable) = DirectedChannel::create(3u8, 4);
+        let (bidirected, bidirected1, bidirected2) =
+            BidirectedChannel::create(5u16, 6, String::from("a"), String::from("b"));
+        *data_pointer2.get_mut(&master_key.get_data_key()) = 7;
+        *writable.get_mut(&master_key.get_data_key()) = 8;
+
+        let channels: Vec<Box<dyn ErasedDestroy>> = vec![
+            Box::new(undirected),
+            Box::new(directed),
+            Box::new(bidirected),
+        ];
+        let pointers = vec![
+            vec![data_pointer2.erase(), data_pointer1.erase()],
+            vec![writable.erase(), read_only.erase(), read_only.erase()],
+            vec![bidirected1.erase(), bidirected2.erase()],
+        ];
+        let values: Vec<Vec<Box<dyn Any + Send>>> = channels
+            .into_iter()
+            .zip(pointers)
+            .map(|(channel, pointers)| channel.destroy_erased(pointers).unwrap())
+            .collect();
+
+        assert_eq!(values[0][0].downcast_ref(), Some(&1u32));
+        assert_eq!(values[0][1].downcast_ref(), Some(&7u32));
+        assert_eq!(values[1][0].downcast_ref(), Some(&3u8));
+        assert_eq!(values[1][1].downcast_ref(), Some(&8u8));
+        assert_eq!(values[2][1].downcast_ref(), Some(&6u16));
+        assert_eq!(values[2][3].downcast_ref(), Some(&String::from("b")));
+    }
+
+    #[test]
+    fn rejects_wrong_pointers() {
+        let (undirected, data_pointer1, data_pointer2) = UndirectedChannel::create(1u32, 2);
+        let (other, other1, other2) = UndirectedChannel::create(1u32, 2);
+        let (directed, read_only, writable) = DirectedChannel::create(3u8, 4);
+
+        assert_eq!(
+            Box::new(undirected)
+                .destroy_erased(vec![data_pointer1.erase()])
+                .err(),
+            Some(DestroyError::WrongPointerCount {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert!(matches!(
+            Box::new(other)
+                .destroy_erased(vec![other1.erase(), read_only.erase()])
+                .err(),
+            Some(DestroyError::WrongPointerType { index: 1, .. })
+        ));
+        assert_eq!(
+            Box::new(directed)
+                .destroy_erased(vec![data_pointer2.erase(), writable.erase()])
+                .err(),
+            Some(DestroyError::WrongPointerType {
+                index: 0,
+                expected: "two_phase_channel::directed::WritableDataPointer<u8>",
+                found: "two_phase_channel::undirected::UndirectedDataPointer<u32>",
+            })
+        );
+        let _ = other2;
+    }
+
+    #[test]
+    fn rejects_foreign_pointers() {
+        let (directed, read_only, writable) = DirectedChannel::create(1u8, 2);
+        let (_other, other_read_only, _other_writable) = DirectedChannel::create(1u8, 2);
+        let (bidirected, bidirected1, _) = BidirectedChannel::create(1u8, 2, 3u8, 4);
+        let (_other, _, other2) = BidirectedChannel::create(1u8, 2, 3u8, 4);
+
+        assert_eq!(
+            Box::new(directed)
+                .destroy_erased(vec![
+                    read_only.erase(),
+                    other_read_only.erase(),
+                    writable.erase()
+                ])
+                .err(),
+            Some(DestroyError::ForeignPointer { index: 1 })
+        );
+        assert_eq!(
+            Box::new(bidirected)
+                .destroy_erased(vec![bidirected1.erase(), other2.erase()])
+                .err(),
+            Some(DestroyError::ForeignPointer { index: 1 })
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Fault injection for testing how an application recovers from misbehaving channels, i.e. the `fault-injection` feature.
+//!
+//! A [FaultPlan] decides for each flush, swap or phase it is consulted for whether it panics, is silently skipped, or is delayed.
+//! It is attached to directed, bidirected and undirected channels via the `set_fault_plan` method of their channel pointers,
+//! and to the phases of a [PhaseRunner](crate::runner::PhaseRunner) via [PhaseRunner::set_fault_plan](crate::runner::PhaseRunner::set_fault_plan).
+//! A plan can be shared by many channels, and counts the operations per [FaultSite] across all of them.
+//!
+//! Scripted faults hit a given operation, e.g. the third flush, which makes failures reproducible.
+//! Random faults hit operations with a given probability, drawn from a generator seeded via [FaultPlan::with_seed],
+//! such that they are reproducible as long as the plan is consulted in the same order.
+//! Every injected fault is recorded, such that tests can assert against it (see [FaultPlan::injected]).
+//!
+//! Without the feature, this module does not exist and channels do not consult any plan.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use two_phase_channel::{
+//!     directed::DirectedChannel,
+//!     fault::{Fault, FaultPlan, FaultSite},
+//!     MasterKey,
+//! };
+//!
+//! let plan = Arc::new(FaultPlan::new().at(FaultSite::Flush, 0, Fault::Skip));
+//! let mut master_key = MasterKey::create();
+//! let (mut channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+//! channel_pointer.set_fault_plan(plan.clone());
+//!
+//! *writable.get_mut(&master_key.get_data_key()) = 1;
+//! channel_pointer.flush(&master_key.get_channel_key());
+//! assert_eq!(*read_only.get(&master_key.get_data_key()), 0);
+//! channel_pointer.flush(&master_key.get_channel_key());
+//! assert_eq!(*read_only.get(&master_key.get_data_key()), 1);
+//! assert_eq!(plan.injected()[0].fault, Fault::Skip);
+//! # channel_pointer.destroy_single(read_only, writable);
+//! ```
+
+use core::fmt;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+/// The kind of operation a [FaultPlan] is consulted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FaultSite {
+    /// A flush of a directed channel, or of one direction of a bidirected channel.
+    Flush,
+    /// A swap of an undirected channel.
+    Swap,
+    /// The channel phase of a [PhaseRunner](crate::runner::PhaseRunner).
+    Phase,
+}
+
+/// What happens to an operation hit by a fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// The operation panics before it changes the channel.
+    Panic,
+    /// The operation silently does nothing, e.g. a flush leaves the channel dirty, and a phase does not call the coordinator.
+    Skip,
+    /// The operation is performed after sleeping for the given duration.
+    Delay(Duration),
+}
+
+/// A fault that was injected by a [FaultPlan].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectedFault {
+    /// The kind of the operation.
+    pub site: FaultSite,
+    /// The index of the operation among all operations of its kind the plan was consulted for, starting at zero.
+    pub operation: u64,
+    /// The label of the channel at the time the plan was attached to it, if any.
+    pub label: Option<String>,
+    /// The injected fault.
+    pub fault: Fault,
+}
+
+/// Decides which channel operations are hit by faults, see the [module documentation](self).
+/// It is thread-safe, such that it can be shared by channels advanced on different threads.
+#[derive(Debug, Default)]
+pub struct FaultPlan {
+    state: Mutex<PlanState>,
+}
+
+#[derive(Debug, Default)]
+struct PlanState {
+    /// The scripted faults by site and operation index.
+    scripted: BTreeMap<(FaultSite, u64), Fault>,
+    /// The random faults, each with the probability to hit an operation of its site.
+    rates: Vec<(FaultSite, f64, Fault)>,
+    /// The state of the random number generator.
+    random: u64,
+    /// The number of operations per site the plan was consulted for.
+    operations: BTreeMap<FaultSite, u64>,
+    injected: Vec<InjectedFault>,
+}
+
+/// A fault plan attached to a channel or runner, together with the label recorded with its faults.
+#[derive(Debug, Clone)]
+pub(crate) struct FaultHook {
+    plan: Arc<FaultPlan>,
+    label: Option<String>,
+}
+
+impl FaultPlan {
+    /// Create a plan that injects no faults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inject the given fault into the operation with the given index among all operations of the given site, starting at zero.
+    /// Scripted faults take precedence over random faults.
+    pub fn at(mut self, site: FaultSite, operation: u64, fault: Fault) -> Self {
+        self.state_mut().scripted.insert((site, operation), fault);
+        self
+    }
+
+    /// Inject the given fault into each operation of the given site with the given probability.
+    /// If multiple random faults hit an operation, the one added first is injected.
+    ///
+    /// **Panics** if the probability is not between zero and one.
+    pub fn with_rate(mut self, site: FaultSite, probability: f64, fault: Fault) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "fault probability must be between zero and one"
+        );
+        self.state_mut().rates.push((site, probability, fault));
+        self
+    }
+
+    /// Seed the random number generator that decides which operations are hit by random faults.
+    /// The seed defaults to zero.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.state_mut().random = seed;
+        self
+    }
+
+    /// The faults injected so far, in the order they were injected.
+    pub fn injected(&self) -> Vec<InjectedFault> {
+        self.state().injected.clone()
+    }
+
+    /// The number of operations of the given site the plan was consulted for.
+    pub fn operations(&self, site: FaultSite) -> u64 {
+        self.state().operations.get(&site).copied().unwrap_or(0)
+    }
+
+    /// Decide whether the next operation of the given site is hit by a fault, record the fault, and inject it.
+    /// Returns `false` if the operation is to be skipped.
+    fn inject(&self, site: FaultSite, label: Option<&str>) -> bool {
+        let mut state = self.state();
+        let operations = state.operations.entry(site).or_insert(0);
+        let operation = *operations;
+        *operations += 1;
+
+        let mut fault = state.scripted.get(&(site, operation)).copied();
+        for index in 0..state.rates.len() {
+            let (rate_site, probability, rate_fault) = state.rates[index];
+            if rate_site == site {
+                // Draw for every random fault of the site, such that the sequence does not depend on scripted faults.
+                let hit = (next_random(&mut state.random) >> 11) as f64 / (1u64 << 53) as f64;
+                if fault.is_none() && hit < probability {
+                    fault = Some(rate_fault);
+                }
+            }
+        }
+        let fault = match fault {
+            Some(fault) => fault,
+            None => return true,
+        };
+        state.injected.push(InjectedFault {
+            site,
+            operation,
+            label: label.map(str::to_owned),
+            fault,
+        });
+        // Release the lock before panicking, such that the plan stays usable.
+        drop(state);
+
+        match fault {
+            Fault::Panic => match label {
+                Some(label) => panic!(
+                    "injected fault: {} {} of channel {:?} panicked",
+                    site, operation, label
+                ),
+                None => panic!("injected fault: {} {} panicked", site, operation),
+            },
+            Fault::Skip => false,
+            Fault::Delay(delay) => {
+                thread::sleep(delay);
+                true
+            }
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, PlanState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn state_mut(&mut self) -> &mut PlanState {
+        self.state.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl FaultHook {
+    pub(crate) fn new(plan: Arc<FaultPlan>, label: Option<&str>) -> Self {
+        Self {
+            plan,
+            label: label.map(str::to_owned),
+        }
+    }
+}
+
+/// Consult the given fault plan, if any, for the next operation of the given site.
+/// Returns `false` if the operation is to be skipped.
+pub(crate) fn inject(hook: &Option<FaultHook>, site: FaultSite) -> bool {
+    match hook {
+        Some(hook) => hook.plan.inject(site, hook.label.as_deref()),
+        None => true,
+    }
+}
+
+/// The SplitMix64 generator, which is good enough to pick faults.
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl fmt::Display for FaultSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FaultSite::Flush => "flush",
+            FaultSite::Swap => "swap",
+            FaultSite::Phase => "phase",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ops::ControlFlow;
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        fault::{Fault, FaultPlan, FaultSite, InjectedFault},
+        runner::PhaseRunner,
+        undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    #[test]
+    fn scripted() {
+        let plan = Arc::new(
+            FaultPlan::new()
+                .at(FaultSite::Swap, 1, Fault::Skip)
+                .at(FaultSite::Swap, 2, Fault::Panic)
+                .at(FaultSite::Swap, 3, Fault::Delay(Duration::from_millis(20))),
+        );
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        let mut channel_pointer = channel_pointer.with_label("input");
+        channel_pointer.set_fault_plan(plan.clone());
+
+        let channel_key = master_key.get_channel_key();
+        channel_pointer.swap(&channel_key);
+        channel_pointer.swap(&channel_key);
+        assert_eq!(*data_pointer1.get(&master_key.get_data_key()), 2);
+        let channel_key = master_key.get_channel_key();
+        let panic = panic::catch_unwind(AssertUnwindSafe(|| channel_pointer.swap(&channel_key)))
+            .unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().unwrap(),
+            "injected fault: swap 2 of channel \"input\" panicked"
+        );
+        let start = Instant::now();
+        channel_pointer.swap(&channel_key);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        assert_eq!(plan.operations(FaultSite::Swap), 4);
+        let label = Some(String::from("input"));
+        assert_eq!(
+            plan.injected(),
+            [
+                (1, Fault::Skip),
+                (2, Fault::Panic),
+                (3, Fault::Delay(Duration::from_millis(20)))
+            ]
+            .map(|(operation, fault)| InjectedFault {
+                site: FaultSite::Swap,
+                operation,
+                label: label.clone(),
+                fault,
+            })
+        );
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (1, 2)
+        );
+    }
+
+    #[test]
+    fn seeded_phase_faults_are_reproducible() {
+        let run = |seed| {
+            let plan = Arc::new(FaultPlan::new().with_seed(seed).with_rate(
+                FaultSite::Phase,
+                0.3,
+                Fault::Skip,
+            ));
+            let mut runner = PhaseRunner::new(unsafe { MasterKey::create_unlimited() });
+            runner.set_fault_plan(plan.clone());
+            let channel_phases = Arc::new(AtomicUsize::new(0));
+            let coordinator_phases = channel_phases.clone();
+            runner.set_coordinator(move |_| {
+                coordinator_phases.fetch_add(1, Ordering::Relaxed);
+                ControlFlow::Continue(())
+            });
+            assert_eq!(runner.run_for(50).unwrap(), 50);
+            // Skipped phases do not call the coordinator.
+            let injected = plan.injected();
+            assert_eq!(channel_phases.load(Ordering::Relaxed), 50 - injected.len());
+            injected
+        };
+        let injected = run(7);
+        assert!(!injected.is_empty());
+        assert_eq!(run(7), injected);
+        assert_ne!(run(8), injected);
+    }
+}
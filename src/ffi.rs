@@ -0,0 +1,525 @@
+//! A C interface to the [undirected](crate::undirected) and [directed](crate::directed) channels.
+//! The transmitted data is a zero-initialised byte buffer of a size chosen at creation, aligned to 16 bytes.
+//! All channel pointers, data pointers and the master key are handed out as opaque handles,
+//! and all functions report errors via [ScStatus] instead of unwinding into the caller.
+//!
+//! The matching C declarations are found in `include/two_phase_channel.h`.
+
+use core::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{
+    directed::{DirectedChannel, DirectedChannelPointer, ReadOnlyDataPointer, WritableDataPointer},
+    undirected::{UndirectedChannel, UndirectedChannelPointer, UndirectedDataPointer},
+    MasterKey,
+};
+
+/// The unit of the byte buffers, used to align them to 16 bytes.
+#[derive(Debug, Clone, Copy)]
+#[repr(C, align(16))]
+struct Chunk([u8; 16]);
+
+type Buffer = Box<[Chunk]>;
+
+fn buffer(size: usize) -> Buffer {
+    let chunks = (size + core::mem::size_of::<Chunk>() - 1) / core::mem::size_of::<Chunk>();
+    vec![Chunk([0; 16]); chunks].into_boxed_slice()
+}
+
+/// The result of a function of the C interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub enum ScStatus {
+    /// The function completed successfully.
+    Ok = 0,
+    /// One of the given pointers was null.
+    NullPointer = 1,
+    /// A master key was requested while another master key exists.
+    MasterKeyExists = 2,
+    /// The function panicked, e.g. because the handles given to a destroy function do not belong to the same channel.
+    Panic = 3,
+}
+
+/// An opaque handle to a [MasterKey].
+#[repr(C)]
+pub struct ScMasterKey {
+    _private: [u8; 0],
+}
+
+/// An opaque handle to an [UndirectedChannelPointer] over a byte buffer.
+#[repr(C)]
+pub struct ScUndirectedChannel {
+    _private: [u8; 0],
+}
+
+/// An opaque handle to an [UndirectedDataPointer] over a byte buffer.
+#[repr(C)]
+pub struct ScUndirectedData {
+    _private: [u8; 0],
+}
+
+/// An opaque handle to a [DirectedChannelPointer] over a byte buffer.
+#[repr(C)]
+pub struct ScDirectedChannel {
+    _private: [u8; 0],
+}
+
+/// An opaque handle to a [ReadOnlyDataPointer] over a byte buffer.
+#[repr(C)]
+pub struct ScReadOnlyData {
+    _private: [u8; 0],
+}
+
+/// An opaque handle to a [WritableDataPointer] over a byte buffer.
+#[repr(C)]
+pub struct ScWritableData {
+    _private: [u8; 0],
+}
+
+/// Run `f`, converting a panic into [ScStatus::Panic].
+fn guard(f: impl FnOnce() -> ScStatus) -> ScStatus {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(ScStatus::Panic)
+}
+
+/// Reinterpret a handle handed out by this module as a mutable reference to the value it owns.
+unsafe fn handle<'a, Handle, Value>(handle: *mut Handle) -> Option<&'a mut Value> {
+    (handle as *mut Value).as_mut()
+}
+
+/// Create the master key and store its handle in `out_key`.
+///
+/// Returns [ScStatus::MasterKeyExists] if there already is a master key.
+///
+/// # Safety
+///
+/// `out_key` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sc_master_key_create(out_key: *mut *mut ScMasterKey) -> ScStatus {
+    guard(|| {
+        if out_key.is_null() {
+            return ScStatus::NullPointer;
+        }
+        match MasterKey::try_create() {
+            Some(master_key) => {
+                *out_key = Box::into_raw(Box::new(master_key)) as *mut ScMasterKey;
+                ScStatus::Ok
+            }
+            None => ScStatus::MasterKeyExists,
+        }
+    })
+}
+
+/// Destroy the master key, such that a new one can be created.
+///
+/// # Safety
+///
+/// `key` must be null or a handle returned by [sc_master_key_create] that was not destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sc_master_key_destroy(key: *mut ScMasterKey) -> ScStatus {
+    guard(|| {
+        if key.is_null() {
+            return ScStatus::NullPointer;
+        }
+        drop(Box::from_raw(key as *mut MasterKey));
+        ScStatus::Ok
+    })
+}
+
+/// Create an undirected channel over two zero-initialised buffers of `size` bytes each,
+/// and store the handles to its channel pointer and its two data pointers in the given locations.
+///
+/// # Safety
+///
+/// The out pointers must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sc_undirected_create(
+    size: usize,
+    out_channel: *mut *mut ScUndirectedChannel,
+    out_data1: *mut *mut ScUndirectedData,
+    out_data2: *mut *mut ScUndirectedData,
+) -> ScStatus {
+    guard(|| {
+        if out_channel.is_null() || out_data1.is_null() || out_data2.is_null() {
+            return ScStatus::NullPointer;
+        }
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            UndirectedChannel::create(buffer(size), buffer(size));
+        *out_channel = channel_pointer.into_raw() as *mut ScUndirectedChannel;
+        *out_data1 = data_pointer1.into_raw() as *mut ScUndirectedData;
+        *out_data2 = data_pointer2.into_raw() as *mut ScUndirectedData;
+        ScStatus::Ok
+    })
+}
+
+/// Swap the two buffers of an undirected channel (see [UndirectedChannelPointer::swap]).
+///
+/// # Safety
+///
+/// The handles must be null or alive, and no buffer address obtained by [sc_undirected_get] may be used concurrently.
+#[no_mangle]
+pub unsafe extern "C" fn sc_undirected_swap(
+    key: *mut ScMasterKey,
+    channel: *mut ScUndirectedChannel,
+) -> ScStatus {
+    guard(|| {
+        match (
+            handle::<_, MasterKey>(key),
+            handle::<_, UndirectedChannelPointer<Buffer>>(channel),
+        ) {
+            (Some(master_key), Some(channel_pointer)) => {
+                channel_pointer.swap(&master_key.get_channel_key());
+                ScStatus::Ok
+            }
+            _ => ScStatus::NullPointer,
+        }
+    })
+}
+
+/// Store the address of the buffer pointed to by an undirected data pointer in `out_buffer`.
+/// The address is valid for reads and writes until the next swap or the destruction of the channel.
+///
+/// # Safety
+///
+/// The handles must be null or alive, and `out_buffer` must be null or valid for writes.
+/// The buffer may not be accessed concurrently through the same data pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sc_undirected_get(
+    key: *mut ScMasterKey,
+    data: *mut ScUndirectedData,
+    out_buffer: *mut *mut c_void,
+) -> ScStatus {
+    guard(|| {
+        match (
+            handle::<_, MasterKey>(key),
+            handle::<_, UndirectedDataPointer<Buffer>>(data),
+        ) {
+            (Some(master_key), Some(data_pointer)) if !out_buffer.is_null() => {
+                *out_buffer = data_pointer
+                    .get_mut(&master_key.get_data_key())
+                    .as_mut_ptr() as *mut c_void;
+                ScStatus::Ok
+            }
+            _ => ScStatus::NullPointer,
+        }
+    })
+}
+
+/// Destroy an undirected channel (see [UndirectedChannel::destroy]).
+/// The handles are consumed even if the function fails.
+///
+/// Returns [ScStatus::Panic] if the handles do not belong to the same channel.
+///
+/// # Safety
+///
+/// The handles must be null or alive.
+#[no_mangle]
+pub unsafe extern "C" fn sc_undirected_destroy(
+    channel: *mut ScUndirectedChannel,
+    data1: *mut ScUndirectedData,
+    data2: *mut ScUndirectedData,
+) -> ScStatus {
+    guard(|| {
+        if channel.is_null() || data1.is_null() || data2.is_null() {
+            return ScStatus::NullPointer;
+        }
+        UndirectedChannel::destroy(
+            UndirectedChannelPointer::<Buffer>::from_raw(channel as *mut ()),
+            UndirectedDataPointer::from_raw(data1 as *mut ()),
+            UndirectedDataPointer::from_raw(data2 as *mut ()),
+        );
+        ScStatus::Ok
+    })
+}
+
+/// Create a directed channel over two zero-initialised buffers of `size` bytes each,
+/// and store the handles to its channel pointer, its read-only data pointer and its writable data pointer in the given locations.
+///
+/// # Safety
+///
+/// The out pointers must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sc_directed_create(
+    size: usize,
+    out_channel: *mut *mut ScDirectedChannel,
+    out_read_only: *mut *mut ScReadOnlyData,
+    out_writable: *mut *mut ScWritableData,
+) -> ScStatus {
+    guard(|| {
+        if out_channel.is_null() || out_read_only.is_null() || out_writable.is_null() {
+            return ScStatus::NullPointer;
+        }
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create(buffer(size), buffer(size));
+        *out_channel = channel_pointer.into_raw() as *mut ScDirectedChannel;
+        *out_read_only = read_only_data_pointer.into_raw() as *mut ScReadOnlyData;
+        *out_writable = writable_data_pointer.into_raw() as *mut ScWritableData;
+        ScStatus::Ok
+    })
+}
+
+/// Copy the writable buffer of a directed channel into its read-only buffer (see [DirectedChannelPointer::flush]).
+///
+/// # Safety
+///
+/// The handles must be null or alive, and no buffer address obtained by [sc_directed_read] or [sc_directed_write] may be used concurrently.
+#[no_mangle]
+pub unsafe extern "C" fn sc_directed_flush(
+    key: *mut ScMasterKey,
+    channel: *mut ScDirectedChannel,
+) -> ScStatus {
+    guard(|| {
+        match (
+            handle::<_, MasterKey>(key),
+            handle::<_, DirectedChannelPointer<Buffer>>(channel),
+        ) {
+            (Some(master_key), Some(channel_pointer)) => {
+                channel_pointer.flush(&master_key.get_channel_key());
+                ScStatus::Ok
+            }
+            _ => ScStatus::NullPointer,
+        }
+    })
+}
+
+/// Store the address of the read-only buffer of a directed channel in `out_buffer`.
+/// The address is valid for reads until the next flush or the destruction of the channel.
+///
+/// # Safety
+///
+/// The handles must be null or alive, and `out_buffer` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sc_directed_read(
+    key: *mut ScMasterKey,
+    read_only: *mut ScReadOnlyData,
+    out_buffer: *mut *const c_void,
+) -> ScStatus {
+    guard(|| {
+        match (
+            handle::<_, MasterKey>(key),
+            handle::<_, ReadOnlyDataPointer<Buffer>>(read_only),
+        ) {
+            (Some(master_key), Some(data_pointer)) if !out_buffer.is_null() => {
+                *out_buffer =
+                    data_pointer.get(&master_key.get_data_key()).as_ptr() as *const c_void;
+                ScStatus::Ok
+            }
+            _ => ScStatus::NullPointer,
+        }
+    })
+}
+
+/// Store the address of the writable buffer of a directed channel in `out_buffer`.
+/// The address is valid for reads and writes until the destruction of the channel.
+///
+/// # Safety
+///
+/// The handles must be null or alive, and `out_buffer` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sc_directed_write(
+    key: *mut ScMasterKey,
+    writable: *mut ScWritableData,
+    out_buffer: *mut *mut c_void,
+) -> ScStatus {
+    guard(|| {
+        match (
+            handle::<_, MasterKey>(key),
+            handle::<_, WritableDataPointer<Buffer>>(writable),
+        ) {
+            (Some(master_key), Some(data_pointer)) if !out_buffer.is_null() => {
+                *out_buffer = data_pointer
+                    .get_mut(&master_key.get_data_key())
+                    .as_mut_ptr() as *mut c_void;
+                ScStatus::Ok
+            }
+            _ => ScStatus::NullPointer,
+        }
+    })
+}
+
+/// Destroy a directed channel (see [DirectedChannel::destroy_single]).
+/// The handles are consumed even if the function fails.
+///
+/// Returns [ScStatus::Panic] if the handles do not belong to the same channel.
+///
+/// # Safety
+///
+/// The handles must be null or alive.
+#[no_mangle]
+pub unsafe extern "C" fn sc_directed_destroy(
+    channel: *mut ScDirectedChannel,
+    read_only: *mut ScReadOnlyData,
+    writable: *mut ScWritableData,
+) -> ScStatus {
+    guard(|| {
+        if channel.is_null() || read_only.is_null() || writable.is_null() {
+            return ScStatus::NullPointer;
+        }
+        DirectedChannel::destroy_single(
+            DirectedChannelPointer::<Buffer>::from_raw(channel as *mut ()),
+            ReadOnlyDataPointer::from_raw(read_only as *mut ()),
+            WritableDataPointer::from_raw(writable as *mut ()),
+        );
+        ScStatus::Ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ffi::c_void;
+    use std::{
+        ffi::{CStr, CString},
+        os::raw::{c_char, c_int},
+        process::Command,
+    };
+
+    use crate::ffi::*;
+
+    /// The functions of the C interface, passed to the C test program,
+    /// since the test executable does not export them to dynamically loaded libraries.
+    /// Must match `struct sc_api` in `tests/ffi.c`.
+    #[cfg(target_os = "linux")]
+    #[repr(C)]
+    struct Api {
+        master_key_create: unsafe extern "C" fn(*mut *mut ScMasterKey) -> ScStatus,
+        master_key_destroy: unsafe extern "C" fn(*mut ScMasterKey) -> ScStatus,
+        undirected_create: unsafe extern "C" fn(
+            usize,
+            *mut *mut ScUndirectedChannel,
+            *mut *mut ScUndirectedData,
+            *mut *mut ScUndirectedData,
+        ) -> ScStatus,
+        undirected_swap:
+            unsafe extern "C" fn(*mut ScMasterKey, *mut ScUndirectedChannel) -> ScStatus,
+        undirected_get: unsafe extern "C" fn(
+            *mut ScMasterKey,
+            *mut ScUndirectedData,
+            *mut *mut c_void,
+        ) -> ScStatus,
+        undirected_destroy: unsafe extern "C" fn(
+            *mut ScUndirectedChannel,
+            *mut ScUndirectedData,
+            *mut ScUndirectedData,
+        ) -> ScStatus,
+        directed_create: unsafe extern "C" fn(
+            usize,
+            *mut *mut ScDirectedChannel,
+            *mut *mut ScReadOnlyData,
+            *mut *mut ScWritableData,
+        ) -> ScStatus,
+        directed_flush: unsafe extern "C" fn(*mut ScMasterKey, *mut ScDirectedChannel) -> ScStatus,
+        directed_read: unsafe extern "C" fn(
+            *mut ScMasterKey,
+            *mut ScReadOnlyData,
+            *mut *const c_void,
+        ) -> ScStatus,
+        directed_write: unsafe extern "C" fn(
+            *mut ScMasterKey,
+            *mut ScWritableData,
+            *mut *mut c_void,
+        ) -> ScStatus,
+        directed_destroy: unsafe extern "C" fn(
+            *mut ScDirectedChannel,
+            *mut ScReadOnlyData,
+            *mut ScWritableData,
+        ) -> ScStatus,
+    }
+
+    #[cfg(target_os = "linux")]
+    #[link(name = "dl")]
+    extern "C" {
+        fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        fn dlerror() -> *const c_char;
+    }
+
+    #[cfg(target_os = "linux")]
+    const RTLD_NOW: c_int = 2;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn c_program() {
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let library = std::env::temp_dir().join(format!(
+            "two_phase_channel_ffi_test_{}.so",
+            std::process::id()
+        ));
+        let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".into()))
+            .args(["-shared", "-fPIC", "-Wall", "-Werror", "-I"])
+            .arg(format!("{}/include", manifest_dir))
+            .arg(format!("{}/tests/ffi.c", manifest_dir))
+            .arg("-o")
+            .arg(&library)
+            .status()
+            .expect("could not run the C compiler");
+        assert!(status.success());
+
+        let api = Api {
+            master_key_create: sc_master_key_create,
+            master_key_destroy: sc_master_key_destroy,
+            undirected_create: sc_undirected_create,
+            undirected_swap: sc_undirected_swap,
+            undirected_get: sc_undirected_get,
+            undirected_destroy: sc_undirected_destroy,
+            directed_create: sc_directed_create,
+            directed_flush: sc_directed_flush,
+            directed_read: sc_directed_read,
+            directed_write: sc_directed_write,
+            directed_destroy: sc_directed_destroy,
+        };
+
+        let result = unsafe {
+            let path = CString::new(library.to_str().unwrap()).unwrap();
+            let handle = dlopen(path.as_ptr(), RTLD_NOW);
+            assert!(
+                !handle.is_null(),
+                "{}",
+                CStr::from_ptr(dlerror()).to_string_lossy()
+            );
+            let run = dlsym(handle, b"sc_test_run\0".as_ptr() as *const c_char);
+            assert!(!run.is_null());
+            let run: unsafe extern "C" fn(*const Api) -> c_int = core::mem::transmute(run);
+            run(&api)
+        };
+        std::fs::remove_file(&library).ok();
+        assert_eq!(result, 0, "C test failed in line {}", result);
+    }
+
+    #[test]
+    fn null_pointers() {
+        unsafe {
+            assert_eq!(
+                sc_master_key_create(core::ptr::null_mut()),
+                ScStatus::NullPointer
+            );
+            assert_eq!(
+                sc_directed_flush(core::ptr::null_mut(), core::ptr::null_mut()),
+                ScStatus::NullPointer
+            );
+        }
+    }
+
+    #[test]
+    fn destroy_rejects_foreign_handles() {
+        unsafe {
+            let mut channel1 = core::ptr::null_mut();
+            let mut data1 = core::ptr::null_mut();
+            let mut data2 = core::ptr::null_mut();
+            let mut channel2 = core::ptr::null_mut();
+            let mut data3 = core::ptr::null_mut();
+            let mut data4 = core::ptr::null_mut();
+            assert_eq!(
+                sc_undirected_create(8, &mut channel1, &mut data1, &mut data2),
+                ScStatus::Ok
+            );
+            assert_eq!(
+                sc_undirected_create(8, &mut channel2, &mut data3, &mut data4),
+                ScStatus::Ok
+            );
+
+            assert_eq!(
+                sc_undirected_destroy(channel1, data1, data3),
+                ScStatus::Panic
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! A frame buffer for a pair of update and render threads, as in games, built on top of the [directed channel](crate::directed).
+//! The update thread writes the next frame, the render thread reads the last presented frame,
+//! and the main thread presents the written frame once per frame.
+//!
+//! Presenting clones the written frame into the presented frame with [Clone::clone_from], reusing the allocations of the presented frame,
+//! and is skipped if the frame was not written since the last present.
+
+use core::{cell::Cell, marker::PhantomData};
+
+use crate::{
+    directed::{
+        CloneFromFlush, DirectedChannel, DirectedChannelPointer, FlushStats, ReadOnlyDataPointer,
+        WritableDataPointer,
+    },
+    ChannelKey, DataKey, GenerationPointer,
+};
+
+/// A frame buffer used for communication between an update and a render thread.
+///
+/// See [FrameBuffered::create] for more info.
+#[derive(Debug)]
+pub struct FrameBuffered<Frame> {
+    phantom: PhantomData<Frame>,
+}
+
+/// The pointer used to write the next frame.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [FrameBuffered::destroy] or [FramePresenter::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct FrameProducer<Frame> {
+    data_pointer: WritableDataPointer<Frame>,
+}
+
+/// The pointer used to read the last presented frame.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [FrameBuffered::destroy] or [FramePresenter::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct FrameConsumer<Frame> {
+    data_pointer: ReadOnlyDataPointer<Frame>,
+    generation: GenerationPointer,
+    /// The generation of the frame returned by the last read.
+    last_read: Cell<u64>,
+    fresh: Cell<bool>,
+}
+
+/// The pointer used to present the written frame.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [FrameBuffered::destroy] or [FramePresenter::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct FramePresenter<Frame> {
+    channel_pointer: DirectedChannelPointer<Frame>,
+}
+
+impl<Frame: Clone> FrameBuffered<Frame> {
+    /// Create a frame buffer whose written and presented frames are initialised equally from the given frame, and hand out three pointers to it.
+    /// One [FrameProducer] used by the update thread to write the next frame,
+    /// one [FrameConsumer] used by the render thread to read the last presented frame, and
+    /// one [FramePresenter] used by the main thread to present the written frame.
+    pub fn create(
+        frame: Frame,
+    ) -> (
+        FrameProducer<Frame>,
+        FrameConsumer<Frame>,
+        FramePresenter<Frame>,
+    ) {
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create_equal(frame);
+        (
+            FrameProducer {
+                data_pointer: writable_data_pointer,
+            },
+            FrameConsumer {
+                generation: DirectedChannel::generation_of_read_only(read_only_data_pointer.data),
+                data_pointer: read_only_data_pointer,
+                last_read: Cell::new(0),
+                fresh: Cell::new(false),
+            },
+            FramePresenter { channel_pointer },
+        )
+    }
+}
+
+impl<Frame> FrameBuffered<Frame> {
+    /// Destroys the frame buffer linked with the three pointers (see [FrameBuffered::create]).
+    /// Returns the presented and the written frame, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same frame buffer.
+    pub fn destroy(
+        presenter: FramePresenter<Frame>,
+        producer: FrameProducer<Frame>,
+        consumer: FrameConsumer<Frame>,
+    ) -> (Frame, Frame) {
+        DirectedChannel::destroy_single(
+            presenter.channel_pointer,
+            consumer.data_pointer,
+            producer.data_pointer,
+        )
+    }
+}
+
+impl<Frame> FrameProducer<Frame> {
+    /// Call the given function with a mutable reference to the next frame, and return its result.
+    /// The frame is presented by the next [FramePresenter::present].
+    pub fn write<R>(&mut self, data_key: &DataKey, f: impl FnOnce(&mut Frame) -> R) -> R {
+        self.data_pointer.write(data_key, f)
+    }
+
+    /// Get a reference to the next frame, as written so far.
+    pub fn get(&self, data_key: &DataKey) -> &Frame {
+        self.data_pointer.get(data_key)
+    }
+}
+
+impl<Frame> FrameConsumer<Frame> {
+    /// Get a reference to the last presented frame, and record whether it was presented since the previous read (see [FrameConsumer::is_fresh]).
+    pub fn read(&self, data_key: &DataKey) -> &Frame {
+        let mut last_read = self.last_read.get();
+        self.fresh
+            .set(self.generation.changed_since(&mut last_read));
+        self.last_read.set(last_read);
+        self.data_pointer.get(data_key)
+    }
+
+    /// Returns `true` if the frame returned by the last [FrameConsumer::read] was presented since the read before it,
+    /// e.g. to skip rendering an unchanged frame.
+    /// Before the first present, no frame is fresh.
+    pub fn is_fresh(&self) -> bool {
+        self.fresh.get()
+    }
+}
+
+impl<Frame: Clone> FramePresenter<Frame> {
+    /// Present the frame written since the last present, such that the following reads return it.
+    /// Returns `false` if the frame was not written since the last present, in which case presenting is skipped.
+    pub fn present(&mut self, #[allow(unused)] channel_key: &ChannelKey) -> bool {
+        self.channel_pointer.flush_with_if_dirty(&CloneFromFlush)
+    }
+}
+
+impl<Frame> FramePresenter<Frame> {
+    /// Statistics about the presents, where a flush is a present and a skipped flush is a skipped present.
+    pub fn stats(&self, channel_key: &ChannelKey) -> FlushStats {
+        self.channel_pointer.stats(channel_key)
+    }
+
+    /// Call the given function on both the presented and the written frame.
+    /// This allows to reserve capacity for growable frames, such that presenting does not allocate.
+    pub fn manage_capacity(&mut self, channel_key: &ChannelKey, f: impl FnMut(&mut Frame)) {
+        self.channel_pointer.manage_capacity(channel_key, f);
+    }
+
+    /// Shorthand for [FrameBuffered::destroy].
+    pub fn destroy(
+        self,
+        producer: FrameProducer<Frame>,
+        consumer: FrameConsumer<Frame>,
+    ) -> (Frame, Frame) {
+        FrameBuffered::destroy(self, producer, consumer)
+    }
+}
+
+unsafe impl<Frame> Send for FrameConsumer<Frame> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{frame::FrameBuffered, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut producer, consumer, mut presenter) = FrameBuffered::create(vec![0]);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*consumer.read(&data_key), [0]);
+        assert!(!consumer.is_fresh());
+        producer.write(&data_key, |frame| frame.push(1));
+        assert_eq!(*consumer.read(&data_key), [0]);
+
+        let channel_key = data_key.into_channel_key();
+        assert!(presenter.present(&channel_key));
+        // Clean frames are not presented again.
+        assert!(!presenter.present(&channel_key));
+
+        let data_key = channel_key.into_data_key();
+        assert_eq!(*consumer.read(&data_key), [0, 1]);
+        assert!(consumer.is_fresh());
+        consumer.read(&data_key);
+        assert!(!consumer.is_fresh());
+        assert_eq!(*producer.get(&data_key), [0, 1]);
+
+        let stats = presenter.stats(&data_key.into_channel_key());
+        assert_eq!((stats.flushes, stats.skipped), (1, 1));
+        assert_eq!(
+            presenter.destroy(producer, consumer),
+            (vec![0, 1], vec![0, 1])
+        );
+    }
+}
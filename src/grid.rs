@@ -0,0 +1,317 @@
+//! A dense grid for a writer that touches a few rows per phase, built on top of the [directed channel](crate::directed).
+//! The writer marks each row it accesses mutably as dirty, and flushing clones only the dirty rows into the read grid,
+//! instead of the whole grid as flushing a directed channel of a grid would.
+//!
+//! The reader sees the whole grid as of the last flush, and additionally which rows changed since it last called [GridReader::acknowledge],
+//! e.g. to upload only the changed rows of a texture.
+//! See `cargo bench --bench grid` for a comparison with flushing the whole grid.
+
+use core::marker::PhantomData;
+
+use crate::{
+    directed::{
+        DirectedChannel, DirectedChannelPointer, FlushStats, FlushStrategy, ReadOnlyDataPointer,
+        WritableDataPointer,
+    },
+    ChannelKey, DataKey, GenerationPointer,
+};
+
+/// The rows of a grid, allocated on the heap.
+pub type Rows<T, const ROWS: usize, const COLS: usize> = Box<[[T; COLS]; ROWS]>;
+
+/// A grid channel used for communication between a writer and a reader thread.
+///
+/// See [GridChannel::create] for more info.
+#[derive(Debug)]
+pub struct GridChannel<T, const ROWS: usize, const COLS: usize> {
+    phantom: PhantomData<T>,
+}
+
+/// One side of a grid channel, i.e. the `Data` of the underlying directed channel.
+#[derive(Debug)]
+struct Grid<T, const ROWS: usize, const COLS: usize> {
+    rows: Rows<T, ROWS, COLS>,
+    /// The rows accessed mutably since the last flush, one bit per row. Empty in the read grid.
+    dirty: Box<[u64]>,
+    /// The generation of the flush that last changed each row. Empty in the written grid.
+    generations: Box<[u64]>,
+}
+
+/// Clones the dirty rows of the written grid into the read grid, and records the given generation for them.
+struct RowFlush {
+    generation: u64,
+}
+
+/// The pointer used to flush a grid channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [GridChannel::destroy] or [GridChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct GridChannelPointer<T, const ROWS: usize, const COLS: usize> {
+    channel_pointer: DirectedChannelPointer<Grid<T, ROWS, COLS>>,
+}
+
+/// The pointer used to write the rows of a grid channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [GridChannel::destroy] or [GridChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct GridWriter<T, const ROWS: usize, const COLS: usize> {
+    data_pointer: WritableDataPointer<Grid<T, ROWS, COLS>>,
+}
+
+/// The pointer used to read the rows of a grid channel, as of the last flush.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [GridChannel::destroy] or [GridChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct GridReader<T, const ROWS: usize, const COLS: usize> {
+    data_pointer: ReadOnlyDataPointer<Grid<T, ROWS, COLS>>,
+    generation: GenerationPointer,
+    /// The generation of the channel when [GridReader::acknowledge] was last called.
+    acknowledged: u64,
+}
+
+impl<T: Clone, const ROWS: usize, const COLS: usize> GridChannel<T, ROWS, COLS> {
+    /// Create a grid channel whose cells are all initialised to the given value, and hand out three pointers to it.
+    /// One [GridChannelPointer] used to flush the dirty rows,
+    /// one [GridReader] used to read the grid, and
+    /// one [GridWriter] used to write the grid.
+    ///
+    /// Both grids are allocated on the heap without materialising them on the stack.
+    pub fn create(
+        value: T,
+    ) -> (
+        GridChannelPointer<T, ROWS, COLS>,
+        GridReader<T, ROWS, COLS>,
+        GridWriter<T, ROWS, COLS>,
+    ) {
+        let read_only = Grid {
+            rows: filled(value.clone()),
+            dirty: Box::default(),
+            generations: vec![0; ROWS].into_boxed_slice(),
+        };
+        let writable = Grid {
+            rows: filled(value),
+            dirty: vec![0; (ROWS + 63) / 64].into_boxed_slice(),
+            generations: Box::default(),
+        };
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create(read_only, writable);
+        (
+            GridChannelPointer { channel_pointer },
+            GridReader {
+                generation: DirectedChannel::generation_of_read_only(read_only_data_pointer.data),
+                data_pointer: read_only_data_pointer,
+                acknowledged: 0,
+            },
+            GridWriter {
+                data_pointer: writable_data_pointer,
+            },
+        )
+    }
+}
+
+impl<T, const ROWS: usize, const COLS: usize> GridChannel<T, ROWS, COLS> {
+    /// Destroys the grid channel linked with the three pointers (see [GridChannel::create]).
+    /// Returns the read and the written grid, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same grid channel.
+    pub fn destroy(
+        channel_pointer: GridChannelPointer<T, ROWS, COLS>,
+        reader: GridReader<T, ROWS, COLS>,
+        writer: GridWriter<T, ROWS, COLS>,
+    ) -> (Rows<T, ROWS, COLS>, Rows<T, ROWS, COLS>) {
+        let (read_only, writable) = DirectedChannel::destroy_single(
+            channel_pointer.channel_pointer,
+            reader.data_pointer,
+            writer.data_pointer,
+        );
+        (read_only.rows, writable.rows)
+    }
+}
+
+/// Allocate a grid with all cells set to the given value on the heap.
+fn filled<T: Clone, const ROWS: usize, const COLS: usize>(value: T) -> Rows<T, ROWS, COLS> {
+    let cells = vec![value; ROWS * COLS].into_boxed_slice();
+    // A grid has the same layout as a slice of all its cells.
+    unsafe { Box::from_raw(Box::into_raw(cells) as *mut [[T; COLS]; ROWS]) }
+}
+
+/// **Panics** if the given row is out of bounds.
+#[track_caller]
+fn check_row<const ROWS: usize>(row: usize) {
+    assert!(
+        row < ROWS,
+        "row {} is out of bounds for a grid with {} rows",
+        row,
+        ROWS
+    );
+}
+
+impl<T: Clone, const ROWS: usize, const COLS: usize> FlushStrategy<Grid<T, ROWS, COLS>>
+    for RowFlush
+{
+    fn flush(&self, read_only: &mut Grid<T, ROWS, COLS>, writable: &mut Grid<T, ROWS, COLS>) {
+        for (word_index, word) in writable.dirty.iter_mut().enumerate() {
+            let mut bits = *word;
+            while bits != 0 {
+                let row = word_index * 64 + bits.trailing_zeros() as usize;
+                read_only.rows[row].clone_from_slice(&writable.rows[row]);
+                read_only.generations[row] = self.generation;
+                bits &= bits - 1;
+            }
+            *word = 0;
+        }
+    }
+}
+
+impl<T: Clone, const ROWS: usize, const COLS: usize> GridChannelPointer<T, ROWS, COLS> {
+    /// Clone the rows written since the last flush into the read grid, and clear their dirty marks.
+    /// If no row was written since the last flush, the flush is skipped.
+    ///
+    /// This clones only the dirty rows, and is hence [real-time safe](crate::rt) if cloning `T` does not allocate.
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        let generation = self.channel_pointer.stats(channel_key).generation + 1;
+        self.channel_pointer
+            .flush_with_if_dirty(&RowFlush { generation });
+    }
+}
+
+impl<T, const ROWS: usize, const COLS: usize> GridChannelPointer<T, ROWS, COLS> {
+    /// Statistics about the flushes performed on this channel.
+    pub fn stats(&self, channel_key: &ChannelKey) -> FlushStats {
+        self.channel_pointer.stats(channel_key)
+    }
+
+    /// Shorthand for [GridChannel::destroy].
+    pub fn destroy(
+        self,
+        reader: GridReader<T, ROWS, COLS>,
+        writer: GridWriter<T, ROWS, COLS>,
+    ) -> (Rows<T, ROWS, COLS>, Rows<T, ROWS, COLS>) {
+        GridChannel::destroy(self, reader, writer)
+    }
+}
+
+impl<T, const ROWS: usize, const COLS: usize> GridWriter<T, ROWS, COLS> {
+    /// Get a reference to the given row of the written grid.
+    ///
+    /// **Panics** if the row is out of bounds.
+    #[track_caller]
+    pub fn row(&self, data_key: &DataKey, row: usize) -> &[T; COLS] {
+        check_row::<ROWS>(row);
+        &self.data_pointer.get(data_key).rows[row]
+    }
+
+    /// Get a mutable reference to the given row of the written grid, and mark it dirty,
+    /// such that the next flush clones it into the read grid.
+    ///
+    /// **Panics** if the row is out of bounds.
+    #[track_caller]
+    pub fn row_mut(&mut self, data_key: &DataKey, row: usize) -> &mut [T; COLS] {
+        check_row::<ROWS>(row);
+        let grid = self.data_pointer.get_mut(data_key);
+        grid.dirty[row / 64] |= 1 << (row % 64);
+        &mut grid.rows[row]
+    }
+}
+
+impl<T, const ROWS: usize, const COLS: usize> GridReader<T, ROWS, COLS> {
+    /// Get a reference to the given row of the read grid.
+    ///
+    /// **Panics** if the row is out of bounds.
+    #[track_caller]
+    pub fn row(&self, data_key: &DataKey, row: usize) -> &[T; COLS] {
+        check_row::<ROWS>(row);
+        &self.data_pointer.get(data_key).rows[row]
+    }
+
+    /// Get a reference to the whole read grid.
+    pub fn rows(&self, data_key: &DataKey) -> &[[T; COLS]; ROWS] {
+        &self.data_pointer.get(data_key).rows
+    }
+
+    /// Iterate over the rows changed by the flushes since the last [GridReader::acknowledge], together with their indices, in ascending order.
+    /// Before the first acknowledge, these are all rows changed since the grid channel was created.
+    pub fn changed_rows<'a>(
+        &'a self,
+        data_key: &'a DataKey,
+    ) -> impl Iterator<Item = (usize, &'a [T; COLS])> + 'a {
+        let grid = self.data_pointer.get(data_key);
+        let acknowledged = self.acknowledged;
+        grid.rows
+            .iter()
+            .zip(grid.generations.iter())
+            .enumerate()
+            .filter(move |(_, (_, &generation))| generation > acknowledged)
+            .map(|(index, (row, _))| (index, row))
+    }
+
+    /// Acknowledge all flushes so far, such that [GridReader::changed_rows] only returns rows changed by later flushes.
+    /// This also marks the current generation as consumed (see [ReadOnlyDataPointer::mark_consumed]).
+    pub fn acknowledge(&mut self, data_key: &DataKey) {
+        self.acknowledged = self.generation.get();
+        self.data_pointer.mark_consumed(data_key);
+    }
+}
+
+unsafe impl<T: Send, const ROWS: usize, const COLS: usize> Send for GridReader<T, ROWS, COLS> {}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use crate::{grid::GridChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut reader, mut writer) = GridChannel::<u32, 100, 3>::create(0);
+
+        let data_key = master_key.get_data_key();
+        writer.row_mut(&data_key, 1)[0] = 1;
+        writer.row_mut(&data_key, 70)[2] = 70;
+        assert_eq!(reader.row(&data_key, 70), &[0, 0, 0]);
+        assert_eq!(reader.changed_rows(&data_key).count(), 0);
+
+        channel_pointer.flush(&data_key.into_channel_key());
+        let data_key = master_key.get_data_key();
+        let changed: Vec<_> = reader.changed_rows(&data_key).collect();
+        assert_eq!(changed, [(1, &[1, 0, 0]), (70, &[0, 0, 70])]);
+        reader.acknowledge(&data_key);
+        assert_eq!(reader.changed_rows(&data_key).count(), 0);
+        assert_eq!(writer.row(&data_key, 70), &[0, 0, 70]);
+        writer.row_mut(&data_key, 99)[1] = 99;
+
+        // Only the dirty rows are cloned, and the skipped flush does not change any row.
+        let channel_key = data_key.into_channel_key();
+        channel_pointer.flush(&channel_key);
+        channel_pointer.flush(&channel_key);
+        let stats = channel_pointer.stats(&channel_key);
+        assert_eq!((stats.flushes, stats.skipped), (2, 1));
+        let data_key = master_key.get_data_key();
+        let changed: Vec<_> = reader.changed_rows(&data_key).map(|(row, _)| row).collect();
+        assert_eq!(changed, [99]);
+        assert_eq!(reader.rows(&data_key)[1], [1, 0, 0]);
+
+        let (read, written) = channel_pointer.destroy(reader, writer);
+        assert_eq!(read, written);
+        assert_eq!(read[99], [0, 99, 0]);
+    }
+
+    #[test]
+    fn out_of_bounds_rows_panic() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, reader, mut writer) = GridChannel::<u8, 4, 4>::create(0);
+        let data_key = master_key.get_data_key();
+        let out_of_bounds =
+            panic::catch_unwind(AssertUnwindSafe(|| writer.row_mut(&data_key, 4)[0] = 1));
+        assert!(out_of_bounds.is_err());
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| reader.row(&data_key, 4).len())).is_err());
+        channel_pointer.destroy(reader, writer);
+    }
+}
@@ -0,0 +1,1494 @@
+//! A group of channels of mixed kinds that are advanced together.
+//! A coordinator thread usually manages many channels, and this saves it from keeping a separate collection per kind.
+//!
+//! A [ChannelGroup] advances its channels through a virtual call each.
+//! If the channel types are known in advance, [channel_group_enum](crate::channel_group_enum) generates an enum over them instead,
+//! which advances without virtual calls, see `cargo bench --bench enum_group` for the difference on many small channels.
+
+#[cfg(feature = "checkpoint")]
+use std::io;
+use std::{
+    any::Any,
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::{self, Write},
+    sync::{Arc, Weak},
+};
+
+#[cfg(feature = "checkpoint")]
+use crate::checkpoint::{self, Checkpoint, CheckpointError, CheckpointFns, Record};
+
+use crate::{
+    bidirected::IBidirectedChannel,
+    directed::{IDirectedChannel, ReadOnlyDataPointer},
+    erased::{DestroyError, ErasedDataPointer, ErasedDestroy},
+    notify::{Notifier, NotifierState},
+    undirected::{ImmutableUndirectedDataPointer, UndirectedSwapChannel},
+    ChannelKey, DataKey, SwapChannel,
+};
+
+/// The kind of a channel in a [ChannelGroup], as given when adding it.
+/// All channels are advanced via [SwapChannel::advance], independent of their kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelKind {
+    /// An [`UndirectedSwapChannel`], which is advanced by swapping it.
+    Undirected,
+    /// An [`IDirectedChannel`], which is advanced by flushing it.
+    Directed,
+    /// An [`IBidirectedChannel`], which is advanced by flushing both of its directions.
+    Bidirected,
+    /// Any other [`SwapChannel`], added via [ChannelGroup::push].
+    Other,
+}
+
+/// A group of labelled channel pointers of mixed kinds, which are advanced together in insertion order.
+/// It can only be changed or advanced using a [ChannelKey], such that channels can come and go between phases.
+///
+/// If a channel must be advanced after another one, e.g. because advancing it reads what the other one just published,
+/// this can be declared via [ChannelGroup::advance_after] instead of relying on the insertion order.
+/// Channels whose order is not pinned this way can be advanced earlier via [ChannelGroup::set_priority].
+///
+/// The channels are stored with their concrete types, such that they can be removed again for destruction (see [ChannelGroup::remove]).
+/// Channels added as `Box<dyn ErasedDestroy>` can also be destroyed without knowing their types (see [ChannelGroup::destroy]).
+///
+/// Each channel can be given a validator, which is called with its readable `Data` at the end of each data phase (see [ChannelGroup::set_validator]).
+#[derive(Debug, Default)]
+pub struct ChannelGroup {
+    /// The channels in insertion order.
+    entries: Vec<Entry>,
+    /// The positions of the entries in the order they are advanced, see [ChannelGroup::advance_after].
+    order: Vec<usize>,
+    slots: Vec<Slot>,
+    /// The indices of the slots that are not in use.
+    free_slots: Vec<usize>,
+    /// The states of the notifiers that were created via [ChannelGroup::notifier] and not yet dropped.
+    notifiers: Vec<Weak<NotifierState>>,
+}
+
+/// The id of a channel in a [ChannelGroup].
+/// It stays valid until the channel is removed, independent of other channels being added or removed,
+/// and is never reused for a different channel of the same group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GroupId {
+    slot: usize,
+    generation: u64,
+}
+
+/// Maps a [GroupId] to the position of its channel in the entries of a [ChannelGroup].
+#[derive(Debug)]
+struct Slot {
+    /// Incremented whenever the channel in this slot is removed, invalidating its id.
+    generation: u64,
+    position: Option<usize>,
+}
+
+/// A data pointer that gives read access to the `Data` it points to, independent of its type.
+/// This is implemented by the copyable data pointers, such that a [ChannelGroup] can co-own them for [ChannelGroup::visit_data].
+pub trait ReadAny: Send + Sync + 'static {
+    /// Get a reference to the `Data` pointed to by this pointer.
+    fn read_any(&self, data_key: &DataKey) -> &dyn Any;
+}
+
+/// The reason why [ChannelGroup::advance_after] rejected an ordering constraint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderError {
+    /// One of the channels was already removed.
+    UnknownChannel,
+    /// The channel is already advanced before the other channel, possibly via further channels, or the channels are the same.
+    Cycle,
+}
+
+/// Calls the validator of a channel with its readable `Data`, see [ChannelGroup::set_validator].
+type Validate = Box<dyn Fn(&DataKey) + Send + Sync>;
+
+struct Entry {
+    id: GroupId,
+    label: String,
+    kind: ChannelKind,
+    channel: Box<dyn Any + Send + Sync>,
+    /// Advances the channel and returns `true` if this changed its `Data`, see [SwapChannel::generation].
+    advance: fn(&mut (dyn Any + Send + Sync), &ChannelKey) -> bool,
+    /// Gives access to the introspection methods of the channel, see [ChannelGroup::to_dot].
+    as_swap_channel: fn(&(dyn Any + Send + Sync)) -> &dyn SwapChannel,
+    /// The names of the threads the channel connects, if it was created by a [Topology](crate::topology::Topology).
+    endpoints: Option<(String, String)>,
+    /// The channels this channel is advanced after, see [ChannelGroup::advance_after].
+    after: Vec<GroupId>,
+    /// See [ChannelGroup::set_priority].
+    priority: i32,
+    /// The length of the longest chain of channels this channel is advanced after, i.e. the wave it is advanced in by [ChannelGroup::par_advance_all].
+    wave: usize,
+    validate: Option<Validate>,
+    #[cfg(feature = "checkpoint")]
+    checkpoint: Option<CheckpointFns>,
+}
+
+impl core::fmt::Debug for Entry {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Entry")
+            .field("id", &self.id)
+            .field("label", &self.label)
+            .field("kind", &self.kind)
+            .field("after", &self.after)
+            .field("priority", &self.priority)
+            .field("validated", &self.validate.is_some())
+            .finish()
+    }
+}
+
+impl ChannelGroup {
+    /// Create an empty channel group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an undirected channel to the end of the group.
+    /// Returns its id.
+    pub fn push_undirected<T: UndirectedSwapChannel + 'static>(
+        &mut self,
+        channel_key: &ChannelKey,
+        label: impl Into<String>,
+        channel: T,
+    ) -> GroupId {
+        self.insert(channel_key, label, ChannelKind::Undirected, channel)
+    }
+
+    /// Add a directed channel to the end of the group.
+    /// Returns its id.
+    pub fn push_directed<T: IDirectedChannel + 'static>(
+        &mut self,
+        channel_key: &ChannelKey,
+        label: impl Into<String>,
+        channel: T,
+    ) -> GroupId {
+        self.insert(channel_key, label, ChannelKind::Directed, channel)
+    }
+
+    /// Add a bidirected channel to the end of the group.
+    /// Returns its id.
+    pub fn push_bidirected<T: IBidirectedChannel + 'static>(
+        &mut self,
+        channel_key: &ChannelKey,
+        label: impl Into<String>,
+        channel: T,
+    ) -> GroupId {
+        self.insert(channel_key, label, ChannelKind::Bidirected, channel)
+    }
+
+    /// Add any channel to the end of the group, e.g. a `Box<dyn SwapChannel>`.
+    /// Its kind is [ChannelKind::Other].
+    /// Returns its id.
+    pub fn push<T: SwapChannel + 'static>(
+        &mut self,
+        channel_key: &ChannelKey,
+        label: impl Into<String>,
+        channel: T,
+    ) -> GroupId {
+        self.insert(channel_key, label, ChannelKind::Other, channel)
+    }
+
+    fn insert<T: SwapChannel + 'static>(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        label: impl Into<String>,
+        kind: ChannelKind,
+        channel: T,
+    ) -> GroupId {
+        let position = Some(self.entries.len());
+        let slot = if let Some(slot) = self.free_slots.pop() {
+            self.slots[slot].position = position;
+            slot
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                position,
+            });
+            self.slots.len() - 1
+        };
+        let id = GroupId {
+            slot,
+            generation: self.slots[slot].generation,
+        };
+
+        self.entries.push(Entry {
+            id,
+            label: label.into(),
+            kind,
+            channel: Box::new(channel),
+            advance: advance::<T>,
+            as_swap_channel: as_swap_channel::<T>,
+            endpoints: None,
+            after: Vec::new(),
+            priority: 0,
+            wave: 0,
+            validate: None,
+            #[cfg(feature = "checkpoint")]
+            checkpoint: None,
+        });
+        if self.is_ordered() {
+            self.reorder();
+        } else {
+            self.order.push(self.entries.len() - 1);
+        }
+        id
+    }
+
+    /// Declare that the channel with the given id is advanced after the channel `before` by [ChannelGroup::advance_all],
+    /// independent of the order in which they were added.
+    /// Constraints involving a channel are dropped when it is removed.
+    ///
+    /// Returns [OrderError::Cycle] if `before` is already advanced after the channel, possibly via further channels,
+    /// and [OrderError::UnknownChannel] if one of the channels was already removed.
+    /// In both cases, the group is not changed.
+    pub fn advance_after(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        id: GroupId,
+        before: GroupId,
+    ) -> Result<(), OrderError> {
+        let (position, before_position) = match (self.position(id), self.position(before)) {
+            (Some(position), Some(before_position)) => (position, before_position),
+            _ => return Err(OrderError::UnknownChannel),
+        };
+        if self.is_advanced_after(before_position, id) {
+            return Err(OrderError::Cycle);
+        }
+        if !self.entries[position].after.contains(&before) {
+            self.entries[position].after.push(before);
+            self.reorder();
+        }
+        Ok(())
+    }
+
+    /// Set the priority of the channel with the given id, which is zero by default.
+    /// Among the channels that are not pinned after each other via [ChannelGroup::advance_after],
+    /// those with higher priority are advanced earlier, and those with equal priority in insertion order.
+    /// Returns `false` if the channel was already removed.
+    pub fn set_priority(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        id: GroupId,
+        priority: i32,
+    ) -> bool {
+        let position = match self.position(id) {
+            Some(position) => position,
+            None => return false,
+        };
+        self.entries[position].priority = priority;
+        self.reorder();
+        true
+    }
+
+    /// Iterate over the ids of the channels in the order they are advanced by [ChannelGroup::advance_all].
+    pub fn advance_order(&self) -> impl Iterator<Item = GroupId> + '_ {
+        self.order
+            .iter()
+            .map(move |&position| self.entries[position].id)
+    }
+
+    /// The position of the channel with the given id in the entries, if it was not removed.
+    fn position(&self, id: GroupId) -> Option<usize> {
+        let slot = &self.slots[id.slot];
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.position
+    }
+
+    /// Returns `true` if the channel at the given position is advanced after the channel with the given id, possibly via further channels,
+    /// or if it is that channel.
+    fn is_advanced_after(&self, position: usize, id: GroupId) -> bool {
+        let mut stack = vec![position];
+        let mut visited = vec![false; self.entries.len()];
+        while let Some(position) = stack.pop() {
+            let entry = &self.entries[position];
+            if entry.id == id {
+                return true;
+            }
+            if !visited[position] {
+                visited[position] = true;
+                stack.extend(entry.after.iter().filter_map(|&after| self.position(after)));
+            }
+        }
+        false
+    }
+
+    /// Returns `true` if the order of the channels deviates from the insertion order.
+    fn is_ordered(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.priority != 0 || !entry.after.is_empty())
+    }
+
+    /// Compute the order in which the channels are advanced, and the wave of each channel.
+    /// This is a topological order of the constraints, where the channel with the highest priority and then the smallest position
+    /// is advanced first among the channels whose constraints are satisfied.
+    fn reorder(&mut self) {
+        let mut waiting = vec![0; self.entries.len()];
+        let mut dependents = vec![Vec::new(); self.entries.len()];
+        for (position, entry) in self.entries.iter().enumerate() {
+            for before in entry.after.iter().filter_map(|&after| self.position(after)) {
+                waiting[position] += 1;
+                dependents[before].push(position);
+            }
+        }
+        let mut ready: BinaryHeap<_> = (0..self.entries.len())
+            .filter(|&position| waiting[position] == 0)
+            .map(|position| (self.entries[position].priority, Reverse(position)))
+            .collect();
+        for entry in &mut self.entries {
+            entry.wave = 0;
+        }
+
+        self.order.clear();
+        while let Some((_, Reverse(position))) = ready.pop() {
+            self.order.push(position);
+            let wave = self.entries[position].wave + 1;
+            for &dependent in &dependents[position] {
+                let entry = &mut self.entries[dependent];
+                entry.wave = entry.wave.max(wave);
+                waiting[dependent] -= 1;
+                if waiting[dependent] == 0 {
+                    ready.push((entry.priority, Reverse(dependent)));
+                }
+            }
+        }
+        debug_assert_eq!(
+            self.order.len(),
+            self.entries.len(),
+            "cycles are rejected by advance_after"
+        );
+    }
+
+    /// Swap or flush every channel in the group, in insertion order unless ordered otherwise (see [ChannelGroup::advance_after]).
+    /// Afterwards, the notifiers of the updated channels are woken (see [ChannelGroup::notifier]).
+    pub fn advance_all(&mut self, channel_key: &ChannelKey) {
+        let entries = &mut self.entries;
+        let updated: Vec<_> = self
+            .order
+            .iter()
+            .filter_map(|&position| {
+                let entry = &mut entries[position];
+                (entry.advance)(entry.channel.as_mut(), channel_key).then(|| entry.id)
+            })
+            .collect();
+        self.notify(&updated);
+    }
+
+    /// Swap or flush only the channel with the given id.
+    /// Afterwards, its notifiers are woken if it was updated (see [ChannelGroup::notifier]).
+    /// Returns `false` if the channel was already removed.
+    pub fn advance(&mut self, channel_key: &ChannelKey, id: GroupId) -> bool {
+        let slot = &self.slots[id.slot];
+        if slot.generation != id.generation {
+            return false;
+        }
+        let entry = &mut self.entries[slot.position.expect("slot of a valid id is in use")];
+        if (entry.advance)(entry.channel.as_mut(), channel_key) {
+            self.notify(&[id]);
+        }
+        true
+    }
+
+    /// Like [ChannelGroup::advance_all], but the channels are advanced in parallel on the rayon thread pool, in no particular order.
+    /// This pays off if the group holds many channels, or channels that are expensive to advance.
+    ///
+    /// If channels are ordered via [ChannelGroup::advance_after], they are advanced in waves,
+    /// where each wave holds the channels whose predecessors were all advanced in earlier waves.
+    /// Priorities do not apply.
+    ///
+    /// Advancing multiple channels at the same time under a single channel key is sound,
+    /// since each channel pointer is borrowed mutably by exactly one worker, such that no two workers ever access the same channel,
+    /// and the channel key guarantees that no data pointer is accessed until all workers have finished.
+    #[cfg(feature = "rayon")]
+    pub fn par_advance_all(&mut self, channel_key: &ChannelKey) {
+        use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+
+        let waves = self.entries.iter().map(|entry| entry.wave + 1).max();
+        let mut waves: Vec<Vec<&mut Entry>> = (0..waves.unwrap_or(0)).map(|_| Vec::new()).collect();
+        for entry in &mut self.entries {
+            waves[entry.wave].push(entry);
+        }
+        let mut updated = Vec::new();
+        for mut wave in waves {
+            updated.extend(
+                wave.par_iter_mut()
+                    .filter_map(|entry| {
+                        (entry.advance)(entry.channel.as_mut(), channel_key).then(|| entry.id)
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+        self.notify(&updated);
+    }
+
+    /// Create a notifier for a reader thread that is interested in the channels with the given ids.
+    /// The notifier wakes the reader whenever [ChannelGroup::advance_all] or its parallel variant changed the `Data` of at least one of these channels,
+    /// which excludes skipped flushes of clean channels.
+    /// Channels that cannot report whether they changed (see [SwapChannel::generation]) count as changed by every advance.
+    pub fn notifier(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        ids: impl IntoIterator<Item = GroupId>,
+    ) -> Notifier {
+        let (notifier, state) = Notifier::new(ids.into_iter().collect());
+        self.notifiers.push(Arc::downgrade(&state));
+        notifier
+    }
+
+    /// Wake the notifiers of the given updated channels, and forget the notifiers that were dropped.
+    fn notify(&mut self, updated: &[GroupId]) {
+        self.notifiers.retain(|notifier| match notifier.upgrade() {
+            Some(notifier) => {
+                notifier.notify(updated);
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Remove the channel with the given id from the group and return it, e.g. for destruction.
+    /// Returns `None` if the channel was already removed.
+    /// The ids of the other channels stay valid.
+    ///
+    /// **Panics** if the channel is not of type `T`.
+    /// In this case, the group is not changed.
+    pub fn remove<T: 'static>(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        id: GroupId,
+    ) -> Option<T> {
+        let slot = &mut self.slots[id.slot];
+        if slot.generation != id.generation {
+            return None;
+        }
+        let position = slot.position.expect("slot of a valid id is in use");
+        assert!(
+            self.entries[position].channel.is::<T>(),
+            "channel {:?} has a different type",
+            self.entries[position].label
+        );
+
+        slot.position = None;
+        slot.generation += 1;
+        self.free_slots.push(id.slot);
+        let entry = self.entries.remove(position);
+        for entry in &self.entries[position..] {
+            let position = self.slots[entry.id.slot].position.as_mut().unwrap();
+            *position -= 1;
+        }
+        for other in &mut self.entries {
+            other.after.retain(|&after| after != id);
+        }
+        self.reorder();
+        Some(*entry.channel.downcast().unwrap())
+    }
+
+    /// Remove the channel with the given id from the group and destroy it via [ErasedDestroy::destroy_erased] with the given data pointers.
+    /// Returns the `Data` fields of the channel.
+    ///
+    /// The channel must have been added as a `Box<dyn ErasedDestroy>` via [ChannelGroup::push],
+    /// otherwise [DestroyError::NotErased] is returned and the group is not changed.
+    /// If the channel was already removed, [DestroyError::UnknownChannel] is returned.
+    pub fn destroy(
+        &mut self,
+        channel_key: &ChannelKey,
+        id: GroupId,
+        pointers: Vec<ErasedDataPointer>,
+    ) -> Result<Vec<Box<dyn Any + Send>>, DestroyError> {
+        let slot = &self.slots[id.slot];
+        if slot.generation != id.generation {
+            return Err(DestroyError::UnknownChannel);
+        }
+        let position = slot.position.expect("slot of a valid id is in use");
+        if !self.entries[position]
+            .channel
+            .is::<Box<dyn ErasedDestroy>>()
+        {
+            return Err(DestroyError::NotErased);
+        }
+
+        let channel: Box<dyn ErasedDestroy> = self.remove(channel_key, id).unwrap();
+        channel.destroy_erased(pointers)
+    }
+
+    /// Set the validator of the channel with the given id, replacing the previous one.
+    /// The group keeps the given data pointer, which must point to the channel, to read its `Data` in [ChannelGroup::visit_data].
+    /// Returns `false` if the channel was already removed.
+    pub fn set_validator(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        id: GroupId,
+        data_pointer: impl ReadAny,
+        validator: impl Fn(&dyn Any) + Send + Sync + 'static,
+    ) -> bool {
+        let slot = &self.slots[id.slot];
+        if slot.generation != id.generation {
+            return false;
+        }
+        let position = slot.position.expect("slot of a valid id is in use");
+        self.entries[position].validate = Some(Box::new(move |data_key| {
+            validator(data_pointer.read_any(data_key))
+        }));
+        true
+    }
+
+    /// Call the validator of each channel that has one with its readable `Data`, in insertion order.
+    /// This is usually done at the end of each data phase, e.g. to check invariants of all channels without knowing their types.
+    pub fn visit_data(&self, data_key: &DataKey) {
+        for validate in self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.validate.as_ref())
+        {
+            validate(data_key);
+        }
+    }
+
+    /// Include the channel with the given id in the checkpoints of the group (see [ChannelGroup::checkpoint]).
+    /// Returns `false` if the channel was already removed.
+    ///
+    /// **Panics** if the channel is not of type `T`.
+    #[cfg(feature = "checkpoint")]
+    pub fn enable_checkpoint<T: Checkpoint + 'static>(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        id: GroupId,
+    ) -> bool {
+        let slot = &self.slots[id.slot];
+        if slot.generation != id.generation {
+            return false;
+        }
+        let entry = &mut self.entries[slot.position.expect("slot of a valid id is in use")];
+        assert!(
+            entry.channel.is::<T>(),
+            "channel {:?} has a different type",
+            entry.label
+        );
+        entry.checkpoint = Some(CheckpointFns::of::<T>());
+        true
+    }
+
+    /// Write the contents of all channels that were included via [ChannelGroup::enable_checkpoint] to the given writer, in insertion order.
+    /// Since the channel key is held throughout, all contents are from the same phase boundary.
+    /// See the [checkpoint] module for the format.
+    #[cfg(feature = "checkpoint")]
+    pub fn checkpoint<W: io::Write>(&self, channel_key: &ChannelKey, writer: W) -> io::Result<()> {
+        let records = self
+            .entries
+            .iter()
+            .filter_map(|entry| Some((entry, entry.checkpoint?)))
+            .map(|(entry, checkpoint)| {
+                Ok(Record {
+                    label: entry.label.clone(),
+                    type_hash: checkpoint.type_hash,
+                    contents: (checkpoint.save)(entry.channel.as_ref(), channel_key)?,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        checkpoint::write_records(writer, &records)
+    }
+
+    /// Replace the contents of all channels that were included via [ChannelGroup::enable_checkpoint] with the contents in the checkpoint from the given reader.
+    ///
+    /// The checkpoint must hold exactly the included channels, in insertion order, with the same labels and types.
+    /// Otherwise, a [CheckpointError] is returned as the inner error of an [io::Error] of kind [io::ErrorKind::InvalidData].
+    /// Partial restores are not supported, i.e. if a channel is missing from the checkpoint, no channel is restored.
+    /// The same holds if the contents of a channel cannot be decoded, in which case the channels that were already restored are rolled back.
+    #[cfg(feature = "checkpoint")]
+    pub fn restore<R: io::Read>(&mut self, channel_key: &ChannelKey, reader: R) -> io::Result<()> {
+        let records = checkpoint::read_records(reader)?;
+        let mut entries: Vec<_> = self
+            .entries
+            .iter_mut()
+            .filter_map(|entry| Some((entry.checkpoint?, entry)))
+            .collect();
+        if entries.len() != records.len() {
+            return Err(CheckpointError::WrongChannelCount {
+                expected: entries.len(),
+                found: records.len(),
+            }
+            .into());
+        }
+        for (index, ((checkpoint, entry), record)) in entries.iter().zip(&records).enumerate() {
+            if entry.label != record.label {
+                return Err(CheckpointError::WrongLabel {
+                    index,
+                    expected: entry.label.clone(),
+                    found: record.label.clone(),
+                }
+                .into());
+            }
+            if checkpoint.type_hash != record.type_hash {
+                return Err(CheckpointError::WrongType {
+                    label: entry.label.clone(),
+                }
+                .into());
+            }
+        }
+
+        // Keep the current contents to roll back if the contents of a channel cannot be decoded.
+        let backups = entries
+            .iter()
+            .map(|(checkpoint, entry)| (checkpoint.save)(entry.channel.as_ref(), channel_key))
+            .collect::<io::Result<Vec<_>>>()?;
+        for index in 0..entries.len() {
+            let (checkpoint, entry) = &mut entries[index];
+            if let Err(error) = (checkpoint.load)(
+                entry.channel.as_mut(),
+                channel_key,
+                &records[index].contents,
+            ) {
+                for ((checkpoint, entry), backup) in entries[..index].iter_mut().zip(&backups) {
+                    (checkpoint.load)(entry.channel.as_mut(), channel_key, backup)
+                        .expect("could not roll back a channel to its previous contents");
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// The channel with the given id, or `None` if it was already removed.
+    pub(crate) fn channel(&self, id: GroupId) -> Option<&(dyn Any + Send + Sync)> {
+        let slot = &self.slots[id.slot];
+        if slot.generation != id.generation {
+            return None;
+        }
+        let position = slot.position.expect("slot of a valid id is in use");
+        Some(self.entries[position].channel.as_ref())
+    }
+
+    /// Iterate over the ids, labels and kinds of the channels in the group, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (GroupId, &str, ChannelKind)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.id, entry.label.as_str(), entry.kind))
+    }
+
+    /// Render the channels of the group as a [DOT](https://graphviz.org/doc/info/lang.html) graph, e.g. to draw it with Graphviz.
+    ///
+    /// Each channel is a box showing its label, kind, payload type, generation and whether it is dirty, as far as the channel reports them
+    /// via [SwapChannel].
+    /// Channels created by a [Topology](crate::topology::Topology) are connected to the threads at their ends,
+    /// which are drawn as ellipses in the order they first appear.
+    /// The output only depends on the state of the channels, such that it can be compared in tests.
+    pub fn to_dot(&self, channel_key: &ChannelKey) -> String {
+        let channels = self.entries.iter().map(|entry| {
+            let channel = (entry.as_swap_channel)(entry.channel.as_ref());
+            DotChannel {
+                label: &entry.label,
+                kind: entry.kind,
+                payload_type_name: channel.payload_type_name(),
+                generation: channel.generation(channel_key),
+                dirty: channel.dirty(channel_key),
+                endpoints: entry
+                    .endpoints
+                    .as_ref()
+                    .map(|(from, to)| (from.as_str(), to.as_str())),
+            }
+        });
+        let mut threads = Vec::new();
+        for (from, to) in self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.endpoints.as_ref())
+        {
+            for thread in [from, to] {
+                if !threads.contains(&thread.as_str()) {
+                    threads.push(thread.as_str());
+                }
+            }
+        }
+        to_dot(&threads, channels)
+    }
+
+    /// Record the threads connected by the channel with the given id, for [ChannelGroup::to_dot].
+    pub(crate) fn set_endpoints(&mut self, id: GroupId, from: &str, to: &str) {
+        let position = self.slots[id.slot]
+            .position
+            .expect("slot of a valid id is in use");
+        self.entries[position].endpoints = Some((from.to_owned(), to.to_owned()));
+    }
+
+    /// The number of channels in the group.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the group has no channels.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::UnknownChannel => write!(f, "the channel was already removed"),
+            OrderError::Cycle => write!(
+                f,
+                "the channels would have to be advanced before each other"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+impl<Data: 'static> ReadAny for ReadOnlyDataPointer<Data> {
+    fn read_any(&self, data_key: &DataKey) -> &dyn Any {
+        self.get(data_key)
+    }
+}
+
+impl<Data: 'static> ReadAny for ImmutableUndirectedDataPointer<Data> {
+    fn read_any(&self, data_key: &DataKey) -> &dyn Any {
+        self.get(data_key)
+    }
+}
+
+fn advance<T: SwapChannel + 'static>(
+    channel: &mut (dyn Any + Send + Sync),
+    channel_key: &ChannelKey,
+) -> bool {
+    let channel = channel.downcast_mut::<T>().unwrap();
+    let generation = channel.generation(channel_key);
+    channel.advance(channel_key);
+    generation.is_none() || channel.generation(channel_key) != generation
+}
+
+fn as_swap_channel<T: SwapChannel + 'static>(
+    channel: &(dyn Any + Send + Sync),
+) -> &dyn SwapChannel {
+    channel.downcast_ref::<T>().unwrap()
+}
+
+/// A channel as drawn by [to_dot].
+pub(crate) struct DotChannel<'a> {
+    pub(crate) label: &'a str,
+    pub(crate) kind: ChannelKind,
+    pub(crate) payload_type_name: Option<&'static str>,
+    pub(crate) generation: Option<u64>,
+    pub(crate) dirty: Option<bool>,
+    /// The threads the channel connects.
+    pub(crate) endpoints: Option<(&'a str, &'a str)>,
+}
+
+/// Render the given threads and channels as a DOT graph, see [ChannelGroup::to_dot].
+/// Channels are named by their index, and threads by their name.
+pub(crate) fn to_dot<'a>(
+    threads: &[&str],
+    channels: impl IntoIterator<Item = DotChannel<'a>>,
+) -> String {
+    let mut dot = String::from("digraph {\n    node [shape=box];\n");
+    for thread in threads {
+        writeln!(dot, "    \"{}\" [shape=ellipse];", escape(thread)).unwrap();
+    }
+    for (index, channel) in channels.into_iter().enumerate() {
+        let kind = match channel.kind {
+            ChannelKind::Undirected => "undirected",
+            ChannelKind::Directed => "directed",
+            ChannelKind::Bidirected => "bidirected",
+            ChannelKind::Other => "other",
+        };
+        let mut label = format!("{}\\n{}", escape(channel.label), kind);
+        if let Some(payload_type_name) = channel.payload_type_name {
+            write!(label, "\\n{}", escape(payload_type_name)).unwrap();
+        }
+        if let Some(generation) = channel.generation {
+            write!(label, "\\ngeneration {}", generation).unwrap();
+        }
+        if let Some(dirty) = channel.dirty {
+            label += if dirty { "\\ndirty" } else { "\\nclean" };
+        }
+        writeln!(dot, "    channel{} [label=\"{}\"];", index, label).unwrap();
+
+        if let Some((from, to)) = channel.endpoints {
+            // Only a directed channel transmits in a single direction.
+            let attributes = if channel.kind == ChannelKind::Directed {
+                ""
+            } else {
+                " [dir=both]"
+            };
+            writeln!(
+                dot,
+                "    \"{}\" -> channel{}{};",
+                escape(from),
+                index,
+                attributes
+            )
+            .unwrap();
+            writeln!(
+                dot,
+                "    channel{} -> \"{}\"{};",
+                index,
+                escape(to),
+                attributes
+            )
+            .unwrap();
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Escape the given string for a quoted DOT string.
+fn escape(string: &str) -> String {
+    string.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Declare an enum over the given channel pointer types, with one tuple variant per type,
+/// such that a collection of mixed channels can be advanced without virtual calls.
+///
+/// The enum gets the inherent methods `advance` and `label`, which match on the variant and call the [SwapChannel] methods of the concrete type,
+/// such that they can be inlined.
+/// It also implements [SwapChannel] itself, as well as `From` for each of the types.
+/// Each type must appear only once.
+///
+/// Compared to a [ChannelGroup], advancing a `Vec` of the enum avoids the virtual call and bookkeeping per channel,
+/// which pays off for many small channels, but the group supports any channel type, validators, notifiers and removal by id.
+///
+/// ```
+/// use two_phase_channel::{
+///     channel_group_enum, directed::{DirectedChannel, DirectedChannelPointer},
+///     undirected::{UndirectedChannel, UndirectedChannelPointer}, MasterKey,
+/// };
+///
+/// channel_group_enum! {
+///     #[derive(Debug)]
+///     pub enum Channels {
+///         Frame(DirectedChannelPointer<Vec<u8>>),
+///         Input(UndirectedChannelPointer<u32>),
+///     }
+/// }
+///
+/// let mut master_key = MasterKey::create();
+/// let (frame, frame_read_only, mut frame_writable) = DirectedChannel::create_equal(Vec::new());
+/// let (input, input1, input2) = UndirectedChannel::create(0, 0);
+/// let mut channels: Vec<Channels> = vec![frame.with_label("frame").into(), input.into()];
+///
+/// frame_writable.get_mut(&master_key.get_data_key()).push(1);
+/// let channel_key = master_key.get_channel_key();
+/// for channel in &mut channels {
+///     channel.advance(&channel_key);
+/// }
+/// assert_eq!(channels[0].label(), Some("frame"));
+/// assert_eq!(*frame_read_only.get(&master_key.get_data_key()), [1]);
+/// ```
+#[macro_export]
+macro_rules! channel_group_enum {
+    ($(#[$attribute:meta])* $visibility:vis enum $name:ident { $($variant:ident($channel:ty)),+ $(,)? }) => {
+        $(#[$attribute])*
+        $visibility enum $name {
+            $($variant($channel),)+
+        }
+
+        impl $name {
+            /// Advance the channel, i.e. swap or flush it depending on its type.
+            #[inline]
+            pub fn advance(&mut self, channel_key: &$crate::ChannelKey) {
+                match self {
+                    $(Self::$variant(channel) => $crate::SwapChannel::advance(channel, channel_key),)+
+                }
+            }
+
+            /// The label of the channel, if it has one.
+            pub fn label(&self) -> Option<&str> {
+                match self {
+                    $(Self::$variant(channel) => $crate::SwapChannel::label(channel),)+
+                }
+            }
+        }
+
+        impl $crate::SwapChannel for $name {
+            #[inline]
+            fn advance(&mut self, channel_key: &$crate::ChannelKey) {
+                $name::advance(self, channel_key);
+            }
+
+            fn label(&self) -> Option<&str> {
+                $name::label(self)
+            }
+
+            fn generation(&self, channel_key: &$crate::ChannelKey) -> Option<u64> {
+                match self {
+                    $(Self::$variant(channel) => $crate::SwapChannel::generation(channel, channel_key),)+
+                }
+            }
+
+            fn payload_type_name(&self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant(channel) => $crate::SwapChannel::payload_type_name(channel),)+
+                }
+            }
+
+            fn dirty(&self, channel_key: &$crate::ChannelKey) -> Option<bool> {
+                match self {
+                    $(Self::$variant(channel) => $crate::SwapChannel::dirty(channel, channel_key),)+
+                }
+            }
+        }
+
+        $(impl From<$channel> for $name {
+            fn from(channel: $channel) -> Self {
+                Self::$variant(channel)
+            }
+        })+
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        bidirected::{BidirectedChannel, BidirectedChannelPointer},
+        directed::{DirectedChannel, DirectedChannelPointer},
+        erased::{DestroyError, ErasedDestroy},
+        group::{ChannelGroup, ChannelKind, GroupId, OrderError},
+        undirected::{UndirectedChannel, UndirectedChannelPointer, UndirectedSwapChannel},
+        ChannelKey, MasterKey, SwapChannel,
+    };
+
+    crate::channel_group_enum! {
+        enum Channels {
+            Undirected(UndirectedChannelPointer<u32>),
+            Directed(DirectedChannelPointer<u32>),
+            Bidirected(BidirectedChannelPointer<u8, u16>),
+        }
+    }
+
+    #[test]
+    fn channel_group_enum() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (undirected, undirected1, undirected2) = UndirectedChannel::create(1, 2);
+        let (directed, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let (bidirected, mut bidirected1, bidirected2) = BidirectedChannel::create(0, 0, 0, 0);
+        let mut channels: Vec<Channels> = vec![
+            undirected.with_label("undirected").into(),
+            directed.into(),
+            bidirected.into(),
+        ];
+
+        let data_key = master_key.get_data_key();
+        *writable.get_mut(&data_key) = 3;
+        *bidirected1.get_output(&data_key) = 4;
+        let channel_key = data_key.into_channel_key();
+        for channel in &mut channels {
+            channel.advance(&channel_key);
+        }
+        // Both directions of the bidirected channel were flushed.
+        assert_eq!(
+            channels
+                .iter()
+                .map(|channel| SwapChannel::generation(channel, &channel_key))
+                .collect::<Vec<_>>(),
+            [Some(1), Some(1), Some(2)]
+        );
+        assert_eq!(channels[0].label(), Some("undirected"));
+        assert_eq!(channels[1].label(), None);
+
+        let data_key = channel_key.into_data_key();
+        assert_eq!(*undirected1.get(&data_key), 2);
+        assert_eq!(*read_only.get(&data_key), 3);
+        assert_eq!(*bidirected2.get_input(&data_key), 4);
+
+        let mut channels = channels.into_iter();
+        match (channels.next(), channels.next(), channels.next()) {
+            (
+                Some(Channels::Undirected(undirected)),
+                Some(Channels::Directed(directed)),
+                Some(Channels::Bidirected(bidirected)),
+            ) => {
+                undirected.destroy(undirected1, undirected2);
+                directed.destroy_single(read_only, writable);
+                bidirected.destroy(bidirected1, bidirected2);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (undirected, mut undirected1, undirected2) = UndirectedChannel::create(1, 2);
+        let (directed, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let (bidirected, mut bidirected1, bidirected2) = BidirectedChannel::create(0, 0, 0, 0);
+
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let undirected = group.push_undirected(&channel_key, "undirected", undirected);
+        let directed = group.push_directed(&channel_key, "directed", directed);
+        let bidirected = group.push_bidirected(&channel_key, "bidirected", bidirected);
+        assert_eq!(group.len(), 3);
+        assert!(group.iter().eq([
+            (undirected, "undirected", ChannelKind::Undirected),
+            (directed, "directed", ChannelKind::Directed),
+            (bidirected, "bidirected", ChannelKind::Bidirected),
+        ]));
+
+        let data_key = channel_key.into_data_key();
+        *undirected1.get_mut(&data_key) += 10;
+        *writable.get_mut(&data_key) = 3;
+        *bidirected1.get_output(&data_key) = 4;
+        group.advance_all(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*undirected1.get(&data_key), 2);
+        assert_eq!(*read_only.get(&data_key), 3);
+        assert_eq!(*bidirected2.get_input(&data_key), 4);
+
+        let channel_key = data_key.into_channel_key();
+        let bidirected_pointer: BidirectedChannelPointer<i32, i32> =
+            group.remove(&channel_key, bidirected).unwrap();
+        assert_eq!(
+            bidirected_pointer.destroy(bidirected1, bidirected2),
+            (0, 0, 4, 4)
+        );
+        assert!(group
+            .remove::<BidirectedChannelPointer<i32, i32>>(&channel_key, bidirected)
+            .is_none());
+        let directed: DirectedChannelPointer<i32> = group.remove(&channel_key, directed).unwrap();
+        assert_eq!(directed.destroy_single(read_only, writable), (3, 3));
+        let undirected: UndirectedChannelPointer<i32> =
+            group.remove(&channel_key, undirected).unwrap();
+        assert_eq!(undirected.destroy(undirected1, undirected2), (2, 11));
+        assert!(group.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_rejects_wrong_type() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, _read_only, _writable) = DirectedChannel::create(0i32, 0);
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let id = group.push_directed(&channel_key, "directed", channel_pointer);
+
+        let _channel_pointer: Option<DirectedChannelPointer<u8>> = group.remove(&channel_key, id);
+    }
+
+    #[test]
+    fn insert_and_remove_across_phases() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut random = 3u64;
+        let mut next_random = || {
+            random = random
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            random >> 33
+        };
+        let mut group = ChannelGroup::new();
+        let mut channels = HashMap::new();
+        let mut removed = Vec::new();
+
+        for phase in 0..100u64 {
+            let channel_key = master_key.get_channel_key();
+            for _ in 0..next_random() % 10 {
+                let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+                let id = group.push_directed(&channel_key, "directed", channel_pointer);
+                assert!(channels.insert(id, (read_only, writable)).is_none());
+            }
+            let ids: Vec<_> = channels.keys().copied().collect();
+            for id in ids {
+                if next_random() % 4 == 0 {
+                    let channel_pointer: DirectedChannelPointer<u64> =
+                        group.remove(&channel_key, id).unwrap();
+                    let (read_only, writable) = channels.remove(&id).unwrap();
+                    channel_pointer.destroy_single(read_only, writable);
+                    removed.push(id);
+                }
+            }
+            for &id in &removed {
+                assert!(group
+                    .remove::<DirectedChannelPointer<u64>>(&channel_key, id)
+                    .is_none());
+            }
+            assert_eq!(group.len(), channels.len());
+            assert!(group.iter().all(|(id, _, _)| channels.contains_key(&id)));
+
+            let data_key = channel_key.into_data_key();
+            for (_, writable) in channels.values_mut() {
+                *writable.get_mut(&data_key) = phase;
+            }
+            group.advance_all(&data_key.into_channel_key());
+
+            let data_key = master_key.get_data_key();
+            for (read_only, _) in channels.values() {
+                assert_eq!(*read_only.get(&data_key), phase);
+            }
+        }
+
+        let channel_key = master_key.get_channel_key();
+        for (id, (read_only, writable)) in channels {
+            let channel_pointer: DirectedChannelPointer<u64> =
+                group.remove(&channel_key, id).unwrap();
+            channel_pointer.destroy_single(read_only, writable);
+        }
+        assert!(group.is_empty());
+    }
+
+    #[test]
+    fn push_any_swap_channel() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (undirected, undirected1, undirected2) = UndirectedChannel::create(1, 2);
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let boxed: Box<dyn UndirectedSwapChannel> = Box::new(undirected);
+        let id = group.push(&channel_key, "boxed", boxed);
+        assert!(group.iter().eq([(id, "boxed", ChannelKind::Other)]));
+
+        group.advance_all(&channel_key);
+        let data_key = channel_key.into_data_key();
+        assert_eq!(*undirected1.get(&data_key), 2);
+
+        let boxed: Box<dyn UndirectedSwapChannel> =
+            group.remove(&data_key.into_channel_key(), id).unwrap();
+        assert_eq!(boxed.swap_count(&master_key.get_channel_key()), 1);
+        // Trait objects cannot be destroyed, so keep the channel alive instead of dropping it with live data pointers.
+        std::mem::forget((boxed, undirected1, undirected2));
+    }
+
+    #[test]
+    fn destroy_erased() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (undirected, undirected1, undirected2) = UndirectedChannel::create(1, 2);
+        let (directed, read_only, writable) = DirectedChannel::create(3, 4);
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let erased: Box<dyn ErasedDestroy> = Box::new(undirected);
+        let undirected = group.push(&channel_key, "undirected", erased);
+        let directed = group.push_directed(&channel_key, "directed", directed);
+
+        assert_eq!(
+            group
+                .destroy(&channel_key, directed, vec![writable.erase()])
+                .err(),
+            Some(DestroyError::NotErased)
+        );
+        let values = group
+            .destroy(
+                &channel_key,
+                undirected,
+                vec![undirected1.erase(), undirected2.erase()],
+            )
+            .unwrap();
+        assert_eq!(values[0].downcast_ref(), Some(&1));
+        assert_eq!(values[1].downcast_ref(), Some(&2));
+        assert_eq!(
+            group.destroy(&channel_key, undirected, Vec::new()).err(),
+            Some(DestroyError::UnknownChannel)
+        );
+        assert_eq!(group.len(), 1);
+        let directed: DirectedChannelPointer<i32> = group.remove(&channel_key, directed).unwrap();
+        let _ = (directed, read_only);
+    }
+
+    #[test]
+    fn visit_data() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (directed, read_only, mut writable) = DirectedChannel::create(vec![1.0f32], vec![]);
+        let (undirected, undirected1, undirected2) = UndirectedChannel::create(1u32, 2);
+        let undirected1 = undirected1.into_immutable();
+        let invalid = Arc::new(Mutex::new(Vec::new()));
+
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let directed_id = group.push_directed(&channel_key, "directed", directed);
+        let undirected_id = group.push_undirected(&channel_key, "undirected", undirected);
+        let invalid_directed = invalid.clone();
+        assert!(
+            group.set_validator(&channel_key, directed_id, read_only, move |data| {
+                let data: &Vec<f32> = data.downcast_ref().unwrap();
+                if data.iter().any(|value| value.is_nan()) {
+                    invalid_directed.lock().unwrap().push("directed");
+                }
+            })
+        );
+        let invalid_undirected = invalid.clone();
+        assert!(
+            group.set_validator(&channel_key, undirected_id, undirected1, move |data| {
+                if data.downcast_ref::<u32>() != Some(&1) {
+                    invalid_undirected.lock().unwrap().push("undirected");
+                }
+            })
+        );
+
+        let data_key = channel_key.into_data_key();
+        group.visit_data(&data_key);
+        assert!(invalid.lock().unwrap().is_empty());
+        writable.get_mut(&data_key).push(f32::NAN);
+        let channel_key = data_key.into_channel_key();
+        group.advance_all(&channel_key);
+        group.visit_data(&channel_key.into_data_key());
+        assert_eq!(*invalid.lock().unwrap(), ["directed", "undirected"]);
+
+        let channel_key = master_key.get_channel_key();
+        let directed: DirectedChannelPointer<Vec<f32>> =
+            group.remove(&channel_key, directed_id).unwrap();
+        assert!(!group.set_validator(&channel_key, directed_id, read_only, |_| {}));
+        let undirected: UndirectedChannelPointer<u32> =
+            group.remove(&channel_key, undirected_id).unwrap();
+        directed.destroy([read_only], writable);
+        undirected.destroy_immutable(undirected2, [undirected1]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_advance_all() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let mut channels: Vec<_> = (0..100)
+            .map(|_| {
+                let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+                let id = group.push_directed(&channel_key, "directed", channel_pointer);
+                (id, read_only, writable)
+            })
+            .collect();
+
+        let data_key = channel_key.into_data_key();
+        for (index, (_, _, writable)) in channels.iter_mut().enumerate() {
+            *writable.get_mut(&data_key) = index;
+        }
+        group.par_advance_all(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        for (index, (_, read_only, _)) in channels.iter().enumerate() {
+            assert_eq!(*read_only.get(&data_key), index);
+        }
+
+        let channel_key = data_key.into_channel_key();
+        for (id, read_only, writable) in channels {
+            let channel_pointer: DirectedChannelPointer<usize> =
+                group.remove(&channel_key, id).unwrap();
+            channel_pointer.destroy_single(read_only, writable);
+        }
+    }
+
+    /// A channel that logs its label whenever it is advanced.
+    struct Logged {
+        label: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl SwapChannel for Logged {
+        fn advance(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+            self.log.lock().unwrap().push(self.label);
+        }
+    }
+
+    /// Add a logged channel per label to the group, returning their ids by label.
+    fn push_logged(
+        group: &mut ChannelGroup,
+        channel_key: &ChannelKey,
+        log: &Arc<Mutex<Vec<&'static str>>>,
+        labels: &[&'static str],
+    ) -> HashMap<&'static str, GroupId> {
+        labels
+            .iter()
+            .map(|&label| {
+                let channel = Logged {
+                    label,
+                    log: log.clone(),
+                };
+                (label, group.push(channel_key, label, channel))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn advance_after() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let channel_key = master_key.get_channel_key();
+
+        // Pinning the order makes it independent of the insertion order.
+        for labels in [["a", "b", "c"], ["c", "b", "a"], ["b", "c", "a"]] {
+            let log = Arc::default();
+            let mut group = ChannelGroup::new();
+            let ids = push_logged(&mut group, &channel_key, &log, &labels);
+            group
+                .advance_after(&channel_key, ids["c"], ids["b"])
+                .unwrap();
+            group
+                .advance_after(&channel_key, ids["b"], ids["a"])
+                .unwrap();
+            group.advance_all(&channel_key);
+            assert_eq!(*log.lock().unwrap(), ["a", "b", "c"]);
+        }
+
+        let log = Arc::default();
+        let mut group = ChannelGroup::new();
+        let ids = push_logged(&mut group, &channel_key, &log, &["a", "b", "c", "d"]);
+        group
+            .advance_after(&channel_key, ids["a"], ids["c"])
+            .unwrap();
+        group
+            .advance_after(&channel_key, ids["c"], ids["b"])
+            .unwrap();
+        assert_eq!(
+            group.advance_after(&channel_key, ids["b"], ids["a"]),
+            Err(OrderError::Cycle)
+        );
+        assert_eq!(
+            group.advance_after(&channel_key, ids["d"], ids["d"]),
+            Err(OrderError::Cycle)
+        );
+        // Unpinned channels keep the insertion order, unless they have a higher priority.
+        assert!(group.set_priority(&channel_key, ids["d"], 1));
+        group.advance_all(&channel_key);
+        assert_eq!(*log.lock().unwrap(), ["d", "b", "c", "a"]);
+
+        // Removing a channel drops its constraints.
+        group.remove::<Logged>(&channel_key, ids["c"]).unwrap();
+        assert_eq!(
+            group.advance_after(&channel_key, ids["c"], ids["b"]),
+            Err(OrderError::UnknownChannel)
+        );
+        group
+            .advance_after(&channel_key, ids["b"], ids["a"])
+            .unwrap();
+        assert_eq!(
+            group.advance_order().collect::<Vec<_>>(),
+            [ids["d"], ids["a"], ids["b"]]
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_advance_all_in_waves() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let channel_key = master_key.get_channel_key();
+        let log = Arc::default();
+        let mut group = ChannelGroup::new();
+        let ids = push_logged(&mut group, &channel_key, &log, &["c", "b", "a", "x"]);
+        group
+            .advance_after(&channel_key, ids["c"], ids["b"])
+            .unwrap();
+        group
+            .advance_after(&channel_key, ids["b"], ids["a"])
+            .unwrap();
+        group.par_advance_all(&channel_key);
+
+        let log = log.lock().unwrap();
+        let index = |label| log.iter().position(|&logged| logged == label).unwrap();
+        assert_eq!(log.len(), 4);
+        assert!(index("a") < index("b") && index("b") < index("c"));
+    }
+
+    #[cfg(feature = "checkpoint")]
+    #[test]
+    fn checkpoint() {
+        use std::io;
+
+        use crate::checkpoint::CheckpointError;
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (undirected, mut undirected1, undirected2) = UndirectedChannel::create(1u32, 2);
+        let (directed, read_only, mut writable) = DirectedChannel::create(vec![], vec![]);
+        let (bidirected, bidirected1, mut bidirected2) =
+            BidirectedChannel::create(0u8, 0, String::new(), String::new());
+        let (unchecked, unchecked_read_only, mut unchecked_writable) =
+            DirectedChannel::create(0, 0);
+
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let ids = [
+            group.push_undirected(&channel_key, "undirected", undirected),
+            group.push_directed(&channel_key, "directed", directed),
+            group.push_bidirected(&channel_key, "bidirected", bidirected),
+            group.push_directed(&channel_key, "unchecked", unchecked),
+        ];
+        assert!(group.enable_checkpoint::<UndirectedChannelPointer<u32>>(&channel_key, ids[0]));
+        assert!(
+            group.enable_checkpoint::<DirectedChannelPointer<Vec<String>>>(&channel_key, ids[1])
+        );
+        assert!(
+            group.enable_checkpoint::<BidirectedChannelPointer<u8, String>>(&channel_key, ids[2])
+        );
+
+        let data_key = channel_key.into_data_key();
+        writable.get_mut(&data_key).push(String::from("saved"));
+        *bidirected2.get_output(&data_key) = 7;
+        group.advance_all(&data_key.into_channel_key());
+        let mut blob = Vec::new();
+        group
+            .checkpoint(&master_key.get_channel_key(), &mut blob)
+            .unwrap();
+
+        let data_key = master_key.get_data_key();
+        *undirected1.get_mut(&data_key) = 10;
+        writable.get_mut(&data_key).clear();
+        *bidirected2.get_output(&data_key) = 8;
+        *unchecked_writable.get_mut(&data_key) = 9;
+        group.advance_all(&data_key.into_channel_key());
+        group
+            .restore(&master_key.get_channel_key(), blob.as_slice())
+            .unwrap();
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*undirected1.get(&data_key), 2);
+        assert_eq!(*undirected2.get(&data_key), 1);
+        assert_eq!(*read_only.get(&data_key), ["saved"]);
+        assert_eq!(*writable.get(&data_key), ["saved"]);
+        assert_eq!(*bidirected1.get_input(&data_key), 7);
+        // Channels that were not included keep their contents.
+        assert_eq!(*unchecked_read_only.get(&data_key), 9);
+
+        // A group with a different set of channels rejects the checkpoint, and is unchanged.
+        let channel_key = data_key.into_channel_key();
+        let removed: DirectedChannelPointer<Vec<String>> =
+            group.remove(&channel_key, ids[1]).unwrap();
+        let error = group.restore(&channel_key, blob.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.into_inner().unwrap().downcast_ref(),
+            Some(&CheckpointError::WrongChannelCount {
+                expected: 2,
+                found: 3
+            })
+        );
+        let error = group.restore(&channel_key, &blob[..20]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        let error = group.restore(&channel_key, &b"checkpoint"[..]).unwrap_err();
+        assert_eq!(
+            error.into_inner().unwrap().downcast_ref(),
+            Some(&CheckpointError::NotACheckpoint)
+        );
+        let _ = (removed, unchecked_read_only, unchecked_writable);
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (undirected, undirected1, undirected2) = UndirectedChannel::create(0u8, 0);
+        let (directed, read_only, mut writable) = DirectedChannel::create(0u32, 0);
+        let (other, other1, other2) = UndirectedChannel::create(0u8, 0);
+
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        group.push_undirected(&channel_key, "swap \"quoted\"", undirected);
+        group.push_directed(&channel_key, "frame", directed);
+        group.push(
+            &channel_key,
+            "other",
+            Box::new(other) as Box<dyn SwapChannel>,
+        );
+        let data_key = channel_key.into_data_key();
+        *writable.get_mut(&data_key) = 1;
+
+        assert_eq!(
+            group.to_dot(&data_key.into_channel_key()),
+            "digraph {\n    node [shape=box];\n    \
+             channel0 [label=\"swap \\\"quoted\\\"\\nundirected\\nu8\\ngeneration 0\"];\n    \
+             channel1 [label=\"frame\\ndirected\\nu32\\ngeneration 0\\ndirty\"];\n    \
+             channel2 [label=\"other\\nother\\nu8\\ngeneration 0\"];\n\
+             }\n"
+        );
+        let _ = (undirected1, undirected2, read_only, other1, other2);
+    }
+}
@@ -0,0 +1,72 @@
+//! Support for constructing channels with large payloads directly on the heap,
+//! without ever materialising the payloads on the stack.
+//! See for example [`UndirectedChannel::create_boxed_zeroed`](crate::undirected::UndirectedChannel::create_boxed_zeroed).
+
+use core::ptr::NonNull;
+#[cfg(feature = "zeroize")]
+use core::{
+    mem, ptr,
+    sync::atomic::{self, Ordering},
+};
+use std::alloc::{self, Layout};
+
+/// Types for which a value consisting only of zero bytes is valid.
+///
+/// # Safety
+///
+/// Implementors must ensure that the all-zero bit pattern is a valid value of the type.
+pub unsafe trait Zeroable {}
+
+unsafe impl Zeroable for () {}
+unsafe impl Zeroable for bool {}
+unsafe impl Zeroable for char {}
+unsafe impl Zeroable for u8 {}
+unsafe impl Zeroable for u16 {}
+unsafe impl Zeroable for u32 {}
+unsafe impl Zeroable for u64 {}
+unsafe impl Zeroable for u128 {}
+unsafe impl Zeroable for usize {}
+unsafe impl Zeroable for i8 {}
+unsafe impl Zeroable for i16 {}
+unsafe impl Zeroable for i32 {}
+unsafe impl Zeroable for i64 {}
+unsafe impl Zeroable for i128 {}
+unsafe impl Zeroable for isize {}
+unsafe impl Zeroable for f32 {}
+unsafe impl Zeroable for f64 {}
+unsafe impl<T: Zeroable, const N: usize> Zeroable for [T; N] {}
+
+/// Allocate memory for a `T` with the global allocator, suitable for [Box::from_raw] once it is initialised.
+/// If `zeroed` is `true`, the memory is zeroed.
+pub(crate) fn allocate<T>(zeroed: bool) -> *mut T {
+    let layout = Layout::new::<T>();
+    if layout.size() == 0 {
+        return NonNull::dangling().as_ptr();
+    }
+
+    let pointer = unsafe {
+        if zeroed {
+            alloc::alloc_zeroed(layout)
+        } else {
+            alloc::alloc(layout)
+        }
+    };
+    if pointer.is_null() {
+        alloc::handle_alloc_error(layout);
+    }
+    pointer as *mut T
+}
+
+/// Overwrite the memory of a `T` with zeros in a way that is not optimised away, e.g. right before the memory is freed.
+///
+/// # Safety
+///
+/// The pointer must be valid for writes, and the `T` must not be used afterwards, except for freeing its memory.
+#[cfg(feature = "zeroize")]
+pub(crate) unsafe fn wipe<T>(pointer: *mut T) {
+    let bytes = pointer as *mut u8;
+    for offset in 0..mem::size_of::<T>() {
+        ptr::write_volatile(bytes.add(offset), 0);
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,198 @@
+//! A bidirected two-phase channel that detects stalled endpoints.
+//! Each direction carries a heartbeat counter next to its payload,
+//! which counts the flushes since the writing endpoint last accessed its output.
+
+use core::marker::PhantomData;
+
+use crate::{
+    bidirected::{BidirectedChannel, BidirectedChannelPointer, BidirectedDataPointer},
+    ChannelKey, DataKey,
+};
+
+/// A bidirected channel used for communication between threads that are expected to update their output in every data phase.
+///
+/// See [HeartbeatChannel::create] for more info.
+#[derive(Debug)]
+pub struct HeartbeatChannel<Data1, Data2> {
+    phantom: PhantomData<(Data1, Data2)>,
+}
+
+/// A payload together with the number of flushes since it was last written.
+#[derive(Debug, Clone)]
+struct Beat<Data> {
+    payload: Data,
+    phases_since_update: u64,
+}
+
+/// A pointer to a heartbeat channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [HeartbeatChannel::destroy] or [HeartbeatChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct HeartbeatChannelPointer<Data1, Data2> {
+    channel_pointer: BidirectedChannelPointer<Beat<Data1>, Beat<Data2>>,
+}
+
+/// A pair of pointers to the data fields of a heartbeat channel, see [BidirectedDataPointer].
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [HeartbeatChannel::destroy] or [HeartbeatChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct HeartbeatDataPointer<Input, Output> {
+    data_pointer: BidirectedDataPointer<Beat<Input>, Beat<Output>>,
+}
+
+impl<Data> Beat<Data> {
+    fn new(payload: Data) -> Self {
+        Self {
+            payload,
+            phases_since_update: 0,
+        }
+    }
+}
+
+impl<Data1, Data2> HeartbeatChannel<Data1, Data2> {
+    /// Create a heartbeat channel and hand out three pointers to it, like [BidirectedChannel::create].
+    ///
+    /// Every access to the output of a [HeartbeatDataPointer] counts as an update of its direction.
+    /// On each flush, the heartbeat counter of a direction is reset to zero if it was updated in the preceding data phase,
+    /// and incremented otherwise (see [HeartbeatDataPointer::phases_since_peer_update]).
+    pub fn create(
+        read_only1: Data1,
+        writable1: Data1,
+        read_only2: Data2,
+        writable2: Data2,
+    ) -> (
+        HeartbeatChannelPointer<Data1, Data2>,
+        HeartbeatDataPointer<Data1, Data2>,
+        HeartbeatDataPointer<Data2, Data1>,
+    ) {
+        let (channel_pointer, data_pointer1, data_pointer2) = BidirectedChannel::create(
+            Beat::new(read_only1),
+            Beat::new(writable1),
+            Beat::new(read_only2),
+            Beat::new(writable2),
+        );
+        (
+            HeartbeatChannelPointer { channel_pointer },
+            HeartbeatDataPointer {
+                data_pointer: data_pointer1,
+            },
+            HeartbeatDataPointer {
+                data_pointer: data_pointer2,
+            },
+        )
+    }
+
+    /// Destroys the heartbeat channel linked with the three pointers (see [HeartbeatChannel::create]).
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: HeartbeatChannelPointer<Data1, Data2>,
+        data_pointer1: HeartbeatDataPointer<Data1, Data2>,
+        data_pointer2: HeartbeatDataPointer<Data2, Data1>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        let (read_only1, writable1, read_only2, writable2) = channel_pointer
+            .channel_pointer
+            .destroy(data_pointer1.data_pointer, data_pointer2.data_pointer);
+        (
+            read_only1.payload,
+            writable1.payload,
+            read_only2.payload,
+            writable2.payload,
+        )
+    }
+}
+
+impl<Data1: Clone, Data2: Clone> HeartbeatChannelPointer<Data1, Data2> {
+    /// Clone the writable `Data`s into the read-only `Data`s, and update the heartbeat counters (see [HeartbeatChannel::create]).
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        let (forward_updated, backward_updated) = self.channel_pointer.is_dirty(channel_key);
+        self.channel_pointer.flush(channel_key);
+
+        let beat = |published: &mut u64, updated: bool| {
+            *published = if updated { 0 } else { *published + 1 };
+        };
+        let (forward, backward) = self.channel_pointer.published_mut();
+        beat(&mut forward.phases_since_update, forward_updated);
+        beat(&mut backward.phases_since_update, backward_updated);
+    }
+}
+
+impl<Data1, Data2> HeartbeatChannelPointer<Data1, Data2> {
+    /// Returns for the forward and the backward direction, in this order, the number of flushes since the writing data pointer last accessed its output.
+    /// The forward direction is written by the second data pointer, and the backward direction by the first.
+    pub fn phases_since_update(&self, channel_key: &ChannelKey) -> (u64, u64) {
+        self.channel_pointer
+            .inspect(channel_key, |forward, _, backward, _| {
+                (forward.phases_since_update, backward.phases_since_update)
+            })
+    }
+
+    /// Shorthand for [HeartbeatChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: HeartbeatDataPointer<Data1, Data2>,
+        data_pointer2: HeartbeatDataPointer<Data2, Data1>,
+    ) -> (Data1, Data1, Data2, Data2) {
+        HeartbeatChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+}
+
+impl<Input, Output> HeartbeatDataPointer<Input, Output> {
+    /// Get a reference to the input data field pointed to by this pointer.
+    pub fn get_input(&self, data_key: &DataKey) -> &Input {
+        &self.data_pointer.get_input(data_key).payload
+    }
+
+    /// Get a mutable reference to the output data field pointed to by this pointer.
+    /// This counts as an update of the output, see [HeartbeatChannel::create].
+    pub fn get_output(&mut self, data_key: &DataKey) -> &mut Output {
+        &mut self.data_pointer.get_output(data_key).payload
+    }
+
+    /// The number of flushes since the other data pointer last accessed its output, as of the last flush.
+    /// A stalled peer can be detected by this number growing.
+    pub fn phases_since_peer_update(&self, data_key: &DataKey) -> u64 {
+        self.data_pointer.get_input(data_key).phases_since_update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{heartbeat::HeartbeatChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            HeartbeatChannel::create(0, 0, 0, 0);
+
+        for phase in 1..=4 {
+            let data_key = master_key.get_data_key();
+            *data_pointer1.get_output(&data_key) = phase;
+            // The second data pointer stalls after the first phase.
+            if phase == 1 {
+                *data_pointer2.get_output(&data_key) = phase;
+            }
+            channel_pointer.flush(&data_key.into_channel_key());
+
+            let data_key = master_key.get_data_key();
+            assert_eq!(data_pointer1.phases_since_peer_update(&data_key), phase - 1);
+            assert_eq!(data_pointer2.phases_since_peer_update(&data_key), 0);
+            assert_eq!(*data_pointer1.get_input(&data_key), 1);
+            assert_eq!(*data_pointer2.get_input(&data_key), phase);
+        }
+        assert_eq!(
+            channel_pointer.phases_since_update(&master_key.get_channel_key()),
+            (3, 0)
+        );
+
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (1, 1, 4, 4)
+        );
+    }
+}
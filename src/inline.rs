@@ -0,0 +1,287 @@
+//! Undirected channels for small payloads, such as a `u32` or a small enum, whose `Data` fields live inside the channel itself instead of on the heap.
+//!
+//! An [InlineUndirectedChannel] is a plain value that can be stored wherever the caller likes, e.g. on the stack or many of them in a single slice.
+//! Since the data pointers point into the channel, it must not move while they exist.
+//! The channel is hence only usable when pinned, and hands out data pointers via [InlineUndirectedChannel::data_pointers],
+//! which must be returned via [InlineUndirectedChannel::release] before the channel is dropped.
+//!
+//! Misuse is caught as far as possible:
+//!
+//!  * moving a pinned channel does not compile,
+//!  * handing out data pointers twice, or releasing data pointers of another channel, **panics**,
+//!  * dropping a channel while its data pointers exist **panics** and then aborts the process, since unwinding would let the data pointers dangle, and
+//!  * with the `checked-backend` feature, accessing a data pointer after it was released **panics**.
+//!
+//! ```compile_fail
+//! use std::pin::Pin;
+//! use two_phase_channel::inline::InlineUndirectedChannel;
+//!
+//! let mut channel = Box::pin(InlineUndirectedChannel::new(1u32, 2u32));
+//! let (data_pointer1, data_pointer2) = channel.as_mut().data_pointers();
+//! // The channel cannot be moved out of its pin.
+//! let moved: InlineUndirectedChannel<u32> = *Pin::into_inner(channel);
+//! ```
+
+use core::{cell::UnsafeCell, fmt, marker::PhantomPinned, mem, pin::Pin, ptr};
+use std::process;
+
+use crate::{
+    checked::{CheckedChannel, CheckedPointer},
+    common, ChannelKey, DataKey,
+};
+
+/// The maximum size and alignment in bytes of the `Data` of an [InlineUndirectedChannel].
+/// Larger `Data` should use a heap-allocated [UndirectedChannel](crate::undirected::UndirectedChannel),
+/// which swaps two pointers instead of the whole `Data`.
+pub const MAX_INLINE_SIZE: usize = 64;
+
+/// An undirected channel whose `Data` fields are stored inline, used for communication between threads.
+/// It behaves like an [`UndirectedChannel`](crate::undirected::UndirectedChannel), but is its own channel pointer.
+///
+/// See the [module documentation](self) for more info.
+pub struct InlineUndirectedChannel<Data> {
+    data: [UnsafeCell<Data>; 2],
+    /// `true` while the data pointers exist.
+    handed_out: bool,
+    /// Replaced on release, such that the released data pointers are recognised by the `checked-backend`.
+    checked: CheckedChannel,
+    _pinned: PhantomPinned,
+}
+
+/// A pointer to one of the data fields in an inline undirected channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type must be returned to its channel via [InlineUndirectedChannel::release] before the channel is dropped.
+#[derive(Debug)]
+#[must_use]
+pub struct InlineUndirectedDataPointer<Data> {
+    data: *mut Data,
+    checked: CheckedPointer,
+}
+
+impl<Data> InlineUndirectedChannel<Data> {
+    /// Create an inline undirected channel with the given `Data` fields.
+    /// The channel must be pinned before its data pointers can be handed out, e.g. via [Box::pin] or [core::pin::pin].
+    ///
+    /// **Panics** if the size or alignment of `Data` exceeds [MAX_INLINE_SIZE].
+    pub fn new(data1: Data, data2: Data) -> Self {
+        assert!(
+            mem::size_of::<Data>() <= MAX_INLINE_SIZE && mem::align_of::<Data>() <= MAX_INLINE_SIZE,
+            "the data of an inline channel must not be larger than {} bytes",
+            MAX_INLINE_SIZE
+        );
+        Self {
+            data: [UnsafeCell::new(data1), UnsafeCell::new(data2)],
+            handed_out: false,
+            checked: CheckedChannel::new(),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Hand out the two data pointers to the pinned channel, one to each `Data` field.
+    ///
+    /// **Panics** if the data pointers were handed out before and not released yet.
+    pub fn data_pointers(
+        self: Pin<&mut Self>,
+    ) -> (
+        InlineUndirectedDataPointer<Data>,
+        InlineUndirectedDataPointer<Data>,
+    ) {
+        // The fields are not moved.
+        let this = unsafe { self.get_unchecked_mut() };
+        assert!(
+            !mem::replace(&mut this.handed_out, true),
+            "the data pointers of the inline channel were already handed out"
+        );
+        let checked = this.checked.pointer();
+        let pointer = |index: usize| InlineUndirectedDataPointer {
+            data: this.data[index].get(),
+            checked,
+        };
+        (pointer(0), pointer(1))
+    }
+
+    /// Swap the two `Data` fields of the channel.
+    /// This moves the `Data` fields, which is cheap since they are small.
+    pub fn swap(self: Pin<&Self>, #[allow(unused)] channel_key: &ChannelKey) {
+        self.checked.advance();
+        unsafe { ptr::swap(self.data[0].get(), self.data[1].get()) };
+    }
+
+    /// Take back the data pointers handed out via [InlineUndirectedChannel::data_pointers], such that the channel can be dropped,
+    /// and return references to the `Data` fields they pointed to, in the same order.
+    ///
+    /// **Panics** if the data pointers do not point to this channel, or if both point to the same `Data` field.
+    pub fn release(
+        self: Pin<&mut Self>,
+        data_pointer1: InlineUndirectedDataPointer<Data>,
+        data_pointer2: InlineUndirectedDataPointer<Data>,
+    ) -> (&Data, &Data) {
+        // The fields are not moved.
+        let this = unsafe { self.get_unchecked_mut() };
+        let index = |data_pointer: &InlineUndirectedDataPointer<Data>| {
+            this.data
+                .iter()
+                .position(|data| ptr::eq(data.get(), data_pointer.data))
+                .expect("a data pointer does not point to this inline channel")
+        };
+        let (index1, index2) = (index(&data_pointer1), index(&data_pointer2));
+        assert_ne!(
+            index1, index2,
+            "both data pointers point to the same data field"
+        );
+
+        this.handed_out = false;
+        // Invalidate copies of the data pointers for the `checked-backend`.
+        this.checked = CheckedChannel::new();
+        // No data pointer exists anymore.
+        let (data1, data2) = unsafe { (&*this.data[index1].get(), &*this.data[index2].get()) };
+        (data1, data2)
+    }
+
+    /// Pin each channel of the given pinned slice, e.g. of many channels stored in a single `Pin<Box<[_]>>`.
+    pub fn iter_pinned(channels: Pin<&[Self]>) -> impl Iterator<Item = Pin<&Self>> {
+        // The channels of a pinned slice never move either.
+        channels
+            .get_ref()
+            .iter()
+            .map(|channel| unsafe { Pin::new_unchecked(channel) })
+    }
+
+    /// Pin each channel of the given mutably pinned slice, e.g. to hand out or release the data pointers of many channels.
+    pub fn iter_pinned_mut(channels: Pin<&mut [Self]>) -> impl Iterator<Item = Pin<&mut Self>> {
+        // The channels of a pinned slice never move either.
+        unsafe { channels.get_unchecked_mut() }
+            .iter_mut()
+            .map(|channel| unsafe { Pin::new_unchecked(channel) })
+    }
+}
+
+impl<Data> fmt::Debug for InlineUndirectedChannel<Data> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InlineUndirectedChannel")
+            .field("handed_out", &self.handed_out)
+            .finish()
+    }
+}
+
+impl<Data> Drop for InlineUndirectedChannel<Data> {
+    /// **Panics** and then aborts the process if the data pointers were not released,
+    /// since unwinding would free the `Data` fields while the data pointers still exist.
+    fn drop(&mut self) {
+        if self.handed_out {
+            let _abort = AbortOnUnwind;
+            panic!("an inline channel was dropped while its data pointers still exist");
+        }
+    }
+}
+
+/// Aborts the process when dropped, i.e. when unwinding past it.
+struct AbortOnUnwind;
+
+impl Drop for AbortOnUnwind {
+    fn drop(&mut self) {
+        process::abort();
+    }
+}
+
+impl<Data> InlineUndirectedDataPointer<Data> {
+    common::read_accessors!();
+    common::write_accessors!();
+
+    fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+
+    fn access_mut(&mut self, data_key: &DataKey) {
+        self.access(data_key);
+    }
+}
+
+// Handing out and releasing the data pointers requires a mutable reference,
+// hence the only change through a shared reference is `swap`, which requires the channel key.
+unsafe impl<Data: Send> Sync for InlineUndirectedChannel<Data> {}
+unsafe impl<Data: Send> Send for InlineUndirectedDataPointer<Data> {}
+unsafe impl<Data: Sync> Sync for InlineUndirectedDataPointer<Data> {}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        thread,
+    };
+
+    use crate::{inline::InlineUndirectedChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut channels: std::pin::Pin<Box<[_]>> = (0..100u32)
+            .map(|i| InlineUndirectedChannel::new(i, 0))
+            .collect::<Box<[_]>>()
+            .into();
+        let (mut writers, readers): (Vec<_>, Vec<_>) =
+            InlineUndirectedChannel::iter_pinned_mut(channels.as_mut())
+                .map(|channel| channel.data_pointers())
+                .unzip();
+
+        let reader = thread::spawn(move || readers);
+        let readers = reader.join().unwrap();
+        let data_key = master_key.get_data_key();
+        for writer in &mut writers {
+            *writer.get_mut(&data_key) += 1;
+        }
+        let channel_key = data_key.into_channel_key();
+        for channel in InlineUndirectedChannel::iter_pinned(channels.as_ref()) {
+            channel.swap(&channel_key);
+        }
+        let data_key = channel_key.into_data_key();
+        for (i, reader) in readers.iter().enumerate() {
+            assert_eq!(*reader.get(&data_key), i as u32 + 1);
+        }
+
+        let channels = InlineUndirectedChannel::iter_pinned_mut(channels.as_mut());
+        for (i, ((writer, reader), channel)) in
+            writers.into_iter().zip(readers).zip(channels).enumerate()
+        {
+            assert_eq!(channel.release(reader, writer), (&(i as u32 + 1), &0));
+        }
+    }
+
+    #[test]
+    fn wrong_usage_panics() {
+        let mut channel1 = Box::pin(InlineUndirectedChannel::new(1u8, 2));
+        let mut channel2 = Box::pin(InlineUndirectedChannel::new(3u8, 4));
+        let (data_pointer1, data_pointer2) = channel1.as_mut().data_pointers();
+        let handed_out_twice =
+            panic::catch_unwind(AssertUnwindSafe(|| channel1.as_mut().data_pointers()));
+        assert!(handed_out_twice.is_err());
+
+        let (data_pointer3, data_pointer4) = channel2.as_mut().data_pointers();
+        let foreign = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = channel2.as_mut().release(data_pointer1, data_pointer2);
+        }));
+        assert!(foreign.is_err());
+        // The channel stays handed out, hence leak it instead of aborting.
+        std::mem::forget(channel1);
+        assert_eq!(
+            channel2.as_mut().release(data_pointer4, data_pointer3),
+            (&4, &3)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "checked-backend")]
+    #[should_panic(expected = "a data pointer was used after its channel was destroyed")]
+    fn use_after_release_panics() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut channel = Box::pin(InlineUndirectedChannel::new(1u8, 2));
+        let (data_pointer1, data_pointer2) = channel.as_mut().data_pointers();
+        let copy = crate::inline::InlineUndirectedDataPointer {
+            data: data_pointer1.data,
+            checked: data_pointer1.checked,
+        };
+        channel.as_mut().release(data_pointer1, data_pointer2);
+        copy.get(&master_key.get_data_key());
+    }
+}
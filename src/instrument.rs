@@ -0,0 +1,223 @@
+//! Instrumentation of channel operations, i.e. the `metrics` and `tracing` features.
+//! Without these features, the functions here only run the given operation and compile away.
+//!
+//! With the `tracing` feature, each flush or swap emits a `DEBUG` event with target `two_phase_channel` and the fields
+//! `operation` (`"flush"` or `"swap"`), `channel` (the label, if any), `direction` (`"forward"` or `"backward"` for bidirected channels),
+//! `generation` (after the operation), `size_hint` (the size of `Data` in bytes) and `skipped` (`true` if the channel was clean or the swap condition did not hold).
+//! These field names are a stable contract.
+
+use crate::Label;
+
+/// The target of all spans and events emitted with the `tracing` feature.
+#[cfg(feature = "tracing")]
+pub(crate) const TARGET: &str = "two_phase_channel";
+
+/// Run the given flush, which returns `true` if the channel was flushed and `false` if it was skipped,
+/// together with the generation of the channel afterwards.
+/// Returns whether the channel was flushed.
+#[inline]
+pub(crate) fn flush(
+    #[allow(unused)] label: &Label,
+    #[allow(unused)] direction: Option<&'static str>,
+    #[allow(unused)] size_hint: usize,
+    flush: impl FnOnce() -> (bool, u64),
+) -> bool {
+    #[cfg(feature = "metrics")]
+    #[allow(unused)]
+    let (flushed, generation) = {
+        let mut generation = 0;
+        let flushed = crate::metrics::record_flush(label, direction, || {
+            let (flushed, new_generation) = flush();
+            generation = new_generation;
+            flushed
+        });
+        (flushed, generation)
+    };
+    #[cfg(not(feature = "metrics"))]
+    #[allow(unused)]
+    let (flushed, generation) = flush();
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: TARGET,
+        operation = "flush",
+        channel = label.get(),
+        direction,
+        generation,
+        size_hint,
+        skipped = !flushed,
+    );
+    flushed
+}
+
+/// Record a swap of a channel, or a conditional swap that was skipped.
+#[inline]
+pub(crate) fn swap(
+    #[allow(unused)] label: &Label,
+    #[allow(unused)] size_hint: usize,
+    #[allow(unused)] generation: u64,
+    #[allow(unused)] skipped: bool,
+) {
+    #[cfg(feature = "metrics")]
+    if !skipped {
+        crate::metrics::record_swap(label);
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: TARGET,
+        operation = "swap",
+        channel = label.get(),
+        generation,
+        size_hint,
+        skipped,
+    );
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        fmt::Debug,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use crate::{
+        bidirected::BidirectedChannel, directed::DirectedChannel, undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    /// Captures the names of new spans and the fields of events.
+    #[derive(Default)]
+    struct CapturingSubscriber {
+        next_id: AtomicU64,
+        spans: Arc<Mutex<Vec<String>>>,
+        events: Arc<Mutex<Vec<Fields>>>,
+    }
+
+    /// The fields of an event, formatted as `name=value` and sorted by name.
+    #[derive(Default)]
+    struct Fields(BTreeMap<String, String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.insert(
+                field.name().to_string(),
+                format!("{}={:?}", field.name(), value),
+            );
+        }
+    }
+
+    impl Subscriber for CapturingSubscriber {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.spans
+                .lock()
+                .unwrap()
+                .push(span.metadata().name().to_string());
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            assert_eq!(event.metadata().target(), "two_phase_channel");
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            self.events.lock().unwrap().push(fields);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test() {
+        let subscriber = CapturingSubscriber::default();
+        let spans = subscriber.spans.clone();
+        let events = subscriber.events.clone();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut master_key = unsafe { MasterKey::create_unlimited() };
+            let (directed, read_only, writable) = DirectedChannel::create(0u32, 0);
+            let mut directed = directed.with_label("directed");
+            let (mut undirected, data_pointer1, data_pointer2) = UndirectedChannel::create(0u64, 1);
+            let (bidirected, bidirected1, bidirected2) = BidirectedChannel::create(0u8, 0, 0u16, 0);
+            let mut bidirected = bidirected.with_label("bidirected");
+
+            let data_key = master_key.get_data_key();
+            let channel_key = data_key.into_channel_key();
+            directed.flush(&channel_key);
+            directed.flush(&channel_key);
+            undirected.swap(&channel_key);
+            undirected.swap_if(&channel_key, |_, _| false);
+            bidirected.flush_forward(&channel_key);
+
+            directed.destroy([read_only], writable);
+            undirected.destroy(data_pointer1, data_pointer2);
+            bidirected.destroy(bidirected1, bidirected2);
+        });
+
+        assert_eq!(*spans.lock().unwrap(), ["data_phase", "channel_phase"]);
+        let events: Vec<Vec<String>> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|fields| fields.0.values().cloned().collect())
+            .collect();
+        assert_eq!(
+            events,
+            [
+                vec![
+                    "channel=\"directed\"",
+                    "generation=1",
+                    "operation=\"flush\"",
+                    "size_hint=4",
+                    "skipped=false"
+                ],
+                vec![
+                    "channel=\"directed\"",
+                    "generation=1",
+                    "operation=\"flush\"",
+                    "size_hint=4",
+                    "skipped=true"
+                ],
+                vec![
+                    "generation=1",
+                    "operation=\"swap\"",
+                    "size_hint=8",
+                    "skipped=false"
+                ],
+                vec![
+                    "generation=1",
+                    "operation=\"swap\"",
+                    "size_hint=8",
+                    "skipped=true"
+                ],
+                vec![
+                    "channel=\"bidirected\"",
+                    "direction=\"forward\"",
+                    "generation=1",
+                    "operation=\"flush\"",
+                    "size_hint=1",
+                    "skipped=false"
+                ],
+            ]
+        );
+    }
+}
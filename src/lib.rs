@@ -1,12 +1,322 @@
-use core::marker::PhantomData;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+#[cfg(feature = "std")]
+use core::fmt;
+#[allow(unused_imports)]
+use core::sync::atomic::AtomicBool;
+use core::{marker::PhantomData, sync::atomic::Ordering};
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+use checked::{CheckedMaster, KeyPhase};
+
+#[cfg(not(all(
+    feature = "portable",
+    target_arch = "wasm32",
+    not(target_feature = "atomics")
+)))]
 static MASTER_KEY_EXISTS: AtomicBool = AtomicBool::new(false);
+#[cfg(all(
+    feature = "portable",
+    target_arch = "wasm32",
+    not(target_feature = "atomics")
+))]
+use single_threaded::MASTER_KEY_EXISTS;
 
+#[cfg(feature = "std")]
+pub mod acknowledged;
+#[cfg(feature = "std")]
+pub mod allocator;
+#[cfg(feature = "std")]
+pub mod any;
+#[cfg(feature = "std")]
+pub mod arena;
+#[cfg(feature = "std")]
+pub mod array;
+#[cfg(all(feature = "std", feature = "bytemuck"))]
+pub mod atomic;
+#[cfg(feature = "std")]
 pub mod bidirected;
+#[cfg(feature = "std")]
+pub mod boxed;
+#[cfg(feature = "std")]
+pub mod bridge;
+#[cfg(feature = "std")]
+pub mod capacity;
+#[cfg(feature = "std")]
+pub mod cell;
+mod checked;
+#[cfg(feature = "checkpoint")]
+pub mod checkpoint;
+#[cfg(feature = "checksum")]
+mod checksum;
+mod common;
+#[cfg(feature = "std")]
 pub mod directed;
+#[cfg(feature = "std")]
+pub mod double_buffer;
+#[cfg(feature = "std")]
+pub mod erased;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod frame;
+#[cfg(feature = "std")]
+pub mod grid;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "std")]
+pub mod heap;
+#[cfg(feature = "std")]
+pub mod heartbeat;
+#[cfg(feature = "std")]
+pub mod inline;
+#[cfg(feature = "std")]
+mod instrument;
+#[cfg(feature = "std")]
+pub mod local;
+#[cfg(feature = "std")]
+pub mod mailbox;
+#[cfg(feature = "std")]
+pub mod mapped;
+#[cfg(all(feature = "std", feature = "metrics"))]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod notify;
+#[cfg(feature = "std")]
+pub mod observer;
+#[cfg(feature = "std")]
+pub mod ping_pong;
+#[cfg(feature = "std")]
+pub mod pinned;
+#[cfg(feature = "std")]
+pub mod pipeline;
+#[cfg(feature = "std")]
+pub mod publisher;
+#[cfg(feature = "std")]
+pub mod queue;
+#[cfg(feature = "std")]
+pub mod read_mostly;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod request_response;
+#[cfg(feature = "std")]
+pub mod ring;
+#[cfg(feature = "std")]
+pub mod rotating;
+#[cfg(feature = "std")]
+pub mod rt;
+#[cfg(feature = "std")]
+pub mod runner;
+pub mod scoped;
+#[cfg(feature = "std")]
+pub mod set;
+#[cfg(all(
+    feature = "portable",
+    target_arch = "wasm32",
+    not(target_feature = "atomics")
+))]
+mod single_threaded;
+#[cfg(feature = "async")]
+pub mod sink;
+#[cfg(feature = "std")]
+pub mod slice;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod split;
+#[cfg(feature = "std")]
+pub mod star;
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "std")]
+pub mod tick;
+#[cfg(feature = "std")]
+pub mod topology;
+#[cfg(feature = "std")]
 pub mod undirected;
+#[cfg(feature = "std")]
+pub mod uninit;
+#[cfg(feature = "std")]
+pub mod watchdog;
+
+/// Derive a channel per field of a struct, for shared state whose fields are owned by different threads.
+///
+/// For a struct `WorldState`, this generates two types in the same module with the same visibility:
+/// `WorldStateChannels`, which holds the channel pointers of all fields, and `WorldStatePointers`, which holds their data pointers.
+/// `WorldStateChannels::create(initial)` creates the channels from the fields of the given value,
+/// `WorldStateChannels::flush_all(&channel_key)` flushes or swaps all of them,
+/// and `WorldStateChannels::destroy(pointers)` reassembles two `WorldState`s from the `Data` fields of the channels, see below.
+///
+/// Each field gets a [directed channel](crate::directed) by default, or an [undirected channel](crate::undirected) if it is annotated with `#[channel(undirected)]`.
+/// The data pointers of a field `physics` with a directed channel are `physics_reader` and `physics_writer`,
+/// and the data pointers of a field `audio` with an undirected channel are `audio_pointer1` and `audio_pointer2`.
+/// All field types must be [Clone], since both `Data` fields of each channel are initialised from the same value.
+///
+/// Destroying returns the value assembled from the read-only `Data` of the directed and the first `Data` of the undirected channels,
+/// and the value assembled from the writable `Data` of the directed and the second `Data` of the undirected channels, in this order.
+///
+/// Requires the `derive` feature.
+///
+/// ```
+/// use two_phase_channel::{MasterKey, SwapFields};
+///
+/// #[derive(Debug, Clone, PartialEq, SwapFields)]
+/// struct WorldState {
+///     physics: Vec<(f32, f32)>,
+///     #[channel(undirected)]
+///     audio: u32,
+/// }
+///
+/// let mut master_key = MasterKey::create();
+/// let (mut channels, mut pointers) = WorldStateChannels::create(WorldState {
+///     physics: vec![(0.0, 0.0)],
+///     audio: 0,
+/// });
+///
+/// let data_key = master_key.get_data_key();
+/// pointers.physics_writer.get_mut(&data_key)[0].1 = 1.0;
+/// *pointers.audio_pointer1.get_mut(&data_key) = 2;
+/// channels.flush_all(&data_key.into_channel_key());
+///
+/// let data_key = master_key.get_data_key();
+/// assert_eq!(*pointers.physics_reader.get(&data_key), [(0.0, 1.0)]);
+/// assert_eq!(*pointers.audio_pointer2.get(&data_key), 2);
+///
+/// let (read, written) = channels.destroy(pointers);
+/// assert_eq!(read, WorldState { physics: vec![(0.0, 1.0)], audio: 0 });
+/// assert_eq!(written, WorldState { physics: vec![(0.0, 1.0)], audio: 2 });
+/// ```
+#[cfg(feature = "derive")]
+pub use two_phase_channel_derive::SwapFields;
+
+/// A wrapper that aligns its content to its own cache line if the `cache-padded` feature is enabled.
+/// The alignment of 128 bytes also covers CPUs that prefetch adjacent cache lines.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "cache-padded", repr(align(128)))]
+#[derive(Debug)]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+#[cfg(feature = "std")]
+impl<T> core::ops::Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> core::ops::DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// A read-only pointer to a generation counter of a channel.
+/// The counter is only written during the channel phase and only read during the data phase,
+/// hence the key protocol synchronises all accesses.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GenerationPointer {
+    generation: *const u64,
+}
+
+#[cfg(feature = "std")]
+impl GenerationPointer {
+    pub(crate) fn new(generation: &u64) -> Self {
+        Self::from_raw(generation)
+    }
+
+    pub(crate) fn from_raw(generation: *const u64) -> Self {
+        Self { generation }
+    }
+
+    /// The current value of the counter.
+    pub(crate) fn get(&self) -> u64 {
+        unsafe { *self.generation }
+    }
+
+    /// Returns `true` if the counter differs from `last`, and updates `last` to the current value.
+    pub(crate) fn changed_since(&self, last: &mut u64) -> bool {
+        let generation = self.get();
+        let changed = generation != *last;
+        *last = generation;
+        changed
+    }
+}
+
+/// The optional label of a channel, used in panic messages and for introspection.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Label(Option<Cow<'static, str>>);
+
+#[cfg(feature = "std")]
+impl Label {
+    pub(crate) fn set(&mut self, label: impl Into<Cow<'static, str>>) {
+        self.0 = Some(label.into());
+    }
+
+    pub(crate) fn get(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(label) => write!(f, "channel {:?}", label),
+            None => f.write_str("unlabelled channel"),
+        }
+    }
+}
+
+/// A user-supplied callback that is invoked by a channel operation, if it is set.
+#[cfg(feature = "std")]
+pub(crate) struct Hook<Arg> {
+    hook: Option<Box<dyn FnMut(Arg) + Send>>,
+}
+
+#[cfg(feature = "std")]
+impl<Arg> Hook<Arg> {
+    pub(crate) fn set(&mut self, hook: Box<dyn FnMut(Arg) + Send>) {
+        self.hook = Some(hook);
+    }
+
+    pub(crate) fn call(&mut self, arg: Arg) {
+        if let Some(hook) = &mut self.hook {
+            hook(arg);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Arg> Default for Hook<Arg> {
+    fn default() -> Self {
+        Self { hook: None }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Arg> core::fmt::Debug for Hook<Arg> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Hook")
+            .field("is_set", &self.hook.is_some())
+            .finish()
+    }
+}
 
 /// The master key.
 /// Only one instance of this type can exist at any time.
@@ -18,20 +328,35 @@ pub struct MasterKey {
     /// For debug purposes, multiple master keys can be created.
     /// To prevent them from interfering with the "real" master key, we mark them as "unlimited".
     unlimited: bool,
+    checked: CheckedMaster,
+    #[cfg(feature = "checksum")]
+    data_phases: checksum::DataPhases,
 }
 
 impl MasterKey {
     /// Creates a new master key.
     /// If there already is an existing master key, this function **panics**.
     pub fn create() -> Self {
-        // Assert that the master key does not exist
+        Self::try_create().expect("a master key already exists")
+    }
+
+    /// Creates a new master key, or returns `None` if there already is an existing master key.
+    pub(crate) fn try_create() -> Option<Self> {
+        // Check that the master key does not exist
         // and set it as existing.
         // Using `Ordering::Relaxed` is fine here, since if the result is `true`,
-        // we anyways panic, and if the result is `false`, nothing happens.
-        assert!(!MASTER_KEY_EXISTS.swap(true, Ordering::Relaxed));
-
-        // Return a new master key.
-        Self { unlimited: false }
+        // nothing happens, and if the result is `false`, nothing else happens either.
+        if MASTER_KEY_EXISTS.swap(true, Ordering::Relaxed) {
+            None
+        } else {
+            // Return a new master key.
+            Some(Self {
+                unlimited: false,
+                checked: CheckedMaster::new(),
+                #[cfg(feature = "checksum")]
+                data_phases: Default::default(),
+            })
+        }
     }
 
     /// Creates a new master key without checking if there already is one.
@@ -44,14 +369,21 @@ impl MasterKey {
     ///
     /// Use this only for testing and debugging purposes.
     pub unsafe fn create_unlimited() -> Self {
-        Self { unlimited: true }
+        Self {
+            unlimited: true,
+            checked: CheckedMaster::new(),
+            #[cfg(feature = "checksum")]
+            data_phases: Default::default(),
+        }
     }
 
     /// Get a unique data key from this master key.
     /// The data key mutably borrows from the master key, hence there can be no other keys at the same time.
     pub fn get_data_key(&mut self) -> DataKey<'_> {
         DataKey {
-            scope: Default::default(),
+            #[cfg(feature = "checksum")]
+            data_phases: Some(self.data_phases.begin()),
+            ..DataKey::new(self.checked.next_phase())
         }
     }
 
@@ -59,7 +391,9 @@ impl MasterKey {
     /// The channel key mutably borrows from the master key, hence there can be no other keys at the same time.
     pub fn get_channel_key(&mut self) -> ChannelKey<'_> {
         ChannelKey {
-            scope: Default::default(),
+            #[cfg(feature = "checksum")]
+            data_phases: Some(&self.data_phases),
+            ..ChannelKey::new(self.checked.next_phase())
         }
     }
 }
@@ -77,30 +411,241 @@ impl Drop for MasterKey {
     }
 }
 
+/// The original data pointer of a projected data pointer,
+/// as returned for example by [`UndirectedDataPointer::project`](undirected::UndirectedDataPointer::project).
+/// It is used to restore the original data pointer from the projected one, which is required for destroying the channel.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+#[must_use]
+pub struct Projection<Pointer> {
+    pub(crate) original: Pointer,
+}
+
 /// The key used for accessing a data pointer, such as a [`ReadOnlyDataPointer`](directed::ReadOnlyDataPointer), a [`WritableDataPointer`](directed::WritableDataPointer), or a [`DataPointer`](undirected::UndirectedDataPointer).
 /// Only one can simultaneously exist at any point, and only if there is no channel key.
+///
+/// With the `tracing` feature, each data key holds a `data_phase` span, which is closed when the key is dropped or converted.
 pub struct DataKey<'master_key> {
     scope: PhantomData<&'master_key mut MasterKey>,
+    pub(crate) phase: KeyPhase,
+    /// The data phases of the master key this key was derived from, if any.
+    #[cfg(feature = "checksum")]
+    pub(crate) data_phases: Option<&'master_key checksum::DataPhases>,
+    #[cfg(all(feature = "std", feature = "tracing"))]
+    _span: tracing::Span,
 }
 
 /// The key used for accessing a channel pointer, such as a [`DirectedChannelPointer`](directed::DirectedChannelPointer) or an [`UndirectedChannelPointer`](undirected::UndirectedChannelPointer).
 /// Only one can simultaneously exist at any point, and only if there is no data key.
+///
+/// With the `tracing` feature, each channel key holds a `channel_phase` span, which is closed when the key is dropped or converted.
 pub struct ChannelKey<'master_key> {
     scope: PhantomData<&'master_key mut MasterKey>,
+    phase: KeyPhase,
+    /// The data phases of the master key this key was derived from, if any.
+    #[cfg(feature = "checksum")]
+    pub(crate) data_phases: Option<&'master_key checksum::DataPhases>,
+    #[cfg(all(feature = "std", feature = "tracing"))]
+    _span: tracing::Span,
 }
 
 impl<'master_key> DataKey<'master_key> {
+    /// Create a data key, which starts a data phase.
+    /// The caller must ensure that no channel key exists while the data key exists.
+    pub(crate) fn new(phase: KeyPhase) -> Self {
+        Self {
+            scope: PhantomData,
+            phase,
+            #[cfg(feature = "checksum")]
+            data_phases: None,
+            #[cfg(all(feature = "std", feature = "tracing"))]
+            _span: tracing::debug_span!(target: instrument::TARGET, "data_phase"),
+        }
+    }
+
     /// Convert this data key into a channel key.
     /// This consumes the data key, ensuring that there is never both a channel key and a data key.
     pub fn into_channel_key(self) -> ChannelKey<'master_key> {
-        ChannelKey { scope: self.scope }
+        // Close the span of this phase before the span of the next phase starts.
+        #[cfg(all(feature = "std", feature = "tracing"))]
+        drop(self._span);
+        ChannelKey {
+            #[cfg(feature = "checksum")]
+            data_phases: self.data_phases,
+            ..ChannelKey::new(self.phase.next())
+        }
     }
 }
 
 impl<'master_key> ChannelKey<'master_key> {
+    /// Create a channel key, which starts a channel phase.
+    /// The caller must ensure that no data key exists while the channel key exists.
+    pub(crate) fn new(phase: KeyPhase) -> Self {
+        Self {
+            scope: PhantomData,
+            phase,
+            #[cfg(feature = "checksum")]
+            data_phases: None,
+            #[cfg(all(feature = "std", feature = "tracing"))]
+            _span: tracing::debug_span!(target: instrument::TARGET, "channel_phase"),
+        }
+    }
+
     /// Convert this channel key into a data key.
     /// This consumes the channel key, ensuring that there is never both a channel key and a data key.
     pub fn into_data_key(self) -> DataKey<'master_key> {
-        DataKey { scope: self.scope }
+        // Close the span of this phase before the span of the next phase starts.
+        #[cfg(all(feature = "std", feature = "tracing"))]
+        drop(self._span);
+        DataKey {
+            #[cfg(feature = "checksum")]
+            data_phases: self.data_phases.map(checksum::DataPhases::begin),
+            ..DataKey::new(self.phase.next())
+        }
+    }
+
+    /// The id of the current data phase of the master key this key was derived from, if any.
+    #[cfg(feature = "checksum")]
+    pub(crate) fn data_phase(&self) -> Option<u64> {
+        self.data_phases.map(checksum::DataPhases::current)
+    }
+}
+
+/// Object-safe trait for the channel pointers of all kinds of channels,
+/// such that a coordinator can advance channels of mixed kinds with the same code.
+///
+/// The operations specific to a kind of channel remain available through its own trait,
+/// i.e. [`UndirectedSwapChannel`](undirected::UndirectedSwapChannel), [`IDirectedChannel`](directed::IDirectedChannel) or [`IBidirectedChannel`](bidirected::IBidirectedChannel),
+/// each of which has this trait as supertrait.
+pub trait SwapChannel: Send + Sync {
+    /// Advance the channel to make the writes of the last data phase visible,
+    /// i.e. swap an undirected channel or flush a directed or bidirected channel.
+    fn advance(&mut self, channel_key: &ChannelKey);
+
+    /// The label of the channel, if it has one.
+    /// Defaults to `None`.
+    fn label(&self) -> Option<&str> {
+        None
+    }
+
+    /// A counter that changes whenever advancing the channel changes the `Data` visible to its data pointers,
+    /// e.g. to detect flushes that were skipped because the channel was clean.
+    /// Defaults to `None`, in which case every advance is assumed to change the `Data`.
+    fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<u64> {
+        None
+    }
+
+    /// The name of the type of the `Data` transmitted by the channel, as returned by [core::any::type_name].
+    /// Defaults to `None`.
+    fn payload_type_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns `true` if `Data` was written since the last advance, i.e. if the next advance changes the `Data` visible to its data pointers.
+    /// Defaults to `None`, in which case the channel does not track writes.
+    fn dirty(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<bool> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: SwapChannel + ?Sized> SwapChannel for Box<T> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        T::advance(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        T::label(self)
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        T::generation(self, channel_key)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        T::payload_type_name(self)
+    }
+
+    fn dirty(&self, channel_key: &ChannelKey) -> Option<bool> {
+        T::dirty(self, channel_key)
+    }
+}
+
+impl<T: SwapChannel + ?Sized> SwapChannel for &mut T {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        T::advance(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        T::label(self)
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        T::generation(self, channel_key)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        T::payload_type_name(self)
+    }
+
+    fn dirty(&self, channel_key: &ChannelKey) -> Option<bool> {
+        T::dirty(self, channel_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    /// Check the library without default features, with the given features and for the given target, if any.
+    fn check(target: Option<&str>, features: &str, target_dir: &str) -> bool {
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+        command.args([
+            "check",
+            "--lib",
+            "--no-default-features",
+            "--features",
+            features,
+        ]);
+        if let Some(target) = target {
+            command.args(["--target", target]);
+        }
+        command
+            .arg("--manifest-path")
+            .arg(format!("{}/Cargo.toml", manifest_dir))
+            .arg("--target-dir")
+            .arg(std::env::temp_dir().join(target_dir))
+            .status()
+            .expect("could not run cargo")
+            .success()
+    }
+
+    /// Checks that the crate builds for `wasm32-unknown-unknown` with the `portable` feature.
+    #[test]
+    #[ignore = "requires the wasm32-unknown-unknown target, run with `cargo test -- --ignored`"]
+    fn portable_wasm32() {
+        assert!(check(
+            Some("wasm32-unknown-unknown"),
+            "std,portable",
+            "two_phase_channel_portable_test"
+        ));
+    }
+
+    #[test]
+    fn no_std() {
+        // The crate is `no_std` without the `std` feature, hence this also checks that nothing uses the standard library on the host.
+        assert!(check(None, "", "two_phase_channel_no_std_test"));
+    }
+
+    /// Checks that the crate builds without the `std` feature for a target without a standard library.
+    #[test]
+    #[ignore = "requires the thumbv7em-none-eabihf target, run with `cargo test -- --ignored`"]
+    fn no_std_thumbv7em() {
+        assert!(check(
+            Some("thumbv7em-none-eabihf"),
+            "",
+            "two_phase_channel_no_std_test"
+        ));
     }
 }
@@ -0,0 +1,188 @@
+//! Master keys that are unique per thread instead of per process, for applications that run fully independent pipelines,
+//! e.g. one per core, each with its own coordinator and its own channels.
+//!
+//! A [ThreadLocalMasterKey] can exist once per thread, and can exist next to a [MasterKey](crate::MasterKey).
+//! It and the keys derived from it are not `Send`, such that they never migrate to a thread with its own master key.
+//! The derived [LocalDataKey] and [LocalChannelKey] dereference to a [DataKey] and a [ChannelKey], and are used like these.
+//!
+//! Since the keys of different pipelines exist at the same time, the pipelines must not share any channel.
+//! With the `checked-backend` feature, each channel accessed via a key of a thread-local master key is branded with that master key,
+//! and accessing it via a key of any other master key **panics**.
+//!
+//! The derived keys cannot be sent to another thread:
+//!
+//! ```compile_fail
+//! use two_phase_channel::local::ThreadLocalMasterKey;
+//!
+//! fn assert_send<T: Send>(_: &T) {}
+//!
+//! let mut master_key = unsafe { ThreadLocalMasterKey::create() };
+//! assert_send(&master_key.get_data_key());
+//! ```
+
+use core::{cell::Cell, marker::PhantomData, ops::Deref};
+
+use crate::{checked::CheckedMaster, ChannelKey, DataKey};
+
+thread_local! {
+    static LOCAL_MASTER_KEY_EXISTS: Cell<bool> = Cell::new(false);
+}
+
+/// A master key of which only one instance can exist per thread, see the [module documentation](self).
+pub struct ThreadLocalMasterKey {
+    checked: CheckedMaster,
+    #[cfg(feature = "checksum")]
+    data_phases: crate::checksum::DataPhases,
+    /// The flag of the creating thread is reset on drop, hence the key must stay on that thread.
+    not_send: PhantomData<*const ()>,
+}
+
+/// A data key derived from a [ThreadLocalMasterKey], which dereferences to a [DataKey].
+pub struct LocalDataKey<'master_key> {
+    data_key: DataKey<'master_key>,
+    not_send: PhantomData<*const ()>,
+}
+
+/// A channel key derived from a [ThreadLocalMasterKey], which dereferences to a [ChannelKey].
+pub struct LocalChannelKey<'master_key> {
+    channel_key: ChannelKey<'master_key>,
+    not_send: PhantomData<*const ()>,
+}
+
+impl ThreadLocalMasterKey {
+    /// Creates a new master key for the current thread.
+    /// If there already is a thread-local master key on this thread, this function **panics**.
+    ///
+    /// # Safety
+    ///
+    /// The channels accessed via keys of this master key must not be accessed via keys of any other master key,
+    /// i.e. each channel must belong to the pipeline of a single thread.
+    /// Otherwise, a channel may be accessed by a channel key and a data key at the same time, which is undefined behavior.
+    pub unsafe fn create() -> Self {
+        let exists = LOCAL_MASTER_KEY_EXISTS.with(|exists| exists.replace(true));
+        assert!(
+            !exists,
+            "a thread-local master key already exists on this thread"
+        );
+        Self {
+            checked: CheckedMaster::branded(),
+            #[cfg(feature = "checksum")]
+            data_phases: Default::default(),
+            not_send: PhantomData,
+        }
+    }
+
+    /// Get a unique data key from this master key.
+    /// The data key mutably borrows from the master key, hence there can be no other keys at the same time.
+    pub fn get_data_key(&mut self) -> LocalDataKey<'_> {
+        LocalDataKey {
+            data_key: DataKey {
+                #[cfg(feature = "checksum")]
+                data_phases: Some(self.data_phases.begin()),
+                ..DataKey::new(self.checked.next_phase())
+            },
+            not_send: PhantomData,
+        }
+    }
+
+    /// Get a unique channel key from this master key.
+    /// The channel key mutably borrows from the master key, hence there can be no other keys at the same time.
+    pub fn get_channel_key(&mut self) -> LocalChannelKey<'_> {
+        LocalChannelKey {
+            channel_key: ChannelKey {
+                #[cfg(feature = "checksum")]
+                data_phases: Some(&self.data_phases),
+                ..ChannelKey::new(self.checked.next_phase())
+            },
+            not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for ThreadLocalMasterKey {
+    /// After dropping a thread-local master key, a new one can be created again on the same thread.
+    fn drop(&mut self) {
+        LOCAL_MASTER_KEY_EXISTS.with(|exists| exists.set(false));
+    }
+}
+
+impl<'master_key> LocalDataKey<'master_key> {
+    /// Convert this data key into a channel key.
+    /// This consumes the data key, ensuring that there is never both a channel key and a data key.
+    pub fn into_channel_key(self) -> LocalChannelKey<'master_key> {
+        LocalChannelKey {
+            channel_key: self.data_key.into_channel_key(),
+            not_send: PhantomData,
+        }
+    }
+}
+
+impl<'master_key> LocalChannelKey<'master_key> {
+    /// Convert this channel key into a data key.
+    /// This consumes the channel key, ensuring that there is never both a channel key and a data key.
+    pub fn into_data_key(self) -> LocalDataKey<'master_key> {
+        LocalDataKey {
+            data_key: self.channel_key.into_data_key(),
+            not_send: PhantomData,
+        }
+    }
+}
+
+impl<'master_key> Deref for LocalDataKey<'master_key> {
+    type Target = DataKey<'master_key>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data_key
+    }
+}
+
+impl<'master_key> Deref for LocalChannelKey<'master_key> {
+    type Target = ChannelKey<'master_key>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.channel_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        thread,
+    };
+
+    use crate::{local::ThreadLocalMasterKey, undirected::UndirectedChannel};
+
+    #[test]
+    fn test() {
+        let pipelines: Vec<_> = (0..4)
+            .map(|index| {
+                thread::spawn(move || {
+                    let mut master_key = unsafe { ThreadLocalMasterKey::create() };
+                    let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+                        UndirectedChannel::create(index, 0);
+                    for _ in 0..3 {
+                        let data_key = master_key.get_data_key();
+                        *data_pointer1.get_mut(&data_key) += 1;
+                        channel_pointer.swap(&data_key.into_channel_key());
+                    }
+                    channel_pointer.destroy(data_pointer1, data_pointer2)
+                })
+            })
+            .collect();
+        for (index, pipeline) in pipelines.into_iter().enumerate() {
+            assert_eq!(pipeline.join().unwrap(), (1, index + 2));
+        }
+    }
+
+    #[test]
+    fn one_per_thread() {
+        let master_key = unsafe { ThreadLocalMasterKey::create() };
+        let second = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            ThreadLocalMasterKey::create()
+        }));
+        assert!(second.is_err());
+        drop(master_key);
+        let _ = unsafe { ThreadLocalMasterKey::create() };
+    }
+}
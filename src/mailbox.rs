@@ -0,0 +1,295 @@
+//! A fixed number of independent slots between a writer and a reader, built on top of the [directed channel](crate::directed).
+//! The writer marks each slot it accesses mutably as dirty, and flushing clones only the dirty slots,
+//! which suits many items of which only a few change per phase, without creating a channel per item.
+//!
+//! The reader sees all slots as of the last flush, and additionally which slots the last flush updated.
+
+use core::marker::PhantomData;
+
+use crate::{
+    directed::{
+        DirectedChannel, DirectedChannelPointer, FlushStats, FlushStrategy, ReadOnlyDataPointer,
+        WritableDataPointer,
+    },
+    ChannelKey, DataKey,
+};
+
+/// A mailbox channel used for communication between a writer and a reader thread.
+///
+/// See [MailboxChannel::create] for more info.
+#[derive(Debug)]
+pub struct MailboxChannel<Data> {
+    phantom: PhantomData<Data>,
+}
+
+/// One side of a mailbox channel, i.e. the `Data` of the underlying directed channel.
+#[derive(Debug)]
+struct Slots<Data> {
+    slots: Box<[Data]>,
+    /// The slots accessed mutably since the last flush, one bit per slot. Empty on the read side.
+    dirty: Box<[u64]>,
+    /// The slots updated by the last flush, in ascending order. Empty on the written side.
+    /// Its capacity suffices for all slots, such that flushing never allocates.
+    flushed: Vec<usize>,
+}
+
+/// Clones the dirty slots of the written side into the read side, and records them as flushed.
+struct SlotFlush;
+
+/// The pointer used to flush a mailbox channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [MailboxChannel::destroy] or [MailboxChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct MailboxChannelPointer<Data> {
+    channel_pointer: DirectedChannelPointer<Slots<Data>>,
+}
+
+/// The pointer used to write the slots of a mailbox channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [MailboxChannel::destroy] or [MailboxChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct MailboxWriter<Data> {
+    data_pointer: WritableDataPointer<Slots<Data>>,
+}
+
+/// The pointer used to read the slots of a mailbox channel, as of the last flush.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [MailboxChannel::destroy] or [MailboxChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct MailboxReader<Data> {
+    data_pointer: ReadOnlyDataPointer<Slots<Data>>,
+}
+
+impl<Data> MailboxChannel<Data> {
+    /// Create a mailbox channel with `n` slots, where both sides of slot `i` are initialised with `init(i)`, and hand out three pointers to it.
+    /// One [MailboxChannelPointer] used to flush the dirty slots,
+    /// one [MailboxReader] used to read the slots, and
+    /// one [MailboxWriter] used to write the slots.
+    pub fn create(
+        n: usize,
+        init: impl Fn(usize) -> Data,
+    ) -> (
+        MailboxChannelPointer<Data>,
+        MailboxReader<Data>,
+        MailboxWriter<Data>,
+    ) {
+        let read_only = Slots {
+            slots: (0..n).map(&init).collect(),
+            dirty: Box::default(),
+            flushed: Vec::with_capacity(n),
+        };
+        let writable = Slots {
+            slots: (0..n).map(&init).collect(),
+            dirty: vec![0; (n + 63) / 64].into_boxed_slice(),
+            flushed: Vec::new(),
+        };
+        let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+            DirectedChannel::create(read_only, writable);
+        (
+            MailboxChannelPointer { channel_pointer },
+            MailboxReader {
+                data_pointer: read_only_data_pointer,
+            },
+            MailboxWriter {
+                data_pointer: writable_data_pointer,
+            },
+        )
+    }
+
+    /// Destroys the mailbox channel linked with the three pointers (see [MailboxChannel::create]).
+    /// Returns the read and the written `Data` of each slot, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same mailbox channel.
+    pub fn destroy(
+        channel_pointer: MailboxChannelPointer<Data>,
+        reader: MailboxReader<Data>,
+        writer: MailboxWriter<Data>,
+    ) -> Vec<(Data, Data)> {
+        let (read_only, writable) = DirectedChannel::destroy_single(
+            channel_pointer.channel_pointer,
+            reader.data_pointer,
+            writer.data_pointer,
+        );
+        Vec::from(read_only.slots)
+            .into_iter()
+            .zip(Vec::from(writable.slots))
+            .collect()
+    }
+}
+
+impl<Data> Slots<Data> {
+    /// **Panics** if the given slot is out of bounds.
+    #[track_caller]
+    fn check_slot(&self, slot: usize) {
+        assert!(
+            slot < self.slots.len(),
+            "slot {} is out of bounds for a mailbox with {} slots",
+            slot,
+            self.slots.len()
+        );
+    }
+}
+
+impl<Data: Clone> FlushStrategy<Slots<Data>> for SlotFlush {
+    fn flush(&self, read_only: &mut Slots<Data>, writable: &mut Slots<Data>) {
+        read_only.flushed.clear();
+        // Only the words with dirty slots are written, such that the bitset is reused as is in the next phase.
+        for (word_index, word) in writable.dirty.iter_mut().enumerate() {
+            let mut bits = *word;
+            if bits == 0 {
+                continue;
+            }
+            while bits != 0 {
+                let slot = word_index * 64 + bits.trailing_zeros() as usize;
+                read_only.slots[slot].clone_from(&writable.slots[slot]);
+                read_only.flushed.push(slot);
+                bits &= bits - 1;
+            }
+            *word = 0;
+        }
+    }
+}
+
+impl<Data: Clone> MailboxChannelPointer<Data> {
+    /// Clone the slots written since the last flush into the read side via [Clone::clone_from], and clear their dirty marks.
+    /// If no slot was written since the last flush, the flush is skipped, and the reader sees no updated slots.
+    ///
+    /// This clones only the dirty slots, and is hence [real-time safe](crate::rt) if cloning `Data` into an existing `Data` does not allocate.
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        if !self.channel_pointer.flush_with_if_dirty(&SlotFlush) {
+            self.channel_pointer
+                .read_only_mut(channel_key)
+                .flushed
+                .clear();
+        }
+    }
+}
+
+impl<Data> MailboxChannelPointer<Data> {
+    /// Statistics about the flushes performed on this channel.
+    pub fn stats(&self, channel_key: &ChannelKey) -> FlushStats {
+        self.channel_pointer.stats(channel_key)
+    }
+
+    /// Shorthand for [MailboxChannel::destroy].
+    pub fn destroy(
+        self,
+        reader: MailboxReader<Data>,
+        writer: MailboxWriter<Data>,
+    ) -> Vec<(Data, Data)> {
+        MailboxChannel::destroy(self, reader, writer)
+    }
+}
+
+impl<Data> MailboxWriter<Data> {
+    /// Get a reference to the given slot, as written so far.
+    ///
+    /// **Panics** if the slot is out of bounds.
+    #[track_caller]
+    pub fn slot(&self, data_key: &DataKey, slot: usize) -> &Data {
+        let slots = self.data_pointer.get(data_key);
+        slots.check_slot(slot);
+        &slots.slots[slot]
+    }
+
+    /// Get a mutable reference to the given slot, and mark it dirty, such that the next flush clones it to the reader.
+    ///
+    /// **Panics** if the slot is out of bounds.
+    #[track_caller]
+    pub fn slot_mut(&mut self, data_key: &DataKey, slot: usize) -> &mut Data {
+        self.data_pointer.get(data_key).check_slot(slot);
+        let slots = self.data_pointer.get_mut(data_key);
+        slots.dirty[slot / 64] |= 1 << (slot % 64);
+        &mut slots.slots[slot]
+    }
+
+    /// The number of slots.
+    pub fn len(&self, data_key: &DataKey) -> usize {
+        self.data_pointer.get(data_key).slots.len()
+    }
+}
+
+impl<Data> MailboxReader<Data> {
+    /// Get a reference to the given slot, as of the last flush.
+    ///
+    /// **Panics** if the slot is out of bounds.
+    #[track_caller]
+    pub fn slot(&self, data_key: &DataKey, slot: usize) -> &Data {
+        let slots = self.data_pointer.get(data_key);
+        slots.check_slot(slot);
+        &slots.slots[slot]
+    }
+
+    /// The slots updated by the flush of the last channel phase, in ascending order.
+    /// This is empty if the last flush was skipped.
+    pub fn dirty_slots(&self, data_key: &DataKey) -> &[usize] {
+        &self.data_pointer.get(data_key).flushed
+    }
+
+    /// The number of slots.
+    pub fn len(&self, data_key: &DataKey) -> usize {
+        self.data_pointer.get(data_key).slots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use crate::{mailbox::MailboxChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, reader, mut writer) = MailboxChannel::create(200, |i| vec![i]);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(reader.len(&data_key), 200);
+        writer.slot_mut(&data_key, 3).push(1);
+        writer.slot_mut(&data_key, 130).push(2);
+        writer.slot_mut(&data_key, 3).push(3);
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(reader.dirty_slots(&data_key), [3, 130]);
+        assert_eq!(*reader.slot(&data_key, 3), [3, 1, 3]);
+        assert_eq!(*reader.slot(&data_key, 4), [4]);
+        writer.slot_mut(&data_key, 64).clear();
+
+        // The bitset was cleared by the previous flush, hence only the new dirty slot is flushed.
+        channel_pointer.flush(&data_key.into_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(reader.dirty_slots(&data_key), [64]);
+        assert!(reader.slot(&data_key, 64).is_empty());
+        assert_eq!(*writer.slot(&data_key, 130), [130, 2]);
+
+        let channel_key = data_key.into_channel_key();
+        channel_pointer.flush(&channel_key);
+        let stats = channel_pointer.stats(&channel_key);
+        assert_eq!((stats.flushes, stats.skipped), (2, 1));
+        assert!(reader.dirty_slots(&master_key.get_data_key()).is_empty());
+
+        let slots = channel_pointer.destroy(reader, writer);
+        assert_eq!(slots.len(), 200);
+        assert_eq!(slots[130], (vec![130, 2], vec![130, 2]));
+        assert!(slots.iter().all(|(read, written)| read == written));
+    }
+
+    #[test]
+    fn out_of_bounds_slots_panic() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, reader, mut writer) = MailboxChannel::create(64, |_| 0u8);
+        let data_key = master_key.get_data_key();
+        let out_of_bounds =
+            panic::catch_unwind(AssertUnwindSafe(|| *writer.slot_mut(&data_key, 64) = 1));
+        assert!(out_of_bounds.is_err());
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| *reader.slot(&data_key, 64))).is_err());
+        assert_eq!(channel_pointer.destroy(reader, writer), vec![(0, 0); 64]);
+    }
+}
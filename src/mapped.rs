@@ -0,0 +1,282 @@
+//! A bidirected two-phase channel whose endpoints see different types.
+//! Each direction stores a transform that converts what one endpoint writes into what the other endpoint reads.
+//! The transform runs once per flush during the channel phase, instead of on every read during the data phase.
+
+use core::fmt;
+
+use crate::{ChannelKey, DataKey};
+
+/// A bidirected channel used for communication between a hub and a spoke thread that use different representations of the transmitted data.
+/// The forward direction converts the `HubOut` written by the hub into the `SpokeIn` read by the spoke,
+/// and the backward direction converts the `SpokeOut` written by the spoke into the `HubIn` read by the hub.
+///
+/// See [MappedBidirectedChannelBuilder] for more info.
+#[derive(Debug)]
+pub struct MappedBidirectedChannel<HubOut, SpokeIn, SpokeOut, HubIn> {
+    forward: MappedDirection<HubOut, SpokeIn>,
+    backward: MappedDirection<SpokeOut, HubIn>,
+}
+
+/// One direction of a mapped bidirected channel.
+#[derive(Debug)]
+struct MappedDirection<Source, Target> {
+    source: Source,
+    target: Target,
+    /// `true` if the source was accessed mutably since the last flush.
+    dirty: bool,
+}
+
+/// The transform of one direction of a mapped bidirected channel.
+struct Map<Source, Target> {
+    #[allow(clippy::type_complexity)]
+    map: Box<dyn FnMut(&Source, &mut Target) + Send>,
+}
+
+impl<Source, Target> fmt::Debug for Map<Source, Target> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Map").finish()
+    }
+}
+
+/// A builder for a [MappedBidirectedChannel].
+///
+/// Start with [MappedBidirectedChannelBuilder::forward], continue with [MappedBidirectedChannelBuilder::backward], and finish with [MappedBidirectedChannelBuilder::build].
+#[derive(Debug)]
+#[must_use]
+pub struct MappedBidirectedChannelBuilder<HubOut, SpokeIn, SpokeOut, HubIn> {
+    forward: (HubOut, SpokeIn, Map<HubOut, SpokeIn>),
+    backward: (SpokeOut, HubIn, Map<SpokeOut, HubIn>),
+}
+
+/// A pointer to a mapped bidirected channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [MappedBidirectedChannel::destroy] or [MappedBidirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct MappedBidirectedChannelPointer<HubOut, SpokeIn, SpokeOut, HubIn> {
+    channel: Box<MappedBidirectedChannel<HubOut, SpokeIn, SpokeOut, HubIn>>,
+    forward_map: Map<HubOut, SpokeIn>,
+    backward_map: Map<SpokeOut, HubIn>,
+}
+
+/// A pair of pointers to the data fields of a mapped bidirected channel, used by the hub or the spoke.
+/// The `input` is written by flushes, and the `output` is converted by flushes into the input of the other endpoint.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [MappedBidirectedChannel::destroy] or [MappedBidirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct MappedDataPointer<Input, Output> {
+    input: *const Input,
+    output: *mut Output,
+    dirty: *mut bool,
+}
+
+impl<HubOut, SpokeIn> MappedBidirectedChannelBuilder<HubOut, SpokeIn, (), ()> {
+    /// Initialise the forward direction with the initial output of the hub, the initial input of the spoke,
+    /// and the transform that converts the output of the hub into the input of the spoke on each flush.
+    pub fn forward(
+        hub_out: HubOut,
+        spoke_in: SpokeIn,
+        map: impl FnMut(&HubOut, &mut SpokeIn) + Send + 'static,
+    ) -> Self {
+        Self {
+            forward: (hub_out, spoke_in, Map { map: Box::new(map) }),
+            backward: (
+                (),
+                (),
+                Map {
+                    map: Box::new(|_, _| {}),
+                },
+            ),
+        }
+    }
+
+    /// Initialise the backward direction with the initial output of the spoke, the initial input of the hub,
+    /// and the transform that converts the output of the spoke into the input of the hub on each flush.
+    pub fn backward<SpokeOut, HubIn>(
+        self,
+        spoke_out: SpokeOut,
+        hub_in: HubIn,
+        map: impl FnMut(&SpokeOut, &mut HubIn) + Send + 'static,
+    ) -> MappedBidirectedChannelBuilder<HubOut, SpokeIn, SpokeOut, HubIn> {
+        MappedBidirectedChannelBuilder {
+            forward: self.forward,
+            backward: (spoke_out, hub_in, Map { map: Box::new(map) }),
+        }
+    }
+}
+
+impl<HubOut, SpokeIn, SpokeOut, HubIn>
+    MappedBidirectedChannelBuilder<HubOut, SpokeIn, SpokeOut, HubIn>
+{
+    /// Create the mapped bidirected channel and hand out three pointers to it.
+    /// One [MappedBidirectedChannelPointer] used to flush the channel,
+    /// one [MappedDataPointer] for the hub, and one [MappedDataPointer] for the spoke, in this order.
+    ///
+    /// Both directions are initially dirty, so the first flush runs both transforms.
+    #[allow(clippy::type_complexity)]
+    pub fn build(
+        self,
+    ) -> (
+        MappedBidirectedChannelPointer<HubOut, SpokeIn, SpokeOut, HubIn>,
+        MappedDataPointer<HubIn, HubOut>,
+        MappedDataPointer<SpokeIn, SpokeOut>,
+    ) {
+        let (hub_out, spoke_in, forward_map) = self.forward;
+        let (spoke_out, hub_in, backward_map) = self.backward;
+        let mut channel_pointer = MappedBidirectedChannelPointer {
+            channel: Box::new(MappedBidirectedChannel {
+                forward: MappedDirection {
+                    source: hub_out,
+                    target: spoke_in,
+                    dirty: true,
+                },
+                backward: MappedDirection {
+                    source: spoke_out,
+                    target: hub_in,
+                    dirty: true,
+                },
+            }),
+            forward_map,
+            backward_map,
+        };
+        let channel = &mut channel_pointer.channel;
+        let hub = MappedDataPointer {
+            input: &channel.backward.target as *const HubIn,
+            output: &mut channel.forward.source as *mut HubOut,
+            dirty: &mut channel.forward.dirty as *mut bool,
+        };
+        let spoke = MappedDataPointer {
+            input: &channel.forward.target as *const SpokeIn,
+            output: &mut channel.backward.source as *mut SpokeOut,
+            dirty: &mut channel.backward.dirty as *mut bool,
+        };
+        (channel_pointer, hub, spoke)
+    }
+}
+
+impl<Source, Target> MappedDirection<Source, Target> {
+    fn flush(&mut self, map: &mut Map<Source, Target>) {
+        if self.dirty {
+            (map.map)(&self.source, &mut self.target);
+            self.dirty = false;
+        }
+    }
+}
+
+impl<HubOut, SpokeIn, SpokeOut, HubIn> MappedBidirectedChannel<HubOut, SpokeIn, SpokeOut, HubIn> {
+    /// Destroys the mapped bidirected channel linked with the three pointers (see [MappedBidirectedChannelBuilder::build]).
+    /// Returns the `HubOut`, `SpokeIn`, `SpokeOut` and `HubIn` fields, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: MappedBidirectedChannelPointer<HubOut, SpokeIn, SpokeOut, HubIn>,
+        hub: MappedDataPointer<HubIn, HubOut>,
+        spoke: MappedDataPointer<SpokeIn, SpokeOut>,
+    ) -> (HubOut, SpokeIn, SpokeOut, HubIn) {
+        let MappedBidirectedChannelPointer { channel, .. } = channel_pointer;
+        assert_eq!(hub.input, &channel.backward.target as *const HubIn);
+        assert_eq!(
+            hub.output as *const HubOut,
+            &channel.forward.source as *const HubOut
+        );
+        assert_eq!(spoke.input, &channel.forward.target as *const SpokeIn);
+        assert_eq!(
+            spoke.output as *const SpokeOut,
+            &channel.backward.source as *const SpokeOut
+        );
+
+        let MappedBidirectedChannel { forward, backward } = *channel;
+        (
+            forward.source,
+            forward.target,
+            backward.source,
+            backward.target,
+        )
+    }
+}
+
+impl<HubOut, SpokeIn, SpokeOut, HubIn>
+    MappedBidirectedChannelPointer<HubOut, SpokeIn, SpokeOut, HubIn>
+{
+    /// Convert the output of each endpoint into the input of the other endpoint using the transform of its direction.
+    /// Each direction is only converted if its output was accessed mutably since the last flush.
+    pub fn flush(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.channel.forward.flush(&mut self.forward_map);
+        self.channel.backward.flush(&mut self.backward_map);
+    }
+
+    /// Shorthand for [MappedBidirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        hub: MappedDataPointer<HubIn, HubOut>,
+        spoke: MappedDataPointer<SpokeIn, SpokeOut>,
+    ) -> (HubOut, SpokeIn, SpokeOut, HubIn) {
+        MappedBidirectedChannel::destroy(self, hub, spoke)
+    }
+}
+
+impl<Input, Output> MappedDataPointer<Input, Output> {
+    /// Get a reference to the input data field pointed to by this pointer.
+    pub fn get_input(&self, #[allow(unused)] data_key: &DataKey) -> &Input {
+        unsafe { &*self.input }
+    }
+
+    /// Get a mutable reference to the output data field pointed to by this pointer.
+    /// This marks the output as dirty, such that the next flush converts it.
+    pub fn get_output(&mut self, #[allow(unused)] data_key: &DataKey) -> &mut Output {
+        unsafe {
+            *self.dirty = true;
+            &mut *self.output
+        }
+    }
+}
+
+unsafe impl<HubOut, SpokeIn, SpokeOut, HubIn> Send
+    for MappedBidirectedChannelPointer<HubOut, SpokeIn, SpokeOut, HubIn>
+{
+}
+unsafe impl<Input, Output> Send for MappedDataPointer<Input, Output> {}
+
+unsafe impl<HubOut, SpokeIn, SpokeOut, HubIn> Sync
+    for MappedBidirectedChannelPointer<HubOut, SpokeIn, SpokeOut, HubIn>
+{
+}
+unsafe impl<Input, Output> Sync for MappedDataPointer<Input, Output> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mapped::MappedBidirectedChannelBuilder, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut hub, mut spoke) = MappedBidirectedChannelBuilder::forward(
+            vec![0u8; 2],
+            0u16,
+            |bytes: &Vec<u8>, value: &mut u16| *value = u16::from_le_bytes([bytes[0], bytes[1]]),
+        )
+        .backward(0u16, Vec::new(), |value: &u16, bytes: &mut Vec<u8>| {
+            bytes.clear();
+            bytes.extend_from_slice(&value.to_le_bytes());
+        })
+        .build();
+
+        let data_key = master_key.get_data_key();
+        hub.get_output(&data_key).copy_from_slice(&[1, 2]);
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*spoke.get_input(&data_key), 0x0201);
+        *spoke.get_output(&data_key) = spoke.get_input(&data_key) + 1;
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        assert_eq!(*hub.get_input(&master_key.get_data_key()), [2, 2]);
+        assert_eq!(
+            channel_pointer.destroy(hub, spoke),
+            (vec![1, 2], 0x0201, 0x0202, vec![2, 2])
+        );
+    }
+}
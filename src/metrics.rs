@@ -0,0 +1,152 @@
+//! Recording of channel operations through the [`metrics`] facade, enabled by the `metrics` feature.
+//! Only labelled channels are recorded (see for example [`DirectedChannelPointer::with_label`](crate::directed::DirectedChannelPointer::with_label)),
+//! with their label as the `channel` label of each metric.
+//! The flushes of bidirected channels additionally carry a `direction` label, which is either `forward` or `backward`.
+//!
+//! The metrics are recorded during the channel operations, i.e. while the channel key is held.
+
+use std::time::Instant;
+
+use crate::Label;
+
+/// Counter of the flushes performed on directed and bidirected channels.
+pub const FLUSHES: &str = "two_phase_channel_flushes_total";
+/// Counter of the flushes of directed and bidirected channels that were skipped because the channel was clean.
+pub const FLUSHES_SKIPPED: &str = "two_phase_channel_flushes_skipped_total";
+/// Histogram of the duration of the flushes performed on directed and bidirected channels, in seconds.
+pub const FLUSH_DURATION: &str = "two_phase_channel_flush_duration_seconds";
+/// Counter of the swaps performed on undirected channels.
+pub const SWAPS: &str = "two_phase_channel_swaps_total";
+
+/// The labels of the metrics of the channel with the given label, or `None` if the channel is unlabelled.
+fn labels(label: &Label, direction: Option<&'static str>) -> Option<Vec<::metrics::Label>> {
+    let channel = label.0.clone()?;
+    let mut labels = vec![::metrics::Label::new("channel", channel)];
+    if let Some(direction) = direction {
+        labels.push(::metrics::Label::new("direction", direction));
+    }
+    Some(labels)
+}
+
+/// Run the given flush, which returns `true` if the channel was flushed and `false` if it was skipped, and record it.
+pub(crate) fn record_flush(
+    label: &Label,
+    direction: Option<&'static str>,
+    flush: impl FnOnce() -> bool,
+) -> bool {
+    let labels = match labels(label, direction) {
+        Some(labels) => labels,
+        None => return flush(),
+    };
+
+    let start = Instant::now();
+    let flushed = flush();
+    if flushed {
+        ::metrics::histogram!(FLUSH_DURATION, labels.clone()).record(start.elapsed());
+        ::metrics::counter!(FLUSHES, labels).increment(1);
+    } else {
+        ::metrics::counter!(FLUSHES_SKIPPED, labels).increment(1);
+    }
+    flushed
+}
+
+/// Record a swap of the channel with the given label.
+pub(crate) fn record_swap(label: &Label) {
+    if let Some(labels) = labels(label, None) {
+        ::metrics::counter!(SWAPS, labels).increment(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use crate::{
+        bidirected::BidirectedChannel,
+        directed::DirectedChannel,
+        metrics::{FLUSHES, FLUSHES_SKIPPED, FLUSH_DURATION, SWAPS},
+        undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+
+        ::metrics::with_local_recorder(&recorder, || {
+            let (directed, read_only, mut writable) = DirectedChannel::create(0, 0);
+            let mut directed = directed.with_label("directed");
+            let (undirected, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 1);
+            let mut undirected = undirected.with_label("undirected");
+            let (bidirected, bidirected1, bidirected2) = BidirectedChannel::create(0, 0, 0, 0);
+            let mut bidirected = bidirected.with_label("bidirected");
+            let (mut unlabelled, unlabelled1, unlabelled2) = UndirectedChannel::create(0, 1);
+
+            for phase in 0..3 {
+                if phase != 1 {
+                    *writable.get_mut(&master_key.get_data_key()) = phase;
+                }
+                let channel_key = master_key.get_channel_key();
+                directed.flush(&channel_key);
+                undirected.swap(&channel_key);
+                bidirected.flush(&channel_key);
+                unlabelled.swap(&channel_key);
+            }
+
+            directed.destroy([read_only], writable);
+            undirected.destroy(data_pointer1, data_pointer2);
+            bidirected.destroy(bidirected1, bidirected2);
+            unlabelled.destroy(unlabelled1, unlabelled2);
+        });
+
+        let metrics: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels: Vec<_> = key
+                    .labels()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect();
+                (key.name().to_string(), labels.join(","), value)
+            })
+            .collect();
+        let metric = |name: &str, labels: &str| {
+            metrics
+                .iter()
+                .find(|(metric_name, metric_labels, _)| {
+                    metric_name == name && metric_labels == labels
+                })
+                .map(|(_, _, value)| value)
+        };
+
+        assert_eq!(
+            metric(FLUSHES, "channel=directed"),
+            Some(&DebugValue::Counter(2))
+        );
+        assert_eq!(
+            metric(FLUSHES_SKIPPED, "channel=directed"),
+            Some(&DebugValue::Counter(1))
+        );
+        assert_eq!(
+            metric(SWAPS, "channel=undirected"),
+            Some(&DebugValue::Counter(3))
+        );
+        assert_eq!(
+            metric(FLUSHES, "channel=bidirected,direction=forward"),
+            Some(&DebugValue::Counter(1))
+        );
+        assert_eq!(
+            metric(FLUSHES_SKIPPED, "channel=bidirected,direction=backward"),
+            Some(&DebugValue::Counter(2))
+        );
+        match metric(FLUSH_DURATION, "channel=directed") {
+            Some(DebugValue::Histogram(durations)) => assert_eq!(durations.len(), 2),
+            value => panic!("unexpected flush durations {:?}", value),
+        }
+        assert!(metrics.iter().all(|(_, labels, _)| !labels.is_empty()));
+    }
+}
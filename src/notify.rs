@@ -0,0 +1,163 @@
+//! Notification of reader threads about updates of the channels of a [ChannelGroup](crate::group::ChannelGroup),
+//! such that they can sleep instead of polling their channels in every data phase.
+//! See [ChannelGroup::notifier](crate::group::ChannelGroup::notifier).
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::group::GroupId;
+
+/// A handle of a reader thread to wait for updates of the channels it registered interest in.
+/// It is created via [ChannelGroup::notifier](crate::group::ChannelGroup::notifier).
+///
+/// Updates are collected from the creation of the notifier on, and each update is reported exactly once.
+/// Updates that happen while the reader is not waiting are kept until the next wait.
+#[derive(Debug)]
+pub struct Notifier {
+    state: Arc<NotifierState>,
+}
+
+/// The channels that were updated since the last wait of a [Notifier], in the order of the group.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateSet {
+    ids: Vec<GroupId>,
+}
+
+/// The state shared between a [Notifier] and its group.
+#[derive(Debug)]
+pub(crate) struct NotifierState {
+    /// The channels the reader is interested in.
+    channels: Vec<GroupId>,
+    /// The updated channels that were not yet reported.
+    updated: Mutex<UpdateSet>,
+    condvar: Condvar,
+}
+
+impl Notifier {
+    pub(crate) fn new(channels: Vec<GroupId>) -> (Self, Arc<NotifierState>) {
+        let state = Arc::new(NotifierState {
+            channels,
+            updated: Mutex::new(UpdateSet::default()),
+            condvar: Condvar::new(),
+        });
+        (
+            Self {
+                state: state.clone(),
+            },
+            state,
+        )
+    }
+
+    /// Block until at least one of the channels of this notifier was updated since the last call, and return the updated channels.
+    /// Spurious wakeups of the underlying condition variable are handled internally.
+    pub fn wait_for_update(&self) -> UpdateSet {
+        let mut updated = self.state.updated.lock().unwrap();
+        while updated.is_empty() {
+            updated = self.state.condvar.wait(updated).unwrap();
+        }
+        std::mem::take(&mut *updated)
+    }
+
+    /// Return the channels of this notifier that were updated since the last call, without blocking.
+    /// The returned set is empty if there was no update.
+    pub fn take_updates(&self) -> UpdateSet {
+        std::mem::take(&mut *self.state.updated.lock().unwrap())
+    }
+
+    /// The channels this notifier is interested in.
+    pub fn channels(&self) -> &[GroupId] {
+        &self.state.channels
+    }
+}
+
+impl NotifierState {
+    /// Record the channels of the given updated channels that this notifier is interested in, in order,
+    /// and wake the reader if there are any.
+    pub(crate) fn notify(&self, updated: &[GroupId]) {
+        let mut relevant = updated
+            .iter()
+            .filter(|id| self.channels.contains(id))
+            .peekable();
+        if relevant.peek().is_none() {
+            return;
+        }
+
+        let mut pending = self.updated.lock().unwrap();
+        for id in relevant {
+            if !pending.contains(*id) {
+                pending.ids.push(*id);
+            }
+        }
+        self.condvar.notify_all();
+    }
+}
+
+impl UpdateSet {
+    /// Returns `true` if the channel with the given id was updated.
+    pub fn contains(&self, id: GroupId) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Iterate over the ids of the updated channels.
+    pub fn iter(&self) -> impl Iterator<Item = GroupId> + '_ {
+        self.ids.iter().copied()
+    }
+
+    /// The number of updated channels.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns `true` if no channel was updated.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{
+        directed::DirectedChannel, group::ChannelGroup, undirected::UndirectedChannel, MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (directed1, read_only1, writable1) = DirectedChannel::create(0, 0);
+        let (directed2, read_only2, mut writable2) = DirectedChannel::create(0, 0);
+        let (undirected, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 0);
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let id1 = group.push_directed(&channel_key, "directed1", directed1);
+        let id2 = group.push_directed(&channel_key, "directed2", directed2);
+        let id3 = group.push_undirected(&channel_key, "undirected", undirected);
+        let notifier = group.notifier(&channel_key, [id1, id2]);
+        let undirected_notifier = group.notifier(&channel_key, [id3]);
+        assert_eq!(notifier.channels(), [id1, id2]);
+
+        // New channels are dirty, hence the first flush happens.
+        group.advance_all(&channel_key);
+        assert_eq!(notifier.take_updates().len(), 2);
+        // Afterwards, both directed channels are clean, so their flushes are skipped.
+        group.advance_all(&channel_key);
+        assert!(notifier.take_updates().is_empty());
+        // Undirected channels change with every swap.
+        assert!(undirected_notifier.wait_for_update().contains(id3));
+        drop(undirected_notifier);
+
+        let reader = thread::spawn(move || notifier.wait_for_update());
+        *writable2.get_mut(&master_key.get_data_key()) = 1;
+        group.advance_all(&master_key.get_channel_key());
+        let updates = reader.join().unwrap();
+        assert!(updates.iter().eq([id2]));
+
+        let _ = (
+            read_only1,
+            writable1,
+            read_only2,
+            data_pointer1,
+            data_pointer2,
+        );
+    }
+}
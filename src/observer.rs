@@ -0,0 +1,256 @@
+//! Read-only views of a channel that do not need to be returned when destroying it,
+//! e.g. for a long-lived diagnostics subsystem that reads the channels of other subsystems while they exist.
+//!
+//! An [ObserverPointer] is created via [UndirectedChannelPointer::observer](crate::undirected::UndirectedChannelPointer::observer)
+//! or [DirectedChannelPointer::observer](crate::directed::DirectedChannelPointer::observer).
+//! It only observes `Data` fields that are never written during the data phase,
+//! i.e. a side of an undirected channel that was made [immutable](crate::undirected::UndirectedDataPointer::into_immutable),
+//! or the read-only `Data` of a directed channel.
+//! All observers of a channel share a state with its channel pointer, which is marked as destroyed when the channel pointer is destroyed or dropped.
+//! Afterwards, [ObserverPointer::read] returns `None` instead of reading the freed `Data`.
+//!
+//! # Validity
+//!
+//! Like the data pointers, an observer only reads the `Data` with a [DataKey], i.e. during the data phase.
+//! The `Data` is only lent to a closure, during which the observer is registered as a reader in the shared state.
+//! Destroying or dropping the channel pointer waits until no observer is reading anymore before the channel is freed,
+//! hence a read never sees freed memory, even if the channel is destroyed on another thread during the data phase.
+//!
+//! # Blocking
+//!
+//! Destroying a channel is not wait-free once it has observers, since the destroyed channel returns its `Data` by value,
+//! which cannot be moved while it is lent to a reading observer.
+//! Destroying or dropping the channel pointer marks the channel as destroyed first, such that reads that start afterwards return `None` right away,
+//! and then blocks the destroying thread, spinning with [thread::yield_now], until all reads that started before have returned.
+//! Long-running reads hence delay destroying the channel for their whole duration.
+//!
+//! Destroying or dropping the channel pointer from within the closure of a read of one of its observers **deadlocks**,
+//! as the destroy waits for the read, which waits for the destroy to return.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crate::DataKey;
+
+/// Set in [ObserverState] once the channel is destroyed.
+const DESTROYED: usize = 1;
+/// Added to [ObserverState] per reading observer.
+const READER: usize = 2;
+
+/// The state shared by a channel pointer with its observers: the number of reading observers times [READER],
+/// plus [DESTROYED] once the channel is destroyed.
+type ObserverState = Arc<AtomicUsize>;
+
+/// A read-only pointer to a `Data` field of a channel that becomes inert once the channel is destroyed.
+/// It can only be accessed using a [DataKey].
+///
+/// See the [module documentation](self) for more info.
+#[derive(Debug)]
+pub struct ObserverPointer<Data> {
+    data: *const Data,
+    state: ObserverState,
+}
+
+/// The state shared by a channel pointer with its observers, created with the first observer.
+/// Marks the channel as destroyed when dropped together with the channel pointer, and blocks until no observer is reading anymore.
+#[derive(Debug, Default)]
+pub(crate) struct Observers {
+    state: Option<ObserverState>,
+}
+
+impl Observers {
+    /// Create an observer of the given `Data` field.
+    pub(crate) fn observe<Data>(&mut self, data: *const Data) -> ObserverPointer<Data> {
+        let state = self
+            .state
+            .get_or_insert_with(|| Arc::new(AtomicUsize::new(0)));
+        ObserverPointer {
+            data,
+            state: Arc::clone(state),
+        }
+    }
+}
+
+impl Drop for Observers {
+    fn drop(&mut self) {
+        if let Some(state) = &self.state {
+            // Readers that register afterwards see the flag and do not read.
+            let mut current = state.fetch_or(DESTROYED, Ordering::AcqRel);
+            while current & !DESTROYED != 0 {
+                thread::yield_now();
+                current = state.load(Ordering::Acquire);
+            }
+        }
+    }
+}
+
+/// Unregisters a reading observer, also if its closure panics.
+struct ReadGuard<'a>(&'a AtomicUsize);
+
+impl Drop for ReadGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(READER, Ordering::Release);
+    }
+}
+
+impl<Data> ObserverPointer<Data> {
+    /// Call the given closure with a reference to the observed `Data` field and return its result,
+    /// or return `None` if the channel was destroyed.
+    /// Destroying the channel blocks until the closure returns, hence the closure must not destroy the channel,
+    /// see the [module documentation](self#blocking).
+    pub fn read<Result>(
+        &self,
+        #[allow(unused)] data_key: &DataKey,
+        f: impl FnOnce(&Data) -> Result,
+    ) -> Option<Result> {
+        let previous = self.state.fetch_add(READER, Ordering::Acquire);
+        let _guard = ReadGuard(&self.state);
+        if previous & DESTROYED == 0 {
+            Some(f(unsafe { &*self.data }))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the channel was not destroyed yet.
+    /// The channel may still be destroyed right after, in which case a following [ObserverPointer::read] returns `None`.
+    pub fn is_alive(&self) -> bool {
+        self.state.load(Ordering::Acquire) & DESTROYED == 0
+    }
+}
+
+impl<Data> Clone for ObserverPointer<Data> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data,
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+unsafe impl<Data: Sync> Send for ObserverPointer<Data> {}
+unsafe impl<Data: Sync> Sync for ObserverPointer<Data> {}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{mpsc, Arc, Barrier},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{directed::DirectedChannel, undirected::UndirectedChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create(vec![1], vec![2]);
+        let data_pointer2 = data_pointer2.into_immutable();
+        let observer = channel_pointer.observer(&data_pointer2);
+        let (mut directed_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let directed_observer = directed_pointer.observer();
+
+        let diagnostics = thread::spawn(move || (observer, directed_observer));
+        let (observer, directed_observer) = diagnostics.join().unwrap();
+        let data_key = master_key.get_data_key();
+        data_pointer1.get_mut(&data_key).push(3);
+        *writable.get_mut(&data_key) = 4;
+        assert_eq!(observer.read(&data_key, Vec::clone), Some(vec![2]));
+
+        let channel_key = data_key.into_channel_key();
+        channel_pointer.swap(&channel_key);
+        directed_pointer.flush(&channel_key);
+        let data_key = master_key.get_data_key();
+        // The observer sees the content of its field after the swap, like a data pointer to it.
+        assert_eq!(observer.read(&data_key, Vec::clone), Some(vec![1, 3]));
+        assert_eq!(directed_observer.read(&data_key, |data| *data), Some(4));
+
+        // The observers are not returned, and become inert.
+        let _channel_key = data_key.into_channel_key();
+        channel_pointer.destroy_immutable(data_pointer1, [data_pointer2]);
+        let data_key = master_key.get_data_key();
+        assert_eq!(observer.read(&data_key, Vec::clone), None);
+        assert!(directed_observer.clone().is_alive());
+        let _channel_key = data_key.into_channel_key();
+        directed_pointer.destroy_single(read_only, writable);
+        assert_eq!(
+            directed_observer.read(&master_key.get_data_key(), |data| *data),
+            None
+        );
+    }
+
+    #[test]
+    fn destroy_waits_for_reading_observers() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) =
+            UndirectedChannel::create(vec![1], vec![2]);
+        let data_pointer1 = data_pointer1.into_immutable();
+        let observer = channel_pointer.observer(&data_pointer1);
+        let data_key = master_key.get_data_key();
+        let reading = Arc::new(Barrier::new(2));
+        let (destroyed_sender, destroyed) = mpsc::channel();
+
+        let destroyer = {
+            let reading = Arc::clone(&reading);
+            thread::spawn(move || {
+                reading.wait();
+                // E.g. while unwinding the owning subsystem, which leaks the data pointers.
+                drop(channel_pointer);
+                destroyed_sender.send(()).unwrap();
+            })
+        };
+        let read = observer.read(&data_key, |data| {
+            reading.wait();
+            // The channel is not freed while it is read.
+            thread::sleep(Duration::from_millis(50));
+            assert!(destroyed.try_recv().is_err());
+            data.clone()
+        });
+        assert_eq!(read, Some(vec![1]));
+        destroyer.join().unwrap();
+        let _ = (data_pointer1, data_pointer2);
+        assert_eq!(observer.read(&data_key, Vec::clone), None);
+    }
+
+    #[test]
+    fn read_during_destroy_returns_none() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) =
+            UndirectedChannel::create(vec![1], vec![2]);
+        let data_pointer1 = data_pointer1.into_immutable();
+        let observer = channel_pointer.observer(&data_pointer1);
+        let late_observer = observer.clone();
+        let data_key = master_key.get_data_key();
+        let reading = Arc::new(Barrier::new(2));
+        let (destroyed_sender, destroyed) = mpsc::channel();
+
+        let destroyer = {
+            let reading = Arc::clone(&reading);
+            thread::spawn(move || {
+                reading.wait();
+                drop(channel_pointer);
+                destroyed_sender.send(()).unwrap();
+            })
+        };
+        let read = observer.read(&data_key, |data| {
+            reading.wait();
+            // The destroy is blocked by this read, but already visible to reads that start now.
+            while late_observer.is_alive() {
+                thread::yield_now();
+            }
+            assert_eq!(late_observer.read(&data_key, Vec::clone), None);
+            assert!(destroyed.try_recv().is_err());
+            data.clone()
+        });
+        assert_eq!(read, Some(vec![1]));
+        destroyer.join().unwrap();
+        assert!(destroyed.try_recv().is_ok());
+        let _ = (data_pointer1, data_pointer2);
+    }
+}
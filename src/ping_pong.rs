@@ -0,0 +1,135 @@
+//! A ping-pong channel for exchanging lists of events.
+//! Producers push events into the back buffer, consumers read the events from the front buffer,
+//! and swapping the buffers clears the new back buffer, such that no event is seen twice.
+
+use core::marker::PhantomData;
+
+use crate::{
+    undirected::{
+        ImmutableUndirectedDataPointer, UndirectedChannel, UndirectedChannelPointer,
+        UndirectedDataPointer,
+    },
+    ChannelKey,
+};
+
+/// A ping-pong channel used for communication between threads.
+/// This is an [`UndirectedChannel`] over two vectors with designated front and back roles.
+/// The back vector is written by the producer, the front vector is read by the consumers.
+///
+/// See [PingPongChannel::create] for more info.
+#[derive(Debug)]
+pub struct PingPongChannel<T> {
+    phantom: PhantomData<T>,
+}
+
+/// A pointer to a ping-pong channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [PingPongChannel::destroy] or [PingPongChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct PingPongChannelPointer<T> {
+    channel_pointer: UndirectedChannelPointer<Vec<T>>,
+}
+
+impl<T> PingPongChannel<T> {
+    /// Create a ping-pong channel and hand out three pointers to it.
+    /// One [PingPongChannelPointer] used to swap the front and back vectors,
+    /// one [ImmutableUndirectedDataPointer] to the front vector, which can be copied for multiple consumers, and
+    /// one [UndirectedDataPointer] to the back vector.
+    pub fn create() -> (
+        PingPongChannelPointer<T>,
+        ImmutableUndirectedDataPointer<Vec<T>>,
+        UndirectedDataPointer<Vec<T>>,
+    ) {
+        Self::create_with_capacity(0)
+    }
+
+    /// Create a ping-pong channel where both vectors have at least the given capacity.
+    ///
+    /// See [PingPongChannel::create] for more details.
+    pub fn create_with_capacity(
+        capacity: usize,
+    ) -> (
+        PingPongChannelPointer<T>,
+        ImmutableUndirectedDataPointer<Vec<T>>,
+        UndirectedDataPointer<Vec<T>>,
+    ) {
+        let (channel_pointer, front, back) =
+            UndirectedChannel::create(Vec::with_capacity(capacity), Vec::with_capacity(capacity));
+        (
+            PingPongChannelPointer { channel_pointer },
+            front.into_immutable(),
+            back,
+        )
+    }
+
+    /// Destroys the ping-pong channel linked with the given pointers (see [PingPongChannel::create]).
+    /// Returns the front and the back vector, in this order.
+    ///
+    /// **Panics** if not all pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: PingPongChannelPointer<T>,
+        front: impl IntoIterator<Item = ImmutableUndirectedDataPointer<Vec<T>>>,
+        back: UndirectedDataPointer<Vec<T>>,
+    ) -> (Vec<T>, Vec<T>) {
+        UndirectedChannel::destroy_immutable(channel_pointer.channel_pointer, back, front)
+    }
+}
+
+impl<T> PingPongChannelPointer<T> {
+    /// Swap the front and back vectors, and clear the new back vector.
+    /// The new back vector keeps its capacity.
+    pub fn swap_and_clear_back(&mut self, channel_key: &ChannelKey) {
+        self.channel_pointer.swap(channel_key);
+        self.channel_pointer.channel.data2.clear();
+    }
+
+    /// The number of elements in the front vector.
+    pub fn front_len(&self, #[allow(unused)] channel_key: &ChannelKey) -> usize {
+        self.channel_pointer.channel.data1.len()
+    }
+
+    /// Shorthand for [PingPongChannel::destroy].
+    pub fn destroy(
+        self,
+        front: impl IntoIterator<Item = ImmutableUndirectedDataPointer<Vec<T>>>,
+        back: UndirectedDataPointer<Vec<T>>,
+    ) -> (Vec<T>, Vec<T>) {
+        PingPongChannel::destroy(self, front, back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ping_pong::PingPongChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, front, mut back) = PingPongChannel::create_with_capacity(4);
+
+        back.get_mut(&master_key.get_data_key()).extend([1, 2]);
+        channel_pointer.swap_and_clear_back(&master_key.get_channel_key());
+        assert_eq!(channel_pointer.front_len(&master_key.get_channel_key()), 2);
+        assert_eq!(*front.get(&master_key.get_data_key()), [1, 2]);
+        assert!(back.get(&master_key.get_data_key()).is_empty());
+
+        back.get_mut(&master_key.get_data_key()).push(3);
+        channel_pointer.swap_and_clear_back(&master_key.get_channel_key());
+        assert_eq!(*front.get(&master_key.get_data_key()), [3]);
+
+        // The new back vector was cleared, but kept its capacity.
+        let back_data = back.get(&master_key.get_data_key());
+        assert!(back_data.is_empty());
+        assert!(back_data.capacity() >= 4);
+
+        channel_pointer.swap_and_clear_back(&master_key.get_channel_key());
+        assert_eq!(channel_pointer.front_len(&master_key.get_channel_key()), 0);
+
+        assert_eq!(
+            channel_pointer.destroy([front], back),
+            (Vec::new(), Vec::new())
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! Two-phase channels for address-sensitive payloads, such as buffers registered with the operating system.
+//! Each `Data` field lives in its own pinned allocation and is never moved while the channel exists.
+//! Instead of moving the `Data` fields, swapping exchanges an index that the data pointers consult on each access.
+
+use core::pin::Pin;
+use std::ptr;
+
+use crate::{arena::ChannelBox, ChannelKey, DataKey};
+
+/// An undirected channel whose `Data` fields never move, used for communication between threads.
+/// It behaves like an [`UndirectedChannel`](crate::undirected::UndirectedChannel),
+/// except that swapping exchanges which `Data` field each data pointer points to.
+///
+/// See [PinnedUndirectedChannel::create] for more info.
+#[derive(Debug)]
+pub struct PinnedUndirectedChannel<Data> {
+    data: [Pin<Box<Data>>; 2],
+    swapped: bool,
+}
+
+/// A pointer to a pinned undirected channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [PinnedUndirectedChannel::destroy] or [PinnedUndirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct PinnedUndirectedChannelPointer<Data> {
+    channel: ChannelBox<PinnedUndirectedChannel<Data>>,
+}
+
+/// A pointer to one of the data fields in a pinned undirected channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [PinnedUndirectedChannel::destroy] or [PinnedUndirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct PinnedUndirectedDataPointer<Data> {
+    channel: *mut PinnedUndirectedChannel<Data>,
+    side: bool,
+}
+
+/// A directed channel whose `Data` fields never move, used for communication between threads.
+/// It behaves like a [`DirectedChannel`](crate::directed::DirectedChannel),
+/// except that the `Data` fields are only accessible as pinned references.
+///
+/// See [PinnedDirectedChannel::create] for more info.
+#[derive(Debug)]
+pub struct PinnedDirectedChannel<Data> {
+    data: [Pin<Box<Data>>; 2],
+    swapped: bool,
+}
+
+/// A pointer to a pinned directed channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [PinnedDirectedChannel::destroy] or [PinnedDirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct PinnedDirectedChannelPointer<Data> {
+    channel: ChannelBox<PinnedDirectedChannel<Data>>,
+}
+
+/// A pointer to the read-only data field in a pinned directed channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [PinnedDirectedChannel::destroy] or [PinnedDirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct PinnedReadOnlyDataPointer<Data> {
+    channel: *const PinnedDirectedChannel<Data>,
+}
+
+/// A pointer to the writable data field in a pinned directed channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [PinnedDirectedChannel::destroy] or [PinnedDirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct PinnedWritableDataPointer<Data> {
+    channel: *mut PinnedDirectedChannel<Data>,
+}
+
+impl<Data> PinnedUndirectedChannel<Data> {
+    /// Create a pinned undirected channel and hand out three pointers to it.
+    /// One [PinnedUndirectedChannelPointer] used to swap the two `Data` fields,
+    /// and two [PinnedUndirectedDataPointer]s, one to each data field.
+    ///
+    /// Each `Data` field is pinned in its own allocation.
+    /// Swapping does not move the `Data` fields, but exchanges the data pointers' access to them.
+    pub fn create(
+        data1: Data,
+        data2: Data,
+    ) -> (
+        PinnedUndirectedChannelPointer<Data>,
+        PinnedUndirectedDataPointer<Data>,
+        PinnedUndirectedDataPointer<Data>,
+    ) {
+        let mut channel = ChannelBox::new(Self {
+            data: [Box::pin(data1), Box::pin(data2)],
+            swapped: false,
+        });
+        let pointer = &mut *channel as *mut Self;
+        (
+            PinnedUndirectedChannelPointer { channel },
+            PinnedUndirectedDataPointer {
+                channel: pointer,
+                side: false,
+            },
+            PinnedUndirectedDataPointer {
+                channel: pointer,
+                side: true,
+            },
+        )
+    }
+
+    /// Destroys the pinned undirected channel linked with the three pointers (see [PinnedUndirectedChannel::create]).
+    /// Returns the `Data` fields the two data pointers pointed to, in the same order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel, or if both data pointers point to the same `Data` field.
+    pub fn destroy(
+        channel_pointer: PinnedUndirectedChannelPointer<Data>,
+        data_pointer1: PinnedUndirectedDataPointer<Data>,
+        data_pointer2: PinnedUndirectedDataPointer<Data>,
+    ) -> (Pin<Box<Data>>, Pin<Box<Data>>) {
+        let channel = channel_pointer.channel;
+        let pointer = &*channel as *const Self;
+        assert!(
+            ptr::eq(data_pointer1.channel, pointer) && ptr::eq(data_pointer2.channel, pointer),
+            "the data pointers do not point to this channel"
+        );
+        assert_ne!(
+            data_pointer1.side, data_pointer2.side,
+            "both data pointers point to the same data field"
+        );
+
+        let channel = channel.into_inner();
+        let [data1, data2] = channel.data;
+        if data_pointer1.side != channel.swapped {
+            (data2, data1)
+        } else {
+            (data1, data2)
+        }
+    }
+}
+
+impl<Data> PinnedUndirectedChannelPointer<Data> {
+    /// Swap the two `Data` fields of the channel, such that each data pointer points to the field the other one pointed to.
+    /// The `Data` fields are not moved.
+    pub fn swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.channel.swapped = !self.channel.swapped;
+    }
+
+    /// Shorthand for [PinnedUndirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: PinnedUndirectedDataPointer<Data>,
+        data_pointer2: PinnedUndirectedDataPointer<Data>,
+    ) -> (Pin<Box<Data>>, Pin<Box<Data>>) {
+        PinnedUndirectedChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+}
+
+impl<Data> PinnedUndirectedDataPointer<Data> {
+    /// The index of the `Data` field this pointer currently points to.
+    fn index(&self) -> usize {
+        unsafe { (self.side != (*self.channel).swapped) as usize }
+    }
+
+    /// Get a reference to the `Data` field this pointer currently points to.
+    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> Pin<&Data> {
+        // Only the field this pointer points to is borrowed, the other data pointer may borrow the other field concurrently.
+        unsafe { (*self.channel).data[self.index()].as_ref() }
+    }
+
+    /// Get a pinned mutable reference to the `Data` field this pointer currently points to.
+    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> Pin<&mut Data> {
+        unsafe { (*self.channel).data[self.index()].as_mut() }
+    }
+}
+
+impl<Data> PinnedDirectedChannel<Data> {
+    /// Create a pinned directed channel and hand out three pointers to it.
+    /// One [PinnedDirectedChannelPointer] used to flush the content of the writable `Data` field into the read-only field,
+    /// one [PinnedReadOnlyDataPointer] used to read from the channel, and
+    /// one [PinnedWritableDataPointer] used to write to the channel.
+    ///
+    /// Each `Data` field is pinned in its own allocation.
+    pub fn create(
+        read_only: Data,
+        writable: Data,
+    ) -> (
+        PinnedDirectedChannelPointer<Data>,
+        PinnedReadOnlyDataPointer<Data>,
+        PinnedWritableDataPointer<Data>,
+    ) {
+        let mut channel = ChannelBox::new(Self {
+            data: [Box::pin(read_only), Box::pin(writable)],
+            swapped: false,
+        });
+        let pointer = &mut *channel as *mut Self;
+        (
+            PinnedDirectedChannelPointer { channel },
+            PinnedReadOnlyDataPointer { channel: pointer },
+            PinnedWritableDataPointer { channel: pointer },
+        )
+    }
+
+    /// Destroys the pinned directed channel linked with the three pointers (see [PinnedDirectedChannel::create]).
+    /// Returns the read-only and the writable `Data` field, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: PinnedDirectedChannelPointer<Data>,
+        read_only_data_pointer: PinnedReadOnlyDataPointer<Data>,
+        writable_data_pointer: PinnedWritableDataPointer<Data>,
+    ) -> (Pin<Box<Data>>, Pin<Box<Data>>) {
+        let channel = channel_pointer.channel;
+        let pointer = &*channel as *const Self;
+        assert!(
+            ptr::eq(read_only_data_pointer.channel, pointer)
+                && ptr::eq(writable_data_pointer.channel, pointer),
+            "the data pointers do not point to this channel"
+        );
+
+        let channel = channel.into_inner();
+        let swapped = channel.swapped;
+        let [data1, data2] = channel.data;
+        if swapped {
+            (data2, data1)
+        } else {
+            (data1, data2)
+        }
+    }
+
+    /// The index of the read-only `Data` field.
+    fn read_only_index(&self) -> usize {
+        self.swapped as usize
+    }
+
+    /// The index of the writable `Data` field.
+    fn writable_index(&self) -> usize {
+        !self.swapped as usize
+    }
+}
+
+impl<Data> PinnedDirectedChannelPointer<Data> {
+    /// Flush the writable `Data` field into the read-only field with the given function,
+    /// which receives the read-only field as its first and the writable field as its second argument.
+    /// The `Data` fields are not moved, so the function has to update the read-only field in place.
+    pub fn flush_with(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        flush: impl FnOnce(Pin<&mut Data>, &Data),
+    ) {
+        let channel = &mut *self.channel;
+        let [data1, data2] = &mut channel.data;
+        let (read_only, writable) = if channel.swapped {
+            (data2, data1)
+        } else {
+            (data1, data2)
+        };
+        flush(read_only.as_mut(), writable);
+    }
+
+    /// Exchange the read-only and the writable `Data` field without moving them.
+    /// Afterwards, the read-only data pointer points to what was written, and the writable data pointer points to what was read before.
+    pub fn flush_swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.channel.swapped = !self.channel.swapped;
+    }
+
+    /// Shorthand for [PinnedDirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        read_only_data_pointer: PinnedReadOnlyDataPointer<Data>,
+        writable_data_pointer: PinnedWritableDataPointer<Data>,
+    ) -> (Pin<Box<Data>>, Pin<Box<Data>>) {
+        PinnedDirectedChannel::destroy(self, read_only_data_pointer, writable_data_pointer)
+    }
+}
+
+impl<Data: Clone + Unpin> PinnedDirectedChannelPointer<Data> {
+    /// Flush (copy) the content of the writable `Data` field into the read-only field using [Clone::clone_from].
+    /// Since `Data` is [Unpin], it may be modified in place through a mutable reference.
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        self.flush_with(channel_key, |read_only, writable| {
+            Pin::get_mut(read_only).clone_from(writable)
+        });
+    }
+}
+
+impl<Data> PinnedReadOnlyDataPointer<Data> {
+    /// Get a reference to the read-only `Data` field.
+    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> Pin<&Data> {
+        unsafe {
+            let channel = self.channel;
+            (*channel).data[(*channel).read_only_index()].as_ref()
+        }
+    }
+}
+
+impl<Data> PinnedWritableDataPointer<Data> {
+    /// Get a reference to the writable `Data` field.
+    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> Pin<&Data> {
+        unsafe {
+            let channel = self.channel;
+            (*channel).data[(*channel).writable_index()].as_ref()
+        }
+    }
+
+    /// Get a pinned mutable reference to the writable `Data` field.
+    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> Pin<&mut Data> {
+        unsafe {
+            let channel = self.channel;
+            (*channel).data[(*channel).writable_index()].as_mut()
+        }
+    }
+}
+
+unsafe impl<Data: Send> Send for PinnedUndirectedChannelPointer<Data> {}
+unsafe impl<Data: Send> Send for PinnedUndirectedDataPointer<Data> {}
+unsafe impl<Data: Send> Send for PinnedDirectedChannelPointer<Data> {}
+unsafe impl<Data: Send + Sync> Send for PinnedReadOnlyDataPointer<Data> {}
+unsafe impl<Data: Send> Send for PinnedWritableDataPointer<Data> {}
+
+unsafe impl<Data: Sync> Sync for PinnedUndirectedChannelPointer<Data> {}
+unsafe impl<Data: Sync> Sync for PinnedUndirectedDataPointer<Data> {}
+unsafe impl<Data: Sync> Sync for PinnedDirectedChannelPointer<Data> {}
+unsafe impl<Data: Sync> Sync for PinnedReadOnlyDataPointer<Data> {}
+unsafe impl<Data: Sync> Sync for PinnedWritableDataPointer<Data> {}
+
+#[cfg(test)]
+mod tests {
+    use core::{marker::PhantomPinned, pin::Pin};
+
+    use crate::{
+        pinned::{PinnedDirectedChannel, PinnedUndirectedChannel},
+        MasterKey,
+    };
+
+    /// A payload that must not be moved.
+    #[derive(Debug)]
+    struct Registered {
+        value: u32,
+        _pinned: PhantomPinned,
+    }
+
+    impl Registered {
+        fn new(value: u32) -> Self {
+            Self {
+                value,
+                _pinned: PhantomPinned,
+            }
+        }
+
+        fn set(self: Pin<&mut Self>, value: u32) {
+            unsafe { self.get_unchecked_mut().value = value }
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            PinnedUndirectedChannel::create(Registered::new(0), Registered::new(1));
+
+        let data_key = master_key.get_data_key();
+        let address1 = &*data_pointer1.get(&data_key) as *const Registered;
+        let address2 = &*data_pointer2.get(&data_key) as *const Registered;
+        data_pointer1.get_mut(&data_key).set(2);
+
+        channel_pointer.swap(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.get(&data_key).value, 1);
+        assert_eq!(data_pointer2.get(&data_key).value, 2);
+        assert_eq!(&*data_pointer1.get(&data_key) as *const _, address2);
+        assert_eq!(&*data_pointer2.get(&data_key) as *const _, address1);
+
+        let (data1, data2) = channel_pointer.destroy(data_pointer1, data_pointer2);
+        assert_eq!((data1.value, data2.value), (1, 2));
+        assert_eq!(&*data1 as *const _, address2);
+        assert_eq!(&*data2 as *const _, address1);
+    }
+
+    #[test]
+    fn directed() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, read_only, mut writable) =
+            PinnedDirectedChannel::create(Registered::new(0), Registered::new(0));
+
+        writable.get_mut(&master_key.get_data_key()).set(1);
+        channel_pointer.flush_with(&master_key.get_channel_key(), |read_only, writable| {
+            read_only.set(writable.value)
+        });
+        assert_eq!(read_only.get(&master_key.get_data_key()).value, 1);
+
+        writable.get_mut(&master_key.get_data_key()).set(2);
+        channel_pointer.flush_swap(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only.get(&data_key).value, 2);
+        assert_eq!(writable.get(&data_key).value, 1);
+
+        let (read_only, writable) = channel_pointer.destroy(read_only, writable);
+        assert_eq!((read_only.value, writable.value), (2, 1));
+
+        let (mut channel_pointer, read_only, mut writable) =
+            PinnedDirectedChannel::create(vec![0], vec![0]);
+        writable.get_mut(&master_key.get_data_key()).push(1);
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*read_only.get(&master_key.get_data_key()), [0, 1]);
+        let (read_only, writable) = channel_pointer.destroy(read_only, writable);
+        assert_eq!((&*read_only, &*writable), (&vec![0, 1], &vec![0, 1]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn destroy_rejects_foreign_pointers() {
+        let (channel_pointer, data_pointer1, _) = PinnedUndirectedChannel::create(0, 1);
+        let (_, _, data_pointer2) = PinnedUndirectedChannel::create(0, 1);
+        let _ = channel_pointer.destroy(data_pointer1, data_pointer2);
+    }
+}
@@ -0,0 +1,151 @@
+//! A pipeline of two-phase stages built on top of the [rotating channel](crate::rotating).
+//! Each stage owns one instance of the transmitted data,
+//! and advancing the pipeline hands each instance on to the next stage.
+
+use core::marker::PhantomData;
+
+use crate::{
+    rotating::{RotatingChannel, RotatingChannelPointer, RotatingDataPointer},
+    ChannelKey, DataKey,
+};
+
+/// A pipeline used for communication between the stages of an assembly line, each running in its own thread.
+/// It holds one instance of `Data` per stage.
+///
+/// See [Pipeline::create] for more info.
+#[derive(Debug)]
+pub struct Pipeline<Data> {
+    phantom: PhantomData<Data>,
+}
+
+/// A pointer to a pipeline, used to advance the `Data` fields to the next stage.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [Pipeline::destroy] or [PipelinePointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct PipelinePointer<Data> {
+    channel_pointer: RotatingChannelPointer<Data>,
+}
+
+/// A pointer to the `Data` field of one stage of a pipeline.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [Pipeline::destroy] or [PipelinePointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct StageDataPointer<Data> {
+    data_pointer: RotatingDataPointer<Data>,
+}
+
+impl<Data> Pipeline<Data> {
+    /// Create a pipeline with one stage per given `Data` and hand out pointers to it.
+    /// One [PipelinePointer] used to advance the `Data` fields through the stages,
+    /// and one [StageDataPointer] per stage, in stage order.
+    ///
+    /// On [PipelinePointer::advance], the `Data` of stage `i` moves to stage `i + 1`.
+    /// The `Data` of the last stage has completed the pipeline and wraps around to the first stage,
+    /// such that it can be reused as the buffer for new input.
+    pub fn create(initials: Vec<Data>) -> (PipelinePointer<Data>, Vec<StageDataPointer<Data>>) {
+        let (channel_pointer, data_pointers) = RotatingChannel::create(initials);
+        (
+            PipelinePointer { channel_pointer },
+            data_pointers
+                .into_iter()
+                .map(|data_pointer| StageDataPointer { data_pointer })
+                .collect(),
+        )
+    }
+
+    /// Destroys the pipeline linked with the given pointers (see [Pipeline::create]).
+    /// The `Data` fields are returned in stage order.
+    ///
+    /// **Panics** if not all pointers point to the same pipeline, or if not every stage is pointed to by exactly one of the given pointers.
+    pub fn destroy(
+        pipeline_pointer: PipelinePointer<Data>,
+        stage_data_pointers: impl IntoIterator<Item = StageDataPointer<Data>>,
+    ) -> Vec<Data> {
+        RotatingChannel::destroy(
+            pipeline_pointer.channel_pointer,
+            stage_data_pointers
+                .into_iter()
+                .map(|stage_data_pointer| stage_data_pointer.data_pointer),
+        )
+    }
+}
+
+impl<Data> PipelinePointer<Data> {
+    /// Move the `Data` of each stage on to the next stage,
+    /// and the `Data` of the last stage back to the first stage.
+    pub fn advance(&mut self, channel_key: &ChannelKey) {
+        self.channel_pointer.rotate(channel_key);
+    }
+
+    /// The number of stages in the pipeline.
+    pub fn stages(&self) -> usize {
+        self.channel_pointer.len()
+    }
+
+    /// Shorthand for [Pipeline::destroy].
+    pub fn destroy(
+        self,
+        stage_data_pointers: impl IntoIterator<Item = StageDataPointer<Data>>,
+    ) -> Vec<Data> {
+        Pipeline::destroy(self, stage_data_pointers)
+    }
+}
+
+impl<Data> StageDataPointer<Data> {
+    /// Get a reference to the `Data` field of this stage.
+    pub fn get(&self, data_key: &DataKey) -> &Data {
+        self.data_pointer.get(data_key)
+    }
+
+    /// Get a mutable reference to the `Data` field of this stage.
+    pub fn get_mut(&mut self, data_key: &DataKey) -> &mut Data {
+        self.data_pointer.get_mut(data_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{pipeline::Pipeline, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut pipeline_pointer, mut stage_data_pointers) = Pipeline::create(vec![Vec::new(); 3]);
+        assert_eq!(pipeline_pointer.stages(), 3);
+
+        for item in 0..5 {
+            let data_key = master_key.get_data_key();
+            for (stage, stage_data_pointer) in stage_data_pointers.iter_mut().enumerate() {
+                let data = stage_data_pointer.get_mut(&data_key);
+                if stage == 0 {
+                    data.clear();
+                }
+                data.push((item, stage));
+            }
+
+            pipeline_pointer.advance(&data_key.into_channel_key());
+        }
+
+        // Each buffer passed through all stages in order, and the last stage's buffer wrapped around to the first stage.
+        let data_key = master_key.get_data_key();
+        assert_eq!(
+            stage_data_pointers[0].get(&data_key),
+            &[(2, 0), (3, 1), (4, 2)]
+        );
+        assert_eq!(stage_data_pointers[1].get(&data_key), &[(4, 0)]);
+        assert_eq!(stage_data_pointers[2].get(&data_key), &[(3, 0), (4, 1)]);
+
+        assert_eq!(
+            pipeline_pointer.destroy(stage_data_pointers),
+            [
+                vec![(2, 0), (3, 1), (4, 2)],
+                vec![(4, 0)],
+                vec![(3, 0), (4, 1)]
+            ]
+        );
+    }
+}
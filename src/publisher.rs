@@ -0,0 +1,204 @@
+//! Publication of the values flushed through a [directed channel](crate::directed) to threads that do not participate in the phase protocol,
+//! such as a logger or a network broadcaster.
+//! The subscribers hold plain mpsc [Receiver]s.
+
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver, SyncSender, TrySendError},
+};
+
+use crate::{directed::DirectedChannelPointer, ChannelKey};
+
+/// What a [SnapshotPublisher] does with a value for a subscriber whose queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FullPolicy {
+    /// Drop the new value.
+    DropNewest,
+    /// Keep the new value in a backlog of the publisher, which is sent before newer values once the queue has space again.
+    /// If the backlog exceeds the capacity of the queue, its oldest value is dropped.
+    /// Values that were already sent to the queue are never dropped, since they cannot be taken back from an mpsc channel.
+    DropOldest,
+    /// Block the channel phase until the subscriber received enough values for the new value to fit.
+    /// A slow subscriber stalls the coordinator with this policy.
+    Block,
+}
+
+/// Wraps a [DirectedChannelPointer] and sends a clone of each newly flushed value to its subscribers.
+/// Flushes of clean channels are skipped, and hence publish nothing.
+///
+/// With [FullPolicy::DropNewest] and [FullPolicy::DropOldest], sending never blocks, such that a slow subscriber cannot stall the channel phase.
+/// Subscribers whose receiver was dropped are removed at the next flush.
+#[derive(Debug)]
+pub struct SnapshotPublisher<Data> {
+    channel_pointer: DirectedChannelPointer<Data>,
+    subscribers: Vec<Subscriber<Data>>,
+}
+
+#[derive(Debug)]
+struct Subscriber<Data> {
+    sender: SyncSender<Data>,
+    policy: FullPolicy,
+    capacity: usize,
+    /// The values that did not fit into the queue with [FullPolicy::DropOldest], oldest first.
+    backlog: VecDeque<Data>,
+}
+
+impl<Data: Clone + Send> SnapshotPublisher<Data> {
+    /// Wrap the given channel pointer, without subscribers.
+    pub fn new(channel_pointer: DirectedChannelPointer<Data>) -> Self {
+        Self {
+            channel_pointer,
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Add a subscriber with a queue of the given capacity and the given policy for a full queue.
+    /// The subscriber receives the values flushed after this call.
+    ///
+    /// **Panics** if the capacity is zero.
+    pub fn subscribe(&mut self, capacity: usize, policy: FullPolicy) -> Receiver<Data> {
+        assert!(
+            capacity > 0,
+            "the capacity of a subscriber must not be zero"
+        );
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        self.subscribers.push(Subscriber {
+            sender,
+            policy,
+            capacity,
+            backlog: VecDeque::new(),
+        });
+        receiver
+    }
+
+    /// Flush the channel, and send a clone of the flushed value to each subscriber if the channel was dirty.
+    /// Returns `true` if the channel was flushed.
+    pub fn flush(&mut self, channel_key: &ChannelKey) -> bool {
+        let dirty = self.channel_pointer.is_dirty(channel_key);
+        self.channel_pointer.flush(channel_key);
+        if dirty {
+            let value = self
+                .channel_pointer
+                .inspect(channel_key, |read_only, _| read_only.clone());
+            self.publish(value);
+        }
+        dirty
+    }
+
+    /// Send the given value to each subscriber according to its policy, and remove the disconnected subscribers.
+    fn publish(&mut self, value: Data) {
+        let mut index = 0;
+        while index < self.subscribers.len() {
+            if self.subscribers[index].send(value.clone()) {
+                index += 1;
+            } else {
+                self.subscribers.remove(index);
+            }
+        }
+    }
+
+    /// The number of subscribers that were not disconnected at the last flush.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// The wrapped channel pointer.
+    pub fn channel_pointer(&mut self) -> &mut DirectedChannelPointer<Data> {
+        &mut self.channel_pointer
+    }
+
+    /// Return the wrapped channel pointer, e.g. for destruction, dropping all subscribers.
+    pub fn into_inner(self) -> DirectedChannelPointer<Data> {
+        self.channel_pointer
+    }
+}
+
+impl<Data> Subscriber<Data> {
+    /// Send the given value according to the policy.
+    /// Returns `false` if the subscriber is disconnected.
+    fn send(&mut self, value: Data) -> bool {
+        match self.policy {
+            FullPolicy::DropNewest => !matches!(
+                self.sender.try_send(value),
+                Err(TrySendError::Disconnected(_))
+            ),
+            FullPolicy::DropOldest => {
+                self.backlog.push_back(value);
+                while let Some(value) = self.backlog.pop_front() {
+                    match self.sender.try_send(value) {
+                        Ok(()) => {}
+                        Err(TrySendError::Full(value)) => {
+                            self.backlog.push_front(value);
+                            break;
+                        }
+                        Err(TrySendError::Disconnected(_)) => return false,
+                    }
+                }
+                if self.backlog.len() > self.capacity {
+                    self.backlog.pop_front();
+                }
+                true
+            }
+            FullPolicy::Block => self.sender.send(value).is_ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc::TryRecvError, thread};
+
+    use crate::{
+        directed::DirectedChannel,
+        publisher::{FullPolicy, SnapshotPublisher},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let mut publisher = SnapshotPublisher::new(channel_pointer);
+        let newest = publisher.subscribe(2, FullPolicy::DropNewest);
+        let oldest = publisher.subscribe(2, FullPolicy::DropOldest);
+        let disconnected = publisher.subscribe(1, FullPolicy::DropNewest);
+        drop(disconnected);
+
+        // New channels are dirty, hence the first flush publishes.
+        assert!(publisher.flush(&master_key.get_channel_key()));
+        assert_eq!(publisher.subscriber_count(), 2);
+        // Clean flushes publish nothing.
+        assert!(!publisher.flush(&master_key.get_channel_key()));
+        for value in 1..=5 {
+            *writable.get_mut(&master_key.get_data_key()) = value;
+            assert!(publisher.flush(&master_key.get_channel_key()));
+        }
+
+        assert_eq!(newest.try_iter().collect::<Vec<_>>(), [0, 1]);
+        // The queue holds 0 and 1, and the backlog the newest two of 2 to 5.
+        assert_eq!(oldest.try_iter().collect::<Vec<_>>(), [0, 1]);
+        *writable.get_mut(&master_key.get_data_key()) = 6;
+        publisher.flush(&master_key.get_channel_key());
+        assert_eq!(oldest.try_iter().collect::<Vec<_>>(), [4, 5]);
+        assert_eq!(newest.try_iter().collect::<Vec<_>>(), [6]);
+        assert_eq!(oldest.try_recv(), Err(TryRecvError::Empty));
+        let _ = (read_only, publisher.into_inner());
+    }
+
+    #[test]
+    fn block() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let mut publisher = SnapshotPublisher::new(channel_pointer);
+        let receiver = publisher.subscribe(1, FullPolicy::Block);
+        let subscriber = thread::spawn(move || receiver.iter().collect::<Vec<_>>());
+
+        for value in 0..10 {
+            *writable.get_mut(&master_key.get_data_key()) = value;
+            publisher.flush(&master_key.get_channel_key());
+        }
+        let channel_pointer = publisher.into_inner();
+        assert_eq!(subscriber.join().unwrap(), (0..10).collect::<Vec<_>>());
+        let _ = (read_only, channel_pointer);
+    }
+}
@@ -0,0 +1,662 @@
+//! Queue channels built on top of the [undirected channel](crate::undirected).
+//! The sender pushes items into its outbox, and a flush appends the outbox to the inbox of the receiver,
+//! which drains it at its own pace.
+//! Items that the receiver did not drain before a flush are kept, so no item is lost or duplicated.
+//!
+//! The [bounded queue channel](BoundedQueueChannel) instead holds at most a fixed number of items in its outbox and its inbox,
+//! and drops items according to its [OverflowPolicy] if the receiver stalls.
+
+use core::marker::PhantomData;
+use std::{
+    any,
+    collections::{vec_deque, VecDeque},
+    vec::Drain,
+};
+
+use crate::{
+    undirected::{UndirectedChannel, UndirectedChannelPointer, UndirectedDataPointer},
+    ChannelKey, DataKey, SwapChannel,
+};
+
+/// A queue channel used for sending any number of items from one thread to another per data phase.
+///
+/// See [QueueChannel::create] for more info.
+#[derive(Debug)]
+pub struct QueueChannel<T> {
+    phantom: PhantomData<T>,
+}
+
+/// A pointer to a queue channel, used to deliver the pushed items to the receiver.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [QueueChannel::destroy] or [QueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct QueueChannelPointer<T> {
+    channel_pointer: UndirectedChannelPointer<Vec<T>>,
+}
+
+/// A pointer to the outbox of a queue channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [QueueChannel::destroy] or [QueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct QueueSender<T> {
+    data_pointer: UndirectedDataPointer<Vec<T>>,
+}
+
+/// A pointer to the inbox of a queue channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [QueueChannel::destroy] or [QueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct QueueReceiver<T> {
+    data_pointer: UndirectedDataPointer<Vec<T>>,
+}
+
+impl<T> QueueChannel<T> {
+    /// Create a queue channel and hand out three pointers to it.
+    /// One [QueueChannelPointer] used to deliver the pushed items,
+    /// one [QueueSender] used to push items, and
+    /// one [QueueReceiver] used to drain the delivered items.
+    pub fn create() -> (QueueChannelPointer<T>, QueueSender<T>, QueueReceiver<T>) {
+        let (channel_pointer, outbox, inbox) = UndirectedChannel::create(Vec::new(), Vec::new());
+        (
+            QueueChannelPointer { channel_pointer },
+            QueueSender {
+                data_pointer: outbox,
+            },
+            QueueReceiver {
+                data_pointer: inbox,
+            },
+        )
+    }
+
+    /// Destroys the queue channel linked with the three pointers (see [QueueChannel::create]).
+    /// Returns the items that were pushed but not delivered, and the items that were delivered but not drained, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: QueueChannelPointer<T>,
+        sender: QueueSender<T>,
+        receiver: QueueReceiver<T>,
+    ) -> (Vec<T>, Vec<T>) {
+        UndirectedChannel::destroy(
+            channel_pointer.channel_pointer,
+            sender.data_pointer,
+            receiver.data_pointer,
+        )
+    }
+}
+
+impl<T> QueueChannelPointer<T> {
+    /// Append the items in the outbox to the inbox, preserving the capacity of the outbox.
+    /// Items in the inbox that were not drained yet are kept before the newly delivered items.
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        let channel = &mut self.channel_pointer.channel;
+        if channel.data1.is_empty() {
+            return;
+        }
+
+        channel.verify_checksum(channel_key);
+        let UndirectedChannel {
+            data1: outbox,
+            data2: inbox,
+            ..
+        } = &mut **channel;
+        inbox.append(outbox);
+        channel.generation += 1;
+        channel.record_checksum(channel_key);
+    }
+
+    /// Append the given items directly to the inbox, after the items that were already delivered.
+    /// This bypasses the outbox, e.g. to deliver items from outside of the phase protocol (see [MpscBridge](crate::bridge::MpscBridge)).
+    /// Returns the number of delivered items.
+    pub fn deliver(
+        &mut self,
+        channel_key: &ChannelKey,
+        items: impl IntoIterator<Item = T>,
+    ) -> usize {
+        let channel = &mut self.channel_pointer.channel;
+        channel.verify_checksum(channel_key);
+        let inbox = &mut channel.data2.0;
+        let len = inbox.len();
+        inbox.extend(items);
+        let delivered = inbox.len() - len;
+        if delivered > 0 {
+            channel.generation += 1;
+        }
+        channel.record_checksum(channel_key);
+        delivered
+    }
+
+    /// Shorthand for [QueueChannel::destroy].
+    pub fn destroy(self, sender: QueueSender<T>, receiver: QueueReceiver<T>) -> (Vec<T>, Vec<T>) {
+        QueueChannel::destroy(self, sender, receiver)
+    }
+}
+
+impl<T> QueueSender<T> {
+    /// Push the given item into the outbox, such that it is delivered by the next flush.
+    pub fn push(&mut self, data_key: &DataKey, item: T) {
+        self.data_pointer.get_mut(data_key).push(item);
+    }
+}
+
+impl<T> QueueReceiver<T> {
+    /// Remove all delivered items from the inbox, in the order in which they were pushed.
+    pub fn drain<'a>(&'a mut self, data_key: &'a DataKey) -> Drain<'a, T> {
+        self.data_pointer.get_mut(data_key).drain(..)
+    }
+
+    /// The number of delivered items that were not drained yet.
+    pub fn len(&self, data_key: &DataKey) -> usize {
+        self.data_pointer.get(data_key).len()
+    }
+
+    /// Returns `true` if all delivered items were drained.
+    pub fn is_empty(&self, data_key: &DataKey) -> bool {
+        self.data_pointer.get(data_key).is_empty()
+    }
+}
+
+/// A pair of queue channels used for exchanging items between two threads in both directions.
+/// The forward direction transmits `Fwd` from the second to the first endpoint,
+/// and the backward direction transmits `Bwd` from the first to the second endpoint, like in a [`BidirectedChannel`](crate::bidirected::BidirectedChannel).
+///
+/// See [BidirectedQueueChannel::create] for more info.
+#[derive(Debug)]
+pub struct BidirectedQueueChannel<Fwd, Bwd> {
+    phantom: PhantomData<(Fwd, Bwd)>,
+}
+
+/// A pointer to a bidirected queue channel, used to deliver the pushed items in both directions.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [BidirectedQueueChannel::destroy] or [BidirectedQueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BidirectedQueueChannelPointer<Fwd, Bwd> {
+    forward: QueueChannelPointer<Fwd>,
+    backward: QueueChannelPointer<Bwd>,
+}
+
+/// An endpoint of a bidirected queue channel, which receives `Input` and sends `Output`.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [BidirectedQueueChannel::destroy] or [BidirectedQueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BidirectedQueueEndpoint<Input, Output> {
+    receiver: QueueReceiver<Input>,
+    sender: QueueSender<Output>,
+}
+
+impl<Fwd, Bwd> BidirectedQueueChannel<Fwd, Bwd> {
+    /// Create a bidirected queue channel and hand out three pointers to it.
+    /// One [BidirectedQueueChannelPointer] used to deliver the pushed items in both directions,
+    /// and two [BidirectedQueueEndpoint]s, one per thread.
+    #[allow(clippy::type_complexity)]
+    pub fn create() -> (
+        BidirectedQueueChannelPointer<Fwd, Bwd>,
+        BidirectedQueueEndpoint<Fwd, Bwd>,
+        BidirectedQueueEndpoint<Bwd, Fwd>,
+    ) {
+        let (forward, forward_sender, forward_receiver) = QueueChannel::create();
+        let (backward, backward_sender, backward_receiver) = QueueChannel::create();
+        (
+            BidirectedQueueChannelPointer { forward, backward },
+            BidirectedQueueEndpoint {
+                receiver: forward_receiver,
+                sender: backward_sender,
+            },
+            BidirectedQueueEndpoint {
+                receiver: backward_receiver,
+                sender: forward_sender,
+            },
+        )
+    }
+
+    /// Destroys the bidirected queue channel linked with the three pointers (see [BidirectedQueueChannel::create]).
+    /// Returns the undelivered and undrained items of the forward and the backward direction, in this order (see [QueueChannel::destroy]).
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    #[allow(clippy::type_complexity)]
+    pub fn destroy(
+        channel_pointer: BidirectedQueueChannelPointer<Fwd, Bwd>,
+        endpoint1: BidirectedQueueEndpoint<Fwd, Bwd>,
+        endpoint2: BidirectedQueueEndpoint<Bwd, Fwd>,
+    ) -> ((Vec<Fwd>, Vec<Fwd>), (Vec<Bwd>, Vec<Bwd>)) {
+        (
+            channel_pointer
+                .forward
+                .destroy(endpoint2.sender, endpoint1.receiver),
+            channel_pointer
+                .backward
+                .destroy(endpoint1.sender, endpoint2.receiver),
+        )
+    }
+}
+
+impl<Fwd, Bwd> BidirectedQueueChannelPointer<Fwd, Bwd> {
+    /// Deliver the pushed items in both directions (see [QueueChannelPointer::flush]).
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        self.forward.flush(channel_key);
+        self.backward.flush(channel_key);
+    }
+
+    /// Shorthand for [BidirectedQueueChannel::destroy].
+    #[allow(clippy::type_complexity)]
+    pub fn destroy(
+        self,
+        endpoint1: BidirectedQueueEndpoint<Fwd, Bwd>,
+        endpoint2: BidirectedQueueEndpoint<Bwd, Fwd>,
+    ) -> ((Vec<Fwd>, Vec<Fwd>), (Vec<Bwd>, Vec<Bwd>)) {
+        BidirectedQueueChannel::destroy(self, endpoint1, endpoint2)
+    }
+}
+
+impl<T> SwapChannel for QueueChannelPointer<T> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        QueueChannelPointer::flush(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.channel_pointer.label()
+    }
+
+    fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel_pointer.channel.generation)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<T>())
+    }
+}
+
+impl<Fwd, Bwd> SwapChannel for BidirectedQueueChannelPointer<Fwd, Bwd> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        BidirectedQueueChannelPointer::flush(self, channel_key);
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        Some(
+            self.forward
+                .generation(channel_key)?
+                .wrapping_add(self.backward.generation(channel_key)?),
+        )
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<(Fwd, Bwd)>())
+    }
+}
+
+impl<Input, Output> BidirectedQueueEndpoint<Input, Output> {
+    /// Push the given item to the other endpoint (see [QueueSender::push]).
+    pub fn push_out(&mut self, data_key: &DataKey, item: Output) {
+        self.sender.push(data_key, item);
+    }
+
+    /// Remove all items received from the other endpoint (see [QueueReceiver::drain]).
+    pub fn drain_in<'a>(&'a mut self, data_key: &'a DataKey) -> Drain<'a, Input> {
+        self.receiver.drain(data_key)
+    }
+}
+
+/// A queue channel with a fixed capacity, used for sending up to a bounded number of items from one thread to another per data phase,
+/// e.g. log entries or events.
+///
+/// See [BoundedQueueChannel::create] for more info.
+#[derive(Debug)]
+pub struct BoundedQueueChannel<T> {
+    phantom: PhantomData<T>,
+}
+
+/// What a bounded queue channel does with an item that does not fit into its outbox or inbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Keep the older items and drop the new item.
+    /// [BoundedQueueSender::push] returns the item as error.
+    DropNewest,
+    /// Drop the oldest item to make room for the new item.
+    DropOldest,
+}
+
+/// A pointer to a bounded queue channel, used to deliver the pushed items to the receiver.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [BoundedQueueChannel::destroy] or [BoundedQueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BoundedQueueChannelPointer<T> {
+    channel_pointer: UndirectedChannelPointer<Ring<T>>,
+}
+
+/// A pointer to the outbox of a bounded queue channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [BoundedQueueChannel::destroy] or [BoundedQueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BoundedQueueSender<T> {
+    data_pointer: UndirectedDataPointer<Ring<T>>,
+}
+
+/// A pointer to the inbox of a bounded queue channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [BoundedQueueChannel::destroy] or [BoundedQueueChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct BoundedQueueReceiver<T> {
+    data_pointer: UndirectedDataPointer<Ring<T>>,
+}
+
+/// The outbox or the inbox of a bounded queue channel.
+#[derive(Debug)]
+pub(crate) struct Ring<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    /// The total number of dropped items, only maintained in the outbox.
+    dropped: u64,
+}
+
+impl<T> Ring<T> {
+    fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+            policy,
+            dropped: 0,
+        }
+    }
+}
+
+impl<T> BoundedQueueChannel<T> {
+    /// Create a bounded queue channel whose outbox and inbox each hold at most `capacity` items, and hand out three pointers to it.
+    /// One [BoundedQueueChannelPointer] used to deliver the pushed items,
+    /// one [BoundedQueueSender] used to push items, and
+    /// one [BoundedQueueReceiver] used to drain the delivered items.
+    ///
+    /// Both the outbox and the inbox are ring buffers that are allocated here, such that pushing, flushing and draining never allocate.
+    ///
+    /// **Panics** if the capacity is zero.
+    pub fn create(
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> (
+        BoundedQueueChannelPointer<T>,
+        BoundedQueueSender<T>,
+        BoundedQueueReceiver<T>,
+    ) {
+        assert!(
+            capacity > 0,
+            "the capacity of a bounded queue channel must not be zero"
+        );
+        let (channel_pointer, outbox, inbox) =
+            UndirectedChannel::create(Ring::new(capacity, policy), Ring::new(capacity, policy));
+        (
+            BoundedQueueChannelPointer { channel_pointer },
+            BoundedQueueSender {
+                data_pointer: outbox,
+            },
+            BoundedQueueReceiver {
+                data_pointer: inbox,
+            },
+        )
+    }
+
+    /// Destroys the bounded queue channel linked with the three pointers (see [BoundedQueueChannel::create]).
+    /// Returns the items that were pushed but not delivered, and the items that were delivered but not drained, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: BoundedQueueChannelPointer<T>,
+        sender: BoundedQueueSender<T>,
+        receiver: BoundedQueueReceiver<T>,
+    ) -> (VecDeque<T>, VecDeque<T>) {
+        let (outbox, inbox) = UndirectedChannel::destroy(
+            channel_pointer.channel_pointer,
+            sender.data_pointer,
+            receiver.data_pointer,
+        );
+        (outbox.items, inbox.items)
+    }
+}
+
+impl<T> BoundedQueueChannelPointer<T> {
+    /// Move the items in the outbox to the inbox, after the items that were not drained yet.
+    /// If the inbox has no room for all items, items are dropped according to the [OverflowPolicy]:
+    /// with [OverflowPolicy::DropNewest], only as many items as fit are moved and the remaining items are dropped,
+    /// and with [OverflowPolicy::DropOldest], the oldest undrained items are dropped to make room.
+    ///
+    /// Returns the number of items dropped by this flush.
+    pub fn flush(&mut self, channel_key: &ChannelKey) -> usize {
+        let channel = &mut self.channel_pointer.channel;
+        if channel.data1.items.is_empty() {
+            return 0;
+        }
+
+        channel.verify_checksum(channel_key);
+        let UndirectedChannel {
+            data1: outbox,
+            data2: inbox,
+            ..
+        } = &mut **channel;
+        let free = inbox.capacity - inbox.items.len();
+        let overflow = outbox.items.len().saturating_sub(free);
+        let fitting = outbox.items.len() - overflow;
+        match outbox.policy {
+            OverflowPolicy::DropNewest => outbox.items.truncate(fitting),
+            OverflowPolicy::DropOldest => {
+                inbox.items.drain(..overflow);
+            }
+        }
+        inbox.items.extend(outbox.items.drain(..));
+        outbox.dropped += overflow as u64;
+        channel.generation += 1;
+        channel.record_checksum(channel_key);
+        overflow
+    }
+
+    /// The total number of items dropped so far, both when pushing and when flushing.
+    pub fn dropped(&self, #[allow(unused)] channel_key: &ChannelKey) -> u64 {
+        self.channel_pointer.channel.data1.dropped
+    }
+
+    /// Shorthand for [BoundedQueueChannel::destroy].
+    pub fn destroy(
+        self,
+        sender: BoundedQueueSender<T>,
+        receiver: BoundedQueueReceiver<T>,
+    ) -> (VecDeque<T>, VecDeque<T>) {
+        BoundedQueueChannel::destroy(self, sender, receiver)
+    }
+}
+
+impl<T> BoundedQueueSender<T> {
+    /// Push the given item into the outbox, such that it is delivered by the next flush.
+    /// If the outbox is full, an item is dropped according to the [OverflowPolicy]:
+    /// with [OverflowPolicy::DropNewest], the given item is returned as error,
+    /// and with [OverflowPolicy::DropOldest], the oldest item in the outbox is dropped.
+    pub fn push(&mut self, data_key: &DataKey, item: T) -> Result<(), T> {
+        let outbox = self.data_pointer.get_mut(data_key);
+        if outbox.items.len() == outbox.capacity {
+            outbox.dropped += 1;
+            match outbox.policy {
+                OverflowPolicy::DropNewest => return Err(item),
+                OverflowPolicy::DropOldest => {
+                    outbox.items.pop_front();
+                }
+            }
+        }
+        outbox.items.push_back(item);
+        Ok(())
+    }
+
+    /// The number of pushed items that were not delivered yet.
+    pub fn len(&self, data_key: &DataKey) -> usize {
+        self.data_pointer.get(data_key).items.len()
+    }
+
+    /// Returns `true` if all pushed items were delivered.
+    pub fn is_empty(&self, data_key: &DataKey) -> bool {
+        self.data_pointer.get(data_key).items.is_empty()
+    }
+
+    /// The total number of items dropped so far, both when pushing and when flushing.
+    pub fn dropped(&self, data_key: &DataKey) -> u64 {
+        self.data_pointer.get(data_key).dropped
+    }
+}
+
+impl<T> BoundedQueueReceiver<T> {
+    /// Remove all delivered items from the inbox, in the order in which they were pushed.
+    pub fn drain<'a>(&'a mut self, data_key: &'a DataKey) -> vec_deque::Drain<'a, T> {
+        self.data_pointer.get_mut(data_key).items.drain(..)
+    }
+
+    /// The number of delivered items that were not drained yet.
+    pub fn len(&self, data_key: &DataKey) -> usize {
+        self.data_pointer.get(data_key).items.len()
+    }
+
+    /// Returns `true` if all delivered items were drained.
+    pub fn is_empty(&self, data_key: &DataKey) -> bool {
+        self.data_pointer.get(data_key).items.is_empty()
+    }
+}
+
+impl<T> SwapChannel for BoundedQueueChannelPointer<T> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        BoundedQueueChannelPointer::flush(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.channel_pointer.label()
+    }
+
+    fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel_pointer.channel.generation)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<T>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        queue::{BidirectedQueueChannel, BoundedQueueChannel, OverflowPolicy, QueueChannel},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut sender, mut receiver) = QueueChannel::create();
+
+        sender.push(&master_key.get_data_key(), 1);
+        channel_pointer.flush(&master_key.get_channel_key());
+        // The receiver does not drain, so the next flush appends.
+        sender.push(&master_key.get_data_key(), 2);
+        sender.push(&master_key.get_data_key(), 3);
+        channel_pointer.flush(&master_key.get_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(receiver.len(&data_key), 3);
+        assert!(receiver.drain(&data_key).eq([1, 2, 3]));
+        assert!(receiver.is_empty(&data_key));
+        sender.push(&data_key, 4);
+
+        assert_eq!(
+            channel_pointer.destroy(sender, receiver),
+            (vec![4], Vec::new())
+        );
+    }
+
+    #[test]
+    fn bidirected() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut worker, mut coordinator) =
+            BidirectedQueueChannel::<&str, u32>::create();
+
+        let data_key = master_key.get_data_key();
+        coordinator.push_out(&data_key, "start");
+        coordinator.push_out(&data_key, "stop");
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        let commands: Vec<_> = worker.drain_in(&data_key).collect();
+        assert_eq!(commands, ["start", "stop"]);
+        for ack in 0..commands.len() as u32 {
+            worker.push_out(&data_key, ack);
+        }
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert!(coordinator.drain_in(&data_key).eq([0, 1]));
+        assert_eq!(
+            channel_pointer.destroy(worker, coordinator),
+            ((Vec::new(), Vec::new()), (Vec::new(), Vec::new()))
+        );
+    }
+
+    #[test]
+    fn bounded_drop_newest() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut sender, mut receiver) =
+            BoundedQueueChannel::create(2, OverflowPolicy::DropNewest);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(sender.push(&data_key, 1), Ok(()));
+        assert_eq!(sender.push(&data_key, 2), Ok(()));
+        assert_eq!(sender.push(&data_key, 3), Err(3));
+        assert_eq!(sender.dropped(&data_key), 1);
+        assert_eq!(channel_pointer.flush(&data_key.into_channel_key()), 0);
+
+        // The receiver stalls, so the inbox has no room for the next items.
+        let data_key = master_key.get_data_key();
+        sender.push(&data_key, 4).unwrap();
+        sender.push(&data_key, 5).unwrap();
+        let channel_key = data_key.into_channel_key();
+        assert_eq!(channel_pointer.flush(&channel_key), 2);
+        assert_eq!(channel_pointer.dropped(&channel_key), 3);
+
+        let data_key = channel_key.into_data_key();
+        assert!(sender.is_empty(&data_key));
+        assert!(receiver.drain(&data_key).eq([1, 2]));
+        assert_eq!(
+            channel_pointer.destroy(sender, receiver),
+            (Default::default(), Default::default())
+        );
+    }
+
+    #[test]
+    fn bounded_drop_oldest() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut sender, receiver) =
+            BoundedQueueChannel::create(2, OverflowPolicy::DropOldest);
+
+        let data_key = master_key.get_data_key();
+        for item in 1..=3 {
+            assert_eq!(sender.push(&data_key, item), Ok(()));
+        }
+        assert_eq!(sender.len(&data_key), 2);
+        assert_eq!(channel_pointer.flush(&data_key.into_channel_key()), 0);
+
+        sender.push(&master_key.get_data_key(), 4).unwrap();
+        assert_eq!(channel_pointer.flush(&master_key.get_channel_key()), 1);
+        assert_eq!(sender.dropped(&master_key.get_data_key()), 2);
+        assert_eq!(receiver.len(&master_key.get_data_key()), 2);
+
+        sender.push(&master_key.get_data_key(), 5).unwrap();
+        let (outbox, inbox) = channel_pointer.destroy(sender, receiver);
+        assert_eq!((outbox, inbox), ([5].into(), [3, 4].into()));
+    }
+}
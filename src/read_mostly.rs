@@ -0,0 +1,362 @@
+//! A read-mostly channel in the style of left-right, for readers that do not participate in the phase protocol,
+//! such as tasks of a thread pool.
+//!
+//! The channel holds two copies of `Data`: the published copy, which readers read at any time via [ReaderHandle::read],
+//! and the offline copy, which the writer changes during the channel phase and then publishes.
+//! Publishing switches an atomic index, such that new reads see the new copy,
+//! and waits until all reads of the previously published copy have finished, before bringing it up to date.
+//!
+//! This relaxes the rule that all `Data` is accessed in the data phase for the reader side only.
+//! The write and publish path still requires a [ChannelKey].
+//! Each reader handle maintains an epoch counter, which is odd while it reads,
+//! such that the writer knows when the previously published copy is quiescent.
+
+use core::cell::{Cell, UnsafeCell};
+use std::{
+    any,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::{CachePadded, ChannelKey, SwapChannel};
+
+/// A read-mostly channel used for publishing `Data` to readers that read it at any time.
+///
+/// See [ReadMostly::create] for more info.
+#[derive(Debug)]
+pub struct ReadMostly<Data> {
+    copies: [CachePadded<UnsafeCell<Data>>; 2],
+    /// The index of the published copy.
+    published: AtomicUsize,
+    /// The number of publishes.
+    generation: AtomicU64,
+    /// The epoch counters of all reader handles.
+    epochs: Mutex<Vec<Arc<CachePadded<AtomicU64>>>>,
+}
+
+/// The writer of a read-mostly channel, which changes the offline copy and publishes it.
+/// It can only be accessed using a [ChannelKey].
+#[derive(Debug)]
+pub struct ReadMostlyWriter<Data> {
+    channel: Arc<ReadMostly<Data>>,
+}
+
+/// A handle used to read the published copy of a read-mostly channel at any time, without a key.
+/// Clone it to hand out further handles, e.g. one per task of a thread pool.
+///
+/// A handle can be sent to another thread, but not shared between threads, since the writer tracks the reads per handle.
+#[derive(Debug)]
+pub struct ReaderHandle<Data> {
+    channel: Arc<ReadMostly<Data>>,
+    epoch: Arc<CachePadded<AtomicU64>>,
+    /// The number of nested reads of this handle.
+    depth: Cell<usize>,
+    /// The index of the copy read by the outermost read of this handle.
+    reading: Cell<usize>,
+}
+
+impl<Data: Clone> ReadMostly<Data> {
+    /// Create a read-mostly channel whose copies are initialised equally from the given `Data`, and hand out its writer and a first reader handle.
+    pub fn create(data: Data) -> (ReadMostlyWriter<Data>, ReaderHandle<Data>) {
+        let channel = Arc::new(ReadMostly {
+            copies: [
+                CachePadded(UnsafeCell::new(data.clone())),
+                CachePadded(UnsafeCell::new(data)),
+            ],
+            published: AtomicUsize::new(0),
+            generation: AtomicU64::new(0),
+            epochs: Mutex::new(Vec::new()),
+        });
+        let reader = ReaderHandle::new(channel.clone());
+        (ReadMostlyWriter { channel }, reader)
+    }
+}
+
+impl<Data> ReadMostly<Data> {
+    /// Destroys the read-mostly channel linked with the writer and the given reader handles (see [ReadMostly::create]).
+    /// Returns the published and the offline copy, in this order.
+    ///
+    /// **Panics** if not all reader handles of the channel are given, or if any of them belongs to another channel.
+    pub fn destroy(
+        writer: ReadMostlyWriter<Data>,
+        readers: impl IntoIterator<Item = ReaderHandle<Data>>,
+    ) -> (Data, Data) {
+        let ReadMostlyWriter { channel } = writer;
+        for reader in readers {
+            assert!(
+                Arc::ptr_eq(&reader.channel, &channel),
+                "reader handle does not belong to this read-mostly channel"
+            );
+        }
+        let channel = match Arc::try_unwrap(channel) {
+            Ok(channel) => channel,
+            Err(_) => panic!("not all reader handles of the read-mostly channel were given"),
+        };
+        let published = channel.published.into_inner();
+        let [copy0, copy1] = channel.copies;
+        let (copy0, copy1) = (copy0.0.into_inner(), copy1.0.into_inner());
+        if published == 0 {
+            (copy0, copy1)
+        } else {
+            (copy1, copy0)
+        }
+    }
+
+    fn offline(&self) -> *mut Data {
+        self.copies[1 - self.published.load(Ordering::Relaxed)].get()
+    }
+}
+
+impl<Data: Clone> ReadMostlyWriter<Data> {
+    /// Publish the offline copy, such that all reads that start afterwards see it.
+    ///
+    /// This blocks until all reads of the previously published copy have finished,
+    /// and then clones the new published copy into it with [Clone::clone_from], such that it becomes the new offline copy.
+    /// A reader that never finishes its read hence stalls the channel phase.
+    pub fn publish(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        let channel: &ReadMostly<Data> = &self.channel;
+        let previous = channel.published.load(Ordering::Relaxed);
+        channel.published.store(1 - previous, Ordering::SeqCst);
+        channel.generation.fetch_add(1, Ordering::Relaxed);
+
+        // Readers that are not reading or started a new read since the switch do not read the previous copy.
+        // The lock is released before waiting, since a reading thread may create or drop a reader handle.
+        let epochs = channel.epochs.lock().unwrap().clone();
+        for epoch in &epochs {
+            let observed = epoch.load(Ordering::SeqCst);
+            if observed % 2 == 1 {
+                while epoch.load(Ordering::Acquire) == observed {
+                    thread::yield_now();
+                }
+            }
+        }
+
+        let (published, offline) = unsafe {
+            (
+                &*channel.copies[1 - previous].get(),
+                &mut *channel.copies[previous].get(),
+            )
+        };
+        offline.clone_from(published);
+    }
+}
+
+impl<Data> ReadMostlyWriter<Data> {
+    /// Call the given function with a mutable reference to the offline copy, and return its result.
+    /// The changes become visible to readers with the next [ReadMostlyWriter::publish].
+    pub fn write<R>(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        f: impl FnOnce(&mut Data) -> R,
+    ) -> R {
+        f(unsafe { &mut *self.channel.offline() })
+    }
+
+    /// Get a reference to the offline copy, including the changes that were not yet published.
+    pub fn get(&self, #[allow(unused)] channel_key: &ChannelKey) -> &Data {
+        unsafe { &*self.channel.offline() }
+    }
+
+    /// Create a new reader handle to this channel.
+    pub fn reader(&self) -> ReaderHandle<Data> {
+        ReaderHandle::new(self.channel.clone())
+    }
+
+    /// The number of publishes of this channel.
+    pub fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> u64 {
+        self.channel.generation.load(Ordering::Relaxed)
+    }
+
+    /// Shorthand for [ReadMostly::destroy].
+    pub fn destroy(self, readers: impl IntoIterator<Item = ReaderHandle<Data>>) -> (Data, Data) {
+        ReadMostly::destroy(self, readers)
+    }
+}
+
+impl<Data: Clone + Send + Sync> SwapChannel for ReadMostlyWriter<Data> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        self.publish(channel_key);
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        Some(ReadMostlyWriter::generation(self, channel_key))
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<Data>())
+    }
+}
+
+impl<Data> ReaderHandle<Data> {
+    fn new(channel: Arc<ReadMostly<Data>>) -> Self {
+        let epoch = Arc::new(CachePadded(AtomicU64::new(0)));
+        channel.epochs.lock().unwrap().push(epoch.clone());
+        Self {
+            channel,
+            epoch,
+            depth: Cell::new(0),
+            reading: Cell::new(0),
+        }
+    }
+
+    /// Call the given function with a reference to the published copy, and return its result.
+    /// This never blocks, but a long read delays the next publish.
+    ///
+    /// Nested reads of the same handle see the same copy, also if the writer publishes in the meantime.
+    pub fn read<R>(&self, f: impl FnOnce(&Data) -> R) -> R {
+        let depth = self.depth.get();
+        if depth == 0 {
+            self.epoch.fetch_add(1, Ordering::SeqCst);
+            // The writer does not change this copy until the outermost read ends.
+            self.reading
+                .set(self.channel.published.load(Ordering::SeqCst));
+        }
+        self.depth.set(depth + 1);
+        let _guard = ReadGuard(self);
+        f(unsafe { &*self.channel.copies[self.reading.get()].get() })
+    }
+
+    /// The number of publishes of the channel, as seen by this handle.
+    pub fn generation(&self) -> u64 {
+        self.channel.generation.load(Ordering::Relaxed)
+    }
+}
+
+/// Ends a read of a [ReaderHandle] when dropped, also if the read panics.
+struct ReadGuard<'a, Data>(&'a ReaderHandle<Data>);
+
+impl<Data> Drop for ReadGuard<'_, Data> {
+    fn drop(&mut self) {
+        let depth = self.0.depth.get() - 1;
+        self.0.depth.set(depth);
+        if depth == 0 {
+            self.0.epoch.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+impl<Data> Clone for ReaderHandle<Data> {
+    fn clone(&self) -> Self {
+        Self::new(self.channel.clone())
+    }
+}
+
+impl<Data> Drop for ReaderHandle<Data> {
+    fn drop(&mut self) {
+        self.channel
+            .epochs
+            .lock()
+            .unwrap()
+            .retain(|epoch| !Arc::ptr_eq(epoch, &self.epoch));
+    }
+}
+
+unsafe impl<Data: Send + Sync> Send for ReadMostly<Data> {}
+unsafe impl<Data: Send + Sync> Sync for ReadMostly<Data> {}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+    };
+
+    use crate::{read_mostly::ReadMostly, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut writer, reader) = ReadMostly::create(vec![0]);
+        let channel_key = master_key.get_channel_key();
+
+        writer.write(&channel_key, |data| data.push(1));
+        assert_eq!(reader.read(|data| data.clone()), [0]);
+        assert_eq!(*writer.get(&channel_key), [0, 1]);
+        writer.publish(&channel_key);
+        assert_eq!(reader.read(|data| data.clone()), [0, 1]);
+        // The previously published copy was brought up to date.
+        assert_eq!(*writer.get(&channel_key), [0, 1]);
+
+        let other_reader = reader.clone();
+        reader.read(|outer| other_reader.read(|inner| assert_eq!(outer, inner)));
+        assert_eq!(
+            (reader.generation(), writer.generation(&channel_key)),
+            (1, 1)
+        );
+        assert_eq!(
+            writer.destroy([reader, other_reader]),
+            (vec![0, 1], vec![0, 1])
+        );
+    }
+
+    #[test]
+    fn readers_on_other_threads() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut writer, reader) = ReadMostly::create((0u64, 0u64));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let reader = reader.clone();
+                let stop = stop.clone();
+                thread::spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        // Both fields are always written together, so a torn read would differ.
+                        reader.read(|&(a, b)| assert_eq!(a, b));
+                    }
+                    reader
+                })
+            })
+            .collect();
+        for round in 1..=1000 {
+            let channel_key = master_key.get_channel_key();
+            writer.write(&channel_key, |data| *data = (round, round));
+            writer.publish(&channel_key);
+        }
+        stop.store(true, Ordering::Relaxed);
+        let mut readers: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        readers.push(reader);
+        assert_eq!(writer.destroy(readers), ((1000, 1000), (1000, 1000)));
+    }
+
+    #[test]
+    fn publish_during_nested_reads() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut writer, reader) = ReadMostly::create(0);
+        let (reading_sender, reading) = mpsc::channel();
+
+        let reader_thread = thread::spawn(move || {
+            reader.read(|&outer| {
+                reading_sender.send(()).unwrap();
+                while reader.generation() == 0 {
+                    thread::yield_now();
+                }
+                // The writer waits for this read, which creates and drops a handle in the meantime.
+                drop(reader.clone());
+                reader.read(|&inner| assert_eq!(outer, inner));
+            });
+            reader
+        });
+        reading.recv().unwrap();
+        let channel_key = master_key.get_channel_key();
+        writer.write(&channel_key, |data| *data = 1);
+        writer.publish(&channel_key);
+        let reader = reader_thread.join().unwrap();
+        assert_eq!(reader.read(|&data| data), 1);
+        assert_eq!(writer.destroy([reader]), (1, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "not all reader handles of the read-mostly channel were given")]
+    fn destroy_without_all_readers_panics() {
+        let (writer, reader) = ReadMostly::create(0);
+        let _other = reader.clone();
+        writer.destroy([reader]);
+    }
+}
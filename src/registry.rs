@@ -0,0 +1,255 @@
+//! A registry of directed channels by name, e.g. for plugins that discover their channels at runtime.
+//! The coordinator registers each channel with its pointers, the plugins take the data pointers by name and type,
+//! and the channel pointers are handed to a [ChannelGroup] to be advanced.
+
+use core::fmt;
+use std::{
+    any::{self, Any, TypeId},
+    collections::BTreeMap,
+};
+
+use crate::{
+    directed::{DirectedChannelPointer, ReadOnlyDataPointer, WritableDataPointer},
+    erased::ErasedDestroy,
+    group::{ChannelGroup, GroupId},
+    ChannelKey,
+};
+
+/// A registry of directed channels by name.
+/// Each data pointer of a registered channel can be taken exactly once, and only with the `Data` type the channel was registered with.
+#[derive(Debug, Default)]
+pub struct ChannelRegistry {
+    entries: BTreeMap<String, Entry>,
+}
+
+/// The reason why a data pointer could not be taken from a [ChannelRegistry].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// No channel is registered with the given name.
+    NotFound,
+    /// The channel was registered with a different `Data` type.
+    WrongType {
+        /// The name of the `Data` type the channel was registered with.
+        registered: &'static str,
+        /// The name of the requested `Data` type.
+        requested: &'static str,
+    },
+    /// The data pointer was already taken.
+    AlreadyTaken,
+}
+
+struct Entry {
+    type_id: TypeId,
+    type_name: &'static str,
+    /// `None` once it was handed to a group.
+    channel_pointer: Option<Box<dyn ErasedDestroy>>,
+    /// The [ReadOnlyDataPointer], or `None` if it was taken.
+    read_only: Option<Box<dyn Any + Send>>,
+    /// The [WritableDataPointer], or `None` if it was taken.
+    writable: Option<Box<dyn Any + Send>>,
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("type_name", &self.type_name)
+            .field("in_group", &self.channel_pointer.is_none())
+            .field("read_only_taken", &self.read_only.is_none())
+            .field("writable_taken", &self.writable.is_none())
+            .finish()
+    }
+}
+
+impl ChannelRegistry {
+    /// Create an empty channel registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the directed channel linked with the given pointers under the given name.
+    ///
+    /// **Panics** if a channel with the same name is already registered.
+    pub fn register<Data: Clone + Send + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        channel_pointer: DirectedChannelPointer<Data>,
+        read_only_data_pointer: ReadOnlyDataPointer<Data>,
+        writable_data_pointer: WritableDataPointer<Data>,
+    ) {
+        let name = name.into();
+        assert!(
+            !self.entries.contains_key(&name),
+            "a channel named {:?} is already registered",
+            name
+        );
+        self.entries.insert(
+            name,
+            Entry {
+                type_id: TypeId::of::<Data>(),
+                type_name: any::type_name::<Data>(),
+                channel_pointer: Some(Box::new(channel_pointer)),
+                read_only: Some(Box::new(read_only_data_pointer)),
+                writable: Some(Box::new(writable_data_pointer)),
+            },
+        );
+    }
+
+    /// Take the read-only data pointer of the channel with the given name.
+    pub fn take_reader<Data: 'static>(
+        &mut self,
+        name: &str,
+    ) -> Result<ReadOnlyDataPointer<Data>, RegistryError> {
+        let pointer = self.take::<Data>(name, |entry| &mut entry.read_only)?;
+        Ok(*pointer.downcast().unwrap())
+    }
+
+    /// Take the writable data pointer of the channel with the given name.
+    pub fn take_writer<Data: 'static>(
+        &mut self,
+        name: &str,
+    ) -> Result<WritableDataPointer<Data>, RegistryError> {
+        let pointer = self.take::<Data>(name, |entry| &mut entry.writable)?;
+        Ok(*pointer.downcast().unwrap())
+    }
+
+    fn take<Data: 'static>(
+        &mut self,
+        name: &str,
+        pointer: impl FnOnce(&mut Entry) -> &mut Option<Box<dyn Any + Send>>,
+    ) -> Result<Box<dyn Any + Send>, RegistryError> {
+        let entry = self.entries.get_mut(name).ok_or(RegistryError::NotFound)?;
+        if entry.type_id != TypeId::of::<Data>() {
+            return Err(RegistryError::WrongType {
+                registered: entry.type_name,
+                requested: any::type_name::<Data>(),
+            });
+        }
+        pointer(entry).take().ok_or(RegistryError::AlreadyTaken)
+    }
+
+    /// Add the channel pointers of all registered channels that were not yet added to the given group,
+    /// labelled with their names and in the order of their names.
+    /// They are added as `Box<dyn ErasedDestroy>`, such that they can be destroyed via [ChannelGroup::destroy].
+    /// Returns the names and ids of the added channels.
+    pub fn add_to_group(
+        &mut self,
+        channel_key: &ChannelKey,
+        group: &mut ChannelGroup,
+    ) -> Vec<(String, GroupId)> {
+        self.entries
+            .iter_mut()
+            .filter_map(|(name, entry)| {
+                let channel_pointer = entry.channel_pointer.take()?;
+                Some((
+                    name.clone(),
+                    group.push(channel_key, name.clone(), channel_pointer),
+                ))
+            })
+            .collect()
+    }
+
+    /// Iterate over the names of the registered channels, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::NotFound => write!(f, "no channel with this name is registered"),
+            RegistryError::WrongType {
+                registered,
+                requested,
+            } => write!(
+                f,
+                "the channel carries {}, but {} was requested",
+                registered, requested
+            ),
+            RegistryError::AlreadyTaken => write!(f, "the data pointer was already taken"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        directed::DirectedChannel,
+        group::ChannelGroup,
+        registry::{ChannelRegistry, RegistryError},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut registry = ChannelRegistry::new();
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(vec![1.0f32], vec![]);
+        registry.register("physics.bodies", channel_pointer, read_only, writable);
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(0u32, 0);
+        registry.register("physics.ticks", channel_pointer, read_only, writable);
+        assert!(registry.names().eq(["physics.bodies", "physics.ticks"]));
+
+        let reader = registry.take_reader::<Vec<f32>>("physics.bodies").unwrap();
+        let mut writer = registry.take_writer::<Vec<f32>>("physics.bodies").unwrap();
+
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let ids = registry.add_to_group(&channel_key, &mut group);
+        assert_eq!(ids.len(), 2);
+        assert!(registry.add_to_group(&channel_key, &mut group).is_empty());
+
+        writer.get_mut(&master_key.get_data_key()).push(2.0);
+        group.advance_all(&master_key.get_channel_key());
+        assert_eq!(*reader.get(&master_key.get_data_key()), [2.0]);
+
+        let channel_key = master_key.get_channel_key();
+        let values = group
+            .destroy(&channel_key, ids[0].1, vec![reader.erase(), writer.erase()])
+            .unwrap();
+        assert_eq!(values[0].downcast_ref(), Some(&vec![2.0f32]));
+        let values = group
+            .destroy(
+                &channel_key,
+                ids[1].1,
+                vec![
+                    registry
+                        .take_reader::<u32>("physics.ticks")
+                        .unwrap()
+                        .erase(),
+                    registry
+                        .take_writer::<u32>("physics.ticks")
+                        .unwrap()
+                        .erase(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(values.len(), 2);
+    }
+
+    #[test]
+    fn errors() {
+        let mut registry = ChannelRegistry::new();
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(0u32, 0);
+        registry.register("ticks", channel_pointer, read_only, writable);
+
+        assert_eq!(
+            registry.take_reader::<u32>("bodies").err(),
+            Some(RegistryError::NotFound)
+        );
+        assert_eq!(
+            registry.take_writer::<u64>("ticks").err(),
+            Some(RegistryError::WrongType {
+                registered: "u32",
+                requested: "u64"
+            })
+        );
+        assert!(registry.take_writer::<u32>("ticks").is_ok());
+        assert_eq!(
+            registry.take_writer::<u32>("ticks").err(),
+            Some(RegistryError::AlreadyTaken)
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! Recording the values published through a [directed channel](crate::directed) in a live session,
+//! and replaying them later into a consumer without the producer, e.g. to debug the consumer in isolation.
+//!
+//! A [Recorder] owns the channel pointer of the recorded channel and flushes it once per channel phase.
+//! Each flush that publishes a new value writes a frame with the index of the phase, the generation of the channel and the value.
+//! A [Replayer] owns the channel pointer of a channel read by the consumer, and publishes the recorded values in their original order,
+//! either one per phase or in the recorded phases (see [Pacing]).
+//!
+//! Each frame is a little-endian `u64` length followed by the tuple `(phase, generation, value)` encoded with bincode.
+
+use std::io::{self, Read, Write};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{directed::DirectedChannelPointer, ChannelKey, SwapChannel};
+
+/// A value recorded by a [Recorder].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recorded<Data> {
+    /// The index of the channel phase in which the value was published, counted from the creation of the recorder.
+    pub phase: u64,
+    /// The generation of the channel after publishing the value.
+    pub generation: u64,
+    /// The published value.
+    pub value: Data,
+}
+
+/// When a [Replayer] publishes the recorded values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pacing {
+    /// Publish one value per channel phase.
+    OnePerPhase,
+    /// Publish each value in the channel phase with the same distance to the first replayed phase
+    /// as its recorded phase to the recorded phase of the first value, i.e. keep the gaps between the values.
+    RecordedPhases,
+}
+
+/// Flushes a directed channel and records each newly published value, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Recorder<Data, W> {
+    channel_pointer: DirectedChannelPointer<Data>,
+    writer: W,
+    phase: u64,
+}
+
+/// Publishes recorded values through a directed channel, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Replayer<Data, R> {
+    channel_pointer: DirectedChannelPointer<Data>,
+    reader: R,
+    pacing: Pacing,
+    /// The next recorded value, which was read but not yet published.
+    next: Option<Recorded<Data>>,
+    /// The recorded phase of the first value and the replayed phase it was published in.
+    start: Option<(u64, u64)>,
+    phase: u64,
+    finished: bool,
+}
+
+impl<Data: Clone + Serialize, W: Write> Recorder<Data, W> {
+    /// Record the values published through the given channel to the given writer.
+    pub fn new(channel_pointer: DirectedChannelPointer<Data>, writer: W) -> Self {
+        Self {
+            channel_pointer,
+            writer,
+            phase: 0,
+        }
+    }
+
+    /// Flush the channel like [DirectedChannelPointer::flush], and write a frame if this published a new value.
+    /// This must be called once per channel phase, since the recorded phases are counted by the calls.
+    pub fn flush(&mut self, channel_key: &ChannelKey) -> io::Result<()> {
+        let phase = self.phase;
+        self.phase += 1;
+        let generation = self.channel_pointer.stats(channel_key).generation;
+        self.channel_pointer.flush(channel_key);
+        let new_generation = self.channel_pointer.stats(channel_key).generation;
+        if new_generation == generation {
+            return Ok(());
+        }
+
+        let frame = self
+            .channel_pointer
+            .inspect(channel_key, |read_only, _| {
+                options().serialize(&(phase, new_generation, read_only))
+            })
+            .map_err(invalid_data)?;
+        self.writer.write_all(&(frame.len() as u64).to_le_bytes())?;
+        self.writer.write_all(&frame)
+    }
+
+    /// The recorded channel, e.g. to destroy it.
+    pub fn channel_pointer_mut(&mut self) -> &mut DirectedChannelPointer<Data> {
+        &mut self.channel_pointer
+    }
+
+    /// Return the channel pointer and the writer, e.g. to read the recording from a `Vec<u8>`.
+    pub fn into_inner(self) -> (DirectedChannelPointer<Data>, W) {
+        (self.channel_pointer, self.writer)
+    }
+}
+
+/// Advancing the recorder **panics** if writing the frame fails, use [Recorder::flush] to handle the error.
+impl<Data: Clone + Serialize + Send + Sync, W: Write + Send + Sync> SwapChannel
+    for Recorder<Data, W>
+{
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        self.flush(channel_key)
+            .expect("could not write the recorded value");
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.channel_pointer.label()
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        SwapChannel::generation(&self.channel_pointer, channel_key)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        self.channel_pointer.payload_type_name()
+    }
+
+    fn dirty(&self, channel_key: &ChannelKey) -> Option<bool> {
+        SwapChannel::dirty(&self.channel_pointer, channel_key)
+    }
+}
+
+impl<Data: DeserializeOwned, R: Read> Replayer<Data, R> {
+    /// Replay the values recorded in the given reader into the given channel, paced as given.
+    pub fn new(channel_pointer: DirectedChannelPointer<Data>, reader: R, pacing: Pacing) -> Self {
+        Self {
+            channel_pointer,
+            reader,
+            pacing,
+            next: None,
+            start: None,
+            phase: 0,
+            finished: false,
+        }
+    }
+
+    /// Publish the next recorded value, if it is due in this channel phase.
+    /// The value replaces the writable `Data` of the channel, which is then flushed by swapping.
+    /// Returns `true` if a value was published.
+    ///
+    /// This must be called once per channel phase, since the replayed phases are counted by the calls.
+    pub fn replay(&mut self, channel_key: &ChannelKey) -> io::Result<bool> {
+        let phase = self.phase;
+        self.phase += 1;
+        if self.next.is_none() && !self.finished {
+            self.next = read_frame(&mut self.reader)?;
+            self.finished = self.next.is_none();
+        }
+        let next = match &self.next {
+            Some(next) => next,
+            None => return Ok(false),
+        };
+        let (recorded_start, replayed_start) = *self.start.get_or_insert((next.phase, phase));
+        if self.pacing == Pacing::RecordedPhases
+            && next.phase - recorded_start > phase - replayed_start
+        {
+            return Ok(false);
+        }
+
+        let next = self.next.take().unwrap();
+        self.channel_pointer
+            .replace_writable(channel_key, next.value);
+        self.channel_pointer.flush_swap(channel_key);
+        Ok(true)
+    }
+
+    /// Returns `true` if the end of the recording was reached, i.e. all recorded values were published.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The channel the values are replayed into, e.g. to destroy it.
+    pub fn channel_pointer_mut(&mut self) -> &mut DirectedChannelPointer<Data> {
+        &mut self.channel_pointer
+    }
+
+    /// Return the channel pointer.
+    pub fn into_channel_pointer(self) -> DirectedChannelPointer<Data> {
+        self.channel_pointer
+    }
+}
+
+/// Advancing the replayer **panics** if reading the recording fails, use [Replayer::replay] to handle the error.
+impl<Data: DeserializeOwned + Send + Sync, R: Read + Send + Sync> SwapChannel
+    for Replayer<Data, R>
+{
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        self.replay(channel_key)
+            .expect("could not read the recorded value");
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.channel_pointer.label()
+    }
+
+    fn generation(&self, channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel_pointer.stats(channel_key).generation)
+    }
+}
+
+/// Read all values of a recording, e.g. to inspect it.
+pub fn read_recording<Data: DeserializeOwned>(
+    mut reader: impl Read,
+) -> io::Result<Vec<Recorded<Data>>> {
+    let mut recording = Vec::new();
+    while let Some(recorded) = read_frame(&mut reader)? {
+        recording.push(recorded);
+    }
+    Ok(recording)
+}
+
+/// Read the next frame, or `None` if the reader ends before it.
+fn read_frame<Data: DeserializeOwned>(
+    reader: &mut impl Read,
+) -> io::Result<Option<Recorded<Data>>> {
+    let mut length = [0; 8];
+    let mut filled = 0;
+    while filled < length.len() {
+        match reader.read(&mut length[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    let length = u64::from_le_bytes(length);
+    let mut frame = Vec::new();
+    // Read incrementally, such that a corrupt length does not cause a huge allocation.
+    reader.take(length).read_to_end(&mut frame)?;
+    if frame.len() as u64 != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let (phase, generation, value) = options().deserialize(&frame).map_err(invalid_data)?;
+    Ok(Some(Recorded {
+        phase,
+        generation,
+        value,
+    }))
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+fn invalid_data(error: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        directed::{DirectedChannel, ReadOnlyDataPointer},
+        replay::{read_recording, Pacing, Recorded, Recorder, Replayer},
+        ChannelKey, DataKey, MasterKey,
+    };
+
+    /// The values written by the scripted producer in each phase, if any.
+    const SCRIPT: [Option<u32>; 8] = [Some(1), None, None, Some(2), Some(3), None, None, Some(4)];
+
+    /// Run the given number of phases, and return the value the consumer observed at the start of each data phase.
+    fn observe(
+        master_key: &mut MasterKey,
+        read_only: &ReadOnlyDataPointer<u32>,
+        phases: usize,
+        mut data_phase: impl FnMut(&DataKey, usize),
+        mut channel_phase: impl FnMut(&ChannelKey),
+    ) -> Vec<u32> {
+        (0..phases)
+            .map(|phase| {
+                let data_key = master_key.get_data_key();
+                let observed = *read_only.get(&data_key);
+                data_phase(&data_key, phase);
+                channel_phase(&data_key.into_channel_key());
+                observed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let mut recorder = Recorder::new(channel_pointer, Vec::new());
+        let live = observe(
+            &mut master_key,
+            &read_only,
+            SCRIPT.len() + 1,
+            |data_key, phase| {
+                if let Some(value) = SCRIPT.get(phase).copied().flatten() {
+                    *writable.get_mut(data_key) = value;
+                }
+            },
+            |channel_key| recorder.flush(channel_key).unwrap(),
+        );
+        assert_eq!(live, [0, 1, 1, 1, 2, 3, 3, 3, 4]);
+        let (channel_pointer, recording) = recorder.into_inner();
+        channel_pointer.destroy_single(read_only, writable);
+        assert_eq!(
+            read_recording::<u32>(&recording[..]).unwrap(),
+            [(0, 1, 1), (3, 2, 2), (4, 3, 3), (7, 4, 4)].map(|(phase, generation, value)| {
+                Recorded {
+                    phase,
+                    generation,
+                    value,
+                }
+            })
+        );
+
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+        let mut replayer = Replayer::new(channel_pointer, &recording[..], Pacing::RecordedPhases);
+        let replayed = observe(
+            &mut master_key,
+            &read_only,
+            live.len(),
+            |_, _| {},
+            |channel_key| {
+                replayer.replay(channel_key).unwrap();
+            },
+        );
+        assert!(replayer.is_finished());
+        assert_eq!(replayed, live);
+        replayer
+            .into_channel_pointer()
+            .destroy_single(read_only, writable);
+
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+        let mut replayer = Replayer::new(channel_pointer, &recording[..], Pacing::OnePerPhase);
+        let replayed = observe(
+            &mut master_key,
+            &read_only,
+            5,
+            |_, _| {},
+            |channel_key| {
+                replayer.replay(channel_key).unwrap();
+            },
+        );
+        assert_eq!(replayed, [0, 1, 2, 3, 4]);
+        replayer
+            .into_channel_pointer()
+            .destroy_single(read_only, writable);
+    }
+
+    #[test]
+    fn truncated_recording() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(1u64, 1);
+        let mut recorder = Recorder::new(channel_pointer, Vec::new());
+        recorder.flush(&master_key.get_channel_key()).unwrap();
+        let (channel_pointer, mut recording) = recorder.into_inner();
+        recording.pop();
+        assert_eq!(
+            read_recording::<u64>(&recording[..]).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        channel_pointer.destroy_single(read_only, writable);
+    }
+}
@@ -0,0 +1,152 @@
+//! Lifetime statistics of a channel, returned when destroying it via `destroy_with_report`,
+//! e.g. to log a line per channel at shutdown and audit whether the channels were used as intended.
+
+use core::fmt;
+use std::time::{Duration, Instant};
+
+use crate::Label;
+
+/// The lifetime statistics of a destroyed channel, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChannelReport {
+    /// The label of the channel, if it was set via `with_label`.
+    pub label: Option<String>,
+    /// The number of swaps of an undirected channel, or the number of flushes of a directed channel.
+    /// For a bidirected channel, the flushes of both directions are summed up.
+    pub swaps_or_flushes: u64,
+    /// The number of conditional swaps that were skipped because their condition did not hold,
+    /// or the number of flushes that were skipped because the channel was clean.
+    pub skipped: u64,
+    /// The time the channel was created, or `None` on targets without a clock, i.e. `wasm32-unknown-unknown`.
+    pub created_at: Option<Instant>,
+    /// The time the channel was destroyed, or `None` on targets without a clock.
+    pub destroyed_at: Option<Instant>,
+    /// The generation of the channel when it was destroyed, i.e. the number of operations that changed its `Data`.
+    /// For a bidirected channel, the generations of both directions are summed up.
+    pub last_generation: u64,
+}
+
+impl ChannelReport {
+    pub(crate) fn new(
+        label: &Label,
+        swaps_or_flushes: u64,
+        skipped: u64,
+        created_at: Option<Instant>,
+        last_generation: u64,
+    ) -> Self {
+        Self {
+            label: label.get().map(str::to_owned),
+            swaps_or_flushes,
+            skipped,
+            created_at,
+            destroyed_at: now(),
+            last_generation,
+        }
+    }
+
+    /// The time between the creation and the destruction of the channel, or `None` on targets without a clock.
+    pub fn lifetime(&self) -> Option<Duration> {
+        Some(
+            self.destroyed_at?
+                .saturating_duration_since(self.created_at?),
+        )
+    }
+}
+
+impl fmt::Display for ChannelReport {
+    /// Formats the report as a single line, e.g. `channel "input": 3 swaps or flushes, 1 skipped, generation 3, alive for 1.2ms`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "channel {:?}", label)?,
+            None => f.write_str("unlabelled channel")?,
+        }
+        write!(
+            f,
+            ": {} swaps or flushes, {} skipped, generation {}",
+            self.swaps_or_flushes, self.skipped, self.last_generation
+        )?;
+        if let Some(lifetime) = self.lifetime() {
+            write!(f, ", alive for {:?}", lifetime)?;
+        }
+        Ok(())
+    }
+}
+
+/// The current time, or `None` on targets without a clock, where [Instant::now] panics.
+pub(crate) fn now() -> Option<Instant> {
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        None
+    } else {
+        Some(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bidirected::BidirectedChannel, directed::DirectedChannel, undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 1);
+        let mut channel_pointer = channel_pointer.with_label("undirected");
+        channel_pointer.swap(&master_key.get_channel_key());
+        channel_pointer.swap_if(&master_key.get_channel_key(), |_, _| false);
+        let (data1, data2, report) =
+            channel_pointer.destroy_with_report(data_pointer1, data_pointer2);
+        assert_eq!((data1, data2), (1, 0));
+        assert_eq!(report.label.as_deref(), Some("undirected"));
+        assert_eq!(
+            (
+                report.swaps_or_flushes,
+                report.skipped,
+                report.last_generation
+            ),
+            (1, 1, 1)
+        );
+        assert!(report.created_at <= report.destroyed_at);
+        let line = report.to_string();
+        assert!(
+            line.starts_with(
+                "channel \"undirected\": 1 swaps or flushes, 1 skipped, generation 1, alive for "
+            ),
+            "{}",
+            line
+        );
+
+        let (mut channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+        for value in [1, 1] {
+            *writable.get_mut(&master_key.get_data_key()) = value;
+            channel_pointer.flush(&master_key.get_channel_key());
+        }
+        channel_pointer.flush(&master_key.get_channel_key());
+        let (_, _, report) = channel_pointer.destroy_with_report([read_only], writable);
+        assert_eq!(report.label, None);
+        assert_eq!(
+            (
+                report.swaps_or_flushes,
+                report.skipped,
+                report.last_generation
+            ),
+            (2, 1, 2)
+        );
+
+        let (mut channel_pointer, data_pointer1, mut data_pointer2) =
+            BidirectedChannel::create(0, 0, 0, 0);
+        *data_pointer2.get_output(&master_key.get_data_key()) = 1;
+        channel_pointer.flush(&master_key.get_channel_key());
+        let (_, _, _, _, report) =
+            channel_pointer.destroy_with_report(data_pointer1, data_pointer2);
+        assert_eq!(
+            (
+                report.swaps_or_flushes,
+                report.skipped,
+                report.last_generation
+            ),
+            (2, 0, 2)
+        );
+    }
+}
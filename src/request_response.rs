@@ -0,0 +1,207 @@
+//! A request/response protocol on top of the [bidirected channel](crate::bidirected).
+//! Each request is stamped with an id, and each response carries the id of the request it answers,
+//! such that the requester can distinguish the response to its latest request from stale responses to older requests.
+
+use core::marker::PhantomData;
+
+use crate::{
+    bidirected::{BidirectedChannel, BidirectedChannelPointer, BidirectedDataPointer},
+    ChannelKey, DataKey,
+};
+
+/// The id of a request sent through a [RequestResponseChannel].
+/// Ids are increasing in the order in which the requests are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RequestId(u64);
+
+/// The content of one direction of the underlying bidirected channel: the latest request or response, stamped with its request id.
+type Stamped<T> = Option<(RequestId, T)>;
+
+/// A request/response channel used for communication between a requesting and a responding thread.
+///
+/// See [RequestResponseChannel::create] for more info.
+#[derive(Debug)]
+pub struct RequestResponseChannel<Req, Resp> {
+    phantom: PhantomData<(Req, Resp)>,
+}
+
+/// A pointer to a request/response channel, used to transmit requests and responses.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [RequestResponseChannel::destroy] or [RequestResponseChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct RequestResponseChannelPointer<Req, Resp> {
+    channel_pointer: BidirectedChannelPointer<Stamped<Resp>, Stamped<Req>>,
+}
+
+/// The endpoint of a request/response channel that sends requests and receives responses.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [RequestResponseChannel::destroy] or [RequestResponseChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct Requester<Req, Resp> {
+    data_pointer: BidirectedDataPointer<Stamped<Resp>, Stamped<Req>>,
+    next_id: u64,
+    taken: Option<RequestId>,
+}
+
+/// The endpoint of a request/response channel that receives requests and sends responses.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [RequestResponseChannel::destroy] or [RequestResponseChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct Responder<Req, Resp> {
+    data_pointer: BidirectedDataPointer<Stamped<Req>, Stamped<Resp>>,
+    responded: Option<RequestId>,
+}
+
+impl<Req, Resp> RequestResponseChannel<Req, Resp> {
+    /// Create a request/response channel and hand out three pointers to it.
+    /// One [RequestResponseChannelPointer] used to transmit requests and responses,
+    /// one [Requester] used to send requests and receive responses, and
+    /// one [Responder] used to receive requests and send responses.
+    ///
+    /// A request sent in one data phase can be received by the responder after the next flush,
+    /// and the response can be received by the requester after the flush following the response.
+    pub fn create() -> (
+        RequestResponseChannelPointer<Req, Resp>,
+        Requester<Req, Resp>,
+        Responder<Req, Resp>,
+    ) {
+        let (channel_pointer, requester, responder) =
+            BidirectedChannel::create(None, None, None, None);
+        (
+            RequestResponseChannelPointer { channel_pointer },
+            Requester {
+                data_pointer: requester,
+                next_id: 0,
+                taken: None,
+            },
+            Responder {
+                data_pointer: responder,
+                responded: None,
+            },
+        )
+    }
+
+    /// Destroys the request/response channel linked with the three pointers (see [RequestResponseChannel::create]).
+    /// Requests and responses that were not received are dropped.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: RequestResponseChannelPointer<Req, Resp>,
+        requester: Requester<Req, Resp>,
+        responder: Responder<Req, Resp>,
+    ) {
+        channel_pointer
+            .channel_pointer
+            .destroy(requester.data_pointer, responder.data_pointer);
+    }
+}
+
+impl<Req: Clone, Resp: Clone> RequestResponseChannelPointer<Req, Resp> {
+    /// Transmit the latest request to the responder and the latest response to the requester.
+    pub fn flush(&mut self, channel_key: &ChannelKey) {
+        self.channel_pointer.flush(channel_key);
+    }
+}
+
+impl<Req, Resp> RequestResponseChannelPointer<Req, Resp> {
+    /// Shorthand for [RequestResponseChannel::destroy].
+    pub fn destroy(self, requester: Requester<Req, Resp>, responder: Responder<Req, Resp>) {
+        RequestResponseChannel::destroy(self, requester, responder)
+    }
+}
+
+impl<Req, Resp: Clone> Requester<Req, Resp> {
+    /// Send the given request, replacing any request sent in the same data phase.
+    /// Returns the id of the request, which is used to receive the response.
+    pub fn send_request(&mut self, data_key: &DataKey, request: Req) -> RequestId {
+        let id = RequestId(self.next_id);
+        self.next_id += 1;
+        *self.data_pointer.get_output(data_key) = Some((id, request));
+        id
+    }
+
+    /// Take the response to the request with the given id, if it was received.
+    /// Returns `None` if no response was received yet, if the received response answers a different request,
+    /// or if the response was already taken.
+    pub fn try_take_response(&mut self, data_key: &DataKey, id: RequestId) -> Option<Resp> {
+        match self.data_pointer.get_input(data_key) {
+            Some((response_id, response))
+                if *response_id == id && self.taken.map_or(true, |taken| taken < id) =>
+            {
+                self.taken = Some(id);
+                Some(response.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<Req, Resp> Responder<Req, Resp> {
+    /// The latest received request, if it was not yet responded to.
+    pub fn pending_request(&self, data_key: &DataKey) -> Option<(RequestId, &Req)> {
+        match self.data_pointer.get_input(data_key) {
+            Some((id, request)) if self.responded.map_or(true, |responded| responded < *id) => {
+                Some((*id, request))
+            }
+            _ => None,
+        }
+    }
+
+    /// Send the given response to the request with the given id.
+    ///
+    /// **Panics** if the request with the given id is not pending (see [Responder::pending_request]).
+    pub fn respond(&mut self, data_key: &DataKey, id: RequestId, response: Resp) {
+        assert_eq!(
+            self.pending_request(data_key)
+                .map(|(pending_id, _)| pending_id),
+            Some(id),
+            "the request is not pending"
+        );
+        self.responded = Some(id);
+        *self.data_pointer.get_output(data_key) = Some((id, response));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{request_response::RequestResponseChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut requester, mut responder) =
+            RequestResponseChannel::<u32, u32>::create();
+
+        let first = requester.send_request(&master_key.get_data_key(), 1);
+        channel_pointer.flush(&master_key.get_channel_key());
+
+        // The requester gives up on the first request, while the responder responds to it.
+        let data_key = master_key.get_data_key();
+        let second = requester.send_request(&data_key, 2);
+        let (id, request) = responder.pending_request(&data_key).unwrap();
+        assert_eq!((id, *request), (first, 1));
+        responder.respond(&data_key, id, request * 10);
+        assert!(responder.pending_request(&data_key).is_none());
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(requester.try_take_response(&data_key, second), None);
+        let (id, request) = responder.pending_request(&data_key).unwrap();
+        assert_eq!((id, *request), (second, 2));
+        responder.respond(&data_key, id, request * 10);
+        channel_pointer.flush(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(requester.try_take_response(&data_key, second), Some(20));
+        assert_eq!(requester.try_take_response(&data_key, second), None);
+        assert!(responder.pending_request(&data_key).is_none());
+
+        channel_pointer.destroy(requester, responder);
+    }
+}
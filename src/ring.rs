@@ -0,0 +1,219 @@
+//! A ring topology of [directed channels](crate::directed).
+//! Each endpoint reads from its predecessor and writes to its successor,
+//! with the last endpoint writing to the first.
+
+use core::marker::PhantomData;
+
+use crate::{
+    directed::{DirectedChannel, DirectedChannelPointer, ReadOnlyDataPointer, WritableDataPointer},
+    ChannelKey, DataKey,
+};
+
+/// A ring of directed channels used for communication between the stages of a cyclic pipeline, each running in its own thread.
+/// Channel `i` transmits `Data` from endpoint `i - 1` to endpoint `i`, wrapping around at the ends.
+///
+/// See [RingChannel::create] for more info.
+#[derive(Debug)]
+pub struct RingChannel<Data> {
+    phantom: PhantomData<Data>,
+}
+
+/// A pointer to a ring of directed channels, used to flush all of them at once.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [RingChannel::destroy] or [RingPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct RingPointer<Data> {
+    channel_pointers: Vec<DirectedChannelPointer<Data>>,
+}
+
+/// The endpoint of one stage in a ring of directed channels.
+/// It reads from the channel of its own index, and writes to the channel of the next index.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [RingChannel::destroy] or [RingPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct RingEndpoint<Data> {
+    index: usize,
+    input: ReadOnlyDataPointer<Data>,
+    output: WritableDataPointer<Data>,
+}
+
+impl<Data> RingChannel<Data> {
+    /// Create a ring of `n` directed channels and hand out pointers to it.
+    /// One [RingPointer] used to flush all channels,
+    /// and one [RingEndpoint] per stage, in stage order.
+    ///
+    /// Both `Data` fields of channel `i`, which is read by endpoint `i`, are initialised by calling `init(i)`.
+    pub fn create(
+        n: usize,
+        init: impl Fn(usize) -> Data,
+    ) -> (RingPointer<Data>, Vec<RingEndpoint<Data>>) {
+        let mut channel_pointers = Vec::with_capacity(n);
+        let mut inputs = Vec::with_capacity(n);
+        let mut outputs = Vec::with_capacity(n);
+        for index in 0..n {
+            let (channel_pointer, read_only_data_pointer, writable_data_pointer) =
+                DirectedChannel::create(init(index), init(index));
+            channel_pointers.push(channel_pointer);
+            inputs.push(read_only_data_pointer);
+            outputs.push(writable_data_pointer);
+        }
+        // Endpoint `i` writes to channel `i + 1`.
+        outputs.rotate_left(n.min(1));
+
+        (
+            RingPointer { channel_pointers },
+            inputs
+                .into_iter()
+                .zip(outputs)
+                .enumerate()
+                .map(|(index, (input, output))| RingEndpoint {
+                    index,
+                    input,
+                    output,
+                })
+                .collect(),
+        )
+    }
+
+    /// Destroys the ring linked with the given pointers (see [RingChannel::create]).
+    /// Returns the read-only and writable `Data` fields of each channel, in channel order.
+    ///
+    /// **Panics** if not all pointers point to the same ring, or if not every endpoint is given exactly once.
+    pub fn destroy(
+        ring_pointer: RingPointer<Data>,
+        endpoints: impl IntoIterator<Item = RingEndpoint<Data>>,
+    ) -> Vec<(Data, Data)> {
+        let n = ring_pointer.channel_pointers.len();
+        let mut inputs: Vec<_> = (0..n).map(|_| None).collect();
+        let mut outputs: Vec<_> = (0..n).map(|_| None).collect();
+        for RingEndpoint {
+            index,
+            input,
+            output,
+        } in endpoints
+        {
+            let slot = inputs
+                .get_mut(index)
+                .expect("endpoint does not belong to this ring");
+            assert!(slot.is_none(), "multiple endpoints with the same index");
+            *slot = Some(input);
+            outputs[(index + 1) % n] = Some(output);
+        }
+
+        ring_pointer
+            .channel_pointers
+            .into_iter()
+            .zip(inputs.into_iter().zip(outputs))
+            .map(|(channel_pointer, (input, output))| {
+                channel_pointer.destroy_single(
+                    input.expect("missing endpoint"),
+                    output.expect("missing endpoint"),
+                )
+            })
+            .collect()
+    }
+}
+
+impl<Data: Clone> RingPointer<Data> {
+    /// Flush all channels of the ring (see [DirectedChannelPointer::flush]).
+    ///
+    /// Since no endpoint can access the ring while it is flushed, the order of the flushes does not matter:
+    /// after this call, each endpoint reads exactly what its predecessor wrote in the preceding data phase.
+    pub fn flush_all(&mut self, channel_key: &ChannelKey) {
+        for channel_pointer in &mut self.channel_pointers {
+            channel_pointer.flush(channel_key);
+        }
+    }
+}
+
+impl<Data> RingPointer<Data> {
+    /// The number of endpoints in the ring.
+    pub fn len(&self) -> usize {
+        self.channel_pointers.len()
+    }
+
+    /// Returns `true` if the ring has no endpoints.
+    pub fn is_empty(&self) -> bool {
+        self.channel_pointers.is_empty()
+    }
+
+    /// Shorthand for [RingChannel::destroy].
+    pub fn destroy(
+        self,
+        endpoints: impl IntoIterator<Item = RingEndpoint<Data>>,
+    ) -> Vec<(Data, Data)> {
+        RingChannel::destroy(self, endpoints)
+    }
+}
+
+impl<Data> RingEndpoint<Data> {
+    /// The index of this endpoint in the ring.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Get a reference to the input received from the predecessor of this endpoint.
+    pub fn input(&self, data_key: &DataKey) -> &Data {
+        self.input.get(data_key)
+    }
+
+    /// Get a mutable reference to the output sent to the successor of this endpoint.
+    pub fn output_mut(&mut self, data_key: &DataKey) -> &mut Data {
+        self.output.get_mut(data_key)
+    }
+
+    /// Call the given function with a reference to the input and a mutable reference to the output of this endpoint,
+    /// and return its result.
+    pub fn process<R>(&mut self, data_key: &DataKey, f: impl FnOnce(&Data, &mut Data) -> R) -> R {
+        f(self.input.get(data_key), self.output.get_mut(data_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ring::RingChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut ring_pointer, mut endpoints) = RingChannel::create(3, |index| index * 10);
+        assert_eq!(ring_pointer.len(), 3);
+
+        for _ in 0..2 {
+            let data_key = master_key.get_data_key();
+            for endpoint in &mut endpoints {
+                endpoint.process(&data_key, |input, output| *output = input + 1);
+            }
+            ring_pointer.flush_all(&data_key.into_channel_key());
+        }
+
+        // Each value travelled two steps along the ring.
+        let data_key = master_key.get_data_key();
+        let inputs: Vec<_> = endpoints.iter().map(|e| *e.input(&data_key)).collect();
+        assert_eq!(inputs, [12, 22, 2]);
+
+        endpoints.reverse();
+        assert_eq!(
+            ring_pointer.destroy(endpoints),
+            [(12, 12), (22, 22), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn single_endpoint() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut ring_pointer, mut endpoints) = RingChannel::create(1, |_| 0);
+
+        for _ in 0..3 {
+            let data_key = master_key.get_data_key();
+            endpoints[0].process(&data_key, |input, output| *output = input + 1);
+            ring_pointer.flush_all(&data_key.into_channel_key());
+        }
+
+        assert_eq!(ring_pointer.destroy(endpoints), [(3, 3)]);
+    }
+}
@@ -0,0 +1,237 @@
+//! A rotating two-phase channel.
+//! The channel holds any number of instances of the transmitted data, one per data pointer,
+//! and a rotation moves each instance on to the next data pointer.
+//! For two instances, this behaves like the [undirected channel](crate::undirected).
+
+use crate::{ChannelKey, DataKey};
+
+/// A rotating channel used for communication between threads.
+/// It holds `N` instances of `Data`, which can be accessed or rotated.
+/// At any time, either references to `Data` can exist, or a rotation can be performed.
+///
+/// See [RotatingChannel::create] for more info.
+#[derive(Debug)]
+pub struct RotatingChannel<Data> {
+    slots: Vec<Data>,
+}
+
+/// The direction in which a rotation moves the `Data` fields of a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// The `Data` field of slot `i` moves to slot `i + 1`, and the last slot wraps around to the first.
+    Forward,
+    /// The `Data` field of slot `i + 1` moves to slot `i`, and the first slot wraps around to the last.
+    Backward,
+}
+
+/// A pointer to a rotating channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [RotatingChannel::destroy] or [RotatingChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct RotatingChannelPointer<Data> {
+    channel: Box<RotatingChannel<Data>>,
+    direction: Rotation,
+}
+
+/// A pointer to one of the data fields in a rotating channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [RotatingChannel::destroy] or [RotatingChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct RotatingDataPointer<Data> {
+    data: *mut Data,
+}
+
+impl<Data> RotatingChannel<Data> {
+    /// Create a rotating channel and hand out pointers to it.
+    /// One [RotatingChannelPointer] used to rotate the content of the `Data` fields,
+    /// and one [RotatingDataPointer] for each given `Data`, in the order of the given iterator.
+    ///
+    /// The channel initially rotates in the [Rotation::Forward] direction.
+    pub fn create(
+        slots: impl IntoIterator<Item = Data>,
+    ) -> (RotatingChannelPointer<Data>, Vec<RotatingDataPointer<Data>>) {
+        let mut channel_pointer = RotatingChannelPointer {
+            channel: Box::new(RotatingChannel {
+                slots: slots.into_iter().collect(),
+            }),
+            direction: Rotation::Forward,
+        };
+        let data_pointers = channel_pointer
+            .channel
+            .slots
+            .iter_mut()
+            .map(|data| RotatingDataPointer {
+                data: data as *mut Data,
+            })
+            .collect();
+        (channel_pointer, data_pointers)
+    }
+
+    /// Destroys the rotating channel linked with the given pointers (see [RotatingChannel::create]).
+    /// The `Data` fields are returned in slot order, i.e. the `Data` at index `i` is the one that the `i`th data pointer handed out by [RotatingChannel::create] pointed to.
+    ///
+    /// **Panics** if not all pointers point to the same channel, or if not every slot is pointed to by exactly one of the given pointers.
+    pub fn destroy(
+        channel_pointer: RotatingChannelPointer<Data>,
+        data_pointers: impl IntoIterator<Item = RotatingDataPointer<Data>>,
+    ) -> Vec<Data> {
+        let RotatingChannelPointer { mut channel, .. } = channel_pointer;
+        let mut seen = vec![false; channel.slots.len()];
+        let first_slot = channel.slots.as_mut_ptr();
+
+        for data_pointer in data_pointers {
+            let RotatingDataPointer { data } = data_pointer;
+            let index = (0..seen.len())
+                .find(|&index| first_slot.wrapping_add(index) == data)
+                .expect("data pointer does not point to this channel");
+            assert!(
+                !seen[index],
+                "multiple data pointers point to the same slot"
+            );
+            seen[index] = true;
+        }
+        assert!(seen.into_iter().all(|seen| seen));
+
+        channel.slots
+    }
+}
+
+impl<Data> RotatingChannelPointer<Data> {
+    /// Rotate the `Data` fields by one step in the direction of this channel (see [RotatingChannelPointer::set_direction]).
+    pub fn rotate(&mut self, channel_key: &ChannelKey) {
+        self.rotate_by(channel_key, 1);
+    }
+
+    /// Rotate the `Data` fields by the given number of steps in the direction of this channel (see [RotatingChannelPointer::set_direction]).
+    /// A negative number of steps rotates against the direction of this channel.
+    ///
+    /// The rotation is performed in a single pass over the `Data` fields, independent of the number of steps.
+    pub fn rotate_by(&mut self, #[allow(unused)] channel_key: &ChannelKey, steps: isize) {
+        let slots = &mut self.channel.slots;
+        if slots.is_empty() {
+            return;
+        }
+
+        let steps = match self.direction {
+            Rotation::Forward => steps,
+            Rotation::Backward => steps.wrapping_neg(),
+        };
+        let steps = steps.rem_euclid(slots.len() as isize) as usize;
+        slots.rotate_right(steps);
+    }
+
+    /// Set the direction in which [RotatingChannelPointer::rotate] and [RotatingChannelPointer::rotate_by] move the `Data` fields.
+    pub fn set_direction(&mut self, direction: Rotation) {
+        self.direction = direction;
+    }
+
+    /// The direction in which [RotatingChannelPointer::rotate] and [RotatingChannelPointer::rotate_by] move the `Data` fields.
+    pub fn direction(&self) -> Rotation {
+        self.direction
+    }
+
+    /// The number of `Data` fields in the channel.
+    pub fn len(&self) -> usize {
+        self.channel.slots.len()
+    }
+
+    /// Returns `true` if the channel has no `Data` fields.
+    pub fn is_empty(&self) -> bool {
+        self.channel.slots.is_empty()
+    }
+
+    /// Shorthand for [RotatingChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointers: impl IntoIterator<Item = RotatingDataPointer<Data>>,
+    ) -> Vec<Data> {
+        RotatingChannel::destroy(self, data_pointers)
+    }
+}
+
+impl<Data> RotatingDataPointer<Data> {
+    /// Get a reference to the `Data` field pointed to by this pointer.
+    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> &Data {
+        unsafe { &*self.data }
+    }
+
+    /// Get a mutable reference to the `Data` field pointed to by this pointer.
+    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> &mut Data {
+        unsafe { &mut *self.data }
+    }
+}
+
+unsafe impl<Data> Send for RotatingChannelPointer<Data> {}
+unsafe impl<Data> Send for RotatingDataPointer<Data> {}
+
+unsafe impl<Data> Sync for RotatingChannelPointer<Data> {}
+unsafe impl<Data> Sync for RotatingDataPointer<Data> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        rotating::{RotatingChannel, Rotation},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointers) = RotatingChannel::create(0..4);
+
+        channel_pointer.rotate(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        let values: Vec<_> = data_pointers.iter().map(|p| *p.get(&data_key)).collect();
+        assert_eq!(values, [3, 0, 1, 2]);
+
+        channel_pointer.set_direction(Rotation::Backward);
+        channel_pointer.rotate_by(&master_key.get_channel_key(), 2);
+        let data_key = master_key.get_data_key();
+        let values: Vec<_> = data_pointers.iter().map(|p| *p.get(&data_key)).collect();
+        assert_eq!(values, [1, 2, 3, 0]);
+
+        assert_eq!(channel_pointer.destroy(data_pointers), [1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn rotate_by_is_reversible() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+
+        for len in 0..7 {
+            for direction in [Rotation::Forward, Rotation::Backward] {
+                let (mut channel_pointer, data_pointers) = RotatingChannel::create(0..len);
+                channel_pointer.set_direction(direction);
+
+                for steps in -3 * len as isize - 2..3 * len as isize + 2 {
+                    channel_pointer.rotate_by(&master_key.get_channel_key(), steps);
+                    let data_key = master_key.get_data_key();
+                    let expected = |index: usize| {
+                        let offset = match direction {
+                            Rotation::Forward => -steps,
+                            Rotation::Backward => steps,
+                        };
+                        (index as isize + offset).rem_euclid(len as isize) as usize
+                    };
+                    for (index, data_pointer) in data_pointers.iter().enumerate() {
+                        assert_eq!(*data_pointer.get(&data_key), expected(index));
+                    }
+
+                    channel_pointer.rotate_by(&master_key.get_channel_key(), -steps);
+                    let data_key = master_key.get_data_key();
+                    for (index, data_pointer) in data_pointers.iter().enumerate() {
+                        assert_eq!(*data_pointer.get(&data_key), index);
+                    }
+                }
+
+                assert!(channel_pointer
+                    .destroy(data_pointers)
+                    .into_iter()
+                    .eq(0..len));
+            }
+        }
+    }
+}
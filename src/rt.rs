@@ -0,0 +1,245 @@
+//! Real-time safety of channel operations, e.g. for use in audio callbacks, which must never allocate, lock or take unbounded time.
+//!
+//! The following operations are real-time safe, i.e. they do not allocate, free, lock or loop after the channels were created:
+//!
+//! * Deriving and converting keys: [MasterKey::get_data_key](crate::MasterKey::get_data_key), [MasterKey::get_channel_key](crate::MasterKey::get_channel_key),
+//!   [DataKey::into_channel_key](crate::DataKey::into_channel_key) and [ChannelKey::into_data_key](crate::ChannelKey::into_data_key).
+//! * Accessing data pointers: `get`, `get_mut`, `read` and `write` of all data pointers,
+//!   as well as `generation` and `changed_since` of undirected data pointers.
+//! * Undirected channels: [UndirectedChannelPointer::swap](crate::undirected::UndirectedChannelPointer::swap),
+//!   [swap_if](crate::undirected::UndirectedChannelPointer::swap_if), [rotate_by](crate::undirected::UndirectedChannelPointer::rotate_by),
+//!   [swap_between](crate::undirected::UndirectedChannelPointer::swap_between), [swap_with_scratch](crate::undirected::UndirectedChannelPointer::swap_with_scratch),
+//!   [stats](crate::undirected::UndirectedChannelPointer::stats) and [swap_count](crate::undirected::UndirectedChannelPointer::swap_count).
+//! * Directed channels: [DirectedChannelPointer::flush_swap](crate::directed::DirectedChannelPointer::flush_swap),
+//!   `flush_copy` with the `bytemuck` feature, [is_dirty](crate::directed::DirectedChannelPointer::is_dirty), [stats](crate::directed::DirectedChannelPointer::stats),
+//!   [inspect](crate::directed::DirectedChannelPointer::inspect), [reader_lag](crate::directed::DirectedChannelPointer::reader_lag)
+//!   and [ReadOnlyDataPointer::mark_consumed](crate::directed::ReadOnlyDataPointer::mark_consumed).
+//! * Bidirected channels: [BidirectedChannelPointer::flush_swap](crate::bidirected::BidirectedChannelPointer::flush_swap) and its single-direction variants.
+//! * Bounded queue channels: [BoundedQueueSender::push](crate::queue::BoundedQueueSender::push), [BoundedQueueChannelPointer::flush](crate::queue::BoundedQueueChannelPointer::flush)
+//!   and [BoundedQueueReceiver::drain](crate::queue::BoundedQueueReceiver::drain), if dropping the items does not free memory.
+//! * Atomic channels with the `bytemuck` feature: `AtomicWriter::store` and `AtomicReader::load`,
+//!   which only retry while a store is in progress.
+//! * Read-mostly channels: [ReaderHandle::read](crate::read_mostly::ReaderHandle::read), which never waits for the writer.
+//!
+//! The following operations are real-time safe if the `Data` reuses its allocations when cloned via [Clone::clone_from] and has enough capacity,
+//! e.g. a `Vec` whose capacity was reserved during setup:
+//! [DirectedChannelPointer::flush_clone_from](crate::directed::DirectedChannelPointer::flush_clone_from)
+//! and [FramePresenter::present](crate::frame::FramePresenter::present).
+//!
+//! All other operations are not real-time safe, in particular:
+//!
+//! * creating and destroying channels, which allocates and frees them,
+//! * [DirectedChannelPointer::flush](crate::directed::DirectedChannelPointer::flush) if cloning `Data` allocates,
+//! * snapshots, serialisation, labels, and the unbounded queue, group, runner and watchdog types.
+//!
+//! Hooks such as [UndirectedChannelPointer::set_on_swap](crate::undirected::UndirectedChannelPointer::set_on_swap) run inside the operation,
+//! which is hence only real-time safe if the hook is.
+//! With the `metrics` feature, operations on labelled channels allocate, and with the `tracing` feature, operations are only real-time safe if the subscriber is.
+//!
+//! With the `rt-checks` feature, this is enforced at runtime:
+//! `realtime` runs a function as real-time section, and **panics** if it allocated while an `RtCheckedAllocator` is the global allocator,
+//! or if it created or destroyed a channel.
+//! This is intended for tests, see `cargo test --features rt-checks`.
+
+#[cfg(feature = "rt-checks")]
+use core::cell::Cell;
+#[cfg(feature = "rt-checks")]
+use std::alloc::{GlobalAlloc, Layout, System};
+
+#[cfg(feature = "rt-checks")]
+thread_local! {
+    /// The number of nested real-time sections on this thread.
+    static DEPTH: Cell<usize> = Cell::new(0);
+    /// The number of allocations and deallocations in real-time sections on this thread, as counted by [RtCheckedAllocator].
+    static ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+/// A global allocator that counts the allocations and deallocations in [realtime] sections, delegating to the wrapped allocator.
+///
+/// ```
+/// use std::alloc::System;
+///
+/// use two_phase_channel::rt::RtCheckedAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: RtCheckedAllocator = RtCheckedAllocator(System);
+///
+/// fn main() {}
+/// ```
+#[cfg(feature = "rt-checks")]
+#[derive(Debug, Default)]
+pub struct RtCheckedAllocator<A = System>(pub A);
+
+/// Leaves the real-time section when dropped, also if the section panics.
+#[cfg(feature = "rt-checks")]
+struct Section;
+
+/// Run the given function as real-time section on the current thread, and return its result.
+///
+/// **Panics** if the function allocated or freed memory while an [RtCheckedAllocator] is the global allocator.
+/// Creating or destroying a channel within the function **panics** immediately.
+#[cfg(feature = "rt-checks")]
+#[track_caller]
+pub fn realtime<R>(f: impl FnOnce() -> R) -> R {
+    let before = ALLOCATIONS.with(Cell::get);
+    let section = Section::enter();
+    let result = f();
+    drop(section);
+    let allocations = ALLOCATIONS.with(Cell::get) - before;
+    assert!(
+        allocations == 0,
+        "{} allocations or deallocations in a real-time section",
+        allocations
+    );
+    result
+}
+
+/// Returns `true` if the current thread is in a [realtime] section.
+#[cfg(feature = "rt-checks")]
+pub fn is_realtime() -> bool {
+    DEPTH.with(Cell::get) > 0
+}
+
+/// **Panics** if the current thread is in a [realtime] section, naming the given operation.
+#[cfg(feature = "rt-checks")]
+#[track_caller]
+pub(crate) fn assert_not_realtime(operation: &str) {
+    assert!(
+        !is_realtime(),
+        "{} is not real-time safe, but was called in a real-time section",
+        operation
+    );
+}
+
+/// Does nothing without the `rt-checks` feature.
+#[cfg(not(feature = "rt-checks"))]
+#[inline]
+pub(crate) fn assert_not_realtime(#[allow(unused)] operation: &str) {}
+
+#[cfg(feature = "rt-checks")]
+impl Section {
+    fn enter() -> Self {
+        DEPTH.with(|depth| depth.set(depth.get() + 1));
+        Section
+    }
+}
+
+#[cfg(feature = "rt-checks")]
+impl Drop for Section {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Count an allocation or deallocation if the current thread is in a real-time section.
+/// Thread-local storage that is already destroyed is ignored, since the allocator must not panic.
+#[cfg(feature = "rt-checks")]
+fn record_allocation() {
+    let _ = DEPTH.try_with(|depth| {
+        if depth.get() > 0 {
+            let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        }
+    });
+}
+
+#[cfg(feature = "rt-checks")]
+unsafe impl<A: GlobalAlloc> GlobalAlloc for RtCheckedAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        self.0.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_allocation();
+        self.0.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        self.0.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation();
+        self.0.realloc(ptr, layout, new_size)
+    }
+}
+
+#[cfg(all(test, feature = "rt-checks"))]
+mod tests {
+    use std::alloc::System;
+
+    use crate::{
+        directed::DirectedChannel,
+        frame::FrameBuffered,
+        queue::{BoundedQueueChannel, OverflowPolicy},
+        rt::{realtime, RtCheckedAllocator},
+        undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    #[global_allocator]
+    static ALLOCATOR: RtCheckedAllocator = RtCheckedAllocator(System);
+
+    #[test]
+    fn rt_safe_operations_do_not_allocate() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut undirected, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create(vec![0; 64], vec![0; 64]);
+        let (mut directed, read_only, mut writable) = DirectedChannel::create(0u64, 0);
+        let (mut cloned, cloned_read_only, mut cloned_writable) =
+            DirectedChannel::create_equal(Vec::with_capacity(64));
+        let (mut producer, consumer, mut presenter) =
+            FrameBuffered::create(Vec::<u8>::with_capacity(64));
+        let (mut queue, mut sender, mut receiver) =
+            BoundedQueueChannel::create(4, OverflowPolicy::DropOldest);
+
+        let channel_key = master_key.get_channel_key();
+        cloned.manage_capacity(&channel_key, |data| data.reserve(64));
+        presenter.manage_capacity(&channel_key, |frame| frame.reserve(64));
+
+        for round in 0..10 {
+            realtime(|| {
+                let data_key = master_key.get_data_key();
+                data_pointer1.get_mut(&data_key)[0] = round;
+                *writable.get_mut(&data_key) = round as u64;
+                cloned_writable.write(&data_key, |data| data.push(round));
+                producer.write(&data_key, |frame| frame.push(round as u8));
+                assert_eq!(consumer.read(&data_key).len(), round);
+                // Each round pushes eight items into the queue of capacity four.
+                assert_eq!(
+                    receiver.drain(&data_key).count(),
+                    if round == 0 { 0 } else { 4 }
+                );
+                for item in 0..8 {
+                    sender.push(&data_key, item).unwrap();
+                }
+
+                let channel_key = data_key.into_channel_key();
+                undirected.swap(&channel_key);
+                directed.flush_swap(&channel_key);
+                cloned.flush_clone_from(&channel_key);
+                presenter.present(&channel_key);
+                queue.flush(&channel_key);
+            });
+        }
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*read_only.get(&data_key), 9);
+        assert_eq!(cloned_read_only.get(&data_key).len(), 10);
+        assert_eq!(queue.dropped(&master_key.get_channel_key()), 40);
+        let _ = (data_pointer2, producer, presenter, sender, receiver);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 allocations or deallocations in a real-time section")]
+    fn allocating_panics() {
+        realtime(|| Box::leak(Box::new(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "creating a channel is not real-time safe")]
+    fn creating_channels_panics() {
+        let _ = realtime(|| DirectedChannel::create(0, 0));
+    }
+}
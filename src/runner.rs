@@ -0,0 +1,427 @@
+//! A harness that runs named worker threads and a coordinator in alternating data and channel phases.
+//! It owns the master key, such that the keys handed to the workers and the coordinator are always derived correctly.
+
+use core::{
+    fmt,
+    ops::ControlFlow,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Barrier},
+    thread,
+};
+
+#[cfg(feature = "fault-injection")]
+use crate::fault::{self, FaultHook, FaultPlan, FaultSite};
+use crate::{
+    checked::KeyPhase,
+    watchdog::{Phase, WatchdogHandle, WatchdogWorker},
+    ChannelKey, DataKey, MasterKey,
+};
+
+/// The function of a worker, called once in each data phase.
+type Worker = Box<dyn FnMut(&DataKey) + Send>;
+
+/// The function of the coordinator, called once in each channel phase.
+type Coordinator = Box<dyn FnMut(&ChannelKey) -> ControlFlow<()>>;
+
+/// A coordinator borrowed for a single run.
+type CoordinatorRef<'a> = &'a mut dyn FnMut(&ChannelKey) -> ControlFlow<()>;
+
+/// Runs a set of named workers, each on its own thread, and a coordinator on the current thread.
+///
+/// Each phase consists of a data phase, in which all workers are called once in parallel,
+/// followed by a channel phase, in which the coordinator is called once, e.g. to advance the channels of a [ChannelGroup](crate::group::ChannelGroup).
+/// The threads are synchronised with a barrier at each phase change.
+///
+/// The workers must be `'static`, since scoped threads are not available with the minimum supported Rust version of this crate.
+/// Hence, the workers usually own their data pointers, and the coordinator owns the channel pointers.
+pub struct PhaseRunner {
+    master_key: MasterKey,
+    workers: Vec<(String, Worker)>,
+    coordinator: Option<Coordinator>,
+    watchdog: Option<WatchdogHandle>,
+    #[cfg(feature = "fault-injection")]
+    fault: Option<FaultHook>,
+}
+
+/// A worker of a [PhaseRunner] panicked.
+#[derive(Debug)]
+pub struct WorkerPanic {
+    name: String,
+    payload: Box<dyn Any + Send>,
+}
+
+impl PhaseRunner {
+    /// Create a runner without workers and without a coordinator, which owns the given master key.
+    pub fn new(master_key: MasterKey) -> Self {
+        Self {
+            master_key,
+            workers: Vec::new(),
+            coordinator: None,
+            watchdog: None,
+            #[cfg(feature = "fault-injection")]
+            fault: None,
+        }
+    }
+
+    /// Add a worker, which runs on a thread with the given name.
+    pub fn add_worker(
+        &mut self,
+        name: impl Into<String>,
+        worker: impl FnMut(&DataKey) + Send + 'static,
+    ) {
+        self.workers.push((name.into(), Box::new(worker)));
+    }
+
+    /// Set the coordinator, replacing the previous one.
+    /// The run stops after a channel phase in which the coordinator returns [ControlFlow::Break].
+    /// Without a coordinator, each phase continues.
+    pub fn set_coordinator(
+        &mut self,
+        coordinator: impl FnMut(&ChannelKey) -> ControlFlow<()> + 'static,
+    ) {
+        self.coordinator = Some(Box::new(coordinator));
+    }
+
+    /// Report the phase transitions of the following runs to the watchdog of the given handle.
+    /// During each run, the workers are registered with the watchdog by name, and check in after each of their data phases,
+    /// such that a stall report names the workers that did not finish their data phase.
+    pub fn set_watchdog(&mut self, watchdog: WatchdogHandle) {
+        self.watchdog = Some(watchdog);
+    }
+
+    /// Consult the given fault plan at the start of each channel phase, see the [fault] module.
+    /// A [Fault::Panic](fault::Fault::Panic) is handled like a panic of the coordinator,
+    /// a [Fault::Skip](fault::Fault::Skip) skips the coordinator in that phase, and a [Fault::Delay](fault::Fault::Delay) delays it.
+    #[cfg(feature = "fault-injection")]
+    pub fn set_fault_plan(&mut self, plan: Arc<FaultPlan>) {
+        self.fault = Some(FaultHook::new(plan, None));
+    }
+
+    /// Run phases until the coordinator breaks or a worker panics.
+    /// Returns the number of completed phases.
+    ///
+    /// If a worker panics, the coordinator is not called in that phase, and the panic of the first such worker in insertion order is returned as error.
+    /// The workers are kept, such that the runner can be run again.
+    ///
+    /// **Panics** if the coordinator panics.
+    /// In this case, all worker threads are joined first.
+    pub fn run(&mut self) -> Result<usize, WorkerPanic> {
+        self.run_own_coordinator(None)
+    }
+
+    /// Like [PhaseRunner::run], but runs at most the given number of phases.
+    pub fn run_for(&mut self, phases: usize) -> Result<usize, WorkerPanic> {
+        self.run_own_coordinator(Some(phases))
+    }
+
+    /// Like [PhaseRunner::run], but with the given coordinator instead of the one of this runner.
+    pub(crate) fn run_with(&mut self, coordinator: CoordinatorRef) -> Result<usize, WorkerPanic> {
+        self.run_phases(None, Some(coordinator))
+    }
+
+    /// The names of the workers, in insertion order.
+    pub fn worker_names(&self) -> impl Iterator<Item = &str> {
+        self.workers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Return the master key, dropping the workers and the coordinator.
+    pub fn into_master_key(self) -> MasterKey {
+        self.master_key
+    }
+
+    fn run_own_coordinator(&mut self, limit: Option<usize>) -> Result<usize, WorkerPanic> {
+        let mut coordinator = self.coordinator.take();
+        let result = self.run_phases(
+            limit,
+            coordinator
+                .as_mut()
+                .map(|coordinator| &mut **coordinator as CoordinatorRef),
+        );
+        self.coordinator = coordinator;
+        result
+    }
+
+    fn run_phases(
+        &mut self,
+        limit: Option<usize>,
+        mut coordinator: Option<CoordinatorRef>,
+    ) -> Result<usize, WorkerPanic> {
+        // Each phase starts and ends with a wait on the barrier, such that the workers only run while the coordinator holds a data key.
+        let barrier = Arc::new(Barrier::new(self.workers.len() + 1));
+        // Set by the coordinator before releasing the workers at the start of a phase.
+        let stop = Arc::new(AtomicBool::new(false));
+        // Set if a worker panicked.
+        // Workers only run in the data phase, hence the coordinator sees all panics of a phase after the barrier at its end.
+        let worker_panicked = Arc::new(AtomicBool::new(false));
+
+        let threads: Vec<_> = self
+            .workers
+            .drain(..)
+            .map(|(name, worker)| {
+                let barrier = barrier.clone();
+                let stop = stop.clone();
+                let worker_panicked = worker_panicked.clone();
+                let watchdog_worker = self
+                    .watchdog
+                    .as_ref()
+                    .map(|watchdog| watchdog.register_worker(name.clone()));
+                let thread = thread::Builder::new()
+                    .name(name.clone())
+                    .spawn(move || {
+                        run_worker(worker, &barrier, &stop, &worker_panicked, watchdog_worker)
+                    })
+                    .expect("failed to spawn worker thread");
+                (name, thread)
+            })
+            .collect();
+
+        let watchdog = self.watchdog.clone();
+        let mut phases = 0;
+        let mut coordinator_panic = None;
+        while limit != Some(phases) {
+            let data_key = self.master_key.get_data_key();
+            if let Some(watchdog) = &watchdog {
+                watchdog.transition(Phase::Data);
+            }
+            barrier.wait();
+            barrier.wait();
+            if worker_panicked.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let channel_key = data_key.into_channel_key();
+            if let Some(watchdog) = &watchdog {
+                watchdog.transition(Phase::Channel);
+            }
+            let coordinator = &mut coordinator;
+            #[cfg(feature = "fault-injection")]
+            let fault = &self.fault;
+            let flow = panic::catch_unwind(AssertUnwindSafe(|| {
+                #[cfg(feature = "fault-injection")]
+                if !fault::inject(fault, FaultSite::Phase) {
+                    return ControlFlow::Continue(());
+                }
+                match coordinator {
+                    Some(coordinator) => coordinator(&channel_key),
+                    None => ControlFlow::Continue(()),
+                }
+            }));
+            phases += 1;
+            match flow {
+                Ok(ControlFlow::Continue(())) => {}
+                Ok(ControlFlow::Break(())) => break,
+                Err(payload) => {
+                    coordinator_panic = Some(payload);
+                    break;
+                }
+            }
+        }
+        // Release the workers waiting for the next phase, which then see the stop.
+        stop.store(true, Ordering::Relaxed);
+        barrier.wait();
+
+        let mut first_panic = None;
+        for (name, thread) in threads {
+            let (worker, worker_panic) = thread.join().expect("worker thread panicked");
+            if let (None, Some(payload)) = (&first_panic, worker_panic) {
+                first_panic = Some(WorkerPanic {
+                    name: name.clone(),
+                    payload,
+                });
+            }
+            self.workers.push((name, worker));
+        }
+        if let Some(payload) = coordinator_panic {
+            panic::resume_unwind(payload);
+        }
+        match first_panic {
+            Some(worker_panic) => Err(worker_panic),
+            None => Ok(phases),
+        }
+    }
+}
+
+/// The loop of a worker thread of a [PhaseRunner].
+/// Returns the worker, and its panic, if any.
+fn run_worker(
+    mut worker: Worker,
+    barrier: &Barrier,
+    stop: &AtomicBool,
+    worker_panicked: &AtomicBool,
+    watchdog_worker: Option<WatchdogWorker>,
+) -> (Worker, Option<Box<dyn Any + Send>>) {
+    let mut worker_panic = None;
+    loop {
+        barrier.wait();
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        // The coordinator holds a data key until the next barrier wait, hence no channel key exists in the meantime.
+        if worker_panic.is_none() {
+            let data_key = DataKey::new(KeyPhase::default());
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| worker(&data_key))) {
+                worker_panic = Some(payload);
+                worker_panicked.store(true, Ordering::Relaxed);
+            }
+        }
+        if let Some(watchdog_worker) = &watchdog_worker {
+            watchdog_worker.check_in();
+        }
+        barrier.wait();
+    }
+    (worker, worker_panic)
+}
+
+impl WorkerPanic {
+    /// The name of the worker that panicked.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The message of the panic, if it is a string.
+    pub fn message(&self) -> Option<&str> {
+        if let Some(message) = self.payload.downcast_ref::<&str>() {
+            Some(message)
+        } else {
+            self.payload.downcast_ref::<String>().map(String::as_str)
+        }
+    }
+
+    /// The payload of the panic, e.g. to resume it via [std::panic::resume_unwind].
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+}
+
+impl fmt::Display for WorkerPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message() {
+            Some(message) => write!(f, "worker {:?} panicked: {}", self.name, message),
+            None => write!(f, "worker {:?} panicked", self.name),
+        }
+    }
+}
+
+impl std::error::Error for WorkerPanic {}
+
+impl fmt::Debug for PhaseRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PhaseRunner")
+            .field("workers", &self.worker_names().collect::<Vec<_>>())
+            .field("coordinator", &self.coordinator.is_some())
+            .field("watchdog", &self.watchdog.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ops::ControlFlow,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        directed::DirectedChannel,
+        runner::PhaseRunner,
+        undirected::UndirectedChannel,
+        watchdog::{Phase, PhaseWatchdog},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut directed, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let (mut undirected, mut data_pointer1, data_pointer2) = UndirectedChannel::create(0, 0);
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let mut runner = PhaseRunner::new(master_key);
+        // Writes the phase count to the directed channel.
+        runner.add_worker("producer", move |data_key| {
+            *writable.get_mut(data_key) += 1;
+        });
+        // Forwards what it reads from the directed channel to the undirected channel, with one phase of delay.
+        runner.add_worker("forwarder", move |data_key| {
+            *data_pointer1.get_mut(data_key) = *read_only.get(data_key) * 10;
+        });
+        let received_by_coordinator = received.clone();
+        runner.set_coordinator(move |channel_key| {
+            directed.flush(channel_key);
+            undirected.swap(channel_key);
+            let received = &mut *received_by_coordinator.lock().unwrap();
+            received.push(undirected.snapshot(channel_key).data2);
+            if received.len() != 4 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+
+        assert!(runner.worker_names().eq(["producer", "forwarder"]));
+        assert_eq!(runner.run().unwrap(), 4);
+        assert_eq!(*received.lock().unwrap(), [0, 10, 20, 30]);
+        assert_eq!(runner.run_for(2).unwrap(), 2);
+        assert_eq!(*received.lock().unwrap(), [0, 10, 20, 30, 40, 50]);
+        drop(data_pointer2);
+    }
+
+    #[test]
+    fn worker_panics_are_returned() {
+        let master_key = unsafe { MasterKey::create_unlimited() };
+        let mut runner = PhaseRunner::new(master_key);
+        let mut phase = 0;
+        runner.add_worker("healthy", |_| {
+            assert_eq!(thread::current().name(), Some("healthy"));
+        });
+        runner.add_worker("failing", move |_| {
+            phase += 1;
+            assert!(phase < 3, "worker failed");
+        });
+
+        let error = runner.run_for(5).unwrap_err();
+        assert_eq!(error.name(), "failing");
+        assert_eq!(error.message(), Some("worker failed"));
+        assert_eq!(
+            error.to_string(),
+            "worker \"failing\" panicked: worker failed"
+        );
+        assert_eq!(runner.run_for(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn watchdog_names_stalled_workers() {
+        let (sender, receiver) = mpsc::channel();
+        let watchdog = PhaseWatchdog::start(Duration::from_millis(50), move |stall| {
+            sender.send(stall.clone()).unwrap();
+        });
+        let handle = watchdog.handle();
+        handle.suspend();
+        let mut runner = PhaseRunner::new(unsafe { MasterKey::create_unlimited() });
+        runner.set_watchdog(handle.clone());
+        runner.add_worker("fast", |_| {});
+        let mut phase = 0;
+        runner.add_worker("slow", move |_| {
+            phase += 1;
+            if phase == 2 {
+                thread::sleep(Duration::from_millis(300));
+            }
+        });
+
+        handle.resume();
+        assert_eq!(runner.run_for(3).unwrap(), 3);
+        let stall = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(stall.phase, Phase::Data);
+        assert_eq!(stall.pending_workers, ["slow"]);
+        // The runner is idle between runs.
+        handle.suspend();
+        watchdog.shutdown();
+    }
+}
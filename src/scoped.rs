@@ -0,0 +1,995 @@
+//! Two-phase channels over storage borrowed from the caller, e.g. from a stack frame or a static, without any heap allocation.
+//! The pointers carry the lifetime of the borrow, so they can be used with scoped threads such as `std::thread::scope`,
+//! and destroying the channel ends the borrow, handing the storage back.
+//!
+//! Channels in statics are declared via [static_channel](crate::static_channel), see [StaticChannel].
+//!
+//! This module is also available without the default `std` feature, in which case the crate is `no_std`, e.g. for firmware.
+
+use core::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+pub use crate::common::{
+    BidirectedStats, CloneFlush, CloneFromFlush, FlushStats, FlushStrategy, SwapFlush, SwapStats,
+    TakeFlush,
+};
+use crate::{
+    checked::{CheckedChannel, CheckedPointer},
+    common, ChannelKey, DataKey,
+};
+
+/// The storage of a [ScopedUndirectedChannel], holding two instances of `Data`.
+#[derive(Debug, Default)]
+pub struct UndirectedStorage<Data> {
+    data1: UnsafeCell<Data>,
+    data2: UnsafeCell<Data>,
+}
+
+/// The storage of a [ScopedDirectedChannel], holding the read-only and the writable instance of `Data`.
+#[derive(Debug, Default)]
+pub struct DirectedStorage<Data> {
+    read_only: UnsafeCell<Data>,
+    writable: UnsafeCell<Data>,
+    /// `true` if the writable `Data` was accessed mutably since the last flush.
+    dirty: UnsafeCell<bool>,
+}
+
+/// The storage of a [ScopedBidirectedChannel], holding the directed storages of both directions.
+/// The forward direction transmits `Data1` from the second to the first data pointer,
+/// and the backward direction transmits `Data2` from the first to the second data pointer.
+#[derive(Debug, Default)]
+pub struct BidirectedStorage<Data1, Data2> {
+    forward: DirectedStorage<Data1>,
+    backward: DirectedStorage<Data2>,
+}
+
+/// An undirected channel over an [UndirectedStorage] borrowed from the caller.
+///
+/// See [ScopedUndirectedChannel::create] for more info.
+#[derive(Debug)]
+pub struct ScopedUndirectedChannel<Data> {
+    phantom: PhantomData<Data>,
+}
+
+/// A directed channel over a [DirectedStorage] borrowed from the caller.
+///
+/// See [ScopedDirectedChannel::create] for more info.
+#[derive(Debug)]
+pub struct ScopedDirectedChannel<Data> {
+    phantom: PhantomData<Data>,
+}
+
+/// A bidirected channel over a [BidirectedStorage] borrowed from the caller.
+///
+/// See [ScopedBidirectedChannel::create] for more info.
+#[derive(Debug)]
+pub struct ScopedBidirectedChannel<Data1, Data2> {
+    phantom: PhantomData<(Data1, Data2)>,
+}
+
+/// A pointer to a scoped undirected channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// Destroying the channel via [ScopedUndirectedChannel::destroy] returns mutable access to the storage before the borrow ends.
+#[derive(Debug)]
+#[must_use]
+pub struct ScopedUndirectedChannelPointer<'a, Data> {
+    storage: NonNull<UndirectedStorage<Data>>,
+    checked: CheckedChannel,
+    stats: SwapStats,
+    scope: PhantomData<&'a mut UndirectedStorage<Data>>,
+}
+
+/// A pointer to one of the data fields in a scoped undirected channel.
+/// It can only be accessed using a [DataKey].
+#[derive(Debug)]
+#[must_use]
+pub struct ScopedUndirectedDataPointer<'a, Data> {
+    data: *mut Data,
+    checked: CheckedPointer,
+    scope: PhantomData<&'a mut Data>,
+}
+
+/// A pointer to a scoped directed channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// Destroying the channel via [ScopedDirectedChannel::destroy] returns mutable access to the storage before the borrow ends.
+#[derive(Debug)]
+#[must_use]
+pub struct ScopedDirectedChannelPointer<'a, Data> {
+    storage: NonNull<DirectedStorage<Data>>,
+    checked: CheckedChannel,
+    stats: FlushStats,
+    scope: PhantomData<&'a mut DirectedStorage<Data>>,
+}
+
+/// A pointer to the read-only data field in a scoped directed channel.
+/// It can only be accessed using a [DataKey].
+#[derive(Debug)]
+#[must_use]
+pub struct ScopedReadOnlyDataPointer<'a, Data> {
+    data: *const Data,
+    checked: CheckedPointer,
+    scope: PhantomData<&'a Data>,
+}
+
+/// A pointer to the writable data field in a scoped directed channel.
+/// It can only be accessed using a [DataKey].
+#[derive(Debug)]
+#[must_use]
+pub struct ScopedWritableDataPointer<'a, Data> {
+    data: *mut Data,
+    dirty: *mut bool,
+    checked: CheckedPointer,
+    scope: PhantomData<&'a mut Data>,
+}
+
+/// A pointer to a scoped bidirected channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// Destroying the channel via [ScopedBidirectedChannel::destroy] returns mutable access to the storage before the borrow ends.
+#[derive(Debug)]
+#[must_use]
+pub struct ScopedBidirectedChannelPointer<'a, Data1, Data2> {
+    storage: NonNull<BidirectedStorage<Data1, Data2>>,
+    checked: CheckedChannel,
+    stats: BidirectedStats,
+    scope: PhantomData<&'a mut BidirectedStorage<Data1, Data2>>,
+}
+
+/// A pointer to one endpoint of a scoped bidirected channel, reading `Input` and writing `Output`.
+/// It can only be accessed using a [DataKey].
+#[derive(Debug)]
+#[must_use]
+pub struct ScopedBidirectedDataPointer<'a, Input, Output> {
+    input: ScopedReadOnlyDataPointer<'a, Input>,
+    output: ScopedWritableDataPointer<'a, Output>,
+}
+
+/// A channel storage in a static, whose pointers can be taken exactly once, e.g. at boot.
+/// It is usually declared via [static_channel](crate::static_channel).
+///
+/// The storage is initialised at compile time, and no heap allocation happens at any point.
+#[derive(Debug)]
+pub struct StaticChannel<Storage> {
+    taken: AtomicBool,
+    storage: UnsafeCell<Storage>,
+}
+
+/// A storage that a [StaticChannel] can hand out pointers to.
+/// It is implemented for [UndirectedStorage], [DirectedStorage] and [BidirectedStorage].
+pub trait StaticStorage: Sized + 'static {
+    /// The channel pointer and the two data pointers of the channel, as returned by its `create` function.
+    type Pointers;
+
+    /// Create the channel over the given storage, like the `create` function of its scoped channel.
+    fn create(storage: &'static mut Self) -> Self::Pointers;
+}
+
+impl<Storage> StaticChannel<Storage> {
+    /// Wrap the given storage.
+    pub const fn new(storage: Storage) -> Self {
+        Self {
+            taken: AtomicBool::new(false),
+            storage: UnsafeCell::new(storage),
+        }
+    }
+
+    /// Returns `true` if the pointers were taken.
+    pub fn is_taken(&self) -> bool {
+        self.taken.load(Ordering::Acquire)
+    }
+}
+
+impl<Storage: StaticStorage> StaticChannel<Storage> {
+    /// Create the channel over the storage and hand out its three pointers, or return `None` if they were taken before.
+    pub fn try_take(&'static self) -> Option<Storage::Pointers> {
+        if self.taken.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            // The flag ensures that this is the only mutable reference to the storage ever created.
+            Some(Storage::create(unsafe { &mut *self.storage.get() }))
+        }
+    }
+
+    /// Create the channel over the storage and hand out its three pointers.
+    ///
+    /// **Panics** if the pointers were taken before.
+    pub fn take(&'static self) -> Storage::Pointers {
+        self.try_take()
+            .expect("the pointers of this static channel were already taken")
+    }
+}
+
+impl<Data: 'static> StaticStorage for UndirectedStorage<Data> {
+    type Pointers = (
+        ScopedUndirectedChannelPointer<'static, Data>,
+        ScopedUndirectedDataPointer<'static, Data>,
+        ScopedUndirectedDataPointer<'static, Data>,
+    );
+
+    fn create(storage: &'static mut Self) -> Self::Pointers {
+        ScopedUndirectedChannel::create(storage)
+    }
+}
+
+impl<Data: 'static> StaticStorage for DirectedStorage<Data> {
+    type Pointers = (
+        ScopedDirectedChannelPointer<'static, Data>,
+        ScopedReadOnlyDataPointer<'static, Data>,
+        ScopedWritableDataPointer<'static, Data>,
+    );
+
+    fn create(storage: &'static mut Self) -> Self::Pointers {
+        ScopedDirectedChannel::create(storage)
+    }
+}
+
+impl<Data1: 'static, Data2: 'static> StaticStorage for BidirectedStorage<Data1, Data2> {
+    type Pointers = (
+        ScopedBidirectedChannelPointer<'static, Data1, Data2>,
+        ScopedBidirectedDataPointer<'static, Data1, Data2>,
+        ScopedBidirectedDataPointer<'static, Data2, Data1>,
+    );
+
+    fn create(storage: &'static mut Self) -> Self::Pointers {
+        ScopedBidirectedChannel::create(storage)
+    }
+}
+
+/// Declare a [StaticChannel] in a static, initialised at compile time.
+/// The `Data` fields are given in the order of the `new` function of the respective storage.
+///
+/// ```
+/// use two_phase_channel::static_channel;
+///
+/// static_channel!(static FRAME: undirected<[u8; 4]> = ([0; 4], [0; 4]));
+/// static_channel!(pub static COMMAND: directed<u32> = (0, 0));
+/// static_channel!(static LINK: bidirected<u8, u16> = (0, 0, 0, 0));
+///
+/// let (channel_pointer, data_pointer1, data_pointer2) = FRAME.take();
+/// assert!(FRAME.try_take().is_none());
+/// ```
+#[macro_export]
+macro_rules! static_channel {
+    ($(#[$attribute:meta])* $visibility:vis static $name:ident: undirected<$data:ty> = ($data1:expr, $data2:expr $(,)?)) => {
+        $(#[$attribute])*
+        $visibility static $name: $crate::scoped::StaticChannel<$crate::scoped::UndirectedStorage<$data>> =
+            $crate::scoped::StaticChannel::new($crate::scoped::UndirectedStorage::new($data1, $data2));
+    };
+    ($(#[$attribute:meta])* $visibility:vis static $name:ident: directed<$data:ty> = ($read_only:expr, $writable:expr $(,)?)) => {
+        $(#[$attribute])*
+        $visibility static $name: $crate::scoped::StaticChannel<$crate::scoped::DirectedStorage<$data>> =
+            $crate::scoped::StaticChannel::new($crate::scoped::DirectedStorage::new($read_only, $writable));
+    };
+    ($(#[$attribute:meta])* $visibility:vis static $name:ident: bidirected<$data1:ty, $data2:ty> = ($read_only1:expr, $writable1:expr, $read_only2:expr, $writable2:expr $(,)?)) => {
+        $(#[$attribute])*
+        $visibility static $name: $crate::scoped::StaticChannel<$crate::scoped::BidirectedStorage<$data1, $data2>> =
+            $crate::scoped::StaticChannel::new($crate::scoped::BidirectedStorage::new(
+                $read_only1,
+                $writable1,
+                $read_only2,
+                $writable2,
+            ));
+    };
+}
+
+impl<Data> UndirectedStorage<Data> {
+    /// Create a storage holding the given `Data` fields.
+    pub const fn new(data1: Data, data2: Data) -> Self {
+        Self {
+            data1: UnsafeCell::new(data1),
+            data2: UnsafeCell::new(data2),
+        }
+    }
+
+    /// Get mutable references to both `Data` fields.
+    pub fn get_mut(&mut self) -> (&mut Data, &mut Data) {
+        (self.data1.get_mut(), self.data2.get_mut())
+    }
+
+    /// Returns both `Data` fields.
+    pub fn into_inner(self) -> (Data, Data) {
+        (self.data1.into_inner(), self.data2.into_inner())
+    }
+}
+
+impl<Data> DirectedStorage<Data> {
+    /// Create a storage holding the given `Data` fields.
+    pub const fn new(read_only: Data, writable: Data) -> Self {
+        Self {
+            read_only: UnsafeCell::new(read_only),
+            writable: UnsafeCell::new(writable),
+            dirty: UnsafeCell::new(true),
+        }
+    }
+
+    /// Get mutable references to the read-only and the writable `Data` field, in this order.
+    pub fn get_mut(&mut self) -> (&mut Data, &mut Data) {
+        (self.read_only.get_mut(), self.writable.get_mut())
+    }
+
+    /// Returns the read-only and the writable `Data` field, in this order.
+    pub fn into_inner(self) -> (Data, Data) {
+        (self.read_only.into_inner(), self.writable.into_inner())
+    }
+
+    /// Hand out the data pointers of a newly created channel, which is dirty like a newly created [DirectedChannel](crate::directed::DirectedChannel).
+    fn hand_out<'a>(
+        storage: NonNull<Self>,
+        checked: CheckedPointer,
+    ) -> (
+        ScopedReadOnlyDataPointer<'a, Data>,
+        ScopedWritableDataPointer<'a, Data>,
+    ) {
+        let storage = unsafe { storage.as_ref() };
+        // No data pointer exists yet.
+        unsafe { *storage.dirty.get() = true };
+        (
+            ScopedReadOnlyDataPointer {
+                data: storage.read_only.get(),
+                checked,
+                scope: PhantomData,
+            },
+            ScopedWritableDataPointer {
+                data: storage.writable.get(),
+                dirty: storage.dirty.get(),
+                checked,
+                scope: PhantomData,
+            },
+        )
+    }
+
+    /// Flush with the given strategy if the storage is dirty, counting the flush into the given statistics.
+    /// Returns `true` if the storage was dirty and hence flushed.
+    ///
+    /// Must only be called via a channel pointer with a channel key, i.e. while no data pointer accesses the storage.
+    unsafe fn flush_with_if_dirty(
+        &self,
+        strategy: &impl FlushStrategy<Data>,
+        stats: &mut FlushStats,
+    ) -> bool {
+        if *self.dirty.get() {
+            strategy.flush(&mut *self.read_only.get(), &mut *self.writable.get());
+            *self.dirty.get() = false;
+            stats.flushes += 1;
+            stats.generation += 1;
+            true
+        } else {
+            stats.skipped += 1;
+            false
+        }
+    }
+
+    /// Returns `true` if the given pointers point to the `Data` fields of this storage.
+    fn owns(
+        &self,
+        read_only_data_pointer: &ScopedReadOnlyDataPointer<Data>,
+        writable_data_pointer: &ScopedWritableDataPointer<Data>,
+    ) -> bool {
+        ptr::eq(read_only_data_pointer.data, self.read_only.get())
+            && ptr::eq(writable_data_pointer.data, self.writable.get())
+    }
+}
+
+impl<Data1, Data2> BidirectedStorage<Data1, Data2> {
+    /// Create a storage holding the given `Data` fields,
+    /// with the read-only and the writable `Data` of the forward and the backward direction, in the order of [`BidirectedChannel::create`](crate::bidirected::BidirectedChannel::create).
+    pub const fn new(
+        read_only1: Data1,
+        writable1: Data1,
+        read_only2: Data2,
+        writable2: Data2,
+    ) -> Self {
+        Self {
+            forward: DirectedStorage::new(read_only1, writable1),
+            backward: DirectedStorage::new(read_only2, writable2),
+        }
+    }
+
+    /// Get mutable references to the storages of the forward and the backward direction.
+    pub fn get_mut(&mut self) -> (&mut DirectedStorage<Data1>, &mut DirectedStorage<Data2>) {
+        (&mut self.forward, &mut self.backward)
+    }
+
+    /// Returns all four `Data` fields, in the order of [`BidirectedChannel::create`](crate::bidirected::BidirectedChannel::create).
+    pub fn into_inner(self) -> (Data1, Data1, Data2, Data2) {
+        let (read_only1, writable1) = self.forward.into_inner();
+        let (read_only2, writable2) = self.backward.into_inner();
+        (read_only1, writable1, read_only2, writable2)
+    }
+}
+
+impl<Data> ScopedUndirectedChannel<Data> {
+    /// Create an undirected channel over the given storage and hand out three pointers to it,
+    /// like [`UndirectedChannel::create`](crate::undirected::UndirectedChannel::create).
+    /// The storage stays borrowed until the channel is destroyed.
+    pub fn create(
+        storage: &mut UndirectedStorage<Data>,
+    ) -> (
+        ScopedUndirectedChannelPointer<'_, Data>,
+        ScopedUndirectedDataPointer<'_, Data>,
+        ScopedUndirectedDataPointer<'_, Data>,
+    ) {
+        let storage = NonNull::from(storage);
+        let checked = CheckedChannel::new();
+        let data_pointer = |data| ScopedUndirectedDataPointer {
+            data,
+            checked: checked.pointer(),
+            scope: PhantomData,
+        };
+        let (data_pointer1, data_pointer2) = unsafe {
+            (
+                data_pointer(storage.as_ref().data1.get()),
+                data_pointer(storage.as_ref().data2.get()),
+            )
+        };
+        (
+            ScopedUndirectedChannelPointer {
+                storage,
+                checked,
+                stats: SwapStats::default(),
+                scope: PhantomData,
+            },
+            data_pointer1,
+            data_pointer2,
+        )
+    }
+
+    /// Destroys the scoped undirected channel linked with the three pointers (see [ScopedUndirectedChannel::create]),
+    /// returning mutable access to its storage.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy<'a>(
+        channel_pointer: ScopedUndirectedChannelPointer<'a, Data>,
+        data_pointer1: ScopedUndirectedDataPointer<'a, Data>,
+        data_pointer2: ScopedUndirectedDataPointer<'a, Data>,
+    ) -> &'a mut UndirectedStorage<Data> {
+        let storage = unsafe { &mut *channel_pointer.storage.as_ptr() };
+        let (data1, data2) = (storage.data1.get(), storage.data2.get());
+        assert!(
+            (data_pointer1.data == data1 && data_pointer2.data == data2)
+                || (data_pointer1.data == data2 && data_pointer2.data == data1),
+            "the data pointers do not point to this channel"
+        );
+        storage
+    }
+}
+
+impl<Data> ScopedDirectedChannel<Data> {
+    /// Create a directed channel over the given storage and hand out three pointers to it,
+    /// like [`DirectedChannel::create`](crate::directed::DirectedChannel::create).
+    /// The storage stays borrowed until the channel is destroyed.
+    pub fn create(
+        storage: &mut DirectedStorage<Data>,
+    ) -> (
+        ScopedDirectedChannelPointer<'_, Data>,
+        ScopedReadOnlyDataPointer<'_, Data>,
+        ScopedWritableDataPointer<'_, Data>,
+    ) {
+        let storage = NonNull::from(storage);
+        let checked = CheckedChannel::new();
+        let (read_only_data_pointer, writable_data_pointer) =
+            DirectedStorage::hand_out(storage, checked.pointer());
+        (
+            ScopedDirectedChannelPointer {
+                storage,
+                checked,
+                stats: FlushStats::default(),
+                scope: PhantomData,
+            },
+            read_only_data_pointer,
+            writable_data_pointer,
+        )
+    }
+
+    /// Destroys the scoped directed channel linked with the three pointers (see [ScopedDirectedChannel::create]),
+    /// returning mutable access to its storage.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy<'a>(
+        channel_pointer: ScopedDirectedChannelPointer<'a, Data>,
+        read_only_data_pointer: ScopedReadOnlyDataPointer<'a, Data>,
+        writable_data_pointer: ScopedWritableDataPointer<'a, Data>,
+    ) -> &'a mut DirectedStorage<Data> {
+        let storage = unsafe { &mut *channel_pointer.storage.as_ptr() };
+        assert!(
+            storage.owns(&read_only_data_pointer, &writable_data_pointer),
+            "the data pointers do not point to this channel"
+        );
+        storage
+    }
+}
+
+impl<Data1, Data2> ScopedBidirectedChannel<Data1, Data2> {
+    /// Create a bidirected channel over the given storage and hand out three pointers to it,
+    /// like [`BidirectedChannel::create`](crate::bidirected::BidirectedChannel::create).
+    /// The storage stays borrowed until the channel is destroyed.
+    #[allow(clippy::type_complexity)]
+    pub fn create(
+        storage: &mut BidirectedStorage<Data1, Data2>,
+    ) -> (
+        ScopedBidirectedChannelPointer<'_, Data1, Data2>,
+        ScopedBidirectedDataPointer<'_, Data1, Data2>,
+        ScopedBidirectedDataPointer<'_, Data2, Data1>,
+    ) {
+        let storage = NonNull::from(storage);
+        let (forward, backward) = unsafe {
+            (
+                NonNull::from(&storage.as_ref().forward),
+                NonNull::from(&storage.as_ref().backward),
+            )
+        };
+        let checked = CheckedChannel::new();
+        let (input1, output2) = DirectedStorage::hand_out(forward, checked.pointer());
+        let (input2, output1) = DirectedStorage::hand_out(backward, checked.pointer());
+        (
+            ScopedBidirectedChannelPointer {
+                storage,
+                checked,
+                stats: BidirectedStats::default(),
+                scope: PhantomData,
+            },
+            ScopedBidirectedDataPointer {
+                input: input1,
+                output: output1,
+            },
+            ScopedBidirectedDataPointer {
+                input: input2,
+                output: output2,
+            },
+        )
+    }
+
+    /// Destroys the scoped bidirected channel linked with the three pointers (see [ScopedBidirectedChannel::create]),
+    /// returning mutable access to its storage.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy<'a>(
+        channel_pointer: ScopedBidirectedChannelPointer<'a, Data1, Data2>,
+        data_pointer1: ScopedBidirectedDataPointer<'a, Data1, Data2>,
+        data_pointer2: ScopedBidirectedDataPointer<'a, Data2, Data1>,
+    ) -> &'a mut BidirectedStorage<Data1, Data2> {
+        let storage = unsafe { &mut *channel_pointer.storage.as_ptr() };
+        assert!(
+            storage
+                .forward
+                .owns(&data_pointer1.input, &data_pointer2.output)
+                && storage
+                    .backward
+                    .owns(&data_pointer2.input, &data_pointer1.output),
+            "the data pointers do not point to this channel"
+        );
+        storage
+    }
+}
+
+impl<'a, Data> ScopedUndirectedChannelPointer<'a, Data> {
+    /// Swap the content of the two `Data` fields.
+    pub fn swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.checked.advance();
+        let storage = unsafe { self.storage.as_ref() };
+        unsafe { ptr::swap(storage.data1.get(), storage.data2.get()) };
+        self.stats.swaps += 1;
+        self.stats.last_swap_generation += 1;
+    }
+
+    /// Swap the two `Data` fields if the given predicate holds for them.
+    /// Returns `true` if the `Data` fields were swapped.
+    pub fn swap_if(
+        &mut self,
+        channel_key: &ChannelKey,
+        predicate: impl FnOnce(&Data, &Data) -> bool,
+    ) -> bool {
+        let storage = unsafe { self.storage.as_ref() };
+        if unsafe { predicate(&*storage.data1.get(), &*storage.data2.get()) } {
+            self.swap(channel_key);
+            true
+        } else {
+            self.stats.skipped += 1;
+            false
+        }
+    }
+
+    /// Statistics about the swaps performed on this channel.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> SwapStats {
+        self.stats
+    }
+
+    /// Shorthand for [ScopedUndirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: ScopedUndirectedDataPointer<'a, Data>,
+        data_pointer2: ScopedUndirectedDataPointer<'a, Data>,
+    ) -> &'a mut UndirectedStorage<Data> {
+        ScopedUndirectedChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+}
+
+impl<'a, Data> ScopedUndirectedDataPointer<'a, Data> {
+    common::read_accessors!();
+    common::write_accessors!();
+
+    fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+
+    fn access_mut(&mut self, data_key: &DataKey) {
+        self.access(data_key);
+    }
+}
+
+impl<'a, Data: Clone> ScopedDirectedChannelPointer<'a, Data> {
+    /// Clone the writable `Data` into the read-only `Data`, like [`DirectedChannelPointer::flush`](crate::directed::DirectedChannelPointer::flush).
+    /// If the writable `Data` was not accessed mutably since the last flush, the flush is skipped.
+    pub fn flush(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&CloneFlush);
+    }
+
+    /// Like [ScopedDirectedChannelPointer::flush], but clone via [Clone::clone_from], which may reuse the allocations of the read-only `Data`.
+    pub fn flush_clone_from(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&CloneFromFlush);
+    }
+}
+
+impl<'a, Data> ScopedDirectedChannelPointer<'a, Data> {
+    /// Swap the writable `Data` with the read-only `Data`, like [`DirectedChannelPointer::flush_swap`](crate::directed::DirectedChannelPointer::flush_swap).
+    /// The swap is skipped if the writable `Data` was not accessed mutably since the last flush.
+    pub fn flush_swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&SwapFlush);
+    }
+
+    /// Flush with the given strategy, e.g. [TakeFlush], if the writable `Data` was accessed mutably since the last flush.
+    /// Returns `true` if the channel was dirty and hence flushed.
+    pub fn flush_with(
+        &mut self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        strategy: &impl FlushStrategy<Data>,
+    ) -> bool {
+        self.flush_with_if_dirty(strategy)
+    }
+
+    fn flush_with_if_dirty(&mut self, strategy: &impl FlushStrategy<Data>) -> bool {
+        self.checked.advance();
+        unsafe {
+            self.storage
+                .as_ref()
+                .flush_with_if_dirty(strategy, &mut self.stats)
+        }
+    }
+
+    /// Returns `true` if the writable `Data` was accessed mutably since the last flush,
+    /// i.e. if the next flush will clone it.
+    /// A newly created channel is dirty.
+    pub fn is_dirty(&self, #[allow(unused)] channel_key: &ChannelKey) -> bool {
+        unsafe { *self.storage.as_ref().dirty.get() }
+    }
+
+    /// Statistics about the flushes performed on this channel.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> FlushStats {
+        self.stats
+    }
+
+    /// Shorthand for [ScopedDirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        read_only_data_pointer: ScopedReadOnlyDataPointer<'a, Data>,
+        writable_data_pointer: ScopedWritableDataPointer<'a, Data>,
+    ) -> &'a mut DirectedStorage<Data> {
+        ScopedDirectedChannel::destroy(self, read_only_data_pointer, writable_data_pointer)
+    }
+}
+
+impl<'a, Data> ScopedReadOnlyDataPointer<'a, Data> {
+    common::read_accessors!();
+
+    fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+}
+
+impl<'a, Data> ScopedWritableDataPointer<'a, Data> {
+    common::read_accessors!();
+    common::write_accessors!(
+        /// This marks the channel as dirty, such that the next flush clones the `Data`.
+    );
+
+    fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+
+    fn access_mut(&mut self, data_key: &DataKey) {
+        self.access(data_key);
+        unsafe { *self.dirty = true };
+    }
+}
+
+impl<'a, Data1: Clone, Data2: Clone> ScopedBidirectedChannelPointer<'a, Data1, Data2> {
+    /// Clone the writable `Data`s into the read-only `Data`s, like [`BidirectedChannelPointer::flush`](crate::bidirected::BidirectedChannelPointer::flush).
+    /// Each direction is skipped if its writable `Data` was not accessed mutably since the last flush.
+    pub fn flush(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&CloneFlush, &CloneFlush);
+    }
+
+    /// Like [ScopedBidirectedChannelPointer::flush], but clone via [Clone::clone_from], which may reuse the allocations of the read-only `Data`s.
+    pub fn flush_clone_from(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&CloneFromFlush, &CloneFromFlush);
+    }
+}
+
+impl<'a, Data1, Data2> ScopedBidirectedChannelPointer<'a, Data1, Data2> {
+    /// Swap the writable `Data`s with the read-only `Data`s, like [`BidirectedChannelPointer::flush_swap`](crate::bidirected::BidirectedChannelPointer::flush_swap).
+    /// Each direction is skipped if its writable `Data` was not accessed mutably since the last flush.
+    pub fn flush_swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.flush_with_if_dirty(&SwapFlush, &SwapFlush);
+    }
+
+    fn flush_with_if_dirty(
+        &mut self,
+        forward: &impl FlushStrategy<Data1>,
+        backward: &impl FlushStrategy<Data2>,
+    ) {
+        self.checked.advance();
+        let storage = unsafe { self.storage.as_ref() };
+        unsafe {
+            storage
+                .forward
+                .flush_with_if_dirty(forward, &mut self.stats.forward);
+            storage
+                .backward
+                .flush_with_if_dirty(backward, &mut self.stats.backward);
+        }
+    }
+
+    /// Returns for the forward and the backward direction, in this order,
+    /// `true` if its writable `Data` was accessed mutably since the last flush.
+    /// A newly created channel is dirty in both directions.
+    pub fn is_dirty(&self, #[allow(unused)] channel_key: &ChannelKey) -> (bool, bool) {
+        let storage = unsafe { self.storage.as_ref() };
+        unsafe { (*storage.forward.dirty.get(), *storage.backward.dirty.get()) }
+    }
+
+    /// Statistics about the flushes performed on both directions of this channel.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> BidirectedStats {
+        self.stats
+    }
+
+    /// Shorthand for [ScopedBidirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: ScopedBidirectedDataPointer<'a, Data1, Data2>,
+        data_pointer2: ScopedBidirectedDataPointer<'a, Data2, Data1>,
+    ) -> &'a mut BidirectedStorage<Data1, Data2> {
+        ScopedBidirectedChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+}
+
+impl<'a, Input, Output> ScopedBidirectedDataPointer<'a, Input, Output> {
+    common::endpoint_accessors!();
+}
+
+unsafe impl<'a, Data: Send> Send for ScopedUndirectedChannelPointer<'a, Data> {}
+unsafe impl<'a, Data: Send> Send for ScopedUndirectedDataPointer<'a, Data> {}
+unsafe impl<'a, Data: Send> Send for ScopedDirectedChannelPointer<'a, Data> {}
+unsafe impl<'a, Data: Send + Sync> Send for ScopedReadOnlyDataPointer<'a, Data> {}
+unsafe impl<'a, Data: Send> Send for ScopedWritableDataPointer<'a, Data> {}
+unsafe impl<'a, Data1: Send, Data2: Send> Send
+    for ScopedBidirectedChannelPointer<'a, Data1, Data2>
+{
+}
+
+unsafe impl<'a, Data: Sync> Sync for ScopedUndirectedChannelPointer<'a, Data> {}
+unsafe impl<'a, Data: Sync> Sync for ScopedUndirectedDataPointer<'a, Data> {}
+unsafe impl<'a, Data: Sync> Sync for ScopedDirectedChannelPointer<'a, Data> {}
+unsafe impl<'a, Data: Sync> Sync for ScopedReadOnlyDataPointer<'a, Data> {}
+unsafe impl<'a, Data: Sync> Sync for ScopedWritableDataPointer<'a, Data> {}
+unsafe impl<'a, Data1: Sync, Data2: Sync> Sync
+    for ScopedBidirectedChannelPointer<'a, Data1, Data2>
+{
+}
+
+// The storage is only accessed through the pointers handed out once, which carry the `Send` and `Sync` bounds of their `Data`.
+unsafe impl<Storage: Send> Sync for StaticChannel<Storage> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        scoped::{
+            BidirectedStorage, DirectedStorage, FlushStats, ScopedBidirectedChannel,
+            ScopedDirectedChannel, ScopedUndirectedChannel, SwapStats, TakeFlush,
+            UndirectedStorage,
+        },
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut storage = UndirectedStorage::new(0, 1);
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            ScopedUndirectedChannel::create(&mut storage);
+
+        *data_pointer1.get_mut(&master_key.get_data_key()) = 2;
+        channel_pointer.swap(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointer1.get(&data_key), 1);
+        assert_eq!(*data_pointer2.get(&data_key), 2);
+
+        let storage = channel_pointer.destroy(data_pointer1, data_pointer2);
+        *storage.get_mut().0 = 3;
+        assert_eq!(storage.get_mut(), (&mut 3, &mut 2));
+    }
+
+    #[test]
+    fn directed_and_bidirected() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+
+        let mut storage = DirectedStorage::new(vec![0], vec![0]);
+        let (mut channel_pointer, read_only, mut writable) =
+            ScopedDirectedChannel::create(&mut storage);
+        writable.get_mut(&master_key.get_data_key()).push(1);
+        assert_eq!(read_only.get(&master_key.get_data_key()), &[0]);
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(read_only.get(&master_key.get_data_key()), &[0, 1]);
+        channel_pointer.destroy(read_only, writable);
+        assert_eq!(storage.into_inner(), (vec![0, 1], vec![0, 1]));
+
+        let mut storage = BidirectedStorage::new(0u8, 0, String::new(), String::new());
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            ScopedBidirectedChannel::create(&mut storage);
+        let data_key = master_key.get_data_key();
+        data_pointer1.get_output(&data_key).push('a');
+        *data_pointer2.get_output(&data_key) = 1;
+        channel_pointer.flush(&data_key.into_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointer1.get_input(&data_key), 1);
+        assert_eq!(data_pointer2.get_input(&data_key), "a");
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+        assert_eq!(
+            storage.into_inner(),
+            (1, 1, String::from("a"), String::from("a"))
+        );
+    }
+
+    #[test]
+    fn accessors_and_stats() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+
+        let mut storage = UndirectedStorage::new(0, 1);
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            ScopedUndirectedChannel::create(&mut storage);
+        data_pointer1.write(&master_key.get_data_key(), |data| *data = 2);
+        let channel_key = master_key.get_channel_key();
+        channel_pointer.swap(&channel_key);
+        assert!(!channel_pointer.swap_if(&channel_key, |_, _| false));
+        assert_eq!(
+            channel_pointer.stats(&channel_key),
+            SwapStats {
+                swaps: 1,
+                skipped: 1,
+                last_swap_generation: 1,
+            }
+        );
+        assert_eq!(
+            data_pointer2.read(&master_key.get_data_key(), |data| *data),
+            2
+        );
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+
+        let mut storage = DirectedStorage::new(vec![0], vec![0]);
+        let (mut channel_pointer, read_only, mut writable) =
+            ScopedDirectedChannel::create(&mut storage);
+        let channel_key = master_key.get_channel_key();
+        assert!(channel_pointer.is_dirty(&channel_key));
+        channel_pointer.flush_swap(&channel_key);
+        // Reading does not mark the channel as dirty.
+        assert_eq!(writable.read(&master_key.get_data_key(), Vec::len), 1);
+        let channel_key = master_key.get_channel_key();
+        assert!(!channel_pointer.is_dirty(&channel_key));
+        assert!(!channel_pointer.flush_with(&channel_key, &TakeFlush));
+        writable.write(&master_key.get_data_key(), |data| data.push(1));
+        assert!(channel_pointer.flush_with(&master_key.get_channel_key(), &TakeFlush));
+        assert_eq!(read_only.get(&master_key.get_data_key()), &[0, 1]);
+        assert!(writable.get(&master_key.get_data_key()).is_empty());
+        assert_eq!(
+            channel_pointer.stats(&master_key.get_channel_key()),
+            FlushStats {
+                flushes: 2,
+                skipped: 1,
+                generation: 2,
+            }
+        );
+        channel_pointer.destroy(read_only, writable);
+
+        let mut storage = BidirectedStorage::new(0u8, 0, 0u16, 0);
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            ScopedBidirectedChannel::create(&mut storage);
+        channel_pointer.flush(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(
+            data_pointer1.exchange_replace(&data_key, |input| (u16::from(*input) + 1, ())),
+            (0, ())
+        );
+        assert_eq!(
+            data_pointer2.try_exchange(&data_key, |_| Err::<(u8, ()), _>(())),
+            Err(())
+        );
+        let channel_key = data_key.into_channel_key();
+        assert_eq!(channel_pointer.is_dirty(&channel_key), (false, true));
+        channel_pointer.flush(&channel_key);
+        assert_eq!(channel_pointer.stats(&channel_key).forward.skipped, 1);
+        assert_eq!(channel_pointer.stats(&channel_key).backward.flushes, 2);
+        assert_eq!(
+            data_pointer2.read_input(&master_key.get_data_key(), |input| *input),
+            1
+        );
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    #[cfg(feature = "checked-backend")]
+    #[should_panic(expected = "a data pointer was used after its channel was destroyed")]
+    fn use_after_destroy_panics() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut storage = UndirectedStorage::new(0, 1);
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            ScopedUndirectedChannel::create(&mut storage);
+        let copy = crate::scoped::ScopedUndirectedDataPointer {
+            data: data_pointer1.data,
+            checked: data_pointer1.checked,
+            scope: core::marker::PhantomData,
+        };
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+        copy.get(&master_key.get_data_key());
+    }
+
+    #[test]
+    #[should_panic]
+    fn destroy_rejects_foreign_pointers() {
+        let mut storage1 = UndirectedStorage::new(0, 1);
+        let mut storage2 = UndirectedStorage::new(0, 1);
+        let (channel_pointer, data_pointer1, _) = ScopedUndirectedChannel::create(&mut storage1);
+        let (_, _, data_pointer2) = ScopedUndirectedChannel::create(&mut storage2);
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn static_channels() {
+        static_channel!(static UNDIRECTED: undirected<u32> = (0, 1));
+        static_channel!(static DIRECTED: directed<Option<&'static str>> = (None, None));
+        static_channel!(
+            /// A link.
+            static BIDIRECTED: bidirected<u8, u16> = (0, 0, 0, 0)
+        );
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+
+        assert!(!UNDIRECTED.is_taken());
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) = UNDIRECTED.take();
+        assert!(UNDIRECTED.try_take().is_none());
+        *data_pointer1.get_mut(&master_key.get_data_key()) = 2;
+        channel_pointer.swap(&master_key.get_channel_key());
+        assert_eq!(*data_pointer2.get(&master_key.get_data_key()), 2);
+
+        let (mut channel_pointer, read_only, mut writable) = DIRECTED.take();
+        *writable.get_mut(&master_key.get_data_key()) = Some("boot");
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*read_only.get(&master_key.get_data_key()), Some("boot"));
+
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) = BIDIRECTED.take();
+        *data_pointer1.get_output(&master_key.get_data_key()) = 300;
+        channel_pointer.flush(&master_key.get_channel_key());
+        assert_eq!(*data_pointer2.get_input(&master_key.get_data_key()), 300);
+    }
+
+    #[test]
+    #[should_panic]
+    fn static_channels_are_taken_once() {
+        static_channel!(static CHANNEL: directed<u32> = (0, 0));
+        let _pointers = CHANNEL.take();
+        let _ = CHANNEL.take();
+    }
+}
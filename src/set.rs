@@ -0,0 +1,644 @@
+//! A named set of channels of mixed kinds, built in one go.
+//! The coordinator advances all channels of the set together, while the data pointers are looked up by channel name and moved to the worker threads.
+//!
+//! If the channels are known at compile time, [channel_set](crate::channel_set) declares a struct with a typed field per channel instead.
+
+use core::any::{self, TypeId};
+use std::{any::Any, collections::HashMap};
+
+use crate::{
+    bidirected::{BidirectedChannel, BidirectedChannelPointer, BidirectedDataPointer},
+    directed::{DirectedChannel, DirectedChannelPointer, ReadOnlyDataPointer, WritableDataPointer},
+    group::{ChannelGroup, GroupId},
+    undirected::{UndirectedChannel, UndirectedChannelPointer, UndirectedDataPointer},
+    ChannelKey,
+};
+
+/// A builder for a [ChannelSet].
+/// Each channel is created from the default values of its `Data` types, and labelled with its name.
+#[derive(Default)]
+#[must_use]
+pub struct ChannelSetBuilder {
+    names: Vec<String>,
+    #[allow(clippy::type_complexity)]
+    pending: Vec<Box<dyn FnOnce(&mut ChannelSet, &ChannelKey)>>,
+}
+
+/// A set of named channels of mixed kinds.
+/// It holds all channel pointers, which can be advanced together, and the data pointers until they are taken out.
+///
+/// This type should always be destroyed via the [ChannelSet::destroy_all] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct ChannelSet {
+    group: ChannelGroup,
+    members: HashMap<String, Member>,
+}
+
+/// The type-erased data pointers of a channel in a [ChannelSet].
+struct Member {
+    id: GroupId,
+    /// The types of the data pointers, which stay known while the data pointers are taken out.
+    types: [TypeId; 2],
+    pointers: [Option<Box<dyn Any + Send>>; 2],
+    destroy: Destroy,
+}
+
+type Destroy =
+    fn(&mut ChannelGroup, &ChannelKey, GroupId, [Box<dyn Any + Send>; 2]) -> Box<dyn Any + Send>;
+
+impl core::fmt::Debug for ChannelSetBuilder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ChannelSetBuilder")
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+impl core::fmt::Debug for Member {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Member")
+            .field("id", &self.id)
+            .field(
+                "taken",
+                &[self.pointers[0].is_none(), self.pointers[1].is_none()],
+            )
+            .finish()
+    }
+}
+
+impl ChannelSetBuilder {
+    /// Create a builder for an empty channel set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an [undirected channel](crate::undirected) with the given name.
+    /// Its data pointers can be taken with [ChannelSet::take_undirected].
+    ///
+    /// **Panics** if the name is already used.
+    pub fn undirected<Data: Default + Send + 'static>(mut self, name: impl Into<String>) -> Self {
+        let name = self.add_name(name);
+        self.pending.push(Box::new(move |set, channel_key| {
+            let (channel_pointer, data_pointer1, data_pointer2) =
+                UndirectedChannel::<Data>::create(Default::default(), Default::default());
+            let channel_pointer = channel_pointer.with_label(name.clone());
+            let id = set
+                .group
+                .push_undirected(channel_key, name.clone(), channel_pointer);
+            set.insert(
+                name,
+                id,
+                data_pointer1,
+                data_pointer2,
+                destroy_undirected::<Data>,
+            );
+        }));
+        self
+    }
+
+    /// Add a [directed channel](crate::directed) with the given name.
+    /// Its data pointers can be taken with [ChannelSet::take_directed_reader] and [ChannelSet::take_directed_writer].
+    ///
+    /// **Panics** if the name is already used.
+    pub fn directed<Data: Default + Clone + Send + 'static>(
+        mut self,
+        name: impl Into<String>,
+    ) -> Self {
+        let name = self.add_name(name);
+        self.pending.push(Box::new(move |set, channel_key| {
+            let (channel_pointer, read_only, writable) =
+                DirectedChannel::<Data>::create(Default::default(), Default::default());
+            let channel_pointer = channel_pointer.with_label(name.clone());
+            let id = set
+                .group
+                .push_directed(channel_key, name.clone(), channel_pointer);
+            set.insert(name, id, read_only, writable, destroy_directed::<Data>);
+        }));
+        self
+    }
+
+    /// Add a [bidirected channel](crate::bidirected) with the given name.
+    /// Its data pointers can be taken with [ChannelSet::take_bidirected1] and [ChannelSet::take_bidirected2].
+    ///
+    /// **Panics** if the name is already used.
+    pub fn bidirected<
+        Data1: Default + Clone + Send + 'static,
+        Data2: Default + Clone + Send + 'static,
+    >(
+        mut self,
+        name: impl Into<String>,
+    ) -> Self {
+        let name = self.add_name(name);
+        self.pending.push(Box::new(move |set, channel_key| {
+            let (channel_pointer, data_pointer1, data_pointer2) =
+                BidirectedChannel::<Data1, Data2>::create(
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                );
+            let channel_pointer = channel_pointer.with_label(name.clone());
+            let id = set
+                .group
+                .push_bidirected(channel_key, name.clone(), channel_pointer);
+            set.insert(
+                name,
+                id,
+                data_pointer1,
+                data_pointer2,
+                destroy_bidirected::<Data1, Data2>,
+            );
+        }));
+        self
+    }
+
+    fn add_name(&mut self, name: impl Into<String>) -> String {
+        let name = name.into();
+        assert!(
+            !self.names.contains(&name),
+            "channel name {:?} is already used",
+            name
+        );
+        self.names.push(name.clone());
+        name
+    }
+
+    /// Create all channels of the set.
+    pub fn build(self, channel_key: &ChannelKey) -> ChannelSet {
+        let mut set = ChannelSet {
+            group: ChannelGroup::new(),
+            members: HashMap::new(),
+        };
+        for pending in self.pending {
+            pending(&mut set, channel_key);
+        }
+        set
+    }
+}
+
+impl ChannelSet {
+    fn insert<Pointer1: Send + 'static, Pointer2: Send + 'static>(
+        &mut self,
+        name: String,
+        id: GroupId,
+        pointer1: Pointer1,
+        pointer2: Pointer2,
+        destroy: Destroy,
+    ) {
+        self.members.insert(
+            name,
+            Member {
+                id,
+                types: [TypeId::of::<Pointer1>(), TypeId::of::<Pointer2>()],
+                pointers: [Some(Box::new(pointer1)), Some(Box::new(pointer2))],
+                destroy,
+            },
+        );
+    }
+
+    /// Swap or flush every channel in the set, in the order in which they were added to the builder.
+    pub fn advance_all(&mut self, channel_key: &ChannelKey) {
+        self.group.advance_all(channel_key);
+    }
+
+    /// Like [ChannelSet::advance_all], but see [ChannelGroup::par_advance_all].
+    #[cfg(feature = "rayon")]
+    pub fn par_advance_all(&mut self, channel_key: &ChannelKey) {
+        self.group.par_advance_all(channel_key);
+    }
+
+    /// The number of channels in the set.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if the set has no channels.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Take one of the two data pointers of the undirected channel with the given name.
+    ///
+    /// **Panics** if there is no such channel with `Data` of type `Data`, or if both of its data pointers were already taken.
+    pub fn take_undirected<Data: 'static>(&mut self, name: &str) -> UndirectedDataPointer<Data> {
+        self.take(name, &[0, 1])
+    }
+
+    /// Take the read-only data pointer of the directed channel with the given name.
+    ///
+    /// **Panics** if there is no such channel with `Data` of type `Data`, or if the data pointer was already taken.
+    pub fn take_directed_reader<Data: 'static>(&mut self, name: &str) -> ReadOnlyDataPointer<Data> {
+        self.take(name, &[0])
+    }
+
+    /// Take the writable data pointer of the directed channel with the given name.
+    ///
+    /// **Panics** if there is no such channel with `Data` of type `Data`, or if the data pointer was already taken.
+    pub fn take_directed_writer<Data: 'static>(&mut self, name: &str) -> WritableDataPointer<Data> {
+        self.take(name, &[1])
+    }
+
+    /// Take the first data pointer of the bidirected channel with the given name.
+    ///
+    /// **Panics** if there is no such channel with `Data` of types `Data1` and `Data2`, or if the data pointer was already taken.
+    pub fn take_bidirected1<Data1: 'static, Data2: 'static>(
+        &mut self,
+        name: &str,
+    ) -> BidirectedDataPointer<Data1, Data2> {
+        self.take(name, &[0])
+    }
+
+    /// Take the second data pointer of the bidirected channel with the given name.
+    ///
+    /// **Panics** if there is no such channel with `Data` of types `Data1` and `Data2`, or if the data pointer was already taken.
+    pub fn take_bidirected2<Data1: 'static, Data2: 'static>(
+        &mut self,
+        name: &str,
+    ) -> BidirectedDataPointer<Data2, Data1> {
+        self.take(name, &[1])
+    }
+
+    /// Put a data pointer taken from this set back, which is required before [ChannelSet::destroy_all].
+    ///
+    /// **Panics** if the channel with the given name has no taken data pointer of type `Pointer`.
+    pub fn put_back<Pointer: Send + 'static>(&mut self, name: &str, pointer: Pointer) {
+        let member = self.member(name);
+        let role = (0..2)
+            .find(|&role| {
+                member.types[role] == TypeId::of::<Pointer>() && member.pointers[role].is_none()
+            })
+            .unwrap_or_else(|| {
+                panic!(
+                    "channel {:?} has no taken data pointer of type {}",
+                    name,
+                    any::type_name::<Pointer>()
+                )
+            });
+        member.pointers[role] = Some(Box::new(pointer));
+    }
+
+    fn member(&mut self, name: &str) -> &mut Member {
+        self.members
+            .get_mut(name)
+            .unwrap_or_else(|| panic!("there is no channel named {:?}", name))
+    }
+
+    fn take<Pointer: 'static>(&mut self, name: &str, roles: &[usize]) -> Pointer {
+        let member = self.member(name);
+        let mut roles = roles
+            .iter()
+            .copied()
+            .filter(|&role| member.types[role] == TypeId::of::<Pointer>())
+            .peekable();
+        assert!(
+            roles.peek().is_some(),
+            "channel {:?} has no data pointer of type {}",
+            name,
+            any::type_name::<Pointer>()
+        );
+        let pointer = roles
+            .find_map(|role| member.pointers[role].take())
+            .unwrap_or_else(|| {
+                panic!(
+                    "data pointer of type {} of channel {:?} was already taken",
+                    any::type_name::<Pointer>(),
+                    name
+                )
+            });
+        *pointer.downcast().unwrap()
+    }
+
+    /// Destroys all channels of the set.
+    /// Returns the `Data` fields of each channel by name, as returned by the destroy method of its kind of channel,
+    /// i.e. `(Data, Data)` for undirected and directed channels, and `(Data1, Data1, Data2, Data2)` for bidirected channels.
+    ///
+    /// **Panics** if a data pointer was taken and not put back (see [ChannelSet::put_back]).
+    pub fn destroy_all(mut self, channel_key: &ChannelKey) -> HashMap<String, Box<dyn Any + Send>> {
+        if let Some(name) = self
+            .members
+            .iter()
+            .find_map(|(name, member)| member.pointers.iter().any(Option::is_none).then(|| name))
+        {
+            panic!("a data pointer of channel {:?} was not put back", name);
+        }
+
+        self.members
+            .into_iter()
+            .map(|(name, member)| {
+                let [pointer1, pointer2] = member.pointers;
+                let data = (member.destroy)(
+                    &mut self.group,
+                    channel_key,
+                    member.id,
+                    [pointer1.unwrap(), pointer2.unwrap()],
+                );
+                (name, data)
+            })
+            .collect()
+    }
+}
+
+fn destroy_undirected<Data: Send + 'static>(
+    group: &mut ChannelGroup,
+    channel_key: &ChannelKey,
+    id: GroupId,
+    [pointer1, pointer2]: [Box<dyn Any + Send>; 2],
+) -> Box<dyn Any + Send> {
+    let channel_pointer: UndirectedChannelPointer<Data> = group.remove(channel_key, id).unwrap();
+    Box::new(channel_pointer.destroy(*pointer1.downcast().unwrap(), *pointer2.downcast().unwrap()))
+}
+
+fn destroy_directed<Data: Send + 'static>(
+    group: &mut ChannelGroup,
+    channel_key: &ChannelKey,
+    id: GroupId,
+    [read_only, writable]: [Box<dyn Any + Send>; 2],
+) -> Box<dyn Any + Send> {
+    let channel_pointer: DirectedChannelPointer<Data> = group.remove(channel_key, id).unwrap();
+    Box::new(channel_pointer.destroy_single(
+        *read_only.downcast().unwrap(),
+        *writable.downcast().unwrap(),
+    ))
+}
+
+fn destroy_bidirected<Data1: Send + 'static, Data2: Send + 'static>(
+    group: &mut ChannelGroup,
+    channel_key: &ChannelKey,
+    id: GroupId,
+    mut pointers: [Box<dyn Any + Send>; 2],
+) -> Box<dyn Any + Send> {
+    let channel_pointer: BidirectedChannelPointer<Data1, Data2> =
+        group.remove(channel_key, id).unwrap();
+    // If both data pointers have the same type, they may have been put back in either order.
+    if let Some(data_pointer) = pointers[1].downcast_ref::<BidirectedDataPointer<Data1, Data2>>() {
+        if channel_pointer.is_first_data_pointer(data_pointer) {
+            pointers.swap(0, 1);
+        }
+    }
+    let [pointer1, pointer2] = pointers;
+    Box::new(
+        channel_pointer.destroy(
+            *pointer1
+                .downcast::<BidirectedDataPointer<Data1, Data2>>()
+                .unwrap(),
+            *pointer2
+                .downcast::<BidirectedDataPointer<Data2, Data1>>()
+                .unwrap(),
+        ),
+    )
+}
+
+/// Declare a struct holding a fixed set of named channels with their concrete types, as a statically typed counterpart of a [ChannelSet].
+/// Each field is declared as `name: directed<Data>`, `name: undirected<Data>` or `name: bidirected<Data1, Data2>`,
+/// and the second struct after `with` receives the data pointers of the channels.
+///
+/// The channel pointers are public fields of the first struct, such that each channel can be accessed without any lookup or dynamic dispatch.
+/// The first struct gets the following methods:
+///
+/// * `create`, which takes a tuple with the initial `Data` per channel, in the order of the respective `create` function,
+///   and returns the struct along with the second struct holding the data pointers of each channel as tuple, in the order of the respective `create` function.
+/// * `advance_all`, which advances all channels in declaration order via [SwapChannel::advance](crate::SwapChannel::advance).
+///   Hence the `Data` of directed and bidirected channels must be [Clone].
+/// * `destroy`, which destroys all channels with the data pointers of the second struct, and returns a tuple of the results of the respective `destroy` functions.
+///   It **panics** if the data pointers do not point to the channels.
+///
+/// ```
+/// use two_phase_channel::{channel_set, MasterKey};
+///
+/// channel_set! {
+///     pub struct EngineChannels with EngineChannelEndpoints {
+///         frame: directed<Vec<u8>>,
+///         input: undirected<u32>,
+///         control: bidirected<u8, u16>,
+///     }
+/// }
+///
+/// let mut master_key = MasterKey::create();
+/// let (mut channels, mut endpoints) =
+///     EngineChannels::create((Vec::new(), Vec::new()), (0, 0), (0, 0, 0, 0));
+///
+/// let data_key = master_key.get_data_key();
+/// endpoints.frame.1.get_mut(&data_key).push(1);
+/// *endpoints.control.0.get_output(&data_key) = 2;
+///
+/// let channel_key = data_key.into_channel_key();
+/// channels.advance_all(&channel_key);
+/// // The channels can also be accessed individually.
+/// channels.frame.flush(&channel_key);
+///
+/// let data_key = channel_key.into_data_key();
+/// assert_eq!(*endpoints.frame.0.get(&data_key), [1]);
+/// assert_eq!(*endpoints.control.1.get_input(&data_key), 2);
+/// let (frame, input, control) = channels.destroy(endpoints);
+/// assert_eq!(frame, (vec![1], vec![1]));
+/// ```
+#[macro_export]
+macro_rules! channel_set {
+    (
+        $(#[$attribute:meta])*
+        $visibility:vis struct $name:ident with $endpoints:ident {
+            $($field:ident: $kind:ident<$($data:ty),+>),+ $(,)?
+        }
+    ) => {
+        $(#[$attribute])*
+        $visibility struct $name {
+            $(pub $field: $crate::channel_set!(@channel $kind<$($data),+>),)+
+        }
+
+        /// The data pointers of the channels of a
+        #[doc = concat!("[", stringify!($name), "].")]
+        $visibility struct $endpoints {
+            $(pub $field: $crate::channel_set!(@endpoints $kind<$($data),+>),)+
+        }
+
+        impl $name {
+            /// Create all channels from the given initial `Data`, and return them along with their data pointers.
+            $visibility fn create(
+                $($field: $crate::channel_set!(@initial $kind<$($data),+>),)+
+            ) -> (Self, $endpoints) {
+                $(let $field = $crate::channel_set!(@create $kind $field);)+
+                (
+                    Self { $($field: $field.0,)+ },
+                    $endpoints { $($field: $field.1,)+ },
+                )
+            }
+
+            /// Advance all channels in declaration order.
+            $visibility fn advance_all(&mut self, channel_key: &$crate::ChannelKey) {
+                $($crate::SwapChannel::advance(&mut self.$field, channel_key);)+
+            }
+
+            /// Destroy all channels, and return the results of destroying each channel.
+            ///
+            /// **Panics** if the data pointers do not point to these channels.
+            $visibility fn destroy(
+                self,
+                endpoints: $endpoints,
+            ) -> ($($crate::channel_set!(@initial $kind<$($data),+>),)+) {
+                ($($crate::channel_set!(@destroy $kind self.$field, endpoints.$field),)+)
+            }
+        }
+    };
+
+    (@channel directed<$data:ty>) => { $crate::directed::DirectedChannelPointer<$data> };
+    (@channel undirected<$data:ty>) => { $crate::undirected::UndirectedChannelPointer<$data> };
+    (@channel bidirected<$data1:ty, $data2:ty>) => { $crate::bidirected::BidirectedChannelPointer<$data1, $data2> };
+
+    (@endpoints directed<$data:ty>) => {
+        ($crate::directed::ReadOnlyDataPointer<$data>, $crate::directed::WritableDataPointer<$data>)
+    };
+    (@endpoints undirected<$data:ty>) => {
+        ($crate::undirected::UndirectedDataPointer<$data>, $crate::undirected::UndirectedDataPointer<$data>)
+    };
+    (@endpoints bidirected<$data1:ty, $data2:ty>) => {
+        ($crate::bidirected::BidirectedDataPointer<$data1, $data2>, $crate::bidirected::BidirectedDataPointer<$data2, $data1>)
+    };
+
+    (@initial directed<$data:ty>) => { ($data, $data) };
+    (@initial undirected<$data:ty>) => { ($data, $data) };
+    (@initial bidirected<$data1:ty, $data2:ty>) => { ($data1, $data1, $data2, $data2) };
+
+    (@create directed $initial:ident) => {{
+        let (channel_pointer, read_only, writable) = $crate::directed::DirectedChannel::create($initial.0, $initial.1);
+        (channel_pointer, (read_only, writable))
+    }};
+    (@create undirected $initial:ident) => {{
+        let (channel_pointer, data_pointer1, data_pointer2) = $crate::undirected::UndirectedChannel::create($initial.0, $initial.1);
+        (channel_pointer, (data_pointer1, data_pointer2))
+    }};
+    (@create bidirected $initial:ident) => {{
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            $crate::bidirected::BidirectedChannel::create($initial.0, $initial.1, $initial.2, $initial.3);
+        (channel_pointer, (data_pointer1, data_pointer2))
+    }};
+
+    (@destroy directed $channel_pointer:expr, $data_pointers:expr) => {{
+        let (read_only, writable) = $data_pointers;
+        $channel_pointer.destroy_single(read_only, writable)
+    }};
+    (@destroy undirected $channel_pointer:expr, $data_pointers:expr) => {{
+        let (data_pointer1, data_pointer2) = $data_pointers;
+        $channel_pointer.destroy(data_pointer1, data_pointer2)
+    }};
+    (@destroy bidirected $channel_pointer:expr, $data_pointers:expr) => {{
+        let (data_pointer1, data_pointer2) = $data_pointers;
+        $channel_pointer.destroy(data_pointer1, data_pointer2)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{set::ChannelSetBuilder, MasterKey};
+
+    // The field names clash with the local variables of the macro, which must not be confused due to hygiene.
+    crate::channel_set! {
+        struct Channels with Endpoints {
+            channel_pointer: directed<u32>,
+            read_only: undirected<Vec<u8>>,
+            data_pointer1: bidirected<u8, u16>,
+            channel_key: directed<()>,
+        }
+    }
+
+    #[test]
+    fn channel_set_macro() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channels, mut endpoints) =
+            Channels::create((0, 0), (vec![1], vec![2]), (0, 0, 0, 0), ((), ()));
+
+        let data_key = master_key.get_data_key();
+        *endpoints.channel_pointer.1.get_mut(&data_key) = 7;
+        endpoints.read_only.0.get_mut(&data_key).push(3);
+        *endpoints.data_pointer1.1.get_output(&data_key) = 4;
+        channels.advance_all(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*endpoints.channel_pointer.0.get(&data_key), 7);
+        assert_eq!(*endpoints.read_only.1.get(&data_key), [1, 3]);
+        assert_eq!(*endpoints.data_pointer1.0.get_input(&data_key), 4);
+
+        channels.read_only.swap(&data_key.into_channel_key());
+        assert_eq!(
+            channels.destroy(endpoints),
+            ((7, 7), (vec![1, 3], vec![2]), (4, 4, 0, 0), ((), ()))
+        );
+    }
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut set = ChannelSetBuilder::new()
+            .directed::<u32>("frame")
+            .undirected::<Vec<u8>>("input")
+            .bidirected::<u8, u8>("control")
+            .build(&master_key.get_channel_key());
+        assert_eq!(set.len(), 3);
+
+        let mut writer = set.take_directed_writer::<u32>("frame");
+        let reader = set.take_directed_reader::<u32>("frame");
+        let mut input = set.take_undirected::<Vec<u8>>("input");
+        let other_input = set.take_undirected::<Vec<u8>>("input");
+        let mut control1 = set.take_bidirected1::<u8, u8>("control");
+        let mut control2 = set.take_bidirected2::<u8, u8>("control");
+
+        let data_key = master_key.get_data_key();
+        let worker = thread::spawn(move || {
+            let mut master_key = unsafe { MasterKey::create_unlimited() };
+            let data_key = master_key.get_data_key();
+            *writer.get_mut(&data_key) = 7;
+            *control2.get_output(&data_key) = 2;
+            (writer, control2)
+        });
+        input.get_mut(&data_key).push(1);
+        *control1.get_output(&data_key) = 1;
+        let (writer, control2) = worker.join().unwrap();
+        set.advance_all(&data_key.into_channel_key());
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*reader.get(&data_key), 7);
+        assert_eq!(*other_input.get(&data_key), [1]);
+        assert_eq!(*control1.get_input(&data_key), 2);
+        assert_eq!(*control2.get_input(&data_key), 1);
+
+        // Data pointers can be put back in any order.
+        set.put_back("control", control2);
+        set.put_back("control", control1);
+        set.put_back("frame", reader);
+        set.put_back("frame", writer);
+        set.put_back("input", input);
+        set.put_back("input", other_input);
+        let mut data = set.destroy_all(&master_key.get_channel_key());
+        assert_eq!(
+            *data
+                .remove("frame")
+                .unwrap()
+                .downcast::<(u32, u32)>()
+                .unwrap(),
+            (7, 7)
+        );
+        assert_eq!(
+            *data
+                .remove("control")
+                .unwrap()
+                .downcast::<(u8, u8, u8, u8)>()
+                .unwrap(),
+            (2, 2, 1, 1)
+        );
+        assert!(data.remove("input").is_some());
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "has no data pointer of type")]
+    fn take_rejects_wrong_type() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut set = ChannelSetBuilder::new()
+            .directed::<u32>("frame")
+            .build(&master_key.get_channel_key());
+
+        let _reader = set.take_directed_reader::<u64>("frame");
+    }
+}
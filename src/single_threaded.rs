@@ -0,0 +1,18 @@
+//! The bookkeeping of the master key for the `portable` feature on `wasm32` targets without the `atomics` target feature.
+//! Such targets have no threads, hence the master key flag does not need to be atomic.
+
+use core::{cell::Cell, sync::atomic::Ordering};
+
+pub(crate) static MASTER_KEY_EXISTS: SingleThreadedFlag = SingleThreadedFlag(Cell::new(false));
+
+/// A flag with the interface of an [AtomicBool](core::sync::atomic::AtomicBool) that is only sound if there is a single thread.
+pub(crate) struct SingleThreadedFlag(Cell<bool>);
+
+impl SingleThreadedFlag {
+    pub(crate) fn swap(&self, value: bool, #[allow(unused)] ordering: Ordering) -> bool {
+        self.0.replace(value)
+    }
+}
+
+// The flag is never accessed concurrently, since there is only a single thread.
+unsafe impl Sync for SingleThreadedFlag {}
@@ -0,0 +1,329 @@
+//! A [Sink] that feeds values from an async task into a channel, e.g. for an async network layer producing inputs.
+//! The async task sends values to a [ChannelSink], and the coordinator moves them into the channel via a [SinkDrain] in each channel phase.
+//! No specific async runtime is required.
+//!
+//! The sink buffers at most a fixed number of values.
+//! Once the buffer is full, [Sink::poll_ready] returns [Poll::Pending] until the next channel phase drains the buffer,
+//! such that a producer that is faster than the phases is slowed down to the rate of the phases times the capacity of the buffer.
+//! Likewise, [Sink::poll_flush] completes once all sent values were drained into the channel.
+
+use core::fmt;
+use std::{
+    collections::VecDeque,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures_sink::Sink;
+
+use crate::{directed::DirectedChannelPointer, queue::QueueChannelPointer, ChannelKey};
+
+/// The channel a [SinkDrain] moves the values of its [ChannelSink] into.
+#[derive(Debug)]
+pub enum SinkTarget<Data> {
+    /// Replace the writable `Data` of a directed channel with the latest drained value, discarding the earlier values of the same phase.
+    Latest(DirectedChannelPointer<Data>),
+    /// Deliver all drained values into the inbox of a queue channel, in order.
+    Queued(QueueChannelPointer<Data>),
+}
+
+/// The sending half of a sink channel, held by an async task.
+/// Dropping or closing it marks the sink as closed, see [SinkDrain::is_closed].
+#[derive(Debug)]
+pub struct ChannelSink<Data> {
+    shared: Arc<Mutex<Shared<Data>>>,
+}
+
+/// The receiving half of a sink channel, held by the coordinator.
+/// Dropping it makes the [ChannelSink] return [SinkError::Disconnected].
+#[derive(Debug)]
+pub struct SinkDrain<Data> {
+    target: SinkTarget<Data>,
+    receiver: Receiver<Data>,
+}
+
+/// The reason why a [ChannelSink] did not accept a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SinkError {
+    /// The buffer is full.
+    /// This only happens if [Sink::start_send] is called without [Sink::poll_ready] returning [Poll::Ready] before.
+    Full,
+    /// The [SinkDrain] was dropped.
+    Disconnected,
+}
+
+/// Disconnects the sink when the drain is dropped.
+#[derive(Debug)]
+struct Receiver<Data> {
+    shared: Arc<Mutex<Shared<Data>>>,
+}
+
+#[derive(Debug)]
+struct Shared<Data> {
+    buffer: VecDeque<Data>,
+    capacity: usize,
+    /// The waker of the task that last waited for space in or the draining of the buffer.
+    waker: Option<Waker>,
+    closed: bool,
+    disconnected: bool,
+}
+
+impl<Data> ChannelSink<Data> {
+    /// Create a sink that buffers at most `capacity` values, and the drain that moves them into the given channel.
+    ///
+    /// **Panics** if the capacity is zero.
+    pub fn create(
+        target: SinkTarget<Data>,
+        capacity: usize,
+    ) -> (ChannelSink<Data>, SinkDrain<Data>) {
+        assert!(
+            capacity > 0,
+            "the capacity of a channel sink must not be zero"
+        );
+        let shared = Arc::new(Mutex::new(Shared {
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+            waker: None,
+            closed: false,
+            disconnected: false,
+        }));
+        (
+            ChannelSink {
+                shared: shared.clone(),
+            },
+            SinkDrain {
+                target,
+                receiver: Receiver { shared },
+            },
+        )
+    }
+
+    /// Return [Poll::Ready] if the given condition holds, and register the waker of the task otherwise.
+    fn poll_until(
+        &self,
+        cx: &mut Context<'_>,
+        condition: impl FnOnce(&Shared<Data>) -> bool,
+    ) -> Poll<Result<(), SinkError>> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.disconnected {
+            Poll::Ready(Err(SinkError::Disconnected))
+        } else if condition(&shared) {
+            Poll::Ready(Ok(()))
+        } else {
+            match &shared.waker {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                _ => shared.waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        }
+    }
+}
+
+impl<Data> Sink<Data> for ChannelSink<Data> {
+    type Error = SinkError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SinkError>> {
+        self.poll_until(cx, |shared| shared.buffer.len() < shared.capacity)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Data) -> Result<(), SinkError> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.disconnected {
+            Err(SinkError::Disconnected)
+        } else if shared.buffer.len() == shared.capacity {
+            Err(SinkError::Full)
+        } else {
+            shared.buffer.push_back(item);
+            Ok(())
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SinkError>> {
+        self.poll_until(cx, |shared| shared.buffer.is_empty())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SinkError>> {
+        self.shared.lock().unwrap().closed = true;
+        self.poll_flush(cx)
+    }
+}
+
+impl<Data> Drop for ChannelSink<Data> {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().closed = true;
+    }
+}
+
+impl<Data: Clone> SinkDrain<Data> {
+    /// Move all buffered values into the target channel, and flush it, such that the values are visible in the next data phase.
+    /// Wakes the sink if it is waiting for space in or the draining of the buffer.
+    /// Returns the number of drained values.
+    ///
+    /// With [SinkTarget::Latest], the writable `Data` is only replaced if at least one value was drained,
+    /// overwriting what the [WritableDataPointer](crate::directed::WritableDataPointer) wrote in the preceding data phase.
+    pub fn drain_into(&mut self, channel_key: &ChannelKey) -> usize {
+        let values = {
+            let mut shared = self.receiver.shared.lock().unwrap();
+            let capacity = shared.capacity;
+            let values = mem::replace(&mut shared.buffer, VecDeque::with_capacity(capacity));
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+            values
+        };
+        let drained = values.len();
+
+        match &mut self.target {
+            SinkTarget::Latest(channel_pointer) => {
+                if let Some(latest) = values.into_iter().last() {
+                    channel_pointer.replace_writable(channel_key, latest);
+                }
+                channel_pointer.flush(channel_key);
+            }
+            SinkTarget::Queued(channel_pointer) => {
+                channel_pointer.flush(channel_key);
+                channel_pointer.deliver(channel_key, values);
+            }
+        }
+        drained
+    }
+}
+
+impl<Data> SinkDrain<Data> {
+    /// Returns `true` if the [ChannelSink] was closed or dropped.
+    /// Values it sent before are still drained.
+    pub fn is_closed(&self) -> bool {
+        self.receiver.shared.lock().unwrap().closed
+    }
+
+    /// The number of values waiting to be drained.
+    pub fn len(&self) -> usize {
+        self.receiver.shared.lock().unwrap().buffer.len()
+    }
+
+    /// Returns `true` if no values are waiting to be drained.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The target channel.
+    pub fn target(&mut self) -> &mut SinkTarget<Data> {
+        &mut self.target
+    }
+
+    /// Return the target channel, e.g. for destruction, disconnecting the sink.
+    /// Values that were not drained are dropped.
+    pub fn into_target(self) -> SinkTarget<Data> {
+        self.target
+    }
+}
+
+impl<Data> Drop for Receiver<Data> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.disconnected = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::Full => write!(f, "the buffer of the channel sink is full"),
+            SinkError::Disconnected => write!(f, "the drain of the channel sink was dropped"),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use futures_sink::Sink;
+
+    use crate::{
+        directed::DirectedChannel,
+        queue::QueueChannel,
+        sink::{ChannelSink, SinkError, SinkTarget},
+        MasterKey,
+    };
+
+    /// Counts its wakes.
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, writable) = DirectedChannel::create(0, 0);
+        let (mut sink, mut drain) = ChannelSink::create(SinkTarget::Latest(channel_pointer), 2);
+        let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let cx = &mut Context::from_waker(&waker);
+        let mut sink = Pin::new(&mut sink);
+
+        for value in 1..=2 {
+            assert_eq!(sink.as_mut().poll_ready(cx), Poll::Ready(Ok(())));
+            sink.as_mut().start_send(value).unwrap();
+        }
+        // The buffer is full until the next channel phase.
+        assert_eq!(sink.as_mut().poll_ready(cx), Poll::Pending);
+        assert_eq!(sink.as_mut().start_send(3), Err(SinkError::Full));
+        assert_eq!(drain.len(), 2);
+
+        assert_eq!(drain.drain_into(&master_key.get_channel_key()), 2);
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+        assert_eq!(*read_only.get(&master_key.get_data_key()), 2);
+        assert_eq!(sink.as_mut().poll_ready(cx), Poll::Ready(Ok(())));
+        sink.as_mut().start_send(3).unwrap();
+        assert_eq!(sink.as_mut().poll_close(cx), Poll::Pending);
+        assert!(drain.is_closed());
+
+        drain.drain_into(&master_key.get_channel_key());
+        assert_eq!(sink.as_mut().poll_close(cx), Poll::Ready(Ok(())));
+        assert_eq!(*read_only.get(&master_key.get_data_key()), 3);
+        let _ = (writable, drain.into_target());
+        assert_eq!(
+            sink.as_mut().poll_ready(cx),
+            Poll::Ready(Err(SinkError::Disconnected))
+        );
+    }
+
+    #[test]
+    fn queued() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, mut sender, mut receiver) = QueueChannel::create();
+        let (mut sink, mut drain) = ChannelSink::create(SinkTarget::Queued(channel_pointer), 4);
+        let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+        let cx = &mut Context::from_waker(&waker);
+        let mut sink = Pin::new(&mut sink);
+
+        sender.push(&master_key.get_data_key(), 1);
+        sink.as_mut().start_send(2).unwrap();
+        sink.as_mut().start_send(3).unwrap();
+        assert_eq!(sink.as_mut().poll_flush(cx), Poll::Pending);
+        assert_eq!(drain.drain_into(&master_key.get_channel_key()), 2);
+        assert_eq!(sink.as_mut().poll_flush(cx), Poll::Ready(Ok(())));
+        let data_key = master_key.get_data_key();
+        assert!(receiver.drain(&data_key).eq([1, 2, 3]));
+        let _ = (drain.into_target(), sender, receiver);
+    }
+}
@@ -0,0 +1,237 @@
+//! An undirected two-phase channel over slices of equal length.
+//! Besides swapping the slices as a whole, single elements can be swapped between the slices.
+
+use core::marker::PhantomData;
+use std::mem;
+
+use crate::{
+    undirected::{UndirectedChannel, UndirectedChannelPointer, UndirectedDataPointer},
+    ChannelKey, DataKey,
+};
+
+/// An undirected channel over two slices of equal length, used for communication between threads.
+/// Compared to an [`UndirectedChannel`] of vectors, the elements of the slices can also be swapped individually,
+/// such that both slices converge instead of diverging entirely.
+///
+/// See [SliceSwapChannel::create] for more info.
+#[derive(Debug)]
+pub struct SliceSwapChannel<T> {
+    phantom: PhantomData<T>,
+}
+
+/// A pointer to a slice swap channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [SliceSwapChannel::destroy] or [SliceSwapChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct SliceSwapChannelPointer<T> {
+    channel_pointer: UndirectedChannelPointer<Box<[T]>>,
+}
+
+/// A pointer to one of the slices in a slice swap channel.
+/// It can only be accessed using a [DataKey].
+/// The length of the slice cannot be changed through this pointer.
+///
+/// This type should always be destroyed via the [SliceSwapChannel::destroy] or [SliceSwapChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct SliceDataPointer<T> {
+    data_pointer: UndirectedDataPointer<Box<[T]>>,
+}
+
+impl<T> SliceSwapChannel<T> {
+    /// Create a slice swap channel and hand out three pointers to it.
+    /// One [SliceSwapChannelPointer] used to swap the content of the two slices,
+    /// and two [SliceDataPointer]s, one to each slice.
+    ///
+    /// **Panics** if the given vectors have different lengths.
+    pub fn create(
+        data1: Vec<T>,
+        data2: Vec<T>,
+    ) -> (
+        SliceSwapChannelPointer<T>,
+        SliceDataPointer<T>,
+        SliceDataPointer<T>,
+    ) {
+        assert_eq!(data1.len(), data2.len());
+        let (channel_pointer, data_pointer1, data_pointer2) =
+            UndirectedChannel::create(data1.into_boxed_slice(), data2.into_boxed_slice());
+        (
+            SliceSwapChannelPointer { channel_pointer },
+            SliceDataPointer {
+                data_pointer: data_pointer1,
+            },
+            SliceDataPointer {
+                data_pointer: data_pointer2,
+            },
+        )
+    }
+
+    /// Destroys the slice swap channel linked with the three pointers (see [SliceSwapChannel::create]).
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: SliceSwapChannelPointer<T>,
+        data_pointer1: SliceDataPointer<T>,
+        data_pointer2: SliceDataPointer<T>,
+    ) -> (Vec<T>, Vec<T>) {
+        let (data1, data2) = UndirectedChannel::destroy(
+            channel_pointer.channel_pointer,
+            data_pointer1.data_pointer,
+            data_pointer2.data_pointer,
+        );
+        (data1.into_vec(), data2.into_vec())
+    }
+}
+
+impl<T> SliceSwapChannelPointer<T> {
+    /// Swap the two slices in the channel.
+    pub fn swap(&mut self, channel_key: &ChannelKey) {
+        self.channel_pointer.swap(channel_key);
+    }
+
+    /// Swap the elements of the two slices at all indices where the given mask is `true`.
+    ///
+    /// **Panics** if the mask does not have the same length as the slices.
+    pub fn swap_masked(&mut self, channel_key: &ChannelKey, mask: &[bool]) {
+        let channel: &mut UndirectedChannel<Box<[T]>> = &mut self.channel_pointer.channel;
+        assert_eq!(mask.len(), channel.data1.len());
+        channel.verify_checksum(channel_key);
+
+        for ((element1, element2), _) in channel
+            .data1
+            .iter_mut()
+            .zip(channel.data2.iter_mut())
+            .zip(mask)
+            .filter(|(_, &selected)| selected)
+        {
+            mem::swap(element1, element2);
+        }
+        channel.generation += 1;
+        channel.record_checksum(channel_key);
+    }
+
+    /// Swap the elements of the two slices at the given indices.
+    /// If an index is given multiple times, its elements are swapped multiple times.
+    ///
+    /// **Panics** if an index is out of range. In this case, no elements are swapped.
+    pub fn swap_indices(&mut self, channel_key: &ChannelKey, indices: &[usize]) {
+        let channel: &mut UndirectedChannel<Box<[T]>> = &mut self.channel_pointer.channel;
+        let len = channel.data1.len();
+        if let Some(index) = indices.iter().find(|&&index| index >= len) {
+            panic!(
+                "index {} is out of range for slices of length {}",
+                index, len
+            );
+        }
+
+        channel.verify_checksum(channel_key);
+        for &index in indices {
+            mem::swap(&mut channel.data1[index], &mut channel.data2[index]);
+        }
+        channel.generation += 1;
+        channel.record_checksum(channel_key);
+    }
+
+    /// The length of the slices in the channel.
+    pub fn len(&self) -> usize {
+        self.channel_pointer.channel.data1.len()
+    }
+
+    /// Returns `true` if the slices in the channel are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Shorthand for [SliceSwapChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: SliceDataPointer<T>,
+        data_pointer2: SliceDataPointer<T>,
+    ) -> (Vec<T>, Vec<T>) {
+        SliceSwapChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+}
+
+impl<T> SliceDataPointer<T> {
+    /// Get a reference to the slice pointed to by this pointer.
+    pub fn get(&self, data_key: &DataKey) -> &[T] {
+        self.data_pointer.get(data_key)
+    }
+
+    /// Get a mutable reference to the slice pointed to by this pointer.
+    pub fn get_mut(&mut self, data_key: &DataKey) -> &mut [T] {
+        self.data_pointer.get_mut(data_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{slice::SliceSwapChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            SliceSwapChannel::create(vec![0; 4], vec![1; 4]);
+
+        data_pointer1.get_mut(&master_key.get_data_key())[2] = 2;
+        channel_pointer.swap_masked(&master_key.get_channel_key(), &[true, false, true, false]);
+        channel_pointer.swap_indices(&master_key.get_channel_key(), &[3]);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.get(&data_key), [1, 0, 1, 1]);
+        assert_eq!(data_pointer2.get(&data_key), [0, 1, 2, 0]);
+
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (vec![1, 0, 1, 1], vec![0, 1, 2, 0])
+        );
+    }
+
+    #[test]
+    fn swap_masked_twice_restores() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut random = 7u64;
+        let mut next_random = || {
+            random = random
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            random >> 33
+        };
+
+        for len in 0..20 {
+            let data1: Vec<_> = (0..len).collect();
+            let data2: Vec<_> = (len..2 * len).collect();
+            let (mut channel_pointer, data_pointer1, data_pointer2) =
+                SliceSwapChannel::create(data1.clone(), data2.clone());
+
+            for _ in 0..10 {
+                let mask: Vec<_> = (0..len).map(|_| next_random() % 2 == 0).collect();
+                channel_pointer.swap_masked(&master_key.get_channel_key(), &mask);
+                channel_pointer.swap_masked(&master_key.get_channel_key(), &mask);
+
+                let data_key = master_key.get_data_key();
+                assert_eq!(data_pointer1.get(&data_key), data1);
+                assert_eq!(data_pointer2.get(&data_key), data2);
+            }
+
+            assert_eq!(
+                channel_pointer.destroy(data_pointer1, data_pointer2),
+                (data1, data2)
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_indices_rejects_out_of_range() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) =
+            SliceSwapChannel::create(vec![0; 4], vec![1; 4]);
+
+        channel_pointer.swap_indices(&master_key.get_channel_key(), &[1, 4]);
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+    }
+}
@@ -0,0 +1,86 @@
+//! A versioned local cache over a data pointer.
+//! The cached copy can be read without a key, and is only refreshed when the channel changed.
+
+use crate::{undirected::ImmutableUndirectedDataPointer, DataKey};
+
+/// A reader that keeps a local copy of the `Data` field pointed to by an [ImmutableUndirectedDataPointer].
+/// The copy is only updated by [SnapshotReader::refresh], and only if the generation of the channel advanced since the last refresh
+/// (see [ImmutableUndirectedDataPointer::generation]).
+/// Since the copy is owned by the reader, it can be accessed at any time without a key.
+#[derive(Debug)]
+pub struct SnapshotReader<Data> {
+    data_pointer: ImmutableUndirectedDataPointer<Data>,
+    snapshot: Data,
+    generation: u64,
+}
+
+impl<Data: Clone> SnapshotReader<Data> {
+    /// Create a snapshot reader over the given data pointer, initialised with a copy of the `Data` field it points to.
+    pub fn new(data_pointer: ImmutableUndirectedDataPointer<Data>, data_key: &DataKey) -> Self {
+        Self {
+            snapshot: data_pointer.get(data_key).clone(),
+            generation: data_pointer.generation(data_key),
+            data_pointer,
+        }
+    }
+
+    /// Update the local copy if the generation of the channel advanced since the last refresh.
+    /// Returns `true` if the local copy was updated.
+    pub fn refresh(&mut self, data_key: &DataKey) -> bool {
+        if self
+            .data_pointer
+            .changed_since(data_key, &mut self.generation)
+        {
+            self.snapshot.clone_from(self.data_pointer.get(data_key));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<Data> SnapshotReader<Data> {
+    /// Get a reference to the local copy.
+    pub fn get(&self) -> &Data {
+        &self.snapshot
+    }
+
+    /// The generation of the channel at the time the local copy was taken.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the wrapped data pointer, e.g. for destroying the channel, and the local copy.
+    pub fn into_inner(self) -> (ImmutableUndirectedDataPointer<Data>, Data) {
+        (self.data_pointer, self.snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{snapshot::SnapshotReader, undirected::UndirectedChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create(0, 1);
+        let mut reader =
+            SnapshotReader::new(data_pointer2.into_immutable(), &master_key.get_data_key());
+        assert_eq!(*reader.get(), 1);
+
+        *data_pointer1.get_mut(&master_key.get_data_key()) = 2;
+        assert!(!reader.refresh(&master_key.get_data_key()));
+        assert_eq!(*reader.get(), 1);
+
+        channel_pointer.swap(&master_key.get_channel_key());
+        assert!(reader.refresh(&master_key.get_data_key()));
+        assert!(!reader.refresh(&master_key.get_data_key()));
+        assert_eq!(*reader.get(), 2);
+        assert_eq!(reader.generation(), 1);
+
+        let (data_pointer2, snapshot) = reader.into_inner();
+        assert_eq!(snapshot, 2);
+        UndirectedChannel::destroy_immutable(channel_pointer, data_pointer1, [data_pointer2]);
+    }
+}
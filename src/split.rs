@@ -0,0 +1,218 @@
+//! An undirected two-phase channel where each `Data` field lives in its own heap allocation,
+//! which stays with the data pointer that refers to it.
+//!
+//! Compared to the contiguous [`UndirectedChannel`](crate::undirected::UndirectedChannel),
+//! the two `Data` fields do not share an allocation, so the allocator is free to place each of them close to the thread that allocates it,
+//! for example on its own NUMA node, and the fields never share a cache line.
+//! Swapping still exchanges the content of the fields, so each data pointer keeps accessing the same memory.
+//! This pays off for large payloads that are accessed much more often than they are swapped, especially on multi-socket machines.
+//! For small payloads, the additional allocation and pointer indirection are pure overhead,
+//! and the [`cache-padded`](crate::undirected) feature of the contiguous channel is the better choice against false sharing.
+//! Unlike the [boxed channel](crate::boxed), swapping copies the `Data` fields, so it is not O(1).
+
+use std::{any, mem};
+
+use crate::{
+    undirected::{SwapStats, UndirectedSwapChannel},
+    ChannelKey, DataKey, SwapChannel,
+};
+
+/// An undirected channel used for communication between threads, where each `Data` field lives in its own heap allocation.
+/// It behaves like an [`UndirectedChannel`](crate::undirected::UndirectedChannel).
+///
+/// See [SplitUndirectedChannel::create] for more info.
+#[derive(Debug)]
+pub struct SplitUndirectedChannel<Data> {
+    data1: Box<Data>,
+    data2: Box<Data>,
+    generation: u64,
+    stats: SwapStats,
+}
+
+/// A pointer to a split undirected channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [SplitUndirectedChannel::destroy] or [SplitUndirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct SplitUndirectedChannelPointer<Data> {
+    channel: Box<SplitUndirectedChannel<Data>>,
+}
+
+/// A pointer to one of the data fields in a split undirected channel.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [SplitUndirectedChannel::destroy] or [SplitUndirectedChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct SplitUndirectedDataPointer<Data> {
+    data: *mut Data,
+}
+
+impl<Data> SplitUndirectedChannel<Data> {
+    /// Create a split undirected channel and hand out three pointers to it.
+    /// One [SplitUndirectedChannelPointer] used to swap the content of the two `Data` fields,
+    /// and two [SplitUndirectedDataPointer]s, one to each data field.
+    ///
+    /// Each `Data` field is moved into its own allocation.
+    pub fn create(
+        data1: Data,
+        data2: Data,
+    ) -> (
+        SplitUndirectedChannelPointer<Data>,
+        SplitUndirectedDataPointer<Data>,
+        SplitUndirectedDataPointer<Data>,
+    ) {
+        let mut channel_pointer = SplitUndirectedChannelPointer {
+            channel: Box::new(SplitUndirectedChannel {
+                data1: Box::new(data1),
+                data2: Box::new(data2),
+                generation: 0,
+                stats: SwapStats::default(),
+            }),
+        };
+        let data_pointer1 = SplitUndirectedDataPointer {
+            data: (&mut *channel_pointer.channel.data1) as *mut Data,
+        };
+        let data_pointer2 = SplitUndirectedDataPointer {
+            data: (&mut *channel_pointer.channel.data2) as *mut Data,
+        };
+        (channel_pointer, data_pointer1, data_pointer2)
+    }
+
+    /// Destroys the split undirected channel linked with the three pointers (see [SplitUndirectedChannel::create]).
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: SplitUndirectedChannelPointer<Data>,
+        data_pointer1: SplitUndirectedDataPointer<Data>,
+        data_pointer2: SplitUndirectedDataPointer<Data>,
+    ) -> (Data, Data) {
+        let SplitUndirectedChannelPointer { mut channel } = channel_pointer;
+        let channel_data_pointer1 = (&mut *channel.data1) as *mut Data;
+        let channel_data_pointer2 = (&mut *channel.data2) as *mut Data;
+        let SplitUndirectedDataPointer {
+            data: data_pointer1,
+        } = data_pointer1;
+        let SplitUndirectedDataPointer {
+            data: data_pointer2,
+        } = data_pointer2;
+
+        assert!(
+            (channel_data_pointer1 == data_pointer1 && channel_data_pointer2 == data_pointer2)
+                || (channel_data_pointer1 == data_pointer2
+                    && channel_data_pointer2 == data_pointer1)
+        );
+
+        (*channel.data1, *channel.data2)
+    }
+}
+
+impl<Data> SplitUndirectedChannelPointer<Data> {
+    /// Swap the content of the two `Data` fields in the channel, keeping each of them in its allocation.
+    pub fn swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        let channel: &mut SplitUndirectedChannel<Data> = &mut self.channel;
+        mem::swap(&mut *channel.data1, &mut *channel.data2);
+        channel.generation += 1;
+        channel.stats.swaps += 1;
+        channel.stats.last_swap_generation = channel.generation;
+    }
+
+    /// The number of swaps performed on this channel.
+    pub fn swap_count(&self, #[allow(unused)] channel_key: &ChannelKey) -> u64 {
+        self.channel.stats.swaps
+    }
+
+    /// Statistics about the swaps performed on this channel.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> SwapStats {
+        self.channel.stats
+    }
+
+    /// Shorthand for [SplitUndirectedChannel::destroy].
+    pub fn destroy(
+        self,
+        data_pointer1: SplitUndirectedDataPointer<Data>,
+        data_pointer2: SplitUndirectedDataPointer<Data>,
+    ) -> (Data, Data) {
+        SplitUndirectedChannel::destroy(self, data_pointer1, data_pointer2)
+    }
+}
+
+impl<Data> SplitUndirectedDataPointer<Data> {
+    /// Get a reference to the `Data` field pointed to by this pointer.
+    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> &Data {
+        unsafe { &*self.data }
+    }
+
+    /// Get a mutable reference to the `Data` field pointed to by this pointer.
+    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> &mut Data {
+        unsafe { &mut *self.data }
+    }
+}
+
+unsafe impl<Data> Send for SplitUndirectedChannelPointer<Data> {}
+unsafe impl<Data> Send for SplitUndirectedDataPointer<Data> {}
+
+unsafe impl<Data> Sync for SplitUndirectedChannelPointer<Data> {}
+unsafe impl<Data> Sync for SplitUndirectedDataPointer<Data> {}
+
+impl<Data> SwapChannel for SplitUndirectedChannelPointer<Data> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        SplitUndirectedChannelPointer::swap(self, channel_key);
+    }
+
+    fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel.generation)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<Data>())
+    }
+}
+
+impl<Data> UndirectedSwapChannel for SplitUndirectedChannelPointer<Data> {
+    fn swap(&mut self, channel_key: &ChannelKey) {
+        SplitUndirectedChannelPointer::swap(self, channel_key);
+    }
+
+    fn swap_count(&self, channel_key: &ChannelKey) -> u64 {
+        SplitUndirectedChannelPointer::swap_count(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> SwapStats {
+        SplitUndirectedChannelPointer::stats(self, channel_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+
+    use crate::{undirected::UndirectedChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create_split(vec![0; 3], vec![1; 3]);
+        let address1 = data_pointer1.get(&master_key.get_data_key()) as *const Vec<_>;
+
+        for swaps in 1..4 {
+            let data_key = master_key.get_data_key();
+            data_pointer1.get_mut(&data_key)[0] = swaps;
+            channel_pointer.swap(&data_key.into_channel_key());
+        }
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointer1.get(&data_key), [2, 1, 1]);
+        assert_eq!(*data_pointer2.get(&data_key), [3, 0, 0]);
+        // Each data pointer keeps pointing to its allocation.
+        assert!(ptr::eq(data_pointer1.get(&data_key), address1));
+        assert_eq!(channel_pointer.swap_count(&data_key.into_channel_key()), 3);
+
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (vec![2, 1, 1], vec![3, 0, 0])
+        );
+    }
+}
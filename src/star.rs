@@ -0,0 +1,262 @@
+//! A star topology of [bidirected channels](crate::bidirected).
+//! One hub is connected to any number of spokes, each via its own bidirected channel.
+
+use core::marker::PhantomData;
+
+use crate::{
+    bidirected::{BidirectedChannel, BidirectedChannelPointer, BidirectedDataPointer},
+    ChannelKey, DataKey,
+};
+
+/// A star of bidirected channels used for communication between a hub thread and any number of spoke threads.
+/// The hub sends `HubToSpoke` to each spoke, and each spoke sends `SpokeToHub` to the hub.
+///
+/// See [StarChannel::create] for more info.
+#[derive(Debug)]
+pub struct StarChannel<HubToSpoke, SpokeToHub> {
+    phantom: PhantomData<(HubToSpoke, SpokeToHub)>,
+}
+
+/// A pointer to a star of bidirected channels, used to flush all of them at once.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [StarChannel::destroy] or [StarPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct StarPointer<HubToSpoke, SpokeToHub> {
+    channel_pointers: Vec<BidirectedChannelPointer<SpokeToHub, HubToSpoke>>,
+}
+
+/// The endpoints of the hub in a star of bidirected channels, one per spoke, addressable by the index of the spoke.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [StarChannel::destroy] or [StarPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct HubHandle<HubToSpoke, SpokeToHub> {
+    data_pointers: Vec<BidirectedDataPointer<SpokeToHub, HubToSpoke>>,
+}
+
+/// The endpoint of one spoke in a star of bidirected channels.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be destroyed via the [StarChannel::destroy] or [StarPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct SpokeHandle<HubToSpoke, SpokeToHub> {
+    index: usize,
+    data_pointer: BidirectedDataPointer<HubToSpoke, SpokeToHub>,
+}
+
+impl<HubToSpoke, SpokeToHub> StarChannel<HubToSpoke, SpokeToHub> {
+    /// Create a star of `n` bidirected channels and hand out pointers to it.
+    /// One [StarPointer] used to flush all channels,
+    /// one [HubHandle] used by the hub to communicate with each spoke, and
+    /// one [SpokeHandle] per spoke, in spoke order.
+    ///
+    /// Both `Data` fields of the channel from the hub to spoke `i` are initialised by calling `init_forward(i)`,
+    /// and both `Data` fields of the channel from spoke `i` to the hub are initialised by calling `init_backward(i)`.
+    #[allow(clippy::type_complexity)]
+    pub fn create(
+        n: usize,
+        init_forward: impl Fn(usize) -> HubToSpoke,
+        init_backward: impl Fn(usize) -> SpokeToHub,
+    ) -> (
+        StarPointer<HubToSpoke, SpokeToHub>,
+        HubHandle<HubToSpoke, SpokeToHub>,
+        Vec<SpokeHandle<HubToSpoke, SpokeToHub>>,
+    ) {
+        let mut channel_pointers = Vec::with_capacity(n);
+        let mut hub_data_pointers = Vec::with_capacity(n);
+        let mut spokes = Vec::with_capacity(n);
+        for index in 0..n {
+            let (channel_pointer, hub_data_pointer, spoke_data_pointer) =
+                BidirectedChannel::create_with(|| init_backward(index), || init_forward(index));
+            channel_pointers.push(channel_pointer);
+            hub_data_pointers.push(hub_data_pointer);
+            spokes.push(SpokeHandle {
+                index,
+                data_pointer: spoke_data_pointer,
+            });
+        }
+
+        (
+            StarPointer { channel_pointers },
+            HubHandle {
+                data_pointers: hub_data_pointers,
+            },
+            spokes,
+        )
+    }
+
+    /// Destroys the star linked with the given pointers (see [StarChannel::create]).
+    /// Returns the `Data` fields of each channel in spoke order, in the order returned by [BidirectedChannel::destroy],
+    /// i.e. the read-only and writable `SpokeToHub`, followed by the read-only and writable `HubToSpoke`.
+    ///
+    /// **Panics** if not all pointers point to the same star, or if not every spoke is given exactly once.
+    #[allow(clippy::type_complexity)]
+    pub fn destroy(
+        star_pointer: StarPointer<HubToSpoke, SpokeToHub>,
+        hub: HubHandle<HubToSpoke, SpokeToHub>,
+        spokes: impl IntoIterator<Item = SpokeHandle<HubToSpoke, SpokeToHub>>,
+    ) -> Vec<(SpokeToHub, SpokeToHub, HubToSpoke, HubToSpoke)> {
+        let n = star_pointer.channel_pointers.len();
+        assert_eq!(hub.data_pointers.len(), n);
+        let mut spoke_data_pointers: Vec<_> = (0..n).map(|_| None).collect();
+        for SpokeHandle {
+            index,
+            data_pointer,
+        } in spokes
+        {
+            let slot = spoke_data_pointers
+                .get_mut(index)
+                .expect("spoke does not belong to this star");
+            assert!(slot.is_none(), "multiple spokes with the same index");
+            *slot = Some(data_pointer);
+        }
+
+        star_pointer
+            .channel_pointers
+            .into_iter()
+            .zip(hub.data_pointers)
+            .zip(spoke_data_pointers)
+            .map(
+                |((channel_pointer, hub_data_pointer), spoke_data_pointer)| {
+                    channel_pointer
+                        .destroy(hub_data_pointer, spoke_data_pointer.expect("missing spoke"))
+                },
+            )
+            .collect()
+    }
+}
+
+impl<HubToSpoke: Clone, SpokeToHub: Clone> StarPointer<HubToSpoke, SpokeToHub> {
+    /// Flush all channels of the star (see [BidirectedChannelPointer::flush]).
+    pub fn flush_all(&mut self, channel_key: &ChannelKey) {
+        for channel_pointer in &mut self.channel_pointers {
+            channel_pointer.flush(channel_key);
+        }
+    }
+}
+
+impl<HubToSpoke, SpokeToHub> StarPointer<HubToSpoke, SpokeToHub> {
+    /// The number of spokes in the star.
+    pub fn len(&self) -> usize {
+        self.channel_pointers.len()
+    }
+
+    /// Returns `true` if the star has no spokes.
+    pub fn is_empty(&self) -> bool {
+        self.channel_pointers.is_empty()
+    }
+
+    /// Shorthand for [StarChannel::destroy].
+    #[allow(clippy::type_complexity)]
+    pub fn destroy(
+        self,
+        hub: HubHandle<HubToSpoke, SpokeToHub>,
+        spokes: impl IntoIterator<Item = SpokeHandle<HubToSpoke, SpokeToHub>>,
+    ) -> Vec<(SpokeToHub, SpokeToHub, HubToSpoke, HubToSpoke)> {
+        StarChannel::destroy(self, hub, spokes)
+    }
+}
+
+impl<HubToSpoke, SpokeToHub> HubHandle<HubToSpoke, SpokeToHub> {
+    /// The number of spokes in the star.
+    pub fn len(&self) -> usize {
+        self.data_pointers.len()
+    }
+
+    /// Returns `true` if the star has no spokes.
+    pub fn is_empty(&self) -> bool {
+        self.data_pointers.is_empty()
+    }
+
+    /// Get a reference to the input received from the spoke with the given index.
+    ///
+    /// **Panics** if the index is out of range.
+    pub fn input(&self, data_key: &DataKey, index: usize) -> &SpokeToHub {
+        self.data_pointers[index].get_input(data_key)
+    }
+
+    /// Get a mutable reference to the output sent to the spoke with the given index.
+    ///
+    /// **Panics** if the index is out of range.
+    pub fn output_mut(&mut self, data_key: &DataKey, index: usize) -> &mut HubToSpoke {
+        self.data_pointers[index].get_output(data_key)
+    }
+
+    /// Get the endpoint of the hub connected to the spoke with the given index,
+    /// e.g. to access its input and output at the same time.
+    ///
+    /// **Panics** if the index is out of range.
+    pub fn endpoint(&mut self, index: usize) -> &mut BidirectedDataPointer<SpokeToHub, HubToSpoke> {
+        &mut self.data_pointers[index]
+    }
+}
+
+impl<HubToSpoke, SpokeToHub> SpokeHandle<HubToSpoke, SpokeToHub> {
+    /// The index of this spoke in the star.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Get a reference to the input received from the hub.
+    pub fn input(&self, data_key: &DataKey) -> &HubToSpoke {
+        self.data_pointer.get_input(data_key)
+    }
+
+    /// Get a mutable reference to the output sent to the hub.
+    pub fn output_mut(&mut self, data_key: &DataKey) -> &mut SpokeToHub {
+        self.data_pointer.get_output(data_key)
+    }
+
+    /// Call the given function with a reference to the input received from the hub and a mutable reference to the output sent to the hub,
+    /// and return its result.
+    pub fn process<R>(
+        &mut self,
+        data_key: &DataKey,
+        f: impl FnOnce(&HubToSpoke, &mut SpokeToHub) -> R,
+    ) -> R {
+        self.data_pointer.process(data_key, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{star::StarChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut star_pointer, mut hub, mut spokes) =
+            StarChannel::create(3, |index| index * 10, |_| 0);
+        assert_eq!(star_pointer.len(), 3);
+
+        for _ in 0..2 {
+            let data_key = master_key.get_data_key();
+            for spoke in &mut spokes {
+                spoke.process(&data_key, |input, output| *output = input + 1);
+            }
+            for index in 0..hub.len() {
+                *hub.output_mut(&data_key, index) += 100;
+            }
+            star_pointer.flush_all(&data_key.into_channel_key());
+        }
+
+        let data_key = master_key.get_data_key();
+        let inputs: Vec<_> = (0..hub.len()).map(|i| *hub.input(&data_key, i)).collect();
+        assert_eq!(inputs, [101, 111, 121]);
+
+        // Spokes can be given in any order.
+        spokes.reverse();
+        assert_eq!(
+            star_pointer.destroy(hub, spokes),
+            [
+                (101, 101, 200, 200),
+                (111, 111, 210, 210),
+                (121, 121, 220, 220)
+            ]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! The values flushed through a [directed channel](crate::directed) as a [Stream], e.g. for an async UI layer.
+//! The coordinator publishes the flushed values via a [StreamPump], and an async task consumes them via a [FlushStream].
+//! No specific async runtime is required.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures_core::Stream;
+
+use crate::{directed::DirectedChannelPointer, ChannelKey};
+
+/// What a [StreamPump] does with a new value if the buffer of its [FlushStream] is full,
+/// i.e. if the consumer lags behind the channel phases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LagPolicy {
+    /// Discard all buffered values, such that the stream continues with the newest value.
+    SkipToLatest,
+    /// Discard the oldest buffered value, such that the stream yields the newest values up to the capacity of the buffer, in order.
+    DropOldest,
+}
+
+/// Owns the channel pointer of a directed channel and publishes its flushed values to a [FlushStream].
+/// Dropping the pump ends the stream once the buffered values were consumed.
+#[derive(Debug)]
+pub struct StreamPump<Data> {
+    channel_pointer: DirectedChannelPointer<Data>,
+    sender: Sender<Data>,
+    capacity: usize,
+    policy: LagPolicy,
+}
+
+/// A [Stream] of the values flushed through a directed channel, in order, as published by a [StreamPump].
+/// Values that were discarded because the consumer lagged behind are counted, see [FlushStream::skipped].
+#[derive(Debug)]
+pub struct FlushStream<Data> {
+    shared: Arc<Mutex<Shared<Data>>>,
+}
+
+/// Closes the stream when the pump is dropped.
+#[derive(Debug)]
+struct Sender<Data> {
+    shared: Arc<Mutex<Shared<Data>>>,
+}
+
+#[derive(Debug)]
+struct Shared<Data> {
+    buffer: VecDeque<Data>,
+    /// The waker of the task that last polled the stream while it was empty.
+    waker: Option<Waker>,
+    closed: bool,
+    skipped: u64,
+}
+
+impl<Data: Clone> FlushStream<Data> {
+    /// Wrap the channel pointer of a directed channel into a pump, and create the stream it publishes to.
+    /// The stream buffers at most `capacity` values, and handles a full buffer according to the given policy.
+    ///
+    /// **Panics** if the capacity is zero.
+    pub fn create(
+        channel_pointer: DirectedChannelPointer<Data>,
+        capacity: usize,
+        policy: LagPolicy,
+    ) -> (StreamPump<Data>, FlushStream<Data>) {
+        assert!(
+            capacity > 0,
+            "the capacity of a flush stream must not be zero"
+        );
+        let shared = Arc::new(Mutex::new(Shared {
+            buffer: VecDeque::with_capacity(capacity),
+            waker: None,
+            closed: false,
+            skipped: 0,
+        }));
+        (
+            StreamPump {
+                channel_pointer,
+                sender: Sender {
+                    shared: shared.clone(),
+                },
+                capacity,
+                policy,
+            },
+            FlushStream { shared },
+        )
+    }
+}
+
+impl<Data> FlushStream<Data> {
+    /// The number of values that were discarded so far because the buffer was full.
+    pub fn skipped(&self) -> u64 {
+        self.shared.lock().unwrap().skipped
+    }
+}
+
+impl<Data> Stream for FlushStream<Data> {
+    type Item = Data;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Data>> {
+        let mut shared = self.shared.lock().unwrap();
+        if let Some(value) = shared.buffer.pop_front() {
+            Poll::Ready(Some(value))
+        } else if shared.closed {
+            Poll::Ready(None)
+        } else {
+            match &shared.waker {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                _ => shared.waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let shared = self.shared.lock().unwrap();
+        let buffered = shared.buffer.len();
+        (buffered, shared.closed.then(|| buffered))
+    }
+}
+
+impl<Data: Clone> StreamPump<Data> {
+    /// Flush the channel, and publish a clone of the flushed value to the stream if the channel was dirty.
+    /// Wakes the stream if it is waiting.
+    /// Returns `true` if the channel was flushed.
+    pub fn publish(&mut self, channel_key: &ChannelKey) -> bool {
+        let dirty = self.channel_pointer.is_dirty(channel_key);
+        self.channel_pointer.flush(channel_key);
+        if !dirty {
+            return false;
+        }
+
+        let value = self
+            .channel_pointer
+            .inspect(channel_key, |read_only, _| read_only.clone());
+        let mut shared = self.sender.shared.lock().unwrap();
+        if shared.buffer.len() == self.capacity {
+            match self.policy {
+                LagPolicy::SkipToLatest => {
+                    shared.skipped += shared.buffer.len() as u64;
+                    shared.buffer.clear();
+                }
+                LagPolicy::DropOldest => {
+                    shared.skipped += 1;
+                    shared.buffer.pop_front();
+                }
+            }
+        }
+        shared.buffer.push_back(value);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+
+    /// The wrapped channel pointer.
+    pub fn channel_pointer(&mut self) -> &mut DirectedChannelPointer<Data> {
+        &mut self.channel_pointer
+    }
+
+    /// Return the wrapped channel pointer, e.g. for destruction.
+    /// This ends the stream once the buffered values were consumed.
+    pub fn into_inner(self) -> DirectedChannelPointer<Data> {
+        self.channel_pointer
+    }
+}
+
+impl<Data> Drop for Sender<Data> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.closed = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use futures_core::Stream;
+
+    use crate::{
+        directed::DirectedChannel,
+        stream::{FlushStream, LagPolicy},
+        MasterKey,
+    };
+
+    /// Counts its wakes.
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn poll<S: Stream + Unpin>(stream: &mut S, waker: &Waker) -> Poll<Option<S::Item>> {
+        Pin::new(stream).poll_next(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let (mut pump, mut stream) = FlushStream::create(channel_pointer, 2, LagPolicy::DropOldest);
+        let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+
+        assert_eq!(poll(&mut stream, &waker), Poll::Pending);
+        // New channels are dirty, hence the first flush is published.
+        assert!(pump.publish(&master_key.get_channel_key()));
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+        // Clean flushes are skipped.
+        assert!(!pump.publish(&master_key.get_channel_key()));
+        assert_eq!(poll(&mut stream, &waker), Poll::Ready(Some(0)));
+        assert_eq!(poll(&mut stream, &waker), Poll::Pending);
+
+        for value in 1..=4 {
+            *writable.get_mut(&master_key.get_data_key()) = value;
+            pump.publish(&master_key.get_channel_key());
+        }
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 2);
+        assert_eq!(stream.skipped(), 2);
+        assert_eq!(stream.size_hint(), (2, None));
+        assert_eq!(poll(&mut stream, &waker), Poll::Ready(Some(3)));
+
+        let channel_pointer = pump.into_inner();
+        assert_eq!(poll(&mut stream, &waker), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut stream, &waker), Poll::Ready(None));
+        let _ = (channel_pointer, read_only);
+    }
+
+    #[test]
+    fn skip_to_latest() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+        let (mut pump, mut stream) =
+            FlushStream::create(channel_pointer, 3, LagPolicy::SkipToLatest);
+        let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+
+        for value in 0..5 {
+            *writable.get_mut(&master_key.get_data_key()) = value;
+            pump.publish(&master_key.get_channel_key());
+        }
+        assert_eq!(stream.skipped(), 3);
+        assert_eq!(poll(&mut stream, &waker), Poll::Ready(Some(3)));
+        assert_eq!(poll(&mut stream, &waker), Poll::Ready(Some(4)));
+        assert_eq!(poll(&mut stream, &waker), Poll::Pending);
+        let _ = (pump.into_inner(), read_only);
+    }
+}
@@ -0,0 +1,303 @@
+//! Sampling of the read-only `Data` of selected channels of a [ChannelGroup] for threads that do not participate in the phase protocol,
+//! such as a dashboard that shows a snapshot of some channels once a second.
+//!
+//! The coordinator registers the channels with a [TelemetrySampler] by their labels, and calls [TelemetrySampler::sample] in each channel phase.
+//! The sampler clones the read-only `Data` of each channel whose interval elapsed and whose generation advanced since its last sample,
+//! and sends it as a [TelemetryRecord] to a bounded queue.
+//! If the queue is full, the record is dropped instead of blocking the channel phase (see [TelemetrySampler::dropped]).
+//!
+//! ```
+//! use std::time::Duration;
+//! use two_phase_channel::{
+//!     directed::DirectedChannel, group::ChannelGroup, telemetry::TelemetrySampler, MasterKey,
+//! };
+//!
+//! let mut master_key = MasterKey::create();
+//! let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0u32, 0);
+//! let mut group = ChannelGroup::new();
+//! let id = group.push_directed(&master_key.get_channel_key(), "load", channel_pointer);
+//! let (mut sampler, records) = TelemetrySampler::new(16);
+//! sampler.register::<u32>(&group, "load", Duration::from_secs(1));
+//!
+//! *writable.get_mut(&master_key.get_data_key()) = 7;
+//! let channel_key = master_key.get_channel_key();
+//! group.advance_all(&channel_key);
+//! sampler.sample(&group, &channel_key);
+//!
+//! let record = records.try_recv().unwrap();
+//! assert_eq!(record.label, "load");
+//! assert_eq!(record.value.downcast_ref::<u32>(), Some(&7));
+//! # let channel_pointer: two_phase_channel::directed::DirectedChannelPointer<u32> = group.remove(&channel_key, id).unwrap();
+//! # channel_pointer.destroy_single(read_only, writable);
+//! ```
+
+use std::{
+    any::Any,
+    sync::mpsc::{self, Receiver, SyncSender, TrySendError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    directed::DirectedChannelPointer,
+    group::{ChannelGroup, GroupId},
+    ChannelKey,
+};
+
+/// A sample of the read-only `Data` of a channel, sent by a [TelemetrySampler].
+#[derive(Debug)]
+pub struct TelemetryRecord {
+    /// The label of the channel in its group.
+    pub label: String,
+    /// A clone of the read-only `Data` of the channel, to be downcast to the `Data` type it was registered with.
+    pub value: Box<dyn Any + Send>,
+    /// The generation of the channel at the time of the sample, i.e. the number of flushes that changed its read-only `Data`.
+    pub generation: u64,
+    /// The time of the channel phase the sample was taken in.
+    pub timestamp: Instant,
+}
+
+/// Samples the read-only `Data` of selected directed channels of a [ChannelGroup] during the channel phase.
+/// See the [module documentation](self) for more info.
+#[derive(Debug)]
+pub struct TelemetrySampler {
+    sender: SyncSender<TelemetryRecord>,
+    channels: Vec<Sampled>,
+    /// The number of records that were dropped because the queue was full.
+    dropped: u64,
+}
+
+#[derive(Debug)]
+struct Sampled {
+    id: GroupId,
+    label: String,
+    interval: Duration,
+    /// The time and generation of the last sample, if any.
+    last: Option<(Instant, u64)>,
+    sample: Sample,
+}
+
+/// Returns the generation of a channel, and a clone of its read-only `Data` if the generation differs from the given one.
+type Sample =
+    fn(&(dyn Any + Send + Sync), &ChannelKey, Option<u64>) -> (u64, Option<Box<dyn Any + Send>>);
+
+impl TelemetrySampler {
+    /// Create a sampler without channels, and the receiver of its records, which holds at most `capacity` records.
+    ///
+    /// **Panics** if the capacity is zero.
+    pub fn new(capacity: usize) -> (Self, Receiver<TelemetryRecord>) {
+        assert!(
+            capacity > 0,
+            "the capacity of a telemetry sampler must not be zero"
+        );
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        (
+            Self {
+                sender,
+                channels: Vec::new(),
+                dropped: 0,
+            },
+            receiver,
+        )
+    }
+
+    /// Sample the channel with the given label in the given group at most once per `interval`,
+    /// replacing its previous interval if it is already registered.
+    /// The channel must be a [DirectedChannelPointer] of the given `Data` type.
+    /// If multiple channels have the label, the first one is sampled.
+    /// Returns `false` if the group has no channel with the label.
+    ///
+    /// **Panics** if the channel is not a [DirectedChannelPointer] of the given `Data` type.
+    pub fn register<Data: Clone + Send + 'static>(
+        &mut self,
+        group: &ChannelGroup,
+        label: &str,
+        interval: Duration,
+    ) -> bool {
+        let id = match group.iter().find(|&(_, other, _)| other == label) {
+            Some((id, _, _)) => id,
+            None => return false,
+        };
+        assert!(
+            group
+                .channel(id)
+                .expect("channel of an iterated id exists")
+                .is::<DirectedChannelPointer<Data>>(),
+            "channel {:?} has a different type",
+            label
+        );
+
+        if let Some(sampled) = self.channels.iter_mut().find(|sampled| sampled.id == id) {
+            sampled.interval = interval;
+        } else {
+            self.channels.push(Sampled {
+                id,
+                label: label.to_owned(),
+                interval,
+                last: None,
+                sample: sample::<Data>,
+            });
+        }
+        true
+    }
+
+    /// Stop sampling the channel with the given label.
+    /// Returns `false` if it was not registered.
+    pub fn unregister(&mut self, label: &str) -> bool {
+        let len = self.channels.len();
+        self.channels.retain(|sampled| sampled.label != label);
+        self.channels.len() < len
+    }
+
+    /// Send a record for each registered channel whose interval elapsed and whose generation advanced since its last sample.
+    /// This is called during the channel phase, usually right after advancing the group, and never blocks.
+    /// Channels that were removed from the group are unregistered.
+    ///
+    /// Returns the number of records sent.
+    pub fn sample(&mut self, group: &ChannelGroup, channel_key: &ChannelKey) -> usize {
+        let now = Instant::now();
+        let mut sent = 0;
+        self.channels
+            .retain(|sampled| group.channel(sampled.id).is_some());
+        for sampled in &mut self.channels {
+            let channel = group
+                .channel(sampled.id)
+                .expect("removed channels were unregistered");
+            let previous = match sampled.last {
+                Some((time, _)) if now.saturating_duration_since(time) < sampled.interval => {
+                    continue
+                }
+                Some((_, generation)) => Some(generation),
+                None => None,
+            };
+            let (generation, value) = (sampled.sample)(channel, channel_key, previous);
+            if let Some(value) = value {
+                sampled.last = Some((now, generation));
+                match self.sender.try_send(TelemetryRecord {
+                    label: sampled.label.clone(),
+                    value,
+                    generation,
+                    timestamp: now,
+                }) {
+                    Ok(()) => sent += 1,
+                    Err(TrySendError::Full(_)) => self.dropped += 1,
+                    // Nobody listens anymore, which is not an error of the coordinator.
+                    Err(TrySendError::Disconnected(_)) => {}
+                }
+            }
+        }
+        sent
+    }
+
+    /// The number of records that were dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+fn sample<Data: Clone + Send + 'static>(
+    channel: &(dyn Any + Send + Sync),
+    channel_key: &ChannelKey,
+    previous: Option<u64>,
+) -> (u64, Option<Box<dyn Any + Send>>) {
+    let channel_pointer = channel
+        .downcast_ref::<DirectedChannelPointer<Data>>()
+        .expect("the type was checked on registration");
+    let generation = channel_pointer.stats(channel_key).generation;
+    let value = (previous != Some(generation)).then(|| {
+        let value: Box<dyn Any + Send> =
+            Box::new(channel_pointer.inspect(channel_key, |read_only, _| read_only.clone()));
+        value
+    });
+    (generation, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        directed::{DirectedChannel, DirectedChannelPointer},
+        group::ChannelGroup,
+        telemetry::TelemetrySampler,
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer1, read_only1, mut writable1) = DirectedChannel::create(0u32, 0);
+        let (channel_pointer2, read_only2, mut writable2) =
+            DirectedChannel::create(String::new(), String::new());
+        let mut group = ChannelGroup::new();
+        let channel_key = master_key.get_channel_key();
+        let id1 = group.push_directed(&channel_key, "fast", channel_pointer1);
+        let id2 = group.push_directed(&channel_key, "slow", channel_pointer2);
+        let (mut sampler, records) = TelemetrySampler::new(16);
+        assert!(sampler.register::<u32>(&group, "fast", Duration::ZERO));
+        assert!(sampler.register::<String>(&group, "slow", Duration::from_secs(3600)));
+        assert!(!sampler.register::<u32>(&group, "missing", Duration::ZERO));
+
+        // Both channels are sampled first, and then only when their generation advances within their interval.
+        let mut values = Vec::new();
+        for i in 1..=3 {
+            let data_key = master_key.get_data_key();
+            if i != 2 {
+                *writable1.get_mut(&data_key) = i;
+            }
+            writable2.get_mut(&data_key).push('x');
+            let channel_key = data_key.into_channel_key();
+            group.advance_all(&channel_key);
+            sampler.sample(&group, &channel_key);
+            for record in records.try_iter() {
+                let value = match record.value.downcast::<u32>() {
+                    Ok(value) => value.to_string(),
+                    Err(value) => *value.downcast::<String>().unwrap(),
+                };
+                values.push((record.label, value, record.generation));
+            }
+        }
+        assert_eq!(
+            values,
+            [
+                ("fast".to_owned(), "1".to_owned(), 1),
+                ("slow".to_owned(), "x".to_owned(), 1),
+                ("fast".to_owned(), "3".to_owned(), 2),
+            ]
+        );
+
+        let channel_key = master_key.get_channel_key();
+        let channel_pointer1: DirectedChannelPointer<u32> =
+            group.remove(&channel_key, id1).unwrap();
+        channel_pointer1.destroy_single(read_only1, writable1);
+        // Removed channels are unregistered.
+        assert_eq!(sampler.sample(&group, &channel_key), 0);
+        assert!(!sampler.unregister("fast"));
+        assert!(sampler.unregister("slow"));
+        let channel_pointer2: DirectedChannelPointer<String> =
+            group.remove(&channel_key, id2).unwrap();
+        channel_pointer2.destroy_single(read_only2, writable2);
+    }
+
+    #[test]
+    fn full_queue_drops() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0u8, 0);
+        let mut group = ChannelGroup::new();
+        let id = group.push_directed(&master_key.get_channel_key(), "channel", channel_pointer);
+        let (mut sampler, records) = TelemetrySampler::new(1);
+        sampler.register::<u8>(&group, "channel", Duration::ZERO);
+
+        for i in 0..3 {
+            *writable.get_mut(&master_key.get_data_key()) = i;
+            let channel_key = master_key.get_channel_key();
+            group.advance_all(&channel_key);
+            sampler.sample(&group, &channel_key);
+        }
+        assert_eq!(sampler.dropped(), 2);
+        assert_eq!(records.try_iter().count(), 1);
+
+        let channel_pointer: DirectedChannelPointer<u8> =
+            group.remove(&master_key.get_channel_key(), id).unwrap();
+        channel_pointer.destroy_single(read_only, writable);
+    }
+}
@@ -0,0 +1,611 @@
+//! Utilities for testing worker and coordinator logic against many interleavings of phases.
+//!
+//! A [ScriptedCoordinator] drives a [ChannelGroup] through a pseudo-random but deterministic schedule derived from a seed,
+//! varying the number of data phases between channel phases, the order in which the channels are advanced,
+//! and injecting skipped and spurious extra advances.
+//! Running the same test with many seeds then covers many schedules, and a failing seed reproduces its schedule exactly.
+//!
+//! A [SimScheduler] runs the workers and the coordinator of a [PhaseRunner](crate::runner::PhaseRunner) on the current thread instead,
+//! one after the other in a seeded or scripted order, such that a concurrency bug in the workers reproduces deterministically
+//! and can be stepped through.
+
+use core::{
+    fmt::{self, Debug},
+    ops::ControlFlow,
+};
+use std::collections::VecDeque;
+
+use crate::{
+    checked::KeyPhase,
+    group::{ChannelGroup, GroupId},
+    ChannelKey, DataKey, MasterKey,
+};
+
+/// A step of the schedule of a [ScriptedCoordinator], as recorded in its history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Step {
+    /// A data phase.
+    DataPhase,
+    /// An advance of the channel with the given id in a channel phase.
+    Advance(GroupId),
+}
+
+/// Drives a [ChannelGroup] through a deterministic schedule of phases derived from a seed.
+///
+/// In each round, one to [ScriptedCoordinator::with_max_data_phases] data phases are followed by one channel phase.
+/// In each channel phase, the channels of the group are advanced one by one, in a shuffled order if enabled,
+/// where each channel is skipped or advanced a second time with the configured probabilities.
+#[derive(Debug, Clone)]
+pub struct ScriptedCoordinator {
+    seed: u64,
+    state: u64,
+    max_data_phases: usize,
+    skip_probability: f64,
+    extra_advance_probability: f64,
+    shuffle: bool,
+    history: Vec<Step>,
+}
+
+impl ScriptedCoordinator {
+    /// Create a coordinator whose schedule is derived from the given seed.
+    /// By default, up to three data phases happen per round, channels are skipped and advanced twice with a probability of 10% each,
+    /// and the order of the channels is shuffled.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: seed,
+            max_data_phases: 3,
+            skip_probability: 0.1,
+            extra_advance_probability: 0.1,
+            shuffle: true,
+            history: Vec::new(),
+        }
+    }
+
+    /// Set the maximum number of data phases per round.
+    ///
+    /// **Panics** if the maximum is zero.
+    pub fn with_max_data_phases(mut self, max_data_phases: usize) -> Self {
+        assert!(max_data_phases > 0, "each round needs a data phase");
+        self.max_data_phases = max_data_phases;
+        self
+    }
+
+    /// Set the probability with which a channel is not advanced in a channel phase.
+    pub fn with_skip_probability(mut self, probability: f64) -> Self {
+        self.skip_probability = probability;
+        self
+    }
+
+    /// Set the probability with which a channel is advanced a second time in a channel phase.
+    pub fn with_extra_advance_probability(mut self, probability: f64) -> Self {
+        self.extra_advance_probability = probability;
+        self
+    }
+
+    /// Set whether the order in which the channels are advanced is shuffled in each channel phase.
+    /// Otherwise, they are advanced in insertion order.
+    pub fn with_shuffle(mut self, shuffle: bool) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+
+    /// The seed of the schedule, e.g. to report it when a test fails.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The steps that were executed so far.
+    pub fn history(&self) -> &[Step] {
+        &self.history
+    }
+
+    /// Run the given number of rounds, calling the given function in each data phase.
+    pub fn run(
+        &mut self,
+        master_key: &mut MasterKey,
+        group: &mut ChannelGroup,
+        rounds: usize,
+        mut data_phase: impl FnMut(&DataKey),
+    ) {
+        for _ in 0..rounds {
+            for _ in 0..=self.below(self.max_data_phases) {
+                data_phase(&master_key.get_data_key());
+                self.history.push(Step::DataPhase);
+            }
+            self.channel_phase(&master_key.get_channel_key(), group);
+        }
+    }
+
+    /// Advance the channels of the given group according to the schedule.
+    pub fn channel_phase(&mut self, channel_key: &ChannelKey, group: &mut ChannelGroup) {
+        let mut ids: Vec<_> = group.iter().map(|(id, _, _)| id).collect();
+        if self.shuffle {
+            shuffle(&mut self.state, &mut ids);
+        }
+
+        for id in ids {
+            if self.chance(self.skip_probability) {
+                continue;
+            }
+            let advances = if self.chance(self.extra_advance_probability) {
+                2
+            } else {
+                1
+            };
+            for _ in 0..advances {
+                group.advance(channel_key, id);
+                self.history.push(Step::Advance(id));
+            }
+        }
+    }
+
+    /// A pseudo-random number below the given bound.
+    fn below(&mut self, bound: usize) -> usize {
+        below(&mut self.state, bound)
+    }
+
+    /// Returns `true` with the given probability.
+    fn chance(&mut self, probability: f64) -> bool {
+        ((next(&mut self.state) >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+/// The next pseudo-random number of the given state, via SplitMix64.
+fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A pseudo-random number below the given bound.
+fn below(state: &mut u64, bound: usize) -> usize {
+    (next(state) % bound as u64) as usize
+}
+
+/// Shuffle the given slice via Fisher-Yates.
+fn shuffle<T>(state: &mut u64, slice: &mut [T]) {
+    for index in (1..slice.len()).rev() {
+        slice.swap(index, below(state, index + 1));
+    }
+}
+
+/// A step executed by a [SimScheduler].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimStep {
+    /// The worker with the given index in insertion order ran in a data phase.
+    Worker(usize),
+    /// The coordinator ran in a channel phase.
+    Coordinator,
+}
+
+/// Runs the workers and the coordinator of a [PhaseRunner](crate::runner::PhaseRunner) deterministically on the current thread.
+///
+/// Like with a [PhaseRunner](crate::runner::PhaseRunner), each phase consists of a data phase, in which each worker is called once,
+/// followed by a channel phase, in which the coordinator is called once.
+/// The workers of a data phase run one after the other, in an order drawn from a seed (see [SimScheduler::with_seed])
+/// or given by a script (see [SimScheduler::with_script]).
+/// The workers share the data key of their data phase, and the coordinator gets a channel key, all derived from the owned master key,
+/// such that the key protocol is the same as with real threads.
+///
+/// Since there is no parallelism, a worker that waits for another worker of the same data phase deadlocks,
+/// and the panics of the workers and the coordinator are not caught, but unwind out of [SimScheduler::step].
+pub struct SimScheduler {
+    master_key: MasterKey,
+    workers: Vec<(String, SimWorker)>,
+    coordinator: Option<SimCoordinator>,
+    inspector: Option<Box<dyn FnMut(SimStep)>>,
+    order: SimOrder,
+    /// The phase of the data key of the current data phase, if a data phase is in progress.
+    data_phase: Option<KeyPhase>,
+    /// The workers that did not run yet in the current data phase, the next one last.
+    pending: Vec<usize>,
+    phases: usize,
+    finished: bool,
+    history: Vec<SimStep>,
+}
+
+/// The function of a worker of a [SimScheduler], which does not need to be `Send`.
+type SimWorker = Box<dyn FnMut(&DataKey)>;
+
+/// The function of the coordinator of a [SimScheduler].
+type SimCoordinator = Box<dyn FnMut(&ChannelKey) -> ControlFlow<()>>;
+
+/// How a [SimScheduler] orders the workers of a data phase.
+#[derive(Debug)]
+enum SimOrder {
+    Seeded(u64),
+    /// The orders of the following data phases, after which the workers run in insertion order.
+    Script(VecDeque<Vec<usize>>),
+}
+
+impl SimScheduler {
+    /// Create a scheduler without workers and without a coordinator, which owns the given master key.
+    /// The workers are ordered by a seed of zero.
+    pub fn new(master_key: MasterKey) -> Self {
+        Self {
+            master_key,
+            workers: Vec::new(),
+            coordinator: None,
+            inspector: None,
+            order: SimOrder::Seeded(0),
+            data_phase: None,
+            pending: Vec::new(),
+            phases: 0,
+            finished: false,
+            history: Vec::new(),
+        }
+    }
+
+    /// Shuffle the workers of each data phase with a pseudo-random generator seeded with the given seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.order = SimOrder::Seeded(seed);
+        self
+    }
+
+    /// Run the workers of the following data phases in the given orders, one per data phase, each listing the indices of all workers.
+    /// Once the script is exhausted, the workers run in insertion order.
+    /// A data phase that is in progress keeps its order.
+    ///
+    /// An order that is not a permutation of the workers **panics** when its data phase starts.
+    pub fn with_script(mut self, script: impl IntoIterator<Item = Vec<usize>>) -> Self {
+        self.order = SimOrder::Script(script.into_iter().collect());
+        self
+    }
+
+    /// Add a worker, like [PhaseRunner::add_worker](crate::runner::PhaseRunner::add_worker).
+    /// Its index is the number of workers added before it.
+    pub fn add_worker(&mut self, name: impl Into<String>, worker: impl FnMut(&DataKey) + 'static) {
+        self.workers.push((name.into(), Box::new(worker)));
+    }
+
+    /// Set the coordinator, replacing the previous one, like [PhaseRunner::set_coordinator](crate::runner::PhaseRunner::set_coordinator).
+    /// The scheduler finishes after a channel phase in which the coordinator returns [ControlFlow::Break].
+    /// Without a coordinator, each phase continues.
+    pub fn set_coordinator(
+        &mut self,
+        coordinator: impl FnMut(&ChannelKey) -> ControlFlow<()> + 'static,
+    ) {
+        self.coordinator = Some(Box::new(coordinator));
+    }
+
+    /// Set a function that is called after each step, replacing the previous one, e.g. to check invariants of shared state between steps.
+    pub fn set_inspector(&mut self, inspector: impl FnMut(SimStep) + 'static) {
+        self.inspector = Some(Box::new(inspector));
+    }
+
+    /// Execute the next step, i.e. run the next worker of the current data phase, or the coordinator once all workers ran.
+    /// Returns the executed step, or `None` if the coordinator returned [ControlFlow::Break] before.
+    pub fn step(&mut self) -> Option<SimStep> {
+        if self.finished {
+            return None;
+        }
+        let phase = match self.data_phase {
+            Some(phase) => phase,
+            None => {
+                let phase = self.master_key.get_data_key().phase;
+                self.pending = self.next_order();
+                self.pending.reverse();
+                self.data_phase = Some(phase);
+                phase
+            }
+        };
+
+        let step = if let Some(index) = self.pending.pop() {
+            // The data phase lasts until all workers ran, hence they share the data key.
+            (self.workers[index].1)(&DataKey::new(phase));
+            SimStep::Worker(index)
+        } else {
+            self.data_phase = None;
+            let channel_key = self.master_key.get_channel_key();
+            let flow = match &mut self.coordinator {
+                Some(coordinator) => coordinator(&channel_key),
+                None => ControlFlow::Continue(()),
+            };
+            self.phases += 1;
+            self.finished = matches!(flow, ControlFlow::Break(()));
+            SimStep::Coordinator
+        };
+        self.history.push(step);
+        if let Some(inspector) = &mut self.inspector {
+            inspector(step);
+        }
+        Some(step)
+    }
+
+    /// Execute steps until the coordinator returns [ControlFlow::Break].
+    /// Returns the number of phases completed by this call.
+    pub fn run(&mut self) -> usize {
+        let phases = self.phases;
+        while self.step().is_some() {}
+        self.phases - phases
+    }
+
+    /// Like [SimScheduler::run], but completes at most the given number of phases.
+    pub fn run_for(&mut self, phases: usize) -> usize {
+        let start = self.phases;
+        while self.phases - start < phases && self.step().is_some() {}
+        self.phases - start
+    }
+
+    /// The number of phases completed so far.
+    pub fn phases(&self) -> usize {
+        self.phases
+    }
+
+    /// The steps executed so far.
+    pub fn history(&self) -> &[SimStep] {
+        &self.history
+    }
+
+    /// The names of the workers, in insertion order.
+    pub fn worker_names(&self) -> impl Iterator<Item = &str> {
+        self.workers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Return the master key, dropping the workers and the coordinator.
+    ///
+    /// **Panics** if a data phase is in progress, since its workers may have accessed channels that the coordinator did not advance yet.
+    pub fn into_master_key(self) -> MasterKey {
+        assert!(
+            self.data_phase.is_none(),
+            "the scheduler is in the middle of a data phase"
+        );
+        self.master_key
+    }
+
+    /// The order of the workers in the next data phase.
+    fn next_order(&mut self) -> Vec<usize> {
+        let workers = self.workers.len();
+        match &mut self.order {
+            SimOrder::Seeded(state) => {
+                let mut order: Vec<_> = (0..workers).collect();
+                shuffle(state, &mut order);
+                order
+            }
+            SimOrder::Script(script) => match script.pop_front() {
+                Some(order) => {
+                    let mut sorted = order.clone();
+                    sorted.sort_unstable();
+                    assert!(
+                        sorted.iter().copied().eq(0..workers),
+                        "the scripted order {:?} is not a permutation of the {} workers",
+                        order,
+                        workers
+                    );
+                    order
+                }
+                None => (0..workers).collect(),
+            },
+        }
+    }
+}
+
+impl fmt::Debug for SimScheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimScheduler")
+            .field("workers", &self.worker_names().collect::<Vec<_>>())
+            .field("coordinator", &self.coordinator.is_some())
+            .field("order", &self.order)
+            .field("phases", &self.phases)
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
+
+/// Assert that the received items are exactly the sent items, in order, e.g. for the items of a [queue channel](crate::queue).
+///
+/// **Panics** with a description of the first difference, i.e. a lost, duplicated or reordered item.
+#[track_caller]
+pub fn assert_delivered_in_order<T: Debug + PartialEq>(sent: &[T], received: &[T]) {
+    if let Some(index) = sent
+        .iter()
+        .zip(received)
+        .position(|(sent, received)| sent != received)
+    {
+        panic!(
+            "item {} was sent as {:?}, but received as {:?}",
+            index, sent[index], received[index]
+        );
+    }
+    if received.len() < sent.len() {
+        panic!(
+            "{} of {} items were not received, starting with {:?}",
+            sent.len() - received.len(),
+            sent.len(),
+            sent[received.len()]
+        );
+    }
+    if received.len() > sent.len() {
+        panic!(
+            "{} more items were received than sent, starting with {:?}",
+            received.len() - sent.len(),
+            received[sent.len()]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        ops::ControlFlow,
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        directed::DirectedChannel,
+        group::ChannelGroup,
+        queue::QueueChannel,
+        runner::PhaseRunner,
+        test_util::{assert_delivered_in_order, ScriptedCoordinator, SimScheduler, SimStep},
+        ChannelKey, DataKey, MasterKey,
+    };
+
+    #[test]
+    fn queue_channels_never_lose_or_duplicate_items() {
+        for seed in 0..200 {
+            let mut master_key = unsafe { MasterKey::create_unlimited() };
+            let (channel_pointer, mut sender, mut receiver) = QueueChannel::create();
+            let (other, _, _) = QueueChannel::<()>::create();
+            let mut group = ChannelGroup::new();
+            let channel_key = master_key.get_channel_key();
+            group.push(&channel_key, "queue", channel_pointer);
+            group.push(&channel_key, "other", other);
+
+            let mut coordinator = ScriptedCoordinator::new(seed)
+                .with_skip_probability(0.3)
+                .with_extra_advance_probability(0.3);
+            let (mut sent, mut received) = (Vec::new(), Vec::new());
+            let mut phase = 0;
+            coordinator.run(&mut master_key, &mut group, 50, |data_key| {
+                received.extend(receiver.drain(data_key));
+                for _ in 0..phase % 4 {
+                    sender.push(data_key, sent.len());
+                    sent.push(sent.len());
+                }
+                phase += 1;
+            });
+            group.advance_all(&master_key.get_channel_key());
+            received.extend(receiver.drain(&master_key.get_data_key()));
+            assert_delivered_in_order(&sent, &received);
+        }
+    }
+
+    #[test]
+    fn directed_readers_never_go_back_in_time() {
+        for seed in 0..200 {
+            let mut master_key = unsafe { MasterKey::create_unlimited() };
+            let (channel_pointer, read_only, mut writable) = DirectedChannel::create(0, 0);
+            let mut group = ChannelGroup::new();
+            group.push_directed(&master_key.get_channel_key(), "counter", channel_pointer);
+
+            let mut coordinator = ScriptedCoordinator::new(seed).with_max_data_phases(5);
+            let mut last = 0;
+            coordinator.run(&mut master_key, &mut group, 50, |data_key| {
+                let read = *read_only.get(data_key);
+                assert!(read >= last && read <= *writable.get(data_key));
+                last = read;
+                *writable.get_mut(data_key) += 1;
+            });
+        }
+    }
+
+    #[test]
+    fn schedules_are_deterministic() {
+        let history = |seed| {
+            let mut master_key = unsafe { MasterKey::create_unlimited() };
+            let mut group = ChannelGroup::new();
+            let channel_key = master_key.get_channel_key();
+            for label in ["a", "b", "c"] {
+                group.push(&channel_key, label, QueueChannel::<()>::create().0);
+            }
+            let mut coordinator = ScriptedCoordinator::new(seed);
+            coordinator.run(&mut master_key, &mut group, 20, |_| {});
+            coordinator.history().to_vec()
+        };
+        assert_eq!(history(7), history(7));
+        assert_ne!(history(7), history(8));
+    }
+
+    type Workers = Vec<(&'static str, Box<dyn FnMut(&DataKey) + Send>)>;
+    type Coordinator = Box<dyn FnMut(&ChannelKey) -> ControlFlow<()>>;
+
+    /// A producer, a doubler and a summer connected by directed channels, whose coordinator records the sums of five phases.
+    fn pipeline() -> (Workers, Coordinator, Arc<Mutex<Vec<u64>>>) {
+        let (mut numbers, numbers_read, mut numbers_write) = DirectedChannel::create(0u64, 0);
+        let (mut doubled, doubled_read, mut doubled_write) = DirectedChannel::create(0u64, 0);
+        let (mut sums, _, mut sums_write) = DirectedChannel::create(0u64, 0);
+        let workers: Workers = vec![
+            (
+                "producer",
+                Box::new(move |data_key| *numbers_write.get_mut(data_key) += 1),
+            ),
+            (
+                "doubler",
+                Box::new(move |data_key| {
+                    *doubled_write.get_mut(data_key) = 2 * *numbers_read.get(data_key);
+                }),
+            ),
+            (
+                "summer",
+                Box::new(move |data_key| {
+                    *sums_write.get_mut(data_key) += *doubled_read.get(data_key)
+                }),
+            ),
+        ];
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let recorded_by_coordinator = recorded.clone();
+        let coordinator: Coordinator = Box::new(move |channel_key| {
+            numbers.flush(channel_key);
+            doubled.flush(channel_key);
+            sums.flush(channel_key);
+            let recorded = &mut *recorded_by_coordinator.lock().unwrap();
+            recorded.push(sums.inspect(channel_key, |read_only, _| *read_only));
+            if recorded.len() < 5 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+        (workers, coordinator, recorded)
+    }
+
+    #[test]
+    fn sim_scheduler_matches_phase_runner() {
+        let mut runner = PhaseRunner::new(unsafe { MasterKey::create_unlimited() });
+        let (workers, coordinator, expected) = pipeline();
+        for (name, worker) in workers {
+            runner.add_worker(name, worker);
+        }
+        runner.set_coordinator(coordinator);
+        assert_eq!(runner.run().unwrap(), 5);
+        let expected = expected.lock().unwrap().clone();
+        assert_eq!(expected, [0, 0, 2, 6, 12]);
+
+        let mut master_key = Some(runner.into_master_key());
+        for seed in 0..20 {
+            let mut scheduler = SimScheduler::new(master_key.take().unwrap()).with_seed(seed);
+            let (workers, coordinator, recorded) = pipeline();
+            for (name, worker) in workers {
+                scheduler.add_worker(name, worker);
+            }
+            scheduler.set_coordinator(coordinator);
+            assert_eq!(scheduler.run(), 5);
+            assert_eq!(*recorded.lock().unwrap(), expected, "seed {}", seed);
+            assert_eq!(scheduler.step(), None);
+            master_key = Some(scheduler.into_master_key());
+        }
+    }
+
+    #[test]
+    fn sim_scheduler_steps_through_script() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut scheduler = SimScheduler::new(unsafe { MasterKey::create_unlimited() })
+            .with_script([vec![1, 0], vec![0, 1]]);
+        for name in ["a", "b"] {
+            let log = log.clone();
+            scheduler.add_worker(name, move |_| log.borrow_mut().push(name));
+        }
+        let inspected = Rc::new(RefCell::new(0));
+        let inspected_by_inspector = inspected.clone();
+        scheduler.set_inspector(move |_| *inspected_by_inspector.borrow_mut() += 1);
+
+        assert_eq!(scheduler.step(), Some(SimStep::Worker(1)));
+        assert_eq!(*log.borrow(), ["b"]);
+        assert_eq!(scheduler.run_for(3), 3);
+        assert_eq!(*log.borrow(), ["b", "a", "a", "b", "a", "b"]);
+        assert_eq!(scheduler.history().len(), 9);
+        assert_eq!(*inspected.borrow(), 9);
+        assert_eq!(scheduler.phases(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 of 3 items were not received, starting with 2")]
+    fn assert_delivered_in_order_reports_lost_items() {
+        assert_delivered_in_order(&[0, 1, 2], &[0, 1]);
+    }
+}
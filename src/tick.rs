@@ -0,0 +1,232 @@
+//! A coordinator that runs the phases of a [PhaseRunner] at a fixed rate, e.g. a simulation at 120 Hz.
+//!
+//! The deadline of each tick is computed from the start of the run, such that sleeping does not accumulate drift.
+//! If a tick ends after the deadline of the next one, the [MissedTickPolicy] decides whether the missed ticks are caught up or skipped.
+
+use core::ops::ControlFlow;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    group::ChannelGroup,
+    runner::{PhaseRunner, WorkerPanic},
+    ChannelKey,
+};
+
+/// What a [TickCoordinator] does if the deadlines of further ticks passed while a tick was running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MissedTickPolicy {
+    /// Run the missed ticks back to back without sleeping, until the ticks are on time again.
+    /// Every tick is run, but each missed one is run late.
+    CatchUp,
+    /// Skip the missed ticks and continue with the next deadline that did not pass yet.
+    Skip,
+}
+
+/// Statistics about the ticks of the last run of a [TickCoordinator].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickStats {
+    /// The number of ticks that were run.
+    pub ticks: u64,
+    /// The number of ticks that were missed, i.e. with [MissedTickPolicy::Skip] the number of skipped ticks,
+    /// and with [MissedTickPolicy::CatchUp] the number of ticks that started at least one period late.
+    pub missed: u64,
+    /// The maximum time a tick started after its deadline.
+    pub max_lateness: Duration,
+}
+
+/// Runs the phases of a [PhaseRunner] at a fixed rate.
+///
+/// Each tick consists of a data phase of the workers of the runner, followed by a channel phase, e.g. advancing a [ChannelGroup],
+/// after which the coordinator sleeps until the deadline of the next tick.
+/// Missed ticks are skipped by default, see [TickCoordinator::set_missed_tick_policy].
+#[derive(Debug)]
+pub struct TickCoordinator {
+    runner: PhaseRunner,
+    period: Duration,
+    policy: MissedTickPolicy,
+    stats: TickStats,
+}
+
+impl TickCoordinator {
+    /// Create a tick coordinator that runs the phases of the given runner at the given rate in ticks per second.
+    /// The coordinator of the runner is not used.
+    ///
+    /// **Panics** if the rate is not positive and finite.
+    pub fn new(runner: PhaseRunner, rate: f64) -> Self {
+        assert!(
+            rate > 0.0 && rate.is_finite(),
+            "tick rate must be positive and finite"
+        );
+        Self::with_period(runner, Duration::from_secs_f64(1.0 / rate))
+    }
+
+    /// Create a tick coordinator that runs the phases of the given runner once per the given period.
+    /// The coordinator of the runner is not used.
+    ///
+    /// **Panics** if the period is zero.
+    pub fn with_period(runner: PhaseRunner, period: Duration) -> Self {
+        assert!(period > Duration::ZERO, "tick period must not be zero");
+        Self {
+            runner,
+            period,
+            policy: MissedTickPolicy::Skip,
+            stats: TickStats::default(),
+        }
+    }
+
+    /// Set the policy for ticks whose deadline passed while a previous tick was running.
+    pub fn set_missed_tick_policy(&mut self, policy: MissedTickPolicy) {
+        self.policy = policy;
+    }
+
+    /// The runner whose phases are run, e.g. to add workers.
+    pub fn runner_mut(&mut self) -> &mut PhaseRunner {
+        &mut self.runner
+    }
+
+    /// Run ticks until `stop` returns `true` after a channel phase, or a worker panics.
+    /// The given function is called in the channel phase of each tick.
+    /// Returns the number of ticks, see [TickCoordinator::stats] for further statistics.
+    ///
+    /// See [PhaseRunner::run] for the handling of panics.
+    pub fn run_until(
+        &mut self,
+        mut tick: impl FnMut(&ChannelKey),
+        stop: impl Fn() -> bool,
+    ) -> Result<usize, WorkerPanic> {
+        let period = self.period;
+        let policy = self.policy;
+        let stats = &mut self.stats;
+        *stats = TickStats::default();
+
+        let mut deadline = Instant::now();
+        self.runner.run_with(&mut |channel_key| {
+            tick(channel_key);
+            stats.ticks += 1;
+            if stop() {
+                return ControlFlow::Break(());
+            }
+
+            deadline += period;
+            let now = Instant::now();
+            if now < deadline {
+                thread::sleep(deadline - now);
+            }
+            let lateness = Instant::now().saturating_duration_since(deadline);
+            stats.max_lateness = stats.max_lateness.max(lateness);
+            let periods_late = (lateness.as_nanos() / period.as_nanos()) as u32;
+            if periods_late > 0 {
+                match policy {
+                    MissedTickPolicy::CatchUp => stats.missed += 1,
+                    MissedTickPolicy::Skip => {
+                        stats.missed += u64::from(periods_late);
+                        deadline += period * periods_late;
+                    }
+                }
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Like [TickCoordinator::run_until], advancing all channels of the given group in each tick.
+    pub fn run_group_until(
+        &mut self,
+        group: &mut ChannelGroup,
+        stop: impl Fn() -> bool,
+    ) -> Result<usize, WorkerPanic> {
+        self.run_until(|channel_key| group.advance_all(channel_key), stop)
+    }
+
+    /// Statistics about the ticks of the last run.
+    pub fn stats(&self) -> TickStats {
+        self.stats
+    }
+
+    /// Return the runner, e.g. to run it without a fixed rate.
+    pub fn into_runner(self) -> PhaseRunner {
+        self.runner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        group::ChannelGroup,
+        runner::PhaseRunner,
+        tick::{MissedTickPolicy, TickCoordinator},
+        undirected::UndirectedChannel,
+        MasterKey,
+    };
+
+    const PERIOD: Duration = Duration::from_millis(5);
+
+    #[test]
+    fn test() {
+        let (channel_pointer, mut data_pointer1, data_pointer2) = UndirectedChannel::create(0, 0);
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let mut group = ChannelGroup::new();
+        group.push_undirected(&master_key.get_channel_key(), "counter", channel_pointer);
+        let phases = Arc::new(AtomicUsize::new(0));
+
+        let mut coordinator = TickCoordinator::new(PhaseRunner::new(master_key), 200.0);
+        let worker_phases = phases.clone();
+        coordinator
+            .runner_mut()
+            .add_worker("worker", move |data_key| {
+                *data_pointer1.get_mut(data_key) += 1;
+                worker_phases.fetch_add(1, Ordering::Relaxed);
+            });
+
+        let start = Instant::now();
+        let stop_after = Arc::clone(&phases);
+        let ticks = coordinator
+            .run_group_until(&mut group, || stop_after.load(Ordering::Relaxed) == 10)
+            .unwrap();
+        // The first tick starts immediately, and each further tick one period after the previous deadline.
+        assert!(start.elapsed() >= PERIOD * 9);
+        assert_eq!((ticks, coordinator.stats().ticks), (10, 10));
+        drop(data_pointer2);
+    }
+
+    #[test]
+    fn missed_ticks() {
+        for policy in [MissedTickPolicy::Skip, MissedTickPolicy::CatchUp] {
+            let mut coordinator = TickCoordinator::with_period(
+                PhaseRunner::new(unsafe { MasterKey::create_unlimited() }),
+                PERIOD,
+            );
+            coordinator.set_missed_tick_policy(policy);
+            let tick = Cell::new(0);
+            coordinator
+                .run_until(
+                    |_| {
+                        tick.set(tick.get() + 1);
+                        if tick.get() == 2 {
+                            thread::sleep(PERIOD * 3);
+                        }
+                    },
+                    || tick.get() == 5,
+                )
+                .unwrap();
+
+            // The second tick overruns the deadlines of the third and the fourth tick.
+            let stats = coordinator.stats();
+            assert_eq!(stats.ticks, 5);
+            assert!(stats.missed >= 2, "{:?}: {:?}", policy, stats);
+            assert!(stats.max_lateness >= PERIOD * 2);
+        }
+    }
+}
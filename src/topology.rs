@@ -0,0 +1,489 @@
+//! A declarative description of the threads of an application and the channels between them.
+//! Nodes are threads, and edges are channels of a kind and with payload types.
+//! Building a [Topology] creates all channels, registers the channel pointers in a [ChannelGroup] for the coordinator,
+//! and collects the data pointers of each node in a [NodePointers] bag.
+//!
+//! This generalises the [star](crate::star) and [ring](crate::ring) topologies.
+
+use core::fmt;
+use std::{
+    any::{self, Any},
+    collections::BTreeMap,
+};
+
+use crate::{
+    bidirected::{BidirectedChannel, BidirectedDataPointer},
+    directed::{DirectedChannel, ReadOnlyDataPointer, WritableDataPointer},
+    erased::ErasedDataPointer,
+    group::{self, ChannelGroup, ChannelKind, DotChannel, GroupId},
+    registry::RegistryError,
+    undirected::{UndirectedChannel, UndirectedDataPointer},
+    ChannelKey,
+};
+
+/// A description of nodes and the channels between them, see the [module documentation](self).
+/// All errors are reported by [Topology::build].
+#[derive(Default)]
+#[must_use]
+pub struct Topology {
+    nodes: Vec<String>,
+    edges: Vec<Edge>,
+}
+
+/// The data pointers of one node of a built [Topology], addressable by the name of the node at the other end of the channel.
+/// Each data pointer can be taken exactly once.
+#[derive(Debug)]
+pub struct NodePointers {
+    name: String,
+    /// The data pointers by peer, or `None` if taken.
+    pointers: BTreeMap<String, Option<ErasedDataPointer>>,
+}
+
+/// The reason why a [Topology] could not be built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopologyError {
+    /// The node was added twice.
+    DuplicateNode(String),
+    /// An edge refers to a node that was not added.
+    UnknownNode(String),
+    /// Two edges connect the same pair of nodes, in any direction.
+    DuplicateEdge(String, String),
+    /// An edge connects a node with itself.
+    SelfLoop(String),
+}
+
+/// Creates the channel of an edge, adds its channel pointer to the group with the given label,
+/// and returns its id in the group and the data pointers of the two nodes of the edge, in order.
+type CreateEdge = Box<
+    dyn FnOnce(
+        &ChannelKey,
+        &mut ChannelGroup,
+        String,
+    ) -> (GroupId, ErasedDataPointer, ErasedDataPointer),
+>;
+
+struct Edge {
+    from: String,
+    to: String,
+    /// The label of the channel in the group, which depends on the kind of the edge.
+    label: String,
+    kind: ChannelKind,
+    payload_type_name: &'static str,
+    create: CreateEdge,
+}
+
+impl Topology {
+    /// Create a topology without nodes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node.
+    pub fn node(mut self, name: impl Into<String>) -> Self {
+        self.nodes.push(name.into());
+        self
+    }
+
+    /// Add a [directed channel](crate::directed) that transmits `Data` from node `from` to node `to`.
+    /// Node `from` gets the [WritableDataPointer], and node `to` the [ReadOnlyDataPointer].
+    /// Both `Data` fields are initialised with `Data::default()`.
+    /// The channel is labelled `from->to` in the group.
+    pub fn directed<Data: Default + Clone + 'static>(
+        self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        self.edge(
+            from,
+            to,
+            ChannelKind::Directed,
+            any::type_name::<Data>(),
+            |channel_key, group, label| {
+                let (channel_pointer, read_only, writable) =
+                    DirectedChannel::create(Data::default(), Data::default());
+                let id = group.push_directed(channel_key, label, channel_pointer);
+                (id, writable.erase(), read_only.erase())
+            },
+        )
+    }
+
+    /// Add a [bidirected channel](crate::bidirected) between node `from` and node `to`,
+    /// where `from` sends `Forward` to `to`, and `to` sends `Backward` to `from`.
+    /// Node `from` gets a `BidirectedDataPointer<Backward, Forward>`, and node `to` a `BidirectedDataPointer<Forward, Backward>`.
+    /// All `Data` fields are initialised with their `Default`.
+    /// The channel is labelled `from<->to` in the group.
+    pub fn bidirected<Forward: Default + Clone + 'static, Backward: Default + Clone + 'static>(
+        self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        self.edge(
+            from,
+            to,
+            ChannelKind::Bidirected,
+            any::type_name::<(Forward, Backward)>(),
+            |channel_key, group, label| {
+                let (channel_pointer, to_pointer, from_pointer) = BidirectedChannel::create(
+                    Forward::default(),
+                    Forward::default(),
+                    Backward::default(),
+                    Backward::default(),
+                );
+                let id = group.push_bidirected(channel_key, label, channel_pointer);
+                (id, from_pointer.erase(), to_pointer.erase())
+            },
+        )
+    }
+
+    /// Add an [undirected channel](crate::undirected) between node `from` and node `to`.
+    /// Node `from` gets the first and node `to` the second [UndirectedDataPointer].
+    /// Both `Data` fields are initialised with `Data::default()`.
+    /// The channel is labelled `from<->to` in the group.
+    pub fn undirected<Data: Default + 'static>(
+        self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        self.edge(
+            from,
+            to,
+            ChannelKind::Undirected,
+            any::type_name::<Data>(),
+            |channel_key, group, label| {
+                let (channel_pointer, data_pointer1, data_pointer2) =
+                    UndirectedChannel::create(Data::default(), Data::default());
+                let id = group.push_undirected(channel_key, label, channel_pointer);
+                (id, data_pointer1.erase(), data_pointer2.erase())
+            },
+        )
+    }
+
+    fn edge(
+        mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        kind: ChannelKind,
+        payload_type_name: &'static str,
+        create: impl FnOnce(
+                &ChannelKey,
+                &mut ChannelGroup,
+                String,
+            ) -> (GroupId, ErasedDataPointer, ErasedDataPointer)
+            + 'static,
+    ) -> Self {
+        let (from, to) = (from.into(), to.into());
+        let arrow = if kind == ChannelKind::Directed {
+            "->"
+        } else {
+            "<->"
+        };
+        let label = format!("{}{}{}", from, arrow, to);
+        self.edges.push(Edge {
+            from,
+            to,
+            label,
+            kind,
+            payload_type_name,
+            create: Box::new(create),
+        });
+        self
+    }
+
+    /// Create all channels, and add their channel pointers to a new [ChannelGroup] in the order of the edges.
+    /// Returns the group, and the data pointers of each node in the order of the nodes.
+    pub fn build(
+        self,
+        channel_key: &ChannelKey,
+    ) -> Result<(ChannelGroup, Vec<NodePointers>), TopologyError> {
+        let mut nodes = BTreeMap::new();
+        for (index, name) in self.nodes.iter().enumerate() {
+            if nodes.insert(name.as_str(), index).is_some() {
+                return Err(TopologyError::DuplicateNode(name.clone()));
+            }
+        }
+        let mut pairs = Vec::new();
+        for Edge { from, to, .. } in &self.edges {
+            for node in [from, to] {
+                if !nodes.contains_key(node.as_str()) {
+                    return Err(TopologyError::UnknownNode(node.clone()));
+                }
+            }
+            if from == to {
+                return Err(TopologyError::SelfLoop(from.clone()));
+            }
+            let pair = if from < to { (from, to) } else { (to, from) };
+            if pairs.contains(&pair) {
+                return Err(TopologyError::DuplicateEdge(from.clone(), to.clone()));
+            }
+            pairs.push(pair);
+        }
+
+        let mut group = ChannelGroup::new();
+        let mut node_pointers: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|name| NodePointers {
+                name: name.clone(),
+                pointers: BTreeMap::new(),
+            })
+            .collect();
+        for edge in self.edges {
+            let (id, from_pointer, to_pointer) = (edge.create)(channel_key, &mut group, edge.label);
+            group.set_endpoints(id, &edge.from, &edge.to);
+            node_pointers[nodes[edge.from.as_str()]]
+                .pointers
+                .insert(edge.to.clone(), Some(from_pointer));
+            node_pointers[nodes[edge.to.as_str()]]
+                .pointers
+                .insert(edge.from, Some(to_pointer));
+        }
+        Ok((group, node_pointers))
+    }
+
+    /// Render the nodes and edges as a [DOT](https://graphviz.org/doc/info/lang.html) graph, like [ChannelGroup::to_dot] renders a built topology,
+    /// but without the generation and dirty state of the channels, which do not exist yet.
+    pub fn to_dot(&self) -> String {
+        let nodes: Vec<_> = self.nodes.iter().map(String::as_str).collect();
+        group::to_dot(
+            &nodes,
+            self.edges.iter().map(|edge| DotChannel {
+                label: &edge.label,
+                kind: edge.kind,
+                payload_type_name: Some(edge.payload_type_name),
+                generation: None,
+                dirty: None,
+                endpoints: Some((&edge.from, &edge.to)),
+            }),
+        )
+    }
+}
+
+impl NodePointers {
+    /// The name of the node.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Iterate over the names of the nodes connected to this node, in order.
+    pub fn peers(&self) -> impl Iterator<Item = &str> {
+        self.pointers.keys().map(String::as_str)
+    }
+
+    /// Take the data pointer of the channel to the given peer.
+    /// The type names of a [RegistryError::WrongType] are the names of the data pointer types.
+    pub fn take<Pointer: Any>(&mut self, peer: &str) -> Result<Pointer, RegistryError> {
+        let slot = self.pointers.get_mut(peer).ok_or(RegistryError::NotFound)?;
+        let pointer = slot.take().ok_or(RegistryError::AlreadyTaken)?;
+        pointer.downcast().map_err(|pointer| {
+            let error = RegistryError::WrongType {
+                registered: pointer.type_name(),
+                requested: std::any::type_name::<Pointer>(),
+            };
+            *slot = Some(pointer);
+            error
+        })
+    }
+
+    /// Take the read-only data pointer of the directed channel from the given peer.
+    pub fn reader<Data: 'static>(
+        &mut self,
+        peer: &str,
+    ) -> Result<ReadOnlyDataPointer<Data>, RegistryError> {
+        self.take(peer)
+    }
+
+    /// Take the writable data pointer of the directed channel to the given peer.
+    pub fn writer<Data: 'static>(
+        &mut self,
+        peer: &str,
+    ) -> Result<WritableDataPointer<Data>, RegistryError> {
+        self.take(peer)
+    }
+
+    /// Take the endpoint of the bidirected channel with the given peer.
+    pub fn endpoint<Input: 'static, Output: 'static>(
+        &mut self,
+        peer: &str,
+    ) -> Result<BidirectedDataPointer<Input, Output>, RegistryError> {
+        self.take(peer)
+    }
+
+    /// Take the data pointer of the undirected channel with the given peer.
+    pub fn undirected<Data: 'static>(
+        &mut self,
+        peer: &str,
+    ) -> Result<UndirectedDataPointer<Data>, RegistryError> {
+        self.take(peer)
+    }
+}
+
+impl fmt::Debug for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Topology")
+            .field("nodes", &self.nodes)
+            .field(
+                "edges",
+                &self
+                    .edges
+                    .iter()
+                    .map(|edge| &edge.label)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyError::DuplicateNode(node) => write!(f, "node {:?} was added twice", node),
+            TopologyError::UnknownNode(node) => write!(f, "node {:?} was not added", node),
+            TopologyError::DuplicateEdge(from, to) => write!(
+                f,
+                "nodes {:?} and {:?} are connected by more than one edge",
+                from, to
+            ),
+            TopologyError::SelfLoop(node) => {
+                write!(f, "node {:?} is connected with itself", node)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        group::ChannelKind,
+        registry::RegistryError,
+        topology::{Topology, TopologyError},
+        MasterKey,
+    };
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut group, mut nodes) = Topology::new()
+            .node("sim")
+            .node("render")
+            .node("audio")
+            .directed::<Vec<u32>>("sim", "render")
+            .bidirected::<String, bool>("sim", "audio")
+            .build(&master_key.get_channel_key())
+            .unwrap();
+        assert!(group.iter().map(|(_, label, kind)| (label, kind)).eq([
+            ("sim->render", ChannelKind::Directed),
+            ("sim<->audio", ChannelKind::Bidirected),
+        ]));
+        let mut audio = nodes.pop().unwrap();
+        let mut render = nodes.pop().unwrap();
+        let mut sim = nodes.pop().unwrap();
+        assert_eq!(sim.name(), "sim");
+        assert!(sim.peers().eq(["audio", "render"]));
+
+        let mut frames = sim.writer::<Vec<u32>>("render").unwrap();
+        let mut commands = sim.endpoint::<bool, String>("audio").unwrap();
+        let rendered = render.reader::<Vec<u32>>("sim").unwrap();
+        let mut acknowledgements = audio.endpoint::<String, bool>("sim").unwrap();
+
+        let data_key = master_key.get_data_key();
+        frames.get_mut(&data_key).push(1);
+        *commands.get_output(&data_key) = String::from("play");
+        let channel_key = data_key.into_channel_key();
+        group.advance_all(&channel_key);
+        let data_key = channel_key.into_data_key();
+        assert_eq!(*rendered.get(&data_key), [1]);
+        assert_eq!(acknowledgements.get_input(&data_key), "play");
+        *acknowledgements.get_output(&data_key) = true;
+        let channel_key = data_key.into_channel_key();
+        group.advance_all(&channel_key);
+        assert!(*commands.get_input(&master_key.get_data_key()));
+    }
+
+    #[test]
+    fn lookup_errors() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (_group, mut nodes) = Topology::new()
+            .node("a")
+            .node("b")
+            .directed::<u8>("a", "b")
+            .build(&master_key.get_channel_key())
+            .unwrap();
+        let b = &mut nodes[1];
+
+        assert_eq!(b.reader::<u8>("c").err(), Some(RegistryError::NotFound));
+        assert_eq!(
+            b.writer::<u8>("a").err(),
+            Some(RegistryError::WrongType {
+                registered: "two_phase_channel::directed::ReadOnlyDataPointer<u8>",
+                requested: "two_phase_channel::directed::WritableDataPointer<u8>",
+            })
+        );
+        assert!(b.reader::<u8>("a").is_ok());
+        assert_eq!(b.reader::<u8>("a").err(), Some(RegistryError::AlreadyTaken));
+    }
+
+    #[test]
+    fn build_errors() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let channel_key = master_key.get_channel_key();
+        let error = |topology: Topology| topology.build(&channel_key).err();
+
+        assert_eq!(
+            error(Topology::new().node("a").node("a")),
+            Some(TopologyError::DuplicateNode("a".into()))
+        );
+        assert_eq!(
+            error(Topology::new().node("a").directed::<u8>("a", "b")),
+            Some(TopologyError::UnknownNode("b".into()))
+        );
+        assert_eq!(
+            error(
+                Topology::new()
+                    .node("a")
+                    .node("b")
+                    .directed::<u8>("a", "b")
+                    .undirected::<u8>("b", "a")
+            ),
+            Some(TopologyError::DuplicateEdge("b".into(), "a".into()))
+        );
+        assert_eq!(
+            error(Topology::new().node("a").undirected::<u8>("a", "a")),
+            Some(TopologyError::SelfLoop("a".into()))
+        );
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let topology = Topology::new()
+            .node("sim")
+            .node("render")
+            .node("audio")
+            .directed::<u32>("sim", "render")
+            .bidirected::<u8, bool>("render", "audio");
+        let dot = topology.to_dot();
+        assert_eq!(
+            dot,
+            "digraph {\n    node [shape=box];\n    \
+             \"sim\" [shape=ellipse];\n    \
+             \"render\" [shape=ellipse];\n    \
+             \"audio\" [shape=ellipse];\n    \
+             channel0 [label=\"sim->render\\ndirected\\nu32\"];\n    \
+             \"sim\" -> channel0;\n    \
+             channel0 -> \"render\";\n    \
+             channel1 [label=\"render<->audio\\nbidirected\\n(u8, bool)\"];\n    \
+             \"render\" -> channel1 [dir=both];\n    \
+             channel1 -> \"audio\" [dir=both];\n\
+             }\n"
+        );
+
+        let (group, _nodes) = topology.build(&master_key.get_channel_key()).unwrap();
+        // The built group additionally shows the state of each channel, which is dirty until its first flush.
+        let dot = dot
+            .replace("u32\"", "u32\\ngeneration 0\\ndirty\"")
+            .replace("bool)\"", "bool)\\ngeneration 0\\ndirty\"");
+        assert_eq!(group.to_dot(&master_key.get_channel_key()), dot);
+    }
+}
@@ -2,9 +2,40 @@
 //! Both instances of the transmitted data are readable and writable,
 //! and the data is swapped instead of being sent only in one direction.
 
-use std::mem;
+use core::{
+    mem::MaybeUninit,
+    sync::atomic::{self, Ordering},
+};
+use std::{
+    any::{self, Any},
+    borrow::Cow,
+    mem, ptr,
+    time::Instant,
+};
 
-use crate::{ChannelKey, DataKey};
+#[cfg(feature = "fault-injection")]
+use std::sync::Arc;
+
+#[cfg(feature = "checksum")]
+use crate::checksum::Checksum;
+pub use crate::common::SwapStats;
+#[cfg(feature = "fault-injection")]
+use crate::fault::{self, FaultHook, FaultPlan, FaultSite};
+use crate::{
+    allocator::Allocator,
+    arena::ChannelBox,
+    capacity::ManageCapacity,
+    checked::{CheckedChannel, CheckedPointer},
+    common,
+    erased::{self, DestroyError, ErasedDataPointer, ErasedDestroy},
+    heap::{self, Zeroable},
+    instrument,
+    observer::{ObserverPointer, Observers},
+    report::{self, ChannelReport},
+    rotating::Rotation,
+    split::{SplitUndirectedChannel, SplitUndirectedChannelPointer, SplitUndirectedDataPointer},
+    CachePadded, ChannelKey, DataKey, GenerationPointer, Hook, Label, Projection, SwapChannel,
+};
 
 /// An undirected channel used for communication between threads.
 /// It holds two instances of `Data`, which can be accessed or swapped.
@@ -15,8 +46,56 @@ use crate::{ChannelKey, DataKey};
 /// See [UndirectedChannel::create] for more info.
 #[derive(Debug)]
 pub struct UndirectedChannel<Data> {
-    data1: Data,
-    data2: Data,
+    pub(crate) data1: CachePadded<Data>,
+    pub(crate) data2: CachePadded<Data>,
+    pub(crate) generation: u64,
+    pub(crate) stats: SwapStats,
+    #[cfg(feature = "checksum")]
+    pub(crate) checksum: Option<Checksum<Data>>,
+    checked: CheckedChannel,
+    #[cfg(feature = "fault-injection")]
+    fault: Option<FaultHook>,
+}
+
+/// A copy of both `Data` fields of an undirected channel, taken during the channel phase.
+/// See [UndirectedChannelPointer::snapshot].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UndirectedSnapshot<Data> {
+    /// The `Data` pointed to by the first data pointer.
+    pub data1: Data,
+    /// The `Data` pointed to by the second data pointer.
+    pub data2: Data,
+}
+
+/// Serialises like an [UndirectedSnapshot], but without cloning the `Data` fields.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(rename = "UndirectedSnapshot")]
+struct UndirectedSnapshotRef<'a, Data> {
+    data1: &'a Data,
+    data2: &'a Data,
+}
+
+/// One of the two physical `Data` fields of an undirected channel.
+/// The first data pointer handed out by [UndirectedChannel::create] always points to the [Side::First] field,
+/// and the second to the [Side::Second] field, while swaps exchange the content of the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// The first `Data` field.
+    First,
+    /// The second `Data` field.
+    Second,
+}
+
+impl Side {
+    /// Returns the other side.
+    pub fn other(self) -> Self {
+        match self {
+            Side::First => Side::Second,
+            Side::Second => Side::First,
+        }
+    }
 }
 
 /// A pointer to an undirected channel.
@@ -26,7 +105,12 @@ pub struct UndirectedChannel<Data> {
 #[derive(Debug)]
 #[must_use]
 pub struct UndirectedChannelPointer<Data> {
-    channel: Box<UndirectedChannel<Data>>,
+    /// Dropped first, such that reading observers are waited for before the channel is freed.
+    observers: Observers,
+    pub(crate) channel: ChannelBox<UndirectedChannel<Data>>,
+    on_swap: Hook<u64>,
+    label: Label,
+    created_at: Option<Instant>,
 }
 
 /// A pointer to one of the data fields in an undirected channel.
@@ -36,7 +120,9 @@ pub struct UndirectedChannelPointer<Data> {
 #[derive(Debug)]
 #[must_use]
 pub struct UndirectedDataPointer<Data> {
-    data: *mut Data,
+    pub(crate) data: *mut Data,
+    generation: GenerationPointer,
+    checked: CheckedPointer,
 }
 
 /// An immutable pointer to one of the data fields in an undirected channel.
@@ -47,12 +133,30 @@ pub struct UndirectedDataPointer<Data> {
 #[must_use]
 pub struct ImmutableUndirectedDataPointer<Data> {
     data: *const Data,
+    generation: GenerationPointer,
+    checked: CheckedPointer,
+}
+
+/// An immutable pointer to one of the data fields in an undirected channel that can be converted back into an [UndirectedDataPointer],
+/// see [UpgradableUndirectedDataPointer::into_mutable].
+/// It can only be accessed using a [DataKey].
+///
+/// Unlike an [ImmutableUndirectedDataPointer], this pointer cannot be copied,
+/// hence no other pointer reads the `Data` field once it is mutable again.
+#[derive(Debug)]
+#[must_use]
+pub struct UpgradableUndirectedDataPointer<Data> {
+    data: *const Data,
+    generation: GenerationPointer,
+    checked: CheckedPointer,
 }
 
 impl<Data> UndirectedChannel<Data> {
     /// Create an undirected channel and hand out three pointers to it.
     /// One [UndirectedChannelPointer] used to swap the content of the two `Data` fields,
     /// and two [UndirectedDataPointer]s, one to each data field.
+    ///
+    /// This allocates the channel, and is hence not [real-time safe](crate::rt).
     pub fn create(
         data1: Data,
         data2: Data,
@@ -60,20 +164,138 @@ impl<Data> UndirectedChannel<Data> {
         UndirectedChannelPointer<Data>,
         UndirectedDataPointer<Data>,
         UndirectedDataPointer<Data>,
+    ) {
+        Self::hand_out(ChannelBox::new(Self::new(data1, data2)))
+    }
+
+    /// Create an undirected channel like [UndirectedChannel::create], but allocate it with the given allocator.
+    /// The allocator is moved into the allocation, and dropped when the channel is destroyed.
+    pub fn create_in<A: Allocator + Send + 'static>(
+        data1: Data,
+        data2: Data,
+        allocator: A,
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        Self::hand_out(ChannelBox::new_in(Self::new(data1, data2), allocator))
+    }
+
+    pub(crate) fn new(data1: Data, data2: Data) -> Self {
+        UndirectedChannel {
+            data1: CachePadded(data1),
+            data2: CachePadded(data2),
+            generation: 0,
+            stats: SwapStats::default(),
+            #[cfg(feature = "checksum")]
+            checksum: None,
+            checked: CheckedChannel::new(),
+            #[cfg(feature = "fault-injection")]
+            fault: None,
+        }
+    }
+
+    /// Create an undirected channel like [UndirectedChannel::create],
+    /// where both `Data` fields are initialised in place on the heap by the given function.
+    /// This avoids materialising large `Data` on the stack.
+    ///
+    /// # Safety
+    ///
+    /// The given function must fully initialise the given `Data`.
+    pub unsafe fn create_boxed_with(
+        f: impl Fn(&mut MaybeUninit<Data>),
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        Self::create_in_place(false, |data| f(&mut *(data as *mut MaybeUninit<Data>)))
+    }
+
+    /// Allocate the channel and initialise the `Data` fields in place with the given function.
+    ///
+    /// # Safety
+    ///
+    /// The given function must fully initialise the given `Data`, where the memory of `Data` is zeroed if `zeroed` is `true`.
+    unsafe fn create_in_place(
+        zeroed: bool,
+        init: impl Fn(*mut Data),
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        let channel = Self::allocate_uninit(zeroed);
+        init(ptr::addr_of_mut!((*channel).data1.0));
+        init(ptr::addr_of_mut!((*channel).data2.0));
+        Self::hand_out(ChannelBox::from_box(Box::from_raw(channel)))
+    }
+
+    /// Allocate an undirected channel on the heap where all fields except for the `Data` fields are initialised.
+    pub(crate) fn allocate_uninit(zeroed: bool) -> *mut Self {
+        let channel = heap::allocate::<Self>(zeroed);
+        unsafe {
+            ptr::addr_of_mut!((*channel).generation).write(0);
+            ptr::addr_of_mut!((*channel).stats).write(SwapStats::default());
+            #[cfg(feature = "checksum")]
+            ptr::addr_of_mut!((*channel).checksum).write(None);
+            ptr::addr_of_mut!((*channel).checked).write(CheckedChannel::new());
+            #[cfg(feature = "fault-injection")]
+            ptr::addr_of_mut!((*channel).fault).write(None);
+        }
+        channel
+    }
+
+    /// Wrap the given channel into a channel pointer and create the data pointers to it.
+    pub(crate) fn hand_out(
+        channel: ChannelBox<Self>,
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
     ) {
         let mut channel_pointer = UndirectedChannelPointer {
-            channel: Box::new(UndirectedChannel { data1, data2 }),
+            channel,
+            on_swap: Hook::default(),
+            label: Label::default(),
+            created_at: report::now(),
+            observers: Observers::default(),
         };
+        let (data_pointer1, data_pointer2) = channel_pointer.channel.data_pointers();
+        (channel_pointer, data_pointer1, data_pointer2)
+    }
+
+    pub(crate) fn data_pointers(
+        &mut self,
+    ) -> (UndirectedDataPointer<Data>, UndirectedDataPointer<Data>) {
+        let generation = GenerationPointer::new(&self.generation);
+        let checked = self.checked.pointer();
         let data_pointer1 = UndirectedDataPointer {
-            data: (&mut channel_pointer.channel.data1) as *mut Data,
+            data: (&mut self.data1.0) as *mut Data,
+            generation,
+            checked,
         };
         let data_pointer2 = UndirectedDataPointer {
-            data: (&mut channel_pointer.channel.data2) as *mut Data,
+            data: (&mut self.data2.0) as *mut Data,
+            generation,
+            checked,
         };
-        (channel_pointer, data_pointer1, data_pointer2)
+        (data_pointer1, data_pointer2)
+    }
+
+    /// Swap the two `Data` fields and update the generation and statistics.
+    pub(crate) fn swap(&mut self, channel_key: &ChannelKey) {
+        self.verify_checksum(channel_key);
+        mem::swap(&mut self.data1, &mut self.data2);
+        self.generation += 1;
+        self.stats.swaps += 1;
+        self.stats.last_swap_generation = self.generation;
+        self.record_checksum(channel_key);
     }
 
     /// Destroys the undirected channel linked with the three pointers (see [UndirectedChannel::create]).
+    /// This frees the channel, and is hence not [real-time safe](crate::rt).
     ///
     /// **Panics** if not all three pointers point to the same channel.
     pub fn destroy(
@@ -81,23 +303,97 @@ impl<Data> UndirectedChannel<Data> {
         data_pointer1: UndirectedDataPointer<Data>,
         data_pointer2: UndirectedDataPointer<Data>,
     ) -> (Data, Data) {
-        let UndirectedChannelPointer { mut channel } = channel_pointer;
-        let channel_data_pointer1 = (&mut channel.data1) as *mut Data;
-        let channel_data_pointer2 = (&mut channel.data2) as *mut Data;
+        let channel =
+            Self::into_channel(channel_pointer, data_pointer1, data_pointer2).into_inner();
+        (channel.data1.0, channel.data2.0)
+    }
+
+    /// Destroys the undirected channel linked with the three pointers like [UndirectedChannel::destroy],
+    /// and additionally returns the [ChannelReport] of the channel, e.g. to log its usage at shutdown.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy_with_report(
+        channel_pointer: UndirectedChannelPointer<Data>,
+        data_pointer1: UndirectedDataPointer<Data>,
+        data_pointer2: UndirectedDataPointer<Data>,
+    ) -> (Data, Data, ChannelReport) {
+        let stats = channel_pointer.channel.stats;
+        let report = ChannelReport::new(
+            &channel_pointer.label,
+            stats.swaps,
+            stats.skipped,
+            channel_pointer.created_at,
+            channel_pointer.channel.generation,
+        );
+        let (data1, data2) = Self::destroy(channel_pointer, data_pointer1, data_pointer2);
+        (data1, data2, report)
+    }
+
+    /// Destroys the undirected channel linked with the three pointers (see [UndirectedChannel::create]),
+    /// moving the `Data` fields into separate boxes on the heap.
+    /// This avoids materialising large `Data` on the stack.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy_boxed(
+        channel_pointer: UndirectedChannelPointer<Data>,
+        data_pointer1: UndirectedDataPointer<Data>,
+        data_pointer2: UndirectedDataPointer<Data>,
+    ) -> (Box<Data>, Box<Data>) {
+        let channel = Box::into_raw(
+            Self::into_channel(channel_pointer, data_pointer1, data_pointer2).into_box(),
+        );
+        unsafe {
+            let data1 = heap::allocate::<Data>(false);
+            let data2 = heap::allocate::<Data>(false);
+            ptr::copy_nonoverlapping(ptr::addr_of!((*channel).data1.0), data1, 1);
+            ptr::copy_nonoverlapping(ptr::addr_of!((*channel).data2.0), data2, 1);
+            #[cfg(feature = "checksum")]
+            ptr::drop_in_place(ptr::addr_of_mut!((*channel).checksum));
+            ptr::drop_in_place(ptr::addr_of_mut!((*channel).checked));
+            #[cfg(feature = "fault-injection")]
+            ptr::drop_in_place(ptr::addr_of_mut!((*channel).fault));
+            // Free the channel without dropping the moved `Data` fields.
+            drop(Box::from_raw(channel as *mut MaybeUninit<Self>));
+            (Box::from_raw(data1), Box::from_raw(data2))
+        }
+    }
+
+    /// Unwrap the channel from the given channel pointer.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    fn into_channel(
+        channel_pointer: UndirectedChannelPointer<Data>,
+        data_pointer1: UndirectedDataPointer<Data>,
+        data_pointer2: UndirectedDataPointer<Data>,
+    ) -> ChannelBox<Self> {
+        let UndirectedChannelPointer {
+            mut channel,
+            label,
+            observers,
+            ..
+        } = channel_pointer;
+        // Wait for reading observers before the channel is freed.
+        drop(observers);
+        let channel_data_pointer1 = (&mut channel.data1.0) as *mut Data;
+        let channel_data_pointer2 = (&mut channel.data2.0) as *mut Data;
         let UndirectedDataPointer {
             data: data_pointer1,
+            ..
         } = data_pointer1;
         let UndirectedDataPointer {
             data: data_pointer2,
+            ..
         } = data_pointer2;
 
         assert!(
             (channel_data_pointer1 == data_pointer1 && channel_data_pointer2 == data_pointer2)
                 || (channel_data_pointer1 == data_pointer2
-                    && channel_data_pointer2 == data_pointer1)
+                    && channel_data_pointer2 == data_pointer1),
+            "the data pointers do not point to the {}",
+            label
         );
 
-        (channel.data1, channel.data2)
+        channel
     }
 
     /// Destroys the undirected channel linked with the pointers (see [UndirectedChannel::create]).
@@ -108,27 +404,168 @@ impl<Data> UndirectedChannel<Data> {
         data_pointer1: UndirectedDataPointer<Data>,
         data_pointer2: impl IntoIterator<Item = ImmutableUndirectedDataPointer<Data>>,
     ) -> (Data, Data) {
-        let UndirectedChannelPointer { mut channel } = channel_pointer;
-        let channel_data_pointer1 = (&mut channel.data1) as *mut Data;
-        let channel_data_pointer2 = (&mut channel.data2) as *mut Data;
+        let UndirectedChannelPointer {
+            mut channel,
+            label,
+            observers,
+            ..
+        } = channel_pointer;
+        // Wait for reading observers before the channel is freed.
+        drop(observers);
+        let channel_data_pointer1 = (&mut channel.data1.0) as *mut Data;
+        let channel_data_pointer2 = (&mut channel.data2.0) as *mut Data;
         let UndirectedDataPointer {
             data: data_pointer1,
+            ..
         } = data_pointer1;
 
         for data_pointer2 in data_pointer2 {
             let ImmutableUndirectedDataPointer {
                 data: data_pointer2,
+                ..
             } = data_pointer2;
 
             assert!(
                 (channel_data_pointer1 == data_pointer1
-                    && channel_data_pointer2 as *const Data == data_pointer2)
-                    || (channel_data_pointer1 as *const Data == data_pointer2
-                        && channel_data_pointer2 == data_pointer1)
+                    && ptr::eq(channel_data_pointer2, data_pointer2))
+                    || (ptr::eq(channel_data_pointer1, data_pointer2)
+                        && channel_data_pointer2 == data_pointer1),
+                "the data pointers do not point to the {}",
+                label
+            );
+        }
+
+        let channel = channel.into_inner();
+        (channel.data1.0, channel.data2.0)
+    }
+
+    /// Destroys the undirected channel linked with the pointers (see [UndirectedChannel::create]),
+    /// where both data pointers were converted into [ImmutableUndirectedDataPointer]s.
+    ///
+    /// **Panics** if not all pointers point to the same channel, or if there is not at least one pointer to each of the two `Data` fields.
+    pub fn destroy_all_immutable(
+        channel_pointer: UndirectedChannelPointer<Data>,
+        data_pointers: impl IntoIterator<Item = ImmutableUndirectedDataPointer<Data>>,
+    ) -> (Data, Data) {
+        let UndirectedChannelPointer {
+            channel,
+            label,
+            observers,
+            ..
+        } = channel_pointer;
+        // Wait for reading observers before the channel is freed.
+        drop(observers);
+        let channel_data_pointer1 = (&channel.data1.0) as *const Data;
+        let channel_data_pointer2 = (&channel.data2.0) as *const Data;
+        let mut seen1 = false;
+        let mut seen2 = false;
+
+        for data_pointer in data_pointers {
+            let ImmutableUndirectedDataPointer {
+                data: data_pointer, ..
+            } = data_pointer;
+
+            if data_pointer == channel_data_pointer1 {
+                seen1 = true;
+            } else if data_pointer == channel_data_pointer2 {
+                seen2 = true;
+            } else {
+                panic!("data pointer does not point to the {}", label);
+            }
+        }
+        assert!(
+            seen1 && seen2,
+            "a `Data` field of the {} has no data pointer",
+            label
+        );
+
+        let channel = channel.into_inner();
+        (channel.data1.0, channel.data2.0)
+    }
+}
+
+impl<Data> UndirectedChannel<Data> {
+    /// Create an undirected channel where each `Data` field lives in its own allocation.
+    /// Shorthand for [SplitUndirectedChannel::create], see the [split](crate::split) module for when this is beneficial.
+    pub fn create_split(
+        data1: Data,
+        data2: Data,
+    ) -> (
+        SplitUndirectedChannelPointer<Data>,
+        SplitUndirectedDataPointer<Data>,
+        SplitUndirectedDataPointer<Data>,
+    ) {
+        SplitUndirectedChannel::create(data1, data2)
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<Data: core::hash::Hash> UndirectedChannel<Data> {
+    /// Create an undirected channel like [UndirectedChannel::create], whose `Data` fields are hashed at the end of each channel operation.
+    /// At the start of the next channel operation, the hashes are verified if the master key of its channel key did not create a data key in between,
+    /// which detects writes through references that outlived their data phase.
+    ///
+    /// This costs hashing both `Data` fields twice per channel operation, and is intended for debugging only.
+    pub fn create_checksummed(
+        data1: Data,
+        data2: Data,
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        let (mut channel_pointer, data_pointer1, data_pointer2) = Self::create(data1, data2);
+        let channel: &mut UndirectedChannel<Data> = &mut channel_pointer.channel;
+        channel.checksum = Some(Checksum::new());
+        (channel_pointer, data_pointer1, data_pointer2)
+    }
+}
+
+impl<Data> UndirectedChannel<Data> {
+    /// Verify the checksum of the channel, if it is checksummed, at the start of a channel operation.
+    /// With the `checked-backend` feature, this also checks that no data key of another master key may still access the channel.
+    ///
+    /// **Panics** if the `Data` fields were changed since the last channel operation without a data key being created.
+    #[track_caller]
+    pub(crate) fn verify_checksum(&self, #[allow(unused)] channel_key: &ChannelKey) {
+        self.checked.advance();
+        #[cfg(feature = "checksum")]
+        if let Some(checksum) = &self.checksum {
+            checksum.verify(
+                &self.data1.0,
+                &self.data2.0,
+                self as *const Self as *const (),
+                channel_key,
             );
         }
+    }
+
+    /// The `Data` field on the given side.
+    pub(crate) fn side_mut(&mut self, side: Side) -> &mut Data {
+        match side {
+            Side::First => &mut self.data1.0,
+            Side::Second => &mut self.data2.0,
+        }
+    }
+
+    /// Record the checksum of the channel, if it is checksummed, at the end of a channel operation.
+    pub(crate) fn record_checksum(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        #[cfg(feature = "checksum")]
+        if let Some(checksum) = &mut self.checksum {
+            checksum.record(&self.data1.0, &self.data2.0, channel_key);
+        }
+    }
+}
 
-        (channel.data1, channel.data2)
+impl<Data: Zeroable> UndirectedChannel<Data> {
+    /// Create an undirected channel like [UndirectedChannel::create], where both `Data` fields are zeroed in place on the heap.
+    /// This avoids materialising large `Data` on the stack.
+    pub fn create_boxed_zeroed() -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        unsafe { Self::create_in_place(true, |_| {}) }
     }
 }
 
@@ -149,11 +586,231 @@ impl<Data: Clone> UndirectedChannel<Data> {
     }
 }
 
+impl<T: ?Sized> UndirectedChannel<Box<T>> {
+    /// Create an undirected channel of boxed, possibly unsized values such as trait objects, and hand out three pointers to it.
+    /// This is the same as [UndirectedChannel::create], but guides type inference when the given boxes are coerced to `Box<T>`.
+    /// Swapping exchanges only the boxes, the boxed values themselves are never moved.
+    #[allow(clippy::type_complexity)]
+    pub fn create_dyn(
+        data1: Box<T>,
+        data2: Box<T>,
+    ) -> (
+        UndirectedChannelPointer<Box<T>>,
+        UndirectedDataPointer<Box<T>>,
+        UndirectedDataPointer<Box<T>>,
+    ) {
+        Self::create(data1, data2)
+    }
+}
+
 impl<Data> UndirectedChannelPointer<Data> {
+    /// Convert this channel pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this channel pointer, and must be converted back with [UndirectedChannelPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
+
+    /// Reconstitute a channel pointer from a raw pointer returned by [UndirectedChannelPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [UndirectedChannelPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
+
+    /// Attach a label to the channel, which is used in the panic messages of destroying the channel and returned by [UndirectedChannelPointer::label].
+    pub fn with_label(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.label.set(label);
+        self
+    }
+
+    /// The label of the channel, if it was set via [UndirectedChannelPointer::with_label].
+    pub fn label(&self) -> Option<&str> {
+        self.label.get()
+    }
+
+    /// Consult the given fault plan at each swap, see the [fault] module.
+    /// The faults are recorded with the current label of the channel.
+    #[cfg(feature = "fault-injection")]
+    pub fn set_fault_plan(&mut self, plan: Arc<FaultPlan>) {
+        self.channel.fault = Some(FaultHook::new(plan, self.label.get()));
+    }
+
     /// Swap the two `Data` fields in the undirected channel.
-    pub fn swap(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+    pub fn swap(&mut self, channel_key: &ChannelKey) {
+        #[cfg(feature = "fault-injection")]
+        if !fault::inject(&self.channel.fault, FaultSite::Swap) {
+            return;
+        }
+        self.channel.swap(channel_key);
+        instrument::swap(
+            &self.label,
+            mem::size_of::<Data>(),
+            self.channel.generation,
+            false,
+        );
+        self.on_swap.call(self.channel.generation);
+    }
+
+    /// Swap the two `Data` fields like [Self::swap], with a [fence](atomic::fence) of the given ordering before and after the swap.
+    /// The fences are also compiler fences, such that volatile accesses of the last data phase are not moved into or past the swap,
+    /// e.g. if one `Data` field is filled by a DMA engine.
+    ///
+    /// **Panics** if `ordering` is [Ordering::Relaxed].
+    pub fn swap_with_fence(&mut self, channel_key: &ChannelKey, ordering: Ordering) {
+        atomic::fence(ordering);
+        self.swap(channel_key);
+        atomic::fence(ordering);
+    }
+
+    /// Set a hook that is called at the end of each swap with the generation of the channel after the swap
+    /// (see [SwapStats::last_swap_generation]).
+    /// The hook runs inside the swap, i.e. while the channel key is held.
+    /// Conditional swaps that do not swap do not call the hook.
+    pub fn set_on_swap(&mut self, hook: Box<dyn FnMut(u64) + Send>) {
+        self.on_swap.set(hook);
+    }
+
+    /// Swap the two `Data` fields in the undirected channel if the given predicate holds for them.
+    /// Returns `true` if the `Data` fields were swapped.
+    pub fn swap_if(
+        &mut self,
+        channel_key: &ChannelKey,
+        predicate: impl FnOnce(&Data, &Data) -> bool,
+    ) -> bool {
+        if predicate(&self.channel.data1, &self.channel.data2) {
+            self.swap(channel_key);
+            true
+        } else {
+            self.channel.stats.skipped += 1;
+            instrument::swap(
+                &self.label,
+                mem::size_of::<Data>(),
+                self.channel.generation,
+                true,
+            );
+            false
+        }
+    }
+
+    /// Call the given function on both `Data` fields of the channel.
+    /// This allows to manage the capacity of growable `Data` during the channel phase,
+    /// where no data pointer can access the `Data` fields.
+    pub fn manage_capacity(&mut self, channel_key: &ChannelKey, mut f: impl FnMut(&mut Data)) {
+        self.channel.verify_checksum(channel_key);
+        f(&mut self.channel.data1);
+        f(&mut self.channel.data2);
+        self.channel.record_checksum(channel_key);
+    }
+
+    /// The number of swaps performed on this channel.
+    pub fn swap_count(&self, #[allow(unused)] channel_key: &ChannelKey) -> u64 {
+        self.channel.stats.swaps
+    }
+
+    /// Statistics about the swaps performed on this channel.
+    pub fn stats(&self, #[allow(unused)] channel_key: &ChannelKey) -> SwapStats {
+        self.channel.stats
+    }
+
+    /// The number of swaps performed on this channel.
+    /// If it is odd, then the content of the `Data` fields is swapped compared to the creation of the channel.
+    pub fn parity(&self, #[allow(unused)] channel_key: &ChannelKey) -> u64 {
+        self.channel.stats.swaps
+    }
+
+    /// Rotate the two `Data` fields by the given number of steps.
+    /// With two `Data` fields, this swaps them if the number of steps is odd, in either direction.
+    ///
+    /// See [`RotatingChannelPointer::rotate_by`](crate::rotating::RotatingChannelPointer::rotate_by) for the general case.
+    pub fn rotate_by(&mut self, channel_key: &ChannelKey, steps: isize) {
+        if steps % 2 != 0 {
+            self.swap(channel_key);
+        }
+    }
+
+    /// Create an observer of the `Data` field the given immutable data pointer points to, which reads it like the data pointer,
+    /// but does not need to be returned when destroying the channel.
+    /// Once the channel is destroyed, the observer reads `None`, see the [observer module](crate::observer).
+    ///
+    /// Only sides without a mutable data pointer can be observed, as otherwise the observer would race with [UndirectedDataPointer::get_mut].
+    ///
+    /// **Panics** if the data pointer does not point to this channel.
+    pub fn observer(
+        &mut self,
+        data_pointer: &ImmutableUndirectedDataPointer<Data>,
+    ) -> ObserverPointer<Data> {
+        let channel_data_pointer1 = (&self.channel.data1.0) as *const Data;
+        let channel_data_pointer2 = (&self.channel.data2.0) as *const Data;
+        assert!(
+            data_pointer.data == channel_data_pointer1
+                || data_pointer.data == channel_data_pointer2,
+            "data pointer does not point to the {}",
+            self.label
+        );
+        self.observers.observe(data_pointer.data)
+    }
+
+    /// Replace the `Data` field on the given side of the channel with the given value, and return the replaced `Data`.
+    /// The data pointer to the given side sees the new value in the next data phase,
+    /// use [UndirectedDataPointer::slot] to find the side of a data pointer.
+    ///
+    /// This does not count as a swap, but increments the generation of the channel.
+    pub fn replace_side(&mut self, channel_key: &ChannelKey, side: Side, value: Data) -> Data {
+        let channel: &mut UndirectedChannel<Data> = &mut self.channel;
+        channel.verify_checksum(channel_key);
+        let replaced = mem::replace(channel.side_mut(side), value);
+        channel.generation += 1;
+        channel.record_checksum(channel_key);
+        replaced
+    }
+
+    /// Swap the `Data` field on the given side of this channel with the `Data` field on `other_side` of the other channel.
+    /// Each data pointer keeps pointing to its channel, and sees the moved `Data` in the next data phase.
+    ///
+    /// This does not count as a swap for either channel, but increments the generation of both channels.
+    pub fn swap_between(
+        &mut self,
+        channel_key: &ChannelKey,
+        side: Side,
+        other: &mut UndirectedChannelPointer<Data>,
+        other_side: Side,
+    ) {
+        let channel: &mut UndirectedChannel<Data> = &mut self.channel;
+        let other_channel: &mut UndirectedChannel<Data> = &mut other.channel;
+        channel.verify_checksum(channel_key);
+        other_channel.verify_checksum(channel_key);
+        mem::swap(channel.side_mut(side), other_channel.side_mut(other_side));
+        channel.generation += 1;
+        other_channel.generation += 1;
+        channel.record_checksum(channel_key);
+        other_channel.record_checksum(channel_key);
+    }
+
+    /// Rotate the content of the first `Data` field, the second `Data` field and the given `scratch` among each other,
+    /// in this order and in the given direction.
+    /// With [Rotation::Forward], the first `Data` moves to the second field, the second `Data` moves to `scratch`, and `scratch` moves to the first field.
+    /// With [Rotation::Backward], the moves are reversed.
+    ///
+    /// This does not count as a swap, but increments the generation of the channel.
+    pub fn swap_with_scratch(
+        &mut self,
+        channel_key: &ChannelKey,
+        scratch: &mut Data,
+        rotation: Rotation,
+    ) {
         let channel: &mut UndirectedChannel<Data> = &mut self.channel;
-        mem::swap(&mut channel.data1, &mut channel.data2);
+        channel.verify_checksum(channel_key);
+        mem::swap(&mut channel.data1.0, scratch);
+        match rotation {
+            Rotation::Forward => mem::swap(&mut channel.data2.0, scratch),
+            Rotation::Backward => mem::swap(&mut channel.data1.0, &mut channel.data2.0),
+        }
+        channel.generation += 1;
+        channel.record_checksum(channel_key);
     }
 
     /// Shorthand for [UndirectedChannel::destroy].
@@ -165,6 +822,53 @@ impl<Data> UndirectedChannelPointer<Data> {
         UndirectedChannel::destroy(self, data_pointer1, data_pointer2)
     }
 
+    /// Shorthand for [UndirectedChannel::destroy_with_report].
+    pub fn destroy_with_report(
+        self,
+        data_pointer1: UndirectedDataPointer<Data>,
+        data_pointer2: UndirectedDataPointer<Data>,
+    ) -> (Data, Data, ChannelReport) {
+        UndirectedChannel::destroy_with_report(self, data_pointer1, data_pointer2)
+    }
+
+    /// Convert the `Data` type of the channel, e.g. after hot-reloading a plugin that changed the shape of its payload.
+    /// This consumes the given pointers, validating them like [UndirectedChannel::destroy],
+    /// converts both `Data` fields with the given function, and hands out pointers to the converted channel,
+    /// where the data pointers are returned in the same order as the given ones and point to the converted `Data` of these.
+    ///
+    /// Since this requires a channel key, no data pointer observes a half-converted channel.
+    /// The label, the swap hook and the creation time of the channel are kept, while its generation and statistics start over.
+    /// This reallocates the channel, and is hence not [real-time safe](crate::rt).
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn migrate<New>(
+        mut self,
+        data_pointer1: UndirectedDataPointer<Data>,
+        data_pointer2: UndirectedDataPointer<Data>,
+        #[allow(unused)] channel_key: &ChannelKey,
+        mut f: impl FnMut(Data) -> New,
+    ) -> (
+        UndirectedChannelPointer<New>,
+        UndirectedDataPointer<New>,
+        UndirectedDataPointer<New>,
+    ) {
+        let swapped = ptr::eq(data_pointer1.data, &self.channel.data2.0);
+        let on_swap = mem::take(&mut self.on_swap);
+        let label = self.label.clone();
+        let created_at = self.created_at;
+        let (data1, data2) = self.destroy(data_pointer1, data_pointer2);
+        let (mut channel_pointer, new_data_pointer1, new_data_pointer2) =
+            UndirectedChannel::create(f(data1), f(data2));
+        channel_pointer.on_swap = on_swap;
+        channel_pointer.label = label;
+        channel_pointer.created_at = created_at;
+        if swapped {
+            (channel_pointer, new_data_pointer2, new_data_pointer1)
+        } else {
+            (channel_pointer, new_data_pointer1, new_data_pointer2)
+        }
+    }
+
     /// Shorthand for [UndirectedChannel::destroy_immutable].
     pub fn destroy_immutable(
         self,
@@ -173,66 +877,714 @@ impl<Data> UndirectedChannelPointer<Data> {
     ) -> (Data, Data) {
         UndirectedChannel::destroy_immutable(self, data_pointer1, data_pointer2)
     }
-}
 
-impl<Data> UndirectedDataPointer<Data> {
-    /// Get a reference to the `Data` field pointed to by this pointer.
-    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> &Data {
-        unsafe { &*self.data }
+    /// Shorthand for [UndirectedChannel::destroy_all_immutable].
+    pub fn destroy_all_immutable(
+        self,
+        data_pointers: impl IntoIterator<Item = ImmutableUndirectedDataPointer<Data>>,
+    ) -> (Data, Data) {
+        UndirectedChannel::destroy_all_immutable(self, data_pointers)
     }
+}
 
-    /// Get a mutable reference to the `Data` field pointed to by this pointer.
-    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> &mut Data {
-        unsafe { &mut *self.data }
+impl<Data: ManageCapacity> UndirectedChannelPointer<Data> {
+    /// Shrink the capacity of both `Data` fields as much as possible.
+    pub fn shrink_both(&mut self, channel_key: &ChannelKey) {
+        self.manage_capacity(channel_key, ManageCapacity::shrink_to_fit);
     }
 
-    pub fn into_immutable(self) -> ImmutableUndirectedDataPointer<Data> {
-        ImmutableUndirectedDataPointer {
-            data: self.data as *const Data,
-        }
+    /// Reserve capacity for at least `additional` more elements in both `Data` fields.
+    pub fn reserve_both(&mut self, channel_key: &ChannelKey, additional: usize) {
+        self.manage_capacity(channel_key, |data| data.reserve(additional));
     }
 }
 
-impl<Data> ImmutableUndirectedDataPointer<Data> {
-    /// Get a reference to the `Data` field pointed to by this pointer.
-    pub fn get(&self, #[allow(unused)] data_key: &DataKey) -> &Data {
-        unsafe { &*self.data }
+impl<Data: PartialEq> UndirectedChannelPointer<Data> {
+    /// Swap the two `Data` fields in the undirected channel if they are not equal.
+    /// Returns `true` if the `Data` fields were swapped.
+    pub fn swap_if_changed(&mut self, channel_key: &ChannelKey) -> bool {
+        self.swap_if(channel_key, |data1, data2| data1 != data2)
     }
 }
 
-impl<Data> Clone for ImmutableUndirectedDataPointer<Data> {
-    fn clone(&self) -> Self {
-        *self
+impl<Data: Clone> UndirectedChannelPointer<Data> {
+    /// Create a new undirected channel whose `Data` fields are clones of the `Data` fields of this channel,
+    /// and hand out three pointers to it (see [UndirectedChannel::create]).
+    /// The new channel is independent of this channel and must be destroyed separately.
+    pub fn clone_channel(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        UndirectedChannel::create(self.channel.data1.0.clone(), self.channel.data2.0.clone())
     }
-}
-
-impl<Data> Copy for ImmutableUndirectedDataPointer<Data> {}
-
-unsafe impl<Data> Send for UndirectedChannelPointer<Data> {}
-unsafe impl<Data> Send for UndirectedDataPointer<Data> {}
-unsafe impl<Data> Send for ImmutableUndirectedDataPointer<Data> {}
 
-unsafe impl<Data> Sync for UndirectedChannelPointer<Data> {}
-unsafe impl<Data> Sync for UndirectedDataPointer<Data> {}
-unsafe impl<Data> Sync for ImmutableUndirectedDataPointer<Data> {}
+    /// Clone both `Data` fields of the channel, e.g. for debugging.
+    pub fn snapshot(&self, #[allow(unused)] channel_key: &ChannelKey) -> UndirectedSnapshot<Data> {
+        UndirectedSnapshot {
+            data1: self.channel.data1.0.clone(),
+            data2: self.channel.data2.0.clone(),
+        }
+    }
+}
 
-/// Object-safe trait for [`UndirectedChannelPointer`]s.
-pub trait UndirectedSwapChannel: Send + Sync {
-    /// Perform the [`UndirectedChannelPointer::swap`] operation.
-    fn swap(&mut self, channel_key: &ChannelKey);
+#[cfg(feature = "serde")]
+impl<Data: serde::Serialize> UndirectedChannelPointer<Data> {
+    /// Serialise both `Data` fields of the channel in the format of an [UndirectedSnapshot], without cloning them.
+    pub fn serialize_contents<S: serde::Serializer>(
+        &self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(
+            &UndirectedSnapshotRef {
+                data1: &self.channel.data1.0,
+                data2: &self.channel.data2.0,
+            },
+            serializer,
+        )
+    }
 }
 
-impl<Data> UndirectedSwapChannel for UndirectedChannelPointer<Data> {
-    fn swap(&mut self, channel_key: &ChannelKey) {
-        UndirectedChannelPointer::swap(self, channel_key);
+#[cfg(feature = "serde")]
+impl<Data> UndirectedChannelPointer<Data> {
+    /// Deserialise both `Data` fields of the channel from the format of an [UndirectedSnapshot], replacing the current ones.
+    /// The generation of the channel advances.
+    /// If deserialisation fails, the channel is unchanged.
+    pub fn deserialize_contents<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        channel_key: &ChannelKey,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        Data: serde::Deserialize<'de>,
+    {
+        let UndirectedSnapshot { data1, data2 } = serde::Deserialize::deserialize(deserializer)?;
+        let channel: &mut UndirectedChannel<Data> = &mut self.channel;
+        channel.verify_checksum(channel_key);
+        channel.data1.0 = data1;
+        channel.data2.0 = data2;
+        channel.generation += 1;
+        channel.record_checksum(channel_key);
+        Ok(())
+    }
+}
+
+impl<T> UndirectedChannelPointer<Option<T>> {
+    /// Use the channel as a hand-off slot, where the first data pointer handed out by [UndirectedChannel::create] (see [Side::First]) is the producer
+    /// and the second data pointer is the consumer.
+    /// Swap the two `Data` fields only if the producer side is loaded and the consumer side is empty,
+    /// such that a value that was not yet taken by the consumer is never swapped back to the producer.
+    /// Returns `true` if the `Data` fields were swapped.
+    pub fn swap_if_loaded(&mut self, channel_key: &ChannelKey) -> bool {
+        self.swap_if(channel_key, |producer, consumer| {
+            producer.is_some() && consumer.is_none()
+        })
+    }
+}
+
+impl<Data> UndirectedDataPointer<Data> {
+    /// Convert this data pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this data pointer, and must be converted back with [UndirectedDataPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
+
+    /// Reconstitute a data pointer from a raw pointer returned by [UndirectedDataPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [UndirectedDataPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
+
+    common::read_accessors!();
+    common::write_accessors!();
+
+    fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+
+    fn access_mut(&mut self, data_key: &DataKey) {
+        self.access(data_key);
+    }
+
+    /// The generation of the channel, which is incremented by every operation that changes the content of the `Data` fields, such as a swap.
+    pub fn generation(&self, data_key: &DataKey) -> u64 {
+        self.checked.access(data_key.phase);
+        self.generation.get()
+    }
+
+    /// Returns `true` if the channel changed the content of the `Data` fields since `last` was recorded,
+    /// and updates `last` to the current [generation](Self::generation).
+    pub fn changed_since(&self, data_key: &DataKey, last: &mut u64) -> bool {
+        self.checked.access(data_key.phase);
+        self.generation.changed_since(last)
+    }
+
+    /// The physical `Data` field of the given channel that this pointer points to.
+    ///
+    /// **Panics** if this pointer does not point to the channel of the given channel pointer.
+    pub fn slot(
+        &self,
+        channel_pointer: &UndirectedChannelPointer<Data>,
+        #[allow(unused)] channel_key: &ChannelKey,
+    ) -> Side {
+        if ptr::eq(self.data, &channel_pointer.channel.data1.0) {
+            Side::First
+        } else if ptr::eq(self.data, &channel_pointer.channel.data2.0) {
+            Side::Second
+        } else {
+            panic!("data pointer does not point to this channel");
+        }
+    }
+
+    /// A raw pointer to the `Data` field pointed to by this pointer, e.g. for volatile reads.
+    ///
+    /// The raw pointer is only valid for reads during the data phase of the given data key,
+    /// and is invalidated when the channel is destroyed.
+    pub fn as_ptr(&self, data_key: &DataKey) -> *const Data {
+        self.checked.access(data_key.phase);
+        self.data
+    }
+
+    /// A raw pointer to the `Data` field pointed to by this pointer, e.g. for volatile writes or to hand it to a DMA engine.
+    ///
+    /// The raw pointer is only valid for reads and writes during the data phase of the given data key,
+    /// and is invalidated when the channel is destroyed.
+    pub fn as_mut_ptr(&mut self, data_key: &DataKey) -> *mut Data {
+        self.checked.access(data_key.phase);
+        self.data
+    }
+
+    /// Project this pointer to a field of the `Data` field pointed to by this pointer.
+    /// The projected pointer keeps pointing to the same `Data` field of the channel, so after a swap, it sees the field of the other `Data`.
+    ///
+    /// Returns the projected pointer and a [Projection] that restores this pointer from the projected pointer via [`Projection::restore`].
+    ///
+    /// # Safety
+    ///
+    /// `projection` must return a pointer to a field stored inline in the given `Data`,
+    /// for example via [`core::ptr::addr_of_mut`].
+    /// It must not dereference any pointers stored in `Data` (such as a `Box` or a `Vec`),
+    /// since these are swapped together with `Data` and the projected pointer would then alias the other `Data`.
+    pub unsafe fn project<Field>(
+        self,
+        projection: impl FnOnce(*mut Data) -> *mut Field,
+    ) -> (
+        UndirectedDataPointer<Field>,
+        Projection<UndirectedDataPointer<Data>>,
+    ) {
+        (
+            UndirectedDataPointer {
+                data: projection(self.data),
+                generation: self.generation,
+                checked: self.checked,
+            },
+            Projection { original: self },
+        )
+    }
+
+    /// Convert this pointer into an [ImmutableUndirectedDataPointer] to the same `Data` field.
+    /// Immutable pointers can be copied, hence they cannot be converted back, see [UndirectedDataPointer::into_upgradable] for that.
+    pub fn into_immutable(self) -> ImmutableUndirectedDataPointer<Data> {
+        ImmutableUndirectedDataPointer {
+            data: self.data as *const Data,
+            generation: self.generation,
+            checked: self.checked,
+        }
+    }
+
+    /// Convert this pointer into an [UpgradableUndirectedDataPointer] to the same `Data` field,
+    /// which only reads it until it is converted back via [UpgradableUndirectedDataPointer::into_mutable].
+    pub fn into_upgradable(self) -> UpgradableUndirectedDataPointer<Data> {
+        UpgradableUndirectedDataPointer {
+            data: self.data as *const Data,
+            generation: self.generation,
+            checked: self.checked,
+        }
+    }
+}
+
+impl<Data: Copy> UndirectedDataPointer<Data> {
+    /// Read the `Data` field pointed to by this pointer with [ptr::read_volatile],
+    /// such that the read is neither elided nor merged with other reads, e.g. if the `Data` is written by a DMA engine.
+    pub fn read_volatile(&self, data_key: &DataKey) -> Data {
+        unsafe { ptr::read_volatile(self.as_ptr(data_key)) }
+    }
+
+    /// Overwrite the `Data` field pointed to by this pointer with [ptr::write_volatile],
+    /// such that the write is neither elided nor merged with other writes, e.g. if the `Data` is read by a DMA engine.
+    pub fn write_volatile(&mut self, data_key: &DataKey, value: Data) {
+        unsafe { ptr::write_volatile(self.as_mut_ptr(data_key), value) }
+    }
+}
+
+impl<Data: 'static> UndirectedDataPointer<Data> {
+    /// Erase the type of this data pointer, such that the channel can be destroyed via [ErasedDestroy].
+    pub fn erase(self) -> ErasedDataPointer {
+        ErasedDataPointer::new(self)
+    }
+}
+
+impl<T> UndirectedDataPointer<Option<T>> {
+    /// Put the given value into the `Data` field pointed to by this pointer, and return the previous value, if any.
+    pub fn put(&mut self, data_key: &DataKey, value: T) -> Option<T> {
+        self.get_mut(data_key).replace(value)
+    }
+
+    /// Take the value out of the `Data` field pointed to by this pointer, leaving it empty.
+    pub fn take(&mut self, data_key: &DataKey) -> Option<T> {
+        self.get_mut(data_key).take()
+    }
+}
+
+impl<Data> ImmutableUndirectedDataPointer<Data> {
+    /// Convert this data pointer into an untyped raw pointer, e.g. to pass it through an FFI boundary.
+    /// The raw pointer owns a heap allocation holding this data pointer, and must be converted back with [ImmutableUndirectedDataPointer::from_raw].
+    pub fn into_raw(self) -> *mut () {
+        Box::into_raw(Box::new(self)) as *mut ()
+    }
+
+    /// Reconstitute a data pointer from a raw pointer returned by [ImmutableUndirectedDataPointer::into_raw].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been returned by [ImmutableUndirectedDataPointer::into_raw] for the same `Data` types,
+    /// and must be reconstituted exactly once.
+    /// The channel must not have been destroyed in the meantime.
+    pub unsafe fn from_raw(raw: *mut ()) -> Self {
+        *Box::from_raw(raw as *mut Self)
+    }
+
+    common::read_accessors!();
+
+    fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+
+    /// The generation of the channel, which is incremented by every operation that changes the content of the `Data` fields, such as a swap.
+    pub fn generation(&self, data_key: &DataKey) -> u64 {
+        self.checked.access(data_key.phase);
+        self.generation.get()
+    }
+
+    /// Returns `true` if the channel changed the content of the `Data` fields since `last` was recorded,
+    /// and updates `last` to the current [generation](Self::generation).
+    pub fn changed_since(&self, data_key: &DataKey, last: &mut u64) -> bool {
+        self.checked.access(data_key.phase);
+        self.generation.changed_since(last)
+    }
+}
+
+impl<Data> UpgradableUndirectedDataPointer<Data> {
+    common::read_accessors!();
+
+    fn access(&self, data_key: &DataKey) {
+        self.checked.access(data_key.phase);
+    }
+
+    /// The generation of the channel, which is incremented by every operation that changes the content of the `Data` fields, such as a swap.
+    pub fn generation(&self, data_key: &DataKey) -> u64 {
+        self.checked.access(data_key.phase);
+        self.generation.get()
+    }
+
+    /// Returns `true` if the channel changed the content of the `Data` fields since `last` was recorded,
+    /// and updates `last` to the current [generation](Self::generation).
+    pub fn changed_since(&self, data_key: &DataKey, last: &mut u64) -> bool {
+        self.checked.access(data_key.phase);
+        self.generation.changed_since(last)
+    }
+
+    /// Convert this pointer back into an [UndirectedDataPointer] to the same `Data` field.
+    ///
+    /// **Panics** if this pointer does not point to the channel of the given channel pointer.
+    pub fn into_mutable(
+        self,
+        channel_pointer: &UndirectedChannelPointer<Data>,
+        #[allow(unused)] channel_key: &ChannelKey,
+    ) -> UndirectedDataPointer<Data> {
+        let channel_data_pointer1 = (&channel_pointer.channel.data1.0) as *const Data;
+        let channel_data_pointer2 = (&channel_pointer.channel.data2.0) as *const Data;
+        assert!(
+            self.data == channel_data_pointer1 || self.data == channel_data_pointer2,
+            "data pointer does not point to the {}",
+            channel_pointer.label
+        );
+
+        UndirectedDataPointer {
+            data: self.data as *mut Data,
+            generation: self.generation,
+            checked: self.checked,
+        }
+    }
+
+    /// Give up converting this pointer back, and convert it into an [ImmutableUndirectedDataPointer], which can be copied.
+    pub fn into_immutable(self) -> ImmutableUndirectedDataPointer<Data> {
+        ImmutableUndirectedDataPointer {
+            data: self.data,
+            generation: self.generation,
+            checked: self.checked,
+        }
+    }
+}
+
+impl<Data> Clone for ImmutableUndirectedDataPointer<Data> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Data> Copy for ImmutableUndirectedDataPointer<Data> {}
+
+/// A triple buffer used for communication between threads.
+/// It holds three instances of `Data`: one that is written to, one that is read from,
+/// and one that is pending to be read.
+///
+/// Publishing swaps the written `Data` with the pending `Data`,
+/// and acquiring swaps the pending `Data` with the read `Data` if something new was published since the last acquire.
+/// Both operations are independent of each other, so the writer never has to wait for the reader to consume the published `Data`.
+///
+/// See [TripleBufferChannel::create] for more info.
+#[derive(Debug)]
+pub struct TripleBufferChannel<Data> {
+    writer: Data,
+    pending: Data,
+    reader: Data,
+    fresh: bool,
+    generation: u64,
+    checked: CheckedChannel,
+}
+
+/// A pointer to a triple buffer channel.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be destroyed via the [TripleBufferChannel::destroy] or [TripleBufferChannelPointer::destroy] method to ensure soundness (at runtime).
+#[derive(Debug)]
+#[must_use]
+pub struct TripleBufferChannelPointer<Data> {
+    channel: Box<TripleBufferChannel<Data>>,
+}
+
+impl<Data> TripleBufferChannel<Data> {
+    /// Create a triple buffer channel and hand out three pointers to it.
+    /// One [TripleBufferChannelPointer] used to publish and acquire the `Data` fields,
+    /// one [UndirectedDataPointer] to the `Data` field of the writer (initially `writer`), and
+    /// one [UndirectedDataPointer] to the `Data` field of the reader (initially `reader`).
+    ///
+    /// The `pending` `Data` is not accessible by any data pointer, and initially does not count as published.
+    pub fn create(
+        writer: Data,
+        pending: Data,
+        reader: Data,
+    ) -> (
+        TripleBufferChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        let mut channel_pointer = TripleBufferChannelPointer {
+            channel: Box::new(TripleBufferChannel {
+                writer,
+                pending,
+                reader,
+                fresh: false,
+                generation: 0,
+                checked: CheckedChannel::new(),
+            }),
+        };
+        let generation = GenerationPointer::new(&channel_pointer.channel.generation);
+        let checked = channel_pointer.channel.checked.pointer();
+        let writer_data_pointer = UndirectedDataPointer {
+            data: (&mut channel_pointer.channel.writer) as *mut Data,
+            generation,
+            checked,
+        };
+        let reader_data_pointer = UndirectedDataPointer {
+            data: (&mut channel_pointer.channel.reader) as *mut Data,
+            generation,
+            checked,
+        };
+        (channel_pointer, writer_data_pointer, reader_data_pointer)
+    }
+
+    /// Destroys the triple buffer channel linked with the three pointers (see [TripleBufferChannel::create]).
+    /// Returns the `Data` fields of the writer, the pending `Data` field and the `Data` field of the reader, in this order.
+    ///
+    /// **Panics** if not all three pointers point to the same channel.
+    pub fn destroy(
+        channel_pointer: TripleBufferChannelPointer<Data>,
+        writer_data_pointer: UndirectedDataPointer<Data>,
+        reader_data_pointer: UndirectedDataPointer<Data>,
+    ) -> (Data, Data, Data) {
+        let TripleBufferChannelPointer { mut channel } = channel_pointer;
+        let UndirectedDataPointer {
+            data: writer_data_pointer,
+            ..
+        } = writer_data_pointer;
+        let UndirectedDataPointer {
+            data: reader_data_pointer,
+            ..
+        } = reader_data_pointer;
+
+        assert_eq!((&mut channel.writer) as *mut Data, writer_data_pointer);
+        assert_eq!((&mut channel.reader) as *mut Data, reader_data_pointer);
+
+        (channel.writer, channel.pending, channel.reader)
+    }
+}
+
+impl<Data> TripleBufferChannelPointer<Data> {
+    /// Swap the `Data` field of the writer with the pending `Data` field.
+    /// Afterwards, the pending `Data` counts as published, and the writer continues with the previously pending `Data`.
+    pub fn publish(&mut self, #[allow(unused)] channel_key: &ChannelKey) {
+        let channel: &mut TripleBufferChannel<Data> = &mut self.channel;
+        channel.checked.advance();
+        mem::swap(&mut channel.writer, &mut channel.pending);
+        channel.fresh = true;
+        channel.generation += 1;
+    }
+
+    /// Swap the pending `Data` field with the `Data` field of the reader, if the pending `Data` was published since the last acquire.
+    /// Returns `true` if the `Data` fields were swapped.
+    pub fn acquire(&mut self, #[allow(unused)] channel_key: &ChannelKey) -> bool {
+        let channel: &mut TripleBufferChannel<Data> = &mut self.channel;
+        channel.checked.advance();
+        if channel.fresh {
+            mem::swap(&mut channel.pending, &mut channel.reader);
+            channel.fresh = false;
+            channel.generation += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` if the pending `Data` was published and not yet acquired.
+    pub fn has_pending(&self, #[allow(unused)] channel_key: &ChannelKey) -> bool {
+        self.channel.fresh
+    }
+
+    /// Shorthand for [TripleBufferChannel::destroy].
+    pub fn destroy(
+        self,
+        writer_data_pointer: UndirectedDataPointer<Data>,
+        reader_data_pointer: UndirectedDataPointer<Data>,
+    ) -> (Data, Data, Data) {
+        TripleBufferChannel::destroy(self, writer_data_pointer, reader_data_pointer)
+    }
+}
+
+impl<Data> Projection<UndirectedDataPointer<Data>> {
+    /// Restore the original pointer from the given pointer projected from it (see [UndirectedDataPointer::project]).
+    ///
+    /// **Panics** if the projected pointer does not point into the `Data` field of the original pointer.
+    pub fn restore<Field>(
+        self,
+        projected: UndirectedDataPointer<Field>,
+    ) -> UndirectedDataPointer<Data> {
+        let original = self.original.data as usize;
+        let projected = projected.data as usize;
+        assert!(
+            original <= projected
+                && projected + mem::size_of::<Field>() <= original + mem::size_of::<Data>()
+        );
+
+        self.original
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<Data: bytemuck::Pod> UndirectedDataPointer<Data> {
+    /// Get the bytes of the `Data` field pointed to by this pointer, e.g. to upload them to the GPU without copying them first.
+    pub fn as_bytes(&self, data_key: &DataKey) -> &[u8] {
+        bytemuck::bytes_of(self.get(data_key))
+    }
+
+    /// Get the mutable bytes of the `Data` field pointed to by this pointer, e.g. to fill them from a buffer.
+    pub fn as_bytes_mut(&mut self, data_key: &DataKey) -> &mut [u8] {
+        bytemuck::bytes_of_mut(self.get_mut(data_key))
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<Data> UndirectedDataPointer<Data> {
+    /// Get the bytes of the elements of the slice in the `Data` field pointed to by this pointer,
+    /// e.g. of a `[T; N]`, a `Box<[T]>` or a `Vec<T>`.
+    pub fn as_slice_bytes<T: bytemuck::Pod>(&self, data_key: &DataKey) -> &[u8]
+    where
+        Data: AsRef<[T]>,
+    {
+        bytemuck::cast_slice(self.get(data_key).as_ref())
+    }
+
+    /// Get the mutable bytes of the elements of the slice in the `Data` field pointed to by this pointer.
+    pub fn as_slice_bytes_mut<T: bytemuck::Pod>(&mut self, data_key: &DataKey) -> &mut [u8]
+    where
+        Data: AsMut<[T]>,
+    {
+        bytemuck::cast_slice_mut(self.get_mut(data_key).as_mut())
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<Data: bytemuck::Pod> ImmutableUndirectedDataPointer<Data> {
+    /// Get the bytes of the `Data` field pointed to by this pointer.
+    pub fn as_bytes(&self, data_key: &DataKey) -> &[u8] {
+        bytemuck::bytes_of(self.get(data_key))
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<Data> ImmutableUndirectedDataPointer<Data> {
+    /// Get the bytes of the elements of the slice in the `Data` field pointed to by this pointer,
+    /// e.g. of a `[T; N]`, a `Box<[T]>` or a `Vec<T>`.
+    pub fn as_slice_bytes<T: bytemuck::Pod>(&self, data_key: &DataKey) -> &[u8]
+    where
+        Data: AsRef<[T]>,
+    {
+        bytemuck::cast_slice(self.get(data_key).as_ref())
+    }
+}
+
+unsafe impl<Data> Send for UndirectedChannelPointer<Data> {}
+unsafe impl<Data> Send for UndirectedDataPointer<Data> {}
+unsafe impl<Data> Send for ImmutableUndirectedDataPointer<Data> {}
+unsafe impl<Data> Send for UpgradableUndirectedDataPointer<Data> {}
+unsafe impl<Data> Send for TripleBufferChannelPointer<Data> {}
+
+unsafe impl<Data> Sync for UndirectedChannelPointer<Data> {}
+unsafe impl<Data> Sync for UndirectedDataPointer<Data> {}
+unsafe impl<Data> Sync for ImmutableUndirectedDataPointer<Data> {}
+unsafe impl<Data> Sync for UpgradableUndirectedDataPointer<Data> {}
+unsafe impl<Data> Sync for TripleBufferChannelPointer<Data> {}
+
+/// Object-safe trait for [`UndirectedChannelPointer`]s.
+pub trait UndirectedSwapChannel: SwapChannel {
+    /// Perform the [`UndirectedChannelPointer::swap`] operation.
+    fn swap(&mut self, channel_key: &ChannelKey);
+
+    /// Perform the [`UndirectedChannelPointer::swap_count`] operation.
+    fn swap_count(&self, channel_key: &ChannelKey) -> u64;
+
+    /// Perform the [`UndirectedChannelPointer::stats`] operation.
+    fn stats(&self, channel_key: &ChannelKey) -> SwapStats;
+}
+
+impl<Data> SwapChannel for UndirectedChannelPointer<Data> {
+    fn advance(&mut self, channel_key: &ChannelKey) {
+        UndirectedChannelPointer::swap(self, channel_key);
+    }
+
+    fn label(&self) -> Option<&str> {
+        UndirectedChannelPointer::label(self)
+    }
+
+    fn generation(&self, #[allow(unused)] channel_key: &ChannelKey) -> Option<u64> {
+        Some(self.channel.generation)
+    }
+
+    fn payload_type_name(&self) -> Option<&'static str> {
+        Some(any::type_name::<Data>())
+    }
+}
+
+impl<Data> UndirectedSwapChannel for UndirectedChannelPointer<Data> {
+    fn swap(&mut self, channel_key: &ChannelKey) {
+        UndirectedChannelPointer::swap(self, channel_key);
+    }
+
+    fn swap_count(&self, channel_key: &ChannelKey) -> u64 {
+        UndirectedChannelPointer::swap_count(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> SwapStats {
+        UndirectedChannelPointer::stats(self, channel_key)
+    }
+}
+
+impl<Data: Send + 'static> ErasedDestroy for UndirectedChannelPointer<Data> {
+    /// Expects the two [UndirectedDataPointer]s in any order, and returns both `Data` fields like [UndirectedChannel::destroy].
+    fn destroy_erased(
+        mut self: Box<Self>,
+        pointers: Vec<ErasedDataPointer>,
+    ) -> Result<Vec<Box<dyn Any + Send>>, DestroyError> {
+        let [data_pointer1, data_pointer2] =
+            match erased::downcast_all::<UndirectedDataPointer<Data>, 2>(pointers) {
+                Ok(pointers) => pointers,
+                Err(error) => return Err(erased::leak(self, error)),
+            };
+        let channel_data_pointers = [
+            (&mut self.channel.data1.0) as *mut Data,
+            (&mut self.channel.data2.0) as *mut Data,
+        ];
+        if !channel_data_pointers.contains(&data_pointer1.data) {
+            return Err(erased::leak(
+                self,
+                DestroyError::ForeignPointer { index: 0 },
+            ));
+        }
+        if !channel_data_pointers.contains(&data_pointer2.data)
+            || data_pointer1.data == data_pointer2.data
+        {
+            return Err(erased::leak(
+                self,
+                DestroyError::ForeignPointer { index: 1 },
+            ));
+        }
+
+        let (data1, data2) = self.destroy(data_pointer1, data_pointer2);
+        Ok(vec![Box::new(data1), Box::new(data2)])
+    }
+}
+
+impl<T: UndirectedSwapChannel + ?Sized> UndirectedSwapChannel for Box<T> {
+    fn swap(&mut self, channel_key: &ChannelKey) {
+        T::swap(self, channel_key);
+    }
+
+    fn swap_count(&self, channel_key: &ChannelKey) -> u64 {
+        T::swap_count(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> SwapStats {
+        T::stats(self, channel_key)
+    }
+}
+
+impl<T: UndirectedSwapChannel + ?Sized> UndirectedSwapChannel for &mut T {
+    fn swap(&mut self, channel_key: &ChannelKey) {
+        T::swap(self, channel_key);
+    }
+
+    fn swap_count(&self, channel_key: &ChannelKey) -> u64 {
+        T::swap_count(self, channel_key)
+    }
+
+    fn stats(&self, channel_key: &ChannelKey) -> SwapStats {
+        T::stats(self, channel_key)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::{any::Any, ptr, sync::atomic::Ordering};
+    use std::sync::{mpsc, Arc};
+
     use crate::{
-        undirected::{UndirectedChannel, UndirectedSwapChannel},
-        MasterKey,
+        rotating::Rotation,
+        undirected::{
+            ImmutableUndirectedDataPointer, Side, SwapStats, TripleBufferChannel,
+            UndirectedChannel, UndirectedChannelPointer, UndirectedDataPointer,
+            UndirectedSwapChannel,
+        },
+        ChannelKey, MasterKey, SwapChannel,
     };
 
     #[test]
@@ -256,6 +1608,19 @@ mod tests {
         assert_eq!(data2, 6);
     }
 
+    #[test]
+    fn rotate_by_swaps_on_odd_steps() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+
+        for steps in [-3, -2, 0, 1, 4, 7] {
+            channel_pointer.rotate_by(&master_key.get_channel_key(), steps);
+        }
+        assert_eq!(*data_pointer1.get(&master_key.get_data_key()), 2);
+        assert_eq!(*data_pointer2.get(&master_key.get_data_key()), 1);
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
     #[test]
     fn ensure_channel_is_object_safe() {
         let mut master_key = unsafe { MasterKey::create_unlimited() };
@@ -267,4 +1632,683 @@ mod tests {
         assert_eq!(*data2.get(&master_key.get_data_key()), 1);
         UndirectedChannel::destroy(channel, data1, data2);
     }
+
+    #[test]
+    fn triple_buffer() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut writer, reader) = TripleBufferChannel::create(0, 0, 0);
+
+        // Nothing was published yet.
+        assert!(!channel_pointer.acquire(&master_key.get_channel_key()));
+
+        *writer.get_mut(&master_key.get_data_key()) = 1;
+        channel_pointer.publish(&master_key.get_channel_key());
+        *writer.get_mut(&master_key.get_data_key()) = 2;
+        channel_pointer.publish(&master_key.get_channel_key());
+        assert!(channel_pointer.has_pending(&master_key.get_channel_key()));
+        assert_eq!(*reader.get(&master_key.get_data_key()), 0);
+
+        // The reader gets the latest published value.
+        assert!(channel_pointer.acquire(&master_key.get_channel_key()));
+        assert_eq!(*reader.get(&master_key.get_data_key()), 2);
+        assert!(!channel_pointer.acquire(&master_key.get_channel_key()));
+        assert_eq!(*reader.get(&master_key.get_data_key()), 2);
+
+        assert_eq!(
+            TripleBufferChannel::destroy(channel_pointer, writer, reader),
+            (1, 0, 2)
+        );
+    }
+
+    #[test]
+    fn destroy_all_immutable() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        let data_pointer1 = data_pointer1.into_immutable();
+        let data_pointer2 = data_pointer2.into_immutable();
+        let observer = data_pointer2;
+
+        channel_pointer.swap(&master_key.get_channel_key());
+        assert_eq!(*observer.get(&master_key.get_data_key()), 1);
+
+        assert_eq!(
+            channel_pointer.destroy_all_immutable([data_pointer1, data_pointer2, observer]),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn destroy_all_immutable_requires_both_sides() {
+        let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        let data_pointer1 = data_pointer1.into_immutable();
+        let _ = data_pointer2.into_immutable();
+
+        channel_pointer.destroy_all_immutable([data_pointer1, data_pointer1]);
+    }
+
+    #[test]
+    fn into_mutable() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        let data_pointer1 = data_pointer1.into_upgradable();
+        assert_eq!(
+            data_pointer1.read(&master_key.get_data_key(), |data| *data),
+            1
+        );
+
+        let (other_channel_pointer, other_data_pointer1, other_data_pointer2) =
+            UndirectedChannel::create(1, 2);
+        let foreign = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            other_data_pointer1
+                .into_upgradable()
+                .into_mutable(&channel_pointer, &master_key.get_channel_key())
+        }));
+        assert!(foreign.is_err());
+        drop((other_channel_pointer, other_data_pointer2));
+
+        let mut data_pointer1 =
+            data_pointer1.into_mutable(&channel_pointer, &master_key.get_channel_key());
+        *data_pointer1.get_mut(&master_key.get_data_key()) = 3;
+        let data_pointer2 = data_pointer2.into_upgradable().into_immutable();
+
+        assert_eq!(
+            channel_pointer.destroy_immutable(data_pointer1, [data_pointer2, data_pointer2]),
+            (3, 2)
+        );
+    }
+
+    #[test]
+    fn project() {
+        struct WorldState {
+            physics: u32,
+            audio: u32,
+        }
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(
+            WorldState {
+                physics: 1,
+                audio: 2,
+            },
+            WorldState {
+                physics: 3,
+                audio: 4,
+            },
+        );
+        let (mut physics, projection1) =
+            unsafe { data_pointer1.project(|data| ptr::addr_of_mut!((*data).physics)) };
+        let (audio, projection2) =
+            unsafe { data_pointer2.project(|data| ptr::addr_of_mut!((*data).audio)) };
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*physics.get(&data_key), 1);
+        assert_eq!(*audio.get(&data_key), 4);
+        *physics.get_mut(&data_key) = 5;
+
+        // After a swap, the projected pointers see the fields of the other `Data`.
+        channel_pointer.swap(&data_key.into_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(*physics.get(&data_key), 3);
+        assert_eq!(*audio.get(&data_key), 2);
+
+        let (data1, data2) =
+            channel_pointer.destroy(projection1.restore(physics), projection2.restore(audio));
+        assert_eq!((data1.physics, data1.audio), (3, 4));
+        assert_eq!((data2.physics, data2.audio), (5, 2));
+    }
+
+    #[test]
+    fn stats() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 1);
+        let dyn_channel_pointer: &mut dyn UndirectedSwapChannel = &mut channel_pointer;
+
+        dyn_channel_pointer.swap(&master_key.get_channel_key());
+        assert_eq!(
+            dyn_channel_pointer.swap_count(&master_key.get_channel_key()),
+            1
+        );
+
+        assert!(!channel_pointer.swap_if_changed(&master_key.get_channel_key()));
+        assert!(channel_pointer.swap_if(&master_key.get_channel_key(), |_, _| true));
+        assert!(!channel_pointer.swap_if(&master_key.get_channel_key(), |_, _| false));
+        assert_eq!(
+            channel_pointer.stats(&master_key.get_channel_key()),
+            SwapStats {
+                swaps: 2,
+                skipped: 2,
+                last_swap_generation: 2,
+            }
+        );
+
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn manage_capacity() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) =
+            UndirectedChannel::create(Vec::<u8>::with_capacity(100), vec![1]);
+
+        channel_pointer.manage_capacity(&master_key.get_channel_key(), Vec::shrink_to_fit);
+        assert_eq!(data_pointer1.get(&master_key.get_data_key()).capacity(), 0);
+        #[cfg(feature = "capacity-impls")]
+        {
+            channel_pointer.reserve_both(&master_key.get_channel_key(), 10);
+            assert!(data_pointer1.get(&master_key.get_data_key()).capacity() >= 10);
+            assert!(data_pointer2.get(&master_key.get_data_key()).capacity() >= 11);
+            channel_pointer.shrink_both(&master_key.get_channel_key());
+            assert_eq!(data_pointer1.get(&master_key.get_data_key()).capacity(), 0);
+        }
+
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn boxed_channel_pointers() {
+        fn swap_all(channels: &mut [impl UndirectedSwapChannel], channel_key: &ChannelKey) {
+            for channel in channels {
+                channel.swap(channel_key);
+            }
+        }
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        let mut channels = vec![Box::new(channel_pointer)];
+
+        swap_all(&mut channels, &master_key.get_channel_key());
+        swap_all(&mut [&mut channels[0]], &master_key.get_channel_key());
+        assert_eq!(channels[0].swap_count(&master_key.get_channel_key()), 2);
+
+        let channel_pointer = *channels.pop().unwrap();
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (1, 2)
+        );
+    }
+
+    #[test]
+    fn slot_and_parity() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+
+        for parity in 0..3 {
+            let channel_key = master_key.get_channel_key();
+            assert_eq!(channel_pointer.parity(&channel_key), parity);
+            assert_eq!(
+                data_pointer1.slot(&channel_pointer, &channel_key),
+                Side::First
+            );
+            assert_eq!(
+                data_pointer2.slot(&channel_pointer, &channel_key),
+                Side::Second
+            );
+            channel_pointer.swap(&channel_key);
+        }
+
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn generation() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 1);
+        let data_pointer2 = data_pointer2.into_immutable();
+        let mut last = 0;
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.generation(&data_key), 0);
+        assert!(!data_pointer2.changed_since(&data_key, &mut last));
+
+        channel_pointer.swap(&master_key.get_channel_key());
+        channel_pointer.swap_if(&master_key.get_channel_key(), |_, _| false);
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.generation(&data_key), 1);
+        assert!(data_pointer2.changed_since(&data_key, &mut last));
+        assert_eq!(last, 1);
+        assert!(!data_pointer2.changed_since(&data_key, &mut last));
+
+        UndirectedChannel::destroy_immutable(channel_pointer, data_pointer1, [data_pointer2]);
+    }
+
+    #[test]
+    fn replace_side() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 1);
+        channel_pointer.swap(&master_key.get_channel_key());
+
+        let channel_key = master_key.get_channel_key();
+        let side = data_pointer1.slot(&channel_pointer, &channel_key);
+        assert_eq!(channel_pointer.replace_side(&channel_key, side, 2), 1);
+        assert_eq!(channel_pointer.parity(&channel_key), 1);
+        assert_eq!(channel_pointer.stats(&channel_key).last_swap_generation, 1);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointer1.get(&data_key), 2);
+        assert_eq!(*data_pointer2.get(&data_key), 0);
+        assert_eq!(data_pointer2.generation(&data_key), 2);
+
+        channel_pointer.swap(&master_key.get_channel_key());
+        let data_key = master_key.get_data_key();
+        assert_eq!(*data_pointer1.get(&data_key), 0);
+        assert_eq!(*data_pointer2.get(&data_key), 2);
+
+        assert_eq!(
+            UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2),
+            (0, 2)
+        );
+    }
+
+    #[test]
+    fn swap_with_scratch() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        let mut scratch = 3;
+
+        channel_pointer.swap_with_scratch(
+            &master_key.get_channel_key(),
+            &mut scratch,
+            Rotation::Forward,
+        );
+        let data_key = master_key.get_data_key();
+        assert_eq!(
+            (
+                *data_pointer1.get(&data_key),
+                *data_pointer2.get(&data_key),
+                scratch
+            ),
+            (3, 1, 2)
+        );
+
+        channel_pointer.swap_with_scratch(
+            &master_key.get_channel_key(),
+            &mut scratch,
+            Rotation::Backward,
+        );
+        channel_pointer.swap_with_scratch(
+            &master_key.get_channel_key(),
+            &mut scratch,
+            Rotation::Backward,
+        );
+        let data_key = master_key.get_data_key();
+        assert_eq!(
+            (
+                *data_pointer1.get(&data_key),
+                *data_pointer2.get(&data_key),
+                scratch
+            ),
+            (2, 3, 1)
+        );
+        assert_eq!(data_pointer1.generation(&data_key), 3);
+
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn hand_off_slot() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut producer, mut consumer) =
+            UndirectedChannel::create(None, None);
+
+        assert!(!channel_pointer.swap_if_loaded(&master_key.get_channel_key()));
+        assert_eq!(producer.put(&master_key.get_data_key(), 1), None);
+        assert!(channel_pointer.swap_if_loaded(&master_key.get_channel_key()));
+
+        // The consumer did not take the job yet, so the next job must wait.
+        assert_eq!(producer.put(&master_key.get_data_key(), 2), None);
+        assert!(!channel_pointer.swap_if_loaded(&master_key.get_channel_key()));
+        assert_eq!(consumer.take(&master_key.get_data_key()), Some(1));
+        assert!(channel_pointer.swap_if_loaded(&master_key.get_channel_key()));
+        assert_eq!(consumer.take(&master_key.get_data_key()), Some(2));
+
+        assert_eq!(
+            UndirectedChannel::destroy(channel_pointer, producer, consumer),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn create_dyn() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, mut data_pointer2) =
+            UndirectedChannel::<Box<dyn Any + Send>>::create_dyn(Box::new(1u32), Box::new("one"));
+
+        for i in 0..4u32 {
+            channel_pointer.swap(&master_key.get_channel_key());
+
+            let data_key = master_key.get_data_key();
+            assert_eq!(
+                data_pointer1.get(&data_key).downcast_ref::<&str>(),
+                Some(&"one")
+            );
+            assert_eq!(
+                data_pointer2.get(&data_key).downcast_ref::<u32>(),
+                Some(&(i + 1))
+            );
+            *data_pointer1.get_mut(&data_key) = Box::new(i + 2);
+            *data_pointer2.get_mut(&data_key) = Box::new("one");
+        }
+
+        let (data1, data2) =
+            UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+        assert_eq!(data1.downcast_ref::<u32>(), Some(&5));
+        assert_eq!(data2.downcast_ref::<&str>(), Some(&"one"));
+    }
+
+    #[test]
+    fn clone_channel() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 1);
+        channel_pointer.swap(&master_key.get_channel_key());
+
+        let (mut clone_pointer, mut clone_data_pointer1, clone_data_pointer2) =
+            channel_pointer.clone_channel(&master_key.get_channel_key());
+        *clone_data_pointer1.get_mut(&master_key.get_data_key()) = 2;
+        clone_pointer.swap(&master_key.get_channel_key());
+
+        assert_eq!(
+            UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2),
+            (1, 0)
+        );
+        assert_eq!(
+            UndirectedChannel::destroy(clone_pointer, clone_data_pointer1, clone_data_pointer2),
+            (0, 2)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "checksum")]
+    fn checksum_detects_writes_outside_of_data_phase() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create_checksummed(0, 1);
+        let data_key = master_key.get_data_key();
+        let stale = data_pointer1.get_mut(&data_key) as *mut i32;
+        channel_pointer.swap(&data_key.into_channel_key());
+        // Writes in a data phase are not reported, also if the data key was converted from a channel key.
+        let data_key = master_key.get_channel_key().into_data_key();
+        *data_pointer1.get_mut(&data_key) = 3;
+        let channel_key = data_key.into_channel_key();
+        channel_pointer.swap(&channel_key);
+        // Data keys of other master keys do not hide writes outside of the data phases of this one.
+        let mut other_master_key = unsafe { MasterKey::create_unlimited() };
+        let _data_key = other_master_key.get_data_key();
+        unsafe { *stale = 2 };
+
+        let detected =
+            panic::catch_unwind(AssertUnwindSafe(|| channel_pointer.swap(&channel_key))).is_err();
+        assert!(detected);
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn on_swap() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(0, 1);
+        let (sender, receiver) = mpsc::channel();
+        channel_pointer.set_on_swap(Box::new(move |generation| sender.send(generation).unwrap()));
+
+        let channel_key = master_key.get_channel_key();
+        channel_pointer.swap(&channel_key);
+        channel_pointer.swap_if(&channel_key, |_, _| false);
+        channel_pointer.replace_side(&channel_key, Side::First, 2);
+        channel_pointer.swap_if(&channel_key, |_, _| true);
+
+        UndirectedChannel::destroy(channel_pointer, data_pointer1, data_pointer2);
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), [1, 3]);
+    }
+
+    #[test]
+    fn migrate() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let marker = Arc::new(());
+        let (mut channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1u8, 2);
+        channel_pointer.swap(&master_key.get_channel_key());
+
+        // The data pointers are given in reverse order, and the new ones are returned in the same order.
+        let (mut channel_pointer, mut data_pointer2, data_pointer1) = channel_pointer.migrate(
+            data_pointer2,
+            data_pointer1,
+            &master_key.get_channel_key(),
+            |old| (Arc::clone(&marker), [u64::from(old); 4]),
+        );
+        assert_eq!(Arc::strong_count(&marker), 3);
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.get(&data_key).1, [2; 4]);
+        assert_eq!(data_pointer2.get(&data_key).1, [1; 4]);
+        data_pointer2.get_mut(&data_key).1[0] = 3;
+        channel_pointer.swap(&data_key.into_channel_key());
+        assert_eq!(data_pointer1.get(&master_key.get_data_key()).1[0], 3);
+
+        // The converted `Data` is dropped with the channel.
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn large_payloads_stay_on_the_heap() {
+        const LEN: usize = 8 << 20;
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::<[u8; LEN]>::create_boxed_zeroed();
+        data_pointer1.get_mut(&master_key.get_data_key())[LEN - 1] = 1;
+
+        let (data1, data2) =
+            UndirectedChannel::destroy_boxed(channel_pointer, data_pointer1, data_pointer2);
+        assert_eq!((data1[LEN - 1], data2[LEN - 1]), (1, 0));
+
+        let (channel_pointer, data_pointer1, data_pointer2) = unsafe {
+            UndirectedChannel::<[u64; LEN / 8]>::create_boxed_with(|data| {
+                let data = data.as_mut_ptr() as *mut u64;
+                for index in 0..LEN / 8 {
+                    data.add(index).write(index as u64);
+                }
+            })
+        };
+        assert_eq!(
+            data_pointer2.get(&master_key.get_data_key())[LEN / 8 - 1],
+            LEN as u64 / 8 - 1
+        );
+        UndirectedChannel::destroy_boxed(channel_pointer, data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    fn swap_between() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut active, active_data_pointer1, active_data_pointer2) =
+            UndirectedChannel::create(0, 1);
+        let (mut background, background_data_pointer1, background_data_pointer2) =
+            UndirectedChannel::create(2, 3);
+
+        let channel_key = master_key.get_channel_key();
+        active.swap(&channel_key);
+        active.swap_between(&channel_key, Side::First, &mut background, Side::Second);
+        assert_eq!(active.parity(&channel_key), 1);
+        assert_eq!(background.parity(&channel_key), 0);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(*active_data_pointer1.get(&data_key), 3);
+        assert_eq!(*background_data_pointer2.get(&data_key), 1);
+        assert_eq!(active_data_pointer1.generation(&data_key), 2);
+        assert_eq!(background_data_pointer2.generation(&data_key), 1);
+
+        assert_eq!(
+            UndirectedChannel::destroy(active, active_data_pointer1, active_data_pointer2),
+            (3, 0)
+        );
+        assert_eq!(
+            UndirectedChannel::destroy(
+                background,
+                background_data_pointer1,
+                background_data_pointer2
+            ),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    fn label() {
+        let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        assert_eq!(channel_pointer.label(), None);
+        let mut channel_pointer = channel_pointer.with_label(String::from("physics_state"));
+        assert_eq!(channel_pointer.label(), Some("physics_state"));
+
+        let dyn_channel: &mut dyn UndirectedSwapChannel = &mut channel_pointer;
+        assert_eq!(dyn_channel.label(), Some("physics_state"));
+        let dyn_channel: &dyn SwapChannel = &dyn_channel;
+        assert_eq!(dyn_channel.label(), Some("physics_state"));
+
+        channel_pointer.destroy(data_pointer1, data_pointer2);
+    }
+
+    #[test]
+    #[should_panic(expected = "do not point to the channel \"physics_state\"")]
+    fn label_in_destroy_panic() {
+        let (channel_pointer, data_pointer1, _) = UndirectedChannel::create(1, 2);
+        let (_, _, other_data_pointer) = UndirectedChannel::create(3, 4);
+        channel_pointer
+            .with_label("physics_state")
+            .destroy(data_pointer1, other_data_pointer);
+    }
+
+    #[test]
+    fn volatile_access() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create([0u8; 4], [0; 4]);
+
+        let data_key = master_key.get_data_key();
+        // Emulate a DMA engine filling the buffer through the raw pointer.
+        unsafe { (*data_pointer1.as_mut_ptr(&data_key))[1] = 1 };
+        data_pointer1.write_volatile(&data_key, [data_pointer1.read_volatile(&data_key)[1]; 4]);
+        channel_pointer.swap_with_fence(&data_key.into_channel_key(), Ordering::SeqCst);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.read_volatile(&data_key), [0; 4]);
+        assert_eq!(unsafe { *data_pointer2.as_ptr(&data_key) }, [1; 4]);
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            ([0; 4], [1; 4])
+        );
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, data_pointer1, data_pointer2) = UndirectedChannel::create(1, 2);
+        let immutable = data_pointer2.into_immutable();
+        let channel_pointer = channel_pointer.with_label("raw");
+
+        let raw = (
+            channel_pointer.into_raw(),
+            data_pointer1.into_raw(),
+            immutable.into_raw(),
+        );
+        let (mut channel_pointer, data_pointer1, immutable) = unsafe {
+            (
+                UndirectedChannelPointer::<i32>::from_raw(raw.0),
+                UndirectedDataPointer::<i32>::from_raw(raw.1),
+                ImmutableUndirectedDataPointer::<i32>::from_raw(raw.2),
+            )
+        };
+
+        assert_eq!(channel_pointer.label(), Some("raw"));
+        channel_pointer.swap(&master_key.get_channel_key());
+        assert_eq!(*immutable.get(&master_key.get_data_key()), 1);
+        assert_eq!(
+            UndirectedChannel::destroy_immutable(channel_pointer, data_pointer1, [immutable]),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn byte_views() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create(vec![0u32; 2].into_boxed_slice(), Box::new([]) as Box<[u32]>);
+
+        data_pointer1
+            .as_slice_bytes_mut(&master_key.get_data_key())
+            .copy_from_slice(&[1u32.to_ne_bytes(), 2u32.to_ne_bytes()].concat());
+        channel_pointer.swap(&master_key.get_channel_key());
+        let data_pointer2 = data_pointer2.into_immutable();
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer2.get(&data_key)[..], [1, 2]);
+        assert!(data_pointer1.as_slice_bytes(&data_key).is_empty());
+        let _ = (channel_pointer, data_pointer1, data_pointer2);
+
+        let (channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create(0u16, 0);
+        data_pointer1.as_bytes_mut(&master_key.get_data_key())[0] = 1;
+        assert_eq!(
+            data_pointer1.as_bytes(&master_key.get_data_key()),
+            1u16.to_ne_bytes()
+        );
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (1, 0)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        use crate::undirected::UndirectedSnapshot;
+
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (mut channel_pointer, mut data_pointer1, data_pointer2) =
+            UndirectedChannel::create(String::from("a"), String::from("b"));
+
+        let mut bincode = Vec::new();
+        channel_pointer
+            .serialize_contents(
+                &master_key.get_channel_key(),
+                &mut bincode::Serializer::new(&mut bincode, bincode::DefaultOptions::new()),
+            )
+            .unwrap();
+        let json = serde_json::to_string(&channel_pointer.snapshot(&master_key.get_channel_key()))
+            .unwrap();
+        assert_eq!(json, r#"{"data1":"a","data2":"b"}"#);
+
+        *data_pointer1.get_mut(&master_key.get_data_key()) = String::from("c");
+        channel_pointer.swap(&master_key.get_channel_key());
+        channel_pointer
+            .deserialize_contents(
+                &master_key.get_channel_key(),
+                &mut bincode::Deserializer::from_slice(&bincode, bincode::DefaultOptions::new()),
+            )
+            .unwrap();
+        assert_eq!(channel_pointer.swap_count(&master_key.get_channel_key()), 1);
+        let data_key = master_key.get_data_key();
+        assert_eq!(data_pointer1.get(&data_key), "a");
+        assert_eq!(data_pointer2.get(&data_key), "b");
+
+        channel_pointer
+            .deserialize_contents(
+                &master_key.get_channel_key(),
+                &mut serde_json::Deserializer::from_str(r#"{"data1":"x","data2":"y"}"#),
+            )
+            .unwrap();
+        assert!(channel_pointer
+            .deserialize_contents(
+                &master_key.get_channel_key(),
+                &mut serde_json::Deserializer::from_str(r#"{"data1":"z"}"#),
+            )
+            .is_err());
+        assert_eq!(
+            channel_pointer.snapshot(&master_key.get_channel_key()),
+            UndirectedSnapshot {
+                data1: String::from("x"),
+                data2: String::from("y"),
+            }
+        );
+        assert_eq!(
+            channel_pointer.destroy(data_pointer1, data_pointer2),
+            (String::from("x"), String::from("y"))
+        );
+    }
 }
@@ -0,0 +1,401 @@
+//! Channels whose `Data` fields start out uninitialised, for payloads that are expensive to construct.
+//! The data pointers initialise their fields during the first data phase,
+//! and the channel pointer then turns all pointers into the usual typed pointers with `assume_init`.
+//! See [DirectedChannel::create_uninit] and [UndirectedChannel::create_uninit].
+//!
+//! Each uninitialised `Data` field has an init flag that is set when it is written, and `assume_init` checks these flags,
+//! hence no uninitialised `Data` ever becomes visible.
+//! Dropping an uninitialised channel pointer drops only the `Data` fields that were initialised.
+
+use core::mem::{self, MaybeUninit};
+use std::ptr;
+
+use crate::{
+    arena::ChannelBox,
+    directed::{DirectedChannel, DirectedChannelPointer, ReadOnlyDataPointer, WritableDataPointer},
+    undirected::{UndirectedChannel, UndirectedChannelPointer, UndirectedDataPointer},
+    ChannelKey, DataKey,
+};
+
+/// A pointer to a directed channel whose `Data` fields may not be initialised yet.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be turned into a [DirectedChannelPointer] via [UninitDirectedChannelPointer::assume_init],
+/// or dropped after the data pointers to it were dropped.
+#[derive(Debug)]
+#[must_use]
+pub struct UninitDirectedChannelPointer<Data> {
+    channel: *mut DirectedChannel<Data>,
+    initialized: *mut bool,
+}
+
+/// A pointer to the read-only data field in a directed channel that is not initialised yet.
+/// It cannot access the field, and only exists to be turned into a [ReadOnlyDataPointer] by [UninitDirectedChannelPointer::assume_init].
+#[derive(Debug)]
+#[must_use]
+pub struct UninitReadPointer<Data> {
+    data: *const Data,
+}
+
+/// A pointer to the writable data field in a directed channel that may not be initialised yet.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be turned into a [WritableDataPointer] via [UninitDirectedChannelPointer::assume_init].
+#[derive(Debug)]
+#[must_use]
+pub struct UninitWritePointer<Data> {
+    data: *mut Data,
+    initialized: *mut bool,
+}
+
+/// A pointer to an undirected channel whose `Data` fields may not be initialised yet.
+/// It can only be accessed using a [ChannelKey].
+///
+/// This type should always be turned into an [UndirectedChannelPointer] via [UninitUndirectedChannelPointer::assume_init],
+/// or dropped after the data pointers to it were dropped.
+#[derive(Debug)]
+#[must_use]
+pub struct UninitUndirectedChannelPointer<Data> {
+    channel: *mut UndirectedChannel<Data>,
+    initialized: *mut [bool; 2],
+}
+
+/// A pointer to one of the data fields in an undirected channel that may not be initialised yet.
+/// It can only be accessed using a [DataKey].
+///
+/// This type should always be turned into an [UndirectedDataPointer] via [UninitUndirectedChannelPointer::assume_init].
+#[derive(Debug)]
+#[must_use]
+pub struct UninitUndirectedDataPointer<Data> {
+    data: *mut Data,
+    initialized: *mut bool,
+}
+
+impl<Data> DirectedChannel<Data> {
+    /// Create a directed channel like [DirectedChannel::create], but without initialising its `Data` fields.
+    /// The writable field is initialised with [UninitWritePointer::write_init] during a data phase,
+    /// and afterwards [UninitDirectedChannelPointer::assume_init] performs the first flush and hands out the usual pointers.
+    pub fn create_uninit() -> (
+        UninitDirectedChannelPointer<Data>,
+        UninitReadPointer<Data>,
+        UninitWritePointer<Data>,
+    ) {
+        let channel = Self::allocate_uninit(false);
+        let initialized = Box::into_raw(Box::new(false));
+        unsafe {
+            (
+                UninitDirectedChannelPointer {
+                    channel,
+                    initialized,
+                },
+                UninitReadPointer {
+                    data: ptr::addr_of!((*channel).read_only),
+                },
+                UninitWritePointer {
+                    data: ptr::addr_of_mut!((*channel).writable),
+                    initialized,
+                },
+            )
+        }
+    }
+}
+
+impl<Data: Clone> UninitDirectedChannelPointer<Data> {
+    /// Turn the channel and the given data pointers into a usual directed channel.
+    /// The writable `Data` field is cloned into the read-only field, like in the first [flush](DirectedChannelPointer::flush).
+    ///
+    /// **Panics** if the writable `Data` field was not initialised, or if the data pointers do not point to this channel.
+    pub fn assume_init(
+        self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        read_pointer: UninitReadPointer<Data>,
+        write_pointer: UninitWritePointer<Data>,
+    ) -> (
+        DirectedChannelPointer<Data>,
+        ReadOnlyDataPointer<Data>,
+        WritableDataPointer<Data>,
+    ) {
+        let channel = self.channel;
+        unsafe {
+            assert!(
+                ptr::eq(read_pointer.data, ptr::addr_of!((*channel).read_only)),
+                "the read pointer does not point to this channel"
+            );
+            assert!(
+                ptr::eq(write_pointer.data, ptr::addr_of_mut!((*channel).writable)),
+                "the write pointer does not point to this channel"
+            );
+            assert!(
+                *self.initialized,
+                "the writable data field of the channel was not initialised"
+            );
+
+            let read_only = (*channel).writable.clone();
+            ptr::addr_of_mut!((*channel).read_only).write(read_only);
+            drop(Box::from_raw(self.initialized));
+            mem::forget(self);
+            DirectedChannel::hand_out(ChannelBox::from_box(Box::from_raw(channel)))
+        }
+    }
+}
+
+impl<Data> UninitDirectedChannelPointer<Data> {
+    /// Returns `true` if the writable `Data` field was initialised.
+    pub fn is_initialized(&self, #[allow(unused)] channel_key: &ChannelKey) -> bool {
+        unsafe { *self.initialized }
+    }
+}
+
+impl<Data> Drop for UninitDirectedChannelPointer<Data> {
+    fn drop(&mut self) {
+        unsafe {
+            // The read-only field is only initialised in `assume_init`.
+            if *self.initialized {
+                ptr::drop_in_place(ptr::addr_of_mut!((*self.channel).writable));
+            }
+            drop(Box::from_raw(self.initialized));
+            drop(Box::from_raw(
+                self.channel as *mut MaybeUninit<DirectedChannel<Data>>,
+            ));
+        }
+    }
+}
+
+impl<Data> UninitWritePointer<Data> {
+    /// Initialise the writable `Data` field, dropping its previous value if it was initialised already.
+    /// Returns a mutable reference to the initialised field.
+    pub fn write_init(&mut self, #[allow(unused)] data_key: &DataKey, data: Data) -> &mut Data {
+        unsafe {
+            if *self.initialized {
+                *self.data = data;
+            } else {
+                self.data.write(data);
+                *self.initialized = true;
+            }
+            &mut *self.data
+        }
+    }
+
+    /// Get a mutable reference to the writable `Data` field, if it was initialised.
+    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> Option<&mut Data> {
+        unsafe {
+            if *self.initialized {
+                Some(&mut *self.data)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl<Data> UndirectedChannel<Data> {
+    /// Create an undirected channel like [UndirectedChannel::create], but without initialising its `Data` fields.
+    /// Each field is initialised with [UninitUndirectedDataPointer::write_init] during a data phase,
+    /// and afterwards [UninitUndirectedChannelPointer::assume_init] hands out the usual pointers.
+    pub fn create_uninit() -> (
+        UninitUndirectedChannelPointer<Data>,
+        UninitUndirectedDataPointer<Data>,
+        UninitUndirectedDataPointer<Data>,
+    ) {
+        let channel = Self::allocate_uninit(false);
+        let initialized = Box::into_raw(Box::new([false; 2]));
+        unsafe {
+            let initialized1 = ptr::addr_of_mut!((*initialized)[0]);
+            let initialized2 = ptr::addr_of_mut!((*initialized)[1]);
+            (
+                UninitUndirectedChannelPointer {
+                    channel,
+                    initialized,
+                },
+                UninitUndirectedDataPointer {
+                    data: ptr::addr_of_mut!((*channel).data1.0),
+                    initialized: initialized1,
+                },
+                UninitUndirectedDataPointer {
+                    data: ptr::addr_of_mut!((*channel).data2.0),
+                    initialized: initialized2,
+                },
+            )
+        }
+    }
+}
+
+impl<Data> UninitUndirectedChannelPointer<Data> {
+    /// Turn the channel and the given data pointers into a usual undirected channel.
+    /// The returned data pointers point to the same `Data` fields as the given ones, in the same order.
+    ///
+    /// **Panics** if not both `Data` fields were initialised, or if the data pointers do not point to this channel.
+    pub fn assume_init(
+        self,
+        #[allow(unused)] channel_key: &ChannelKey,
+        data_pointer1: UninitUndirectedDataPointer<Data>,
+        data_pointer2: UninitUndirectedDataPointer<Data>,
+    ) -> (
+        UndirectedChannelPointer<Data>,
+        UndirectedDataPointer<Data>,
+        UndirectedDataPointer<Data>,
+    ) {
+        let channel = self.channel;
+        unsafe {
+            let data1 = ptr::addr_of_mut!((*channel).data1.0);
+            let data2 = ptr::addr_of_mut!((*channel).data2.0);
+            let swapped = if data_pointer1.data == data1 && data_pointer2.data == data2 {
+                false
+            } else if data_pointer1.data == data2 && data_pointer2.data == data1 {
+                true
+            } else {
+                panic!("the data pointers do not point to this channel");
+            };
+            assert!(
+                *self.initialized == [true; 2],
+                "not all data fields of the channel were initialised"
+            );
+
+            drop(Box::from_raw(self.initialized));
+            mem::forget(self);
+            let (channel_pointer, data_pointer1, data_pointer2) =
+                UndirectedChannel::hand_out(ChannelBox::from_box(Box::from_raw(channel)));
+            if swapped {
+                (channel_pointer, data_pointer2, data_pointer1)
+            } else {
+                (channel_pointer, data_pointer1, data_pointer2)
+            }
+        }
+    }
+
+    /// Returns `true` if both `Data` fields were initialised.
+    pub fn is_initialized(&self, #[allow(unused)] channel_key: &ChannelKey) -> bool {
+        unsafe { *self.initialized == [true; 2] }
+    }
+}
+
+impl<Data> Drop for UninitUndirectedChannelPointer<Data> {
+    fn drop(&mut self) {
+        unsafe {
+            let [initialized1, initialized2] = *self.initialized;
+            if initialized1 {
+                ptr::drop_in_place(ptr::addr_of_mut!((*self.channel).data1.0));
+            }
+            if initialized2 {
+                ptr::drop_in_place(ptr::addr_of_mut!((*self.channel).data2.0));
+            }
+            drop(Box::from_raw(self.initialized));
+            drop(Box::from_raw(
+                self.channel as *mut MaybeUninit<UndirectedChannel<Data>>,
+            ));
+        }
+    }
+}
+
+impl<Data> UninitUndirectedDataPointer<Data> {
+    /// Initialise the `Data` field pointed to by this pointer, dropping its previous value if it was initialised already.
+    /// Returns a mutable reference to the initialised field.
+    pub fn write_init(&mut self, #[allow(unused)] data_key: &DataKey, data: Data) -> &mut Data {
+        unsafe {
+            if *self.initialized {
+                *self.data = data;
+            } else {
+                self.data.write(data);
+                *self.initialized = true;
+            }
+            &mut *self.data
+        }
+    }
+
+    /// Get a mutable reference to the `Data` field pointed to by this pointer, if it was initialised.
+    pub fn get_mut(&mut self, #[allow(unused)] data_key: &DataKey) -> Option<&mut Data> {
+        unsafe {
+            if *self.initialized {
+                Some(&mut *self.data)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+unsafe impl<Data: Send> Send for UninitDirectedChannelPointer<Data> {}
+unsafe impl<Data: Send> Send for UninitReadPointer<Data> {}
+unsafe impl<Data: Send> Send for UninitWritePointer<Data> {}
+unsafe impl<Data: Send> Send for UninitUndirectedChannelPointer<Data> {}
+unsafe impl<Data: Send> Send for UninitUndirectedDataPointer<Data> {}
+
+unsafe impl<Data: Sync> Sync for UninitDirectedChannelPointer<Data> {}
+unsafe impl<Data: Sync> Sync for UninitReadPointer<Data> {}
+unsafe impl<Data: Sync> Sync for UninitWritePointer<Data> {}
+unsafe impl<Data: Sync> Sync for UninitUndirectedChannelPointer<Data> {}
+unsafe impl<Data: Sync> Sync for UninitUndirectedDataPointer<Data> {}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{directed::DirectedChannel, undirected::UndirectedChannel, MasterKey};
+
+    #[test]
+    fn test() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_pointer, mut write_pointer) =
+            DirectedChannel::<Vec<u32>>::create_uninit();
+        let (undirected, mut data_pointer1, mut data_pointer2) =
+            UndirectedChannel::<Vec<u32>>::create_uninit();
+
+        let data_key = master_key.get_data_key();
+        assert!(write_pointer.get_mut(&data_key).is_none());
+        write_pointer.write_init(&data_key, vec![1]).push(2);
+        data_pointer1.write_init(&data_key, vec![3]);
+        data_pointer2.write_init(&data_key, vec![4]);
+        data_pointer2.get_mut(&data_key).unwrap().push(5);
+
+        let channel_key = master_key.get_channel_key();
+        assert!(channel_pointer.is_initialized(&channel_key));
+        let (mut channel_pointer, read_only, writable) =
+            channel_pointer.assume_init(&channel_key, read_pointer, write_pointer);
+        let (mut undirected, data_pointer2, data_pointer1) =
+            undirected.assume_init(&channel_key, data_pointer2, data_pointer1);
+        undirected.swap(&channel_key);
+        channel_pointer.flush(&channel_key);
+
+        let data_key = master_key.get_data_key();
+        assert_eq!(read_only.get(&data_key), &[1, 2]);
+        assert_eq!(data_pointer1.get(&data_key), &[4, 5]);
+        assert_eq!(data_pointer2.get(&data_key), &[3]);
+
+        assert_eq!(
+            channel_pointer.destroy([read_only], writable),
+            (vec![1, 2], vec![1, 2])
+        );
+        assert_eq!(
+            undirected.destroy(data_pointer1, data_pointer2),
+            (vec![4, 5], vec![3])
+        );
+    }
+
+    #[test]
+    fn drop_drops_only_initialized_fields() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let data = Rc::new(());
+        let (channel_pointer, data_pointer1, mut data_pointer2) =
+            UndirectedChannel::<Rc<()>>::create_uninit();
+
+        let data_key = master_key.get_data_key();
+        data_pointer2.write_init(&data_key, data.clone());
+        data_pointer2.write_init(&data_key, data.clone());
+        assert_eq!(Rc::strong_count(&data), 2);
+
+        drop((data_pointer1, data_pointer2));
+        drop(channel_pointer);
+        assert_eq!(Rc::strong_count(&data), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn assume_init_rejects_uninitialized_fields() {
+        let mut master_key = unsafe { MasterKey::create_unlimited() };
+        let (channel_pointer, read_pointer, write_pointer) =
+            DirectedChannel::<Vec<u32>>::create_uninit();
+
+        let _ =
+            channel_pointer.assume_init(&master_key.get_channel_key(), read_pointer, write_pointer);
+    }
+}
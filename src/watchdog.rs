@@ -0,0 +1,349 @@
+//! Detection of stalled phase transitions, e.g. a worker that never finishes its data phase.
+//! The coordinator reports each phase transition to a [PhaseWatchdog], whose background thread calls a callback
+//! if no transition happens within a threshold.
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// A phase of the two-phase protocol, as reported to a [PhaseWatchdog].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// A data phase, in which the workers access their data pointers.
+    Data,
+    /// A channel phase, in which the coordinator advances the channels.
+    Channel,
+}
+
+/// A stall detected by a [PhaseWatchdog].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stall {
+    /// The phase that did not end in time.
+    pub phase: Phase,
+    /// The number of transitions reported before the stall.
+    pub generation: u64,
+    /// The time since the last transition.
+    pub stalled_for: Duration,
+    /// The names of the registered workers that did not check in during the stalled data phase, in registration order.
+    /// Empty if the stalled phase is a channel phase.
+    pub pending_workers: Vec<String>,
+}
+
+/// Watches the phase transitions reported via its [WatchdogHandle]s on a background thread,
+/// and calls its callback once per stall if no transition happens within the threshold.
+///
+/// Reporting a transition only touches a few atomics, such that it can be done in every phase.
+/// The watchdog is shut down when it is dropped, see [PhaseWatchdog::shutdown].
+pub struct PhaseWatchdog {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// A handle to report phase transitions and worker check-ins to a [PhaseWatchdog].
+#[derive(Debug, Clone)]
+pub struct WatchdogHandle {
+    shared: Arc<Shared>,
+}
+
+/// A worker registered with a [PhaseWatchdog], which checks in once it finished its data phase.
+#[derive(Debug)]
+pub struct WatchdogWorker {
+    shared: Arc<Shared>,
+    worker: Arc<Worker>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    start: Instant,
+    /// The time of the last transition, in nanoseconds since the start.
+    last_transition: AtomicU64,
+    /// The number of transitions.
+    generation: AtomicU64,
+    /// `true` if the current phase is a data phase.
+    in_data_phase: AtomicBool,
+    workers: Mutex<Vec<Arc<Worker>>>,
+    control: Mutex<Control>,
+    condvar: Condvar,
+}
+
+#[derive(Debug)]
+struct Worker {
+    name: String,
+    /// The generation in which the worker last checked in.
+    checked_in: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Control {
+    suspended: bool,
+    shutdown: bool,
+}
+
+impl PhaseWatchdog {
+    /// Start a watchdog thread that calls the given callback if no phase transition is reported within the given threshold.
+    /// The watch starts in a channel phase, i.e. the first transition is expected to start a data phase.
+    /// The callback is called at most once per stall, on the watchdog thread.
+    pub fn start(threshold: Duration, mut callback: impl FnMut(&Stall) + Send + 'static) -> Self {
+        let shared = Arc::new(Shared {
+            start: Instant::now(),
+            last_transition: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+            in_data_phase: AtomicBool::new(false),
+            workers: Mutex::new(Vec::new()),
+            control: Mutex::new(Control::default()),
+            condvar: Condvar::new(),
+        });
+        let watched = shared.clone();
+        let thread = thread::Builder::new()
+            .name("phase-watchdog".into())
+            .spawn(move || watched.watch(threshold, &mut callback))
+            .expect("failed to spawn watchdog thread");
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Get a handle to report phase transitions to this watchdog.
+    pub fn handle(&self) -> WatchdogHandle {
+        WatchdogHandle {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Stop and join the watchdog thread.
+    /// The callback is not called anymore afterwards.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.shared.control.lock().unwrap().shutdown = true;
+            self.shared.condvar.notify_all();
+            thread.join().expect("the watchdog callback panicked");
+        }
+    }
+}
+
+impl Drop for PhaseWatchdog {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl fmt::Debug for PhaseWatchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PhaseWatchdog")
+            .field("shared", &self.shared)
+            .finish()
+    }
+}
+
+impl WatchdogHandle {
+    /// Report the start of the given phase.
+    pub fn transition(&self, phase: Phase) {
+        self.shared.touch();
+        self.shared
+            .in_data_phase
+            .store(phase == Phase::Data, Ordering::Relaxed);
+        self.shared.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Register a worker with the given name, which is reported as pending in stalled data phases until it checks in.
+    pub fn register_worker(&self, name: impl Into<String>) -> WatchdogWorker {
+        let worker = Arc::new(Worker {
+            name: name.into(),
+            checked_in: AtomicU64::new(self.shared.generation.load(Ordering::Acquire)),
+        });
+        self.shared.workers.lock().unwrap().push(worker.clone());
+        WatchdogWorker {
+            shared: self.shared.clone(),
+            worker,
+        }
+    }
+
+    /// Pause the watch, e.g. during an intentional pause of the phases.
+    /// No stall is reported until [WatchdogHandle::resume] is called.
+    pub fn suspend(&self) {
+        self.shared.control.lock().unwrap().suspended = true;
+    }
+
+    /// Resume the watch after [WatchdogHandle::suspend].
+    /// The time of the pause does not count towards the threshold.
+    pub fn resume(&self) {
+        self.shared.touch();
+        self.shared.control.lock().unwrap().suspended = false;
+        self.shared.condvar.notify_all();
+    }
+}
+
+impl WatchdogWorker {
+    /// Report that this worker finished its work in the current data phase.
+    pub fn check_in(&self) {
+        self.worker.checked_in.store(
+            self.shared.generation.load(Ordering::Acquire),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// The name of this worker.
+    pub fn name(&self) -> &str {
+        &self.worker.name
+    }
+}
+
+impl Drop for WatchdogWorker {
+    /// Unregister the worker.
+    fn drop(&mut self) {
+        self.shared
+            .workers
+            .lock()
+            .unwrap()
+            .retain(|worker| !Arc::ptr_eq(worker, &self.worker));
+    }
+}
+
+impl Shared {
+    fn now(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+
+    fn touch(&self) {
+        self.last_transition.store(self.now(), Ordering::Relaxed);
+    }
+
+    /// The loop of the watchdog thread.
+    fn watch(&self, threshold: Duration, callback: &mut dyn FnMut(&Stall)) {
+        // The generation of the last reported stall, such that each stall is reported once.
+        let mut reported = None;
+        let mut control = self.control.lock().unwrap();
+        loop {
+            if control.shutdown {
+                return;
+            }
+            if control.suspended {
+                control = self.condvar.wait(control).unwrap();
+                continue;
+            }
+
+            let generation = self.generation.load(Ordering::Acquire);
+            let elapsed = Duration::from_nanos(
+                self.now()
+                    .saturating_sub(self.last_transition.load(Ordering::Relaxed)),
+            );
+            let timeout = if elapsed < threshold {
+                threshold - elapsed
+            } else {
+                if reported != Some(generation) {
+                    reported = Some(generation);
+                    let stall = self.stall(generation, elapsed);
+                    // Do not block suspending or shutting down while the callback runs.
+                    drop(control);
+                    callback(&stall);
+                    control = self.control.lock().unwrap();
+                    continue;
+                }
+                threshold
+            };
+            control = self.condvar.wait_timeout(control, timeout).unwrap().0;
+        }
+    }
+
+    fn stall(&self, generation: u64, stalled_for: Duration) -> Stall {
+        let phase = if self.in_data_phase.load(Ordering::Relaxed) {
+            Phase::Data
+        } else {
+            Phase::Channel
+        };
+        let pending_workers = match phase {
+            Phase::Data => self
+                .workers
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|worker| worker.checked_in.load(Ordering::Relaxed) < generation)
+                .map(|worker| worker.name.clone())
+                .collect(),
+            Phase::Channel => Vec::new(),
+        };
+        Stall {
+            phase,
+            generation,
+            stalled_for,
+            pending_workers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::watchdog::{Phase, PhaseWatchdog};
+
+    #[test]
+    fn test() {
+        let (sender, receiver) = mpsc::channel();
+        let watchdog = PhaseWatchdog::start(Duration::from_millis(50), move |stall| {
+            sender.send(stall.clone()).unwrap();
+        });
+        let handle = watchdog.handle();
+        let fast = handle.register_worker("fast");
+        let slow = handle.register_worker("slow");
+
+        // Transitions within the threshold do not fire.
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(150) {
+            handle.transition(Phase::Data);
+            fast.check_in();
+            slow.check_in();
+            handle.transition(Phase::Channel);
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(receiver.try_recv().is_err());
+
+        handle.transition(Phase::Data);
+        fast.check_in();
+        let stall = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(stall.phase, Phase::Data);
+        assert!(stall.stalled_for >= Duration::from_millis(50));
+        assert_eq!(stall.pending_workers, ["slow"]);
+        // Each stall is reported once.
+        assert!(receiver.recv_timeout(Duration::from_millis(150)).is_err());
+
+        slow.check_in();
+        handle.transition(Phase::Channel);
+        let stall = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(stall.phase, Phase::Channel);
+        assert!(stall.pending_workers.is_empty());
+        watchdog.shutdown();
+    }
+
+    #[test]
+    fn suspend() {
+        let (sender, receiver) = mpsc::channel();
+        let watchdog = PhaseWatchdog::start(Duration::from_millis(50), move |stall| {
+            sender.send(stall.generation).unwrap();
+        });
+        let handle = watchdog.handle();
+        handle.suspend();
+        assert!(receiver.recv_timeout(Duration::from_millis(150)).is_err());
+
+        handle.resume();
+        handle.transition(Phase::Data);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(1));
+        drop(watchdog);
+        assert!(receiver.recv().is_err());
+    }
+}
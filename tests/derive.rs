@@ -0,0 +1,64 @@
+//! Tests of `#[derive(SwapFields)]`, which need the generated code to refer to this crate by name.
+
+use two_phase_channel::{MasterKey, SwapFields};
+
+#[derive(Debug, Clone, PartialEq, SwapFields)]
+pub struct State<T: Clone> {
+    counter: u64,
+    #[channel(directed)]
+    items: Vec<T>,
+    #[channel(undirected)]
+    scratch: [u8; 2],
+}
+
+#[test]
+fn test() {
+    let mut master_key = unsafe { MasterKey::create_unlimited() };
+    let (mut channels, mut pointers) = StateChannels::create(State {
+        counter: 0,
+        items: vec!["a"],
+        scratch: [0, 1],
+    });
+
+    let data_key = master_key.get_data_key();
+    *pointers.counter_writer.get_mut(&data_key) += 1;
+    pointers.items_writer.get_mut(&data_key).push("b");
+    pointers.scratch_pointer1.get_mut(&data_key)[0] = 2;
+    assert_eq!(*pointers.counter_reader.get(&data_key), 0);
+    channels.flush_all(&data_key.into_channel_key());
+
+    let data_key = master_key.get_data_key();
+    assert_eq!(*pointers.counter_reader.get(&data_key), 1);
+    assert_eq!(*pointers.items_reader.get(&data_key), ["a", "b"]);
+    assert_eq!(*pointers.scratch_pointer1.get(&data_key), [0, 1]);
+    assert_eq!(*pointers.scratch_pointer2.get(&data_key), [2, 1]);
+
+    assert_eq!(
+        channels.destroy(pointers),
+        (
+            State {
+                counter: 1,
+                items: vec!["a", "b"],
+                scratch: [0, 1],
+            },
+            State {
+                counter: 1,
+                items: vec!["a", "b"],
+                scratch: [2, 1],
+            },
+        )
+    );
+}
+
+#[test]
+#[should_panic(expected = "does not point to the")]
+fn destroy_with_foreign_pointers_panics() {
+    let initial = State {
+        counter: 0,
+        items: vec![()],
+        scratch: [0; 2],
+    };
+    let (channels, _) = StateChannels::create(initial.clone());
+    let (_, pointers) = StateChannels::create(initial);
+    channels.destroy(pointers);
+}
@@ -0,0 +1,80 @@
+/*
+ * Exercises the C interface, driven by the `c_program` test in `src/ffi.rs`.
+ * Returns 0 on success, and the line of the failed check otherwise.
+ */
+#include <string.h>
+
+#include "two_phase_channel.h"
+
+/* Must match `Api` in `src/ffi.rs`. */
+struct sc_api {
+    sc_status (*master_key_create)(sc_master_key **);
+    sc_status (*master_key_destroy)(sc_master_key *);
+    sc_status (*undirected_create)(size_t, sc_undirected_channel **, sc_undirected_data **,
+                                   sc_undirected_data **);
+    sc_status (*undirected_swap)(sc_master_key *, sc_undirected_channel *);
+    sc_status (*undirected_get)(sc_master_key *, sc_undirected_data *, void **);
+    sc_status (*undirected_destroy)(sc_undirected_channel *, sc_undirected_data *,
+                                    sc_undirected_data *);
+    sc_status (*directed_create)(size_t, sc_directed_channel **, sc_read_only_data **,
+                                 sc_writable_data **);
+    sc_status (*directed_flush)(sc_master_key *, sc_directed_channel *);
+    sc_status (*directed_read)(sc_master_key *, sc_read_only_data *, const void **);
+    sc_status (*directed_write)(sc_master_key *, sc_writable_data *, void **);
+    sc_status (*directed_destroy)(sc_directed_channel *, sc_read_only_data *, sc_writable_data *);
+};
+
+#define CHECK(condition)                                                                           \
+    do {                                                                                           \
+        if (!(condition)) {                                                                        \
+            return __LINE__;                                                                       \
+        }                                                                                          \
+    } while (0)
+
+int sc_test_run(const struct sc_api *api) {
+    sc_master_key *key;
+    sc_master_key *second_key;
+    CHECK(api->master_key_create(&key) == SC_OK);
+    CHECK(api->master_key_create(&second_key) == SC_MASTER_KEY_EXISTS);
+
+    /* Directed: create, write, flush, read, destroy. */
+    sc_directed_channel *directed;
+    sc_read_only_data *read_only;
+    sc_writable_data *writable;
+    CHECK(api->directed_create(sizeof(double[3]), &directed, &read_only, &writable) == SC_OK);
+
+    void *write_buffer;
+    const void *read_buffer;
+    double values[3] = {1.0, 2.0, 3.0};
+    CHECK(api->directed_write(key, writable, &write_buffer) == SC_OK);
+    memcpy(write_buffer, values, sizeof(values));
+    CHECK(api->directed_read(key, read_only, &read_buffer) == SC_OK);
+    CHECK(((const double *)read_buffer)[1] == 0.0);
+
+    CHECK(api->directed_flush(key, directed) == SC_OK);
+    CHECK(api->directed_read(key, read_only, &read_buffer) == SC_OK);
+    CHECK(memcmp(read_buffer, values, sizeof(values)) == 0);
+    CHECK(api->directed_destroy(directed, read_only, writable) == SC_OK);
+
+    /* Undirected: write both sides, swap, read both sides. */
+    sc_undirected_channel *undirected;
+    sc_undirected_data *data1;
+    sc_undirected_data *data2;
+    void *buffer1;
+    void *buffer2;
+    CHECK(api->undirected_create(sizeof(int), &undirected, &data1, &data2) == SC_OK);
+    CHECK(api->undirected_get(key, data1, &buffer1) == SC_OK);
+    CHECK(api->undirected_get(key, data2, &buffer2) == SC_OK);
+    *(int *)buffer1 = 1;
+    *(int *)buffer2 = 2;
+
+    CHECK(api->undirected_swap(key, undirected) == SC_OK);
+    CHECK(api->undirected_get(key, data1, &buffer1) == SC_OK);
+    CHECK(api->undirected_get(key, data2, &buffer2) == SC_OK);
+    CHECK(*(int *)buffer1 == 2 && *(int *)buffer2 == 1);
+    CHECK(api->undirected_destroy(undirected, data1, data2) == SC_OK);
+
+    CHECK(api->directed_flush(key, NULL) == SC_NULL_POINTER);
+    CHECK(api->master_key_destroy(key) == SC_OK);
+    return 0;
+}